@@ -0,0 +1,22 @@
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate asyncio;
+
+use std::str;
+use asyncio::ip::{IpAddr, IpAddrV6, UdpEndpoint};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(s) = str::from_utf8(data) {
+        if let Ok(addr) = s.parse::<IpAddrV6>() {
+            let _ = format!("{} {:#}", addr, addr);
+        }
+        if let Ok(addr) = s.parse::<IpAddr>() {
+            let _ = format!("{}", addr);
+        }
+        if let Ok(ep) = s.parse::<UdpEndpoint>() {
+            // The printed endpoint is parsed back to the same endpoint.
+            assert_eq!(ep.to_string().parse::<UdpEndpoint>().unwrap(), ep);
+        }
+    }
+});
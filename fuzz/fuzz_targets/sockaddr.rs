@@ -0,0 +1,19 @@
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate asyncio;
+
+use asyncio::Endpoint;
+use asyncio::ip::{IpAddr, TcpEndpoint};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(ep) = TcpEndpoint::try_from_sockaddr(data) {
+        // The parsed endpoint is printable and round-trips through the raw bytes.
+        let _ = format!("{} {:?}", ep, ep);
+        match ep.addr() {
+            IpAddr::V4(_) => assert!(ep.to_v4().is_some()),
+            IpAddr::V6(_) => assert!(ep.to_v6().is_some()),
+        }
+        assert_eq!(TcpEndpoint::try_from_sockaddr(ep.as_bytes()).unwrap(), ep);
+    }
+});
@@ -1,20 +1,38 @@
 #![allow(unreachable_patterns)]
 
-use ffi::{SystemError, Timeout, accept, readable, OPERATION_CANCELED, TRY_AGAIN, WOULD_BLOCK,
-          INTERRUPTED};
+use ffi::{RawFd, SystemError, Timeout, accept, close, readable, OPERATION_CANCELED, TRY_AGAIN,
+          WOULD_BLOCK, INTERRUPTED};
 use core::{Protocol, Socket, AsIoContext, Perform, Exec, ThreadIoContext};
 use handler::{Handler, Complete, AsyncReadOp, Failure};
+use observer::{notify_accept, notify_error};
+use stream_socket::StreamSocket;
+#[cfg(all(target_os = "linux", feature = "uring"))]
+use ffi::{SOCK_CLOEXEC, SOCK_NONBLOCK, socklen_t, uring_result};
+#[cfg(all(target_os = "linux", feature = "uring"))]
+use core::Endpoint;
+#[cfg(all(target_os = "linux", feature = "uring"))]
+use io_uring::{opcode, squeue, types};
 
 use std::io;
 use std::marker::PhantomData;
 
-struct AsyncAccept<P, S, F> {
+struct AsyncAccept<P, S, F>
+where
+    P: Protocol,
+{
     soc: *const S,
     handler: F,
+    /// The peer endpoint and its length written by the request to the `io_uring`.
+    #[cfg(all(target_os = "linux", feature = "uring"))]
+    peer: Option<(P::Endpoint, socklen_t)>,
     _marker: PhantomData<P>,
 }
 
-unsafe impl<P, S, F> Send for AsyncAccept<P, S, F> {}
+unsafe impl<P, S, F> Send for AsyncAccept<P, S, F>
+where
+    P: Protocol,
+{
+}
 
 impl<P, S, F> Complete<(P::Socket, P::Endpoint), io::Error> for AsyncAccept<P, S, F>
     where
@@ -24,12 +42,14 @@ impl<P, S, F> Complete<(P::Socket, P::Endpoint), io::Error> for AsyncAccept<P, S
 {
     fn success(self, this: &mut ThreadIoContext, res: (P::Socket, P::Endpoint)) {
         let soc = unsafe { &*self.soc };
+        notify_accept::<P, S>(this.as_ctx(), soc, &res.0, &res.1);
         soc.next_read_op(this);
         self.handler.success(this, res)
     }
 
     fn failure(self, this: &mut ThreadIoContext, err: io::Error) {
         let soc = unsafe { &*self.soc };
+        notify_error(this.as_ctx(), soc, &err);
         soc.next_read_op(this);
         self.handler.failure(this, err)
     }
@@ -62,6 +82,34 @@ where
             }
         }
     }
+
+    #[cfg(all(target_os = "linux", feature = "uring"))]
+    fn uring_entry(&mut self) -> Option<squeue::Entry> {
+        let soc = unsafe { &*self.soc };
+        let ep = unsafe { soc.protocol().uninitialized() };
+        let len = ep.capacity();
+        self.peer = Some((ep, len));
+        let &mut (ref mut ep, ref mut len) = self.peer.as_mut().unwrap();
+        let accept = opcode::Accept::new(types::Fd(soc.as_raw_fd()), ep.as_mut_ptr(), len);
+        Some(accept.flags(SOCK_NONBLOCK | SOCK_CLOEXEC).build())
+    }
+
+    #[cfg(all(target_os = "linux", feature = "uring"))]
+    fn uring_complete(mut self: Box<Self>, this: &mut ThreadIoContext, res: i32) {
+        match uring_result(res) {
+            Ok(acc) => {
+                let (mut ep, len) = self.peer.take().unwrap();
+                unsafe { ep.resize(len) };
+                let pro = unsafe { &*self.soc }.protocol().clone();
+                let soc = unsafe { P::Socket::from_raw_fd(this.as_ctx(), acc as RawFd, pro) };
+                self.success(this, (soc, ep))
+            }
+            Err(INTERRUPTED) | Err(TRY_AGAIN) | Err(WOULD_BLOCK) => {
+                self.perform(this, SystemError::default())
+            }
+            Err(err) => self.failure(this, err.into()),
+        }
+    }
 }
 
 impl<P, S, F> Exec for AsyncAccept<P, S, F>
@@ -91,6 +139,8 @@ where
         ctx.do_dispatch(AsyncAccept {
             soc: soc,
             handler: handler,
+            #[cfg(all(target_os = "linux", feature = "uring"))]
+            peer: None,
             _marker: PhantomData,
         })
     } else {
@@ -98,7 +148,32 @@ where
     })
 }
 
+fn observe<P, S>(
+    soc: &S,
+    res: io::Result<(P::Socket, P::Endpoint)>,
+) -> io::Result<(P::Socket, P::Endpoint)>
+where
+    P: Protocol,
+    S: Socket<P> + AsIoContext,
+{
+    match res {
+        Ok((ref acc, ref ep)) => notify_accept::<P, S>(soc.as_ctx(), soc, acc, ep),
+        Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => (),
+        Err(ref err) => notify_error(soc.as_ctx(), soc, err),
+    }
+    res
+}
+
 pub fn blocking_accept<P, S>(soc: &S, timeout: &Timeout) -> io::Result<(P::Socket, P::Endpoint)>
+where
+    P: Protocol,
+    S: Socket<P> + AsIoContext,
+{
+    let res = accept_until(soc, timeout);
+    observe(soc, res)
+}
+
+fn accept_until<P, S>(soc: &S, timeout: &Timeout) -> io::Result<(P::Socket, P::Endpoint)>
 where
     P: Protocol,
     S: Socket<P> + AsIoContext,
@@ -132,9 +207,152 @@ where
     if soc.as_ctx().stopped() {
         return Err(OPERATION_CANCELED.into());
     }
-    Ok(accept(soc).map(|(acc, ep)| {
+    let res = accept(soc).map(|(acc, ep)| {
         let pro = soc.protocol().clone();
         let acc = unsafe { P::Socket::from_raw_fd(soc.as_ctx(), acc, pro) };
         (acc, ep)
-    })?)
+    });
+    observe(soc, res.map_err(From::from))
+}
+
+/// Adopts the accepted socket into the peer, the socket is closed if failed.
+fn assign<P>(peer: *const StreamSocket<P>, acc: RawFd, pro: P) -> io::Result<()>
+where
+    P: Protocol,
+{
+    // The peer is not used by the others until the accept is completed.
+    let peer = unsafe { &mut *(peer as *mut StreamSocket<P>) };
+    peer.assign(acc, pro).map_err(|err| {
+        close(acc);
+        err
+    })
+}
+
+struct AsyncAcceptInto<P, S, F> {
+    soc: *const S,
+    peer: *const StreamSocket<P>,
+    handler: F,
+}
+
+unsafe impl<P, S, F> Send for AsyncAcceptInto<P, S, F> {}
+
+impl<P, S, F> Complete<P::Endpoint, io::Error> for AsyncAcceptInto<P, S, F>
+where
+    P: Protocol<Socket = StreamSocket<P>>,
+    S: Socket<P> + AsyncReadOp,
+    F: Complete<P::Endpoint, io::Error>,
+{
+    fn success(self, this: &mut ThreadIoContext, ep: P::Endpoint) {
+        let soc = unsafe { &*self.soc };
+        notify_accept::<P, S>(this.as_ctx(), soc, unsafe { &*self.peer }, &ep);
+        soc.next_read_op(this);
+        self.handler.success(this, ep)
+    }
+
+    fn failure(self, this: &mut ThreadIoContext, err: io::Error) {
+        let soc = unsafe { &*self.soc };
+        notify_error(this.as_ctx(), soc, &err);
+        soc.next_read_op(this);
+        self.handler.failure(this, err)
+    }
+}
+
+impl<P, S, F> Perform for AsyncAcceptInto<P, S, F>
+where
+    P: Protocol<Socket = StreamSocket<P>>,
+    S: Socket<P> + AsyncReadOp,
+    F: Complete<P::Endpoint, io::Error>,
+{
+    fn perform(self: Box<Self>, this: &mut ThreadIoContext, err: SystemError) {
+        let soc = unsafe { &*self.soc };
+        if err != Default::default() {
+            return self.failure(this, err.into());
+        }
+
+        loop {
+            match accept(soc) {
+                Ok((acc, ep)) => {
+                    return match assign(self.peer, acc, soc.protocol().clone()) {
+                        Ok(()) => self.success(this, ep),
+                        Err(err) => self.failure(this, err),
+                    };
+                }
+                Err(TRY_AGAIN) | Err(WOULD_BLOCK) => {
+                    return soc.add_read_op(this, self, WOULD_BLOCK)
+                }
+                Err(INTERRUPTED) if !soc.as_ctx().stopped() => {}
+                Err(err) => return self.failure(this, err.into()),
+            }
+        }
+    }
+}
+
+impl<P, S, F> Exec for AsyncAcceptInto<P, S, F>
+where
+    P: Protocol<Socket = StreamSocket<P>>,
+    S: Socket<P> + AsyncReadOp,
+    F: Complete<P::Endpoint, io::Error>,
+{
+    fn call(self, this: &mut ThreadIoContext) {
+        let soc = unsafe { &*self.soc };
+        soc.add_read_op(this, Box::new(self), SystemError::default())
+    }
+
+    fn call_box(self: Box<Self>, this: &mut ThreadIoContext) {
+        let soc = unsafe { &*self.soc };
+        soc.add_read_op(this, self, SystemError::default())
+    }
+}
+
+pub fn async_accept_into<P, S, F>(
+    soc: &S,
+    peer: &StreamSocket<P>,
+    timeout: &Timeout,
+    handler: F,
+) -> F::Output
+where
+    P: Protocol<Socket = StreamSocket<P>>,
+    S: Socket<P> + AsyncReadOp,
+    F: Handler<P::Endpoint, io::Error>,
+{
+    handler.wrap_timeout(soc, timeout, |ctx, handler| if !ctx.stopped() {
+        ctx.do_dispatch(AsyncAcceptInto {
+            soc: soc,
+            peer: peer,
+            handler: handler,
+        })
+    } else {
+        ctx.do_dispatch(Failure::new(OPERATION_CANCELED, handler))
+    })
+}
+
+pub fn blocking_accept_into<P, S>(
+    soc: &S,
+    peer: &StreamSocket<P>,
+    timeout: &Timeout,
+) -> io::Result<P::Endpoint>
+where
+    P: Protocol<Socket = StreamSocket<P>>,
+    S: Socket<P> + AsIoContext,
+{
+    if soc.as_ctx().stopped() {
+        return Err(OPERATION_CANCELED.into());
+    }
+    let res = loop {
+        match accept(soc) {
+            Ok((acc, ep)) => break assign(peer, acc, soc.protocol().clone()).map(|_| ep),
+            Err(TRY_AGAIN) | Err(WOULD_BLOCK) => {
+                if let Err(err) = readable(soc, &timeout) {
+                    break Err(err.into());
+                }
+            }
+            Err(INTERRUPTED) if !soc.as_ctx().stopped() => {}
+            Err(err) => break Err(err.into()),
+        }
+    };
+    match res {
+        Ok(ref ep) => notify_accept::<P, S>(soc.as_ctx(), soc, peer, ep),
+        Err(ref err) => notify_error(soc.as_ctx(), soc, err),
+    }
+    res
 }
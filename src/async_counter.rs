@@ -0,0 +1,148 @@
+use ffi::{AsRawFd, RawFd, SystemError, read, write};
+use reactor::SocketImpl;
+use core::{AsIoContext, IoContext, ThreadIoContext, Perform, Cancel};
+use handler::{Handler, AsyncReadOp};
+use read_ops::{Reader, async_read_op, blocking_read_op, nonblocking_read_op};
+
+use std::io;
+use std::fmt;
+use std::time::Duration;
+use libc::{eventfd, EFD_CLOEXEC, EFD_NONBLOCK};
+
+struct ReadCounter;
+
+impl Reader for ReadCounter {
+    type Socket = AsyncCounter;
+
+    type Output = u64;
+
+    fn read_op(&self, s: &Self::Socket, _: &mut [u8]) -> Result<Self::Output, SystemError> {
+        let mut buf = [0; 8];
+        read(s, &mut buf)?;
+        Ok(u64::from_ne_bytes(buf))
+    }
+}
+
+/// Provides an event counter backed by the `eventfd`.
+///
+/// The counter is added by `signal` from any thread (or a signal handler), and the waiting
+/// handler takes the whole value of the counter which is reset to zero.
+///
+/// # Examples
+///
+/// ```
+/// use std::io;
+/// use std::sync::Arc;
+/// use std::thread;
+/// use asyncio::{IoContext, AsyncCounter, wrap};
+///
+/// fn on_wait(_: Arc<AsyncCounter>, res: io::Result<u64>) {
+///   assert_eq!(res.unwrap(), 3);
+/// }
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let cnt = Arc::new(AsyncCounter::new(ctx).unwrap());
+/// cnt.async_wait(wrap(&cnt, on_wait));
+///
+/// let other = cnt.clone();
+/// thread::spawn(move || other.signal(3).unwrap()).join().unwrap();
+/// ctx.run();
+/// ```
+pub struct AsyncCounter {
+    pimpl: Box<SocketImpl<()>>,
+}
+
+impl AsyncCounter {
+    pub fn new(ctx: &IoContext) -> io::Result<Self> {
+        match unsafe { eventfd(0, EFD_CLOEXEC | EFD_NONBLOCK) } {
+            -1 => Err(SystemError::last_error().into()),
+            fd => Ok(AsyncCounter { pimpl: SocketImpl::new(ctx, fd, ()) }),
+        }
+    }
+
+    /// Asynchronously waits until the counter is non-zero, the handler takes the value.
+    pub fn async_wait<F>(&self, handler: F) -> F::Output
+    where
+        F: Handler<u64, io::Error>,
+    {
+        async_read_op(self, &[], &self.pimpl.timeout, handler, ReadCounter)
+    }
+
+    pub fn get_timeout(&self) -> Duration {
+        self.pimpl.timeout.get()
+    }
+
+    /// Takes the value of the counter, returns the `EAGAIN` error if the counter is zero.
+    pub fn nonblocking_wait(&self) -> io::Result<u64> {
+        nonblocking_read_op(self, &mut [], ReadCounter)
+    }
+
+    pub fn set_timeout(&self, timeout: Duration) -> io::Result<()> {
+        Ok(self.pimpl.timeout.set(timeout)?)
+    }
+
+    /// Adds `n` to the counter.
+    ///
+    /// This is a single `write` system call, that is safe to be called in a signal handler.
+    pub fn signal(&self, n: u64) -> io::Result<()> {
+        write(self, &n.to_ne_bytes())?;
+        Ok(())
+    }
+
+    /// Waits until the counter is non-zero and takes the value.
+    pub fn wait(&self) -> io::Result<u64> {
+        blocking_read_op(self, &mut [], &self.pimpl.timeout, ReadCounter)
+    }
+}
+
+unsafe impl Send for AsyncCounter {}
+
+unsafe impl Sync for AsyncCounter {}
+
+unsafe impl AsIoContext for AsyncCounter {
+    fn as_ctx(&self) -> &IoContext {
+        self.pimpl.as_ctx()
+    }
+}
+
+impl AsRawFd for AsyncCounter {
+    fn as_raw_fd(&self) -> RawFd {
+        self.pimpl.as_raw_fd()
+    }
+}
+
+impl Cancel for AsyncCounter {
+    fn cancel(&self) {
+        self.pimpl.cancel()
+    }
+}
+
+impl AsyncReadOp for AsyncCounter {
+    fn add_read_op(&self, this: &mut ThreadIoContext, op: Box<Perform>, err: SystemError) {
+        self.pimpl.add_read_op(this, op, err)
+    }
+
+    fn next_read_op(&self, this: &mut ThreadIoContext) {
+        self.pimpl.next_read_op(this)
+    }
+}
+
+impl fmt::Debug for AsyncCounter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "AsyncCounter({})", self.as_raw_fd())
+    }
+}
+
+#[test]
+fn test_async_counter() {
+    let ctx = &IoContext::new().unwrap();
+    let cnt = AsyncCounter::new(ctx).unwrap();
+    assert_eq!(
+        cnt.nonblocking_wait().unwrap_err().kind(),
+        io::ErrorKind::WouldBlock
+    );
+    cnt.signal(1).unwrap();
+    cnt.signal(2).unwrap();
+    assert_eq!(cnt.wait().unwrap(), 3);
+    assert!(cnt.nonblocking_wait().is_err());
+}
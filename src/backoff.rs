@@ -0,0 +1,201 @@
+use core::{AsIoContext, IoContext, Cancel};
+use handler::Handler;
+use SteadyTimer;
+
+use std::io;
+use std::cmp;
+use std::process;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+fn to_nanos(d: Duration) -> u64 {
+    d.as_secs().saturating_mul(1_000_000_000).saturating_add(
+        d.subsec_nanos() as u64,
+    )
+}
+
+fn from_nanos(nanos: u64) -> Duration {
+    Duration::new(nanos / 1_000_000_000, (nanos % 1_000_000_000) as u32)
+}
+
+fn new_seed() -> u64 {
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or(0);
+    let seed = ((nanos as u64) << 32) ^ (process::id() as u64) ^
+        ((COUNT.fetch_add(1, Ordering::SeqCst) as u64) << 16);
+    // The xorshift never leaves the zero state.
+    if seed == 0 {
+        0x9e37_79b9_7f4a_7c15
+    } else {
+        seed
+    }
+}
+
+struct State {
+    prev: u64,
+    attempts: usize,
+    seed: u64,
+}
+
+impl State {
+    fn random(&mut self) -> u64 {
+        // xorshift64*
+        self.seed ^= self.seed >> 12;
+        self.seed ^= self.seed << 25;
+        self.seed ^= self.seed >> 27;
+        self.seed.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+}
+
+/// Provides an exponential backoff with the decorrelated jitter for the retry loops.
+///
+/// Each delay is chosen at random between `base` and three times the previous delay, and is
+/// capped at `cap`. The jitter keeps the clients which failed at the same time from retrying at
+/// the same time.
+///
+/// # Examples
+///
+/// ```
+/// use std::io;
+/// use std::sync::Arc;
+/// use std::time::Duration;
+/// use asyncio::{IoContext, Backoff, wrap};
+///
+/// fn on_wait(backoff: Arc<Backoff>, res: io::Result<()>) {
+///     res.unwrap();
+///     // e.g. reconnects here, and waits again if failed.
+///     if backoff.attempts() < 3 {
+///         backoff.async_wait(wrap(&backoff, on_wait));
+///     }
+/// }
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let backoff = Arc::new(Backoff::new(ctx, Duration::from_millis(1), Duration::from_millis(10)));
+/// backoff.async_wait(wrap(&backoff, on_wait));
+/// ctx.run();
+/// assert_eq!(backoff.attempts(), 3);
+/// ```
+pub struct Backoff {
+    timer: SteadyTimer,
+    base: Duration,
+    cap: Duration,
+    state: Mutex<State>,
+}
+
+impl Backoff {
+    pub fn new(ctx: &IoContext, base: Duration, cap: Duration) -> Backoff {
+        Backoff {
+            timer: SteadyTimer::new(ctx),
+            base: base,
+            cap: cmp::max(base, cap),
+            state: Mutex::new(State {
+                prev: to_nanos(base),
+                attempts: 0,
+                seed: new_seed(),
+            }),
+        }
+    }
+
+    /// Asynchronously waits for the next delay.
+    pub fn async_wait<F>(&self, handler: F) -> F::Output
+    where
+        F: Handler<(), io::Error>,
+    {
+        self.timer.expires_from_now(self.next_delay());
+        self.timer.async_wait(handler)
+    }
+
+    /// Returns the number of delays since the creation or the last `reset`.
+    pub fn attempts(&self) -> usize {
+        self.state.lock().unwrap().attempts
+    }
+
+    pub fn base(&self) -> Duration {
+        self.base
+    }
+
+    pub fn cap(&self) -> Duration {
+        self.cap
+    }
+
+    /// Returns the next delay, that is counted as an attempt.
+    pub fn next_delay(&self) -> Duration {
+        let mut state = self.state.lock().unwrap();
+        let base = to_nanos(self.base);
+        let upper = cmp::min(state.prev.saturating_mul(3), to_nanos(self.cap));
+        let delay = if upper > base {
+            base + state.random() % (upper - base + 1)
+        } else {
+            upper
+        };
+        state.prev = delay;
+        state.attempts += 1;
+        from_nanos(delay)
+    }
+
+    /// Resets the delay to the `base`, e.g. after the connection is established.
+    pub fn reset(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.prev = to_nanos(self.base);
+        state.attempts = 0;
+    }
+
+    /// Waits for the next delay without running the context.
+    pub fn wait(&self) -> io::Result<()> {
+        self.timer.expires_from_now(self.next_delay());
+        self.timer.wait()
+    }
+}
+
+unsafe impl AsIoContext for Backoff {
+    fn as_ctx(&self) -> &IoContext {
+        self.timer.as_ctx()
+    }
+}
+
+impl Cancel for Backoff {
+    fn cancel(&self) {
+        self.timer.cancel();
+    }
+}
+
+#[test]
+fn test_backoff() {
+    let ctx = &IoContext::new().unwrap();
+    let base = Duration::from_millis(10);
+    let cap = Duration::from_secs(1);
+    let backoff = Backoff::new(ctx, base, cap);
+    let mut prev = base;
+    for _ in 0..100 {
+        let delay = backoff.next_delay();
+        assert!(delay >= base && delay <= cap);
+        assert!(delay <= prev * 3);
+        prev = delay;
+    }
+    assert_eq!(backoff.attempts(), 100);
+    backoff.reset();
+    assert_eq!(backoff.attempts(), 0);
+    assert!(backoff.next_delay() <= base * 3);
+
+    // the cap less than the base is regarded as the base.
+    let backoff = Backoff::new(ctx, base, Duration::new(0, 0));
+    assert_eq!(backoff.next_delay(), base);
+    assert_eq!(backoff.cap(), base);
+}
+
+#[test]
+fn test_backoff_wait() {
+    use std::time::Instant;
+
+    let ctx = &IoContext::new().unwrap();
+    let backoff = Backoff::new(ctx, Duration::from_millis(5), Duration::from_millis(5));
+    let now = Instant::now();
+    backoff.wait().unwrap();
+    backoff.wait().unwrap();
+    assert!(now.elapsed() >= Duration::from_millis(10));
+    assert_eq!(backoff.attempts(), 2);
+}
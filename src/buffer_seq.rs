@@ -0,0 +1,102 @@
+/// A sequence of the buffers to be written at once, like the `const_buffer_sequence` of Asio.
+///
+/// # Examples
+///
+/// ```
+/// use asyncio::BufferSequence;
+///
+/// let header = b"HTTP/1.1 200 OK\r\n\r\n";
+/// let body = vec![0; 100];
+/// let bufs = [&header[..], &body[..]];
+/// assert_eq!(bufs.buffers().len(), 2);
+/// assert_eq!(bufs.total_len(), 119);
+/// ```
+pub trait BufferSequence {
+    /// Returns the buffers in the order to be written.
+    fn buffers(&self) -> Vec<&[u8]>;
+
+    /// Returns the total length of the buffers.
+    fn total_len(&self) -> usize {
+        self.buffers().iter().map(|buf| buf.len()).sum()
+    }
+}
+
+impl<T> BufferSequence for [T]
+where
+    T: AsRef<[u8]>,
+{
+    fn buffers(&self) -> Vec<&[u8]> {
+        self.iter().map(|buf| buf.as_ref()).collect()
+    }
+}
+
+impl<T> BufferSequence for Vec<T>
+where
+    T: AsRef<[u8]>,
+{
+    fn buffers(&self) -> Vec<&[u8]> {
+        self[..].buffers()
+    }
+}
+
+/// A sequence of the buffers to be read at once, like the `mutable_buffer_sequence` of Asio.
+///
+/// # Examples
+///
+/// ```
+/// use asyncio::MutableBufferSequence;
+///
+/// let mut header = [0; 4];
+/// let mut body = vec![0; 100];
+/// let mut bufs = [&mut header[..], &mut body[..]];
+/// let lens: Vec<_> = bufs.buffers_mut().iter().map(|buf| buf.len()).collect();
+/// assert_eq!(lens, [4, 100]);
+/// ```
+pub trait MutableBufferSequence {
+    /// Returns the buffers in the order to be filled.
+    fn buffers_mut(&mut self) -> Vec<&mut [u8]>;
+}
+
+impl<T> MutableBufferSequence for [T]
+where
+    T: AsMut<[u8]>,
+{
+    fn buffers_mut(&mut self) -> Vec<&mut [u8]> {
+        self.iter_mut().map(|buf| buf.as_mut()).collect()
+    }
+}
+
+impl<T> MutableBufferSequence for Vec<T>
+where
+    T: AsMut<[u8]>,
+{
+    fn buffers_mut(&mut self) -> Vec<&mut [u8]> {
+        self[..].buffers_mut()
+    }
+}
+
+macro_rules! impl_array_buffer_sequence {
+    ($($n:expr)*) => {
+        $(
+            impl<T> BufferSequence for [T; $n]
+            where
+                T: AsRef<[u8]>,
+            {
+                fn buffers(&self) -> Vec<&[u8]> {
+                    self[..].buffers()
+                }
+            }
+
+            impl<T> MutableBufferSequence for [T; $n]
+            where
+                T: AsMut<[u8]>,
+            {
+                fn buffers_mut(&mut self) -> Vec<&mut [u8]> {
+                    self[..].buffers_mut()
+                }
+            }
+        )*
+    }
+}
+
+impl_array_buffer_sequence!(1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16);
@@ -0,0 +1,145 @@
+use core::{AsIoContext, IoContext, Cancel};
+
+use std::fmt;
+use std::sync::{Arc, Mutex, Weak};
+
+/// Provides a group of the sockets and the timers, that are canceled together when the scope is
+/// dropped.
+///
+/// The scope holds the weak references, so it doesn't keep the objects alive. The outstanding
+/// operations of the objects are completed with the `OPERATION_CANCELED` error, and then the
+/// handlers release the objects.
+///
+/// # Examples
+///
+/// ```
+/// use std::io;
+/// use std::sync::Arc;
+/// use std::time::Duration;
+/// use asyncio::{IoContext, CancellationScope, SteadyTimer, wrap};
+///
+/// fn on_wait(_: Arc<SteadyTimer>, res: io::Result<()>) {
+///   assert!(res.is_err());
+/// }
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let scope = CancellationScope::new(ctx);
+/// let timer = Arc::new(SteadyTimer::new(ctx));
+/// scope.add(&timer);
+/// timer.expires_from_now(Duration::new(60, 0));
+/// timer.async_wait(wrap(&timer, on_wait));
+///
+/// ctx.post(move |_| drop(scope));
+/// ctx.run();
+/// ```
+pub struct CancellationScope {
+    ctx: IoContext,
+    objs: Mutex<Vec<Weak<Cancel>>>,
+}
+
+impl CancellationScope {
+    pub fn new(ctx: &IoContext) -> Self {
+        CancellationScope {
+            ctx: ctx.clone(),
+            objs: Mutex::default(),
+        }
+    }
+
+    /// Adds the object to be canceled with the scope.
+    pub fn add<T>(&self, obj: &Arc<T>)
+    where
+        T: Cancel + Send + Sync,
+    {
+        let obj: Arc<Cancel> = obj.clone();
+        let mut objs = self.objs.lock().unwrap();
+        // Forgets the objects already dropped.
+        objs.retain(|obj| obj.upgrade().is_some());
+        objs.push(Arc::downgrade(&obj));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of the living objects in the scope.
+    pub fn len(&self) -> usize {
+        let objs = self.objs.lock().unwrap();
+        objs.iter().filter(|obj| obj.upgrade().is_some()).count()
+    }
+}
+
+unsafe impl Send for CancellationScope {}
+
+unsafe impl Sync for CancellationScope {}
+
+unsafe impl AsIoContext for CancellationScope {
+    fn as_ctx(&self) -> &IoContext {
+        &self.ctx
+    }
+}
+
+impl Cancel for CancellationScope {
+    /// Cancels all of the objects in the scope, the scope is still available after that.
+    fn cancel(&self) {
+        // Cancels without the lock, the object may be added to the scope in the cancel.
+        let objs: Vec<_> = self.objs
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|obj| obj.upgrade())
+            .collect();
+        for obj in objs {
+            obj.cancel();
+        }
+    }
+}
+
+impl Drop for CancellationScope {
+    fn drop(&mut self) {
+        self.cancel()
+    }
+}
+
+impl fmt::Debug for CancellationScope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "CancellationScope({})", self.len())
+    }
+}
+
+#[test]
+fn test_cancellation_scope() {
+    use std::io;
+    use std::time::Duration;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use handler::wrap;
+    use SteadyTimer;
+
+    static CANCELED: AtomicUsize = AtomicUsize::new(0);
+
+    fn on_wait(_: Arc<SteadyTimer>, res: io::Result<()>) {
+        if res.is_err() {
+            CANCELED.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let ctx = &IoContext::new().unwrap();
+    let scope = CancellationScope::new(ctx);
+    let inner = Arc::new(CancellationScope::new(ctx));
+    scope.add(&inner);
+    for _ in 0..3 {
+        let timer = Arc::new(SteadyTimer::new(ctx));
+        inner.add(&timer);
+        timer.expires_from_now(Duration::new(60, 0));
+        timer.async_wait(wrap(&timer, on_wait));
+    }
+    assert_eq!(inner.len(), 3);
+
+    // the dropped object is forgotten.
+    inner.add(&Arc::new(SteadyTimer::new(ctx)));
+    assert_eq!(inner.len(), 3);
+
+    ctx.post(move |_| drop(scope));
+    ctx.run();
+    assert_eq!(CANCELED.load(Ordering::SeqCst), 3);
+    assert_eq!(inner.len(), 0);
+}
@@ -111,6 +111,34 @@ impl Clock for SystemClock {
 }
 
 /// Provides waitable timer functionality.
+///
+/// Multiple handlers can wait on the same timer, and all of them are completed at the expiry.
+/// Changing the expiry cancels all the waiting handlers.
+///
+/// # Examples
+///
+/// ```
+/// use std::io;
+/// use std::sync::Arc;
+/// use std::time::Duration;
+/// use asyncio::{IoContext, SteadyTimer, wrap};
+///
+/// fn on_wait(_: Arc<SteadyTimer>, res: io::Result<()>) {
+///   assert!(res.is_err());  // canceled
+/// }
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let timer = Arc::new(SteadyTimer::new(ctx));
+/// timer.expires_from_now(Duration::new(60, 0));
+/// timer.async_wait(wrap(&timer, on_wait));
+/// timer.async_wait(wrap(&timer, on_wait));
+/// timer.async_wait(wrap(&timer, on_wait));
+/// ctx.post(move |_| {
+///   assert_eq!(timer.cancel_one(), 1);
+///   assert_eq!(timer.cancel(), 2);
+/// });
+/// ctx.run();
+/// ```
 pub struct WaitableTimer<C> {
     pimpl: Box<TimerImpl>,
     _marker: PhantomData<C>,
@@ -127,6 +155,8 @@ where
         }
     }
 
+    /// Asynchronously waits until the expiry of the timer, that can be waited by `Coroutine::wrap`
+    /// in the coroutine as well.
     pub fn async_wait<F>(&self, handler: F) -> F::Output
     where
         F: Handler<(), io::Error>,
@@ -134,6 +164,23 @@ where
         async_wait(self, handler)
     }
 
+    /// Cancels all the waiting handlers with the `ECANCELED` error, returns the number of them.
+    ///
+    /// The expiry of the timer is not changed.
+    pub fn cancel(&self) -> usize {
+        self.pimpl.cancel()
+    }
+
+    /// Cancels the oldest waiting handler with the `ECANCELED` error, returns the number of it.
+    pub fn cancel_one(&self) -> usize {
+        self.pimpl.cancel_one()
+    }
+
+    /// Sets the expiry relative to now, that is the same as `expires_from_now`.
+    pub fn expires_after(&self, expiry: C::Duration) {
+        self.expires_from_now(expiry)
+    }
+
     pub fn expires_at(&self, expiry: C::TimePoint) {
         self.pimpl.reset_expiry(expiry.into());
     }
@@ -142,8 +189,22 @@ where
         self.expires_at(C::now() + expiry);
     }
 
+    /// Blocks the current thread until the expiry of the timer.
+    ///
+    /// This does not need the `IoContext` running, the asynchronous handlers waiting on the timer
+    /// are not affected.
     pub fn wait(&self) -> io::Result<()> {
-        Ok(())
+        Ok(self.pimpl.wait(None)?)
+    }
+
+    /// Blocks until the expiry, returns the `ETIMEDOUT` error if the timeout expires first.
+    pub fn wait_for(&self, timeout: C::Duration) -> io::Result<()> {
+        self.wait_until(C::now() + timeout)
+    }
+
+    /// Blocks until the expiry, returns the `ETIMEDOUT` error if the deadline comes first.
+    pub fn wait_until(&self, deadline: C::TimePoint) -> io::Result<()> {
+        Ok(self.pimpl.wait(Some(deadline.into()))?)
     }
 }
 
@@ -155,7 +216,7 @@ unsafe impl<C> AsIoContext for WaitableTimer<C> {
 
 impl<C: 'static> Cancel for WaitableTimer<C> {
     fn cancel(&self) {
-        self.pimpl.cancel()
+        self.pimpl.cancel();
     }
 }
 
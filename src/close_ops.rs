@@ -0,0 +1,469 @@
+use ffi::{Timeout, TIMED_OUT};
+use core::{Protocol, IoContext, AsIoContext, ThreadIoContext, Cancel};
+use handler::{Handler, Complete, Success, Failure};
+use socket_base::{Linger, Shutdown};
+use stream::Stream;
+use stream_socket::StreamSocket;
+use streambuf::StreamBuf;
+use socket_listener::SocketListener;
+use SteadyTimer;
+
+use std::io;
+use std::cell::UnsafeCell;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+/// The state shared by the draining and the timer, that owns the socket until both are completed.
+struct CloseState<P> {
+    soc: UnsafeCell<StreamSocket<P>>,
+    timer: SteadyTimer,
+    buf: UnsafeCell<[u8; 4096]>,
+    sbuf: UnsafeCell<StreamBuf>,
+    done: Mutex<bool>,
+    expired: AtomicBool,
+}
+
+unsafe impl<P> Send for CloseState<P> {}
+
+unsafe impl<P> Sync for CloseState<P> {}
+
+impl<P> CloseState<P>
+where
+    P: Protocol + 'static,
+{
+    fn async_drain<F>(&self, handler: CloseDrain<P, F>)
+    where
+        F: Complete<(), io::Error>,
+    {
+        let soc = unsafe { &*self.soc.get() };
+        let buf = unsafe { &*self.buf.get() };
+        soc.async_read_some(buf, handler)
+    }
+
+    fn async_flush<F>(&self, handler: DrainFlush<P, F>)
+    where
+        F: Complete<usize, io::Error>,
+    {
+        let soc = unsafe { &*self.soc.get() };
+        let sbuf = unsafe { &mut *self.sbuf.get() };
+        soc.async_write_all::<usize, _>(sbuf, handler)
+    }
+
+    /// Closes the socket without blocking, the remaining data is sent in background.
+    fn close(&self) -> io::Result<()> {
+        let soc = unsafe { &mut *self.soc.get() };
+        let _ = soc.set_option(Linger::new(None));
+        soc.close()
+    }
+}
+
+fn linger_timeout<P>(soc: &StreamSocket<P>) -> Option<Duration>
+where
+    P: Protocol,
+{
+    soc.get_option::<Linger>().ok().and_then(|opt| opt.get()).map(
+        |secs| {
+            Duration::new(secs as u64, 0)
+        },
+    )
+}
+
+pub fn async_close<P, F>(soc: StreamSocket<P>, timeout: Option<Duration>, handler: F) -> F::Output
+where
+    P: Protocol + 'static,
+    F: Handler<(), io::Error>,
+{
+    let ctx = soc.as_ctx().clone();
+    handler.wrap(&ctx, move |ctx, handler| {
+        let mut soc = soc;
+        let timeout = match timeout.or_else(|| linger_timeout(&soc)) {
+            Some(timeout) if timeout > Duration::new(0, 0) => timeout,
+            _ => {
+                // Closes immediately, that is aborted if the linger timeout is zero.
+                return match soc.close() {
+                    Ok(_) => ctx.do_dispatch(Success::new((), handler)),
+                    Err(err) => ctx.do_dispatch(Failure::new(err, handler)),
+                };
+            }
+        };
+        if let Err(err) = soc.shutdown(Shutdown::Write) {
+            let _ = soc.close();
+            return ctx.do_dispatch(Failure::new(err, handler));
+        }
+
+        let state = Arc::new(CloseState {
+            soc: UnsafeCell::new(soc),
+            timer: SteadyTimer::new(ctx),
+            buf: UnsafeCell::new([0; 4096]),
+            sbuf: UnsafeCell::new(StreamBuf::new()),
+            done: Mutex::new(false),
+            expired: AtomicBool::new(false),
+        });
+        state.timer.expires_from_now(timeout);
+        state.timer.async_wait(CloseExpire { state: state.clone() });
+        state.async_drain(CloseDrain {
+            state: state.clone(),
+            handler: handler,
+        })
+    })
+}
+
+struct CloseDrain<P, F> {
+    state: Arc<CloseState<P>>,
+    handler: F,
+}
+
+impl<P, F> CloseDrain<P, F>
+where
+    P: Protocol + 'static,
+    F: Complete<(), io::Error>,
+{
+    fn finish(self, this: &mut ThreadIoContext, res: io::Result<()>) {
+        self.state.timer.cancel();
+        match res.and(self.state.close()) {
+            Ok(_) => self.handler.success(this, ()),
+            Err(err) => self.handler.failure(this, err),
+        }
+    }
+}
+
+impl<P, F> Handler<usize, io::Error> for CloseDrain<P, F>
+where
+    P: Protocol + 'static,
+    F: Complete<(), io::Error>,
+{
+    type Output = ();
+
+    type WrappedHandler = Self;
+
+    fn wrap<W>(self, ctx: &IoContext, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx, self)
+    }
+
+    fn wrap_timeout<W>(self, ctx: &Cancel, _: &Timeout, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx.as_ctx(), self)
+    }
+}
+
+impl<P, F> Complete<usize, io::Error> for CloseDrain<P, F>
+where
+    P: Protocol + 'static,
+    F: Complete<(), io::Error>,
+{
+    fn success(self, this: &mut ThreadIoContext, _: usize) {
+        let expired = {
+            let mut done = self.state.done.lock().unwrap();
+            *done = self.state.expired.load(Ordering::SeqCst);
+            *done
+        };
+        if expired {
+            return self.finish(this, Err(TIMED_OUT.into()));
+        }
+        // Discards the data received after the shutdown.
+        this.decrease_outstanding_work();
+        let state = self.state.clone();
+        state.async_drain(self)
+    }
+
+    fn failure(self, this: &mut ThreadIoContext, err: io::Error) {
+        if !self.state.expired.load(Ordering::SeqCst) {
+            *self.state.done.lock().unwrap() = true;
+        }
+        let res = if self.state.expired.load(Ordering::SeqCst) {
+            Err(TIMED_OUT.into())
+        } else if err.kind() == io::ErrorKind::ConnectionAborted {
+            Ok(()) // received the FIN of the peer.
+        } else {
+            Err(err)
+        };
+        self.finish(this, res)
+    }
+}
+
+struct CloseExpire<P> {
+    state: Arc<CloseState<P>>,
+}
+
+impl<P> Handler<(), io::Error> for CloseExpire<P>
+where
+    P: Protocol + 'static,
+{
+    type Output = ();
+
+    type WrappedHandler = Self;
+
+    fn wrap<W>(self, ctx: &IoContext, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx, self)
+    }
+
+    fn wrap_timeout<W>(self, ctx: &Cancel, _: &Timeout, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx.as_ctx(), self)
+    }
+}
+
+impl<P> Complete<(), io::Error> for CloseExpire<P>
+where
+    P: Protocol + 'static,
+{
+    fn success(self, this: &mut ThreadIoContext, _: ()) {
+        let done = self.state.done.lock().unwrap();
+        if !*done {
+            self.state.expired.store(true, Ordering::SeqCst);
+            unsafe { &*self.state.soc.get() }.cancel();
+        }
+        drop(done);
+        this.decrease_outstanding_work();
+    }
+
+    fn failure(self, this: &mut ThreadIoContext, _: io::Error) {
+        this.decrease_outstanding_work();
+    }
+}
+
+/// Flushes the data, and closes the socket after the peer finishes the connection by the deadline.
+pub fn async_drain_and_close<P, F>(
+    soc: StreamSocket<P>,
+    sbuf: StreamBuf,
+    deadline: Instant,
+    handler: F,
+) -> F::Output
+where
+    P: Protocol + 'static,
+    F: Handler<usize, io::Error>,
+{
+    let ctx = soc.as_ctx().clone();
+    handler.wrap(&ctx, move |ctx, handler| {
+        let empty = sbuf.is_empty();
+        let state = Arc::new(CloseState {
+            soc: UnsafeCell::new(soc),
+            timer: SteadyTimer::new(ctx),
+            buf: UnsafeCell::new([0; 4096]),
+            sbuf: UnsafeCell::new(sbuf),
+            done: Mutex::new(false),
+            expired: AtomicBool::new(false),
+        });
+        state.timer.expires_at(deadline);
+        state.timer.async_wait(CloseExpire { state: state.clone() });
+        let handler = DrainFlush {
+            state: state.clone(),
+            handler: handler,
+        };
+        if empty {
+            // The zero-length write is not distinguishable from the closed connection.
+            return ctx.do_dispatch(Success::new(0, handler));
+        }
+        state.async_flush(handler)
+    })
+}
+
+/// The flushing of the `async_drain_and_close`, that shuts down the sending side when completed.
+struct DrainFlush<P, F> {
+    state: Arc<CloseState<P>>,
+    handler: F,
+}
+
+impl<P, F> DrainFlush<P, F>
+where
+    P: Protocol + 'static,
+    F: Complete<usize, io::Error>,
+{
+    fn finish(self, this: &mut ThreadIoContext, err: io::Error) {
+        self.state.timer.cancel();
+        let _ = self.state.close();
+        self.handler.failure(this, err)
+    }
+}
+
+impl<P, F> Handler<usize, io::Error> for DrainFlush<P, F>
+where
+    P: Protocol + 'static,
+    F: Complete<usize, io::Error>,
+{
+    type Output = ();
+
+    type WrappedHandler = Self;
+
+    fn wrap<W>(self, ctx: &IoContext, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx, self)
+    }
+
+    fn wrap_timeout<W>(self, ctx: &Cancel, _: &Timeout, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx.as_ctx(), self)
+    }
+}
+
+impl<P, F> Complete<usize, io::Error> for DrainFlush<P, F>
+where
+    P: Protocol + 'static,
+    F: Complete<usize, io::Error>,
+{
+    fn success(self, this: &mut ThreadIoContext, len: usize) {
+        if self.state.expired.load(Ordering::SeqCst) {
+            return self.finish(this, TIMED_OUT.into());
+        }
+        if let Err(err) = unsafe { &*self.state.soc.get() }.shutdown(Shutdown::Write) {
+            *self.state.done.lock().unwrap() = true;
+            return self.finish(this, err);
+        }
+        this.decrease_outstanding_work();
+        let DrainFlush { state, handler } = self;
+        state.async_drain(CloseDrain {
+            state: state.clone(),
+            handler: DrainFlushed {
+                len: len,
+                handler: handler,
+            },
+        })
+    }
+
+    fn failure(self, this: &mut ThreadIoContext, err: io::Error) {
+        let expired = {
+            let mut done = self.state.done.lock().unwrap();
+            *done = true;
+            self.state.expired.load(Ordering::SeqCst)
+        };
+        if expired {
+            self.finish(this, TIMED_OUT.into())
+        } else {
+            self.finish(this, err)
+        }
+    }
+}
+
+/// Completes the `async_drain_and_close` with the flushed bytes.
+struct DrainFlushed<F> {
+    len: usize,
+    handler: F,
+}
+
+impl<F> Complete<(), io::Error> for DrainFlushed<F>
+where
+    F: Complete<usize, io::Error>,
+{
+    fn success(self, this: &mut ThreadIoContext, _: ()) {
+        self.handler.success(this, self.len)
+    }
+
+    fn failure(self, this: &mut ThreadIoContext, err: io::Error) {
+        self.handler.failure(this, err)
+    }
+}
+
+/// Accepts the connections established in the backlog without blocking.
+fn drain_backlog<P>(soc: &SocketListener<P>, accepted: &mut Vec<(P::Socket, P::Endpoint)>)
+where
+    P: Protocol,
+{
+    while let Ok(acc) = soc.nonblicking_accept() {
+        accepted.push(acc)
+    }
+}
+
+/// Stops accepting, and closes the listener at the deadline.
+pub fn async_close_gracefully<P, F>(
+    soc: &SocketListener<P>,
+    deadline: Instant,
+    handler: F,
+) -> F::Output
+where
+    P: Protocol,
+    F: Handler<Vec<(P::Socket, P::Endpoint)>, io::Error>,
+{
+    handler.wrap(soc.as_ctx(), move |ctx, handler| {
+        soc.cancel();
+        let mut accepted = Vec::new();
+        drain_backlog(soc, &mut accepted);
+        let timer = Arc::new(SteadyTimer::new(ctx));
+        timer.expires_at(deadline);
+        timer.async_wait(CloseListener {
+            soc: soc,
+            timer: timer.clone(),
+            accepted: accepted,
+            handler: handler,
+        })
+    })
+}
+
+/// The timer of the graceful close, that holds itself until the deadline.
+struct CloseListener<P: Protocol, F> {
+    soc: *const SocketListener<P>,
+    timer: Arc<SteadyTimer>,
+    accepted: Vec<(P::Socket, P::Endpoint)>,
+    handler: F,
+}
+
+unsafe impl<P: Protocol, F> Send for CloseListener<P, F> {}
+
+impl<P, F> CloseListener<P, F>
+where
+    P: Protocol,
+    F: Complete<Vec<(P::Socket, P::Endpoint)>, io::Error>,
+{
+    fn finish(self, this: &mut ThreadIoContext, res: io::Result<()>) {
+        let CloseListener { soc, timer, mut accepted, handler } = self;
+        drop(timer);
+        let soc = unsafe { &mut *(soc as *mut SocketListener<P>) };
+        drain_backlog(soc, &mut accepted);
+        match res.and(soc.close()) {
+            Ok(_) => handler.success(this, accepted),
+            Err(err) => handler.failure(this, err),
+        }
+    }
+}
+
+impl<P, F> Handler<(), io::Error> for CloseListener<P, F>
+where
+    P: Protocol,
+    F: Complete<Vec<(P::Socket, P::Endpoint)>, io::Error>,
+{
+    type Output = ();
+
+    type WrappedHandler = Self;
+
+    fn wrap<W>(self, ctx: &IoContext, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx, self)
+    }
+
+    fn wrap_timeout<W>(self, ctx: &Cancel, _: &Timeout, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx.as_ctx(), self)
+    }
+}
+
+impl<P, F> Complete<(), io::Error> for CloseListener<P, F>
+where
+    P: Protocol,
+    F: Complete<Vec<(P::Socket, P::Endpoint)>, io::Error>,
+{
+    fn success(self, this: &mut ThreadIoContext, _: ()) {
+        self.finish(this, Ok(()))
+    }
+
+    fn failure(self, this: &mut ThreadIoContext, err: io::Error) {
+        self.finish(this, Err(err))
+    }
+}
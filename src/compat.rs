@@ -0,0 +1,345 @@
+//! Converts the sockets between the `IoContext` and the std or the other runtimes.
+//!
+//! The socket is handed over by the raw file descriptor, that the pending operations of the
+//! original runtime must be completed before the conversion.
+
+use ffi::IntoRawFd;
+use core::{IoContext, Socket};
+use ip::{IpProtocol, Tcp, TcpListener, TcpSocket, Udp, UdpSocket};
+use local::{LocalStream, LocalStreamSocket};
+
+use std::io;
+use std::net;
+use std::os::unix::io::FromRawFd;
+use std::os::unix::net::UnixStream;
+
+#[cfg(feature = "tokio")]
+use tokio;
+
+#[cfg(feature = "async-std")]
+use async_std;
+
+fn into_std<S, T>(soc: S) -> T
+where
+    S: IntoRawFd,
+    T: FromRawFd,
+{
+    unsafe { T::from_raw_fd(soc.into_raw_fd()) }
+}
+
+fn ip_protocol<P>(addr: net::SocketAddr) -> P
+where
+    P: IpProtocol,
+{
+    match addr {
+        net::SocketAddr::V4(_) => P::v4(),
+        net::SocketAddr::V6(_) => P::v6(),
+    }
+}
+
+#[cfg(any(feature = "tokio", feature = "async-std"))]
+fn tcp_stream(soc: TcpSocket) -> io::Result<net::TcpStream> {
+    let soc = soc.into_std()?;
+    soc.set_nonblocking(true)?;
+    Ok(soc)
+}
+
+#[cfg(any(feature = "tokio", feature = "async-std"))]
+fn tcp_listener(soc: TcpListener) -> io::Result<net::TcpListener> {
+    let soc = soc.into_std()?;
+    soc.set_nonblocking(true)?;
+    Ok(soc)
+}
+
+#[cfg(any(feature = "tokio", feature = "async-std"))]
+fn udp_socket(soc: UdpSocket) -> io::Result<net::UdpSocket> {
+    let soc = soc.into_std()?;
+    soc.set_nonblocking(true)?;
+    Ok(soc)
+}
+
+#[cfg(any(feature = "tokio", feature = "async-std"))]
+fn unix_stream(soc: LocalStreamSocket) -> io::Result<UnixStream> {
+    let soc = soc.into_std()?;
+    soc.set_nonblocking(true)?;
+    Ok(soc)
+}
+
+impl TcpSocket {
+    /// Adopts the connected std's stream, that the socket options are preserved.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::net;
+    /// use asyncio::IoContext;
+    /// use asyncio::ip::TcpSocket;
+    ///
+    /// let ctx = &IoContext::new().unwrap();
+    /// let acc = net::TcpListener::bind("127.0.0.1:0").unwrap();
+    /// let soc = net::TcpStream::connect(acc.local_addr().unwrap()).unwrap();
+    /// let soc = TcpSocket::from_std(ctx, soc).unwrap();
+    /// assert_eq!(soc.remote_endpoint().unwrap().port(), acc.local_addr().unwrap().port());
+    /// ```
+    pub fn from_std(ctx: &IoContext, soc: net::TcpStream) -> io::Result<Self> {
+        soc.set_nonblocking(true)?;
+        let pro: Tcp = ip_protocol(soc.local_addr()?);
+        Ok(unsafe { TcpSocket::from_raw_fd(ctx, soc.into_raw_fd(), pro) })
+    }
+
+    /// Converts into the std's stream in the blocking mode, that the socket options are preserved.
+    ///
+    /// The pending operations must be completed before the conversion.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::net;
+    /// use std::io::Write;
+    /// use asyncio::IoContext;
+    /// use asyncio::ip::{IpProtocol, Tcp, TcpSocket};
+    ///
+    /// let ctx = &IoContext::new().unwrap();
+    /// let acc = net::TcpListener::bind("127.0.0.1:0").unwrap();
+    /// let soc = TcpSocket::new(ctx, Tcp::v4()).unwrap();
+    /// soc.connect(&acc.local_addr().unwrap().into()).unwrap();
+    ///
+    /// let mut soc: net::TcpStream = soc.into_std().unwrap();
+    /// soc.write_all(b"hello").unwrap();
+    /// ```
+    pub fn into_std(self) -> io::Result<net::TcpStream> {
+        let soc: net::TcpStream = into_std(self);
+        soc.set_nonblocking(false)?;
+        Ok(soc)
+    }
+}
+
+impl TcpListener {
+    /// Adopts the listening std's listener, that the socket options are preserved.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::net;
+    /// use asyncio::IoContext;
+    /// use asyncio::ip::TcpListener;
+    ///
+    /// let ctx = &IoContext::new().unwrap();
+    /// let acc = net::TcpListener::bind("127.0.0.1:0").unwrap();
+    /// let port = acc.local_addr().unwrap().port();
+    /// let acc = TcpListener::from_std(ctx, acc).unwrap();
+    /// assert_eq!(acc.local_endpoint().unwrap().port(), port);
+    /// ```
+    pub fn from_std(ctx: &IoContext, soc: net::TcpListener) -> io::Result<Self> {
+        soc.set_nonblocking(true)?;
+        let pro: Tcp = ip_protocol(soc.local_addr()?);
+        Ok(unsafe { TcpListener::from_raw_fd(ctx, soc.into_raw_fd(), pro) })
+    }
+
+    /// Converts into the std's listener in the blocking mode, that the socket options are
+    /// preserved.
+    pub fn into_std(self) -> io::Result<net::TcpListener> {
+        let soc: net::TcpListener = into_std(self);
+        soc.set_nonblocking(false)?;
+        Ok(soc)
+    }
+}
+
+impl UdpSocket {
+    /// Adopts the bound std's socket, that the socket options are preserved.
+    pub fn from_std(ctx: &IoContext, soc: net::UdpSocket) -> io::Result<Self> {
+        soc.set_nonblocking(true)?;
+        let pro: Udp = ip_protocol(soc.local_addr()?);
+        Ok(unsafe { UdpSocket::from_raw_fd(ctx, soc.into_raw_fd(), pro) })
+    }
+
+    /// Converts into the std's socket in the blocking mode, that the socket options are preserved.
+    pub fn into_std(self) -> io::Result<net::UdpSocket> {
+        let soc: net::UdpSocket = into_std(self);
+        soc.set_nonblocking(false)?;
+        Ok(soc)
+    }
+}
+
+impl LocalStreamSocket {
+    /// Adopts the connected std's stream, that the socket options are preserved.
+    pub fn from_std(ctx: &IoContext, soc: UnixStream) -> io::Result<Self> {
+        soc.set_nonblocking(true)?;
+        Ok(unsafe { LocalStreamSocket::from_raw_fd(ctx, soc.into_raw_fd(), LocalStream) })
+    }
+
+    /// Converts into the std's stream in the blocking mode, that the socket options are preserved.
+    pub fn into_std(self) -> io::Result<UnixStream> {
+        let soc: UnixStream = into_std(self);
+        soc.set_nonblocking(false)?;
+        Ok(soc)
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl TcpSocket {
+    /// Converts into the tokio's stream, which must be called in the context of the tokio runtime.
+    pub fn into_tokio(self) -> io::Result<tokio::net::TcpStream> {
+        tokio::net::TcpStream::from_std(tcp_stream(self)?)
+    }
+
+    /// Adopts the tokio's stream.
+    pub fn from_tokio(ctx: &IoContext, soc: tokio::net::TcpStream) -> io::Result<Self> {
+        TcpSocket::from_std(ctx, soc.into_std()?)
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl TcpListener {
+    /// Converts into the tokio's listener, which must be called in the context of the tokio runtime.
+    pub fn into_tokio(self) -> io::Result<tokio::net::TcpListener> {
+        tokio::net::TcpListener::from_std(tcp_listener(self)?)
+    }
+
+    /// Adopts the tokio's listener.
+    pub fn from_tokio(ctx: &IoContext, soc: tokio::net::TcpListener) -> io::Result<Self> {
+        TcpListener::from_std(ctx, soc.into_std()?)
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl UdpSocket {
+    /// Converts into the tokio's socket, which must be called in the context of the tokio runtime.
+    pub fn into_tokio(self) -> io::Result<tokio::net::UdpSocket> {
+        tokio::net::UdpSocket::from_std(udp_socket(self)?)
+    }
+
+    /// Adopts the tokio's socket.
+    pub fn from_tokio(ctx: &IoContext, soc: tokio::net::UdpSocket) -> io::Result<Self> {
+        UdpSocket::from_std(ctx, soc.into_std()?)
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl LocalStreamSocket {
+    /// Converts into the tokio's stream, which must be called in the context of the tokio runtime.
+    pub fn into_tokio(self) -> io::Result<tokio::net::UnixStream> {
+        tokio::net::UnixStream::from_std(unix_stream(self)?)
+    }
+
+    /// Adopts the tokio's stream.
+    pub fn from_tokio(ctx: &IoContext, soc: tokio::net::UnixStream) -> io::Result<Self> {
+        LocalStreamSocket::from_std(ctx, soc.into_std()?)
+    }
+}
+
+#[cfg(feature = "async-std")]
+impl TcpSocket {
+    /// Converts into the async-std's stream.
+    pub fn into_async_std(self) -> io::Result<async_std::net::TcpStream> {
+        Ok(tcp_stream(self)?.into())
+    }
+
+    /// Adopts the async-std's stream, which must not be cloned.
+    pub fn from_async_std(ctx: &IoContext, soc: async_std::net::TcpStream) -> io::Result<Self> {
+        TcpSocket::from_std(ctx, into_std(soc))
+    }
+}
+
+#[cfg(feature = "async-std")]
+impl TcpListener {
+    /// Converts into the async-std's listener.
+    pub fn into_async_std(self) -> io::Result<async_std::net::TcpListener> {
+        Ok(tcp_listener(self)?.into())
+    }
+
+    /// Adopts the async-std's listener.
+    pub fn from_async_std(ctx: &IoContext, soc: async_std::net::TcpListener) -> io::Result<Self> {
+        TcpListener::from_std(ctx, into_std(soc))
+    }
+}
+
+#[cfg(feature = "async-std")]
+impl UdpSocket {
+    /// Converts into the async-std's socket.
+    pub fn into_async_std(self) -> io::Result<async_std::net::UdpSocket> {
+        Ok(udp_socket(self)?.into())
+    }
+
+    /// Adopts the async-std's socket.
+    pub fn from_async_std(ctx: &IoContext, soc: async_std::net::UdpSocket) -> io::Result<Self> {
+        UdpSocket::from_std(ctx, into_std(soc))
+    }
+}
+
+#[cfg(feature = "async-std")]
+impl LocalStreamSocket {
+    /// Converts into the async-std's stream.
+    pub fn into_async_std(self) -> io::Result<async_std::os::unix::net::UnixStream> {
+        Ok(unix_stream(self)?.into())
+    }
+
+    /// Adopts the async-std's stream, which must not be cloned.
+    pub fn from_async_std(
+        ctx: &IoContext,
+        soc: async_std::os::unix::net::UnixStream,
+    ) -> io::Result<Self> {
+        LocalStreamSocket::from_std(ctx, into_std(soc))
+    }
+}
+
+#[test]
+fn test_from_std() {
+    use socket_base::Broadcast;
+
+    let ctx = &IoContext::new().unwrap();
+    let std = net::UdpSocket::bind("127.0.0.1:0").unwrap();
+    std.set_broadcast(true).unwrap();
+    let ep = std.local_addr().unwrap();
+    let soc = UdpSocket::from_std(ctx, std).unwrap();
+    assert_eq!(soc.local_endpoint().unwrap().port(), ep.port());
+    assert!(soc.get_option::<Broadcast>().unwrap().get());
+    soc.send_to(b"hello", 0, &soc.local_endpoint().unwrap()).unwrap();
+    let mut buf = [0; 16];
+    assert_eq!(soc.receive(&mut buf, 0).unwrap(), 5);
+}
+
+#[test]
+#[cfg(any(feature = "tokio", feature = "async-std"))]
+fn test_into_std() {
+    use ip::{IpAddrV4, TcpEndpoint};
+
+    let ctx = &IoContext::new().unwrap();
+    let acc = TcpListener::new(ctx, Tcp::v4()).unwrap();
+    acc.bind(&TcpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+    acc.listen().unwrap();
+    let ep = acc.local_endpoint().unwrap();
+
+    let std = tcp_listener(acc).unwrap();
+    assert_eq!(std.local_addr().unwrap().port(), ep.port());
+    let acc = TcpListener::from_std(ctx, std).unwrap();
+    assert_eq!(acc.local_endpoint().unwrap(), ep);
+}
+
+#[test]
+fn test_into_std_blocking() {
+    use std::io::{Read, Write};
+    use std::time::{Duration, Instant};
+    use ip::{IpAddrV4, UdpEndpoint};
+
+    let ctx = &IoContext::new().unwrap();
+    let soc = UdpSocket::new(ctx, Udp::v4()).unwrap();
+    soc.bind(&UdpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+    let ep = soc.local_endpoint().unwrap();
+    let std = soc.into_std().unwrap();
+    assert_eq!(net::SocketAddr::from(ep), std.local_addr().unwrap());
+
+    // The std's socket blocks until the timeout.
+    std.set_read_timeout(Some(Duration::from_millis(10))).unwrap();
+    let mut buf = [0; 16];
+    let now = Instant::now();
+    assert!(std.recv(&mut buf).is_err());
+    assert!(now.elapsed() >= Duration::from_millis(10));
+
+    let (a, b) = UnixStream::pair().unwrap();
+    let a = LocalStreamSocket::from_std(ctx, a).unwrap();
+    let mut a = a.into_std().unwrap();
+    let mut b = b;
+    a.write_all(b"hello").unwrap();
+    assert_eq!(b.read(&mut buf).unwrap(), 5);
+}
@@ -4,6 +4,7 @@ use ffi::{connect, connection_check, writable, Timeout, SystemError, OPERATION_C
           IN_PROGRESS, WOULD_BLOCK, INTERRUPTED};
 use core::{Protocol, AsIoContext, Socket, Exec, Perform, ThreadIoContext};
 use handler::{Complete, Handler, AsyncWriteOp, Failure};
+use observer::{notify_connect, notify_error};
 
 use std::io;
 use std::marker::PhantomData;
@@ -32,12 +33,14 @@ where
 {
     fn success(self, this: &mut ThreadIoContext, res: ()) {
         let soc = unsafe { &*self.soc };
+        notify_connect::<P, S>(this.as_ctx(), soc, &self.ep);
         soc.next_write_op(this);
         self.handler.success(this, res)
     }
 
     fn failure(self, this: &mut ThreadIoContext, err: io::Error) {
         let soc = unsafe { &*self.soc };
+        notify_error(this.as_ctx(), soc, &err);
         soc.next_write_op(this);
         self.handler.failure(this, err)
     }
@@ -111,23 +114,45 @@ where
 }
 
 
+fn observe<P, S>(soc: &S, ep: &P::Endpoint, res: Result<(), SystemError>) -> io::Result<()>
+where
+    P: Protocol,
+    S: Socket<P> + AsIoContext,
+{
+    match res {
+        Ok(()) => notify_connect::<P, S>(soc.as_ctx(), soc, ep),
+        Err(IN_PROGRESS) | Err(WOULD_BLOCK) => (),
+        Err(err) => notify_error(soc.as_ctx(), soc, &err.into()),
+    }
+    Ok(res?)
+}
+
 pub fn blocking_connect<P, S>(soc: &S, ep: &P::Endpoint, timeout: &Timeout) -> io::Result<()>
+where
+    P: Protocol,
+    S: Socket<P> + AsIoContext,
+{
+    let res = connect_until(soc, ep, timeout);
+    observe(soc, ep, res)
+}
+
+fn connect_until<P, S>(soc: &S, ep: &P::Endpoint, timeout: &Timeout) -> Result<(), SystemError>
 where
     P: Protocol,
     S: Socket<P> + AsIoContext,
 {
     if soc.as_ctx().stopped() {
-        return Err(OPERATION_CANCELED.into());
+        return Err(OPERATION_CANCELED);
     }
     loop {
         match connect(soc, ep) {
             Ok(_) => return Ok(()),
             Err(IN_PROGRESS) | Err(WOULD_BLOCK) => {
                 writable(soc, timeout)?;
-                return Ok(connection_check(soc)?);
+                return connection_check(soc);
             }
             Err(INTERRUPTED) if !soc.as_ctx().stopped() => (),
-            Err(err) => return Err(err.into()),
+            Err(err) => return Err(err),
         }
     }
 }
@@ -141,5 +166,6 @@ where
     if soc.as_ctx().stopped() {
         return Err(OPERATION_CANCELED.into());
     }
-    Ok(connect(soc, ep)?)
+    let res = connect(soc, ep);
+    observe(soc, ep, res)
 }
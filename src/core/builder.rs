@@ -0,0 +1,91 @@
+use core::IoContext;
+use core::pool::ThreadOptions;
+use reactor::{Interrupter, Intr};
+
+use std::io;
+
+/// Builds the `IoContext` with the custom configuration.
+///
+/// The context creates no threads in the default configuration, the handlers and the blocking
+/// operations (e.g. the name resolution) run on the threads calling `run`. The internal threads
+/// are spawned on demand only if `threads` is set, and named and pinned as configured.
+///
+/// # Examples
+///
+/// ```
+/// use asyncio::IoContextBuilder;
+///
+/// let ctx = IoContextBuilder::new()
+///     .threads(2)
+///     .thread_name("resolver")
+///     .thread_affinity(&[0])
+///     .concurrency_hint(4)
+///     .build()
+///     .unwrap();
+/// assert_eq!(ctx.threads(), 2);
+/// assert_eq!(ctx.concurrency_hint(), 4);
+/// ```
+#[derive(Default)]
+pub struct IoContextBuilder {
+    intr: Option<Intr>,
+    options: ThreadOptions,
+    concurrency: usize,
+}
+
+impl IoContextBuilder {
+    pub fn new() -> Self {
+        IoContextBuilder::default()
+    }
+
+    pub fn build(self) -> io::Result<IoContext> {
+        let intr = match self.intr {
+            Some(intr) => intr,
+            None => Intr::new()?,
+        };
+        let ctx = IoContext::with_intr(intr, self.options)?;
+        ctx.set_concurrency_hint(self.concurrency);
+        Ok(ctx)
+    }
+
+    /// Sets the number of the threads expected to call `run`, the default is one.
+    ///
+    /// See `IoContext::set_concurrency_hint`.
+    pub fn concurrency_hint(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency;
+        self
+    }
+
+    /// Uses the custom interrupter to wake up the reactor.
+    pub fn interrupter<T>(mut self, intr: T) -> Self
+    where
+        T: Interrupter,
+    {
+        self.intr = Some(Intr::with(Box::new(intr)));
+        self
+    }
+
+    /// Restricts the internal threads to the CPUs, that is ignored on the platforms other than
+    /// Linux.
+    pub fn thread_affinity(mut self, cpus: &[usize]) -> Self {
+        self.options.affinity = cpus.to_vec();
+        self
+    }
+
+    /// Sets the prefix of the names of the internal threads, the default is `asyncio`.
+    ///
+    /// Each thread is named with the prefix and the index, e.g. `asyncio-0`.
+    pub fn thread_name<S>(mut self, name: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.options.name = Some(name.into());
+        self
+    }
+
+    /// Sets the maximum number of the internal threads for the blocking operations, the default
+    /// is zero.
+    pub fn threads(mut self, count: usize) -> Self {
+        self.options.count = count;
+        self
+    }
+}
@@ -1,20 +1,45 @@
-use ffi::SystemError;
-use core::ThreadCallStack;
-use reactor::Reactor;
+use ffi::{AsRawFd, RawFd, SystemError, OPERATION_CANCELED};
+use core::{ThreadCallStack, LeakTracker, HookGuard, InvokeHook, ThreadPool};
+use core::pool::ThreadOptions;
+use reactor::{Reactor, Interrupter, Intr};
+use observer::SocketObserver;
 
 use std::io;
-use std::sync::{Arc, Condvar, Mutex};
+use std::sync::{Arc, Condvar, Mutex, RwLock};
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::collections::VecDeque;
 use std::ops::Deref;
+use std::time::Duration;
+#[cfg(all(target_os = "linux", feature = "uring"))]
+use io_uring::squeue;
 
 pub trait Perform: Send + 'static {
     fn perform(self: Box<Self>, this: &mut ThreadIoContext, err: SystemError);
+
+    #[doc(hidden)]
+    fn type_name(&self) -> &'static str {
+        ::std::any::type_name::<Self>()
+    }
+
+    /// Returns the request submitted to the `io_uring` in place of polling the handle, if any.
+    #[doc(hidden)]
+    #[cfg(all(target_os = "linux", feature = "uring"))]
+    fn uring_entry(&mut self) -> Option<squeue::Entry> {
+        None
+    }
+
+    /// Completes the request returned by the `uring_entry` with the result of its completion.
+    #[doc(hidden)]
+    #[cfg(all(target_os = "linux", feature = "uring"))]
+    fn uring_complete(self: Box<Self>, this: &mut ThreadIoContext, _res: i32) {
+        self.perform(this, SystemError::default())
+    }
 }
 
 #[derive(Default)]
 pub struct ThreadInfo {
     pending_queue: Vec<(Box<Perform>, SystemError)>,
+    thread_inits: usize,
 }
 
 pub type ThreadIoContext = ThreadCallStack<IoContext, ThreadInfo>;
@@ -47,6 +72,12 @@ pub trait Exec: Send + 'static {
     fn outstanding_work(&self, ctx: &IoContext) {
         ctx.0.outstanding_work.fetch_add(1, Ordering::SeqCst);
     }
+
+    /// Returns false if this is not a handler of the application, which is not surrounded by the
+    /// invocation hook.
+    fn is_handler(&self) -> bool {
+        true
+    }
 }
 
 impl<F> Exec for F
@@ -67,6 +98,7 @@ where
 impl Exec for (Box<Perform>, SystemError) {
     fn call(self, this: &mut ThreadIoContext) {
         let (op, err) = self;
+        this.as_ctx().0.leaks.untrack(&*op);
         op.perform(this, err)
     }
 
@@ -81,8 +113,48 @@ struct Executor {
     mutex: Mutex<VecDeque<Box<Exec>>>,
     condvar: Condvar,
     stopped: AtomicBool,
+    shutdown: AtomicBool,
+    pollable: AtomicBool,
     outstanding_work: AtomicUsize,
+    concurrency: AtomicUsize,
+    idle: AtomicUsize,
+    pollers: AtomicUsize,
     reactor: Reactor,
+    observer: RwLock<Option<Arc<SocketObserver>>>,
+    hook: RwLock<Option<Arc<InvokeHook>>>,
+    io_batch: AtomicUsize,
+    posted_batch: AtomicUsize,
+    leaks: LeakTracker,
+    pool: ThreadPool,
+    thread_inits: Mutex<Vec<Arc<Fn(&IoContext) + Send + Sync>>>,
+    thread_inits_len: AtomicUsize,
+}
+
+impl Drop for Executor {
+    fn drop(&mut self) {
+        self.leaks.report(self.mutex.lock().unwrap().len())
+    }
+}
+
+impl Executor {
+    /// Hands over the completions of the poll, except for the share of this thread, to the idle
+    /// threads.
+    fn share_pending(&self, this: &mut ThreadIoContext) {
+        if self.concurrency.load(Ordering::Relaxed) <= 1 {
+            return;
+        }
+        let idle = self.idle.load(Ordering::SeqCst);
+        let len = this.pending_queue.len();
+        if idle == 0 || len <= 1 {
+            return;
+        }
+        let keep = (len + idle) / (idle + 1);
+        let mut queue = self.mutex.lock().unwrap();
+        for op in this.pending_queue.drain(keep..) {
+            queue.push_back(Box::new(op));
+            self.condvar.notify_one();
+        }
+    }
 }
 
 unsafe impl Send for Executor {}
@@ -107,13 +179,20 @@ impl Exec for ExecutorRef {
     }
 
     fn call_box(self: Box<Self>, this: &mut ThreadIoContext) {
+        if this.as_ctx().is_shutdown() {
+            self.pollers.fetch_sub(1, Ordering::SeqCst);
+            Box::into_raw(self);
+            return;
+        }
         if this.as_ctx().0.outstanding_work.load(Ordering::Relaxed) == 0 {
             this.as_ctx().stop();
         } else {
             let more_handlers = this.as_ctx().0.mutex.lock().unwrap().len();
-            self.reactor.poll(more_handlers == 0, this)
+            self.reactor.poll(more_handlers == 0, this);
+            self.share_pending(this);
         }
         if this.as_ctx().stopped() {
+            self.pollers.fetch_sub(1, Ordering::SeqCst);
             Box::into_raw(self);
         } else {
             this.as_ctx().push(self);
@@ -121,29 +200,171 @@ impl Exec for ExecutorRef {
     }
 
     fn outstanding_work(&self, _: &IoContext) {}
+
+    fn is_handler(&self) -> bool {
+        false
+    }
+}
+
+const FAIR_BATCH: usize = 16;
+
+/// The scheduling policy between the completions of the I/O and the posted handlers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Scheduling {
+    /// Interleaves the batches of up to the number of completions and posted handlers in FIFO order.
+    Fair(usize),
+
+    /// Performs all the ready completions before the posted handlers.
+    PreferIo,
+
+    /// Performs all the queued posted handlers between each of the completions.
+    PreferPosted,
+}
+
+impl Default for Scheduling {
+    fn default() -> Self {
+        Scheduling::Fair(FAIR_BATCH)
+    }
 }
 
 #[derive(Clone)]
 pub struct IoContext(Arc<Executor>);
 
 impl IoContext {
+    /// Returns a new context, that creates no threads.
+    ///
+    /// Use `IoContextBuilder` to configure the internal threads.
     pub fn new() -> io::Result<Self> {
+        IoContext::with_intr(Intr::new()?, ThreadOptions::default())
+    }
+
+    /// Returns a new context for the `run` called from the number of threads.
+    ///
+    /// See `set_concurrency_hint`.
+    pub fn new_with_concurrency(concurrency: usize) -> io::Result<Self> {
+        let ctx = IoContext::new()?;
+        ctx.set_concurrency_hint(concurrency);
+        Ok(ctx)
+    }
+
+    /// Returns a new context using the custom interrupter to wake up the reactor.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::os::unix::io::{AsRawFd, RawFd};
+    /// use asyncio::{IoContext, Interrupter};
+    ///
+    /// struct SelfPipe(RawFd, RawFd);
+    ///
+    /// impl AsRawFd for SelfPipe {
+    ///   fn as_raw_fd(&self) -> RawFd { self.0 }
+    /// }
+    ///
+    /// impl Interrupter for SelfPipe {
+    ///   fn interrupt(&self) {
+    ///     unsafe { libc::write(self.1, b"x".as_ptr() as *const _, 1) };
+    ///   }
+    /// }
+    /// # extern crate libc;
+    /// # fn main() {
+    /// let mut fds = [0; 2];
+    /// unsafe { libc::pipe(fds.as_mut_ptr()) };
+    /// let ctx = IoContext::with_interrupter(SelfPipe(fds[0], fds[1])).unwrap();
+    /// ctx.wake();
+    /// # }
+    /// ```
+    pub fn with_interrupter<T>(intr: T) -> io::Result<Self>
+    where
+        T: Interrupter,
+    {
+        IoContext::with_intr(Intr::with(Box::new(intr)), ThreadOptions::default())
+    }
+
+    #[doc(hidden)]
+    pub fn with_intr(intr: Intr, options: ThreadOptions) -> io::Result<Self> {
         let ctx = Arc::new(Executor {
             mutex: Default::default(),
             condvar: Default::default(),
             stopped: Default::default(),
+            shutdown: Default::default(),
+            pollable: Default::default(),
             outstanding_work: Default::default(),
-            reactor: Reactor::new()?,
+            concurrency: AtomicUsize::new(1),
+            idle: Default::default(),
+            pollers: Default::default(),
+            reactor: Reactor::new(intr)?,
+            observer: Default::default(),
+            hook: Default::default(),
+            io_batch: AtomicUsize::new(FAIR_BATCH),
+            posted_batch: AtomicUsize::new(FAIR_BATCH),
+            leaks: Default::default(),
+            pool: ThreadPool::new(options),
+            thread_inits: Default::default(),
+            thread_inits_len: Default::default(),
         });
         ctx.reactor.init();
         Ok(IoContext(ctx))
     }
 
+    /// Brings forward the expiries of all the waiting timers by the duration, as if the time has
+    /// passed.
+    ///
+    /// The timeouts of the composed operations (e.g. `async_close` and the coroutine) are waited
+    /// on the timers of the context, so the tests can exercise them without the real sleeps. The
+    /// timers set after this call and the blocking waits are not affected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io;
+    /// use std::sync::Arc;
+    /// use std::time::{Duration, Instant};
+    /// use asyncio::{IoContext, SteadyTimer, wrap};
+    ///
+    /// fn on_wait(_: Arc<SteadyTimer>, res: io::Result<()>) {
+    ///     res.unwrap();
+    /// }
+    ///
+    /// let ctx = &IoContext::new().unwrap();
+    /// let timer = Arc::new(SteadyTimer::new(ctx));
+    /// timer.expires_from_now(Duration::new(3600, 0));
+    /// timer.async_wait(wrap(&timer, on_wait));
+    /// ctx.post(|ctx| ctx.advance_clock(Duration::new(3600, 0)));
+    ///
+    /// let now = Instant::now();
+    /// ctx.run();
+    /// assert!(now.elapsed() < Duration::new(60, 0));
+    /// ```
+    pub fn advance_clock(&self, dur: Duration) {
+        self.as_reactor().tq.advance(dur)
+    }
+
+    /// Returns the file descriptor which becomes readable when the context has events to handle.
+    ///
+    /// It can be watched by the external event loop (e.g. the main loop of GUI toolkit) in place
+    /// of the thread calling `run`, that must call `handle_ready_events` when it is readable.
+    /// After this is called, posting the handler also wakes up the reactor.
+    pub fn as_pollable_fd(&self) -> RawFd {
+        self.0.pollable.store(true, Ordering::SeqCst);
+        self.as_reactor().as_raw_fd()
+    }
+
+    #[doc(hidden)]
+    pub fn as_pool(&self) -> &ThreadPool {
+        &self.0.pool
+    }
+
     #[doc(hidden)]
     pub fn as_reactor(&self) -> &Reactor {
         &self.0.reactor
     }
 
+    /// Returns the number of the threads expected to call `run`, that is one by default.
+    pub fn concurrency_hint(&self) -> usize {
+        self.0.concurrency.load(Ordering::Relaxed)
+    }
+
     #[doc(hidden)]
     pub fn do_dispatch<F>(&self, exec: F)
     where
@@ -166,60 +387,471 @@ impl IoContext {
         self.push(Box::new(exec))
     }
 
+    /// Requests to invoke the function, that is dropped without the invocation after the `shutdown`.
     pub fn dispatch<F>(&self, func: F)
     where
         F: FnOnce(&IoContext) + Send + 'static,
     {
-        self.do_dispatch(func)
+        let _ = self.try_dispatch(func);
+    }
+
+    /// Runs the function on each thread running the context, and on the threads calling `run`
+    /// after this, e.g. to initialize the thread-local allocator arenas or RNGs.
+    ///
+    /// The running threads call the function between the handlers, and the new threads call it at
+    /// the entry of `run`. The internal threads for the blocking operations are not included.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::Cell;
+    /// use asyncio::IoContext;
+    ///
+    /// thread_local!(static SEED: Cell<u64> = Cell::new(0));
+    ///
+    /// let ctx = &IoContext::new().unwrap();
+    /// ctx.for_each_thread(|_| SEED.with(|seed| seed.set(42)));
+    /// ctx.post(|_| assert_eq!(SEED.with(|seed| seed.get()), 42));
+    /// ctx.run();
+    /// ```
+    pub fn for_each_thread<F>(&self, func: F)
+    where
+        F: Fn(&IoContext) + Send + Sync + 'static,
+    {
+        {
+            let mut inits = self.0.thread_inits.lock().unwrap();
+            inits.push(Arc::new(func));
+            self.0.thread_inits_len.store(inits.len(), Ordering::SeqCst);
+        }
+        // Wakes up the threads waiting for the handlers or the events.
+        let _queue = self.0.mutex.lock().unwrap();
+        self.0.condvar.notify_all();
+        self.wake();
+    }
+
+    /// Handles the ready events and the queued handlers without blocking, returns the number of
+    /// the invoked handlers.
+    ///
+    /// The handlers queued while handling are deferred to the next call, and the pollable file
+    /// descriptor is kept readable for them. This must not be called while the other threads are
+    /// running the context.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate libc;
+    /// # extern crate asyncio;
+    /// use asyncio::IoContext;
+    ///
+    /// # fn main() {
+    /// let ctx = &IoContext::new().unwrap();
+    /// let fd = ctx.as_pollable_fd();
+    /// ctx.post(|_| println!("hello"));
+    ///
+    /// // e.g. the main loop of GUI toolkit watches the fd.
+    /// let mut pfd = libc::pollfd { fd: fd, events: libc::POLLIN, revents: 0 };
+    /// assert_eq!(unsafe { libc::poll(&mut pfd, 1, 1000) }, 1);
+    /// assert_eq!(ctx.handle_ready_events(), 1);
+    /// # }
+    /// ```
+    pub fn handle_ready_events(&self) -> usize {
+        if self.stopped() {
+            return 0;
+        }
+
+        let mut this = ThreadIoContext::new(self, Default::default());
+        this.init();
+
+        self.as_reactor().poll(false, &mut this);
+        let mut n = self.run_pending(&mut this);
+        let queued = self.0.mutex.lock().unwrap().len();
+        for _ in 0..queued {
+            let exec = match self.0.mutex.lock().unwrap().pop_front() {
+                Some(exec) => exec,
+                None => break,
+            };
+            self.invoke(&mut this, exec);
+            n += 1 + self.run_pending(&mut this);
+        }
+        if !self.0.mutex.lock().unwrap().is_empty() {
+            self.wake();
+        }
+        n
+    }
+
+    fn invoke(&self, this: &mut ThreadIoContext, exec: Box<Exec>) {
+        match self.invoke_hook() {
+            Some(ref hook) if exec.is_handler() => {
+                let _guard = HookGuard::new(&**hook, self);
+                exec.call_box(this)
+            }
+            _ => exec.call_box(this),
+        }
+    }
+
+    fn invoke_hook(&self) -> Option<Arc<InvokeHook>> {
+        self.0.hook.read().unwrap().clone()
+    }
+
+    /// Returns true if the `shutdown` has been called.
+    pub fn is_shutdown(&self) -> bool {
+        self.0.shutdown.load(Ordering::SeqCst)
     }
 
-    fn pop(&self) -> Option<Box<Exec>> {
+    fn pop(&self, this: &mut ThreadIoContext) -> Option<Box<Exec>> {
         let mut queue = self.0.mutex.lock().unwrap();
         loop {
             if let Some(exec) = queue.pop_front() {
                 return Some(exec);
             } else if self.stopped() {
                 return None;
+            } else if this.thread_inits != self.0.thread_inits_len.load(Ordering::SeqCst) {
+                drop(queue);
+                self.run_thread_inits(this);
+                queue = self.0.mutex.lock().unwrap();
+                continue;
             }
+            self.0.idle.fetch_add(1, Ordering::SeqCst);
             queue = self.0.condvar.wait(queue).unwrap();
+            self.0.idle.fetch_sub(1, Ordering::SeqCst);
         }
     }
 
+    /// Requests to invoke the function, that is dropped without the invocation after the `shutdown`.
     pub fn post<F>(&self, func: F)
     where
         F: FnOnce(&IoContext) + Send + 'static,
     {
-        self.do_post(func)
+        let _ = self.try_post(func);
+    }
+
+    /// Same as the `post`, named for the yield of the long-running handler split into the chunks.
+    ///
+    /// The function is queued at the back, behind the handlers already queued, and is never
+    /// invoked in the calling handler unlike the `dispatch`. It may still run on the same thread
+    /// right after the others, there is no further guarantee. Use `Coroutine::yield_now` in the
+    /// coroutines.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::{Arc, Mutex};
+    /// use asyncio::IoContext;
+    ///
+    /// fn chunk(ctx: &IoContext, log: Arc<Mutex<Vec<char>>>, name: char, left: usize) {
+    ///     log.lock().unwrap().push(name);
+    ///     if left > 1 {
+    ///         ctx.post_yield(move |ctx| chunk(ctx, log, name, left - 1));
+    ///     }
+    /// }
+    ///
+    /// let ctx = &IoContext::new().unwrap();
+    /// let log = Arc::new(Mutex::new(Vec::new()));
+    /// for &name in &['a', 'b'] {
+    ///     let log = log.clone();
+    ///     ctx.dispatch(move |ctx| chunk(ctx, log, name, 3));
+    /// }
+    /// ctx.run();
+    /// assert_eq!(*log.lock().unwrap(), vec!['a', 'b', 'a', 'b', 'a', 'b']);
+    /// ```
+    pub fn post_yield<F>(&self, func: F)
+    where
+        F: FnOnce(&IoContext) + Send + 'static,
+    {
+        self.post(func)
     }
 
     fn push(&self, exec: Box<Exec>) {
         let mut queue = self.0.mutex.lock().unwrap();
         queue.push_back(exec);
         self.0.condvar.notify_one();
+        if self.0.pollable.load(Ordering::Relaxed) {
+            self.wake();
+        }
+    }
+
+    /// Removes the invocation hook.
+    pub fn remove_invoke_hook(&self) {
+        *self.0.hook.write().unwrap() = None;
+    }
+
+    /// Removes the socket observer.
+    pub fn remove_socket_observer(&self) {
+        *self.0.observer.write().unwrap() = None;
     }
 
     pub fn restart(&self) {
-        self.0.stopped.store(false, Ordering::Relaxed)
+        if !self.is_shutdown() {
+            self.0.stopped.store(false, Ordering::Relaxed)
+        }
     }
 
     pub fn run(self: &IoContext) {
-        if self.stopped() {
+        if self.stopped() && !self.is_shutdown() {
             return;
         }
 
         let mut this = ThreadIoContext::new(self, Default::default());
         this.init();
+        self.run_thread_inits(&mut this);
+
+        // After the shutdown, only completes the remaining handlers without polling.
+        if !self.is_shutdown() {
+            // The threads share a single poller if they are expected to run concurrently, and the
+            // others wait for the completions handed over by it.
+            if self.0.pollers.fetch_add(1, Ordering::SeqCst) == 0 || self.concurrency_hint() <= 1 {
+                self.push(Box::new(ExecutorRef(&*self.0)));
+            } else {
+                self.0.pollers.fetch_sub(1, Ordering::SeqCst);
+            }
+        }
+        self.run_queue(&mut this);
+    }
+
+    fn run_queue(&self, this: &mut ThreadIoContext) {
+        while let Some(exec) = self.pop(this) {
+            self.invoke(this, exec);
+            self.run_pending(this);
+            self.run_thread_inits(this);
+            if self.concurrency_hint() > 1 && self.0.outstanding_work.load(Ordering::SeqCst) == 0 {
+                // Wakes up the poller to stop, the last work may be done by the other thread.
+                self.wake();
+            }
+        }
+    }
 
-        self.push(Box::new(ExecutorRef(&*self.0)));
-        while let Some(exec) = self.pop() {
-            exec.call_box(&mut this);
-            while !this.pending_queue.is_empty() {
-                let vec: Vec<_> = this.pending_queue.drain(..).collect();
-                for (op, err) in vec {
-                    op.perform(&mut this, err);
+    fn run_pending(&self, this: &mut ThreadIoContext) -> usize {
+        let io_batch = self.0.io_batch.load(Ordering::Relaxed);
+        let posted_batch = self.0.posted_batch.load(Ordering::Relaxed);
+        let mut n = 0;
+        let mut budget = io_batch;
+        while !this.pending_queue.is_empty() {
+            let vec: Vec<_> = this.pending_queue.drain(..).collect();
+            n += vec.len();
+            for (op, err) in vec {
+                if budget == 0 {
+                    n += self.run_posted(this, posted_batch);
+                    budget = io_batch;
+                }
+                budget -= 1;
+                self.0.leaks.untrack(&*op);
+                match self.invoke_hook() {
+                    Some(hook) => {
+                        let _guard = HookGuard::new(&*hook, self);
+                        op.perform(this, err)
+                    }
+                    None => op.perform(this, err),
                 }
             }
         }
+        n
+    }
+
+    /// Calls the functions of `for_each_thread` not yet called on this thread.
+    fn run_thread_inits(&self, this: &mut ThreadIoContext) {
+        if this.thread_inits == self.0.thread_inits_len.load(Ordering::SeqCst) {
+            return;
+        }
+        let inits: Vec<_> = self.0.thread_inits.lock().unwrap()[this.thread_inits..].to_vec();
+        this.thread_inits += inits.len();
+        for init in inits {
+            init(self)
+        }
+    }
+
+    /// Invokes up to the number of the posted handlers at the front of the queue without blocking.
+    fn run_posted(&self, this: &mut ThreadIoContext, max: usize) -> usize {
+        let mut n = 0;
+        while n < max {
+            let exec = {
+                let mut queue = self.0.mutex.lock().unwrap();
+                match queue.front() {
+                    Some(exec) if exec.is_handler() => (),
+                    _ => break,
+                }
+                queue.pop_front().unwrap()
+            };
+            self.invoke(this, exec);
+            n += 1;
+        }
+        n
+    }
+
+    /// Returns the scheduling policy between the completions of the I/O and the posted handlers.
+    pub fn scheduling(&self) -> Scheduling {
+        match (
+            self.0.io_batch.load(Ordering::Relaxed),
+            self.0.posted_batch.load(Ordering::Relaxed),
+        ) {
+            (::std::usize::MAX, 0) => Scheduling::PreferIo,
+            (1, ::std::usize::MAX) => Scheduling::PreferPosted,
+            (n, _) => Scheduling::Fair(n),
+        }
+    }
+
+    /// Sets the number of the threads expected to call `run`, the zero is regarded as one.
+    ///
+    /// If the hint is more than one, only one of the threads polls the reactor at a time, and the
+    /// completions of the I/O are shared with the threads waiting for the handlers. Otherwise all
+    /// of the completions of a poll are performed by the thread polled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::thread;
+    /// use asyncio::IoContext;
+    ///
+    /// let ctx = &IoContext::new_with_concurrency(4).unwrap();
+    /// assert_eq!(ctx.concurrency_hint(), 4);
+    ///
+    /// let thrds: Vec<_> = (0..3).map(|_| {
+    ///     let ctx = ctx.clone();
+    ///     thread::spawn(move || ctx.run())
+    /// }).collect();
+    /// ctx.run();
+    /// for thrd in thrds {
+    ///     thrd.join().unwrap();
+    /// }
+    /// ```
+    pub fn set_concurrency_hint(&self, concurrency: usize) {
+        self.0.concurrency.store(concurrency.max(1), Ordering::Relaxed);
+    }
+
+    /// Sets the hook invoked around each handler, that replaces the previous one.
+    ///
+    /// The hook surrounds each unit of work run by the `IoContext`; the functions of the `post` and
+    /// the `dispatch` called outside the handler, the asynchronous operations started outside the
+    /// handler, and the completions of them. The handlers dispatched within a handler are invoked
+    /// inside the hook of the caller.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cell::Cell;
+    /// use asyncio::{IoContext, InvokeHook};
+    ///
+    /// thread_local!(static REQUEST_ID: Cell<u32> = Cell::new(0));
+    ///
+    /// struct Propagate(u32);
+    ///
+    /// impl InvokeHook for Propagate {
+    ///     fn before(&self, _: &IoContext) {
+    ///         REQUEST_ID.with(|id| id.set(self.0));
+    ///     }
+    ///
+    ///     fn after(&self, _: &IoContext) {
+    ///         REQUEST_ID.with(|id| id.set(0));
+    ///     }
+    /// }
+    ///
+    /// let ctx = &IoContext::new().unwrap();
+    /// ctx.set_invoke_hook(Propagate(42));
+    /// ctx.post(|_| assert_eq!(REQUEST_ID.with(|id| id.get()), 42));
+    /// ctx.run();
+    /// assert_eq!(REQUEST_ID.with(|id| id.get()), 0);
+    /// ```
+    pub fn set_invoke_hook<T>(&self, hook: T)
+    where
+        T: InvokeHook,
+    {
+        *self.0.hook.write().unwrap() = Some(Arc::new(hook));
+    }
+
+    /// Sets the scheduling policy between the completions of the I/O and the posted handlers.
+    ///
+    /// The default is `Scheduling::Fair(16)`, that neither of them starves under load. The batch
+    /// size of zero is regarded as one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use asyncio::{IoContext, Scheduling};
+    ///
+    /// let ctx = &IoContext::new().unwrap();
+    /// assert_eq!(ctx.scheduling(), Scheduling::Fair(16));
+    /// ctx.set_scheduling(Scheduling::PreferIo);
+    /// assert_eq!(ctx.scheduling(), Scheduling::PreferIo);
+    /// ```
+    pub fn set_scheduling(&self, policy: Scheduling) {
+        let (io_batch, posted_batch) = match policy {
+            Scheduling::Fair(n) => (n.max(1), n.max(1)),
+            Scheduling::PreferIo => (::std::usize::MAX, 0),
+            Scheduling::PreferPosted => (1, ::std::usize::MAX),
+        };
+        self.0.io_batch.store(io_batch, Ordering::Relaxed);
+        self.0.posted_batch.store(posted_batch, Ordering::Relaxed);
+    }
+
+    /// Sets the socket observer, that replaces the previous one.
+    pub fn set_socket_observer<T>(&self, observer: T)
+    where
+        T: SocketObserver,
+    {
+        *self.0.observer.write().unwrap() = Some(Arc::new(observer));
+    }
+
+    #[doc(hidden)]
+    pub fn socket_observer(&self) -> Option<Arc<SocketObserver>> {
+        self.0.observer.read().unwrap().clone()
+    }
+
+    /// Shuts down the context deterministically.
+    ///
+    /// All pending operations of the sockets and the timers are canceled, and those handlers are
+    /// invoked with the `ECANCELED` error before returning if the context is not running
+    /// (otherwise by the running threads). After the shutdown, the new operations are completed
+    /// with the `ECANCELED` error by the `run` without polling, the `post` and the `dispatch` drop
+    /// the function, and the `restart` does not take effect. The sockets can be closed safely at any time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io;
+    /// use std::sync::Arc;
+    /// use std::time::Duration;
+    /// use asyncio::{IoContext, SteadyTimer, wrap};
+    ///
+    /// fn on_wait(_: Arc<SteadyTimer>, res: io::Result<()>) {
+    ///   assert!(res.is_err());  // canceled
+    /// }
+    ///
+    /// let ctx = &IoContext::new().unwrap();
+    /// let timer = Arc::new(SteadyTimer::new(ctx));
+    /// timer.expires_from_now(Duration::new(60, 0));
+    /// timer.async_wait(wrap(&timer, on_wait));
+    /// ctx.shutdown();
+    /// assert!(ctx.stopped());
+    /// assert!(ctx.try_post(|_| ()).is_err());
+    /// ```
+    pub fn shutdown(&self) {
+        if self.0.shutdown.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        self.as_reactor().cancel_all(self, OPERATION_CANCELED);
+        for op in self.as_reactor().tq.erase_all() {
+            self.do_post((op, OPERATION_CANCELED))
+        }
+        self.stop();
+        if ThreadIoContext::callstack(self).is_none() {
+            let mut this = ThreadIoContext::new(self, Default::default());
+            this.init();
+            self.run_queue(&mut this);
+        }
+    }
+
+    /// Returns the maximum number of the internal threads, that is zero by default.
+    pub fn threads(&self) -> usize {
+        self.0.pool.options().count
+    }
+
+    #[doc(hidden)]
+    pub fn track_op(&self, op: &Perform) {
+        self.0.leaks.track(op)
+    }
+
+    #[doc(hidden)]
+    pub fn untrack_op(&self, op: &Perform) {
+        self.0.leaks.untrack(op)
     }
 
     pub fn stop(&self) {
@@ -233,6 +865,36 @@ impl IoContext {
     pub fn stopped(&self) -> bool {
         self.0.stopped.load(Ordering::Relaxed)
     }
+
+    /// Requests to invoke the function, returns the `ECANCELED` error after the `shutdown`.
+    pub fn try_dispatch<F>(&self, func: F) -> io::Result<()>
+    where
+        F: FnOnce(&IoContext) + Send + 'static,
+    {
+        if self.is_shutdown() {
+            return Err(OPERATION_CANCELED.into());
+        }
+        self.do_dispatch(func);
+        Ok(())
+    }
+
+    /// Requests to invoke the function later, returns the `ECANCELED` error after the `shutdown`.
+    pub fn try_post<F>(&self, func: F) -> io::Result<()>
+    where
+        F: FnOnce(&IoContext) + Send + 'static,
+    {
+        if self.is_shutdown() {
+            return Err(OPERATION_CANCELED.into());
+        }
+        self.do_post(func);
+        Ok(())
+    }
+
+    /// Forces the reactor out of the waiting, that is thread-safe and async-signal-safe with the
+    /// default interrupter.
+    pub fn wake(&self) {
+        self.as_reactor().interrupt()
+    }
 }
 
 impl Eq for IoContext {}
@@ -312,3 +974,307 @@ fn test_multithread_work() {
 
     assert_eq!(COUNT.load(Ordering::Relaxed), 100);
 }
+
+#[test]
+fn test_shutdown() {
+    use std::thread;
+    use std::time::Duration;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use handler::wrap;
+    use ip::{IpAddrV4, IpProtocol, Udp, UdpEndpoint, UdpSocket};
+    use SteadyTimer;
+
+    static CANCELED: AtomicUsize = AtomicUsize::new(0);
+
+    fn on_wait(_: Arc<SteadyTimer>, res: io::Result<()>) {
+        assert!(res.is_err());
+        CANCELED.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn on_receive(_: Arc<UdpSocket>, res: io::Result<usize>) {
+        assert!(res.is_err());
+        CANCELED.fetch_add(1, Ordering::SeqCst);
+    }
+
+    let ctx = &IoContext::new().unwrap();
+    let _work = IoContextWork::new(ctx);
+    let timer = Arc::new(SteadyTimer::new(ctx));
+    timer.expires_from_now(Duration::new(60, 0));
+    timer.async_wait(wrap(&timer, on_wait));
+    let soc = Arc::new(UdpSocket::new(ctx, Udp::v4()).unwrap());
+    soc.bind(&UdpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+    soc.async_receive(&mut [0; 16], 0, wrap(&soc, on_receive));
+
+    let thrd = {
+        let ctx = ctx.clone();
+        thread::spawn(move || ctx.run())
+    };
+    thread::sleep(Duration::from_millis(100));
+    ctx.shutdown();
+    thrd.join().unwrap();
+    assert_eq!(CANCELED.load(Ordering::SeqCst), 2);
+    assert!(ctx.is_shutdown() && ctx.stopped());
+
+    // The new operations are canceled immediately.
+    assert!(ctx.try_post(|_| ()).is_err());
+    soc.async_receive(&mut [0; 16], 0, wrap(&soc, on_receive));
+    ctx.run();
+    assert_eq!(CANCELED.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn test_custom_interrupter() {
+    use std::thread;
+    use std::os::unix::io::{AsRawFd, RawFd};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use ffi::{close, pipe};
+    use libc;
+
+    static WAKES: AtomicUsize = AtomicUsize::new(0);
+
+    struct SelfPipe(RawFd, RawFd);
+
+    impl AsRawFd for SelfPipe {
+        fn as_raw_fd(&self) -> RawFd {
+            self.0
+        }
+    }
+
+    impl Interrupter for SelfPipe {
+        fn interrupt(&self) {
+            WAKES.fetch_add(1, Ordering::SeqCst);
+            unsafe { libc::write(self.1, b"x".as_ptr() as *const _, 1) };
+        }
+    }
+
+    impl Drop for SelfPipe {
+        fn drop(&mut self) {
+            close(self.0);
+            close(self.1);
+        }
+    }
+
+    let (rfd, wfd) = pipe().unwrap();
+    let ctx = &IoContext::with_interrupter(SelfPipe(rfd, wfd)).unwrap();
+    let work = IoContextWork::new(ctx);
+    let thrd = {
+        let ctx = ctx.clone();
+        thread::spawn(move || ctx.run())
+    };
+    ctx.wake();
+    assert_eq!(WAKES.load(Ordering::SeqCst), 1);
+    ctx.post(|_| ());
+    drop(work);
+    thrd.join().unwrap();
+    assert!(ctx.stopped());
+}
+
+#[test]
+fn test_handle_ready_events() {
+    use std::time::Duration;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use handler::wrap;
+    use libc;
+    use SteadyTimer;
+
+    static EXPIRED: AtomicUsize = AtomicUsize::new(0);
+
+    fn on_wait(_: Arc<SteadyTimer>, res: io::Result<()>) {
+        res.unwrap();
+        EXPIRED.fetch_add(1, Ordering::SeqCst);
+    }
+
+    let ctx = &IoContext::new().unwrap();
+    let fd = ctx.as_pollable_fd();
+    let timer = Arc::new(SteadyTimer::new(ctx));
+    timer.expires_from_now(Duration::from_millis(10));
+    timer.async_wait(wrap(&timer, on_wait));
+    while EXPIRED.load(Ordering::SeqCst) == 0 {
+        let mut pfd = libc::pollfd {
+            fd: fd,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        assert_eq!(unsafe { libc::poll(&mut pfd, 1, 1000) }, 1);
+        ctx.handle_ready_events();
+    }
+}
+
+#[test]
+fn test_invoke_hook() {
+    use std::cell::Cell;
+    use std::time::Duration;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use handler::wrap;
+    use SteadyTimer;
+
+    static BEFORE: AtomicUsize = AtomicUsize::new(0);
+    static AFTER: AtomicUsize = AtomicUsize::new(0);
+    thread_local!(static TAG: Cell<usize> = Cell::new(0));
+
+    struct Count;
+
+    impl InvokeHook for Count {
+        fn before(&self, _: &IoContext) {
+            TAG.with(|tag| tag.set(BEFORE.fetch_add(1, Ordering::SeqCst) + 1));
+        }
+
+        fn after(&self, _: &IoContext) {
+            TAG.with(|tag| tag.set(0));
+            AFTER.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn on_wait(_: Arc<SteadyTimer>, res: io::Result<()>) {
+        res.unwrap();
+        assert_ne!(TAG.with(|tag| tag.get()), 0);
+    }
+
+    let ctx = &IoContext::new().unwrap();
+    ctx.set_invoke_hook(Count);
+    ctx.post(|_| assert_eq!(TAG.with(|tag| tag.get()), 1));
+    let timer = Arc::new(SteadyTimer::new(ctx));
+    timer.expires_from_now(Duration::from_millis(1));
+    timer.async_wait(wrap(&timer, on_wait));
+    ctx.run();
+    // the post, the start of the wait and the completion of it.
+    assert_eq!(BEFORE.load(Ordering::SeqCst), 3);
+    assert_eq!(AFTER.load(Ordering::SeqCst), 3);
+    assert_eq!(TAG.with(|tag| tag.get()), 0);
+
+    ctx.restart();
+    ctx.remove_invoke_hook();
+    ctx.post(|_| assert_eq!(TAG.with(|tag| tag.get()), 0));
+    ctx.run();
+    assert_eq!(BEFORE.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn test_scheduling() {
+    use std::sync::Mutex;
+
+    struct Op(Arc<Mutex<Vec<char>>>);
+
+    impl Perform for Op {
+        fn perform(self: Box<Self>, _: &mut ThreadIoContext, _: SystemError) {
+            self.0.lock().unwrap().push('i')
+        }
+    }
+
+    fn order(ctx: &IoContext, policy: Scheduling) -> String {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        ctx.set_scheduling(policy);
+        let mut this = ThreadIoContext::new(ctx, Default::default());
+        this.init();
+        for _ in 0..4 {
+            this.push(Box::new(Op(log.clone())), SystemError::default());
+            let log = log.clone();
+            ctx.post(move |_| log.lock().unwrap().push('p'));
+        }
+        ctx.run_pending(&mut this);
+        drop(this);
+
+        // the posted handlers left in the queue are invoked by the run.
+        ctx.run();
+        ctx.restart();
+        let order = log.lock().unwrap().iter().collect();
+        order
+    }
+
+    let ctx = &IoContext::new().unwrap();
+    assert_eq!(ctx.scheduling(), Scheduling::default());
+    assert_eq!(order(ctx, Scheduling::Fair(1)), "ipipipip");
+    assert_eq!(order(ctx, Scheduling::Fair(2)), "iippiipp");
+    assert_eq!(order(ctx, Scheduling::PreferPosted), "ippppiii");
+    assert_eq!(ctx.scheduling(), Scheduling::PreferPosted);
+    assert_eq!(order(ctx, Scheduling::PreferIo), "iiiipppp");
+    ctx.set_scheduling(Scheduling::Fair(0));
+    assert_eq!(ctx.scheduling(), Scheduling::Fair(1));
+}
+
+#[test]
+fn test_concurrency_hint() {
+    use std::thread;
+    use std::cell::Cell;
+    use std::time::Instant;
+    use handler::wrap;
+    use SteadyTimer;
+
+    thread_local!(static INDEX: Cell<usize> = Cell::new(0));
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+    static SEEN: AtomicUsize = AtomicUsize::new(0);
+
+    fn on_wait(_: Arc<SteadyTimer>, res: io::Result<()>) {
+        res.unwrap();
+        COUNT.fetch_add(1, Ordering::SeqCst);
+        SEEN.fetch_or(1 << INDEX.with(|i| i.get()), Ordering::SeqCst);
+        thread::sleep(Duration::from_millis(20));
+    }
+
+    let ctx = &IoContext::new_with_concurrency(0).unwrap();
+    assert_eq!(ctx.concurrency_hint(), 1);
+    ctx.set_concurrency_hint(4);
+    assert_eq!(ctx.concurrency_hint(), 4);
+
+    // the timers expired at once are shared with the waiting threads.
+    let expiry = Instant::now() + Duration::from_millis(100);
+    for _ in 0..8 {
+        let timer = Arc::new(SteadyTimer::new(ctx));
+        timer.expires_at(expiry);
+        timer.async_wait(wrap(&timer, on_wait));
+    }
+    let thrds: Vec<_> = (1..4)
+        .map(|i| {
+            let ctx = ctx.clone();
+            thread::spawn(move || {
+                INDEX.with(|index| index.set(i));
+                ctx.run()
+            })
+        })
+        .collect();
+    ctx.run();
+    for thrd in thrds {
+        thrd.join().unwrap();
+    }
+    assert_eq!(COUNT.load(Ordering::SeqCst), 8);
+    assert!(SEEN.load(Ordering::SeqCst).count_ones() > 1);
+}
+
+#[test]
+fn test_for_each_thread() {
+    use std::thread;
+    use std::cell::Cell;
+    use handler::wrap;
+    use SteadyTimer;
+
+    thread_local!(static INIT: Cell<bool> = Cell::new(false));
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+
+    fn on_wait(_: Arc<SteadyTimer>, res: io::Result<()>) {
+        res.unwrap();
+        assert!(INIT.with(|init| init.get()));
+    }
+
+    let ctx = &IoContext::new_with_concurrency(3).unwrap();
+    let timer = Arc::new(SteadyTimer::new(ctx));
+    timer.expires_from_now(Duration::from_millis(200));
+    timer.async_wait(wrap(&timer, on_wait));
+    let thrds: Vec<_> = (0..2)
+        .map(|_| {
+            let ctx = ctx.clone();
+            thread::spawn(move || ctx.run())
+        })
+        .collect();
+
+    // the running threads and the thread calling `run` after this.
+    thread::sleep(Duration::from_millis(50));
+    ctx.for_each_thread(|_| {
+        INIT.with(|init| init.set(true));
+        COUNT.fetch_add(1, Ordering::SeqCst);
+    });
+    ctx.run();
+    for thrd in thrds {
+        thrd.join().unwrap();
+    }
+    assert_eq!(COUNT.load(Ordering::SeqCst), 3);
+}
@@ -0,0 +1,34 @@
+use core::IoContext;
+
+/// A hook invoked around each handler run by the `IoContext`.
+///
+/// The hook is useful to set up the thread-local state of the application (e.g. the tracing span
+/// or the request ID) for every handler, without wrapping each handler manually.
+pub trait InvokeHook: Send + Sync + 'static {
+    /// Invoked before the handler runs.
+    fn before(&self, ctx: &IoContext);
+
+    /// Invoked after the handler returns, or while unwinding if the handler panics.
+    fn after(&self, ctx: &IoContext);
+}
+
+pub struct HookGuard<'a> {
+    hook: &'a InvokeHook,
+    ctx: &'a IoContext,
+}
+
+impl<'a> HookGuard<'a> {
+    pub fn new(hook: &'a InvokeHook, ctx: &'a IoContext) -> Self {
+        hook.before(ctx);
+        HookGuard {
+            hook: hook,
+            ctx: ctx,
+        }
+    }
+}
+
+impl<'a> Drop for HookGuard<'a> {
+    fn drop(&mut self) {
+        self.hook.after(self.ctx)
+    }
+}
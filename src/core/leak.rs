@@ -0,0 +1,104 @@
+//! Detects the asynchronous operations that are never completed nor canceled in debug builds.
+//!
+//! Each operation queued to the reactor or the timer queue is recorded until it is performed.
+//! The records remaining when the `IoContext` drops are reported to the standard error, that
+//! includes the backtrace where the operation was queued if the `leak-backtrace` feature is enabled.
+
+use core::Perform;
+
+#[cfg(debug_assertions)]
+use std::collections::HashMap;
+#[cfg(debug_assertions)]
+use std::sync::Mutex;
+#[cfg(all(debug_assertions, feature = "leak-backtrace"))]
+use std::backtrace::Backtrace;
+
+fn key(op: &Perform) -> usize {
+    op as *const Perform as *const u8 as usize
+}
+
+#[cfg(debug_assertions)]
+struct Record {
+    name: &'static str,
+    #[cfg(feature = "leak-backtrace")]
+    backtrace: Backtrace,
+}
+
+#[cfg(debug_assertions)]
+#[derive(Default)]
+pub struct LeakTracker {
+    ops: Mutex<HashMap<usize, Record>>,
+}
+
+#[cfg(debug_assertions)]
+impl LeakTracker {
+    pub fn track(&self, op: &Perform) {
+        let rec = Record {
+            name: op.type_name(),
+            #[cfg(feature = "leak-backtrace")]
+            backtrace: Backtrace::force_capture(),
+        };
+        self.ops.lock().unwrap().insert(key(op), rec);
+    }
+
+    pub fn untrack(&self, op: &Perform) {
+        self.ops.lock().unwrap().remove(&key(op));
+    }
+
+    pub fn report(&self, queued: usize) {
+        let ops = self.ops.lock().unwrap();
+        if ops.is_empty() && queued == 0 {
+            return;
+        }
+        eprintln!(
+            "asyncio: IoContext dropped with {} outstanding operations and {} queued handlers",
+            ops.len(),
+            queued
+        );
+        for rec in ops.values() {
+            eprintln!("  never completed: {}", rec.name);
+            #[cfg(feature = "leak-backtrace")]
+            eprintln!("{}", rec.backtrace);
+        }
+    }
+}
+
+#[cfg(not(debug_assertions))]
+#[derive(Default)]
+pub struct LeakTracker;
+
+#[cfg(not(debug_assertions))]
+impl LeakTracker {
+    #[inline]
+    pub fn track(&self, _: &Perform) {}
+
+    #[inline]
+    pub fn untrack(&self, _: &Perform) {}
+
+    #[inline]
+    pub fn report(&self, _: usize) {}
+}
+
+#[test]
+#[cfg(debug_assertions)]
+fn test_leak_tracker() {
+    use core::ThreadIoContext;
+    use ffi::SystemError;
+
+    #[allow(dead_code)]
+    struct Op(u8);
+
+    impl Perform for Op {
+        fn perform(self: Box<Self>, _: &mut ThreadIoContext, _: SystemError) {}
+    }
+
+    let leaks = LeakTracker::default();
+    let a: Box<Perform> = Box::new(Op(0));
+    let b: Box<Perform> = Box::new(Op(1));
+    leaks.track(&*a);
+    leaks.track(&*b);
+    assert_eq!(leaks.ops.lock().unwrap().len(), 2);
+    leaks.untrack(&*a);
+    assert_eq!(leaks.ops.lock().unwrap().len(), 1);
+    assert!(leaks.ops.lock().unwrap()[&key(&*b)].name.ends_with("Op"));
+}
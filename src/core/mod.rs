@@ -1,10 +1,26 @@
 use ffi::{c_void, sockaddr, socklen_t, AsRawFd, RawFd};
 
+use std::slice;
+
 mod callstack;
 use self::callstack::ThreadCallStack;
 
+mod leak;
+use self::leak::LeakTracker;
+
+mod hook;
+use self::hook::HookGuard;
+pub use self::hook::InvokeHook;
+
+mod pool;
+pub use self::pool::ThreadPool;
+
 mod exec;
-pub use self::exec::{IoContext, AsIoContext, IoContextWork, Exec, Perform, ThreadIoContext};
+pub use self::exec::{IoContext, AsIoContext, IoContextWork, Exec, Perform, ThreadIoContext,
+                     Scheduling};
+
+mod builder;
+pub use self::builder::IoContextBuilder;
 
 pub trait Endpoint<P>: Clone + Eq + Ord + Send + 'static {
     fn protocol(&self) -> P;
@@ -18,6 +34,37 @@ pub trait Endpoint<P>: Clone + Eq + Ord + Send + 'static {
     fn size(&self) -> socklen_t;
 
     unsafe fn resize(&mut self, len: socklen_t);
+
+    /// Returns a address family of the socket address, or `0` if the size is too short.
+    fn family(&self) -> i32 {
+        if self.size() < 2 {
+            return 0;
+        }
+        unsafe { &*self.as_ptr() }.sa_family as i32
+    }
+
+    /// Returns a raw bytes of the socket address, that can be passed as the `struct sockaddr` of
+    /// the `size()` bytes to the other C libraries.
+    fn as_bytes(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.as_ptr() as *const u8, self.size() as usize) }
+    }
+
+    /// Returns a pair of the address family and the copied raw bytes of the socket address.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use asyncio::Endpoint;
+    /// use asyncio::ip::{IpAddrV4, TcpEndpoint};
+    ///
+    /// let ep = TcpEndpoint::new(IpAddrV4::loopback(), 80);
+    /// let (family, bytes) = ep.to_owned_sockaddr();
+    /// assert_eq!(family, ep.family());
+    /// assert_eq!(&bytes[2..8], &[0, 80, 127, 0, 0, 1]);
+    /// ```
+    fn to_owned_sockaddr(&self) -> (i32, Vec<u8>) {
+        (self.family(), self.as_bytes().to_vec())
+    }
 }
 
 pub trait Protocol: Copy + Eq + Ord + Send + 'static {
@@ -0,0 +1,170 @@
+use std::io;
+use std::thread;
+use std::sync::{Arc, Condvar, Mutex};
+use std::collections::VecDeque;
+
+/// The configuration of the internal threads of the `IoContext`.
+#[derive(Clone, Debug, Default)]
+pub struct ThreadOptions {
+    pub count: usize,
+    pub name: Option<String>,
+    pub affinity: Vec<usize>,
+}
+
+impl ThreadOptions {
+    fn spawn<F>(&self, index: usize, func: F) -> io::Result<thread::JoinHandle<()>>
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let name = match self.name {
+            Some(ref name) => format!("{}-{}", name, index),
+            None => format!("asyncio-{}", index),
+        };
+        let affinity = self.affinity.clone();
+        thread::Builder::new().name(name).spawn(move || {
+            set_affinity(&affinity);
+            func()
+        })
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn set_affinity(cpus: &[usize]) {
+    use std::mem;
+    use libc::{self, cpu_set_t, CPU_SET, CPU_SETSIZE};
+
+    if cpus.is_empty() {
+        return;
+    }
+    unsafe {
+        let mut set: cpu_set_t = mem::zeroed();
+        for &cpu in cpus.iter().filter(|&&cpu| cpu < CPU_SETSIZE as usize) {
+            CPU_SET(cpu, &mut set);
+        }
+        libc::sched_setaffinity(0, mem::size_of::<cpu_set_t>(), &set);
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn set_affinity(_: &[usize]) {}
+
+type Job = Box<FnOnce() + Send>;
+
+#[derive(Default)]
+struct Queue {
+    jobs: VecDeque<Job>,
+    threads: usize,
+    idle: usize,
+    shutdown: bool,
+}
+
+#[derive(Default)]
+struct Shared {
+    mutex: Mutex<Queue>,
+    condvar: Condvar,
+}
+
+impl Shared {
+    fn worker(&self) {
+        let mut queue = self.mutex.lock().unwrap();
+        loop {
+            if let Some(job) = queue.jobs.pop_front() {
+                drop(queue);
+                job();
+                queue = self.mutex.lock().unwrap();
+            } else if queue.shutdown {
+                queue.threads -= 1;
+                return;
+            } else {
+                queue.idle += 1;
+                queue = self.condvar.wait(queue).unwrap();
+                queue.idle -= 1;
+            }
+        }
+    }
+}
+
+/// The pool of the threads running the blocking jobs, e.g. the name resolution.
+///
+/// The threads are spawned on demand up to `count`, so the pool has no threads until the first
+/// job is executed. If `count` is zero the jobs run on the calling thread.
+pub struct ThreadPool {
+    options: ThreadOptions,
+    shared: Arc<Shared>,
+}
+
+impl ThreadPool {
+    pub fn new(options: ThreadOptions) -> Self {
+        ThreadPool {
+            options: options,
+            shared: Arc::default(),
+        }
+    }
+
+    pub fn execute<F>(&self, func: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        if self.options.count == 0 {
+            return func();
+        }
+
+        let mut queue = self.shared.mutex.lock().unwrap();
+        queue.jobs.push_back(Box::new(func));
+        if queue.idle == 0 && queue.threads < self.options.count {
+            let shared = self.shared.clone();
+            if self.options.spawn(queue.threads, move || shared.worker()).is_ok() {
+                queue.threads += 1;
+                return;
+            }
+        }
+        if queue.threads == 0 {
+            // Runs on the calling thread if no thread could be spawned.
+            let func = queue.jobs.pop_back().unwrap();
+            drop(queue);
+            return func();
+        }
+        self.shared.condvar.notify_one();
+    }
+
+    pub fn options(&self) -> &ThreadOptions {
+        &self.options
+    }
+}
+
+impl Drop for ThreadPool {
+    fn drop(&mut self) {
+        self.shared.mutex.lock().unwrap().shutdown = true;
+        self.shared.condvar.notify_all();
+    }
+}
+
+#[test]
+fn test_thread_pool() {
+    use std::sync::mpsc;
+
+    // runs on the calling thread by default.
+    let pool = ThreadPool::new(ThreadOptions::default());
+    let (tx, rx) = mpsc::channel();
+    pool.execute(move || tx.send(thread::current().id()).unwrap());
+    assert_eq!(rx.try_recv().unwrap(), thread::current().id());
+
+    let pool = ThreadPool::new(ThreadOptions {
+        count: 2,
+        name: Some("test".to_string()),
+        affinity: vec![0],
+    });
+    let (tx, rx) = mpsc::channel();
+    for _ in 0..10 {
+        let tx = tx.clone();
+        pool.execute(move || {
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            assert_eq!(unsafe { ::libc::sched_getcpu() }, 0);
+            tx.send(thread::current().name().unwrap().to_owned()).unwrap()
+        });
+    }
+    for _ in 0..10 {
+        let name = rx.recv().unwrap();
+        assert!(name == "test-0" || name == "test-1");
+    }
+}
@@ -0,0 +1,429 @@
+use ffi::Timeout;
+use core::{Protocol, IoContext, AsIoContext, ThreadIoContext, Cancel};
+use handler::{Handler, Complete, Success, Failure};
+use socket_base::Wait;
+use dgram_socket::DgramSocket;
+use SteadyTimer;
+
+use std::io;
+use std::slice;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+/// Provides the arena of the datagrams received by a batch.
+///
+/// The arena has a fixed number of the slots of the same size, that each datagram is received
+/// into. A datagram larger than the slot is truncated.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use asyncio::{IoContext, DgramBatch};
+/// use asyncio::ip::{IpProtocol, IpAddrV4, Udp, UdpEndpoint, UdpSocket};
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let soc = UdpSocket::new(ctx, Udp::v4()).unwrap();
+/// soc.bind(&UdpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+/// let ep = soc.local_endpoint().unwrap();
+/// soc.send_to(b"hello", 0, &ep).unwrap();
+///
+/// let mut batch = DgramBatch::new(16, 1500);
+/// let n = soc.receive_batch_for(&mut batch, Duration::from_millis(10), 0).unwrap();
+/// assert_eq!(n, 1);
+/// for (buf, _) in batch.iter() {
+///     assert_eq!(buf, b"hello");
+/// }
+/// batch.clear();
+/// ```
+pub struct DgramBatch<E> {
+    buf: Box<[u8]>,
+    slot: usize,
+    msgs: Vec<(usize, E)>,
+}
+
+impl<E> DgramBatch<E> {
+    /// Returns a new arena of the `capacity` slots of `slot` bytes.
+    pub fn new(capacity: usize, slot: usize) -> Self {
+        DgramBatch {
+            buf: vec![0; capacity * slot].into_boxed_slice(),
+            slot: slot,
+            msgs: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Returns the maximum number of the datagrams.
+    pub fn capacity(&self) -> usize {
+        self.msgs.capacity()
+    }
+
+    /// Removes all the received datagrams, that makes all the slots available again.
+    pub fn clear(&mut self) {
+        self.msgs.clear()
+    }
+
+    /// Returns the `i`-th received datagram and the sender endpoint.
+    pub fn get(&self, i: usize) -> Option<(&[u8], &E)> {
+        self.msgs.get(i).map(|&(len, ref ep)| {
+            let pos = i * self.slot;
+            (&self.buf[pos..pos + len], ep)
+        })
+    }
+
+    fn is_full(&self) -> bool {
+        self.msgs.len() == self.msgs.capacity()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.msgs.is_empty()
+    }
+
+    /// Returns an iterator over the received datagrams and the sender endpoints.
+    pub fn iter(&self) -> DgramBatchIter<'_, E> {
+        DgramBatchIter {
+            batch: self,
+            msgs: self.msgs.iter(),
+            pos: 0,
+        }
+    }
+
+    /// Returns the number of the received datagrams.
+    pub fn len(&self) -> usize {
+        self.msgs.len()
+    }
+
+    fn next_slot(&mut self) -> &mut [u8] {
+        let pos = self.msgs.len() * self.slot;
+        &mut self.buf[pos..pos + self.slot]
+    }
+
+    fn push(&mut self, len: usize, ep: E) {
+        self.msgs.push((len, ep))
+    }
+
+    /// Returns the size of each slot.
+    pub fn slot_size(&self) -> usize {
+        self.slot
+    }
+}
+
+/// An iterator over the datagrams of the `DgramBatch`.
+pub struct DgramBatchIter<'a, E: 'a> {
+    batch: &'a DgramBatch<E>,
+    msgs: slice::Iter<'a, (usize, E)>,
+    pos: usize,
+}
+
+impl<'a, E> Iterator for DgramBatchIter<'a, E> {
+    type Item = (&'a [u8], &'a E);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.msgs.next().map(|&(len, ref ep)| {
+            let pos = self.pos;
+            self.pos += self.batch.slot;
+            (&self.batch.buf[pos..pos + len], ep)
+        })
+    }
+}
+
+/// Receives the datagrams into the batch without blocking until the batch is full or no more
+/// datagram is available.
+fn drain<P>(soc: &DgramSocket<P>, batch: &mut DgramBatch<P::Endpoint>, flags: i32) -> io::Result<usize>
+where
+    P: Protocol,
+{
+    let mut n = 0;
+    while !batch.is_full() {
+        match soc.nonblocking_receive_from(batch.next_slot(), flags) {
+            Ok((len, ep)) => batch.push(len, ep),
+            Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => break,
+            Err(err) => return Err(err),
+        }
+        n += 1;
+    }
+    Ok(n)
+}
+
+pub fn receive_batch_for<P>(
+    soc: &DgramSocket<P>,
+    batch: &mut DgramBatch<P::Endpoint>,
+    timeout: Duration,
+    flags: i32,
+) -> io::Result<usize>
+where
+    P: Protocol,
+{
+    let deadline = Instant::now() + timeout;
+    let mut n = drain(soc, batch, flags)?;
+    while !batch.is_full() {
+        match soc.wait_until(Wait::Read, deadline) {
+            Ok(_) => (),
+            Err(ref err) if err.kind() == io::ErrorKind::TimedOut => break,
+            Err(err) => return Err(err),
+        }
+        n += drain(soc, batch, flags)?;
+    }
+    Ok(n)
+}
+
+pub fn async_receive_batch_for<P, F>(
+    soc: &DgramSocket<P>,
+    batch: &mut DgramBatch<P::Endpoint>,
+    timeout: Duration,
+    flags: i32,
+    handler: F,
+) -> F::Output
+where
+    P: Protocol + 'static,
+    F: Handler<usize, io::Error>,
+{
+    let ptr = batch as *mut DgramBatch<P::Endpoint>;
+    handler.wrap(soc.as_ctx(), move |ctx, handler| {
+        let batch = unsafe { &mut *ptr };
+        let n = match drain(soc, batch, flags) {
+            Ok(n) => n,
+            Err(err) => return ctx.do_dispatch(Failure::new(err, handler)),
+        };
+        if batch.is_full() {
+            return ctx.do_dispatch(Success::new(n, handler));
+        }
+
+        let state = Arc::new(BatchState {
+            timer: SteadyTimer::new(ctx),
+            done: Mutex::new(false),
+            expired: AtomicBool::new(false),
+        });
+        state.timer.expires_from_now(timeout);
+        state.timer.async_wait(BatchExpire {
+            soc: soc,
+            state: state.clone(),
+        });
+        soc.async_receive_from(
+            batch.next_slot(),
+            flags,
+            BatchRecv {
+                soc: soc,
+                batch: ptr,
+                flags: flags,
+                count: n,
+                state: state,
+                handler: handler,
+            },
+        )
+    })
+}
+
+/// The state shared by the receiving and the timer, that outlives the both operations.
+struct BatchState {
+    timer: SteadyTimer,
+    done: Mutex<bool>,
+    expired: AtomicBool,
+}
+
+unsafe impl Send for BatchState {}
+
+unsafe impl Sync for BatchState {}
+
+struct BatchRecv<P: Protocol, F> {
+    soc: *const DgramSocket<P>,
+    batch: *mut DgramBatch<P::Endpoint>,
+    flags: i32,
+    count: usize,
+    state: Arc<BatchState>,
+    handler: F,
+}
+
+unsafe impl<P: Protocol, F> Send for BatchRecv<P, F> {}
+
+impl<P: Protocol, F> BatchRecv<P, F> {
+    fn finish(&self) {
+        *self.state.done.lock().unwrap() = true;
+        self.state.timer.cancel();
+    }
+}
+
+impl<P, F> Handler<(usize, P::Endpoint), io::Error> for BatchRecv<P, F>
+where
+    P: Protocol + 'static,
+    F: Complete<usize, io::Error>,
+{
+    type Output = ();
+
+    type WrappedHandler = Self;
+
+    fn wrap<W>(self, ctx: &IoContext, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx, self)
+    }
+
+    fn wrap_timeout<W>(self, ctx: &Cancel, _: &Timeout, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx.as_ctx(), self)
+    }
+}
+
+impl<P, F> Complete<(usize, P::Endpoint), io::Error> for BatchRecv<P, F>
+where
+    P: Protocol + 'static,
+    F: Complete<usize, io::Error>,
+{
+    fn success(mut self, this: &mut ThreadIoContext, res: (usize, P::Endpoint)) {
+        let soc = unsafe { &*self.soc };
+        let batch = unsafe { &mut *self.batch };
+        let (len, ep) = res;
+        batch.push(len, ep);
+        self.count += 1;
+        match drain(soc, batch, self.flags) {
+            Ok(n) => self.count += n,
+            Err(err) => {
+                self.finish();
+                return self.handler.failure(this, err);
+            }
+        }
+
+        let fin = {
+            let mut done = self.state.done.lock().unwrap();
+            *done = batch.is_full() || self.state.expired.load(Ordering::SeqCst);
+            *done
+        };
+        if fin {
+            self.state.timer.cancel();
+            let count = self.count;
+            return self.handler.success(this, count);
+        }
+        this.decrease_outstanding_work();
+        soc.async_receive_from(batch.next_slot(), self.flags, self)
+    }
+
+    fn failure(self, this: &mut ThreadIoContext, err: io::Error) {
+        if self.state.expired.load(Ordering::SeqCst) {
+            // Canceled by the timer, that completes with the datagrams received so far.
+            let count = self.count;
+            return self.handler.success(this, count);
+        }
+        self.finish();
+        self.handler.failure(this, err)
+    }
+}
+
+struct BatchExpire<P> {
+    soc: *const DgramSocket<P>,
+    state: Arc<BatchState>,
+}
+
+unsafe impl<P> Send for BatchExpire<P> {}
+
+impl<P> Handler<(), io::Error> for BatchExpire<P>
+where
+    P: Protocol + 'static,
+{
+    type Output = ();
+
+    type WrappedHandler = Self;
+
+    fn wrap<W>(self, ctx: &IoContext, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx, self)
+    }
+
+    fn wrap_timeout<W>(self, ctx: &Cancel, _: &Timeout, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx.as_ctx(), self)
+    }
+}
+
+impl<P> Complete<(), io::Error> for BatchExpire<P>
+where
+    P: Protocol + 'static,
+{
+    fn success(self, this: &mut ThreadIoContext, _: ()) {
+        let done = self.state.done.lock().unwrap();
+        if !*done {
+            // The socket is alive until the receiving is completed.
+            self.state.expired.store(true, Ordering::SeqCst);
+            unsafe { &*self.soc }.cancel();
+        }
+        drop(done);
+        this.decrease_outstanding_work();
+    }
+
+    fn failure(self, this: &mut ThreadIoContext, _: io::Error) {
+        this.decrease_outstanding_work();
+    }
+}
+
+#[test]
+fn test_dgram_batch() {
+    let mut batch: DgramBatch<u8> = DgramBatch::new(3, 4);
+    assert_eq!(batch.capacity(), 3);
+    assert_eq!(batch.slot_size(), 4);
+    assert!(batch.is_empty());
+    batch.next_slot().copy_from_slice(b"abcd");
+    batch.push(4, 0);
+    batch.next_slot()[..2].copy_from_slice(b"ef");
+    batch.push(2, 1);
+    assert_eq!(batch.len(), 2);
+    assert!(!batch.is_full());
+    assert_eq!(batch.get(1), Some((&b"ef"[..], &1)));
+    assert_eq!(batch.get(2), None);
+    let v: Vec<_> = batch.iter().collect();
+    assert_eq!(v, vec![(&b"abcd"[..], &0), (&b"ef"[..], &1)]);
+    batch.clear();
+    assert!(batch.is_empty());
+    assert_eq!(batch.next_slot().len(), 4);
+}
+
+#[test]
+fn test_async_receive_batch_for() {
+    use ip::{IpProtocol, IpAddrV4, Udp, UdpEndpoint, UdpSocket};
+    use handler::wrap;
+    use std::sync::atomic::AtomicUsize;
+
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+
+    fn on_batch(_: Arc<UdpSocket>, res: io::Result<usize>) {
+        COUNT.store(res.unwrap(), Ordering::SeqCst);
+    }
+
+    let ctx = &IoContext::new().unwrap();
+    let soc = Arc::new(UdpSocket::new(ctx, Udp::v4()).unwrap());
+    soc.bind(&UdpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+    let ep = soc.local_endpoint().unwrap();
+
+    // completes when the batch is full, that the datagrams arrive after the start.
+    let mut batch = DgramBatch::new(3, 16);
+    soc.async_receive_batch_for(&mut batch, Duration::from_secs(10), 0, wrap(&soc, on_batch));
+    ctx.post(move |ctx| {
+        let tx = UdpSocket::new(ctx, Udp::v4()).unwrap();
+        for buf in &[&b"a"[..], b"bc", b"def", b"ghij"] {
+            tx.send_to(buf, 0, &ep).unwrap();
+        }
+    });
+    ctx.run();
+    assert_eq!(COUNT.load(Ordering::SeqCst), 3);
+    let v: Vec<_> = batch.iter().map(|(buf, _)| buf.to_vec()).collect();
+    assert_eq!(v, vec![b"a".to_vec(), b"bc".to_vec(), b"def".to_vec()]);
+
+    // completes with the partial data when the timer expires.
+    ctx.restart();
+    batch.clear();
+    soc.async_receive_batch_for(&mut batch, Duration::from_millis(50), 0, wrap(&soc, on_batch));
+    ctx.run();
+    assert_eq!(COUNT.load(Ordering::SeqCst), 1);
+    assert_eq!(batch.get(0).unwrap().0, b"ghij");
+
+    ctx.restart();
+    soc.async_receive_batch_for(&mut batch, Duration::from_millis(10), 0, wrap(&soc, on_batch));
+    ctx.run();
+    assert_eq!(COUNT.load(Ordering::SeqCst), 0);
+    assert_eq!(batch.len(), 1);
+}
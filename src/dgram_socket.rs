@@ -1,17 +1,24 @@
-use ffi::{AsRawFd, RawFd, SystemError, socket, shutdown, bind, ioctl, getsockopt,
-          setsockopt, getpeername, getsockname};
-use reactor::SocketImpl;
+use ffi::{AsRawFd, IntoRawFd, RawFd, SystemError, socket, shutdown, bind, ioctl, getsockopt,
+          raw_getsockopt, raw_setsockopt, setsockopt, getpeername, getsockname, wait, wait_for};
+use reactor::{SocketImpl, OpStats};
+use extensions::Extensions;
 use core::{Protocol, Socket, IoControl, GetSocketOption, SetSocketOption, AsIoContext, IoContext,
            Perform, ThreadIoContext, Cancel};
 use handler::{Handler, AsyncReadOp, AsyncWriteOp};
 use connect_ops::{async_connect, nonblocking_connect};
-use read_ops::{Recv, RecvFrom, async_read_op, blocking_read_op, nonblocking_read_op};
-use write_ops::{Sent, SendTo, async_write_op, blocking_write_op, nonblocking_write_op};
-use socket_base::{BytesReadable, Shutdown};
+use read_ops::{Recv, RecvFds, RecvFrom, RecvFromInto, RecvFromTrunc, RecvFromGrow, async_read_op, blocking_read_op, nonblocking_read_op};
+use write_ops::{Sent, SendFds, SendTo, async_write_op, blocking_write_op, nonblocking_write_op};
+use socket_base::{Wait, BytesReadable, Shutdown};
+use dgram_batch::{DgramBatch, async_receive_batch_for, receive_batch_for};
+use local::LocalDgram;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use read_ops::{RecvFromHopLimit, RecvFromOrigDst};
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use write_ops::{SendToHopLimit, SendToTxTime};
 
 use std::io;
 use std::fmt;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 pub struct DgramSocket<P> {
     pimpl: Box<SocketImpl<P>>,
@@ -40,6 +47,25 @@ where
         async_read_op(self, buf, &self.pimpl.timeout, handler, Recv::new(flags))
     }
 
+    /// Asynchronously receives the datagrams into the remaining slots of the batch, that completes
+    /// once when the batch is full or the `timeout` expires.
+    ///
+    /// The handler is called with the number of datagrams received by this operation, that may be
+    /// zero if the timeout expires. The expiry cancels the other operations of the socket.
+    pub fn async_receive_batch_for<F>(
+        &self,
+        batch: &mut DgramBatch<P::Endpoint>,
+        timeout: Duration,
+        flags: i32,
+        handler: F,
+    ) -> F::Output
+    where
+        P: 'static,
+        F: Handler<usize, io::Error>,
+    {
+        async_receive_batch_for(self, batch, timeout, flags, handler)
+    }
+
     pub fn async_receive_from<F>(&self, buf: &mut [u8], flags: i32, handler: F) -> F::Output
     where
         F: Handler<(usize, P::Endpoint), io::Error>,
@@ -53,6 +79,65 @@ where
         )
     }
 
+    /// Asynchronously receives a datagram, and stores the source endpoint to the `ep`.
+    ///
+    /// The `ep` is reused for each datagram without allocating a new endpoint, that must be alive
+    /// until the handler is invoked, e.g. the local variable of the coroutine.
+    pub fn async_receive_from_into<F>(
+        &self,
+        buf: &mut [u8],
+        flags: i32,
+        ep: &mut P::Endpoint,
+        handler: F,
+    ) -> F::Output
+    where
+        F: Handler<usize, io::Error>,
+    {
+        async_read_op(
+            self,
+            buf,
+            &self.pimpl.timeout,
+            handler,
+            RecvFromInto::new(flags, ep),
+        )
+    }
+
+    /// Asynchronously receives a datagram with a flag that is true if the datagram was truncated to the buffer size.
+    pub fn async_receive_from_trunc<F>(&self, buf: &mut [u8], flags: i32, handler: F) -> F::Output
+    where
+        F: Handler<(usize, P::Endpoint, bool), io::Error>,
+    {
+        async_read_op(
+            self,
+            buf,
+            &self.pimpl.timeout,
+            handler,
+            RecvFromTrunc::new(flags),
+        )
+    }
+
+    /// Asynchronously receives a whole datagram into the vector, that grows up to `max` bytes.
+    ///
+    /// A datagram larger than `max` bytes is discarded with the `EMSGSIZE` error.
+    pub fn async_receive_from_vec<F>(
+        &self,
+        buf: &mut Vec<u8>,
+        max: usize,
+        flags: i32,
+        handler: F,
+    ) -> F::Output
+    where
+        F: Handler<(usize, P::Endpoint), io::Error>,
+    {
+        async_read_op(
+            self,
+            &[],
+            &self.pimpl.timeout,
+            handler,
+            RecvFromGrow::new(flags, buf, max),
+        )
+    }
+
     pub fn async_send<F>(&self, buf: &[u8], flags: i32, handler: F) -> F::Output
     where
         F: Handler<usize, io::Error>,
@@ -79,6 +164,11 @@ where
         )
     }
 
+    /// Adopts the native socket of the protocol in place of the current socket which is closed.
+    pub fn assign(&mut self, soc: RawFd, pro: P) -> io::Result<()> {
+        Ok(self.pimpl.assign(soc, pro)?)
+    }
+
     pub fn available(&self) -> io::Result<usize> {
         let mut bytes = BytesReadable::default();
         ioctl(self, &mut bytes)?;
@@ -89,10 +179,20 @@ where
         Ok(bind(self, ep)?)
     }
 
+    /// Cancels the pending operations and closes the socket, returns the error of the `close`.
+    pub fn close(&mut self) -> io::Result<()> {
+        Ok(self.pimpl.close()?)
+    }
+
     pub fn connect(&self, ep: &P::Endpoint) -> io::Result<()> {
         nonblocking_connect(self, ep)
     }
 
+    /// Returns the typed user data attached to the socket.
+    pub fn extensions(&self) -> &Extensions {
+        &self.pimpl.extensions
+    }
+
     pub fn local_endpoint(&self) -> io::Result<P::Endpoint> {
         Ok(getsockname(self)?)
     }
@@ -115,6 +215,10 @@ where
         Ok(ioctl(self, cmd)?)
     }
 
+    pub fn is_open(&self) -> bool {
+        self.pimpl.is_open()
+    }
+
     pub fn nonblocking_receive(&self, buf: &mut [u8], flags: i32) -> io::Result<usize> {
         nonblocking_read_op(self, buf, Recv::new(flags))
     }
@@ -127,6 +231,32 @@ where
         nonblocking_read_op(self, buf, RecvFrom::new(flags))
     }
 
+    pub fn nonblocking_receive_from_into(
+        &self,
+        buf: &mut [u8],
+        flags: i32,
+        ep: &mut P::Endpoint,
+    ) -> io::Result<usize> {
+        nonblocking_read_op(self, buf, RecvFromInto::new(flags, ep))
+    }
+
+    pub fn nonblocking_receive_from_trunc(
+        &self,
+        buf: &mut [u8],
+        flags: i32,
+    ) -> io::Result<(usize, P::Endpoint, bool)> {
+        nonblocking_read_op(self, buf, RecvFromTrunc::new(flags))
+    }
+
+    pub fn nonblocking_receive_from_vec(
+        &self,
+        buf: &mut Vec<u8>,
+        max: usize,
+        flags: i32,
+    ) -> io::Result<(usize, P::Endpoint)> {
+        nonblocking_read_op(self, &mut [], RecvFromGrow::new(flags, buf, max))
+    }
+
     pub fn nonblocking_send(&self, buf: &[u8], flags: i32) -> io::Result<usize> {
         nonblocking_write_op(self, buf, Sent::new(flags))
     }
@@ -140,14 +270,148 @@ where
         nonblocking_write_op(self, buf, SendTo::new(flags, ep))
     }
 
+    /// Returns the statistics of the asynchronous operations of the socket, that are counted only
+    /// in debug builds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io;
+    /// use std::sync::Arc;
+    /// use asyncio::{IoContext, Cancel, wrap};
+    /// use asyncio::ip::{IpProtocol, IpAddrV4, Udp, UdpEndpoint, UdpSocket};
+    ///
+    /// fn on_receive(_: Arc<UdpSocket>, _: io::Result<usize>) {}
+    ///
+    /// let ctx = &IoContext::new().unwrap();
+    /// let soc = Arc::new(UdpSocket::new(ctx, Udp::v4()).unwrap());
+    /// soc.bind(&UdpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+    /// let mut buf = [0; 16];
+    /// soc.async_receive(&mut buf, 0, wrap(&soc, on_receive));
+    /// let soc_ = soc.clone();
+    /// ctx.post(move |_| {
+    ///   println!("{:?}", soc_.op_stats());
+    ///   soc_.cancel();
+    /// });
+    /// ctx.run();
+    /// assert_eq!(soc.op_stats().live, 0);
+    /// ```
+    pub fn op_stats(&self) -> OpStats {
+        self.pimpl.op_stats()
+    }
+
+    /// Gets the socket option by the raw `level` and `name` into the buffer, returns the length of
+    /// the option value.
+    ///
+    /// # Safety
+    ///
+    /// The layout of the buffer must be what the kernel expects for the option.
+    pub unsafe fn raw_getsockopt(&self, level: i32, name: i32, buf: &mut [u8]) -> io::Result<usize> {
+        Ok(raw_getsockopt(self, level, name, buf)?)
+    }
+
+    /// Sets the socket option by the raw `level` and `name` from the buffer.
+    ///
+    /// # Safety
+    ///
+    /// The layout of the buffer must be what the kernel expects for the option.
+    pub unsafe fn raw_setsockopt(&self, level: i32, name: i32, buf: &[u8]) -> io::Result<()> {
+        Ok(raw_setsockopt(self, level, name, buf)?)
+    }
+
     pub fn receive(&self, buf: &mut [u8], flags: i32) -> io::Result<usize> {
         blocking_read_op(self, buf, &self.pimpl.timeout, Recv::new(flags))
     }
 
+    /// Receives the datagrams into the remaining slots of the batch until the batch is full or the
+    /// `timeout` expires, returns the number of datagrams received.
+    pub fn receive_batch_for(
+        &self,
+        batch: &mut DgramBatch<P::Endpoint>,
+        timeout: Duration,
+        flags: i32,
+    ) -> io::Result<usize> {
+        receive_batch_for(self, batch, timeout, flags)
+    }
+
     pub fn receive_from(&self, buf: &mut [u8], flags: i32) -> io::Result<(usize, P::Endpoint)> {
         blocking_read_op(self, buf, &self.pimpl.timeout, RecvFrom::new(flags))
     }
 
+    /// Receives a datagram, and stores the source endpoint to the `ep` without allocating a new
+    /// endpoint.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use asyncio::IoContext;
+    /// use asyncio::ip::{IpProtocol, IpAddrV4, Udp, UdpEndpoint, UdpSocket};
+    ///
+    /// let ctx = &IoContext::new().unwrap();
+    /// let soc = UdpSocket::new(ctx, Udp::v4()).unwrap();
+    /// soc.bind(&UdpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+    /// let local_ep = soc.local_endpoint().unwrap();
+    /// soc.send_to(b"ping", 0, &local_ep).unwrap();
+    ///
+    /// let mut buf = [0; 1500];
+    /// let mut ep = UdpEndpoint::new(IpAddrV4::any(), 0);
+    /// let len = soc.receive_from_into(&mut buf, 0, &mut ep).unwrap();
+    /// soc.send_to(&buf[..len], 0, &ep).unwrap();
+    /// assert_eq!(ep, local_ep);
+    /// ```
+    pub fn receive_from_into(
+        &self,
+        buf: &mut [u8],
+        flags: i32,
+        ep: &mut P::Endpoint,
+    ) -> io::Result<usize> {
+        blocking_read_op(self, buf, &self.pimpl.timeout, RecvFromInto::new(flags, ep))
+    }
+
+    /// Receives a datagram with a flag that is true if the datagram was truncated to the buffer size.
+    pub fn receive_from_trunc(
+        &self,
+        buf: &mut [u8],
+        flags: i32,
+    ) -> io::Result<(usize, P::Endpoint, bool)> {
+        blocking_read_op(self, buf, &self.pimpl.timeout, RecvFromTrunc::new(flags))
+    }
+
+    /// Receives a whole datagram into the vector, that grows up to `max` bytes.
+    ///
+    /// A datagram larger than `max` bytes is discarded with the `EMSGSIZE` error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use asyncio::IoContext;
+    /// use asyncio::ip::{IpProtocol, IpAddrV4, Udp, UdpEndpoint, UdpSocket};
+    ///
+    /// let ctx = &IoContext::new().unwrap();
+    /// let soc = UdpSocket::new(ctx, Udp::v4()).unwrap();
+    /// soc.bind(&UdpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+    /// let ep = soc.local_endpoint().unwrap();
+    /// soc.send_to(&[0; 3000], 0, &ep).unwrap();
+    ///
+    /// let mut buf = Vec::with_capacity(512);
+    /// let (len, _) = soc.receive_from_vec(&mut buf, 65536, 0).unwrap();
+    /// assert_eq!(len, 3000);
+    /// assert_eq!(buf.len(), 3000);
+    /// ```
+    pub fn receive_from_vec(
+        &self,
+        buf: &mut Vec<u8>,
+        max: usize,
+        flags: i32,
+    ) -> io::Result<(usize, P::Endpoint)> {
+        blocking_read_op(
+            self,
+            &mut [],
+            &self.pimpl.timeout,
+            RecvFromGrow::new(flags, buf, max),
+        )
+    }
+
     pub fn remote_endpoint(&self) -> io::Result<P::Endpoint> {
         Ok(getpeername(self)?)
     }
@@ -174,6 +438,23 @@ where
     pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
         Ok(shutdown(self, how)?)
     }
+
+    /// Blocks until the socket becomes ready to read, write or have the error condition.
+    pub fn wait(&self, w: Wait) -> io::Result<()> {
+        Ok(wait(self, w, -1)?)
+    }
+
+    /// Blocks until the socket becomes ready or the timeout expires with the `ETIMEDOUT` error.
+    pub fn wait_for(&self, w: Wait, timeout: Duration) -> io::Result<()> {
+        Ok(wait_for(self, w, timeout)?)
+    }
+
+    /// Blocks until the socket becomes ready or the deadline with the `ETIMEDOUT` error.
+    pub fn wait_until(&self, w: Wait, deadline: Instant) -> io::Result<()> {
+        let now = Instant::now();
+        let timeout = if deadline > now { deadline - now } else { Duration::new(0, 0) };
+        Ok(wait_for(self, w, timeout)?)
+    }
 }
 
 unsafe impl<P> AsIoContext for DgramSocket<P> {
@@ -182,12 +463,308 @@ unsafe impl<P> AsIoContext for DgramSocket<P> {
     }
 }
 
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<P> DgramSocket<P>
+where
+    P: Protocol,
+{
+    /// Asynchronously receives a datagram with the hop limit of the IP header.
+    ///
+    /// The hop limit is `None` unless the `RecvHopLimit` option is enabled.
+    pub fn async_receive_from_hop_limit<F>(
+        &self,
+        buf: &mut [u8],
+        flags: i32,
+        handler: F,
+    ) -> F::Output
+    where
+        F: Handler<(usize, P::Endpoint, Option<u8>), io::Error>,
+    {
+        async_read_op(
+            self,
+            buf,
+            &self.pimpl.timeout,
+            handler,
+            RecvFromHopLimit::new(flags),
+        )
+    }
+
+    /// Asynchronously receives a datagram with the original destination endpoint.
+    ///
+    /// The destination is `None` unless the `RecvOrigDstAddr` option is enabled.
+    pub fn async_receive_from_orig_dst<F>(&self, buf: &mut [u8], flags: i32, handler: F) -> F::Output
+    where
+        F: Handler<(usize, P::Endpoint, Option<P::Endpoint>), io::Error>,
+    {
+        async_read_op(
+            self,
+            buf,
+            &self.pimpl.timeout,
+            handler,
+            RecvFromOrigDst::new(flags),
+        )
+    }
+
+    /// Asynchronously sends a datagram with the hop limit, that overrides the `UnicastHops` or
+    /// the `MulticastHops` option for the datagram.
+    pub fn async_send_to_hop_limit<F>(
+        &self,
+        buf: &[u8],
+        flags: i32,
+        ep: &P::Endpoint,
+        hops: u8,
+        handler: F,
+    ) -> F::Output
+    where
+        F: Handler<usize, io::Error>,
+    {
+        async_write_op(
+            self,
+            buf,
+            &self.pimpl.timeout,
+            handler,
+            SendToHopLimit::new(flags, ep, hops),
+        )
+    }
+
+    /// Asynchronously sends a datagram to be transmitted at the time, in nanoseconds of the clock
+    /// set by the `TxTime` option.
+    pub fn async_send_to_txtime<F>(
+        &self,
+        buf: &[u8],
+        flags: i32,
+        ep: &P::Endpoint,
+        txtime: u64,
+        handler: F,
+    ) -> F::Output
+    where
+        F: Handler<usize, io::Error>,
+    {
+        async_write_op(
+            self,
+            buf,
+            &self.pimpl.timeout,
+            handler,
+            SendToTxTime::new(flags, ep, txtime),
+        )
+    }
+
+    pub fn nonblocking_receive_from_hop_limit(
+        &self,
+        buf: &mut [u8],
+        flags: i32,
+    ) -> io::Result<(usize, P::Endpoint, Option<u8>)> {
+        nonblocking_read_op(self, buf, RecvFromHopLimit::new(flags))
+    }
+
+    pub fn nonblocking_receive_from_orig_dst(
+        &self,
+        buf: &mut [u8],
+        flags: i32,
+    ) -> io::Result<(usize, P::Endpoint, Option<P::Endpoint>)> {
+        nonblocking_read_op(self, buf, RecvFromOrigDst::new(flags))
+    }
+
+    pub fn nonblocking_send_to_hop_limit(
+        &self,
+        buf: &[u8],
+        flags: i32,
+        ep: &P::Endpoint,
+        hops: u8,
+    ) -> io::Result<usize> {
+        nonblocking_write_op(self, buf, SendToHopLimit::new(flags, ep, hops))
+    }
+
+    pub fn nonblocking_send_to_txtime(
+        &self,
+        buf: &[u8],
+        flags: i32,
+        ep: &P::Endpoint,
+        txtime: u64,
+    ) -> io::Result<usize> {
+        nonblocking_write_op(self, buf, SendToTxTime::new(flags, ep, txtime))
+    }
+
+    /// Receives a datagram with the hop limit of the IP header.
+    ///
+    /// The hop limit is `None` unless the `RecvHopLimit` option is enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use asyncio::IoContext;
+    /// use asyncio::ip::{IpProtocol, IpAddrV6, RecvHopLimit, Udp, UdpEndpoint, UdpSocket};
+    ///
+    /// let ctx = &IoContext::new().unwrap();
+    /// let soc = UdpSocket::new(ctx, Udp::v6()).unwrap();
+    /// soc.set_option(RecvHopLimit::new(true)).unwrap();
+    /// soc.bind(&UdpEndpoint::new(IpAddrV6::loopback(), 0)).unwrap();
+    /// let ep = soc.local_endpoint().unwrap();
+    /// soc.send_to_hop_limit(b"hello", 0, &ep, 255).unwrap();
+    ///
+    /// let mut buf = [0; 16];
+    /// let (len, _, hops) = soc.receive_from_hop_limit(&mut buf, 0).unwrap();
+    /// assert_eq!(len, 5);
+    /// assert_eq!(hops, Some(255));
+    /// ```
+    pub fn receive_from_hop_limit(
+        &self,
+        buf: &mut [u8],
+        flags: i32,
+    ) -> io::Result<(usize, P::Endpoint, Option<u8>)> {
+        blocking_read_op(self, buf, &self.pimpl.timeout, RecvFromHopLimit::new(flags))
+    }
+
+    /// Receives a datagram with the original destination endpoint.
+    ///
+    /// The destination is `None` unless the `RecvOrigDstAddr` option is enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use asyncio::IoContext;
+    /// use asyncio::ip::{IpProtocol, IpAddrV4, RecvOrigDstAddr, Udp, UdpEndpoint, UdpSocket};
+    ///
+    /// let ctx = &IoContext::new().unwrap();
+    /// let soc = UdpSocket::new(ctx, Udp::v4()).unwrap();
+    /// soc.set_option(RecvOrigDstAddr::new(true)).unwrap();
+    /// soc.bind(&UdpEndpoint::new(IpAddrV4::any(), 0)).unwrap();
+    /// let port = soc.local_endpoint().unwrap().port();
+    /// soc.send_to(b"hello", 0, &UdpEndpoint::new(IpAddrV4::loopback(), port)).unwrap();
+    ///
+    /// let mut buf = [0; 16];
+    /// let (len, _, dst) = soc.receive_from_orig_dst(&mut buf, 0).unwrap();
+    /// assert_eq!(len, 5);
+    /// assert_eq!(dst, Some(UdpEndpoint::new(IpAddrV4::loopback(), port)));
+    /// ```
+    pub fn receive_from_orig_dst(
+        &self,
+        buf: &mut [u8],
+        flags: i32,
+    ) -> io::Result<(usize, P::Endpoint, Option<P::Endpoint>)> {
+        blocking_read_op(self, buf, &self.pimpl.timeout, RecvFromOrigDst::new(flags))
+    }
+
+    /// Sends a datagram with the hop limit, that overrides the `UnicastHops` or the
+    /// `MulticastHops` option for the datagram.
+    pub fn send_to_hop_limit(
+        &self,
+        buf: &[u8],
+        flags: i32,
+        ep: &P::Endpoint,
+        hops: u8,
+    ) -> io::Result<usize> {
+        blocking_write_op(
+            self,
+            buf,
+            &self.pimpl.timeout,
+            SendToHopLimit::new(flags, ep, hops),
+        )
+    }
+
+    /// Sends a datagram to be transmitted at the time, in nanoseconds of the clock set by the
+    /// `TxTime` option.
+    ///
+    /// The datagram is held by the etf qdisc until the time, other qdiscs send it immediately.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use asyncio::IoContext;
+    /// use asyncio::ip::{IpProtocol, IpAddrV4, Udp, UdpEndpoint, UdpSocket};
+    /// use asyncio::socket_base::{TxTime, CLOCK_MONOTONIC};
+    ///
+    /// let ctx = &IoContext::new().unwrap();
+    /// let soc = UdpSocket::new(ctx, Udp::v4()).unwrap();
+    /// soc.set_option(TxTime::new(CLOCK_MONOTONIC, 0)).unwrap();
+    /// soc.bind(&UdpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+    /// let ep = soc.local_endpoint().unwrap();
+    /// soc.send_to_txtime(b"hello", 0, &ep, 0).unwrap();
+    ///
+    /// let mut buf = [0; 16];
+    /// let (len, _) = soc.receive_from(&mut buf, 0).unwrap();
+    /// assert_eq!(len, 5);
+    /// ```
+    pub fn send_to_txtime(
+        &self,
+        buf: &[u8],
+        flags: i32,
+        ep: &P::Endpoint,
+        txtime: u64,
+    ) -> io::Result<usize> {
+        blocking_write_op(
+            self,
+            buf,
+            &self.pimpl.timeout,
+            SendToTxTime::new(flags, ep, txtime),
+        )
+    }
+}
+
+impl DgramSocket<LocalDgram> {
+    /// Asynchronously receives the data with the file descriptors passed by the peer, up to
+    /// `max_fds`.
+    ///
+    /// The received file descriptors are owned by the caller, that should close them.
+    pub fn async_receive_fds<F>(&self, buf: &mut [u8], max_fds: usize, handler: F) -> F::Output
+    where
+        F: Handler<(usize, Vec<RawFd>), io::Error>,
+    {
+        async_read_op(self, buf, &self.pimpl.timeout, handler, RecvFds::new(0, max_fds))
+    }
+
+    /// Asynchronously sends the data with the file descriptors to the peer.
+    ///
+    /// The file descriptors are duplicated into the peer, that are still owned by the caller.
+    pub fn async_send_fds<F>(&self, buf: &[u8], fds: &[RawFd], handler: F) -> F::Output
+    where
+        F: Handler<usize, io::Error>,
+    {
+        async_write_op(self, buf, &self.pimpl.timeout, handler, SendFds::new(0, fds))
+    }
+
+    /// Receives the data with the file descriptors passed by the peer, up to `max_fds`.
+    ///
+    /// The received file descriptors are owned by the caller, that should close them.
+    pub fn receive_fds(&self, buf: &mut [u8], max_fds: usize) -> io::Result<(usize, Vec<RawFd>)> {
+        blocking_read_op(self, buf, &self.pimpl.timeout, RecvFds::new(0, max_fds))
+    }
+
+    /// Sends the data with the file descriptors to the peer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::os::unix::io::AsRawFd;
+    /// use asyncio::IoContext;
+    /// use asyncio::local::{LocalDgram, connect_pair};
+    ///
+    /// let ctx = &IoContext::new().unwrap();
+    /// let (tx, rx) = connect_pair(ctx, LocalDgram).unwrap();
+    /// tx.send_fds(b"fd", &[tx.as_raw_fd()]).unwrap();
+    ///
+    /// let mut buf = [0; 16];
+    /// let (len, fds) = rx.receive_fds(&mut buf, 1).unwrap();
+    /// assert_eq!(&buf[..len], b"fd");
+    /// assert_eq!(fds.len(), 1);
+    /// ```
+    pub fn send_fds(&self, buf: &[u8], fds: &[RawFd]) -> io::Result<usize> {
+        blocking_write_op(self, buf, &self.pimpl.timeout, SendFds::new(0, fds))
+    }
+}
+
 impl<P> AsRawFd for DgramSocket<P> {
     fn as_raw_fd(&self) -> RawFd {
         self.pimpl.as_raw_fd()
     }
 }
 
+impl<P> IntoRawFd for DgramSocket<P> {
+    fn into_raw_fd(self) -> RawFd {
+        self.pimpl.into_raw_fd()
+    }
+}
+
 impl<P: 'static> Cancel for DgramSocket<P> {
     fn cancel(&self) {
         self.pimpl.cancel()
@@ -231,6 +808,8 @@ where
 
 unsafe impl<P> Send for DgramSocket<P> {}
 
+unsafe impl<P> Sync for DgramSocket<P> {}
+
 impl<P> Socket<P> for DgramSocket<P>
 where
     P: Protocol,
@@ -240,6 +819,6 @@ where
     }
 
     unsafe fn from_raw_fd(ctx: &IoContext, soc: RawFd, pro: P) -> Self {
-        DgramSocket { pimpl: SocketImpl::new(ctx, soc, pro) }
+        DgramSocket { pimpl: SocketImpl::socket(ctx, soc, pro) }
     }
 }
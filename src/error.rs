@@ -0,0 +1,121 @@
+use ffi::{TIMED_OUT, OPERATION_CANCELED};
+
+use std::error;
+use std::fmt;
+use std::io;
+use libc::{ETIMEDOUT, ECANCELED, ECONNABORTED};
+
+/// The error of the timed operations, that tells the expiry of the timeout from the others.
+///
+/// The operations fail with the `ETIMEDOUT` error when the timeout or the deadline expires, that
+/// is distinct from the `ECANCELED` error of the `cancel` and the end of the stream.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use asyncio::{IoContext, Error};
+/// use asyncio::ip::{IpProtocol, IpAddrV4, Udp, UdpEndpoint, UdpSocket};
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let soc = UdpSocket::new(ctx, Udp::v4()).unwrap();
+/// soc.bind(&UdpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+/// soc.set_timeout(Duration::from_millis(100)).unwrap();
+///
+/// let mut buf = [0; 256];
+/// match Error::from(soc.receive(&mut buf, 0).unwrap_err()) {
+///     Error::TimedOut => (), // retries.
+///     Error::Canceled | Error::UnexpectedEof => return,
+///     Error::Io(err) => panic!("{}", err),
+/// }
+/// ```
+#[derive(Debug)]
+pub enum Error {
+    /// The timeout or the deadline of the operation expired.
+    TimedOut,
+    /// The operation was canceled by the `cancel`, or the `IoContext` was stopped.
+    Canceled,
+    /// The stream was closed by the peer, that is the `ECONNABORTED` of the reads.
+    UnexpectedEof,
+    /// The other errors.
+    Io(io::Error),
+}
+
+impl Error {
+    /// Returns true if the timeout of the operation expired.
+    pub fn is_timed_out(&self) -> bool {
+        match self {
+            &Error::TimedOut => true,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &Error::TimedOut => write!(f, "timed out"),
+            &Error::Canceled => write!(f, "operation canceled"),
+            &Error::UnexpectedEof => write!(f, "unexpected eof"),
+            &Error::Io(ref err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Error {
+        match err.raw_os_error() {
+            Some(ETIMEDOUT) => Error::TimedOut,
+            Some(ECANCELED) => Error::Canceled,
+            Some(ECONNABORTED) => Error::UnexpectedEof,
+            _ => match err.kind() {
+                io::ErrorKind::TimedOut => Error::TimedOut,
+                io::ErrorKind::UnexpectedEof => Error::UnexpectedEof,
+                _ => Error::Io(err),
+            },
+        }
+    }
+}
+
+impl From<Error> for io::Error {
+    fn from(err: Error) -> io::Error {
+        match err {
+            Error::TimedOut => TIMED_OUT.into(),
+            Error::Canceled => OPERATION_CANCELED.into(),
+            Error::UnexpectedEof => io::Error::new(io::ErrorKind::UnexpectedEof, "unexpected eof"),
+            Error::Io(err) => err,
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(error::Error + 'static)> {
+        match self {
+            &Error::Io(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[test]
+fn test_error() {
+    assert!(Error::from(io::Error::from(TIMED_OUT)).is_timed_out());
+    assert!(Error::from(io::Error::new(io::ErrorKind::TimedOut, "")).is_timed_out());
+    match Error::from(io::Error::from(OPERATION_CANCELED)) {
+        Error::Canceled => (),
+        err => panic!("{:?}", err),
+    }
+    match Error::from(io::Error::new(io::ErrorKind::UnexpectedEof, "")) {
+        Error::UnexpectedEof => (),
+        err => panic!("{:?}", err),
+    }
+    match Error::from(io::Error::new(io::ErrorKind::Other, "")) {
+        Error::Io(_) => (),
+        err => panic!("{:?}", err),
+    }
+
+    let err: io::Error = Error::TimedOut.into();
+    assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+    let err: io::Error = Error::Canceled.into();
+    assert_eq!(err.raw_os_error(), io::Error::from(OPERATION_CANCELED).raw_os_error());
+}
@@ -0,0 +1,128 @@
+use std::fmt;
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// A map of the values keyed by the type, that attaches the user data to the socket.
+///
+/// Each socket owns the map, that is dropped with the socket.
+///
+/// # Examples
+///
+/// ```
+/// use asyncio::IoContext;
+/// use asyncio::ip::{IpProtocol, Tcp, TcpSocket};
+///
+/// #[derive(Clone, Debug, PartialEq)]
+/// struct User(String);
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let soc = TcpSocket::new(ctx, Tcp::v4()).unwrap();
+/// soc.extensions().insert(User("alice".to_owned()));
+/// assert_eq!(soc.extensions().get::<User>(), Some(User("alice".to_owned())));
+///
+/// soc.extensions().with(|user: &mut User| user.0.push('!'));
+/// assert_eq!(soc.extensions().remove::<User>(), Some(User("alice!".to_owned())));
+/// assert!(!soc.extensions().contains::<User>());
+/// ```
+#[derive(Default)]
+pub struct Extensions {
+    map: Mutex<HashMap<TypeId, Box<Any + Send + Sync>>>,
+}
+
+impl Extensions {
+    pub fn new() -> Self {
+        Extensions::default()
+    }
+
+    /// Removes all the values.
+    pub fn clear(&self) {
+        self.map.lock().unwrap().clear()
+    }
+
+    /// Returns true if the value of the type is attached.
+    pub fn contains<T>(&self) -> bool
+    where
+        T: Any + Send + Sync,
+    {
+        self.map.lock().unwrap().contains_key(&TypeId::of::<T>())
+    }
+
+    /// Returns a clone of the value of the type.
+    pub fn get<T>(&self) -> Option<T>
+    where
+        T: Any + Send + Sync + Clone,
+    {
+        self.map.lock().unwrap().get(&TypeId::of::<T>()).and_then(
+            |val| val.downcast_ref::<T>().cloned(),
+        )
+    }
+
+    /// Attaches the value, returns the previous value of the type if any.
+    pub fn insert<T>(&self, val: T) -> Option<T>
+    where
+        T: Any + Send + Sync,
+    {
+        self.map
+            .lock()
+            .unwrap()
+            .insert(TypeId::of::<T>(), Box::new(val))
+            .and_then(|val| val.downcast().ok().map(|val| *val))
+    }
+
+    /// Returns true if no values are attached.
+    pub fn is_empty(&self) -> bool {
+        self.map.lock().unwrap().is_empty()
+    }
+
+    /// Detaches the value of the type.
+    pub fn remove<T>(&self) -> Option<T>
+    where
+        T: Any + Send + Sync,
+    {
+        self.map.lock().unwrap().remove(&TypeId::of::<T>()).and_then(
+            |val| val.downcast().ok().map(|val| *val),
+        )
+    }
+
+    /// Calls the function with the value of the type, returns `None` if not attached.
+    ///
+    /// The map is locked during the call, that must not access the map.
+    pub fn with<T, F, R>(&self, func: F) -> Option<R>
+    where
+        T: Any + Send + Sync,
+        F: FnOnce(&mut T) -> R,
+    {
+        self.map
+            .lock()
+            .unwrap()
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|val| val.downcast_mut::<T>())
+            .map(func)
+    }
+}
+
+impl fmt::Debug for Extensions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Extensions({})", self.map.lock().unwrap().len())
+    }
+}
+
+#[test]
+fn test_extensions() {
+    let ext = Extensions::new();
+    assert!(ext.is_empty());
+    assert_eq!(ext.insert(1u32), None);
+    assert_eq!(ext.insert(2u32), Some(1));
+    assert_eq!(ext.insert("label"), None);
+    assert_eq!(ext.get::<u32>(), Some(2));
+    assert_eq!(ext.get::<&str>(), Some("label"));
+    assert_eq!(ext.get::<u64>(), None);
+
+    assert_eq!(ext.with(|val: &mut u32| { *val += 1; *val }), Some(3));
+    assert_eq!(ext.with(|val: &mut u64| *val), None);
+    assert_eq!(ext.remove::<u32>(), Some(3));
+    assert!(!ext.contains::<u32>());
+    ext.clear();
+    assert!(ext.is_empty());
+}
@@ -5,23 +5,31 @@ use std::io;
 use std::mem;
 use std::ptr;
 use std::fmt;
+use std::slice;
 use std::cell::Cell;
-use std::ffi::CStr;
+use std::ffi::{CStr, CString};
 use std::time::Duration;
 use errno::{errno, Errno};
 
-pub use std::os::unix::io::{AsRawFd, RawFd};
-pub use libc::{addrinfo, c_void, in_addr, ip_mreq, linger, sockaddr, sockaddr_in,
+pub use std::os::unix::io::{AsRawFd, IntoRawFd, RawFd};
+pub use libc::{c_void, in_addr, ip_mreq, linger, sockaddr, sockaddr_in,
                sockaddr_storage, sockaddr_un, socklen_t, AF_INET6, IPPROTO_IPV6,
                IPV6_MULTICAST_LOOP, IPV6_V6ONLY, in6_addr, ipv6_mreq, sockaddr_in6, AF_INET,
                AF_UNIX, FD_CLOEXEC, FD_SETSIZE, FIONBIO, F_GETFD, F_GETFL, F_SETFD, F_SETFL,
                IPPROTO_IP, IPPROTO_TCP, IP_ADD_MEMBERSHIP, IP_DROP_MEMBERSHIP, IP_MULTICAST_LOOP,
                IP_MULTICAST_TTL, IP_TTL, O_CLOEXEC, O_NONBLOCK, SOCK_DGRAM, SOCK_RAW,
                SOCK_SEQPACKET, SOCK_STREAM, SOL_SOCKET, SO_BROADCAST, SO_DEBUG, SO_DONTROUTE,
-               SO_ERROR, SO_KEEPALIVE, SO_LINGER, SO_RCVBUF, SO_RCVLOWAT, SO_REUSEADDR, SO_SNDBUF,
-               SO_SNDLOWAT, TCP_NODELAY, FIONREAD};
-#[cfg(target_os = "linux")]
-pub use libc::{SOCK_CLOEXEC, SOCK_NONBLOCK};
+               SO_ERROR, SO_KEEPALIVE, SO_LINGER, SO_RCVBUF, SO_RCVLOWAT, SO_REUSEADDR,
+               SO_REUSEPORT, SO_SNDBUF, SO_SNDLOWAT, SO_RCVTIMEO, SO_SNDTIMEO, TCP_NODELAY,
+               FIONREAD, MSG_PEEK, MSG_TRUNC, iovec, timeval};
+#[cfg(feature = "resolver")]
+pub use libc::addrinfo;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub use libc::{SOCK_CLOEXEC, SOCK_NONBLOCK, AF_PACKET, sockaddr_ll, IFNAMSIZ, SO_BINDTODEVICE,
+               SO_PEERCRED, MCAST_JOIN_SOURCE_GROUP, MCAST_LEAVE_SOURCE_GROUP, MCAST_BLOCK_SOURCE,
+               MCAST_UNBLOCK_SOURCE, group_source_req, sock_filter, sock_fprog, AF_NETLINK,
+               sockaddr_nl, SOL_NETLINK, NETLINK_ADD_MEMBERSHIP, NETLINK_DROP_MEMBERSHIP,
+               IFF_UP, IFF_BROADCAST, IFF_LOOPBACK, IFF_RUNNING, IFF_MULTICAST};
 
 pub const IPV6_UNICAST_HOPS: libc::c_int = 16;
 pub const IPV6_MULTICAST_IF: libc::c_int = 17;
@@ -30,19 +38,83 @@ pub const IP_MULTICAST_IF: libc::c_int = 32;
 pub const IPPROTO_ICMP: libc::c_int = 1;
 pub const IPPROTO_ICMPV6: libc::c_int = 58;
 pub const IPPROTO_UDP: libc::c_int = 17;
+#[cfg(feature = "resolver")]
 pub const AF_UNSPEC: libc::c_int = 0;
+#[cfg(feature = "resolver")]
 pub const AI_PASSIVE: libc::c_int = 0x0001;
+#[cfg(feature = "resolver")]
+pub const AI_CANONNAME: libc::c_int = 0x0002;
 #[allow(dead_code)]
 pub const AI_NUMERICHOST: libc::c_int = 0x0004;
+#[cfg(feature = "resolver")]
 pub const AI_NUMERICSERV: libc::c_int = 0x0400;
 
-#[cfg(target_os = "linux")]
+#[cfg(any(target_os = "linux", target_os = "android"))]
 pub const IPV6_JOIN_GROUP: libc::c_int = 20;
-#[cfg(target_os = "linux")]
+#[cfg(any(target_os = "linux", target_os = "android"))]
 pub const IPV6_LEAVE_GROUP: libc::c_int = 21;
 #[cfg(target_os = "macos")]
 pub use libc::{IPV6_JOIN_GROUP, IPV6_LEAVE_GROUP};
 
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub const SO_MAX_PACING_RATE: libc::c_int = 47;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub const SO_ATTACH_BPF: libc::c_int = 50;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub const SO_ATTACH_REUSEPORT_CBPF: libc::c_int = 51;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub const SO_ATTACH_REUSEPORT_EBPF: libc::c_int = 52;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub const SO_COOKIE: libc::c_int = 57;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub const SO_TXTIME: libc::c_int = 61;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub const SCM_TXTIME: libc::c_int = SO_TXTIME;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub const SOF_TXTIME_DEADLINE_MODE: u32 = 1 << 0;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub const SOF_TXTIME_REPORT_ERRORS: u32 = 1 << 1;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub use libc::{CLOCK_MONOTONIC, CLOCK_TAI};
+
+/// The classic BPF instructions `ld [k]`, `mod #k` and `ret a`.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub const BPF_LD_W_ABS: u16 = 0x20;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub const BPF_ALU_MOD_K: u16 = 0x94;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub const BPF_RET_A: u16 = 0x16;
+
+/// The offsets of the ancillary data loaded by the classic BPF.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub const SKF_AD_RXHASH: u32 = 0xfffff000 + 32;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub const SKF_AD_CPU: u32 = 0xfffff000 + 36;
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub use libc::{IP_TRANSPARENT, IP_ORIGDSTADDR, IP_RECVORIGDSTADDR, IPV6_TRANSPARENT,
+               IPV6_ORIGDSTADDR, IPV6_RECVORIGDSTADDR, SO_ORIGINAL_DST, IP6T_SO_ORIGINAL_DST,
+               TCP_CORK, TCP_QUICKACK, IP_RECVTTL, IPV6_RECVHOPLIMIT, IPV6_HOPLIMIT};
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub const SPLICE_F_MOVE: libc::c_int = 1;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub const SPLICE_F_NONBLOCK: libc::c_int = 2;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub const SOL_RAW: libc::c_int = 255;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub const ICMP_FILTER: libc::c_int = 1;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub const ICMP6_FILTER: libc::c_int = 1;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub const IP_TOS: libc::c_int = 1;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub const IPV6_TCLASS: libc::c_int = 67;
+#[cfg(target_os = "macos")]
+pub const IP_TOS: libc::c_int = 3;
+#[cfg(target_os = "macos")]
+pub const IPV6_TCLASS: libc::c_int = 36;
+
+#[cfg(feature = "signal")]
 /// A list specifying POSIX categories of signal.
 #[repr(i32)]
 #[derive(Clone, Copy, Eq, PartialEq, Debug)]
@@ -108,7 +180,7 @@ pub enum Signal {
     SIGBUS = libc::SIGBUS,
 
     /// Pollable event (Sys V). Synonym for SIGIO.
-    #[cfg(target_os = "linux")]
+    #[cfg(any(target_os = "linux", target_os = "android"))]
     SIGPOLL = libc::SIGPOLL,
 
     /// Profiling timer expired.
@@ -133,7 +205,8 @@ pub enum Signal {
     SIGXFSZ = libc::SIGXFSZ,
 }
 
-#[cfg(target_os = "linux")]
+#[cfg(feature = "signal")]
+#[cfg(any(target_os = "linux", target_os = "android"))]
 pub fn raise(sig: Signal) -> Result<(), SystemError> {
     match unsafe { libc::raise(sig as i32) } {
         -1 => Err(SystemError::last_error()),
@@ -141,6 +214,7 @@ pub fn raise(sig: Signal) -> Result<(), SystemError> {
     }
 }
 
+#[cfg(feature = "signal")]
 #[cfg(target_os = "macos")]
 pub fn raise(sig: Signal) -> Result<(), SystemError> {
     match unsafe { libc::kill(libc::getpid(), sig as i32) } {
@@ -184,12 +258,28 @@ impl fmt::Display for SystemError {
     }
 }
 
+impl From<io::Error> for SystemError {
+    fn from(err: io::Error) -> Self {
+        SystemError(Errno(err.raw_os_error().unwrap_or(libc::EIO)))
+    }
+}
+
 impl From<SystemError> for io::Error {
     fn from(err: SystemError) -> Self {
         io::Error::from_raw_os_error((err.0).0)
     }
 }
 
+/// Returns the result of the completion of the `io_uring`, that is the negated errno on failure.
+#[cfg(all(target_os = "linux", feature = "uring"))]
+pub fn uring_result(res: i32) -> Result<usize, SystemError> {
+    if res < 0 {
+        Err(SystemError(Errno(-res)))
+    } else {
+        Ok(res as usize)
+    }
+}
+
 // /// Permission denied.
 // pub const ACCESS_DENIED: SystemError = SystemError(Errno(libc::EACCES));
 
@@ -205,20 +295,20 @@ pub const ADDRESS_FAMILY_NOT_SUPPORTED: SystemError = SystemError(Errno(libc::EA
 // /// Operation already in progress.
 // pub const ALREADY_STARTED: SystemError = SystemError(Errno(libc::EALREADY));
 
-// /// Broken pipe.
-// pub const BROKEN_PIPE: SystemError = SystemError(Errno(libc::EPIPE));
+/// Broken pipe.
+pub const BROKEN_PIPE: SystemError = SystemError(Errno(libc::EPIPE));
 
 /// A connection has been aborted.
 pub const CONNECTION_ABORTED: SystemError = SystemError(Errno(libc::ECONNABORTED));
 
-// /// connection refused.
-// pub const CONNECTION_REFUSED: SystemError = SystemError(Errno(libc::ECONNREFUSED));
+/// connection refused.
+pub const CONNECTION_REFUSED: SystemError = SystemError(Errno(libc::ECONNREFUSED));
 
 // /// Connection reset by peer.
 // pub const CONNECTION_RESET: SystemError = SystemError(Errno(libc::ECONNRESET));
 
-// /// Bad file descriptor.
-// pub const BAD_DESCRIPTOR: SystemError = SystemError(Errno(libc::EBADF));
+/// Bad file descriptor.
+pub const BAD_DESCRIPTOR: SystemError = SystemError(Errno(libc::EBADF));
 
 // /// Bad address.
 // pub const FAULT: SystemError = SystemError(Errno(libc::EFAULT));
@@ -235,8 +325,8 @@ pub const INTERRUPTED: SystemError = SystemError(Errno(libc::EINTR));
 /// Invalid argument.
 pub const INVALID_ARGUMENT: SystemError = SystemError(Errno(libc::EINVAL));
 
-// /// Message to long.
-// pub const MESSAGE_SIZE: SystemError = SystemError(Errno(libc::EMSGSIZE));
+/// Message to long.
+pub const MESSAGE_SIZE: SystemError = SystemError(Errno(libc::EMSGSIZE));
 
 /// The name was too long.
 pub const NAME_TOO_LONG: SystemError = SystemError(Errno(libc::ENAMETOOLONG));
@@ -256,6 +346,10 @@ pub const NAME_TOO_LONG: SystemError = SystemError(Errno(libc::ENAMETOOLONG));
 /// No buffer space available.
 pub const NO_BUFFER_SPACE: SystemError = SystemError(Errno(libc::ENOBUFS));
 
+/// Function not implemented.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const NO_SYSCALL: SystemError = SystemError(Errno(libc::ENOSYS));
+
 // /// Cannot allocate memory.
 // pub const NO_MEMORY: SystemError = SystemError(Errno(libc::ENOMEM));
 
@@ -265,8 +359,8 @@ pub const NO_BUFFER_SPACE: SystemError = SystemError(Errno(libc::ENOBUFS));
 // /// Protocol not available.
 // pub const NO_PROTOCOL_OPTION: SystemError = SystemError(Errno(libc::ENOPROTOOPT));
 
-// /// No such device.
-// pub const NO_SUCH_DEVICE: SystemError = SystemError(Errno(libc::ENODEV));
+/// No such device.
+pub const NO_SUCH_DEVICE: SystemError = SystemError(Errno(libc::ENODEV));
 
 // /// Transport endpoint is not connected.
 // pub const NOT_CONNECTED: SystemError = SystemError(Errno(libc::ENOTCONN));
@@ -277,8 +371,8 @@ pub const NO_BUFFER_SPACE: SystemError = SystemError(Errno(libc::ENOBUFS));
 /// Operation cancelled.
 pub const OPERATION_CANCELED: SystemError = SystemError(Errno(libc::ECANCELED));
 
-// /// Operation not supported.
-// pub const OPERATION_NOT_SUPPORTED: SystemError = SystemError(Errno(libc::EOPNOTSUPP));
+/// Operation not supported.
+pub const OPERATION_NOT_SUPPORTED: SystemError = SystemError(Errno(libc::EOPNOTSUPP));
 
 // /// Cannot send after transport endpoint shutdown.
 // pub const SHUT_DOWN: SystemError = SystemError(Errno(libc::ESHUTDOWN));
@@ -317,6 +411,15 @@ impl From<AddrinfoError> for io::Error {
 pub const SERVICE_NOT_FOUND: AddrinfoError = AddrinfoError(EAI_SERVICE);
 const EAI_SERVICE: i32 = 9;
 
+/// The host is not found.
+pub const HOST_NOT_FOUND: AddrinfoError = AddrinfoError(libc::EAI_NONAME);
+
+/// The host is not found, but may be found with trying again later.
+pub const HOST_NOT_FOUND_TRY_AGAIN: AddrinfoError = AddrinfoError(libc::EAI_AGAIN);
+
+/// A non-recoverable error occurred during name resolution.
+pub const NO_RECOVERY: AddrinfoError = AddrinfoError(libc::EAI_FAIL);
+
 // /// The socket type is not supported.
 // pub const SOCKET_TYPE_NOT_SUPPORTED: AddrinfoError = AddrinfoError(EAI_SOCKTYPE);
 // const EAI_SOCKTYPE: i32 = 10;
@@ -334,6 +437,20 @@ pub enum Shutdown {
     Both = libc::SHUT_RDWR,
 }
 
+/// Possible values which can be passed to the wait method.
+#[repr(i16)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Wait {
+    /// Waits for the socket to become readable.
+    Read = libc::POLLIN,
+
+    /// Waits for the socket to become writable.
+    Write = libc::POLLOUT,
+
+    /// Waits for the socket to have the error condition pending.
+    Error = libc::POLLPRI,
+}
+
 pub struct Timeout {
     nano_sec: Cell<Duration>,
     milli_sec: Cell<i32>,
@@ -358,9 +475,10 @@ impl Timeout {
             Err(INVALID_ARGUMENT)
         } else {
             self.nano_sec.set(nano_sec);
+            // Rounds up, the timeout shorter than a millisecond does not busy-wait.
             self.milli_sec.set(
-                (nano_sec.as_secs() * 1000 / nano_sec.subsec_nanos() as u64 /
-                    1000000) as i32,
+                (nano_sec.as_secs() * 1000 +
+                    (nano_sec.subsec_nanos() as u64 + 999999) / 1000000) as i32,
             );
             Ok(())
         }
@@ -371,7 +489,23 @@ impl Timeout {
     }
 }
 
-#[cfg(target_os = "macos")]
+/// Sets the close-on-exec and the non-blocking flags to the adopted file descriptor.
+pub fn set_nonblocking(fd: RawFd) -> Result<(), SystemError> {
+    unsafe {
+        let flags = libc::fcntl(fd, F_GETFD);
+        if flags == -1 || libc::fcntl(fd, F_SETFD, flags | FD_CLOEXEC) == -1 {
+            return Err(SystemError::last_error());
+        }
+        let flags = libc::fcntl(fd, F_GETFL);
+        if flags == -1 || libc::fcntl(fd, F_SETFL, flags | O_NONBLOCK) == -1 {
+            return Err(SystemError::last_error());
+        }
+    }
+    Ok(())
+}
+
+/// Sets the `FD_CLOEXEC` and the `O_NONBLOCK` flags, in place of the `SOCK_CLOEXEC` and the
+/// `SOCK_NONBLOCK` flags which are not supported by the platform or the kernel.
 fn init_fd(fd: RawFd) {
     unsafe {
         // FD_CLOEXEC
@@ -406,7 +540,7 @@ where
     }
 }
 
-#[cfg(target_os = "linux")]
+#[cfg(any(target_os = "linux", target_os = "android"))]
 pub fn accept<P, S>(soc: &S) -> Result<(RawFd, P::Endpoint), SystemError>
 where
     P: Protocol,
@@ -422,6 +556,17 @@ where
             SOCK_NONBLOCK | SOCK_CLOEXEC,
         )
     } {
+        -1 if SystemError::last_error() == NO_SYSCALL => {
+            // The accept4 is not available, e.g. the old Android kernels.
+            match unsafe { libc::accept(soc.as_raw_fd(), sa.as_mut_ptr(), &mut salen) } {
+                -1 => Err(SystemError::last_error()),
+                fd => unsafe {
+                    init_fd(fd);
+                    sa.resize(salen);
+                    Ok((fd, sa))
+                },
+            }
+        }
         -1 => Err(SystemError::last_error()),
         fd => unsafe {
             sa.resize(salen);
@@ -441,6 +586,14 @@ where
     }
 }
 
+/// Closes the file descriptor, returns the error instead of panicking.
+pub fn try_close(fd: RawFd) -> Result<(), SystemError> {
+    match unsafe { libc::close(fd) } {
+        -1 => Err(SystemError::last_error()),
+        _ => Ok(()),
+    }
+}
+
 #[cfg(debug_assertions)]
 pub fn close(fd: RawFd) {
     if 0 != unsafe { libc::close(fd) } {
@@ -490,10 +643,12 @@ where
     }
 }
 
+#[cfg(feature = "resolver")]
 pub fn freeaddrinfo(ai: *mut addrinfo) {
     unsafe { libc::freeaddrinfo(ai) }
 }
 
+#[cfg(feature = "resolver")]
 pub fn getaddrinfo<P>(
     pro: &P,
     node: &CStr,
@@ -503,11 +658,31 @@ pub fn getaddrinfo<P>(
 where
     P: Protocol,
 {
+    getaddrinfo_hints(
+        pro.family_type(),
+        pro.socket_type(),
+        pro.protocol_type(),
+        node,
+        serv,
+        flags,
+    )
+}
+
+/// Resolves with the hints, the zero of the socket type and the protocol matches any.
+#[cfg(feature = "resolver")]
+pub fn getaddrinfo_hints(
+    family: i32,
+    socktype: i32,
+    protocol: i32,
+    node: &CStr,
+    serv: &CStr,
+    flags: i32,
+) -> Result<*mut addrinfo, AddrinfoError> {
     let mut hints: addrinfo = unsafe { mem::zeroed() };
     hints.ai_flags = flags;
-    hints.ai_family = pro.family_type();
-    hints.ai_socktype = pro.socket_type();
-    hints.ai_protocol = pro.protocol_type();
+    hints.ai_family = family;
+    hints.ai_socktype = socktype;
+    hints.ai_protocol = protocol;
 
     let node = if node.to_bytes().is_empty() {
         ptr::null()
@@ -528,6 +703,30 @@ where
     }
 }
 
+/// Returns the name of the service of the port, or the numeric port if not found.
+#[cfg(feature = "resolver")]
+pub fn getnameinfo_service(port: u16, dgram: bool) -> Result<String, AddrinfoError> {
+    let mut sin: sockaddr_in = unsafe { mem::zeroed() };
+    sin.sin_family = AF_INET as _;
+    sin.sin_port = port.to_be();
+    let mut serv: [libc::c_char; 32] = [0; 32];
+    let flags = if dgram { libc::NI_DGRAM } else { 0 };
+    match unsafe {
+        libc::getnameinfo(
+            &sin as *const _ as *const sockaddr,
+            mem::size_of_val(&sin) as socklen_t,
+            ptr::null_mut(),
+            0,
+            serv.as_mut_ptr(),
+            serv.len() as socklen_t,
+            flags,
+        )
+    } {
+        0 => Ok(unsafe { CStr::from_ptr(serv.as_ptr()) }.to_string_lossy().into_owned()),
+        ec => Err(AddrinfoError(ec)),
+    }
+}
+
 pub fn gethostname() -> Result<String, SystemError> {
     let mut name: [libc::c_char; 65] = unsafe { mem::uninitialized() };
     match unsafe { libc::gethostname(name.as_mut_ptr(), mem::size_of_val(&name)) } {
@@ -597,6 +796,39 @@ where
     }
 }
 
+pub unsafe fn raw_getsockopt<T>(soc: &T, level: i32, name: i32, buf: &mut [u8]) -> Result<usize, SystemError>
+where
+    T: AsRawFd,
+{
+    let mut buflen = buf.len() as socklen_t;
+    match libc::getsockopt(
+        soc.as_raw_fd(),
+        level,
+        name,
+        buf.as_mut_ptr() as *mut c_void,
+        &mut buflen,
+    ) {
+        -1 => Err(SystemError::last_error()),
+        _ => Ok(buflen as usize),
+    }
+}
+
+pub unsafe fn raw_setsockopt<T>(soc: &T, level: i32, name: i32, buf: &[u8]) -> Result<(), SystemError>
+where
+    T: AsRawFd,
+{
+    match libc::setsockopt(
+        soc.as_raw_fd(),
+        level,
+        name,
+        buf.as_ptr() as *const c_void,
+        buf.len() as socklen_t,
+    ) {
+        -1 => Err(SystemError::last_error()),
+        _ => Ok(()),
+    }
+}
+
 pub fn if_nametoindex(name: &CStr) -> Result<u32, SystemError> {
     match unsafe { libc::if_nametoindex(name.as_ptr()) } {
         0 => Err(SystemError::last_error()),
@@ -604,12 +836,139 @@ pub fn if_nametoindex(name: &CStr) -> Result<u32, SystemError> {
     }
 }
 
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn ifreq(name: &CStr) -> Result<libc::ifreq, SystemError> {
+    let name = name.to_bytes();
+    let mut ifr: libc::ifreq = unsafe { mem::zeroed() };
+    if name.len() >= ifr.ifr_name.len() {
+        return Err(NAME_TOO_LONG);
+    }
+    for (dst, src) in ifr.ifr_name.iter_mut().zip(name.iter()) {
+        *dst = *src as libc::c_char;
+    }
+    Ok(ifr)
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn if_hwaddr<S>(soc: &S, name: &CStr) -> Result<[u8; 6], SystemError>
+where
+    S: AsRawFd,
+{
+    let mut ifr = ifreq(name)?;
+    match unsafe { libc::ioctl(soc.as_raw_fd(), libc::SIOCGIFHWADDR as _, &mut ifr) } {
+        -1 => Err(SystemError::last_error()),
+        _ => {
+            let mut bytes = [0; 6];
+            let data = unsafe { &ifr.ifr_ifru.ifru_hwaddr.sa_data };
+            for (dst, src) in bytes.iter_mut().zip(data.iter()) {
+                *dst = *src as u8;
+            }
+            Ok(bytes)
+        }
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn if_inaddr<S>(soc: &S, name: &CStr) -> Result<[u8; 4], SystemError>
+where
+    S: AsRawFd,
+{
+    let mut ifr = ifreq(name)?;
+    match unsafe { libc::ioctl(soc.as_raw_fd(), libc::SIOCGIFADDR as _, &mut ifr) } {
+        -1 => Err(SystemError::last_error()),
+        _ => unsafe {
+            let sin = &*(&ifr.ifr_ifru.ifru_addr as *const _ as *const sockaddr_in);
+            Ok(mem::transmute(sin.sin_addr))
+        },
+    }
+}
+
+/// Returns the MTU of the interface.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn if_mtu<S>(soc: &S, name: &CStr) -> Result<u32, SystemError>
+where
+    S: AsRawFd,
+{
+    let mut ifr = ifreq(name)?;
+    match unsafe { libc::ioctl(soc.as_raw_fd(), libc::SIOCGIFMTU as _, &mut ifr) } {
+        -1 => Err(SystemError::last_error()),
+        _ => Ok(unsafe { ifr.ifr_ifru.ifru_mtu } as u32),
+    }
+}
+
+/// Calls the function with the name, the flags, the address, the netmask and the broadcast
+/// address of each entry of the `getifaddrs`, that skips the entries without the address.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn if_addrs<F>(mut func: F) -> Result<(), SystemError>
+where
+    F: FnMut(&CStr, u32, &sockaddr, Option<&sockaddr>, Option<&sockaddr>),
+{
+    let mut ifap: *mut libc::ifaddrs = ptr::null_mut();
+    if unsafe { libc::getifaddrs(&mut ifap) } == -1 {
+        return Err(SystemError::last_error());
+    }
+    let mut ifa = ifap;
+    while !ifa.is_null() {
+        let cur = unsafe { &*ifa };
+        if !cur.ifa_addr.is_null() {
+            unsafe {
+                func(
+                    CStr::from_ptr(cur.ifa_name),
+                    cur.ifa_flags,
+                    &*cur.ifa_addr,
+                    cur.ifa_netmask.as_ref(),
+                    cur.ifa_ifu.as_ref(),
+                )
+            }
+        }
+        ifa = cur.ifa_next;
+    }
+    unsafe { libc::freeifaddrs(ifap) };
+    Ok(())
+}
+
+/// Returns the name of the interface which has the address of the `family`.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn if_name_by_addr(family: i32, addr: &[u8]) -> Result<CString, SystemError> {
+    let mut ifap: *mut libc::ifaddrs = ptr::null_mut();
+    if unsafe { libc::getifaddrs(&mut ifap) } == -1 {
+        return Err(SystemError::last_error());
+    }
+    let mut res = Err(NO_SUCH_DEVICE);
+    let mut ifa = ifap;
+    while !ifa.is_null() {
+        let cur = unsafe { &*ifa };
+        if !cur.ifa_addr.is_null() && unsafe { &*cur.ifa_addr }.sa_family as i32 == family {
+            let bytes: &[u8] = unsafe {
+                match family {
+                    AF_INET => {
+                        let sin = &*(cur.ifa_addr as *const sockaddr_in);
+                        slice::from_raw_parts(&sin.sin_addr as *const _ as *const u8, 4)
+                    }
+                    AF_INET6 => {
+                        let sin6 = &*(cur.ifa_addr as *const sockaddr_in6);
+                        slice::from_raw_parts(&sin6.sin6_addr as *const _ as *const u8, 16)
+                    }
+                    _ => &[],
+                }
+            };
+            if bytes == addr {
+                res = Ok(unsafe { CStr::from_ptr(cur.ifa_name) }.to_owned());
+                break;
+            }
+        }
+        ifa = cur.ifa_next;
+    }
+    unsafe { libc::freeifaddrs(ifap) };
+    res
+}
+
 pub fn ioctl<S, D>(soc: &S, data: &mut D) -> Result<(), SystemError>
 where
     S: AsRawFd,
     D: IoControl,
 {
-    match unsafe { libc::ioctl(soc.as_raw_fd(), data.name(), data.as_mut_ptr()) } {
+    match unsafe { libc::ioctl(soc.as_raw_fd(), data.name() as _, data.as_mut_ptr()) } {
         -1 => Err(SystemError::last_error()),
         _ => Ok(()),
     }
@@ -626,6 +985,16 @@ where
     }
 }
 
+/// Returns the file descriptor referring to the process, that becomes readable when the process
+/// terminates.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn pidfd_open(pid: u32) -> Result<RawFd, SystemError> {
+    match unsafe { libc::syscall(libc::SYS_pidfd_open, pid as libc::pid_t, 0) } {
+        -1 => Err(SystemError::last_error()),
+        fd => Ok(fd as RawFd),
+    }
+}
+
 #[cfg(target_os = "macos")]
 pub fn pipe() -> Result<(RawFd, RawFd), SystemError> {
     let mut fds: [RawFd; 2] = unsafe { mem::uninitialized() };
@@ -639,16 +1008,119 @@ pub fn pipe() -> Result<(RawFd, RawFd), SystemError> {
     }
 }
 
-#[cfg(target_os = "linux")]
-#[allow(dead_code)]
+#[cfg(any(target_os = "linux", target_os = "android"))]
 pub fn pipe() -> Result<(RawFd, RawFd), SystemError> {
     let mut fds: [RawFd; 2] = unsafe { mem::uninitialized() };
     match unsafe { libc::pipe2(fds.as_mut_ptr(), O_CLOEXEC | O_NONBLOCK) } {
+        -1 if SystemError::last_error() == NO_SYSCALL => {
+            match unsafe { libc::pipe(fds.as_mut_ptr()) } {
+                -1 => Err(SystemError::last_error()),
+                _ => {
+                    init_fd(fds[0]);
+                    init_fd(fds[1]);
+                    Ok((fds[0], fds[1]))
+                }
+            }
+        }
         -1 => Err(SystemError::last_error()),
         _ => Ok((fds[0], fds[1])),
     }
 }
 
+/// Moves the data between the file descriptors, which one must be a pipe.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn splice<R, W>(fd_in: &R, fd_out: &W, len: usize, flags: i32) -> Result<usize, SystemError>
+where
+    R: AsRawFd,
+    W: AsRawFd,
+{
+    match unsafe {
+        libc::splice(
+            fd_in.as_raw_fd(),
+            ptr::null_mut(),
+            fd_out.as_raw_fd(),
+            ptr::null_mut(),
+            len,
+            (flags | SPLICE_F_NONBLOCK) as _,
+        )
+    } {
+        -1 => Err(SystemError::last_error()),
+        len => Ok(len as usize),
+    }
+}
+
+/// Sends up to `len` bytes of the file from the offset to the socket without copying to the
+/// userspace, that doesn't change the offset of the file.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn sendfile<S, T>(soc: &S, file: &T, offset: u64, len: usize) -> Result<usize, SystemError>
+where
+    S: AsRawFd,
+    T: AsRawFd,
+{
+    let mut offset = offset as libc::off_t;
+    match unsafe { libc::sendfile(soc.as_raw_fd(), file.as_raw_fd(), &mut offset, len) } {
+        -1 => Err(SystemError::last_error()),
+        len => Ok(len as usize),
+    }
+}
+
+/// Duplicates the data between the pipes without consuming.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn tee<R, W>(fd_in: &R, fd_out: &W, len: usize, flags: i32) -> Result<usize, SystemError>
+where
+    R: AsRawFd,
+    W: AsRawFd,
+{
+    match unsafe {
+        libc::tee(
+            fd_in.as_raw_fd(),
+            fd_out.as_raw_fd(),
+            len,
+            (flags | SPLICE_F_NONBLOCK) as _,
+        )
+    } {
+        -1 => Err(SystemError::last_error()),
+        len => Ok(len as usize),
+    }
+}
+
+pub fn pread<S>(soc: &S, buf: &mut [u8], offset: u64) -> Result<usize, SystemError>
+where
+    S: AsRawFd,
+{
+    debug_assert!(buf.len() > 0);
+    match unsafe {
+        libc::pread(
+            soc.as_raw_fd(),
+            buf.as_mut_ptr() as *mut _,
+            buf.len(),
+            offset as _,
+        )
+    } {
+        -1 => Err(SystemError::last_error()),
+        0 => Err(CONNECTION_ABORTED),
+        len => Ok(len as usize),
+    }
+}
+
+pub fn pwrite<S>(soc: &S, buf: &[u8], offset: u64) -> Result<usize, SystemError>
+where
+    S: AsRawFd,
+{
+    debug_assert!(buf.len() > 0);
+    match unsafe {
+        libc::pwrite(
+            soc.as_raw_fd(),
+            buf.as_ptr() as *const _,
+            buf.len(),
+            offset as _,
+        )
+    } {
+        -1 => Err(SystemError::last_error()),
+        len => Ok(len as usize),
+    }
+}
+
 pub fn read<S>(soc: &S, buf: &mut [u8]) -> Result<usize, SystemError>
 where
     S: AsRawFd,
@@ -661,6 +1133,18 @@ where
     }
 }
 
+pub fn readv<S>(soc: &S, iov: &[iovec]) -> Result<usize, SystemError>
+where
+    S: AsRawFd,
+{
+    debug_assert!(iov.iter().any(|iov| iov.iov_len > 0));
+    match unsafe { libc::readv(soc.as_raw_fd(), iov.as_ptr(), iov.len() as i32) } {
+        -1 => Err(SystemError::last_error()),
+        0 => Err(CONNECTION_ABORTED),
+        len => Ok(len as usize),
+    }
+}
+
 pub fn readable<S>(soc: &S, timeout: &Timeout) -> Result<(), SystemError>
 where
     S: AsRawFd,
@@ -705,8 +1189,22 @@ where
     P: Protocol,
     S: Socket<P>,
 {
-    debug_assert!(buf.len() > 0);
     let mut sa = unsafe { soc.protocol().uninitialized() };
+    let len = recvfrom_into(soc, buf, flags, &mut sa)?;
+    Ok((len, sa))
+}
+
+pub fn recvfrom_into<P, S>(
+    soc: &S,
+    buf: &mut [u8],
+    flags: i32,
+    sa: &mut P::Endpoint,
+) -> Result<usize, SystemError>
+where
+    P: Protocol,
+    S: Socket<P>,
+{
+    debug_assert!(buf.len() > 0);
     let mut salen = sa.capacity();
     match unsafe {
         libc::recvfrom(
@@ -722,11 +1220,324 @@ where
         0 => Err(CONNECTION_ABORTED),
         len => unsafe {
             sa.resize(salen);
-            Ok((len as usize, sa))
+            Ok(len as usize)
         },
     }
 }
 
+pub fn recvmsg<P, S>(
+    soc: &S,
+    buf: &mut [u8],
+    flags: i32,
+) -> Result<(usize, P::Endpoint, bool), SystemError>
+where
+    P: Protocol,
+    S: Socket<P>,
+{
+    debug_assert!(buf.len() > 0);
+    let mut sa = unsafe { soc.protocol().uninitialized() };
+    let mut iov = libc::iovec {
+        iov_base: buf.as_mut_ptr() as *mut _,
+        iov_len: buf.len(),
+    };
+    let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+    msg.msg_name = sa.as_mut_ptr() as *mut _;
+    msg.msg_namelen = sa.capacity();
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+    match unsafe { libc::recvmsg(soc.as_raw_fd(), &mut msg, flags) } {
+        -1 => Err(SystemError::last_error()),
+        0 => Err(CONNECTION_ABORTED),
+        len => unsafe {
+            sa.resize(msg.msg_namelen);
+            Ok((len as usize, sa, msg.msg_flags & MSG_TRUNC != 0))
+        },
+    }
+}
+
+/// Receives the data with the file descriptors of the SCM_RIGHTS ancillary data, up to `max_fds`.
+///
+/// The received file descriptors are set the close-on-exec flag.
+pub fn recvmsg_fds<P, S>(
+    soc: &S,
+    buf: &mut [u8],
+    flags: i32,
+    max_fds: usize,
+) -> Result<(usize, Vec<RawFd>), SystemError>
+where
+    P: Protocol,
+    S: Socket<P>,
+{
+    debug_assert!(buf.len() > 0);
+    let mut iov = libc::iovec {
+        iov_base: buf.as_mut_ptr() as *mut _,
+        iov_len: buf.len(),
+    };
+    let space = unsafe { libc::CMSG_SPACE((max_fds * mem::size_of::<RawFd>()) as _) } as usize;
+    let mut cmsg = vec![0u64; (space + 7) / 8];
+    let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+    if max_fds > 0 {
+        msg.msg_control = cmsg.as_mut_ptr() as *mut _;
+        msg.msg_controllen = space as _;
+    }
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    let flags = flags | libc::MSG_CMSG_CLOEXEC;
+    let len = match unsafe { libc::recvmsg(soc.as_raw_fd(), &mut msg, flags) } {
+        -1 => return Err(SystemError::last_error()),
+        0 => return Err(CONNECTION_ABORTED),
+        len => len as usize,
+    };
+    let mut fds = Vec::new();
+    unsafe {
+        let mut cm = libc::CMSG_FIRSTHDR(&msg);
+        while !cm.is_null() {
+            let hdr = &*cm;
+            if hdr.cmsg_level == SOL_SOCKET && hdr.cmsg_type == libc::SCM_RIGHTS {
+                let data = libc::CMSG_DATA(cm) as *const RawFd;
+                let n = (hdr.cmsg_len as usize - libc::CMSG_LEN(0) as usize) /
+                    mem::size_of::<RawFd>();
+                for i in 0..n {
+                    let fd = ptr::read_unaligned(data.offset(i as isize));
+                    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+                    libc::ioctl(fd, libc::FIOCLEX);
+                    fds.push(fd);
+                }
+            }
+            cm = libc::CMSG_NXTHDR(&msg, cm);
+        }
+    }
+    Ok((len, fds))
+}
+
+/// Receives a datagram with the original destination address of the IP_ORIGDSTADDR or
+/// IPV6_ORIGDSTADDR ancillary data.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn recvmsg_origdst<P, S>(
+    soc: &S,
+    buf: &mut [u8],
+    flags: i32,
+) -> Result<(usize, P::Endpoint, Option<P::Endpoint>), SystemError>
+where
+    P: Protocol,
+    S: Socket<P>,
+{
+    debug_assert!(buf.len() > 0);
+    let mut sa = unsafe { soc.protocol().uninitialized() };
+    let mut dst = unsafe { soc.protocol().uninitialized() };
+    let mut iov = libc::iovec {
+        iov_base: buf.as_mut_ptr() as *mut _,
+        iov_len: buf.len(),
+    };
+    let mut cmsg = [0u64; 16];
+    let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+    msg.msg_name = sa.as_mut_ptr() as *mut _;
+    msg.msg_namelen = sa.capacity();
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+    msg.msg_control = cmsg.as_mut_ptr() as *mut _;
+    msg.msg_controllen = mem::size_of_val(&cmsg) as _;
+    let len = match unsafe { libc::recvmsg(soc.as_raw_fd(), &mut msg, flags) } {
+        -1 => return Err(SystemError::last_error()),
+        0 => return Err(CONNECTION_ABORTED),
+        len => len as usize,
+    };
+    let mut found = false;
+    unsafe {
+        sa.resize(msg.msg_namelen);
+        let mut cm = libc::CMSG_FIRSTHDR(&msg);
+        while !cm.is_null() {
+            let hdr = &*cm;
+            if (hdr.cmsg_level == IPPROTO_IP && hdr.cmsg_type == IP_ORIGDSTADDR) ||
+                (hdr.cmsg_level == IPPROTO_IPV6 && hdr.cmsg_type == IPV6_ORIGDSTADDR)
+            {
+                let data_len = (hdr.cmsg_len as usize - libc::CMSG_LEN(0) as usize)
+                    .min(dst.capacity() as usize);
+                ptr::copy_nonoverlapping(
+                    libc::CMSG_DATA(cm),
+                    dst.as_mut_ptr() as *mut u8,
+                    data_len,
+                );
+                dst.resize(data_len as socklen_t);
+                found = true;
+            }
+            cm = libc::CMSG_NXTHDR(&msg, cm);
+        }
+    }
+    Ok((len, sa, if found { Some(dst) } else { None }))
+}
+
+/// Receives a datagram with the hop limit of the IP_TTL or IPV6_HOPLIMIT ancillary data.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn recvmsg_hoplimit<P, S>(
+    soc: &S,
+    buf: &mut [u8],
+    flags: i32,
+) -> Result<(usize, P::Endpoint, Option<u8>), SystemError>
+where
+    P: Protocol,
+    S: Socket<P>,
+{
+    debug_assert!(buf.len() > 0);
+    let mut sa = unsafe { soc.protocol().uninitialized() };
+    let mut iov = libc::iovec {
+        iov_base: buf.as_mut_ptr() as *mut _,
+        iov_len: buf.len(),
+    };
+    let mut cmsg = [0u64; 16];
+    let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+    msg.msg_name = sa.as_mut_ptr() as *mut _;
+    msg.msg_namelen = sa.capacity();
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+    msg.msg_control = cmsg.as_mut_ptr() as *mut _;
+    msg.msg_controllen = mem::size_of_val(&cmsg) as _;
+    let len = match unsafe { libc::recvmsg(soc.as_raw_fd(), &mut msg, flags) } {
+        -1 => return Err(SystemError::last_error()),
+        0 => return Err(CONNECTION_ABORTED),
+        len => len as usize,
+    };
+    let mut hops = None;
+    unsafe {
+        sa.resize(msg.msg_namelen);
+        let mut cm = libc::CMSG_FIRSTHDR(&msg);
+        while !cm.is_null() {
+            let hdr = &*cm;
+            if (hdr.cmsg_level == IPPROTO_IP && hdr.cmsg_type == IP_TTL) ||
+                (hdr.cmsg_level == IPPROTO_IPV6 && hdr.cmsg_type == IPV6_HOPLIMIT)
+            {
+                let val = ptr::read_unaligned(libc::CMSG_DATA(cm) as *const libc::c_int);
+                hops = Some(val as u8);
+            }
+            cm = libc::CMSG_NXTHDR(&msg, cm);
+        }
+    }
+    Ok((len, sa, hops))
+}
+
+/// Sends a datagram with the hop limit of the IP_TTL or IPV6_HOPLIMIT ancillary data.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn sendmsg_hoplimit<P, S>(
+    soc: &S,
+    buf: &[u8],
+    flags: i32,
+    sa: &P::Endpoint,
+    hops: u8,
+) -> Result<usize, SystemError>
+where
+    P: Protocol,
+    S: Socket<P>,
+{
+    debug_assert!(buf.len() > 0);
+    let mut iov = libc::iovec {
+        iov_base: buf.as_ptr() as *mut _,
+        iov_len: buf.len(),
+    };
+    let mut cmsg = [0u64; 4];
+    let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+    msg.msg_name = sa.as_ptr() as *mut _;
+    msg.msg_namelen = sa.size();
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+    msg.msg_control = cmsg.as_mut_ptr() as *mut _;
+    msg.msg_controllen = unsafe { libc::CMSG_SPACE(mem::size_of::<libc::c_int>() as _) } as _;
+    unsafe {
+        let cm = &mut *libc::CMSG_FIRSTHDR(&msg);
+        if soc.protocol().family_type() == AF_INET6 {
+            cm.cmsg_level = IPPROTO_IPV6;
+            cm.cmsg_type = IPV6_HOPLIMIT;
+        } else {
+            cm.cmsg_level = IPPROTO_IP;
+            cm.cmsg_type = IP_TTL;
+        }
+        cm.cmsg_len = libc::CMSG_LEN(mem::size_of::<libc::c_int>() as _) as _;
+        ptr::write_unaligned(libc::CMSG_DATA(cm) as *mut libc::c_int, hops as libc::c_int);
+    }
+    match unsafe { libc::sendmsg(soc.as_raw_fd(), &msg, flags) } {
+        -1 => Err(SystemError::last_error()),
+        0 => Err(CONNECTION_ABORTED),
+        len => Ok(len as usize),
+    }
+}
+
+/// Sends the datagram with the transmission time of the SCM_TXTIME ancillary data, in
+/// nanoseconds of the clock set by the SO_TXTIME option.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn sendmsg_txtime<P, S>(
+    soc: &S,
+    buf: &[u8],
+    flags: i32,
+    sa: &P::Endpoint,
+    txtime: u64,
+) -> Result<usize, SystemError>
+where
+    P: Protocol,
+    S: Socket<P>,
+{
+    debug_assert!(buf.len() > 0);
+    let mut iov = libc::iovec {
+        iov_base: buf.as_ptr() as *mut _,
+        iov_len: buf.len(),
+    };
+    let mut cmsg = [0u64; 4];
+    let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+    msg.msg_name = sa.as_ptr() as *mut _;
+    msg.msg_namelen = sa.size();
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+    msg.msg_control = cmsg.as_mut_ptr() as *mut _;
+    msg.msg_controllen = unsafe { libc::CMSG_SPACE(mem::size_of::<u64>() as _) } as _;
+    unsafe {
+        let cm = &mut *libc::CMSG_FIRSTHDR(&msg);
+        cm.cmsg_level = SOL_SOCKET;
+        cm.cmsg_type = SCM_TXTIME;
+        cm.cmsg_len = libc::CMSG_LEN(mem::size_of::<u64>() as _) as _;
+        ptr::write_unaligned(libc::CMSG_DATA(cm) as *mut u64, txtime);
+    }
+    match unsafe { libc::sendmsg(soc.as_raw_fd(), &msg, flags) } {
+        -1 => Err(SystemError::last_error()),
+        0 => Err(CONNECTION_ABORTED),
+        len => Ok(len as usize),
+    }
+}
+
+/// Sends the data with the file descriptors of the SCM_RIGHTS ancillary data.
+pub fn sendmsg_fds<P, S>(soc: &S, buf: &[u8], flags: i32, fds: &[RawFd]) -> Result<usize, SystemError>
+where
+    P: Protocol,
+    S: Socket<P>,
+{
+    debug_assert!(buf.len() > 0);
+    let mut iov = libc::iovec {
+        iov_base: buf.as_ptr() as *mut _,
+        iov_len: buf.len(),
+    };
+    let space = unsafe { libc::CMSG_SPACE((fds.len() * mem::size_of::<RawFd>()) as _) } as usize;
+    let mut cmsg = vec![0u64; (space + 7) / 8];
+    let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+    if !fds.is_empty() {
+        msg.msg_control = cmsg.as_mut_ptr() as *mut _;
+        msg.msg_controllen = space as _;
+        unsafe {
+            let cm = &mut *libc::CMSG_FIRSTHDR(&msg);
+            cm.cmsg_level = SOL_SOCKET;
+            cm.cmsg_type = libc::SCM_RIGHTS;
+            cm.cmsg_len = libc::CMSG_LEN((fds.len() * mem::size_of::<RawFd>()) as _) as _;
+            let data = libc::CMSG_DATA(cm) as *mut RawFd;
+            for (i, fd) in fds.iter().enumerate() {
+                ptr::write_unaligned(data.offset(i as isize), *fd);
+            }
+        }
+    }
+    match unsafe { libc::sendmsg(soc.as_raw_fd(), &msg, flags) } {
+        -1 => Err(SystemError::last_error()),
+        len => Ok(len as usize),
+    }
+}
+
 pub fn setsockopt<P, S, D>(soc: &S, data: D) -> Result<(), SystemError>
 where
     P: Protocol,
@@ -826,7 +1637,7 @@ where
     }
 }
 
-#[cfg(target_os = "linux")]
+#[cfg(any(target_os = "linux", target_os = "android"))]
 pub fn socket<P>(pro: &P) -> Result<RawFd, SystemError>
 where
     P: Protocol,
@@ -838,6 +1649,18 @@ where
             pro.protocol_type(),
         )
     } {
+        -1 if SystemError::last_error() == INVALID_ARGUMENT => {
+            // The kernels older than 2.6.27 do not accept the flags in the socket type.
+            match unsafe {
+                libc::socket(pro.family_type(), pro.socket_type(), pro.protocol_type())
+            } {
+                -1 => Err(SystemError::last_error()),
+                fd => {
+                    init_fd(fd);
+                    Ok(fd)
+                }
+            }
+        }
         -1 => Err(SystemError::last_error()),
         fd => Ok(fd),
     }
@@ -866,7 +1689,7 @@ where
     }
 }
 
-#[cfg(target_os = "linux")]
+#[cfg(any(target_os = "linux", target_os = "android"))]
 pub fn socketpair<P>(pro: &P) -> Result<(RawFd, RawFd), SystemError>
 where
     P: Protocol,
@@ -880,6 +1703,23 @@ where
             fds.as_mut_ptr(),
         )
     } {
+        -1 if SystemError::last_error() == INVALID_ARGUMENT => {
+            match unsafe {
+                libc::socketpair(
+                    pro.family_type(),
+                    pro.socket_type(),
+                    pro.protocol_type(),
+                    fds.as_mut_ptr(),
+                )
+            } {
+                -1 => Err(SystemError::last_error()),
+                _ => {
+                    init_fd(fds[0]);
+                    init_fd(fds[1]);
+                    Ok((fds[0], fds[1]))
+                }
+            }
+        }
         -1 => Err(SystemError::last_error()),
         _ => Ok((fds[0], fds[1])),
     }
@@ -896,6 +1736,52 @@ where
     }
 }
 
+pub fn writev<S>(soc: &S, iov: &[iovec]) -> Result<usize, SystemError>
+where
+    S: AsRawFd,
+{
+    debug_assert!(iov.iter().any(|iov| iov.iov_len > 0));
+    match unsafe { libc::writev(soc.as_raw_fd(), iov.as_ptr(), iov.len() as i32) } {
+        -1 => Err(SystemError::last_error()),
+        len => Ok(len as usize),
+    }
+}
+
+/// Waits for the socket to become ready in `timeout` milliseconds, or forever if it is negative.
+pub fn wait<S>(soc: &S, w: Wait, timeout: i32) -> Result<(), SystemError>
+where
+    S: AsRawFd,
+{
+    let mut pfd = libc::pollfd {
+        fd: soc.as_raw_fd(),
+        events: w as i16,
+        revents: 0,
+    };
+    loop {
+        match unsafe { libc::poll(&mut pfd, 1, timeout) } {
+            0 => return Err(TIMED_OUT),
+            -1 => {
+                match SystemError::last_error() {
+                    INTERRUPTED => (),
+                    err => return Err(err),
+                }
+            }
+            _ => return Ok(()),
+        }
+    }
+}
+
+/// Waits for the socket to become ready until the timeout.
+pub fn wait_for<S>(soc: &S, w: Wait, timeout: Duration) -> Result<(), SystemError>
+where
+    S: AsRawFd,
+{
+    // Rounds up to wait at least the timeout.
+    let msec = timeout.as_secs().saturating_mul(1000) +
+        ((timeout.subsec_nanos() + 999_999) / 1_000_000) as u64;
+    wait(soc, w, msec.min(i32::max_value() as u64) as i32)
+}
+
 pub fn writable<S>(soc: &S, timeout: &Timeout) -> Result<(), SystemError>
 where
     S: AsRawFd,
@@ -910,3 +1796,17 @@ where
         _ => Ok(()),
     }
 }
+
+#[test]
+fn test_timeout_set() {
+    let timeout = Timeout::max();
+    timeout.set(Duration::new(1, 0)).unwrap();
+    assert_eq!(timeout.milliseconds(), 1000);
+    timeout.set(Duration::from_millis(1500)).unwrap();
+    assert_eq!(timeout.milliseconds(), 1500);
+    timeout.set(Duration::new(0, 1)).unwrap();
+    assert_eq!(timeout.milliseconds(), 1);
+    timeout.set(Duration::new(0, 0)).unwrap();
+    assert_eq!(timeout.milliseconds(), 0);
+    assert!(timeout.set(Duration::new(TIMEOUT_MAX, 0)).is_err());
+}
@@ -9,6 +9,10 @@ impl PodTrait for libc::sockaddr_in6 {}
 impl PodTrait for libc::sockaddr_storage {}
 #[cfg(unix)]
 impl PodTrait for libc::sockaddr_un {}
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl PodTrait for libc::sockaddr_ll {}
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl PodTrait for libc::sockaddr_nl {}
 
 #[cfg(target_os = "macos")]
 mod bsd;
@@ -0,0 +1,106 @@
+use ffi::{RawFd, SystemError, WOULD_BLOCK};
+use super::{FileEvent, WatchDescriptor, WatchMask, MODIFY, ATTRIB, DELETE, RENAME};
+
+use std::mem;
+use std::ptr;
+use std::ffi::{CStr, OsString};
+use std::os::unix::ffi::OsStringExt;
+use libc::{self, inotify_event, IN_CLOEXEC, IN_NONBLOCK, IN_MODIFY, IN_ATTRIB, IN_CREATE,
+           IN_DELETE, IN_DELETE_SELF, IN_MOVED_FROM, IN_MOVED_TO, IN_MOVE_SELF};
+
+const MAX_EVENTS_LEN: usize = 4096;
+
+fn to_inotify(mask: WatchMask) -> u32 {
+    let mut bits = 0;
+    if mask.0 & MODIFY != 0 {
+        bits |= IN_MODIFY | IN_CREATE | IN_DELETE | IN_MOVED_FROM | IN_MOVED_TO;
+    }
+    if mask.0 & ATTRIB != 0 {
+        bits |= IN_ATTRIB;
+    }
+    if mask.0 & DELETE != 0 {
+        bits |= IN_DELETE_SELF;
+    }
+    if mask.0 & RENAME != 0 {
+        bits |= IN_MOVE_SELF;
+    }
+    bits
+}
+
+fn from_inotify(bits: u32) -> WatchMask {
+    let mut mask = 0;
+    if bits & (IN_MODIFY | IN_CREATE | IN_DELETE | IN_MOVED_FROM | IN_MOVED_TO) != 0 {
+        mask |= MODIFY;
+    }
+    if bits & IN_ATTRIB != 0 {
+        mask |= ATTRIB;
+    }
+    if bits & IN_DELETE_SELF != 0 {
+        mask |= DELETE;
+    }
+    if bits & IN_MOVE_SELF != 0 {
+        mask |= RENAME;
+    }
+    WatchMask(mask)
+}
+
+pub fn watcher() -> Result<RawFd, SystemError> {
+    match unsafe { libc::inotify_init1(IN_CLOEXEC | IN_NONBLOCK) } {
+        -1 => Err(SystemError::last_error()),
+        fd => Ok(fd),
+    }
+}
+
+pub fn add_watch(fd: RawFd, path: &CStr, mask: WatchMask) -> Result<i32, SystemError> {
+    match unsafe { libc::inotify_add_watch(fd, path.as_ptr(), to_inotify(mask)) } {
+        -1 => Err(SystemError::last_error()),
+        wd => Ok(wd),
+    }
+}
+
+pub fn remove_watch(fd: RawFd, wd: i32) -> Result<(), SystemError> {
+    match unsafe { libc::inotify_rm_watch(fd, wd) } {
+        -1 => Err(SystemError::last_error()),
+        _ => Ok(()),
+    }
+}
+
+pub fn read_events(fd: RawFd) -> Result<Vec<FileEvent>, SystemError> {
+    let mut buf = [0u8; MAX_EVENTS_LEN];
+    let len = match unsafe { libc::read(fd, buf.as_mut_ptr() as *mut _, buf.len()) } {
+        -1 => return Err(SystemError::last_error()),
+        0 => return Err(WOULD_BLOCK),
+        len => len as usize,
+    };
+
+    let mut events = Vec::new();
+    let mut pos = 0;
+    while pos + mem::size_of::<inotify_event>() <= len {
+        let ev: inotify_event = unsafe { ptr::read_unaligned(buf[pos..].as_ptr() as *const _) };
+        pos += mem::size_of::<inotify_event>();
+        let name = &buf[pos..pos + ev.len as usize];
+        pos += ev.len as usize;
+
+        // Skips the events other than watched, e.g. the IN_IGNORED and the IN_Q_OVERFLOW.
+        let mask = from_inotify(ev.mask);
+        if mask.is_empty() {
+            continue;
+        }
+        let name = match name.iter().position(|&ch| ch == 0) {
+            Some(0) => None,
+            Some(end) => Some(OsString::from_vec(name[..end].to_vec())),
+            None if name.is_empty() => None,
+            None => Some(OsString::from_vec(name.to_vec())),
+        };
+        events.push(FileEvent {
+            wd: WatchDescriptor(ev.wd),
+            mask: mask,
+            name: name,
+        });
+    }
+    if events.is_empty() {
+        Err(WOULD_BLOCK)
+    } else {
+        Ok(events)
+    }
+}
@@ -0,0 +1,126 @@
+use ffi::{RawFd, SystemError, WOULD_BLOCK};
+use super::{FileEvent, WatchDescriptor, WatchMask, MODIFY, ATTRIB, DELETE, RENAME};
+
+use std::ptr;
+use std::ffi::CStr;
+use libc::{self, EV_ADD, EV_CLEAR, EV_DELETE, EVFILT_VNODE, NOTE_ATTRIB, NOTE_DELETE,
+           NOTE_EXTEND, NOTE_RENAME, NOTE_WRITE, O_CLOEXEC, O_EVTONLY};
+
+const MAX_EVENTS: usize = 64;
+
+fn to_vnode(mask: WatchMask) -> u32 {
+    let mut bits = 0;
+    if mask.0 & MODIFY != 0 {
+        bits |= NOTE_WRITE | NOTE_EXTEND;
+    }
+    if mask.0 & ATTRIB != 0 {
+        bits |= NOTE_ATTRIB;
+    }
+    if mask.0 & DELETE != 0 {
+        bits |= NOTE_DELETE;
+    }
+    if mask.0 & RENAME != 0 {
+        bits |= NOTE_RENAME;
+    }
+    bits
+}
+
+fn from_vnode(bits: u32) -> WatchMask {
+    let mut mask = 0;
+    if bits & (NOTE_WRITE | NOTE_EXTEND) != 0 {
+        mask |= MODIFY;
+    }
+    if bits & NOTE_ATTRIB != 0 {
+        mask |= ATTRIB;
+    }
+    if bits & NOTE_DELETE != 0 {
+        mask |= DELETE;
+    }
+    if bits & NOTE_RENAME != 0 {
+        mask |= RENAME;
+    }
+    WatchMask(mask)
+}
+
+fn ev_set(ident: RawFd, flags: u16, fflags: u32) -> libc::kevent {
+    libc::kevent {
+        ident: ident as usize,
+        filter: EVFILT_VNODE,
+        flags: flags,
+        fflags: fflags,
+        data: 0,
+        udata: ptr::null_mut(),
+    }
+}
+
+fn kevent(kq: RawFd, kev: &libc::kevent) -> Result<(), SystemError> {
+    match unsafe { libc::kevent(kq, kev, 1, ptr::null_mut(), 0, ptr::null()) } {
+        -1 => Err(SystemError::last_error()),
+        _ => Ok(()),
+    }
+}
+
+/// Returns the kqueue, that is readable by the reactor when the events are notified.
+pub fn watcher() -> Result<RawFd, SystemError> {
+    match unsafe { libc::kqueue() } {
+        -1 => Err(SystemError::last_error()),
+        kq => {
+            unsafe { libc::fcntl(kq, libc::F_SETFD, libc::FD_CLOEXEC) };
+            Ok(kq)
+        }
+    }
+}
+
+/// Opens the file only for the events, and returns the descriptor as the watch descriptor.
+pub fn add_watch(kq: RawFd, path: &CStr, mask: WatchMask) -> Result<i32, SystemError> {
+    let fd = match unsafe { libc::open(path.as_ptr(), O_EVTONLY | O_CLOEXEC) } {
+        -1 => return Err(SystemError::last_error()),
+        fd => fd,
+    };
+    match kevent(kq, &ev_set(fd, EV_ADD | EV_CLEAR, to_vnode(mask))) {
+        Ok(_) => Ok(fd),
+        Err(err) => {
+            unsafe { libc::close(fd) };
+            Err(err)
+        }
+    }
+}
+
+pub fn remove_watch(kq: RawFd, wd: i32) -> Result<(), SystemError> {
+    let res = kevent(kq, &ev_set(wd, EV_DELETE, 0));
+    unsafe { libc::close(wd) };
+    res
+}
+
+pub fn read_events(kq: RawFd) -> Result<Vec<FileEvent>, SystemError> {
+    let mut evs: [libc::kevent; MAX_EVENTS] = unsafe { ::std::mem::zeroed() };
+    let tv = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    match unsafe {
+        libc::kevent(
+            kq,
+            ptr::null(),
+            0,
+            evs.as_mut_ptr(),
+            evs.len() as i32,
+            &tv,
+        )
+    } {
+        -1 => Err(SystemError::last_error()),
+        0 => Err(WOULD_BLOCK),
+        len => Ok(
+            evs[..len as usize]
+                .iter()
+                .map(|ev| {
+                    FileEvent {
+                        wd: WatchDescriptor(ev.ident as i32),
+                        mask: from_vnode(ev.fflags),
+                        name: None,
+                    }
+                })
+                .collect(),
+        ),
+    }
+}
@@ -0,0 +1,309 @@
+use ffi::{AsRawFd, RawFd, SystemError};
+use reactor::SocketImpl;
+use core::{AsIoContext, IoContext, ThreadIoContext, Perform, Cancel};
+use handler::{Handler, AsyncReadOp};
+use read_ops::{Reader, async_read_op, blocking_read_op, nonblocking_read_op};
+
+use std::io;
+use std::fmt;
+use std::ffi::{CString, OsString};
+use std::ops::BitOr;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod linux;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use self::linux::{watcher, add_watch, remove_watch, read_events};
+
+#[cfg(target_os = "macos")]
+mod macos;
+#[cfg(target_os = "macos")]
+use self::macos::{watcher, add_watch, remove_watch, read_events};
+
+const MODIFY: u32 = 0x01;
+const ATTRIB: u32 = 0x02;
+const DELETE: u32 = 0x04;
+const RENAME: u32 = 0x08;
+
+/// The kinds of the file events to be watched, that are combined by the `|` operator.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct WatchMask(u32);
+
+impl WatchMask {
+    /// Returns all kinds of the file events.
+    pub fn all() -> WatchMask {
+        WatchMask(MODIFY | ATTRIB | DELETE | RENAME)
+    }
+
+    /// Returns the kind that the attributes (e.g. the permissions) of the file are changed.
+    pub fn attrib() -> WatchMask {
+        WatchMask(ATTRIB)
+    }
+
+    /// Returns true if this contains all kinds of the other.
+    pub fn contains(&self, other: WatchMask) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Returns the kind that the watched file itself is deleted.
+    pub fn delete() -> WatchMask {
+        WatchMask(DELETE)
+    }
+
+    /// Returns true if this contains no kinds.
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns the kind that the file is written, or the entries of the directory are changed.
+    pub fn modify() -> WatchMask {
+        WatchMask(MODIFY)
+    }
+
+    /// Returns the kind that the watched file itself is renamed or moved.
+    pub fn rename() -> WatchMask {
+        WatchMask(RENAME)
+    }
+}
+
+impl BitOr for WatchMask {
+    type Output = WatchMask;
+
+    fn bitor(self, rhs: WatchMask) -> WatchMask {
+        WatchMask(self.0 | rhs.0)
+    }
+}
+
+impl fmt::Debug for WatchMask {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("WatchMask")
+            .field("modify", &self.contains(WatchMask::modify()))
+            .field("attrib", &self.contains(WatchMask::attrib()))
+            .field("delete", &self.contains(WatchMask::delete()))
+            .field("rename", &self.contains(WatchMask::rename()))
+            .finish()
+    }
+}
+
+/// The identifier of the watched file returned by the `add_watch`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct WatchDescriptor(i32);
+
+/// The file event notified by the `FileWatcher`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileEvent {
+    wd: WatchDescriptor,
+    mask: WatchMask,
+    name: Option<OsString>,
+}
+
+impl FileEvent {
+    /// Returns the kinds of the event.
+    pub fn mask(&self) -> WatchMask {
+        self.mask
+    }
+
+    /// Returns the name of the entry of the watched directory, that is available on Linux only.
+    pub fn name(&self) -> Option<&OsString> {
+        self.name.as_ref()
+    }
+
+    /// Returns the watch descriptor of the watched file.
+    pub fn wd(&self) -> WatchDescriptor {
+        self.wd
+    }
+}
+
+struct ReadEvents;
+
+impl Reader for ReadEvents {
+    type Socket = FileWatcher;
+
+    type Output = Vec<FileEvent>;
+
+    fn read_op(&self, s: &Self::Socket, _: &mut [u8]) -> Result<Self::Output, SystemError> {
+        read_events(s.as_raw_fd())
+    }
+}
+
+/// Provides the notifications of the changes of the files, by the inotify on Linux or the
+/// kqueue `EVFILT_VNODE` on macOS.
+///
+/// # Examples
+///
+/// ```
+/// use std::io;
+/// use std::env;
+/// use std::fs::File;
+/// use std::sync::Arc;
+/// use asyncio::{IoContext, FileWatcher, FileEvent, WatchMask, wrap};
+///
+/// fn on_event(_: Arc<FileWatcher>, res: io::Result<Vec<FileEvent>>) {
+///   let events = res.unwrap();
+///   assert!(events[0].mask().contains(WatchMask::modify()));
+/// }
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let path = env::temp_dir().join("asyncio_doc_file_watcher");
+/// File::create(&path).unwrap();
+///
+/// let watcher = Arc::new(FileWatcher::new(ctx).unwrap());
+/// watcher.add_watch(&path, WatchMask::modify()).unwrap();
+/// watcher.async_wait(wrap(&watcher, on_event));
+/// File::create(&path).unwrap().set_len(16).unwrap();
+/// ctx.run();
+/// ```
+pub struct FileWatcher {
+    pimpl: Box<SocketImpl<()>>,
+    watches: Mutex<Vec<WatchDescriptor>>,
+}
+
+impl FileWatcher {
+    pub fn new(ctx: &IoContext) -> io::Result<Self> {
+        let fd = watcher()?;
+        Ok(FileWatcher {
+            pimpl: SocketImpl::new(ctx, fd, ()),
+            watches: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// Starts watching the file or the directory for the kinds of the events.
+    pub fn add_watch<P>(&self, path: P, mask: WatchMask) -> io::Result<WatchDescriptor>
+    where
+        P: AsRef<Path>,
+    {
+        let path = CString::new(path.as_ref().as_os_str().as_bytes()).map_err(|_| {
+            io::Error::new(io::ErrorKind::InvalidInput, "path contains a NUL character")
+        })?;
+        let mut watches = self.watches.lock().unwrap();
+        let wd = WatchDescriptor(add_watch(self.as_raw_fd(), &path, mask)?);
+        if !watches.contains(&wd) {
+            watches.push(wd);
+        }
+        Ok(wd)
+    }
+
+    /// Asynchronously waits until the events of the watched files are notified.
+    pub fn async_wait<F>(&self, handler: F) -> F::Output
+    where
+        F: Handler<Vec<FileEvent>, io::Error>,
+    {
+        async_read_op(self, &[], &self.pimpl.timeout, handler, ReadEvents)
+    }
+
+    pub fn get_timeout(&self) -> Duration {
+        self.pimpl.timeout.get()
+    }
+
+    /// Takes the notified events, returns the `EAGAIN` error if none is notified.
+    pub fn nonblocking_wait(&self) -> io::Result<Vec<FileEvent>> {
+        nonblocking_read_op(self, &mut [], ReadEvents)
+    }
+
+    /// Stops watching the file.
+    pub fn remove_watch(&self, wd: WatchDescriptor) -> io::Result<()> {
+        let mut watches = self.watches.lock().unwrap();
+        watches.retain(|&it| it != wd);
+        Ok(remove_watch(self.as_raw_fd(), wd.0)?)
+    }
+
+    pub fn set_timeout(&self, timeout: Duration) -> io::Result<()> {
+        Ok(self.pimpl.timeout.set(timeout)?)
+    }
+
+    /// Waits until the events of the watched files are notified.
+    pub fn wait(&self) -> io::Result<Vec<FileEvent>> {
+        blocking_read_op(self, &mut [], &self.pimpl.timeout, ReadEvents)
+    }
+}
+
+impl Drop for FileWatcher {
+    fn drop(&mut self) {
+        let fd = self.as_raw_fd();
+        for wd in self.watches.lock().unwrap().drain(..) {
+            let _ = remove_watch(fd, wd.0);
+        }
+    }
+}
+
+unsafe impl Send for FileWatcher {}
+
+unsafe impl Sync for FileWatcher {}
+
+unsafe impl AsIoContext for FileWatcher {
+    fn as_ctx(&self) -> &IoContext {
+        self.pimpl.as_ctx()
+    }
+}
+
+impl AsRawFd for FileWatcher {
+    fn as_raw_fd(&self) -> RawFd {
+        self.pimpl.as_raw_fd()
+    }
+}
+
+impl Cancel for FileWatcher {
+    fn cancel(&self) {
+        self.pimpl.cancel()
+    }
+}
+
+impl AsyncReadOp for FileWatcher {
+    fn add_read_op(&self, this: &mut ThreadIoContext, op: Box<Perform>, err: SystemError) {
+        self.pimpl.add_read_op(this, op, err)
+    }
+
+    fn next_read_op(&self, this: &mut ThreadIoContext) {
+        self.pimpl.next_read_op(this)
+    }
+}
+
+impl fmt::Debug for FileWatcher {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "FileWatcher({})", self.as_raw_fd())
+    }
+}
+
+#[test]
+fn test_watch_mask() {
+    let mask = WatchMask::modify() | WatchMask::delete();
+    assert!(mask.contains(WatchMask::modify()));
+    assert!(mask.contains(WatchMask::delete()));
+    assert!(!mask.contains(WatchMask::attrib()));
+    assert!(!mask.contains(WatchMask::all()));
+    assert!(WatchMask::all().contains(mask));
+    assert!(!mask.is_empty());
+}
+
+#[test]
+fn test_file_watcher() {
+    use std::env;
+    use std::fs;
+
+    let ctx = &IoContext::new().unwrap();
+    let path = env::temp_dir().join("asyncio_test_file_watcher");
+    fs::write(&path, b"").unwrap();
+
+    let watcher = FileWatcher::new(ctx).unwrap();
+    let wd = watcher.add_watch(&path, WatchMask::all()).unwrap();
+    assert_eq!(
+        watcher.nonblocking_wait().unwrap_err().kind(),
+        io::ErrorKind::WouldBlock
+    );
+
+    fs::write(&path, b"hello").unwrap();
+    let events = watcher.wait().unwrap();
+    assert_eq!(events[0].wd(), wd);
+    assert!(events[0].mask().contains(WatchMask::modify()));
+
+    fs::remove_file(&path).unwrap();
+    let mut deleted = false;
+    while !deleted {
+        deleted = watcher.wait().unwrap().iter().any(|ev| {
+            ev.wd() == wd && ev.mask().contains(WatchMask::delete())
+        });
+    }
+}
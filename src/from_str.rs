@@ -1,5 +1,5 @@
 use ffi::{if_nametoindex, ADDRESS_FAMILY_NOT_SUPPORTED};
-use ip::{IpAddr, IpAddrV4, IpAddrV6, LlAddr};
+use ip::{IpAddr, IpAddrV4, IpAddrV6, IpEndpoint, IpProtocol, LlAddr};
 
 use std::io;
 use std::str::{Chars, FromStr};
@@ -107,6 +107,31 @@ impl Parser for Dec8 {
     }
 }
 
+/// The decimal number up to the maximum value.
+#[derive(Clone, Copy)]
+struct Dec(u32);
+
+impl Parser for Dec {
+    type Output = u32;
+
+    fn parse<'a>(&self, mut it: Chars<'a>) -> Result<(Self::Output, Chars<'a>)> {
+        let mut n = match it.next().and_then(|ch| ch.to_digit(10)) {
+            Some(i) => i as u64,
+            _ => return Err(ParseError),
+        };
+        loop {
+            if n > self.0 as u64 {
+                return Err(ParseError);
+            }
+            let p = it.clone();
+            n = match it.next().and_then(|ch| ch.to_digit(10)) {
+                Some(i) => n * 10 + i as u64,
+                _ => return Ok((n as u32, p)),
+            };
+        }
+    }
+}
+
 #[derive(Clone, Copy)]
 struct Hex08;
 
@@ -402,8 +427,8 @@ impl Parser for ScopeId {
                     return Ok((id, it));
                 }
             }
-            if let Ok((dec, it)) = Dec8.parse(it.clone()) {
-                return Ok((dec as u32, it));
+            if let Ok((dec, it)) = Dec(u32::max_value()).parse(it.clone()) {
+                return Ok((dec, it));
             }
         }
         Ok((0, it))
@@ -479,6 +504,42 @@ impl FromStr for IpAddr {
     }
 }
 
+impl<P> FromStr for IpEndpoint<P>
+where
+    P: IpProtocol,
+{
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> io::Result<IpEndpoint<P>> {
+        let port = Cat(Lit(':'), Dec(65535));
+        let addr = Sep4By(Dec8, Lit('.'));
+        if let Ok(((addr, (_, port)), _)) = Eos(Cat(addr, port)).parse(s.chars()) {
+            return Ok(IpEndpoint::new(
+                IpAddrV4::new(addr[0], addr[1], addr[2], addr[3]),
+                port as u16,
+            ));
+        }
+        let addr = Between(Lit('['), Cat(IpV6, ScopeId), Lit(']'));
+        if let Ok((((addr, id), (_, port)), _)) = Eos(Cat(addr, port)).parse(s.chars()) {
+            return Ok(IpEndpoint::new(
+                IpAddrV6::with_scope_id(
+                    addr[0],
+                    addr[1],
+                    addr[2],
+                    addr[3],
+                    addr[4],
+                    addr[5],
+                    addr[6],
+                    addr[7],
+                    id,
+                ),
+                port as u16,
+            ));
+        }
+        Err(ADDRESS_FAMILY_NOT_SUPPORTED.into())
+    }
+}
+
 #[test]
 fn test_lit() {
     assert_eq!(Lit('.').parse(".0".chars()).unwrap().0, ());
@@ -670,7 +731,7 @@ fn test_ipaddr_v6() {
         IpAddrV6::with_scope_id(1, 2, 3, 4, 5, 6, 7, 8, 10)
     );
 
-    if cfg!(target_os = "linux") {
+    if cfg!(any(target_os = "linux", target_os = "android")) {
         assert!(IpAddrV6::from_str("1:2:3:4:5:6:7:8%lo").unwrap().scope_id() != 0);
     } else if cfg!(windows) {
         // TODO
@@ -705,3 +766,44 @@ fn test_ipaddr() {
         )
     );
 }
+
+#[test]
+fn test_dec() {
+    assert_eq!(Dec(65535).parse("0".chars()).unwrap().0, 0);
+    assert_eq!(Dec(65535).parse("65535:".chars()).unwrap().0, 65535);
+    assert!(Dec(65535).parse("65536".chars()).is_err());
+    assert!(Dec(65535).parse("".chars()).is_err());
+}
+
+#[test]
+fn test_ip_endpoint() {
+    use ip::{Tcp, TcpEndpoint};
+
+    assert_eq!(
+        TcpEndpoint::from_str("1.2.3.4:80").unwrap(),
+        TcpEndpoint::new(IpAddrV4::new(1, 2, 3, 4), 80)
+    );
+    assert_eq!(
+        "[::1]:8080".parse::<IpEndpoint<Tcp>>().unwrap(),
+        TcpEndpoint::new(IpAddrV6::loopback(), 8080)
+    );
+    assert_eq!(
+        TcpEndpoint::from_str("[fe80::1%300]:65535").unwrap(),
+        TcpEndpoint::new(IpAddrV6::with_scope_id(0xfe80, 0, 0, 0, 0, 0, 0, 1, 300), 65535)
+    );
+    assert_eq!(
+        TcpEndpoint::from_str("[fe80::1%300]:65535").unwrap().to_string(),
+        "[fe80::1%300]:65535"
+    );
+    assert!(TcpEndpoint::from_str("1.2.3.4").is_err());
+    assert!(TcpEndpoint::from_str("1.2.3.4:65536").is_err());
+    assert!(TcpEndpoint::from_str("::1:80").is_err());
+    assert!(TcpEndpoint::from_str("[::1]").is_err());
+
+    if cfg!(any(target_os = "linux", target_os = "android")) {
+        match TcpEndpoint::from_str("[::1%lo]:8080").unwrap().addr() {
+            IpAddr::V6(addr) => assert!(addr.scope_id() != 0),
+            addr => panic!("{}", addr),
+        }
+    }
+}
@@ -3,6 +3,8 @@ use core::{Endpoint, Protocol};
 use dgram_socket::DgramSocket;
 use generic::GenericEndpoint;
 
+use std::fmt;
+
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
 pub struct GenericDgram {
     family: i32,
@@ -32,6 +34,12 @@ impl Protocol for GenericDgram {
     }
 }
 
+impl fmt::Display for GenericDgram {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "GenericDgram(family={}, protocol={})", self.family, self.protocol)
+    }
+}
+
 impl Endpoint<GenericDgram> for GenericEndpoint<GenericDgram> {
     fn protocol(&self) -> GenericDgram {
         GenericDgram {
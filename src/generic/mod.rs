@@ -1,8 +1,10 @@
 use ffi::SockAddr;
+use core::{Endpoint, Protocol};
 
+use std::fmt;
 use std::slice;
 use std::marker::PhantomData;
-use libc::socklen_t;
+use libc::{sockaddr, socklen_t};
 
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub struct GenericEndpoint<P> {
@@ -24,6 +26,45 @@ impl<P> GenericEndpoint<P> {
         }
     }
 
+    /// Returns a generic endpoint converted from the other endpoint.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use asyncio::Endpoint;
+    /// use asyncio::ip::{IpAddrV4, TcpEndpoint};
+    /// use asyncio::generic::GenericStreamEndpoint;
+    ///
+    /// let ep = TcpEndpoint::new(IpAddrV4::loopback(), 80);
+    /// let gep = GenericStreamEndpoint::from_endpoint(&ep);
+    /// assert_eq!(gep.size(), ep.size());
+    /// ```
+    pub fn from_endpoint<T, E>(ep: &E) -> GenericEndpoint<P>
+    where
+        T: Protocol,
+        E: Endpoint<T>,
+    {
+        let len = ep.size() as usize;
+        let mut sa = vec![0; ep.capacity() as usize];
+        let src = unsafe { slice::from_raw_parts(ep.as_ptr() as *const u8, len) };
+        sa[..len].copy_from_slice(src);
+        GenericEndpoint {
+            sa: SockAddr::from_vec(sa, len as u8),
+            protocol: ep.protocol().protocol_type(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns a address family.
+    pub fn family(&self) -> i32 {
+        unsafe { &*(self.sa.sa.as_ptr() as *const sockaddr) }.sa_family as i32
+    }
+
+    /// Returns a raw bytes of the socket address.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.sa.sa[..self.sa.size() as usize]
+    }
+
     fn default(capacity: socklen_t, protocol: i32) -> GenericEndpoint<P> {
         GenericEndpoint {
             sa: SockAddr::from_vec(vec![0; capacity as usize], 0),
@@ -33,6 +74,12 @@ impl<P> GenericEndpoint<P> {
     }
 }
 
+impl<P> fmt::Debug for GenericEndpoint<P> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "GenericEndpoint(family={}, size={})", self.family(), self.sa.size())
+    }
+}
+
 mod stream;
 pub use self::stream::*;
 
@@ -3,6 +3,8 @@ use core::{Endpoint, Protocol};
 use dgram_socket::DgramSocket;
 use generic::GenericEndpoint;
 
+use std::fmt;
+
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
 pub struct GenericRaw {
     family: i32,
@@ -32,6 +34,12 @@ impl Protocol for GenericRaw {
     }
 }
 
+impl fmt::Display for GenericRaw {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "GenericRaw(family={}, protocol={})", self.family, self.protocol)
+    }
+}
+
 impl Endpoint<GenericRaw> for GenericEndpoint<GenericRaw> {
     fn protocol(&self) -> GenericRaw {
         GenericRaw {
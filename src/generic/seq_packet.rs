@@ -4,6 +4,8 @@ use dgram_socket::DgramSocket;
 use socket_listener::SocketListener;
 use generic::GenericEndpoint;
 
+use std::fmt;
+
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
 pub struct GenericSeqPacket {
     family: i32,
@@ -43,6 +45,12 @@ impl Protocol for GenericSeqPacket {
     }
 }
 
+impl fmt::Display for GenericSeqPacket {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "GenericSeqPacket(family={}, protocol={})", self.family, self.protocol)
+    }
+}
+
 impl Endpoint<GenericSeqPacket> for GenericEndpoint<GenericSeqPacket> {
     fn protocol(&self) -> GenericSeqPacket {
         GenericSeqPacket {
@@ -4,6 +4,8 @@ use stream_socket::StreamSocket;
 use socket_listener::SocketListener;
 use generic::GenericEndpoint;
 
+use std::fmt;
+
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
 pub struct GenericStream {
     family: i32,
@@ -33,6 +35,12 @@ impl Protocol for GenericStream {
     }
 }
 
+impl fmt::Display for GenericStream {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "GenericStream(family={}, protocol={})", self.family, self.protocol)
+    }
+}
+
 impl Endpoint<GenericStream> for GenericEndpoint<GenericStream> {
     fn protocol(&self) -> GenericStream {
         GenericStream {
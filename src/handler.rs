@@ -64,6 +64,30 @@ where
     }
 }
 
+pub struct Success<F, R, E>(R, F, PhantomData<E>);
+
+impl<F, R, E> Success<F, R, E> {
+    pub fn new(res: R, handler: F) -> Self {
+        Success(res, handler, PhantomData)
+    }
+}
+
+impl<F, R, E> Exec for Success<F, R, E>
+where
+    F: Complete<R, E>,
+    R: Send + 'static,
+    E: Send + 'static,
+{
+    fn call(self, this: &mut ThreadIoContext) {
+        let Success(res, handler, _marker) = self;
+        handler.success(this, res)
+    }
+
+    fn call_box(self: Box<Self>, this: &mut ThreadIoContext) {
+        self.call(this)
+    }
+}
+
 pub struct ArcHandler<T, F, R, E> {
     data: Arc<T>,
     handler: F,
@@ -160,3 +184,109 @@ pub fn wrap<T, F, R, E>(data: &Arc<T>, handler: F) -> ArcHandler<T, F, R, E> {
         _marker: PhantomData,
     }
 }
+
+trait BoxedComplete<R, E>: Send + 'static {
+    fn success_box(self: Box<Self>, this: &mut ThreadIoContext, res: R);
+
+    fn failure_box(self: Box<Self>, this: &mut ThreadIoContext, err: E);
+}
+
+impl<F, R, E> BoxedComplete<R, E> for F
+where
+    F: Complete<R, E>,
+{
+    fn success_box(self: Box<Self>, this: &mut ThreadIoContext, res: R) {
+        self.success(this, res)
+    }
+
+    fn failure_box(self: Box<Self>, this: &mut ThreadIoContext, err: E) {
+        self.failure(this, err)
+    }
+}
+
+/// Provides a type-erased handler to asynchronous operation.
+///
+/// The BoxHandler is able to store the different handlers in a collection,
+/// that accepts a handler of `Handler::Output` is `()` (e.g. `ArcHandler` or `StrandHandler`).
+///
+/// # Examples
+///
+/// ```
+/// use std::io;
+/// use std::sync::Arc;
+/// use std::collections::HashMap;
+/// use asyncio::{IoContext, SteadyTimer, BoxHandler, wrap};
+///
+/// fn on_wait(_: Arc<SteadyTimer>, res: io::Result<()>) {
+/// }
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let timer = Arc::new(SteadyTimer::new(ctx));
+/// let mut pending: HashMap<u32, BoxHandler<(), io::Error>> = HashMap::new();
+/// pending.insert(1, BoxHandler::new(wrap(&timer, on_wait)));
+/// pending.insert(2, BoxHandler::new(wrap(&timer, |_, _| ())));
+/// timer.async_wait(pending.remove(&1).unwrap());
+/// ```
+pub struct BoxHandler<R, E>(Box<BoxedComplete<R, E>>);
+
+impl<R, E> BoxHandler<R, E>
+where
+    R: Send + 'static,
+    E: Send + 'static,
+{
+    pub fn new<F>(handler: F) -> Self
+    where
+        F: Handler<R, E, Output = ()> + Complete<R, E>,
+    {
+        BoxHandler(Box::new(handler))
+    }
+
+    #[doc(hidden)]
+    pub fn from_wrapped<F>(handler: F) -> Self
+    where
+        F: Complete<R, E>,
+    {
+        BoxHandler(Box::new(handler))
+    }
+}
+
+impl<R, E> Handler<R, E> for BoxHandler<R, E>
+where
+    R: Send + 'static,
+    E: Send + 'static,
+{
+    type Output = ();
+
+    #[doc(hidden)]
+    type WrappedHandler = Self;
+
+    #[doc(hidden)]
+    fn wrap<W>(self, ctx: &IoContext, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx, self)
+    }
+
+    #[doc(hidden)]
+    fn wrap_timeout<W>(self, ctx: &Cancel, _: &Timeout, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx.as_ctx(), self)
+    }
+}
+
+impl<R, E> Complete<R, E> for BoxHandler<R, E>
+where
+    R: Send + 'static,
+    E: Send + 'static,
+{
+    fn success(self, this: &mut ThreadIoContext, res: R) {
+        self.0.success_box(this, res)
+    }
+
+    fn failure(self, this: &mut ThreadIoContext, err: E) {
+        self.0.failure_box(this, err)
+    }
+}
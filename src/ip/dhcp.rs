@@ -0,0 +1,740 @@
+//! The Dynamic Host Configuration Protocol (DHCP) building blocks.
+//!
+//! This module provides the message codec, the DISCOVER/OFFER/REQUEST/ACK state machine
+//! and a client which drives the state machine over a `UdpSocket` with retransmission timers.
+
+use ffi::{Timeout, TIMED_OUT, INVALID_ARGUMENT, if_hwaddr};
+use core::{IoContext, AsIoContext, ThreadIoContext, Cancel};
+use handler::{Handler, Complete, Failure};
+use ip::{IpAddrV4, LlAddr, IpProtocol, Udp, UdpEndpoint, UdpSocket};
+use socket_base::{Broadcast, BindToDevice, ReuseAddr};
+use SteadyTimer;
+
+use std::io;
+use std::cmp;
+use std::process;
+use std::cell::UnsafeCell;
+use std::ffi::CString;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The UDP port number of DHCP server.
+pub const SERVER_PORT: u16 = 67;
+
+/// The UDP port number of DHCP client.
+pub const CLIENT_PORT: u16 = 68;
+
+/// The message type of the client broadcast to locate the servers.
+pub const DHCPDISCOVER: u8 = 1;
+
+/// The message type of the server offering the address to the client.
+pub const DHCPOFFER: u8 = 2;
+
+/// The message type of the client requesting the offered address.
+pub const DHCPREQUEST: u8 = 3;
+
+/// The message type of the client declining the address already in use.
+pub const DHCPDECLINE: u8 = 4;
+
+/// The message type of the server committing the lease.
+pub const DHCPACK: u8 = 5;
+
+/// The message type of the server refusing the request.
+pub const DHCPNAK: u8 = 6;
+
+/// The message type of the client relinquishing the lease.
+pub const DHCPRELEASE: u8 = 7;
+
+/// The message type of the client asking only for the configuration parameters.
+pub const DHCPINFORM: u8 = 8;
+
+/// The option code of the padding.
+pub const OPTION_PAD: u8 = 0;
+
+/// The option code of the subnet mask.
+pub const OPTION_SUBNET_MASK: u8 = 1;
+
+/// The option code of the router addresses.
+pub const OPTION_ROUTER: u8 = 3;
+
+/// The option code of the domain name server addresses.
+pub const OPTION_DOMAIN_NAME_SERVER: u8 = 6;
+
+/// The option code of the host name of the client.
+pub const OPTION_HOST_NAME: u8 = 12;
+
+/// The option code of the address requested by the client.
+pub const OPTION_REQUESTED_IP_ADDRESS: u8 = 50;
+
+/// The option code of the lease time in seconds.
+pub const OPTION_LEASE_TIME: u8 = 51;
+
+/// The option code of the DHCP message type.
+pub const OPTION_MESSAGE_TYPE: u8 = 53;
+
+/// The option code of the address identifying the server.
+pub const OPTION_SERVER_IDENTIFIER: u8 = 54;
+
+/// The option code of the list of the option codes requested by the client.
+pub const OPTION_PARAMETER_REQUEST_LIST: u8 = 55;
+
+/// The option code of the end of the options.
+pub const OPTION_END: u8 = 255;
+
+const BOOTREQUEST: u8 = 1;
+const BOOTREPLY: u8 = 2;
+const FLAG_BROADCAST: u16 = 0x8000;
+const MAGIC_COOKIE: [u8; 4] = [99, 130, 83, 99];
+const HEADER_LEN: usize = 236;
+const MAX_RETRANSMIT: usize = 4;
+
+fn ipaddr(buf: &[u8]) -> IpAddrV4 {
+    IpAddrV4::new(buf[0], buf[1], buf[2], buf[3])
+}
+
+/// A DHCP option encoded as code, length and value.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct DhcpOption {
+    pub code: u8,
+    pub data: Vec<u8>,
+}
+
+/// The DHCP message.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct DhcpMessage {
+    pub op: u8,
+    pub hops: u8,
+    pub xid: u32,
+    pub secs: u16,
+    pub flags: u16,
+    pub ciaddr: IpAddrV4,
+    pub yiaddr: IpAddrV4,
+    pub siaddr: IpAddrV4,
+    pub giaddr: IpAddrV4,
+    pub chaddr: LlAddr,
+    pub options: Vec<DhcpOption>,
+}
+
+impl DhcpMessage {
+    /// Returns a client request message of the `message_type`.
+    pub fn request(message_type: u8, xid: u32, chaddr: LlAddr) -> DhcpMessage {
+        let mut msg = DhcpMessage {
+            op: BOOTREQUEST,
+            hops: 0,
+            xid: xid,
+            secs: 0,
+            flags: FLAG_BROADCAST,
+            ciaddr: IpAddrV4::any(),
+            yiaddr: IpAddrV4::any(),
+            siaddr: IpAddrV4::any(),
+            giaddr: IpAddrV4::any(),
+            chaddr: chaddr,
+            options: Vec::new(),
+        };
+        msg.set_option(OPTION_MESSAGE_TYPE, &[message_type]);
+        msg
+    }
+
+    /// Returns true if this is a reply message from server.
+    pub fn is_reply(&self) -> bool {
+        self.op == BOOTREPLY
+    }
+
+    /// Returns a value of the DHCP message type option.
+    pub fn message_type(&self) -> Option<u8> {
+        self.option(OPTION_MESSAGE_TYPE).and_then(
+            |data| data.first().cloned(),
+        )
+    }
+
+    /// Returns a value of the option.
+    pub fn option(&self, code: u8) -> Option<&[u8]> {
+        self.options.iter().find(|opt| opt.code == code).map(
+            |opt| &opt.data[..],
+        )
+    }
+
+    /// Returns a IP-v4 address of the option.
+    pub fn option_addr(&self, code: u8) -> Option<IpAddrV4> {
+        self.option(code).and_then(|data| if data.len() >= 4 {
+            Some(ipaddr(data))
+        } else {
+            None
+        })
+    }
+
+    /// Sets a value of the option, replacing any previous value.
+    pub fn set_option(&mut self, code: u8, data: &[u8]) {
+        debug_assert!(data.len() < 256);
+        self.options.retain(|opt| opt.code != code);
+        self.options.push(DhcpOption {
+            code: code,
+            data: data.to_vec(),
+        });
+    }
+
+    /// Returns a wire-format bytes of the message.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use asyncio::ip::LlAddr;
+    /// use asyncio::ip::dhcp::{DhcpMessage, DHCPDISCOVER};
+    ///
+    /// let msg = DhcpMessage::request(DHCPDISCOVER, 0x1234, LlAddr::new(1,2,3,4,5,6));
+    /// let buf = msg.to_bytes();
+    /// assert_eq!(DhcpMessage::from_bytes(&buf), Some(msg));
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = vec![0; HEADER_LEN];
+        buf[0] = self.op;
+        buf[1] = 1; // ethernet
+        buf[2] = 6;
+        buf[3] = self.hops;
+        buf[4] = (self.xid >> 24) as u8;
+        buf[5] = (self.xid >> 16) as u8;
+        buf[6] = (self.xid >> 8) as u8;
+        buf[7] = self.xid as u8;
+        buf[8] = (self.secs >> 8) as u8;
+        buf[9] = self.secs as u8;
+        buf[10] = (self.flags >> 8) as u8;
+        buf[11] = self.flags as u8;
+        buf[12..16].copy_from_slice(self.ciaddr.as_bytes());
+        buf[16..20].copy_from_slice(self.yiaddr.as_bytes());
+        buf[20..24].copy_from_slice(self.siaddr.as_bytes());
+        buf[24..28].copy_from_slice(self.giaddr.as_bytes());
+        buf[28..34].copy_from_slice(self.chaddr.as_bytes());
+        buf.extend_from_slice(&MAGIC_COOKIE);
+        for opt in &self.options {
+            buf.push(opt.code);
+            buf.push(opt.data.len() as u8);
+            buf.extend_from_slice(&opt.data);
+        }
+        buf.push(OPTION_END);
+        buf
+    }
+
+    /// Returns a DHCP message parsed from wire-format bytes.
+    ///
+    /// Returns `None` if the bytes is malformed.
+    pub fn from_bytes(buf: &[u8]) -> Option<DhcpMessage> {
+        if buf.len() < HEADER_LEN + 4 || buf[1] != 1 || buf[2] != 6 ||
+            buf[HEADER_LEN..HEADER_LEN + 4] != MAGIC_COOKIE
+        {
+            return None;
+        }
+        let mut chaddr = [0; 6];
+        chaddr.copy_from_slice(&buf[28..34]);
+        let mut msg = DhcpMessage {
+            op: buf[0],
+            hops: buf[3],
+            xid: (buf[4] as u32) << 24 | (buf[5] as u32) << 16 | (buf[6] as u32) << 8 |
+                buf[7] as u32,
+            secs: (buf[8] as u16) << 8 | buf[9] as u16,
+            flags: (buf[10] as u16) << 8 | buf[11] as u16,
+            ciaddr: ipaddr(&buf[12..16]),
+            yiaddr: ipaddr(&buf[16..20]),
+            siaddr: ipaddr(&buf[20..24]),
+            giaddr: ipaddr(&buf[24..28]),
+            chaddr: LlAddr::from(chaddr),
+            options: Vec::new(),
+        };
+        let mut it = &buf[HEADER_LEN + 4..];
+        while let Some((&code, rest)) = it.split_first() {
+            match code {
+                OPTION_PAD => it = rest,
+                OPTION_END => return Some(msg),
+                _ => {
+                    let len = match rest.first() {
+                        Some(&len) if rest.len() > len as usize => len as usize,
+                        _ => return None,
+                    };
+                    msg.options.push(DhcpOption {
+                        code: code,
+                        data: rest[1..len + 1].to_vec(),
+                    });
+                    it = &rest[len + 1..];
+                }
+            }
+        }
+        None
+    }
+}
+
+/// The lease acquired from DHCP server.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct DhcpLease {
+    pub addr: IpAddrV4,
+    pub server: IpAddrV4,
+    pub lease_time: Duration,
+    pub subnet_mask: Option<IpAddrV4>,
+    pub routers: Vec<IpAddrV4>,
+    pub dns_servers: Vec<IpAddrV4>,
+}
+
+impl DhcpLease {
+    fn from_ack(msg: &DhcpMessage) -> DhcpLease {
+        fn addrs(data: Option<&[u8]>) -> Vec<IpAddrV4> {
+            data.map(|data| data.chunks(4).filter(|x| x.len() == 4).map(ipaddr).collect())
+                .unwrap_or_default()
+        }
+        let lease_time = match msg.option(OPTION_LEASE_TIME) {
+            Some(data) if data.len() >= 4 => {
+                (data[0] as u64) << 24 | (data[1] as u64) << 16 | (data[2] as u64) << 8 |
+                    data[3] as u64
+            }
+            _ => 0,
+        };
+        DhcpLease {
+            addr: msg.yiaddr,
+            server: msg.option_addr(OPTION_SERVER_IDENTIFIER).unwrap_or(
+                msg.siaddr,
+            ),
+            lease_time: Duration::new(lease_time, 0),
+            subnet_mask: msg.option_addr(OPTION_SUBNET_MASK),
+            routers: addrs(msg.option(OPTION_ROUTER)),
+            dns_servers: addrs(msg.option(OPTION_DOMAIN_NAME_SERVER)),
+        }
+    }
+}
+
+/// The state of DHCP client.
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum DhcpState {
+    Init,
+    Selecting,
+    Requesting,
+    Bound,
+}
+
+/// The next action of DHCP client decided by the state machine.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum DhcpAction {
+    /// Ignores the message and continues waiting.
+    Ignore,
+
+    /// Sends the message to the server.
+    Send(DhcpMessage),
+
+    /// Acquired the lease.
+    Bound(DhcpLease),
+}
+
+/// The client state machine of DISCOVER/OFFER/REQUEST/ACK exchange.
+///
+/// # Examples
+///
+/// ```
+/// use asyncio::ip::{IpAddrV4, LlAddr};
+/// use asyncio::ip::dhcp::*;
+///
+/// let mut fsm = DhcpStateMachine::new(1, LlAddr::new(1,2,3,4,5,6));
+/// let discover = fsm.start();
+/// assert_eq!(discover.message_type(), Some(DHCPDISCOVER));
+/// assert_eq!(fsm.state(), DhcpState::Selecting);
+/// ```
+pub struct DhcpStateMachine {
+    state: DhcpState,
+    xid: u32,
+    chaddr: LlAddr,
+    last: Option<DhcpMessage>,
+}
+
+impl DhcpStateMachine {
+    pub fn new(xid: u32, chaddr: LlAddr) -> DhcpStateMachine {
+        DhcpStateMachine {
+            state: DhcpState::Init,
+            xid: xid,
+            chaddr: chaddr,
+            last: None,
+        }
+    }
+
+    /// Returns a current state.
+    pub fn state(&self) -> DhcpState {
+        self.state
+    }
+
+    /// Starts the exchange and returns a DHCPDISCOVER message.
+    pub fn start(&mut self) -> DhcpMessage {
+        let mut msg = DhcpMessage::request(DHCPDISCOVER, self.xid, self.chaddr);
+        msg.set_option(
+            OPTION_PARAMETER_REQUEST_LIST,
+            &[
+                OPTION_SUBNET_MASK,
+                OPTION_ROUTER,
+                OPTION_DOMAIN_NAME_SERVER,
+                OPTION_LEASE_TIME,
+            ],
+        );
+        self.state = DhcpState::Selecting;
+        self.last = Some(msg.clone());
+        msg
+    }
+
+    /// Returns a last sent message for the retransmission.
+    pub fn last_message(&self) -> Option<&DhcpMessage> {
+        self.last.as_ref()
+    }
+
+    /// Processes a received message and returns the next action.
+    pub fn handle(&mut self, msg: &DhcpMessage) -> DhcpAction {
+        if !msg.is_reply() || msg.xid != self.xid || msg.chaddr != self.chaddr {
+            return DhcpAction::Ignore;
+        }
+        match (self.state, msg.message_type()) {
+            (DhcpState::Selecting, Some(DHCPOFFER)) => {
+                let mut req = DhcpMessage::request(DHCPREQUEST, self.xid, self.chaddr);
+                req.set_option(OPTION_REQUESTED_IP_ADDRESS, msg.yiaddr.as_bytes());
+                if let Some(server) = msg.option(OPTION_SERVER_IDENTIFIER) {
+                    req.set_option(OPTION_SERVER_IDENTIFIER, server);
+                }
+                self.state = DhcpState::Requesting;
+                self.last = Some(req.clone());
+                DhcpAction::Send(req)
+            }
+            (DhcpState::Requesting, Some(DHCPACK)) => {
+                self.state = DhcpState::Bound;
+                self.last = None;
+                DhcpAction::Bound(DhcpLease::from_ack(msg))
+            }
+            (DhcpState::Requesting, Some(DHCPNAK)) => DhcpAction::Send(self.start()),
+            _ => DhcpAction::Ignore,
+        }
+    }
+}
+
+fn new_xid() -> u32 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or(0);
+    nanos ^ process::id().rotate_left(16)
+}
+
+/// The DHCP client over a UDP socket bound to 0.0.0.0:68 on the interface.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use std::sync::Arc;
+/// use asyncio::{IoContext, wrap};
+/// use asyncio::ip::dhcp::DhcpClient;
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let cl = Arc::new(DhcpClient::new(ctx, "eth0").unwrap());
+/// cl.async_acquire(wrap(&cl, |_, res| if let Ok(lease) = res {
+///     println!("{:?}", lease);
+/// }));
+/// ctx.run();
+/// ```
+pub struct DhcpClient {
+    inner: Arc<DhcpClientImpl>,
+    interval: Duration,
+}
+
+impl DhcpClient {
+    pub fn new(ctx: &IoContext, iface: &str) -> io::Result<DhcpClient> {
+        let name = CString::new(iface).or(Err(INVALID_ARGUMENT))?;
+        let soc = UdpSocket::new(ctx, Udp::v4())?;
+        soc.set_option(ReuseAddr::new(true))?;
+        soc.set_option(Broadcast::new(true))?;
+        soc.set_option(BindToDevice::new(iface)?)?;
+        soc.bind(&UdpEndpoint::new(IpAddrV4::any(), CLIENT_PORT))?;
+        let chaddr = LlAddr::from(if_hwaddr(&soc, &name)?);
+        Ok(DhcpClient {
+            inner: Arc::new(DhcpClientImpl {
+                soc: soc,
+                timer: SteadyTimer::new(ctx),
+                chaddr: chaddr,
+                fsm: Mutex::new(DhcpStateMachine::new(new_xid(), chaddr)),
+                buf: UnsafeCell::new([0; 1500]),
+                expired: AtomicBool::new(false),
+            }),
+            interval: Duration::new(4, 0),
+        })
+    }
+
+    /// Returns a hardware address of the interface.
+    pub fn chaddr(&self) -> LlAddr {
+        self.inner.chaddr
+    }
+
+    /// Returns a current state.
+    pub fn state(&self) -> DhcpState {
+        self.inner.fsm.lock().unwrap().state()
+    }
+
+    /// Sets a initial retransmission interval, doubled for each retransmission.
+    pub fn set_interval(&mut self, interval: Duration) {
+        self.interval = interval;
+    }
+
+    /// Sends the message, broadcasted unless the client has a address.
+    pub fn send(&self, msg: &DhcpMessage) -> io::Result<usize> {
+        self.inner.send(msg)
+    }
+
+    /// Asynchronously acquires a lease by the DISCOVER/OFFER/REQUEST/ACK exchange.
+    ///
+    /// Each message is retransmitted with exponential backoff, and the handler is
+    /// completed with `TimedOut` error if no server responds.
+    pub fn async_acquire<F>(&self, handler: F) -> F::Output
+    where
+        F: Handler<DhcpLease, io::Error>,
+    {
+        handler.wrap(self.as_ctx(), move |ctx, handler| {
+            let cl = &self.inner;
+            let msg = {
+                let mut fsm = cl.fsm.lock().unwrap();
+                *fsm = DhcpStateMachine::new(new_xid(), cl.chaddr);
+                fsm.start()
+            };
+            match cl.send(&msg) {
+                Ok(_) => {
+                    DhcpClientImpl::start_timer(cl, self.interval);
+                    cl.async_receive(DhcpReceive {
+                        client: cl.clone(),
+                        interval: self.interval,
+                        retries: 0,
+                        handler: handler,
+                    })
+                }
+                Err(err) => ctx.do_dispatch(Failure::new(err, handler)),
+            }
+        })
+    }
+}
+
+unsafe impl AsIoContext for DhcpClient {
+    fn as_ctx(&self) -> &IoContext {
+        self.inner.soc.as_ctx()
+    }
+}
+
+impl Cancel for DhcpClient {
+    fn cancel(&self) {
+        self.inner.timer.cancel();
+        self.inner.soc.cancel();
+    }
+}
+
+/// The socket and the state shared with the handlers, which keep it alive until completed.
+struct DhcpClientImpl {
+    soc: UdpSocket,
+    timer: SteadyTimer,
+    chaddr: LlAddr,
+    fsm: Mutex<DhcpStateMachine>,
+    buf: UnsafeCell<[u8; 1500]>,
+    expired: AtomicBool,
+}
+
+impl DhcpClientImpl {
+    fn send(&self, msg: &DhcpMessage) -> io::Result<usize> {
+        let addr = match msg.option_addr(OPTION_SERVER_IDENTIFIER) {
+            Some(server) if !msg.ciaddr.is_unspecified() => server,
+            _ => IpAddrV4::new(255, 255, 255, 255),
+        };
+        self.soc.send_to(
+            &msg.to_bytes(),
+            0,
+            &UdpEndpoint::new(addr, SERVER_PORT),
+        )
+    }
+
+    fn start_timer(cl: &Arc<DhcpClientImpl>, interval: Duration) {
+        cl.expired.store(false, Ordering::SeqCst);
+        cl.timer.expires_from_now(interval);
+        cl.timer.async_wait(DhcpRetransmit { client: cl.clone() });
+    }
+
+    fn async_receive<F>(&self, handler: DhcpReceive<F>)
+    where
+        F: Complete<DhcpLease, io::Error>,
+    {
+        let buf = unsafe { &mut *self.buf.get() };
+        self.soc.async_receive_from(buf, 0, handler)
+    }
+}
+
+unsafe impl Sync for DhcpClientImpl {}
+
+struct DhcpReceive<F> {
+    client: Arc<DhcpClientImpl>,
+    interval: Duration,
+    retries: usize,
+    handler: F,
+}
+
+impl<F> Handler<(usize, UdpEndpoint), io::Error> for DhcpReceive<F>
+where
+    F: Complete<DhcpLease, io::Error>,
+{
+    type Output = ();
+
+    type WrappedHandler = Self;
+
+    fn wrap<W>(self, ctx: &IoContext, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx, self)
+    }
+
+    fn wrap_timeout<W>(self, ctx: &Cancel, _: &Timeout, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx.as_ctx(), self)
+    }
+}
+
+impl<F> Complete<(usize, UdpEndpoint), io::Error> for DhcpReceive<F>
+where
+    F: Complete<DhcpLease, io::Error>,
+{
+    fn success(mut self, this: &mut ThreadIoContext, res: (usize, UdpEndpoint)) {
+        let cl = self.client.clone();
+        let (len, _) = res;
+        let buf = unsafe { &*cl.buf.get() };
+        let action = match DhcpMessage::from_bytes(&buf[..len]) {
+            Some(msg) => cl.fsm.lock().unwrap().handle(&msg),
+            None => DhcpAction::Ignore,
+        };
+        match action {
+            DhcpAction::Ignore => (),
+            DhcpAction::Send(msg) => {
+                if let Err(err) = cl.send(&msg) {
+                    cl.timer.cancel();
+                    return self.handler.failure(this, err);
+                }
+                self.retries = 0;
+                DhcpClientImpl::start_timer(&cl, self.interval);
+            }
+            DhcpAction::Bound(lease) => {
+                cl.timer.cancel();
+                return self.handler.success(this, lease);
+            }
+        }
+        this.decrease_outstanding_work();
+        cl.async_receive(self)
+    }
+
+    fn failure(mut self, this: &mut ThreadIoContext, err: io::Error) {
+        let cl = self.client.clone();
+        if !cl.expired.load(Ordering::SeqCst) {
+            cl.timer.cancel();
+            return self.handler.failure(this, err);
+        }
+        self.retries += 1;
+        if self.retries > MAX_RETRANSMIT {
+            return self.handler.failure(this, TIMED_OUT.into());
+        }
+        let msg = cl.fsm.lock().unwrap().last_message().cloned();
+        if let Some(msg) = msg {
+            if let Err(err) = cl.send(&msg) {
+                return self.handler.failure(this, err);
+            }
+        }
+        DhcpClientImpl::start_timer(&cl, self.interval * cmp::min(1 << self.retries, 16));
+        this.decrease_outstanding_work();
+        cl.async_receive(self)
+    }
+}
+
+struct DhcpRetransmit {
+    client: Arc<DhcpClientImpl>,
+}
+
+impl Handler<(), io::Error> for DhcpRetransmit {
+    type Output = ();
+
+    type WrappedHandler = Self;
+
+    fn wrap<W>(self, ctx: &IoContext, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx, self)
+    }
+
+    fn wrap_timeout<W>(self, ctx: &Cancel, _: &Timeout, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx.as_ctx(), self)
+    }
+}
+
+impl Complete<(), io::Error> for DhcpRetransmit {
+    fn success(self, this: &mut ThreadIoContext, _: ()) {
+        let cl = &self.client;
+        cl.expired.store(true, Ordering::SeqCst);
+        cl.soc.cancel();
+        this.decrease_outstanding_work();
+    }
+
+    fn failure(self, this: &mut ThreadIoContext, _: io::Error) {
+        this.decrease_outstanding_work();
+    }
+}
+
+#[test]
+fn test_dhcp_message() {
+    let mut msg = DhcpMessage::request(DHCPREQUEST, 0xdeadbeef, LlAddr::new(1, 2, 3, 4, 5, 6));
+    msg.set_option(OPTION_REQUESTED_IP_ADDRESS, &[192, 168, 0, 10]);
+    let buf = msg.to_bytes();
+    assert_eq!(buf.len(), 236 + 4 + 3 + 6 + 1);
+    assert_eq!(&buf[4..8], &[0xde, 0xad, 0xbe, 0xef]);
+    let res = DhcpMessage::from_bytes(&buf).unwrap();
+    assert_eq!(res, msg);
+    assert_eq!(res.message_type(), Some(DHCPREQUEST));
+    assert_eq!(
+        res.option_addr(OPTION_REQUESTED_IP_ADDRESS),
+        Some(IpAddrV4::new(192, 168, 0, 10))
+    );
+    assert_eq!(DhcpMessage::from_bytes(&buf[..buf.len() - 1]), None);
+}
+
+#[test]
+fn test_dhcp_state_machine() {
+    let chaddr = LlAddr::new(1, 2, 3, 4, 5, 6);
+    let mut fsm = DhcpStateMachine::new(7, chaddr);
+    assert_eq!(fsm.start().message_type(), Some(DHCPDISCOVER));
+
+    let mut offer = DhcpMessage::request(DHCPOFFER, 7, chaddr);
+    offer.op = BOOTREPLY;
+    offer.yiaddr = IpAddrV4::new(10, 0, 0, 5);
+    offer.set_option(OPTION_SERVER_IDENTIFIER, &[10, 0, 0, 1]);
+    assert_eq!(
+        fsm.handle(&DhcpMessage { xid: 8, ..offer.clone() }),
+        DhcpAction::Ignore
+    );
+    match fsm.handle(&offer) {
+        DhcpAction::Send(req) => {
+            assert_eq!(req.message_type(), Some(DHCPREQUEST));
+            assert_eq!(
+                req.option_addr(OPTION_REQUESTED_IP_ADDRESS),
+                Some(IpAddrV4::new(10, 0, 0, 5))
+            );
+        }
+        _ => panic!(),
+    }
+    assert_eq!(fsm.state(), DhcpState::Requesting);
+
+    let mut ack = offer.clone();
+    ack.set_option(OPTION_MESSAGE_TYPE, &[DHCPACK]);
+    ack.set_option(OPTION_LEASE_TIME, &[0, 0, 0x0e, 0x10]);
+    ack.set_option(OPTION_ROUTER, &[10, 0, 0, 1, 10, 0, 0, 2]);
+    match fsm.handle(&ack) {
+        DhcpAction::Bound(lease) => {
+            assert_eq!(lease.addr, IpAddrV4::new(10, 0, 0, 5));
+            assert_eq!(lease.server, IpAddrV4::new(10, 0, 0, 1));
+            assert_eq!(lease.lease_time, Duration::new(3600, 0));
+            assert_eq!(lease.routers.len(), 2);
+        }
+        _ => panic!(),
+    }
+    assert_eq!(fsm.state(), DhcpState::Bound);
+}
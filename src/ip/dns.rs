@@ -0,0 +1,1425 @@
+//! The Domain Name System (DNS) stub resolver.
+//!
+//! This module provides the wire-format codec of DNS messages, the `/etc/resolv.conf` parser,
+//! a record cache with TTLs and a resolver which sends the queries over `UdpSocket`
+//! and retries over `TcpSocket` if the response is truncated.
+
+use ffi::{Timeout, TIMED_OUT, INVALID_ARGUMENT, NAME_TOO_LONG, HOST_NOT_FOUND,
+          HOST_NOT_FOUND_TRY_AGAIN, NO_RECOVERY};
+use core::{IoContext, AsIoContext, ThreadIoContext, Cancel};
+use handler::{Handler, Complete, Failure, Success};
+use ip::{IpAddr, IpAddrV4, IpAddrV6, IpProtocol, Udp, UdpEndpoint, UdpSocket, TcpEndpoint,
+         TcpSocket};
+use SteadyTimer;
+
+use std::io;
+use std::cmp;
+use std::str;
+use std::process;
+use std::fs::File;
+use std::io::Read;
+use std::cell::UnsafeCell;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// The port number of DNS server.
+pub const PORT: u16 = 53;
+
+pub const TYPE_A: u16 = 1;
+pub const TYPE_NS: u16 = 2;
+pub const TYPE_CNAME: u16 = 5;
+pub const TYPE_SOA: u16 = 6;
+pub const TYPE_PTR: u16 = 12;
+pub const TYPE_TXT: u16 = 16;
+pub const TYPE_AAAA: u16 = 28;
+pub const TYPE_SRV: u16 = 33;
+
+pub const CLASS_IN: u16 = 1;
+
+pub const RCODE_NOERROR: u8 = 0;
+pub const RCODE_FORMERR: u8 = 1;
+pub const RCODE_SERVFAIL: u8 = 2;
+pub const RCODE_NXDOMAIN: u8 = 3;
+pub const RCODE_NOTIMP: u8 = 4;
+pub const RCODE_REFUSED: u8 = 5;
+
+const FLAG_QR: u16 = 0x8000;
+const FLAG_TC: u16 = 0x0200;
+const FLAG_RD: u16 = 0x0100;
+const HEADER_LEN: usize = 12;
+const MAX_UDP_LEN: usize = 512;
+const MAX_LABEL_LEN: usize = 63;
+const MAX_NAME_LEN: usize = 255;
+const MAX_POINTERS: usize = 64;
+
+/// The resource data of DNS record.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum DnsData {
+    A(IpAddrV4),
+    Aaaa(IpAddrV6),
+    Ns(String),
+    Cname(String),
+    Ptr(String),
+    Txt(Vec<Vec<u8>>),
+    Srv {
+        priority: u16,
+        weight: u16,
+        port: u16,
+        target: String,
+    },
+    Other(Vec<u8>),
+}
+
+/// The DNS resource record.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct DnsRecord {
+    pub name: String,
+    pub rtype: u16,
+    pub class: u16,
+    pub ttl: u32,
+    pub data: DnsData,
+}
+
+impl DnsRecord {
+    /// Returns a record of class `IN`.
+    pub fn new(name: &str, ttl: u32, data: DnsData) -> DnsRecord {
+        let rtype = match data {
+            DnsData::A(_) => TYPE_A,
+            DnsData::Aaaa(_) => TYPE_AAAA,
+            DnsData::Ns(_) => TYPE_NS,
+            DnsData::Cname(_) => TYPE_CNAME,
+            DnsData::Ptr(_) => TYPE_PTR,
+            DnsData::Txt(_) => TYPE_TXT,
+            DnsData::Srv { .. } => TYPE_SRV,
+            DnsData::Other(_) => 0,
+        };
+        DnsRecord {
+            name: name.to_owned(),
+            rtype: rtype,
+            class: CLASS_IN,
+            ttl: ttl,
+            data: data,
+        }
+    }
+}
+
+/// The question section entry of DNS message.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct DnsQuestion {
+    pub name: String,
+    pub qtype: u16,
+    pub qclass: u16,
+}
+
+/// The DNS message.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct DnsMessage {
+    pub id: u16,
+    pub flags: u16,
+    pub questions: Vec<DnsQuestion>,
+    pub answers: Vec<DnsRecord>,
+    pub authorities: Vec<DnsRecord>,
+    pub additionals: Vec<DnsRecord>,
+}
+
+impl DnsMessage {
+    /// Returns a recursive query message for the `name`.
+    pub fn query(id: u16, name: &str, qtype: u16) -> DnsMessage {
+        DnsMessage {
+            id: id,
+            flags: FLAG_RD,
+            questions: vec![
+                DnsQuestion {
+                    name: name.to_owned(),
+                    qtype: qtype,
+                    qclass: CLASS_IN,
+                },
+            ],
+            answers: Vec::new(),
+            authorities: Vec::new(),
+            additionals: Vec::new(),
+        }
+    }
+
+    /// Returns true if this is a response message.
+    pub fn is_response(&self) -> bool {
+        (self.flags & FLAG_QR) != 0
+    }
+
+    /// Returns true if this message is truncated.
+    pub fn is_truncated(&self) -> bool {
+        (self.flags & FLAG_TC) != 0
+    }
+
+    /// Returns a response code.
+    pub fn rcode(&self) -> u8 {
+        (self.flags & 0x000F) as u8
+    }
+
+    /// Returns a wire-format bytes of the message.
+    ///
+    /// Returns a `InvalidInput` error if the message contains a invalid domain name.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use asyncio::ip::dns::{DnsMessage, TYPE_A};
+    ///
+    /// let msg = DnsMessage::query(1, "example.com", TYPE_A);
+    /// let buf = msg.to_bytes().unwrap();
+    /// assert_eq!(DnsMessage::from_bytes(&buf), Some(msg));
+    /// ```
+    pub fn to_bytes(&self) -> io::Result<Vec<u8>> {
+        let mut buf = Vec::with_capacity(MAX_UDP_LEN);
+        put_u16(&mut buf, self.id);
+        put_u16(&mut buf, self.flags);
+        put_u16(&mut buf, self.questions.len() as u16);
+        put_u16(&mut buf, self.answers.len() as u16);
+        put_u16(&mut buf, self.authorities.len() as u16);
+        put_u16(&mut buf, self.additionals.len() as u16);
+        for q in &self.questions {
+            put_name(&mut buf, &q.name)?;
+            put_u16(&mut buf, q.qtype);
+            put_u16(&mut buf, q.qclass);
+        }
+        for rr in self.answers.iter().chain(&self.authorities).chain(
+            &self.additionals,
+        )
+        {
+            put_record(&mut buf, rr)?;
+        }
+        Ok(buf)
+    }
+
+    /// Returns a DNS message parsed from wire-format bytes.
+    ///
+    /// Returns `None` if the bytes is malformed.
+    pub fn from_bytes(buf: &[u8]) -> Option<DnsMessage> {
+        if buf.len() < HEADER_LEN {
+            return None;
+        }
+        let mut rd = Reader { buf: buf, pos: 0 };
+        let id = rd.u16()?;
+        let flags = rd.u16()?;
+        let qdcount = rd.u16()?;
+        let ancount = rd.u16()?;
+        let nscount = rd.u16()?;
+        let arcount = rd.u16()?;
+        let mut questions = Vec::new();
+        for _ in 0..qdcount {
+            questions.push(DnsQuestion {
+                name: rd.name()?,
+                qtype: rd.u16()?,
+                qclass: rd.u16()?,
+            });
+        }
+        let answers = rd.records(ancount)?;
+        let authorities = rd.records(nscount)?;
+        let additionals = rd.records(arcount)?;
+        Some(DnsMessage {
+            id: id,
+            flags: flags,
+            questions: questions,
+            answers: answers,
+            authorities: authorities,
+            additionals: additionals,
+        })
+    }
+}
+
+fn put_u16(buf: &mut Vec<u8>, val: u16) {
+    buf.push((val >> 8) as u8);
+    buf.push(val as u8);
+}
+
+fn put_u32(buf: &mut Vec<u8>, val: u32) {
+    put_u16(buf, (val >> 16) as u16);
+    put_u16(buf, val as u16);
+}
+
+fn put_name(buf: &mut Vec<u8>, name: &str) -> io::Result<()> {
+    let name = name.trim_end_matches('.');
+    let mut len = 1;
+    if !name.is_empty() {
+        for label in name.split('.') {
+            if label.is_empty() || label.len() > MAX_LABEL_LEN {
+                return Err(INVALID_ARGUMENT.into());
+            }
+            len += label.len() + 1;
+            buf.push(label.len() as u8);
+            buf.extend_from_slice(label.as_bytes());
+        }
+    }
+    if len > MAX_NAME_LEN {
+        return Err(NAME_TOO_LONG.into());
+    }
+    buf.push(0);
+    Ok(())
+}
+
+fn put_record(buf: &mut Vec<u8>, rr: &DnsRecord) -> io::Result<()> {
+    put_name(buf, &rr.name)?;
+    put_u16(buf, rr.rtype);
+    put_u16(buf, rr.class);
+    put_u32(buf, rr.ttl);
+    let pos = buf.len();
+    put_u16(buf, 0);
+    match rr.data {
+        DnsData::A(ref addr) => buf.extend_from_slice(addr.as_bytes()),
+        DnsData::Aaaa(ref addr) => buf.extend_from_slice(addr.as_bytes()),
+        DnsData::Ns(ref name) |
+        DnsData::Cname(ref name) |
+        DnsData::Ptr(ref name) => put_name(buf, name)?,
+        DnsData::Txt(ref txt) => {
+            for s in txt {
+                if s.len() > 255 {
+                    return Err(INVALID_ARGUMENT.into());
+                }
+                buf.push(s.len() as u8);
+                buf.extend_from_slice(s);
+            }
+        }
+        DnsData::Srv {
+            priority,
+            weight,
+            port,
+            ref target,
+        } => {
+            put_u16(buf, priority);
+            put_u16(buf, weight);
+            put_u16(buf, port);
+            put_name(buf, target)?;
+        }
+        DnsData::Other(ref data) => buf.extend_from_slice(data),
+    }
+    let len = buf.len() - pos - 2;
+    if len > 0xFFFF {
+        return Err(INVALID_ARGUMENT.into());
+    }
+    buf[pos] = (len >> 8) as u8;
+    buf[pos + 1] = len as u8;
+    Ok(())
+}
+
+struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        let buf = self.buf.get(self.pos..self.pos + len)?;
+        self.pos += len;
+        Some(buf)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.bytes(1).map(|buf| buf[0])
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        self.bytes(2).map(|buf| (buf[0] as u16) << 8 | buf[1] as u16)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some((self.u16()? as u32) << 16 | self.u16()? as u32)
+    }
+
+    fn name(&mut self) -> Option<String> {
+        let mut name = String::new();
+        let mut pos = self.pos;
+        let mut end = None;
+        let mut jumps = 0;
+        loop {
+            let len = *self.buf.get(pos)? as usize;
+            match len & 0xC0 {
+                0xC0 => {
+                    // compression pointer
+                    let ptr = (len & 0x3F) << 8 | *self.buf.get(pos + 1)? as usize;
+                    if end.is_none() {
+                        end = Some(pos + 2);
+                    }
+                    jumps += 1;
+                    if jumps > MAX_POINTERS {
+                        return None;
+                    }
+                    pos = ptr;
+                }
+                0x00 if len == 0 => {
+                    pos += 1;
+                    break;
+                }
+                0x00 => {
+                    let label = self.buf.get(pos + 1..pos + 1 + len)?;
+                    if !name.is_empty() {
+                        name.push('.');
+                    }
+                    name.push_str(&String::from_utf8_lossy(label));
+                    if name.len() > MAX_NAME_LEN {
+                        return None;
+                    }
+                    pos += 1 + len;
+                }
+                _ => return None,
+            }
+        }
+        self.pos = end.unwrap_or(pos);
+        Some(name)
+    }
+
+    fn records(&mut self, count: u16) -> Option<Vec<DnsRecord>> {
+        let mut vec = Vec::new();
+        for _ in 0..count {
+            vec.push(self.record()?);
+        }
+        Some(vec)
+    }
+
+    fn record(&mut self) -> Option<DnsRecord> {
+        let name = self.name()?;
+        let rtype = self.u16()?;
+        let class = self.u16()?;
+        let ttl = self.u32()?;
+        let len = self.u16()? as usize;
+        let end = self.pos + len;
+        if end > self.buf.len() {
+            return None;
+        }
+        let data = match rtype {
+            TYPE_A if len == 4 => {
+                let buf = self.bytes(4)?;
+                DnsData::A(IpAddrV4::new(buf[0], buf[1], buf[2], buf[3]))
+            }
+            TYPE_AAAA if len == 16 => {
+                let mut bytes = [0; 16];
+                bytes.copy_from_slice(self.bytes(16)?);
+                DnsData::Aaaa(IpAddrV6::from(bytes, 0))
+            }
+            TYPE_NS => DnsData::Ns(self.name()?),
+            TYPE_CNAME => DnsData::Cname(self.name()?),
+            TYPE_PTR => DnsData::Ptr(self.name()?),
+            TYPE_TXT => {
+                let mut txt = Vec::new();
+                while self.pos < end {
+                    let len = self.u8()? as usize;
+                    txt.push(self.bytes(len)?.to_vec());
+                }
+                DnsData::Txt(txt)
+            }
+            TYPE_SRV => {
+                DnsData::Srv {
+                    priority: self.u16()?,
+                    weight: self.u16()?,
+                    port: self.u16()?,
+                    target: self.name()?,
+                }
+            }
+            _ => DnsData::Other(self.bytes(len)?.to_vec()),
+        };
+        if self.pos != end {
+            return None;
+        }
+        Some(DnsRecord {
+            name: name,
+            rtype: rtype,
+            class: class,
+            ttl: ttl,
+            data: data,
+        })
+    }
+}
+
+/// The resolver configuration of `/etc/resolv.conf`.
+#[derive(Clone, Debug)]
+pub struct ResolvConf {
+    /// The name servers to query, up to 3 servers.
+    pub nameservers: Vec<IpAddr>,
+
+    /// The search list for host-name lookup.
+    pub search: Vec<String>,
+
+    /// The number of dots which must appear in a name before an initial absolute query.
+    pub ndots: usize,
+
+    /// The amount of time to wait for a response from a name server.
+    pub timeout: Duration,
+
+    /// The number of times to send queries to each name server.
+    pub attempts: usize,
+}
+
+impl ResolvConf {
+    /// Returns a configuration parsed from the contents of `resolv.conf`.
+    ///
+    /// Unknown or malformed lines are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use asyncio::ip::IpAddrV4;
+    /// use asyncio::ip::dns::ResolvConf;
+    ///
+    /// let conf = ResolvConf::parse("nameserver 192.168.0.1\noptions timeout:2");
+    /// assert_eq!(conf.nameservers, vec![IpAddrV4::new(192,168,0,1).into()]);
+    /// assert_eq!(conf.timeout, Duration::new(2, 0));
+    /// ```
+    pub fn parse(text: &str) -> ResolvConf {
+        let mut conf = ResolvConf::default();
+        conf.nameservers.clear();
+        for line in text.lines() {
+            let mut it = line.split(|c| c == ' ' || c == '\t').filter(|s| !s.is_empty());
+            match it.next() {
+                Some("nameserver") => {
+                    if let Some(Ok(addr)) = it.next().map(|s| s.parse()) {
+                        if conf.nameservers.len() < 3 {
+                            conf.nameservers.push(addr);
+                        }
+                    }
+                }
+                Some("domain") => conf.search = it.take(1).map(|s| s.to_owned()).collect(),
+                Some("search") => conf.search = it.map(|s| s.to_owned()).collect(),
+                Some("options") => {
+                    for opt in it {
+                        let mut kv = opt.splitn(2, ':');
+                        match (kv.next(), kv.next().and_then(|s| s.parse::<u64>().ok())) {
+                            (Some("ndots"), Some(n)) => conf.ndots = cmp::min(n, 15) as usize,
+                            (Some("timeout"), Some(n)) => {
+                                conf.timeout = Duration::new(cmp::max(n, 1), 0)
+                            }
+                            (Some("attempts"), Some(n)) => {
+                                conf.attempts = cmp::max(cmp::min(n, 5), 1) as usize
+                            }
+                            _ => (),
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+        if conf.nameservers.is_empty() {
+            conf.nameservers = ResolvConf::default().nameservers;
+        }
+        conf
+    }
+
+    /// Returns a configuration loaded from `/etc/resolv.conf`.
+    ///
+    /// Returns a default configuration if the file does not exist.
+    pub fn load() -> io::Result<ResolvConf> {
+        let mut text = String::new();
+        match File::open("/etc/resolv.conf") {
+            Ok(mut file) => {
+                file.read_to_string(&mut text)?;
+                Ok(ResolvConf::parse(&text))
+            }
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => Ok(ResolvConf::default()),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Returns a list of fully-qualified names to query for the `name` in order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use asyncio::ip::dns::ResolvConf;
+    ///
+    /// let conf = ResolvConf::parse("search example.com");
+    /// assert_eq!(conf.candidates("www"), vec!["www.example.com", "www"]);
+    /// assert_eq!(conf.candidates("www.rust-lang.org"), vec!["www.rust-lang.org", "www.rust-lang.org.example.com"]);
+    /// assert_eq!(conf.candidates("localhost."), vec!["localhost"]);
+    /// ```
+    pub fn candidates(&self, name: &str) -> Vec<String> {
+        if name.ends_with('.') {
+            return vec![name.trim_end_matches('.').to_owned()];
+        }
+        let search = self.search.iter().map(|domain| {
+            format!("{}.{}", name, domain.trim_end_matches('.'))
+        });
+        if name.matches('.').count() >= self.ndots {
+            Some(name.to_owned()).into_iter().chain(search).collect()
+        } else {
+            search.chain(Some(name.to_owned())).collect()
+        }
+    }
+}
+
+impl Default for ResolvConf {
+    fn default() -> ResolvConf {
+        ResolvConf {
+            nameservers: vec![IpAddr::V4(IpAddrV4::loopback())],
+            search: Vec::new(),
+            ndots: 1,
+            timeout: Duration::new(5, 0),
+            attempts: 2,
+        }
+    }
+}
+
+/// The cache of DNS records which expires by the TTLs.
+pub struct DnsCache {
+    entries: Mutex<HashMap<(String, u16), (Instant, Vec<DnsRecord>)>>,
+}
+
+impl DnsCache {
+    /// Returns a empty cache.
+    pub fn new() -> DnsCache {
+        DnsCache { entries: Mutex::new(HashMap::new()) }
+    }
+
+    /// Returns a cached records if not expired.
+    pub fn get(&self, name: &str, qtype: u16) -> Option<Vec<DnsRecord>> {
+        let key = (name.to_lowercase(), qtype);
+        let mut entries = self.entries.lock().unwrap();
+        let expired = match entries.get(&key) {
+            Some(&(expiry, ref records)) if expiry > Instant::now() => return Some(records.clone()),
+            Some(_) => true,
+            None => false,
+        };
+        if expired {
+            entries.remove(&key);
+        }
+        None
+    }
+
+    /// Inserts a records, which expires at the minimum TTL of the records.
+    ///
+    /// The records are not cached if the minimum TTL is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use asyncio::ip::IpAddrV4;
+    /// use asyncio::ip::dns::{DnsCache, DnsData, DnsRecord, TYPE_A};
+    ///
+    /// let cache = DnsCache::new();
+    /// let rr = DnsRecord::new("example.com", 300, DnsData::A(IpAddrV4::new(192,0,2,1)));
+    /// cache.insert("example.com", TYPE_A, vec![rr.clone()]);
+    /// assert_eq!(cache.get("EXAMPLE.COM", TYPE_A), Some(vec![rr]));
+    /// ```
+    pub fn insert(&self, name: &str, qtype: u16, records: Vec<DnsRecord>) {
+        let ttl = records.iter().map(|rr| rr.ttl).min().unwrap_or(0);
+        if ttl > 0 {
+            let expiry = Instant::now() + Duration::new(ttl as u64, 0);
+            self.entries.lock().unwrap().insert(
+                (name.to_lowercase(), qtype),
+                (expiry, records),
+            );
+        }
+    }
+
+    /// Removes all records.
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear()
+    }
+}
+
+impl Default for DnsCache {
+    fn default() -> DnsCache {
+        DnsCache::new()
+    }
+}
+
+struct DnsResolverImpl {
+    ctx: IoContext,
+    conf: ResolvConf,
+    cache: DnsCache,
+    next_id: AtomicUsize,
+}
+
+/// The asynchronous DNS stub resolver.
+///
+/// The queries are sent to the name servers of `ResolvConf` in order with retransmission timers,
+/// and are retried over TCP if the response is truncated.
+/// The answers are cached until the TTLs are expired.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use std::io;
+/// use std::sync::Arc;
+/// use asyncio::{IoContext, wrap};
+/// use asyncio::ip::dns::{DnsResolver, DnsRecord, DnsData, TYPE_A};
+///
+/// fn on_query(_: Arc<DnsResolver>, res: io::Result<Vec<DnsRecord>>) {
+///     if let Ok(records) = res {
+///         for rr in records {
+///             if let DnsData::A(addr) = rr.data {
+///                 println!("{}", addr);
+///             }
+///         }
+///     }
+/// }
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let res = Arc::new(DnsResolver::new(ctx).unwrap());
+/// res.async_query("www.example.com", TYPE_A, wrap(&res, on_query));
+/// ctx.run();
+/// ```
+#[derive(Clone)]
+pub struct DnsResolver {
+    inner: Arc<DnsResolverImpl>,
+}
+
+impl DnsResolver {
+    /// Returns a resolver configured by `/etc/resolv.conf`.
+    pub fn new(ctx: &IoContext) -> io::Result<DnsResolver> {
+        Ok(DnsResolver::with_conf(ctx, ResolvConf::load()?))
+    }
+
+    /// Returns a resolver with the configuration.
+    pub fn with_conf(ctx: &IoContext, conf: ResolvConf) -> DnsResolver {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.subsec_nanos())
+            .unwrap_or(0);
+        DnsResolver {
+            inner: Arc::new(DnsResolverImpl {
+                ctx: ctx.clone(),
+                conf: conf,
+                cache: DnsCache::new(),
+                next_id: AtomicUsize::new((nanos ^ process::id()) as usize),
+            }),
+        }
+    }
+
+    /// Returns a configuration of the resolver.
+    pub fn conf(&self) -> &ResolvConf {
+        &self.inner.conf
+    }
+
+    /// Returns a cache of the resolver.
+    pub fn cache(&self) -> &DnsCache {
+        &self.inner.cache
+    }
+
+    /// Asynchronously queries the records of `qtype` for the `name`.
+    ///
+    /// The handler is completed with the answer records of `qtype`.
+    /// The name is tried with the search list of the `ResolvConf`,
+    /// and returns `HOST_NOT_FOUND` error if no records are found for all of the names.
+    pub fn async_query<F>(&self, name: &str, qtype: u16, handler: F) -> F::Output
+    where
+        F: Handler<Vec<DnsRecord>, io::Error>,
+    {
+        handler.wrap(&self.inner.ctx, move |ctx, handler| {
+            if let Some(records) = self.inner.cache.get(name, qtype) {
+                return ctx.do_dispatch(Success::new(records, handler));
+            }
+            let query = match DnsQuery::new(&self.inner, name, qtype) {
+                Ok(query) => query,
+                Err(err) => return ctx.do_dispatch(Failure::new(err, handler)),
+            };
+            match query.send_udp() {
+                Ok(soc) => {
+                    DnsQuery::start_timer(&query);
+                    query.async_receive_udp(
+                        soc,
+                        DnsUdpReceive {
+                            query: query.clone(),
+                            handler: handler,
+                        },
+                    )
+                }
+                Err(err) => ctx.do_dispatch(Failure::new(err, handler)),
+            }
+        })
+    }
+}
+
+unsafe impl AsIoContext for DnsResolver {
+    fn as_ctx(&self) -> &IoContext {
+        &self.inner.ctx
+    }
+}
+
+struct DnsState {
+    name: usize,
+    tries: usize,
+    id: u16,
+    server: UdpEndpoint,
+    req: Vec<u8>,
+    buf: Vec<u8>,
+    len: usize,
+    not_found: bool,
+    servfail: bool,
+}
+
+struct DnsQuery {
+    resolver: Arc<DnsResolverImpl>,
+    key: String,
+    qtype: u16,
+    names: Vec<String>,
+    udp_v4: Option<UdpSocket>,
+    udp_v6: Option<UdpSocket>,
+    tcp: Mutex<Option<Arc<TcpSocket>>>,
+    timer: SteadyTimer,
+    timer_id: AtomicUsize,
+    expired: AtomicBool,
+    state: UnsafeCell<DnsState>,
+}
+
+unsafe impl Send for DnsQuery {}
+
+unsafe impl Sync for DnsQuery {}
+
+impl DnsQuery {
+    fn new(resolver: &Arc<DnsResolverImpl>, name: &str, qtype: u16) -> io::Result<Arc<Self>> {
+        let ctx = &resolver.ctx;
+        let conf = &resolver.conf;
+        let names = conf.candidates(name);
+        if names.iter().any(|name| name.len() > MAX_NAME_LEN) {
+            return Err(NAME_TOO_LONG.into());
+        }
+        let udp_v4 = if conf.nameservers.iter().any(|addr| match *addr {
+            IpAddr::V4(_) => true,
+            IpAddr::V6(_) => false,
+        })
+        {
+            UdpSocket::new(ctx, Udp::v4()).ok()
+        } else {
+            None
+        };
+        let udp_v6 = if conf.nameservers.iter().any(|addr| match *addr {
+            IpAddr::V4(_) => false,
+            IpAddr::V6(_) => true,
+        })
+        {
+            UdpSocket::new(ctx, Udp::v6()).ok()
+        } else {
+            None
+        };
+        if udp_v4.is_none() && udp_v6.is_none() {
+            return Err(INVALID_ARGUMENT.into());
+        }
+        Ok(Arc::new(DnsQuery {
+            resolver: resolver.clone(),
+            key: name.to_owned(),
+            qtype: qtype,
+            names: names,
+            udp_v4: udp_v4,
+            udp_v6: udp_v6,
+            tcp: Mutex::new(None),
+            timer: SteadyTimer::new(ctx),
+            timer_id: AtomicUsize::new(0),
+            expired: AtomicBool::new(false),
+            state: UnsafeCell::new(DnsState {
+                name: 0,
+                tries: 0,
+                id: 0,
+                server: UdpEndpoint::new(IpAddrV4::any(), PORT),
+                req: Vec::new(),
+                buf: Vec::new(),
+                len: 0,
+                not_found: false,
+                servfail: false,
+            }),
+        }))
+    }
+
+    fn state(&self) -> &mut DnsState {
+        unsafe { &mut *self.state.get() }
+    }
+
+    fn udp(&self, addr: &IpAddr) -> Option<&UdpSocket> {
+        match *addr {
+            IpAddr::V4(_) => self.udp_v4.as_ref(),
+            IpAddr::V6(_) => self.udp_v6.as_ref(),
+        }
+    }
+
+    fn exhausted(&self) -> io::Error {
+        let st = self.state();
+        if st.servfail {
+            HOST_NOT_FOUND_TRY_AGAIN.into()
+        } else {
+            TIMED_OUT.into()
+        }
+    }
+
+    /// Sends the query to the next name server.
+    fn send_udp(&self) -> io::Result<&UdpSocket> {
+        let conf = &self.resolver.conf;
+        let st = self.state();
+        let mut res = Err(self.exhausted());
+        while st.tries < conf.nameservers.len() * conf.attempts {
+            let addr = conf.nameservers[st.tries % conf.nameservers.len()].clone();
+            st.tries += 1;
+            let soc = match self.udp(&addr) {
+                Some(soc) => soc,
+                None => continue,
+            };
+            st.id = self.resolver.next_id.fetch_add(1, Ordering::SeqCst) as u16;
+            st.req = DnsMessage::query(st.id, &self.names[st.name], self.qtype)
+                .to_bytes()?;
+            st.server = UdpEndpoint::new(addr, PORT);
+            match soc.send_to(&st.req, 0, &st.server) {
+                Ok(_) => return Ok(soc),
+                Err(err) => res = Err(err),
+            }
+        }
+        res
+    }
+
+    /// Moves to the next name of the search list.
+    fn next_name(&self) -> bool {
+        let st = self.state();
+        st.not_found = true;
+        if st.name + 1 < self.names.len() {
+            st.name += 1;
+            st.tries = 0;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn start_timer(query: &Arc<DnsQuery>) {
+        let id = query.timer_id.fetch_add(1, Ordering::SeqCst) + 1;
+        query.expired.store(false, Ordering::SeqCst);
+        query.timer.expires_from_now(query.resolver.conf.timeout);
+        query.timer.async_wait(DnsTimeout {
+            query: query.clone(),
+            id: id,
+        });
+    }
+
+    fn stop_timer(&self) {
+        self.timer_id.fetch_add(1, Ordering::SeqCst);
+        self.timer.cancel();
+    }
+
+    fn async_receive_udp<F>(&self, soc: &UdpSocket, handler: DnsUdpReceive<F>)
+    where
+        F: Complete<Vec<DnsRecord>, io::Error>,
+    {
+        let st = self.state();
+        st.buf.resize(MAX_UDP_LEN, 0);
+        soc.async_receive_from(&mut st.buf, 0, handler)
+    }
+
+    fn async_receive_tcp<F>(&self, handler: DnsTcpReceive<F>)
+    where
+        F: Complete<Vec<DnsRecord>, io::Error>,
+    {
+        let st = self.state();
+        let soc = handler.soc.clone();
+        soc.async_receive(&mut st.buf[st.len..], 0, handler)
+    }
+
+    fn cancel(&self) {
+        if let Some(ref soc) = self.udp_v4 {
+            soc.cancel();
+        }
+        if let Some(ref soc) = self.udp_v6 {
+            soc.cancel();
+        }
+        if let Some(ref soc) = *self.tcp.lock().unwrap() {
+            soc.cancel();
+        }
+    }
+}
+
+/// Retries the query with the next name server over UDP.
+fn retry<F>(query: Arc<DnsQuery>, this: &mut ThreadIoContext, handler: F)
+where
+    F: Complete<Vec<DnsRecord>, io::Error>,
+{
+    query.stop_timer();
+    match query.send_udp() {
+        Ok(soc) => {
+            this.decrease_outstanding_work();
+            DnsQuery::start_timer(&query);
+            query.async_receive_udp(
+                soc,
+                DnsUdpReceive {
+                    query: query.clone(),
+                    handler: handler,
+                },
+            )
+        }
+        Err(err) => handler.failure(this, err),
+    }
+}
+
+/// Retries the query with the same name server over TCP.
+fn retry_tcp<F>(query: Arc<DnsQuery>, this: &mut ThreadIoContext, handler: F)
+where
+    F: Complete<Vec<DnsRecord>, io::Error>,
+{
+    query.stop_timer();
+    let ep = {
+        let st = query.state();
+        let len = st.req.len();
+        st.req.insert(0, (len >> 8) as u8);
+        st.req.insert(1, len as u8);
+        st.len = 0;
+        TcpEndpoint::new(st.server.addr(), PORT)
+    };
+    match TcpSocket::new(&query.resolver.ctx, ep.protocol()) {
+        Ok(soc) => {
+            // The previous socket is kept alive by the handler of its pending operation.
+            let soc = Arc::new(soc);
+            *query.tcp.lock().unwrap() = Some(soc.clone());
+            this.decrease_outstanding_work();
+            DnsQuery::start_timer(&query);
+            soc.async_connect(
+                &ep,
+                DnsTcpConnect {
+                    query: query.clone(),
+                    soc: soc.clone(),
+                    handler: handler,
+                },
+            )
+        }
+        Err(_) => retry(query, this, handler),
+    }
+}
+
+fn response<F>(
+    query: Arc<DnsQuery>,
+    this: &mut ThreadIoContext,
+    msg: DnsMessage,
+    tcp: bool,
+    handler: F,
+) where
+    F: Complete<Vec<DnsRecord>, io::Error>,
+{
+    if msg.is_truncated() && !tcp {
+        return retry_tcp(query, this, handler);
+    }
+    match msg.rcode() {
+        RCODE_NOERROR => {
+            let records: Vec<_> = msg.answers
+                .into_iter()
+                .filter(|rr| rr.rtype == query.qtype)
+                .collect();
+            if !records.is_empty() {
+                query.stop_timer();
+                query.resolver.cache.insert(
+                    &query.key,
+                    query.qtype,
+                    records.clone(),
+                );
+                handler.success(this, records)
+            } else if query.next_name() {
+                retry(query, this, handler)
+            } else {
+                query.stop_timer();
+                handler.failure(this, HOST_NOT_FOUND.into())
+            }
+        }
+        RCODE_NXDOMAIN => {
+            if query.next_name() {
+                retry(query, this, handler)
+            } else {
+                query.stop_timer();
+                handler.failure(this, HOST_NOT_FOUND.into())
+            }
+        }
+        RCODE_SERVFAIL | RCODE_REFUSED => {
+            query.state().servfail = true;
+            retry(query, this, handler)
+        }
+        _ => {
+            query.stop_timer();
+            handler.failure(this, NO_RECOVERY.into())
+        }
+    }
+}
+
+struct DnsUdpReceive<F> {
+    query: Arc<DnsQuery>,
+    handler: F,
+}
+
+impl<F> Handler<(usize, UdpEndpoint), io::Error> for DnsUdpReceive<F>
+where
+    F: Complete<Vec<DnsRecord>, io::Error>,
+{
+    type Output = ();
+
+    type WrappedHandler = Self;
+
+    fn wrap<W>(self, ctx: &IoContext, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx, self)
+    }
+
+    fn wrap_timeout<W>(self, ctx: &Cancel, _: &Timeout, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx.as_ctx(), self)
+    }
+}
+
+impl<F> Complete<(usize, UdpEndpoint), io::Error> for DnsUdpReceive<F>
+where
+    F: Complete<Vec<DnsRecord>, io::Error>,
+{
+    fn success(self, this: &mut ThreadIoContext, res: (usize, UdpEndpoint)) {
+        let (len, ep) = res;
+        let DnsUdpReceive { query, handler } = self;
+        let msg = {
+            let st = query.state();
+            match DnsMessage::from_bytes(&st.buf[..len]) {
+                Some(msg) if msg.id == st.id && msg.is_response() && ep == st.server => msg,
+                _ => {
+                    // ignores the unexpected datagram
+                    this.decrease_outstanding_work();
+                    let soc = query.udp(&ep.addr()).unwrap();
+                    return query.async_receive_udp(
+                        soc,
+                        DnsUdpReceive {
+                            query: query.clone(),
+                            handler: handler,
+                        },
+                    );
+                }
+            }
+        };
+        response(query, this, msg, false, handler)
+    }
+
+    fn failure(self, this: &mut ThreadIoContext, err: io::Error) {
+        if self.query.expired.load(Ordering::SeqCst) {
+            retry(self.query, this, self.handler)
+        } else {
+            self.query.stop_timer();
+            self.handler.failure(this, err)
+        }
+    }
+}
+
+struct DnsTcpConnect<F> {
+    query: Arc<DnsQuery>,
+    soc: Arc<TcpSocket>,
+    handler: F,
+}
+
+impl<F> Handler<(), io::Error> for DnsTcpConnect<F>
+where
+    F: Complete<Vec<DnsRecord>, io::Error>,
+{
+    type Output = ();
+
+    type WrappedHandler = Self;
+
+    fn wrap<W>(self, ctx: &IoContext, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx, self)
+    }
+
+    fn wrap_timeout<W>(self, ctx: &Cancel, _: &Timeout, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx.as_ctx(), self)
+    }
+}
+
+impl<F> Complete<(), io::Error> for DnsTcpConnect<F>
+where
+    F: Complete<Vec<DnsRecord>, io::Error>,
+{
+    fn success(self, this: &mut ThreadIoContext, _: ()) {
+        this.decrease_outstanding_work();
+        let query = self.query.clone();
+        let st = query.state();
+        let soc = self.soc.clone();
+        soc.async_send(
+            &st.req,
+            0,
+            DnsTcpSend {
+                query: self.query,
+                soc: self.soc,
+                handler: self.handler,
+            },
+        )
+    }
+
+    fn failure(self, this: &mut ThreadIoContext, _: io::Error) {
+        retry(self.query, this, self.handler)
+    }
+}
+
+struct DnsTcpSend<F> {
+    query: Arc<DnsQuery>,
+    soc: Arc<TcpSocket>,
+    handler: F,
+}
+
+impl<F> Handler<usize, io::Error> for DnsTcpSend<F>
+where
+    F: Complete<Vec<DnsRecord>, io::Error>,
+{
+    type Output = ();
+
+    type WrappedHandler = Self;
+
+    fn wrap<W>(self, ctx: &IoContext, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx, self)
+    }
+
+    fn wrap_timeout<W>(self, ctx: &Cancel, _: &Timeout, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx.as_ctx(), self)
+    }
+}
+
+impl<F> Complete<usize, io::Error> for DnsTcpSend<F>
+where
+    F: Complete<Vec<DnsRecord>, io::Error>,
+{
+    fn success(self, this: &mut ThreadIoContext, len: usize) {
+        this.decrease_outstanding_work();
+        let query = self.query.clone();
+        let st = query.state();
+        st.len += len;
+        if st.len < st.req.len() {
+            let soc = self.soc.clone();
+            soc.async_send(&st.req[st.len..], 0, self)
+        } else {
+            st.buf.clear();
+            st.buf.resize(2, 0);
+            st.len = 0;
+            query.async_receive_tcp(DnsTcpReceive {
+                query: self.query,
+                soc: self.soc,
+                handler: self.handler,
+            })
+        }
+    }
+
+    fn failure(self, this: &mut ThreadIoContext, _: io::Error) {
+        retry(self.query, this, self.handler)
+    }
+}
+
+struct DnsTcpReceive<F> {
+    query: Arc<DnsQuery>,
+    soc: Arc<TcpSocket>,
+    handler: F,
+}
+
+impl<F> Handler<usize, io::Error> for DnsTcpReceive<F>
+where
+    F: Complete<Vec<DnsRecord>, io::Error>,
+{
+    type Output = ();
+
+    type WrappedHandler = Self;
+
+    fn wrap<W>(self, ctx: &IoContext, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx, self)
+    }
+
+    fn wrap_timeout<W>(self, ctx: &Cancel, _: &Timeout, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx.as_ctx(), self)
+    }
+}
+
+impl<F> Complete<usize, io::Error> for DnsTcpReceive<F>
+where
+    F: Complete<Vec<DnsRecord>, io::Error>,
+{
+    fn success(self, this: &mut ThreadIoContext, len: usize) {
+        if len == 0 {
+            return retry(self.query, this, self.handler);
+        }
+        let query = self.query.clone();
+        let st = query.state();
+        st.len += len;
+        if st.len == 2 && st.buf.len() == 2 {
+            let total = 2 + ((st.buf[0] as usize) << 8 | st.buf[1] as usize);
+            st.buf.resize(total, 0);
+        }
+        if st.len < st.buf.len() {
+            this.decrease_outstanding_work();
+            return query.async_receive_tcp(self);
+        }
+        match DnsMessage::from_bytes(&st.buf[2..]) {
+            Some(msg) if msg.id == st.id && msg.is_response() => {
+                response(self.query, this, msg, true, self.handler)
+            }
+            _ => retry(self.query, this, self.handler),
+        }
+    }
+
+    fn failure(self, this: &mut ThreadIoContext, _: io::Error) {
+        retry(self.query, this, self.handler)
+    }
+}
+
+struct DnsTimeout {
+    query: Arc<DnsQuery>,
+    id: usize,
+}
+
+impl Handler<(), io::Error> for DnsTimeout {
+    type Output = ();
+
+    type WrappedHandler = Self;
+
+    fn wrap<W>(self, ctx: &IoContext, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx, self)
+    }
+
+    fn wrap_timeout<W>(self, ctx: &Cancel, _: &Timeout, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx.as_ctx(), self)
+    }
+}
+
+impl Complete<(), io::Error> for DnsTimeout {
+    fn success(self, this: &mut ThreadIoContext, _: ()) {
+        let query = &self.query;
+        if query.timer_id.load(Ordering::SeqCst) == self.id {
+            query.expired.store(true, Ordering::SeqCst);
+            query.cancel();
+        }
+        this.decrease_outstanding_work();
+    }
+
+    fn failure(self, this: &mut ThreadIoContext, _: io::Error) {
+        this.decrease_outstanding_work();
+    }
+}
+
+#[test]
+fn test_dns_message() {
+    let mut msg = DnsMessage::query(0x1234, "example.com.", TYPE_SRV);
+    msg.flags |= FLAG_QR;
+    msg.answers.push(DnsRecord::new(
+        "_http._tcp.example.com",
+        60,
+        DnsData::Srv {
+            priority: 10,
+            weight: 5,
+            port: 80,
+            target: "www.example.com".to_owned(),
+        },
+    ));
+    msg.additionals.push(DnsRecord::new(
+        "www.example.com",
+        60,
+        DnsData::A(IpAddrV4::new(192, 0, 2, 1)),
+    ));
+    msg.additionals.push(DnsRecord::new(
+        "www.example.com",
+        60,
+        DnsData::Aaaa(IpAddrV6::loopback()),
+    ));
+    msg.additionals.push(DnsRecord::new(
+        "example.com",
+        60,
+        DnsData::Txt(vec![b"v=spf1".to_vec(), b"-all".to_vec()]),
+    ));
+    msg.additionals.push(DnsRecord::new(
+        "1.2.0.192.in-addr.arpa",
+        60,
+        DnsData::Ptr("www.example.com".to_owned()),
+    ));
+    let buf = msg.to_bytes().unwrap();
+    assert_eq!(&buf[..6], &[0x12, 0x34, 0x81, 0x00, 0, 1]);
+    let res = DnsMessage::from_bytes(&buf).unwrap();
+    assert!(res.is_response());
+    assert!(!res.is_truncated());
+    assert_eq!(res.rcode(), RCODE_NOERROR);
+    assert_eq!(res.questions[0].name, "example.com");
+    assert_eq!(res.answers, msg.answers);
+    assert_eq!(res.additionals, msg.additionals);
+    assert_eq!(DnsMessage::from_bytes(&buf[..buf.len() - 1]), None);
+
+    let long = vec!["a"; 64].concat();
+    assert!(DnsMessage::query(0, &long, TYPE_A).to_bytes().is_err());
+}
+
+#[test]
+fn test_dns_name_compression() {
+    let buf = [
+        0, 1, 0x81, 0x80, 0, 1, 0, 1, 0, 0, 0, 0,
+        3, b'w', b'w', b'w', 7, b'e', b'x', b'a', b'm', b'p', b'l', b'e', 3, b'c', b'o', b'm', 0,
+        0, 5, 0, 1,
+        0xC0, 12, 0, 5, 0, 1, 0, 0, 0, 10, 0, 2, 0xC0, 16,
+    ];
+    let msg = DnsMessage::from_bytes(&buf).unwrap();
+    assert_eq!(msg.answers[0].name, "www.example.com");
+    assert_eq!(msg.answers[0].data, DnsData::Cname("example.com".to_owned()));
+
+    // pointer loop
+    let mut buf = buf.to_vec();
+    buf[45] = 0xC0;
+    buf[46] = 45;
+    assert_eq!(DnsMessage::from_bytes(&buf), None);
+}
+
+#[test]
+fn test_resolv_conf() {
+    let conf = ResolvConf::parse(
+        "# comment\n\
+         nameserver 8.8.8.8\n\
+         nameserver ::1\n\
+         nameserver localhost\n\
+         domain example.org\n\
+         search example.com example.net\n\
+         options ndots:2 timeout:3 attempts:9 rotate\n",
+    );
+    assert_eq!(
+        conf.nameservers,
+        vec![
+            IpAddr::V4(IpAddrV4::new(8, 8, 8, 8)),
+            IpAddr::V6(IpAddrV6::loopback()),
+        ]
+    );
+    assert_eq!(conf.search, vec!["example.com", "example.net"]);
+    assert_eq!(conf.ndots, 2);
+    assert_eq!(conf.timeout, Duration::new(3, 0));
+    assert_eq!(conf.attempts, 5);
+    assert_eq!(
+        conf.candidates("a.b"),
+        vec!["a.b.example.com", "a.b.example.net", "a.b"]
+    );
+
+    let conf = ResolvConf::parse("");
+    assert_eq!(conf.nameservers, vec![IpAddr::V4(IpAddrV4::loopback())]);
+}
+
+#[test]
+fn test_dns_cache() {
+    let cache = DnsCache::new();
+    let rr = DnsRecord::new("example.com", 0, DnsData::A(IpAddrV4::new(192, 0, 2, 1)));
+    cache.insert("example.com", TYPE_A, vec![rr]);
+    assert_eq!(cache.get("example.com", TYPE_A), None);
+
+    let rr = DnsRecord::new("example.com", 60, DnsData::A(IpAddrV4::new(192, 0, 2, 1)));
+    cache.insert("example.com", TYPE_A, vec![rr.clone()]);
+    assert_eq!(cache.get("example.com", TYPE_AAAA), None);
+    assert_eq!(cache.get("Example.Com", TYPE_A), Some(vec![rr]));
+    cache.clear();
+    assert_eq!(cache.get("example.com", TYPE_A), None);
+}
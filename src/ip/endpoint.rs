@@ -1,10 +1,13 @@
 use ffi::{AF_INET, AF_INET6, SockAddr, socklen_t, sockaddr, sockaddr_in, sockaddr_in6,
-          sockaddr_storage};
+          sockaddr_storage, INVALID_ARGUMENT, ADDRESS_FAMILY_NOT_SUPPORTED};
 use core::Endpoint;
 use ip::{IpProtocol, IpAddrV4, IpAddrV6, IpAddr};
 
+use std::io;
 use std::fmt;
 use std::mem;
+use std::net;
+use std::ptr;
 use std::marker::PhantomData;
 
 /// The endpoint of internet protocol.
@@ -100,6 +103,75 @@ where
         unreachable!("Invalid address family ({}).", self.ss.sa.ss_family);
     }
 
+    /// Returns a IpEndpoint from the raw bytes of the `struct sockaddr_in` or the
+    /// `struct sockaddr_in6`, that may be received from the untrusted sources.
+    ///
+    /// Returns the `EINVAL` error if the bytes are too short or too long for the address family,
+    /// or the `EAFNOSUPPORT` error if the address family is neither `AF_INET` nor `AF_INET6`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use asyncio::Endpoint;
+    /// use asyncio::ip::{IpAddrV4, TcpEndpoint};
+    ///
+    /// let ep = TcpEndpoint::new(IpAddrV4::loopback(), 80);
+    /// assert_eq!(TcpEndpoint::try_from_sockaddr(ep.as_bytes()).unwrap(), ep);
+    /// assert!(TcpEndpoint::try_from_sockaddr(&ep.as_bytes()[..8]).is_err());
+    /// ```
+    pub fn try_from_sockaddr(bytes: &[u8]) -> io::Result<Self> {
+        if bytes.len() < 2 || bytes.len() > mem::size_of::<sockaddr_storage>() {
+            return Err(INVALID_ARGUMENT.into());
+        }
+        let mut ss: sockaddr_storage = unsafe { mem::zeroed() };
+        unsafe {
+            ptr::copy_nonoverlapping(bytes.as_ptr(), &mut ss as *mut _ as *mut u8, bytes.len());
+        }
+        let len = match ss.ss_family as i32 {
+            AF_INET => mem::size_of::<sockaddr_in>(),
+            AF_INET6 => mem::size_of::<sockaddr_in6>(),
+            _ => return Err(ADDRESS_FAMILY_NOT_SUPPORTED.into()),
+        };
+        if bytes.len() != len {
+            return Err(INVALID_ARGUMENT.into());
+        }
+        Ok(IpEndpoint::from_ss(SockAddr::from(&ss, len as u8)))
+    }
+
+    /// Returns the IP-v4 endpoint, returns `None` if this is IpEndpoint of IP-v6 address.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use asyncio::ip::{IpAddrV4, IpAddrV6, TcpEndpoint};
+    ///
+    /// let ep = TcpEndpoint::new(IpAddrV4::loopback(), 80);
+    /// assert_eq!(ep.to_v4().unwrap().addr(), IpAddrV4::loopback());
+    /// assert!(TcpEndpoint::new(IpAddrV6::loopback(), 80).to_v4().is_none());
+    /// ```
+    pub fn to_v4(&self) -> Option<IpEndpointV4<P>> {
+        match self.addr() {
+            IpAddr::V4(addr) => Some(IpEndpointV4::new(addr, self.port())),
+            IpAddr::V6(_) => None,
+        }
+    }
+
+    /// Returns the IP-v6 endpoint with the flow info, returns `None` if this is IpEndpoint of
+    /// IP-v4 address.
+    pub fn to_v6(&self) -> Option<IpEndpointV6<P>> {
+        match self.addr() {
+            IpAddr::V4(_) => None,
+            IpAddr::V6(addr) => {
+                let sin6 = unsafe { &*(&self.ss.sa as *const _ as *const sockaddr_in6) };
+                Some(IpEndpointV6::with_flowinfo(
+                    addr,
+                    self.port(),
+                    u32::from_be(sin6.sin6_flowinfo),
+                ))
+            }
+        }
+    }
+
     #[doc(hidden)]
     pub fn from_ss(ss: SockAddr<sockaddr_storage>) -> Self {
         IpEndpoint {
@@ -147,7 +219,7 @@ impl<P: IpProtocol> fmt::Display for IpEndpoint<P> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self.addr() {
             IpAddr::V4(addr) => write!(f, "{}:{}", addr, self.port()),
-            IpAddr::V6(addr) => write!(f, "[{}]:{}", addr, self.port()),
+            IpAddr::V6(addr) => write!(f, "[{:#}]:{}", addr, self.port()),
         }
     }
 }
@@ -155,8 +227,19 @@ impl<P: IpProtocol> fmt::Display for IpEndpoint<P> {
 impl<P: IpProtocol> fmt::Debug for IpEndpoint<P> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self.addr() {
-            IpAddr::V4(addr) => write!(f, "{}:{}", addr, self.port()),
-            IpAddr::V6(addr) => write!(f, "[{}]:{}", addr, self.port()),
+            IpAddr::V4(addr) => write!(f, "{:?}:{}:{}", self.protocol(), addr, self.port()),
+            IpAddr::V6(addr) => {
+                let sin6 = unsafe { &*(&self.ss.sa as *const _ as *const sockaddr_in6) };
+                write!(
+                    f,
+                    "{:?}:[{}]:{} (flowinfo={}, scope_id={})",
+                    self.protocol(),
+                    addr,
+                    self.port(),
+                    u32::from_be(sin6.sin6_flowinfo),
+                    sin6.sin6_scope_id
+                )
+            }
         }
     }
 }
@@ -271,6 +354,221 @@ impl<P: IpProtocol> From<(IpAddrV6, u16)> for IpEndpoint<P> {
     }
 }
 
+impl<P: IpProtocol> From<net::SocketAddrV4> for IpEndpoint<P> {
+    fn from(sa: net::SocketAddrV4) -> Self {
+        IpEndpoint::from((IpAddrV4::from(*sa.ip()), sa.port()))
+    }
+}
+
+impl<P: IpProtocol> From<net::SocketAddrV6> for IpEndpoint<P> {
+    fn from(sa: net::SocketAddrV6) -> Self {
+        let addr = IpAddrV6::from(sa.ip().octets(), sa.scope_id());
+        let mut ep = IpEndpoint::from((addr, sa.port()));
+        unsafe {
+            let sin6 = &mut *(&mut ep.ss.sa as *mut _ as *mut sockaddr_in6);
+            sin6.sin6_flowinfo = sa.flowinfo().to_be();
+        }
+        ep
+    }
+}
+
+impl<P: IpProtocol> From<net::SocketAddr> for IpEndpoint<P> {
+    fn from(sa: net::SocketAddr) -> Self {
+        match sa {
+            net::SocketAddr::V4(sa) => IpEndpoint::from(sa),
+            net::SocketAddr::V6(sa) => IpEndpoint::from(sa),
+        }
+    }
+}
+
+/// Converts to the std's socket address, that keeps the flow info and the scope ID of IP-v6.
+impl<P: IpProtocol> From<IpEndpoint<P>> for net::SocketAddr {
+    fn from(ep: IpEndpoint<P>) -> Self {
+        match ep.ss.sa.ss_family as i32 {
+            AF_INET => {
+                let sin = unsafe { &*(&ep.ss.sa as *const _ as *const sockaddr_in) };
+                let bytes: [u8; 4] = unsafe { mem::transmute(sin.sin_addr) };
+                net::SocketAddr::V4(net::SocketAddrV4::new(
+                    bytes.into(),
+                    u16::from_be(sin.sin_port),
+                ))
+            }
+            AF_INET6 => {
+                let sin6 = unsafe { &*(&ep.ss.sa as *const _ as *const sockaddr_in6) };
+                let bytes: [u8; 16] = unsafe { mem::transmute(sin6.sin6_addr) };
+                net::SocketAddr::V6(net::SocketAddrV6::new(
+                    bytes.into(),
+                    u16::from_be(sin6.sin6_port),
+                    u32::from_be(sin6.sin6_flowinfo),
+                    sin6.sin6_scope_id,
+                ))
+            }
+            family => unreachable!("Invalid address family ({}).", family),
+        }
+    }
+}
+
+/// The endpoint of IP-v4 address, that is typed by the address family.
+///
+/// # Examples
+///
+/// ```
+/// use asyncio::ip::{IpAddrV4, IpEndpointV4, Udp, UdpEndpoint};
+///
+/// let ep: IpEndpointV4<Udp> = IpEndpointV4::new(IpAddrV4::loopback(), 53);
+/// let addr: IpAddrV4 = ep.addr();
+/// assert_eq!(UdpEndpoint::from(ep), UdpEndpoint::new(addr, 53));
+/// ```
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct IpEndpointV4<P> {
+    addr: IpAddrV4,
+    port: u16,
+    _marker: PhantomData<P>,
+}
+
+impl<P> IpEndpointV4<P>
+where
+    P: IpProtocol,
+{
+    /// Returns a IpEndpointV4 from IP-v4 address and port number.
+    pub fn new(addr: IpAddrV4, port: u16) -> Self {
+        IpEndpointV4 {
+            addr: addr,
+            port: port,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns a IP-v4 address.
+    pub fn addr(&self) -> IpAddrV4 {
+        self.addr
+    }
+
+    /// Returns a port number.
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Returns a IP-v4 protocol.
+    pub fn protocol(&self) -> P {
+        P::v4()
+    }
+}
+
+impl<P: IpProtocol> fmt::Display for IpEndpointV4<P> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.addr, self.port)
+    }
+}
+
+impl<P: IpProtocol> fmt::Debug for IpEndpointV4<P> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}:{}:{}", self.protocol(), self.addr, self.port)
+    }
+}
+
+impl<P: IpProtocol> From<IpEndpointV4<P>> for IpEndpoint<P> {
+    fn from(ep: IpEndpointV4<P>) -> Self {
+        IpEndpoint::from((ep.addr, ep.port))
+    }
+}
+
+/// The endpoint of IP-v6 address with the flow info, that is typed by the address family.
+///
+/// # Examples
+///
+/// ```
+/// use asyncio::ip::{IpAddrV6, IpEndpointV6, Tcp, TcpEndpoint};
+///
+/// let addr = IpAddrV6::with_scope_id(0xfe80, 0, 0, 0, 0, 0, 0, 1, 2);
+/// let ep: IpEndpointV6<Tcp> = IpEndpointV6::with_flowinfo(addr, 80, 0x12345);
+/// assert_eq!(ep.scope_id(), 2);
+/// assert_eq!(TcpEndpoint::from(ep).to_v6(), Some(ep));
+/// ```
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct IpEndpointV6<P> {
+    addr: IpAddrV6,
+    port: u16,
+    flowinfo: u32,
+    _marker: PhantomData<P>,
+}
+
+impl<P> IpEndpointV6<P>
+where
+    P: IpProtocol,
+{
+    /// Returns a IpEndpointV6 from IP-v6 address and port number.
+    pub fn new(addr: IpAddrV6, port: u16) -> Self {
+        Self::with_flowinfo(addr, port, 0)
+    }
+
+    /// Returns a IpEndpointV6 from IP-v6 address, port number and flow info.
+    pub fn with_flowinfo(addr: IpAddrV6, port: u16, flowinfo: u32) -> Self {
+        IpEndpointV6 {
+            addr: addr,
+            port: port,
+            flowinfo: flowinfo,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns a IP-v6 address.
+    pub fn addr(&self) -> IpAddrV6 {
+        self.addr
+    }
+
+    /// Returns a flow info in host byte order.
+    pub fn flowinfo(&self) -> u32 {
+        self.flowinfo
+    }
+
+    /// Returns a port number.
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Returns a IP-v6 protocol.
+    pub fn protocol(&self) -> P {
+        P::v6()
+    }
+
+    /// Returns a scope ID of the address.
+    pub fn scope_id(&self) -> u32 {
+        self.addr.scope_id()
+    }
+}
+
+impl<P: IpProtocol> fmt::Display for IpEndpointV6<P> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[{:#}]:{}", self.addr, self.port)
+    }
+}
+
+impl<P: IpProtocol> fmt::Debug for IpEndpointV6<P> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:?}:[{}]:{} (flowinfo={}, scope_id={})",
+            self.protocol(),
+            self.addr,
+            self.port,
+            self.flowinfo,
+            self.scope_id()
+        )
+    }
+}
+
+impl<P: IpProtocol> From<IpEndpointV6<P>> for IpEndpoint<P> {
+    fn from(ep: IpEndpointV6<P>) -> Self {
+        let mut res = IpEndpoint::from((ep.addr, ep.port));
+        unsafe {
+            let sin6 = &mut *(&mut res.ss.sa as *mut _ as *mut sockaddr_in6);
+            sin6.sin6_flowinfo = ep.flowinfo.to_be();
+        }
+        res
+    }
+}
+
 #[test]
 fn test_endpoint_v4() {
     use ip::UdpEndpoint;
@@ -305,3 +603,95 @@ fn test_endpoint_cmp() {
     assert!(a < b);
     assert!(b < c);
 }
+
+#[test]
+fn test_endpoint_debug() {
+    use ip::{TcpEndpoint, UdpEndpoint, IcmpEndpoint};
+
+    let ep = TcpEndpoint::new(IpAddrV4::new(1, 2, 3, 4), 10);
+    assert_eq!(format!("{:?}", ep), "tcp:1.2.3.4:10");
+    let ep = UdpEndpoint::new(IpAddrV6::with_scope_id(0xfe80, 0, 0, 0, 0, 0, 0, 1, 2), 10);
+    assert_eq!(format!("{:?}", ep), "udp6:[fe80::1]:10 (flowinfo=0, scope_id=2)");
+    let ep = IcmpEndpoint::new(IpAddrV6::loopback(), 0);
+    assert_eq!(format!("{:?}", ep), "icmpv6:[::1]:0 (flowinfo=0, scope_id=0)");
+}
+
+#[test]
+fn test_endpoint_raw() {
+    use ip::UdpEndpoint;
+
+    let ep = UdpEndpoint::new(IpAddrV6::loopback(), 10);
+    assert_eq!(ep.family(), AF_INET6);
+    assert_eq!(ep.as_bytes().len(), mem::size_of::<sockaddr_in6>());
+    let (family, bytes) = ep.to_owned_sockaddr();
+    assert_eq!(family, AF_INET6);
+    assert_eq!(&bytes[2..4], &[0, 10]);
+    assert_eq!(bytes[23], 1);
+}
+
+#[test]
+fn test_endpoint_std() {
+    use ip::{TcpEndpoint, UdpEndpoint};
+
+    let sa: net::SocketAddr = "1.2.3.4:10".parse().unwrap();
+    let ep = TcpEndpoint::from(sa);
+    assert_eq!(ep, TcpEndpoint::new(IpAddrV4::new(1, 2, 3, 4), 10));
+    assert_eq!(net::SocketAddr::from(ep), sa);
+
+    let sa = net::SocketAddrV6::new("fe80::1".parse().unwrap(), 10, 3, 2);
+    let ep = UdpEndpoint::from(sa);
+    assert_eq!(ep.addr(), IpAddr::V6(IpAddrV6::with_scope_id(0xfe80, 0, 0, 0, 0, 0, 0, 1, 2)));
+    assert_eq!(ep.port(), 10);
+    assert_eq!(net::SocketAddr::from(ep), net::SocketAddr::V6(sa));
+
+    let addr: net::IpAddr = IpAddr::V4(IpAddrV4::loopback()).into();
+    assert_eq!(addr, net::IpAddr::V4(net::Ipv4Addr::new(127, 0, 0, 1)));
+    let addr: net::Ipv6Addr = IpAddrV6::with_scope_id(0, 0, 0, 0, 0, 0, 0, 1, 2).into();
+    assert_eq!(addr, net::Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1));
+}
+
+#[test]
+fn test_endpoint_typed() {
+    use ip::{Tcp, TcpEndpoint, Udp};
+
+    let ep = IpEndpointV4::<Udp>::new(IpAddrV4::new(1, 2, 3, 4), 10);
+    assert_eq!(ep.protocol(), Udp::v4());
+    assert_eq!(format!("{}", ep), "1.2.3.4:10");
+    assert_eq!(format!("{:?}", ep), "udp:1.2.3.4:10");
+    assert_eq!(IpEndpoint::from(ep).to_v4(), Some(ep));
+    assert_eq!(IpEndpoint::from(ep).to_v6(), None);
+
+    let addr = IpAddrV6::with_scope_id(0xfe80, 0, 0, 0, 0, 0, 0, 1, 2);
+    let ep = IpEndpointV6::<Tcp>::with_flowinfo(addr, 10, 3);
+    assert_eq!(ep.protocol(), Tcp::v6());
+    assert_eq!(format!("{:?}", ep), "tcp6:[fe80::1]:10 (flowinfo=3, scope_id=2)");
+    let sa = net::SocketAddr::from(TcpEndpoint::from(ep));
+    let std = net::SocketAddrV6::new("fe80::1".parse().unwrap(), 10, 3, 2);
+    assert_eq!(sa, net::SocketAddr::V6(std));
+    assert_eq!(TcpEndpoint::from(sa).to_v6(), Some(ep));
+    assert_eq!(TcpEndpoint::from(ep).to_v4(), None);
+    assert_eq!(format!("{}", ep), format!("{}", TcpEndpoint::from(ep)));
+}
+
+#[test]
+fn test_endpoint_try_from_sockaddr() {
+    use ip::UdpEndpoint;
+
+    let v4 = UdpEndpoint::new(IpAddrV4::new(1, 2, 3, 4), 10);
+    let v6 = UdpEndpoint::new(IpAddrV6::with_scope_id(0xfe80, 0, 0, 0, 0, 0, 0, 1, 2), 10);
+    assert_eq!(UdpEndpoint::try_from_sockaddr(v4.as_bytes()).unwrap(), v4);
+    assert_eq!(UdpEndpoint::try_from_sockaddr(v6.as_bytes()).unwrap(), v6);
+
+    let einval = io::Error::from(INVALID_ARGUMENT).raw_os_error();
+    for bytes in &[&[][..], &[0], &v4.as_bytes()[..15], &v6.as_bytes()[..27], &[0; 129]] {
+        let err = UdpEndpoint::try_from_sockaddr(bytes).unwrap_err();
+        assert_eq!(err.raw_os_error(), einval);
+    }
+    let mut bytes = v4.to_owned_sockaddr().1;
+    bytes.extend_from_slice(&[0; 12]);
+    assert_eq!(UdpEndpoint::try_from_sockaddr(&bytes).unwrap_err().raw_os_error(), einval);
+
+    // The AF_UNIX.
+    let err = UdpEndpoint::try_from_sockaddr(&[1, 0, 1, 1]).unwrap_err();
+    assert_eq!(err.raw_os_error(), io::Error::from(ADDRESS_FAMILY_NOT_SUPPORTED).raw_os_error());
+}
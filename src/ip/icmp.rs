@@ -1,15 +1,37 @@
-use ffi::{AF_INET, AF_INET6, AF_UNSPEC, SOCK_RAW, IPPROTO_ICMP, IPPROTO_ICMPV6};
-use core::Protocol;
-use handler::Handler;
+//! The Internet Control Message Protocol and the echo (ping) helper.
+
+use ffi::{Timeout, AF_INET, AF_INET6, SOCK_RAW, IPPROTO_ICMP, IPPROTO_ICMPV6, TIMED_OUT};
+#[cfg(feature = "resolver")]
+use ffi::AF_UNSPEC;
+use core::{Protocol, IoContext, AsIoContext, ThreadIoContext, Cancel};
+use handler::{Handler, Complete, Failure};
 use dgram_socket::DgramSocket;
-use ip::{IpEndpoint, IpProtocol, Resolver, ResolverIter, ResolverQuery};
+use socket_base::Wait;
+use ip::{IpEndpoint, IpProtocol};
+#[cfg(feature = "resolver")]
+use ip::{Resolver, ResolverIter, ResolverQuery};
+#[cfg(feature = "resolver")]
+use ip::resolver::cache_key;
 
 use std::io;
 use std::fmt;
 use std::mem;
+use std::process;
+use std::cell::UnsafeCell;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+pub const ECHO_REPLY: u8 = 0;
+pub const ECHO_REQUEST: u8 = 8;
+pub const ECHO_REQUEST_V6: u8 = 128;
+pub const ECHO_REPLY_V6: u8 = 129;
+
+const HEADER_LEN: usize = 8;
+const PAYLOAD: &'static [u8] = b"asyncio-ping-0123456789abcdefghi";
 
 /// The Internet Control Message Protocol.
-#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+#[derive(Clone, Copy, Eq, Ord, PartialEq, PartialOrd)]
 pub struct Icmp {
     family: i32,
     protocol: i32,
@@ -91,11 +113,22 @@ impl fmt::Display for Icmp {
         match self.family_type() {
             AF_INET => write!(f, "ICMP"),
             AF_INET6 => write!(f, "ICMP6"),
-            _ => unreachable!("Invalid address family ({}).", self.family),
+            _ => write!(f, "ICMP(protocol={})", self.protocol),
         }
     }
 }
 
+impl fmt::Debug for Icmp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.family_type() {
+            AF_INET => write!(f, "icmp"),
+            AF_INET6 => write!(f, "icmpv6"),
+            _ => write!(f, "Icmp(family={}, protocol={})", self.family, self.protocol_type()),
+        }
+    }
+}
+
+#[cfg(feature = "resolver")]
 impl<'a> ResolverQuery<Icmp> for &'a str {
     fn iter(self) -> io::Result<ResolverIter<Icmp>> {
         ResolverIter::new(
@@ -108,6 +141,18 @@ impl<'a> ResolverQuery<Icmp> for &'a str {
             0,
         )
     }
+
+    fn cache_key(&self) -> Option<String> {
+        Some(cache_key(
+            &Icmp {
+                family: AF_UNSPEC,
+                protocol: 0,
+            },
+            self,
+            "",
+            0,
+        ))
+    }
 }
 
 /// The ICMP endpoint type.
@@ -116,9 +161,387 @@ pub type IcmpEndpoint = IpEndpoint<Icmp>;
 /// The ICMP socket type.
 pub type IcmpSocket = DgramSocket<Icmp>;
 
+#[cfg(feature = "resolver")]
 /// The ICMP resolver type.
 pub type IcmpResolver = Resolver<Icmp>;
 
+/// Returns the internet checksum of RFC 1071.
+///
+/// The checksum of the message including the valid checksum field is zero.
+pub fn checksum(buf: &[u8]) -> u16 {
+    let mut sum = 0u32;
+    for chunk in buf.chunks(2) {
+        sum += (chunk[0] as u32) << 8 | chunk.get(1).cloned().unwrap_or(0) as u32;
+    }
+    while sum >> 16 != 0 {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !sum as u16
+}
+
+/// The ICMP echo request or reply message.
+///
+/// The checksum of the ICMPv6 message includes the pseudo header, that is computed by the
+/// kernel, so the checksum field is encoded as zero and not verified.
+///
+/// # Examples
+///
+/// ```
+/// use asyncio::ip::icmp::{Echo, ECHO_REQUEST, checksum};
+///
+/// let buf = Echo::request(0x1234, 1, b"hello").to_bytes();
+/// assert_eq!(checksum(&buf), 0);
+///
+/// let echo = Echo::from_bytes(&buf).unwrap();
+/// assert_eq!(echo.ty, ECHO_REQUEST);
+/// assert_eq!((echo.id, echo.seq), (0x1234, 1));
+/// assert_eq!(echo.data, b"hello");
+/// ```
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Echo {
+    pub ty: u8,
+    pub code: u8,
+    pub id: u16,
+    pub seq: u16,
+    pub data: Vec<u8>,
+}
+
+impl Echo {
+    /// Returns a ICMP echo request.
+    pub fn request(id: u16, seq: u16, data: &[u8]) -> Echo {
+        Echo {
+            ty: ECHO_REQUEST,
+            code: 0,
+            id: id,
+            seq: seq,
+            data: data.to_vec(),
+        }
+    }
+
+    /// Returns a ICMPv6 echo request.
+    pub fn request_v6(id: u16, seq: u16, data: &[u8]) -> Echo {
+        Echo {
+            ty: ECHO_REQUEST_V6,
+            code: 0,
+            id: id,
+            seq: seq,
+            data: data.to_vec(),
+        }
+    }
+
+    /// Returns a reply to the request.
+    pub fn reply(&self) -> Echo {
+        Echo {
+            ty: if self.is_v6() { ECHO_REPLY_V6 } else { ECHO_REPLY },
+            code: 0,
+            id: self.id,
+            seq: self.seq,
+            data: self.data.clone(),
+        }
+    }
+
+    /// Returns true if this is a ICMPv6 message.
+    pub fn is_v6(&self) -> bool {
+        self.ty == ECHO_REQUEST_V6 || self.ty == ECHO_REPLY_V6
+    }
+
+    /// Parses a echo message, returns `None` if malformed or not a echo message.
+    pub fn from_bytes(buf: &[u8]) -> Option<Echo> {
+        if buf.len() < HEADER_LEN {
+            return None;
+        }
+        match buf[0] {
+            ECHO_REQUEST | ECHO_REPLY if checksum(buf) == 0 => (),
+            ECHO_REQUEST_V6 | ECHO_REPLY_V6 => (),
+            _ => return None,
+        }
+        Some(Echo {
+            ty: buf[0],
+            code: buf[1],
+            id: (buf[4] as u16) << 8 | buf[5] as u16,
+            seq: (buf[6] as u16) << 8 | buf[7] as u16,
+            data: buf[HEADER_LEN..].to_vec(),
+        })
+    }
+
+    /// Parses a echo message in the IPv4 packet, that is received by the raw ICMP socket,
+    /// returns the message and the TTL of the packet.
+    pub fn from_ipv4_packet(buf: &[u8]) -> Option<(Echo, u8)> {
+        if buf.len() < 20 || buf[0] >> 4 != 4 || buf[9] as i32 != IPPROTO_ICMP {
+            return None;
+        }
+        let ihl = (buf[0] & 0xf) as usize * 4;
+        if ihl < 20 || buf.len() < ihl {
+            return None;
+        }
+        Echo::from_bytes(&buf[ihl..]).map(|echo| (echo, buf[8]))
+    }
+
+    /// Returns a encoded message.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(HEADER_LEN + self.data.len());
+        buf.extend_from_slice(&[self.ty, self.code, 0, 0]);
+        buf.extend_from_slice(&self.id.to_be_bytes());
+        buf.extend_from_slice(&self.seq.to_be_bytes());
+        buf.extend_from_slice(&self.data);
+        if !self.is_v6() {
+            let sum = checksum(&buf);
+            buf[2..4].copy_from_slice(&sum.to_be_bytes());
+        }
+        buf
+    }
+}
+
+/// The echo reply received by the `Pinger`.
+#[derive(Clone, Debug)]
+pub struct EchoReply {
+    /// The endpoint which replied.
+    pub ep: IcmpEndpoint,
+    /// The sequence number of the reply.
+    pub seq: u16,
+    /// The length of the echo data.
+    pub len: usize,
+    /// The TTL of the IPv4 packet, or `None` for the ICMPv6.
+    pub ttl: Option<u8>,
+    /// The round trip time.
+    pub rtt: Duration,
+}
+
+fn new_id() -> u16 {
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+    (process::id() as u16) ^ (COUNT.fetch_add(1, Ordering::SeqCst) as u16).rotate_left(8)
+}
+
+/// Returns the reply if the received packet matches the last request.
+fn reply(buf: &[u8], ep: IcmpEndpoint, last: &(IcmpEndpoint, u16, u16, Instant)) -> Option<EchoReply> {
+    let &(ref dst, id, seq, sent) = last;
+    let (echo, ttl) = if ep.is_v4() {
+        match Echo::from_ipv4_packet(buf) {
+            Some((echo, ttl)) => (echo, Some(ttl)),
+            None => return None,
+        }
+    } else {
+        match Echo::from_bytes(buf) {
+            Some(echo) => (echo, None),
+            None => return None,
+        }
+    };
+    let ty = if ep.is_v4() { ECHO_REPLY } else { ECHO_REPLY_V6 };
+    if echo.ty != ty || echo.id != id || echo.seq != seq || ep.addr() != dst.addr() {
+        return None;
+    }
+    Some(EchoReply {
+        ep: ep,
+        seq: seq,
+        len: echo.data.len(),
+        ttl: ttl,
+        rtt: sent.elapsed(),
+    })
+}
+
+/// The ping client over a raw ICMP socket.
+///
+/// The echo requests are identified by the identifier of the pinger and the sequence number,
+/// and the other ICMP messages received by the raw socket are discarded.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use std::time::Duration;
+/// use asyncio::IoContext;
+/// use asyncio::ip::{IpProtocol, IpAddrV4, Icmp, IcmpEndpoint, IcmpSocket};
+/// use asyncio::ip::icmp::Pinger;
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let pinger = Pinger::new(IcmpSocket::new(ctx, Icmp::v4()).unwrap());
+/// let ep = IcmpEndpoint::new(IpAddrV4::loopback(), 0);
+/// let reply = pinger.ping(&ep, 1, Duration::new(1, 0)).unwrap();
+/// println!("seq={} ttl={:?} time={:?}", reply.seq, reply.ttl, reply.rtt);
+/// ```
+pub struct Pinger {
+    soc: IcmpSocket,
+    id: u16,
+    last: Mutex<Option<(IcmpEndpoint, u16, u16, Instant)>>,
+    buf: UnsafeCell<[u8; 1500]>,
+}
+
+impl Pinger {
+    pub fn new(soc: IcmpSocket) -> Pinger {
+        Pinger {
+            soc: soc,
+            id: new_id(),
+            last: Mutex::new(None),
+            buf: UnsafeCell::new([0; 1500]),
+        }
+    }
+
+    /// Asynchronously sends a echo request and waits for the reply.
+    ///
+    /// The handler is completed with `TimedOut` error if the timeout of the socket expires.
+    pub fn async_ping<F>(&self, ep: &IcmpEndpoint, seq: u16, handler: F) -> F::Output
+    where
+        F: Handler<EchoReply, io::Error>,
+    {
+        let ep = ep.clone();
+        handler.wrap(self.as_ctx(), move |ctx, handler| match self.send(ep, seq) {
+            Ok(_) => self.async_receive(PingReceive {
+                pinger: self,
+                handler: handler,
+            }),
+            Err(err) => ctx.do_dispatch(Failure::new(err, handler)),
+        })
+    }
+
+    /// Returns the identifier of the echo requests.
+    pub fn id(&self) -> u16 {
+        self.id
+    }
+
+    /// Returns the socket of the pinger.
+    pub fn into_socket(self) -> IcmpSocket {
+        self.soc
+    }
+
+    /// Sends a echo request and waits for the reply until the timeout expires.
+    pub fn ping(&self, ep: &IcmpEndpoint, seq: u16, timeout: Duration) -> io::Result<EchoReply> {
+        self.send(ep.clone(), seq)?;
+        let last = self.last.lock().unwrap().clone().unwrap();
+        let buf = unsafe { &mut *self.buf.get() };
+        let deadline = Instant::now() + timeout;
+        loop {
+            self.soc.wait_until(Wait::Read, deadline)?;
+            let (len, ep) = match self.soc.nonblocking_receive_from(buf, 0) {
+                Ok(res) => res,
+                Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => continue,
+                Err(err) => return Err(err),
+            };
+            if let Some(res) = reply(&buf[..len], ep, &last) {
+                return Ok(res);
+            }
+            if Instant::now() >= deadline {
+                return Err(TIMED_OUT.into());
+            }
+        }
+    }
+
+    /// Returns the socket of the pinger.
+    pub fn socket(&self) -> &IcmpSocket {
+        &self.soc
+    }
+
+    fn send(&self, ep: IcmpEndpoint, seq: u16) -> io::Result<usize> {
+        let echo = if ep.is_v4() {
+            Echo::request(self.id, seq, PAYLOAD)
+        } else {
+            Echo::request_v6(self.id, seq, PAYLOAD)
+        };
+        *self.last.lock().unwrap() = Some((ep.clone(), self.id, seq, Instant::now()));
+        self.soc.send_to(&echo.to_bytes(), 0, &ep)
+    }
+
+    fn async_receive<F>(&self, handler: PingReceive<F>)
+    where
+        F: Complete<EchoReply, io::Error>,
+    {
+        let buf = unsafe { &mut *self.buf.get() };
+        self.soc.async_receive_from(buf, 0, handler)
+    }
+}
+
+unsafe impl AsIoContext for Pinger {
+    fn as_ctx(&self) -> &IoContext {
+        self.soc.as_ctx()
+    }
+}
+
+impl Cancel for Pinger {
+    fn cancel(&self) {
+        self.soc.cancel();
+    }
+}
+
+unsafe impl Sync for Pinger {}
+
+struct PingReceive<F> {
+    pinger: *const Pinger,
+    handler: F,
+}
+
+unsafe impl<F> Send for PingReceive<F> {}
+
+impl<F> Handler<(usize, IcmpEndpoint), io::Error> for PingReceive<F>
+where
+    F: Complete<EchoReply, io::Error>,
+{
+    type Output = ();
+
+    type WrappedHandler = Self;
+
+    fn wrap<W>(self, ctx: &IoContext, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx, self)
+    }
+
+    fn wrap_timeout<W>(self, ctx: &Cancel, _: &Timeout, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx.as_ctx(), self)
+    }
+}
+
+impl<F> Complete<(usize, IcmpEndpoint), io::Error> for PingReceive<F>
+where
+    F: Complete<EchoReply, io::Error>,
+{
+    fn success(self, this: &mut ThreadIoContext, res: (usize, IcmpEndpoint)) {
+        let pinger = unsafe { &*self.pinger };
+        let (len, ep) = res;
+        let buf = unsafe { &*pinger.buf.get() };
+        let last = pinger.last.lock().unwrap().clone().unwrap();
+        if let Some(res) = reply(&buf[..len], ep, &last) {
+            return self.handler.success(this, res);
+        }
+        this.decrease_outstanding_work();
+        pinger.async_receive(self)
+    }
+
+    fn failure(self, this: &mut ThreadIoContext, err: io::Error) {
+        self.handler.failure(this, err)
+    }
+}
+
+#[test]
+fn test_checksum() {
+    // The example of RFC 1071.
+    assert_eq!(checksum(&[0x00, 0x01, 0xf2, 0x03, 0xf4, 0xf5, 0xf6, 0xf7]), !0xddf2);
+    assert_eq!(checksum(&[0x00, 0x01, 0xf2]), !0xf201);
+}
+
+#[test]
+fn test_echo() {
+    let req = Echo::request(0x1234, 0x5678, b"abc");
+    let buf = req.to_bytes();
+    assert_eq!(&buf[..8], &[8, 0, 0xca, 0xf0, 0x12, 0x34, 0x56, 0x78]);
+    assert_eq!(Echo::from_bytes(&buf), Some(req.clone()));
+    assert_eq!(Echo::from_bytes(&buf[..buf.len() - 1]), None);
+
+    let rep = req.reply();
+    assert_eq!(rep.ty, ECHO_REPLY);
+    assert_eq!(Echo::from_bytes(&rep.to_bytes()), Some(rep.clone()));
+
+    let mut pkt = vec![0x45, 0, 0, 0, 0, 0, 0, 0, 64, 1, 0, 0, 127, 0, 0, 1, 127, 0, 0, 1];
+    pkt.extend(rep.to_bytes());
+    assert_eq!(Echo::from_ipv4_packet(&pkt), Some((rep, 64)));
+    assert_eq!(Echo::from_ipv4_packet(&pkt[4..]), None);
+
+    let req = Echo::request_v6(1, 2, b"");
+    assert_eq!(req.to_bytes(), &[128, 0, 0, 0, 0, 1, 0, 2]);
+    assert_eq!(req.reply().ty, ECHO_REPLY_V6);
+}
+
 #[test]
 fn test_icmp() {
     assert!(Icmp::v4() == Icmp::v4());
@@ -126,6 +549,7 @@ fn test_icmp() {
     assert!(Icmp::v4() != Icmp::v6());
 }
 
+#[cfg(feature = "resolver")]
 #[test]
 fn test_icmp_resolve() {
     use core::IoContext;
@@ -0,0 +1,177 @@
+//! Converts the internationalized host names to the ASCII form passed to the resolver.
+
+use std::io;
+use std::char;
+
+const BASE: u32 = 36;
+const T_MIN: u32 = 1;
+const T_MAX: u32 = 26;
+const SKEW: u32 = 38;
+const DAMP: u32 = 700;
+const INITIAL_BIAS: u32 = 72;
+const INITIAL_N: u32 = 0x80;
+
+const MAX_LABEL_LEN: usize = 63;
+const MAX_HOST_LEN: usize = 253;
+
+fn invalid_host(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, format!("invalid host name: {}", msg))
+}
+
+fn adapt(mut delta: u32, num_points: u32, first_time: bool) -> u32 {
+    delta /= if first_time { DAMP } else { 2 };
+    delta += delta / num_points;
+    let mut k = 0;
+    while delta > ((BASE - T_MIN) * T_MAX) / 2 {
+        delta /= BASE - T_MIN;
+        k += BASE;
+    }
+    k + (BASE - T_MIN + 1) * delta / (delta + SKEW)
+}
+
+fn encode_digit(d: u32) -> char {
+    (if d < 26 { b'a' + d as u8 } else { b'0' + (d - 26) as u8 }) as char
+}
+
+/// Encodes the label to the punycode defined in RFC 3492, without the `xn--` prefix.
+fn punycode(label: &str) -> Option<String> {
+    let input: Vec<u32> = label.chars().map(|ch| ch as u32).collect();
+    let mut output: String = label.chars().filter(|ch| ch.is_ascii()).collect();
+    let basic_len = output.len() as u32;
+    if basic_len > 0 {
+        output.push('-');
+    }
+
+    let mut n = INITIAL_N;
+    let mut delta: u32 = 0;
+    let mut bias = INITIAL_BIAS;
+    let mut h = basic_len;
+    while (h as usize) < input.len() {
+        let m = *input.iter().filter(|&&c| c >= n).min().unwrap();
+        delta = delta.checked_add((m - n).checked_mul(h + 1)?)?;
+        n = m;
+        for &c in &input {
+            if c < n {
+                delta = delta.checked_add(1)?;
+            }
+            if c == n {
+                let mut q = delta;
+                let mut k = BASE;
+                loop {
+                    let t = if k <= bias {
+                        T_MIN
+                    } else if k >= bias + T_MAX {
+                        T_MAX
+                    } else {
+                        k - bias
+                    };
+                    if q < t {
+                        break;
+                    }
+                    output.push(encode_digit(t + (q - t) % (BASE - t)));
+                    q = (q - t) / (BASE - t);
+                    k += BASE;
+                }
+                output.push(encode_digit(q));
+                bias = adapt(delta, h + 1, h == basic_len);
+                delta = 0;
+                h += 1;
+            }
+        }
+        delta += 1;
+        n += 1;
+    }
+    Some(output)
+}
+
+fn is_dot(ch: char) -> bool {
+    // The full stop, the ideographic full stop, the fullwidth full stop and the halfwidth
+    // ideographic full stop.
+    match ch {
+        '.' | '\u{3002}' | '\u{ff0e}' | '\u{ff61}' => true,
+        _ => false,
+    }
+}
+
+/// Returns the host name converted to the ASCII form, the non-ASCII labels are encoded to the
+/// punycode with the `xn--` prefix.
+///
+/// Returns an error of `InvalidInput` if the host name contains a NUL character, an empty label,
+/// a label longer than 63 bytes or is longer than 253 bytes.
+pub fn to_ascii(host: &str) -> io::Result<String> {
+    if host.contains('\0') {
+        return Err(invalid_host("contains a NUL character"));
+    }
+
+    // The empty host (e.g. passive) and the IPv6 address are passed as is.
+    if host.is_empty() || host.contains(':') {
+        return Ok(host.to_owned());
+    }
+
+    let mut labels: Vec<&str> = host.split(is_dot).collect();
+    let fqdn = labels.len() > 1 && labels.last() == Some(&"");
+    if fqdn {
+        labels.pop();
+    }
+
+    let mut ascii = String::with_capacity(host.len());
+    for label in labels {
+        if label.is_empty() {
+            return Err(invalid_host("contains an empty label"));
+        }
+        if !ascii.is_empty() {
+            ascii.push('.');
+        }
+        if label.is_ascii() {
+            if label.len() > MAX_LABEL_LEN {
+                return Err(invalid_host("contains a label longer than 63 bytes"));
+            }
+            ascii.push_str(label);
+        } else {
+            let label: String = label.chars().flat_map(char::to_lowercase).collect();
+            let encoded = punycode(&label).ok_or_else(|| invalid_host("overflows the punycode"))?;
+            if encoded.len() + 4 > MAX_LABEL_LEN {
+                return Err(invalid_host("contains a label longer than 63 bytes"));
+            }
+            ascii.push_str("xn--");
+            ascii.push_str(&encoded);
+        }
+    }
+    if ascii.len() > MAX_HOST_LEN {
+        return Err(invalid_host("is longer than 253 bytes"));
+    }
+    if fqdn {
+        ascii.push('.');
+    }
+    Ok(ascii)
+}
+
+#[test]
+fn test_punycode() {
+    assert_eq!(punycode("bücher").unwrap(), "bcher-kva");
+    assert_eq!(punycode("münchen").unwrap(), "mnchen-3ya");
+    assert_eq!(punycode("例え").unwrap(), "r8jz45g");
+    assert_eq!(punycode("テスト").unwrap(), "zckzah");
+}
+
+#[test]
+fn test_to_ascii() {
+    assert_eq!(to_ascii("").unwrap(), "");
+    assert_eq!(to_ascii("localhost").unwrap(), "localhost");
+    assert_eq!(to_ascii("127.0.0.1").unwrap(), "127.0.0.1");
+    assert_eq!(to_ascii("fe80::1%lo").unwrap(), "fe80::1%lo");
+    assert_eq!(to_ascii("example.com.").unwrap(), "example.com.");
+    assert_eq!(to_ascii("Bücher.example").unwrap(), "xn--bcher-kva.example");
+    assert_eq!(to_ascii("例え。テスト").unwrap(), "xn--r8jz45g.xn--zckzah");
+}
+
+#[test]
+fn test_to_ascii_invalid() {
+    assert_eq!(to_ascii("local\0host").unwrap_err().kind(), io::ErrorKind::InvalidInput);
+    assert_eq!(to_ascii("example..com").unwrap_err().kind(), io::ErrorKind::InvalidInput);
+    assert_eq!(to_ascii(".").unwrap_err().kind(), io::ErrorKind::InvalidInput);
+    let label = "a".repeat(64);
+    assert_eq!(to_ascii(&label).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+    let host = vec!["a".repeat(63); 4].join(".");
+    assert_eq!(to_ascii(&host).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+}
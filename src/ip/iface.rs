@@ -0,0 +1,216 @@
+use ffi::{sockaddr, sockaddr_in, sockaddr_in6, sockaddr_ll, AF_INET, AF_INET6, AF_PACKET,
+          NO_SUCH_DEVICE, IFF_UP, IFF_BROADCAST, IFF_LOOPBACK, IFF_RUNNING, IFF_MULTICAST,
+          if_addrs};
+use ip::{IpAddrV4, IpAddrV6, IpNetworkV4, IpNetworkV6, LlAddr};
+
+use std::io;
+use std::mem;
+
+unsafe fn to_v4(sa: &sockaddr) -> IpAddrV4 {
+    let sin = &*(sa as *const _ as *const sockaddr_in);
+    IpAddrV4::from(mem::transmute::<_, [u8; 4]>(sin.sin_addr))
+}
+
+unsafe fn to_v6(sa: &sockaddr) -> IpAddrV6 {
+    let sin6 = &*(sa as *const _ as *const sockaddr_in6);
+    IpAddrV6::from(mem::transmute::<_, [u8; 16]>(sin6.sin6_addr), sin6.sin6_scope_id)
+}
+
+/// The IP-v4 address assigned to the interface.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct IfaceAddrV4 {
+    /// The address and the prefix length.
+    pub network: IpNetworkV4,
+
+    /// The broadcast address, that is `None` unless the interface supports the broadcast.
+    pub broadcast: Option<IpAddrV4>,
+}
+
+/// The network interface, that is a snapshot of the `getifaddrs`.
+///
+/// # Examples
+///
+/// ```
+/// use asyncio::ip::{Iface, IpAddrV4};
+///
+/// let lo = Iface::interfaces().unwrap().into_iter().find(|iface| iface.is_loopback()).unwrap();
+/// assert!(lo.is_up());
+/// assert!(lo.addrs_v4().iter().any(|addr| addr.network.address() == IpAddrV4::loopback()));
+/// assert_eq!(Iface::by_index(lo.index()).unwrap().name(), lo.name());
+/// ```
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Iface {
+    name: String,
+    index: u32,
+    flags: u32,
+    hw_addr: Option<LlAddr>,
+    addrs_v4: Vec<IfaceAddrV4>,
+    addrs_v6: Vec<IpNetworkV6>,
+}
+
+impl Iface {
+    /// Returns all the interfaces in the order of the `getifaddrs`.
+    pub fn interfaces() -> io::Result<Vec<Iface>> {
+        let mut ifaces: Vec<Iface> = Vec::new();
+        if_addrs(|name, flags, addr, netmask, broadaddr| {
+            let name = name.to_string_lossy();
+            let pos = match ifaces.iter().position(|iface| iface.name == name) {
+                Some(pos) => pos,
+                None => {
+                    ifaces.push(Iface {
+                        name: name.into_owned(),
+                        index: 0,
+                        flags: flags,
+                        hw_addr: None,
+                        addrs_v4: Vec::new(),
+                        addrs_v6: Vec::new(),
+                    });
+                    ifaces.len() - 1
+                }
+            };
+            let iface = &mut ifaces[pos];
+            match addr.sa_family as i32 {
+                AF_PACKET => {
+                    let sll = unsafe { &*(addr as *const _ as *const sockaddr_ll) };
+                    iface.index = sll.sll_ifindex as u32;
+                    if sll.sll_halen == 6 {
+                        let mut bytes = [0; 6];
+                        bytes.copy_from_slice(&sll.sll_addr[..6]);
+                        iface.hw_addr = Some(LlAddr::from(bytes));
+                    }
+                }
+                AF_INET => {
+                    let addr = unsafe { to_v4(addr) };
+                    let netmask = netmask.map(|sa| unsafe { to_v4(sa) });
+                    let broadcast = match broadaddr {
+                        Some(sa) if (flags & IFF_BROADCAST as u32) != 0 => {
+                            Some(unsafe { to_v4(sa) })
+                        }
+                        _ => None,
+                    };
+                    let netmask = netmask.unwrap_or(IpAddrV4::new(255, 255, 255, 255));
+                    if let Some(network) = IpNetworkV4::new(addr, netmask) {
+                        iface.addrs_v4.push(IfaceAddrV4 {
+                            network: network,
+                            broadcast: broadcast,
+                        })
+                    }
+                }
+                AF_INET6 => {
+                    let addr = unsafe { to_v6(addr) };
+                    let netmask = match netmask {
+                        Some(sa) => unsafe { to_v6(sa) },
+                        None => IpAddrV6::from([0xff; 16], 0),
+                    };
+                    if let Some(network) = IpNetworkV6::new(addr, netmask) {
+                        iface.addrs_v6.push(network)
+                    }
+                }
+                _ => (),
+            }
+        })?;
+        Ok(ifaces)
+    }
+
+    /// Returns the interface of the index, returns the `ENODEV` error if not found.
+    pub fn by_index(index: u32) -> io::Result<Iface> {
+        Self::interfaces()?
+            .into_iter()
+            .find(|iface| iface.index == index)
+            .ok_or(NO_SUCH_DEVICE.into())
+    }
+
+    /// Returns the interface of the name, returns the `ENODEV` error if not found.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use asyncio::ip::Iface;
+    ///
+    /// let lo = Iface::by_name("lo").unwrap();
+    /// assert!(lo.is_loopback());
+    /// assert!(Iface::by_name("no such device").is_err());
+    /// ```
+    pub fn by_name(name: &str) -> io::Result<Iface> {
+        Self::interfaces()?
+            .into_iter()
+            .find(|iface| iface.name == name)
+            .ok_or(NO_SUCH_DEVICE.into())
+    }
+
+    /// Returns the IP-v4 addresses.
+    pub fn addrs_v4(&self) -> &[IfaceAddrV4] {
+        &self.addrs_v4
+    }
+
+    /// Returns the IP-v6 addresses, that the link-local addresses are scoped by the `index`.
+    pub fn addrs_v6(&self) -> &[IpNetworkV6] {
+        &self.addrs_v6
+    }
+
+    /// Returns the `IFF_*` flags.
+    pub fn flags(&self) -> u32 {
+        self.flags
+    }
+
+    /// Returns the hardware address, that is `None` unless the ethernet-like interface.
+    pub fn hw_addr(&self) -> Option<LlAddr> {
+        self.hw_addr
+    }
+
+    /// Returns the interface index, that is the scope id of the IP-v6 addresses.
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// Returns true if the interface supports the broadcast.
+    pub fn is_broadcast(&self) -> bool {
+        (self.flags & IFF_BROADCAST as u32) != 0
+    }
+
+    /// Returns true if the interface is the loopback.
+    pub fn is_loopback(&self) -> bool {
+        (self.flags & IFF_LOOPBACK as u32) != 0
+    }
+
+    /// Returns true if the interface supports the multicast.
+    pub fn is_multicast(&self) -> bool {
+        (self.flags & IFF_MULTICAST as u32) != 0
+    }
+
+    /// Returns true if the interface is running, that the link is up.
+    pub fn is_running(&self) -> bool {
+        (self.flags & IFF_RUNNING as u32) != 0
+    }
+
+    /// Returns true if the interface is up by the administrator.
+    pub fn is_up(&self) -> bool {
+        (self.flags & IFF_UP as u32) != 0
+    }
+
+    /// Returns the interface name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+#[test]
+fn test_iface_loopback() {
+    use ffi::if_nametoindex;
+    use std::ffi::CString;
+
+    let lo = Iface::by_name("lo").unwrap();
+    assert!(lo.is_up() && lo.is_loopback());
+    assert!(!lo.is_broadcast());
+    assert_eq!(lo.index(), if_nametoindex(&CString::new("lo").unwrap()).unwrap());
+    assert_eq!(lo.hw_addr(), Some(LlAddr::default()));
+
+    let addr = &lo.addrs_v4()[0];
+    assert_eq!(addr.network, IpNetworkV4::from(IpAddrV4::loopback(), 8).unwrap());
+    assert_eq!(addr.broadcast, None);
+    assert_eq!(Iface::by_index(lo.index()).unwrap(), lo);
+    assert_eq!(
+        Iface::by_index(0).unwrap_err().raw_os_error(),
+        io::Error::from(NO_SUCH_DEVICE).raw_os_error()
+    );
+}
@@ -5,95 +5,135 @@ use std::io;
 use std::fmt;
 use std::mem;
 use std::net;
-use std::hash::{Hash, Hasher};
+use std::borrow::Borrow;
 use std::ops::{AddAssign, SubAssign};
 
-fn add_assign(bytes: &mut [u8], mut rhs: i64) {
+fn overflowing_add_u64(bytes: &mut [u8], mut rhs: u64) -> bool {
+    let mut carry = 0;
+    for it in bytes.iter_mut().rev() {
+        let sum = *it as u64 + (rhs & 0xFF) + carry;
+        *it = sum as u8;
+        carry = sum >> 8;
+        rhs >>= 8;
+    }
+    rhs > 0 || carry > 0
+}
+
+fn overflowing_sub_u64(bytes: &mut [u8], mut rhs: u64) -> bool {
+    let mut borrow = 0;
+    for it in bytes.iter_mut().rev() {
+        let sub = (rhs & 0xFF) + borrow;
+        borrow = if (*it as u64) < sub { 1 } else { 0 };
+        *it = (*it as u64 + (borrow << 8) - sub) as u8;
+        rhs >>= 8;
+    }
+    rhs > 0 || borrow > 0
+}
+
+/// Adds `rhs` to the bytes of the big-endian integer, wraps around and returns true on overflow.
+fn overflowing_add(bytes: &mut [u8], rhs: i64) -> bool {
     if rhs < 0 {
-        sub_assign(bytes, -rhs)
+        overflowing_sub_u64(bytes, rhs.wrapping_neg() as u64)
     } else {
-        for it in bytes.iter_mut().rev() {
-            let (val, car) = it.overflowing_add(rhs as u8);
-            *it = val;
-            rhs >>= 8;
-            if car {
-                rhs += 1;
-            }
-        }
-        if rhs > 0 {
-            panic!("overflow");
-        }
+        overflowing_add_u64(bytes, rhs as u64)
     }
 }
 
-fn sub_assign(bytes: &mut [u8], mut rhs: i64) {
+/// Subtracts `rhs` from the bytes of the big-endian integer, wraps around and returns true on
+/// overflow.
+fn overflowing_sub(bytes: &mut [u8], rhs: i64) -> bool {
     if rhs < 0 {
-        add_assign(bytes, -rhs)
+        overflowing_add_u64(bytes, rhs.wrapping_neg() as u64)
     } else {
-        for it in bytes.iter_mut().rev() {
-            let (val, car) = it.overflowing_sub(rhs as u8);
+        overflowing_sub_u64(bytes, rhs as u64)
+    }
+}
+
+/// Adds `rhs` to the bytes, saturates at the lowest or the highest value on overflow.
+fn saturating_add(bytes: &mut [u8], rhs: i64) {
+    if overflowing_add(bytes, rhs) {
+        let val = if rhs < 0 { 0 } else { 0xFF };
+        for it in bytes.iter_mut() {
             *it = val;
-            rhs >>= 8;
-            if car {
-                rhs += 1;
-            }
-        }
-        if rhs > 0 {
-            panic!("overflow");
         }
     }
 }
 
+fn add_assign(bytes: &mut [u8], rhs: i64) {
+    if overflowing_add(bytes, rhs) {
+        panic!("overflow");
+    }
+}
+
+fn sub_assign(bytes: &mut [u8], rhs: i64) {
+    if overflowing_sub(bytes, rhs) {
+        panic!("overflow");
+    }
+}
+
+/// Writes the IP-v6 address in the canonical text representation of RFC 5952.
+///
+/// The longest run of two or more zero fields is compressed to `::`, the first one if there are
+/// the runs of the same length, and the IPv4-mapped address is written in the dotted decimal.
 fn fmt_v6(bytes: &[u8; 16], f: &mut fmt::Formatter) -> fmt::Result {
-    let ar: &[u16; 8] = unsafe { mem::transmute(bytes) };
+    if bytes[..10].iter().all(|&b| b == 0) && bytes[10] == 0xFF && bytes[11] == 0xFF {
+        return write!(
+            f,
+            "::ffff:{}.{}.{}.{}",
+            bytes[12],
+            bytes[13],
+            bytes[14],
+            bytes[15]
+        );
+    }
+
+    let mut ar = [0u16; 8];
+    for (i, e) in ar.iter_mut().enumerate() {
+        *e = (bytes[i * 2] as u16) << 8 | bytes[i * 2 + 1] as u16;
+    }
+
     let mut cnt = 0;
     let mut max_idx = 0;
     let mut max_cnt = 0;
     for (i, e) in ar.iter().enumerate() {
-        if *e != 0 {
+        if *e == 0 {
+            cnt += 1;
             if max_cnt < cnt {
-                max_idx = i - cnt;
+                max_idx = i + 1 - cnt;
                 max_cnt = cnt;
             }
-            cnt = 0;
         } else {
-            cnt += 1;
+            cnt = 0;
         }
     }
-    if max_cnt < cnt {
-        max_idx = ar.len() - cnt;
-        max_cnt = cnt;
-    }
 
-    if max_idx == 0 && max_cnt == 0 {
+    if max_cnt < 2 {
         return write!(
             f,
             "{:x}:{:x}:{:x}:{:x}:{:x}:{:x}:{:x}:{:x}",
-            u16::from_be(ar[0]),
-            u16::from_be(ar[1]),
-            u16::from_be(ar[2]),
-            u16::from_be(ar[3]),
-            u16::from_be(ar[4]),
-            u16::from_be(ar[5]),
-            u16::from_be(ar[6]),
-            u16::from_be(ar[7])
+            ar[0],
+            ar[1],
+            ar[2],
+            ar[3],
+            ar[4],
+            ar[5],
+            ar[6],
+            ar[7]
         );
     }
 
-    if max_idx == 0 {
-        write!(f, ":")?;
-    } else {
-        for i in 0..max_idx {
-            write!(f, "{:x}:", u16::from_be(ar[i]))?;
+    for (i, e) in ar[..max_idx].iter().enumerate() {
+        if i > 0 {
+            write!(f, ":")?;
         }
+        write!(f, "{:x}", e)?;
     }
-
-    if max_idx + max_cnt == 8 {
-        write!(f, ":")?;
-    } else {
-        for i in max_idx + max_cnt..ar.len() {
-            write!(f, ":{:x}", u16::from_be(ar[i]))?;
+    write!(f, "::")?;
+    for (i, e) in ar[max_idx + max_cnt..].iter().enumerate() {
+        if i > 0 {
+            write!(f, ":")?;
         }
+        write!(f, "{:x}", e)?;
     }
     Ok(())
 }
@@ -101,6 +141,9 @@ fn fmt_v6(bytes: &[u8; 16], f: &mut fmt::Formatter) -> fmt::Result {
 /// Implements Link-layer addresses.
 ///
 /// Also referred to as MAC address and Hardware address.
+///
+/// The addresses are compared and hashed as the bytes, that can be borrowed as `[u8]` to look up
+/// the `HashMap` keyed by the addresses.
 #[derive(Default, Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub struct LlAddr {
     bytes: [u8; 6],
@@ -148,14 +191,56 @@ impl LlAddr {
     pub fn oui(&self) -> i32 {
         ((self.bytes[0] as i32 * 256 + self.bytes[1] as i32) * 256 + self.bytes[2] as i32)
     }
+
+    /// Returns the address added `rhs`, or `None` if it overflows.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use asyncio::ip::LlAddr;
+    ///
+    /// assert_eq!(LlAddr::new(0xff, 0xff, 0xff, 0xff, 0xff, 0xff).checked_add(1), None);
+    /// ```
+    pub fn checked_add(&self, rhs: i64) -> Option<LlAddr> {
+        let mut addr = *self;
+        if overflowing_add(&mut addr.bytes, rhs) {
+            None
+        } else {
+            Some(addr)
+        }
+    }
+
+    /// Returns the address added `rhs`, saturating at the lowest or the highest address.
+    pub fn saturating_add(&self, rhs: i64) -> LlAddr {
+        let mut addr = *self;
+        saturating_add(&mut addr.bytes, rhs);
+        addr
+    }
+
+    /// Returns the address added `rhs`, wrapping around at the lowest or the highest address.
+    pub fn wrapping_add(&self, rhs: i64) -> LlAddr {
+        let mut addr = *self;
+        overflowing_add(&mut addr.bytes, rhs);
+        addr
+    }
 }
 
+/// Adds `rhs` to the address.
+///
+/// # Panics
+///
+/// Panics if the address overflows, use the `checked_add` or the `wrapping_add` instead.
 impl AddAssign<i64> for LlAddr {
     fn add_assign(&mut self, rhs: i64) {
         add_assign(&mut self.bytes, rhs)
     }
 }
 
+/// Subtracts `rhs` from the address.
+///
+/// # Panics
+///
+/// Panics if the address overflows, use the `checked_add` or the `wrapping_add` instead.
 impl SubAssign<i64> for LlAddr {
     fn sub_assign(&mut self, rhs: i64) {
         sub_assign(&mut self.bytes, rhs)
@@ -183,7 +268,22 @@ impl From<[u8; 6]> for LlAddr {
     }
 }
 
+impl AsRef<[u8]> for LlAddr {
+    fn as_ref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl Borrow<[u8]> for LlAddr {
+    fn borrow(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
 /// Implements IP version 4 style addresses.
+///
+/// The addresses are compared and hashed as the bytes, that can be borrowed as `[u8]` to look up
+/// the `HashMap` keyed by the addresses.
 #[derive(Default, Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub struct IpAddrV4 {
     bytes: [u8; 4],
@@ -375,14 +475,56 @@ impl IpAddrV4 {
     pub fn to_u32(&self) -> u32 {
         unsafe { &*(self.bytes.as_ptr() as *const u32) }.to_be()
     }
+
+    /// Returns the address added `rhs`, or `None` if it overflows.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use asyncio::ip::IpAddrV4;
+    ///
+    /// assert_eq!(IpAddrV4::new(255, 255, 255, 255).checked_add(1), None);
+    /// ```
+    pub fn checked_add(&self, rhs: i64) -> Option<IpAddrV4> {
+        let mut addr = *self;
+        if overflowing_add(&mut addr.bytes, rhs) {
+            None
+        } else {
+            Some(addr)
+        }
+    }
+
+    /// Returns the address added `rhs`, saturating at the lowest or the highest address.
+    pub fn saturating_add(&self, rhs: i64) -> IpAddrV4 {
+        let mut addr = *self;
+        saturating_add(&mut addr.bytes, rhs);
+        addr
+    }
+
+    /// Returns the address added `rhs`, wrapping around at the lowest or the highest address.
+    pub fn wrapping_add(&self, rhs: i64) -> IpAddrV4 {
+        let mut addr = *self;
+        overflowing_add(&mut addr.bytes, rhs);
+        addr
+    }
 }
 
+/// Adds `rhs` to the address.
+///
+/// # Panics
+///
+/// Panics if the address overflows, use the `checked_add` or the `wrapping_add` instead.
 impl AddAssign<i64> for IpAddrV4 {
     fn add_assign(&mut self, rhs: i64) {
         *self = Self::from(self.to_u32() + rhs as u32);
     }
 }
 
+/// Subtracts `rhs` from the address.
+///
+/// # Panics
+///
+/// Panics if the address overflows, use the `checked_add` or the `wrapping_add` instead.
 impl SubAssign<i64> for IpAddrV4 {
     fn sub_assign(&mut self, rhs: i64) {
         *self = Self::from(self.to_u32() - rhs as u32);
@@ -408,12 +550,30 @@ impl From<[u8; 4]> for IpAddrV4 {
     }
 }
 
+impl AsRef<[u8]> for IpAddrV4 {
+    fn as_ref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl Borrow<[u8]> for IpAddrV4 {
+    fn borrow(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
 impl From<net::Ipv4Addr> for IpAddrV4 {
     fn from(ip: net::Ipv4Addr) -> Self {
         ip.octets().into()
     }
 }
 
+impl From<IpAddrV4> for net::Ipv4Addr {
+    fn from(ip: IpAddrV4) -> Self {
+        ip.bytes.into()
+    }
+}
+
 impl From<u32> for IpAddrV4 {
     fn from(mut addr: u32) -> Self {
         let d = (addr & 0xFF) as u8;
@@ -427,7 +587,12 @@ impl From<u32> for IpAddrV4 {
 }
 
 /// Implements IP version 6 style addresses.
-#[derive(Default, Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd)]
+///
+/// The addresses are equal only if both the bytes and the scope-id are equal, and the hash
+/// covers the both, e.g. `fe80::1%1` and `fe80::1%2` are different keys of the `HashMap`.
+/// Therefore it implements `AsRef<[u8]>` but not `Borrow<[u8]>`, compare the `as_bytes` to
+/// ignore the scope-id.
+#[derive(Default, Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub struct IpAddrV6 {
     scope_id: u32,
     bytes: [u8; 16],
@@ -724,32 +889,72 @@ impl IpAddrV6 {
             })
         }
     }
+
+    /// Returns the address added `rhs`, or `None` if it overflows.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use asyncio::ip::IpAddrV6;
+    ///
+    /// assert_eq!(IpAddrV6::new(0xffff, 0xffff, 0xffff, 0xffff, 0xffff, 0xffff, 0xffff, 0xffff).checked_add(1), None);
+    /// ```
+    pub fn checked_add(&self, rhs: i64) -> Option<IpAddrV6> {
+        let mut addr = *self;
+        if overflowing_add(&mut addr.bytes, rhs) {
+            None
+        } else {
+            Some(addr)
+        }
+    }
+
+    /// Returns the address added `rhs`, saturating at the lowest or the highest address.
+    pub fn saturating_add(&self, rhs: i64) -> IpAddrV6 {
+        let mut addr = *self;
+        saturating_add(&mut addr.bytes, rhs);
+        addr
+    }
+
+    /// Returns the address added `rhs`, wrapping around at the lowest or the highest address.
+    pub fn wrapping_add(&self, rhs: i64) -> IpAddrV6 {
+        let mut addr = *self;
+        overflowing_add(&mut addr.bytes, rhs);
+        addr
+    }
 }
 
+/// Adds `rhs` to the address.
+///
+/// # Panics
+///
+/// Panics if the address overflows, use the `checked_add` or the `wrapping_add` instead.
 impl AddAssign<i64> for IpAddrV6 {
     fn add_assign(&mut self, rhs: i64) {
         add_assign(&mut self.bytes, rhs)
     }
 }
 
+/// Subtracts `rhs` from the address.
+///
+/// # Panics
+///
+/// Panics if the address overflows, use the `checked_add` or the `wrapping_add` instead.
 impl SubAssign<i64> for IpAddrV6 {
     fn sub_assign(&mut self, rhs: i64) {
         sub_assign(&mut self.bytes, rhs)
     }
 }
 
-impl Hash for IpAddrV6 {
-    fn hash<H>(&self, state: &mut H)
-    where
-        H: Hasher,
-    {
-        state.write(&self.bytes);
-    }
-}
-
+/// Formats the address in the canonical text representation of RFC 5952, e.g. `fe80::1`.
+///
+/// The alternate form `{:#}` appends the non-zero scope-id, e.g. `fe80::1%2`.
 impl fmt::Display for IpAddrV6 {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        fmt_v6(&self.bytes, f)
+        fmt_v6(&self.bytes, f)?;
+        if f.alternate() && self.scope_id != 0 {
+            write!(f, "%{}", self.scope_id)?;
+        }
+        Ok(())
     }
 }
 
@@ -768,6 +973,19 @@ impl From<net::Ipv6Addr> for IpAddrV6 {
     }
 }
 
+/// Converts to the std's address, that has no scope ID.
+impl From<IpAddrV6> for net::Ipv6Addr {
+    fn from(ip: IpAddrV6) -> Self {
+        ip.bytes.into()
+    }
+}
+
+impl AsRef<[u8]> for IpAddrV6 {
+    fn as_ref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
 /// Implements version-independent IP addresses.
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub enum IpAddr {
@@ -807,8 +1025,37 @@ impl IpAddr {
             &IpAddr::V6(ref addr) => addr.as_bytes(),
         }
     }
+
+    /// Returns the address added `rhs`, or `None` if it overflows.
+    pub fn checked_add(&self, rhs: i64) -> Option<IpAddr> {
+        match self {
+            &IpAddr::V4(ref addr) => addr.checked_add(rhs).map(IpAddr::V4),
+            &IpAddr::V6(ref addr) => addr.checked_add(rhs).map(IpAddr::V6),
+        }
+    }
+
+    /// Returns the address added `rhs`, saturating at the lowest or the highest address.
+    pub fn saturating_add(&self, rhs: i64) -> IpAddr {
+        match self {
+            &IpAddr::V4(ref addr) => IpAddr::V4(addr.saturating_add(rhs)),
+            &IpAddr::V6(ref addr) => IpAddr::V6(addr.saturating_add(rhs)),
+        }
+    }
+
+    /// Returns the address added `rhs`, wrapping around at the lowest or the highest address.
+    pub fn wrapping_add(&self, rhs: i64) -> IpAddr {
+        match self {
+            &IpAddr::V4(ref addr) => IpAddr::V4(addr.wrapping_add(rhs)),
+            &IpAddr::V6(ref addr) => IpAddr::V6(addr.wrapping_add(rhs)),
+        }
+    }
 }
 
+/// Adds `rhs` to the address.
+///
+/// # Panics
+///
+/// Panics if the address overflows, use the `checked_add` or the `wrapping_add` instead.
 impl AddAssign<i64> for IpAddr {
     fn add_assign(&mut self, rhs: i64) {
         match self {
@@ -818,6 +1065,11 @@ impl AddAssign<i64> for IpAddr {
     }
 }
 
+/// Subtracts `rhs` from the address.
+///
+/// # Panics
+///
+/// Panics if the address overflows, use the `checked_add` or the `wrapping_add` instead.
 impl SubAssign<i64> for IpAddr {
     fn sub_assign(&mut self, rhs: i64) {
         match self {
@@ -830,12 +1082,18 @@ impl SubAssign<i64> for IpAddr {
 impl fmt::Display for IpAddr {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            &IpAddr::V4(ref addr) => write!(f, "{}", addr),
-            &IpAddr::V6(ref addr) => write!(f, "{}", addr),
+            &IpAddr::V4(ref addr) => fmt::Display::fmt(addr, f),
+            &IpAddr::V6(ref addr) => fmt::Display::fmt(addr, f),
         }
     }
 }
 
+impl AsRef<[u8]> for IpAddr {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
 impl From<IpAddrV4> for IpAddr {
     fn from(ip: IpAddrV4) -> Self {
         IpAddr::V4(ip)
@@ -869,7 +1127,16 @@ impl From<net::IpAddr> for IpAddr {
     }
 }
 
-pub trait IpProtocol: Protocol + Eq + fmt::Display {
+impl From<IpAddr> for net::IpAddr {
+    fn from(ip: IpAddr) -> Self {
+        match ip {
+            IpAddr::V4(addr) => net::IpAddr::V4(addr.into()),
+            IpAddr::V6(addr) => net::IpAddr::V6(addr.into()),
+        }
+    }
+}
+
+pub trait IpProtocol: Protocol + Eq + fmt::Debug + fmt::Display {
     fn async_connect<F>(soc: &Self::Socket, ep: &IpEndpoint<Self>, handler: F) -> F::Output
     where
         F: Handler<(), io::Error>;
@@ -885,25 +1152,66 @@ mod network;
 pub use self::network::{IpNetworkV4, IpNetworkV6};
 
 mod endpoint;
-pub use self::endpoint::IpEndpoint;
+pub use self::endpoint::{IpEndpoint, IpEndpointV4, IpEndpointV6};
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod iface;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub use self::iface::{Iface, IfaceAddrV4};
 
+#[cfg(feature = "resolver")]
+mod idna;
+
+#[cfg(feature = "resolver")]
 mod resolve_op;
 
+#[cfg(feature = "resolver")]
 mod resolver;
-pub use self::resolver::{Passive, Resolver, ResolverIter, ResolverQuery};
+#[cfg(feature = "resolver")]
+pub use self::resolver::{AnySocketType, NoCache, Passive, Resolver, ResolverEntries, ResolverEntry,
+                         ResolverIter, ResolverQuery};
+
+#[cfg(feature = "resolver")]
+mod service;
+#[cfg(feature = "resolver")]
+pub use self::service::{port_to_service, service_to_port};
 
-mod icmp;
-pub use self::icmp::{Icmp, IcmpEndpoint, IcmpResolver, IcmpSocket};
+pub mod icmp;
+pub use self::icmp::{Icmp, IcmpEndpoint, IcmpSocket};
+#[cfg(feature = "resolver")]
+pub use self::icmp::IcmpResolver;
 
 mod udp;
-pub use self::udp::{Udp, UdpEndpoint, UdpResolver, UdpSocket};
+pub use self::udp::{Udp, UdpEndpoint, UdpSocket};
+#[cfg(feature = "resolver")]
+pub use self::udp::UdpResolver;
 
 mod tcp;
-pub use self::tcp::{Tcp, TcpEndpoint, TcpResolver, TcpListener, TcpSocket};
+pub use self::tcp::{Tcp, TcpEndpoint, TcpListener, TcpSocket};
+#[cfg(feature = "resolver")]
+pub use self::tcp::TcpResolver;
 
 mod options;
 pub use self::options::*;
 
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod mtu;
+
+mod profile;
+pub use self::profile::Profile;
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub mod dhcp;
+
+#[cfg(unix)]
+pub mod dns;
+
+#[cfg(unix)]
+pub mod srv;
+
+#[cfg(unix)]
+pub mod stun;
+
 
 #[test]
 fn test_lladdr() {
@@ -1029,19 +1337,70 @@ fn test_ipaddr_v6_format() {
     );
     assert_eq!(
         format!("{}", IpAddrV6::new(0, 2, 3, 4, 5, 6, 7, 8)),
-        "::2:3:4:5:6:7:8"
+        "0:2:3:4:5:6:7:8"
     );
     assert_eq!(
         format!("{}", IpAddrV6::new(1, 2, 3, 4, 5, 6, 7, 0)),
-        "1:2:3:4:5:6:7::"
+        "1:2:3:4:5:6:7:0"
     );
     assert_eq!(
         format!("{}", IpAddrV6::new(1, 2, 3, 4, 0, 6, 7, 8)),
-        "1:2:3:4::6:7:8"
+        "1:2:3:4:0:6:7:8"
     );
     assert_eq!(format!("{}", IpAddrV6::new(1, 0, 0, 0, 0, 0, 0, 8)), "1::8");
 }
 
+#[test]
+fn test_ipaddr_v6_format_rfc5952() {
+    // Section 4.1, the leading zeros are suppressed.
+    assert_eq!(
+        format!("{}", IpAddrV6::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1)),
+        "2001:db8::1"
+    );
+    // Section 4.2.1, the longest run is compressed as much as possible.
+    assert_eq!(
+        format!("{}", IpAddrV6::new(0x2001, 0xdb8, 0, 0, 0, 0, 2, 1)),
+        "2001:db8::2:1"
+    );
+    // Section 4.2.2, the single zero field is not compressed.
+    assert_eq!(
+        format!("{}", IpAddrV6::new(0x2001, 0xdb8, 0, 1, 1, 1, 1, 1)),
+        "2001:db8:0:1:1:1:1:1"
+    );
+    // Section 4.2.3, the longest run is compressed, and the first one if they are equal.
+    assert_eq!(
+        format!("{}", IpAddrV6::new(0x2001, 0, 0, 1, 0, 0, 0, 1)),
+        "2001:0:0:1::1"
+    );
+    assert_eq!(
+        format!("{}", IpAddrV6::new(0x2001, 0xdb8, 0, 0, 1, 0, 0, 1)),
+        "2001:db8::1:0:0:1"
+    );
+    // Section 4.3, the lowercase.
+    assert_eq!(
+        format!("{}", IpAddrV6::new(0x2001, 0xdb8, 0, 0, 0, 0, 0xabcd, 0xef01)),
+        "2001:db8::abcd:ef01"
+    );
+    // Section 5, the IPv4-mapped address.
+    assert_eq!(
+        format!("{}", IpAddrV6::v4_mapped(&IpAddrV4::new(192, 0, 2, 1))),
+        "::ffff:192.0.2.1"
+    );
+    assert_eq!(format!("{}", IpAddrV6::new(0, 0, 0, 0, 0, 0xffff, 0, 0)), "::ffff:0.0.0.0");
+    assert_eq!(format!("{}", IpAddrV6::new(0, 0, 0, 0, 0xffff, 0, 0, 1)), "::ffff:0:0:1");
+    assert_eq!(format!("{}", IpAddrV6::new(0, 0, 0, 0, 0, 0, 0, 0xffff)), "::ffff");
+    assert_eq!(
+        format!("{}", IpAddrV6::new(0xffff, 0xffff, 0xffff, 0xffff, 0xffff, 0xffff, 0xffff, 0xffff)),
+        "ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff"
+    );
+
+    // The alternate form with the scope-id.
+    let ip = IpAddrV6::with_scope_id(0xfe80, 0, 0, 0, 0, 0, 0, 1, 2);
+    assert_eq!(format!("{}", ip), "fe80::1");
+    assert_eq!(format!("{:#}", ip), "fe80::1%2");
+    assert_eq!(format!("{:#}", IpAddrV6::loopback()), "::1");
+}
+
 #[test]
 fn test_add_assign() {
     let mut a = [0, 0];
@@ -1078,6 +1437,53 @@ fn test_sub_assign_underflow() {
     sub_assign(&mut a, 1);
 }
 
+#[test]
+fn test_overflowing_add() {
+    let mut a = [0xFF, 0xFF];
+    assert!(overflowing_add(&mut a, 2));
+    assert_eq!(&a, &[0, 1]);
+    assert!(overflowing_add(&mut a, -2));
+    assert_eq!(&a, &[0xFF, 0xFF]);
+    assert!(!overflowing_add(&mut a, -0xFFFF));
+    assert_eq!(&a, &[0, 0]);
+    assert!(overflowing_add(&mut a, 0x10000));
+    assert_eq!(&a, &[0, 0]);
+    assert!(overflowing_add(&mut a, i64::min_value()));
+    assert_eq!(&a, &[0, 0]);
+    assert!(overflowing_sub(&mut a, i64::min_value()));
+    assert_eq!(&a, &[0, 0]);
+    assert!(!overflowing_sub(&mut a, -0x102));
+    assert_eq!(&a, &[1, 2]);
+}
+
+#[test]
+fn test_ip_addr_checked_add() {
+    let max = IpAddrV4::new(255, 255, 255, 255);
+    assert_eq!(max.checked_add(1), None);
+    assert_eq!(max.checked_add(-1), Some(IpAddrV4::new(255, 255, 255, 254)));
+    assert_eq!(max.saturating_add(1), max);
+    assert_eq!(max.wrapping_add(1), IpAddrV4::any());
+    assert_eq!(IpAddrV4::any().checked_add(-1), None);
+    assert_eq!(IpAddrV4::any().saturating_add(-1), IpAddrV4::any());
+    assert_eq!(IpAddrV4::any().wrapping_add(-1), max);
+    assert_eq!(
+        IpAddrV4::new(10, 0, 0, 255).checked_add(0x101),
+        Some(IpAddrV4::new(10, 0, 2, 0))
+    );
+
+    let v6 = IpAddrV6::with_scope_id(0xfe80, 0, 0, 0, 0, 0, 0, 0xffff, 1);
+    assert_eq!(
+        v6.checked_add(1),
+        Some(IpAddrV6::with_scope_id(0xfe80, 0, 0, 0, 0, 0, 1, 0, 1))
+    );
+    assert_eq!(IpAddrV6::any().saturating_add(i64::min_value()), IpAddrV6::any());
+
+    let mac = LlAddr::new(0, 0, 0, 0, 0, 0xff);
+    assert_eq!(mac.wrapping_add(1), LlAddr::new(0, 0, 0, 0, 1, 0));
+    assert_eq!(IpAddr::V4(max).checked_add(1), None);
+    assert_eq!(IpAddr::V4(max).wrapping_add(1), IpAddr::V4(IpAddrV4::any()));
+}
+
 #[test]
 fn test_ip_addr_as_bytes() {
     let bytes = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16];
@@ -1090,3 +1496,30 @@ fn test_ip_addr_as_bytes() {
     assert!(v6.as_bytes() == &bytes[..]);
     assert!(v6.as_bytes() != v4.as_bytes());
 }
+
+#[test]
+fn test_ip_addr_hash_map() {
+    use std::collections::HashMap;
+
+    let mut map = HashMap::new();
+    map.insert(IpAddrV4::new(192, 168, 0, 1), 1);
+    map.insert(IpAddrV4::new(192, 168, 0, 2), 2);
+    assert_eq!(map.get(&[192, 168, 0, 1][..]), Some(&1));
+    assert_eq!(map.get(&IpAddrV4::new(192, 168, 0, 2)), Some(&2));
+    assert_eq!(map.get(&[192, 168, 0, 3][..]), None);
+
+    let mut map = HashMap::new();
+    map.insert(LlAddr::new(0, 1, 2, 3, 4, 5), 1);
+    assert_eq!(map.get(&[0, 1, 2, 3, 4, 5][..]), Some(&1));
+
+    let mut map = HashMap::new();
+    map.insert(IpAddrV6::with_scope_id(0xfe80, 0, 0, 0, 0, 0, 0, 1, 1), 1);
+    map.insert(IpAddrV6::with_scope_id(0xfe80, 0, 0, 0, 0, 0, 0, 1, 2), 2);
+    assert_eq!(map.len(), 2);
+    assert_eq!(map.get(&IpAddrV6::with_scope_id(0xfe80, 0, 0, 0, 0, 0, 0, 1, 2)), Some(&2));
+    assert_eq!(map.get(&IpAddrV6::new(0xfe80, 0, 0, 0, 0, 0, 0, 1)), None);
+
+    let v6 = IpAddrV6::with_scope_id(0xfe80, 0, 0, 0, 0, 0, 0, 1, 1);
+    assert!(v6.as_ref() == IpAddrV6::new(0xfe80, 0, 0, 0, 0, 0, 0, 1).as_ref());
+    assert!(IpAddr::V6(v6).as_ref() == &v6.as_bytes()[..]);
+}
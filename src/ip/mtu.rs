@@ -0,0 +1,88 @@
+use ffi::{AF_INET6, IPPROTO_IP, IPPROTO_IPV6, if_mtu, if_name_by_addr, raw_getsockopt};
+use core::{Endpoint, Socket};
+use ip::{IpAddr, IpEndpoint, IpProtocol};
+use stream_socket::StreamSocket;
+use dgram_socket::DgramSocket;
+
+use std::io;
+use std::mem;
+use libc::{IP_MTU, IPV6_MTU};
+
+fn path_mtu<P, S>(soc: &S, local: &IpEndpoint<P>) -> io::Result<usize>
+where
+    P: IpProtocol,
+    S: Socket<P>,
+{
+    let (level, name) = if soc.protocol().family_type() == AF_INET6 {
+        (IPPROTO_IPV6, IPV6_MTU)
+    } else {
+        (IPPROTO_IP, IP_MTU)
+    };
+    let mut mtu = [0; 4];
+    if let Ok(len) = unsafe { raw_getsockopt(soc, level, name, &mut mtu) } {
+        if len == mem::size_of_val(&mtu) {
+            return Ok(i32::from_ne_bytes(mtu) as usize);
+        }
+    }
+
+    // Falls back to the MTU of the interface which has the local address.
+    let name = match local.addr() {
+        IpAddr::V4(addr) => if_name_by_addr(local.family(), addr.as_bytes())?,
+        IpAddr::V6(addr) => if_name_by_addr(local.family(), addr.as_bytes())?,
+    };
+    Ok(if_mtu(soc, &name)? as usize)
+}
+
+impl<P> StreamSocket<P>
+where
+    P: IpProtocol<Endpoint = IpEndpoint<P>>,
+{
+    /// Returns the MTU of the path to the connected peer.
+    ///
+    /// Uses the `IP_MTU` or `IPV6_MTU` option, or the MTU of the interface which has the local
+    /// address if the option is not available. The payload size excludes the IP and TCP headers.
+    pub fn path_mtu(&self) -> io::Result<usize> {
+        path_mtu(self, &self.local_endpoint()?)
+    }
+}
+
+impl<P> DgramSocket<P>
+where
+    P: IpProtocol<Endpoint = IpEndpoint<P>>,
+{
+    /// Returns the MTU of the path to the connected peer.
+    ///
+    /// Uses the `IP_MTU` or `IPV6_MTU` option, or the MTU of the interface which has the local
+    /// address if the option is not available. The payload size excludes the IP and UDP headers.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use asyncio::IoContext;
+    /// use asyncio::ip::{IpProtocol, IpAddrV4, Udp, UdpEndpoint, UdpSocket};
+    ///
+    /// let ctx = &IoContext::new().unwrap();
+    /// let soc = UdpSocket::new(ctx, Udp::v4()).unwrap();
+    /// soc.connect(&UdpEndpoint::new(IpAddrV4::loopback(), 12345)).unwrap();
+    /// assert!(soc.path_mtu().unwrap() >= 576);
+    /// ```
+    pub fn path_mtu(&self) -> io::Result<usize> {
+        path_mtu(self, &self.local_endpoint()?)
+    }
+}
+
+#[test]
+fn test_path_mtu_of_interface() {
+    use core::IoContext;
+    use ip::{IpAddrV4, Udp, UdpEndpoint, UdpSocket};
+
+    // The IP_MTU is not available on the unconnected socket.
+    let ctx = &IoContext::new().unwrap();
+    let soc = UdpSocket::new(ctx, Udp::v4()).unwrap();
+    soc.bind(&UdpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+    assert!(soc.path_mtu().unwrap() >= 576);
+
+    let soc = UdpSocket::new(ctx, Udp::v4()).unwrap();
+    soc.bind(&UdpEndpoint::new(IpAddrV4::any(), 0)).unwrap();
+    assert!(soc.path_mtu().is_err());
+}
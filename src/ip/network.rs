@@ -182,8 +182,7 @@ impl IpNetworkV4 {
     }
 
     pub fn hosts(&self) -> (IpAddrV4, IpAddrV4) {
-        let mut addr = self.address();
-        addr += 1;
+        let addr = self.address().wrapping_add(1);
         if self.is_host() {
             (self.address(), addr)
         } else {
@@ -262,6 +261,7 @@ impl fmt::Display for IpNetworkV4 {
 }
 
 /// Implements Network IP version 6 style addresses.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 pub struct IpNetworkV6 {
     bytes: [u8; 16],
     len: u8,
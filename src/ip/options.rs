@@ -1,13 +1,25 @@
 use ffi::{IPPROTO_IP, IPPROTO_IPV6, IPPROTO_TCP, IP_ADD_MEMBERSHIP, IP_DROP_MEMBERSHIP,
           IP_MULTICAST_IF, IP_TTL, IP_MULTICAST_TTL, IPV6_UNICAST_HOPS, IP_MULTICAST_LOOP,
           IPV6_JOIN_GROUP, IPV6_LEAVE_GROUP, IPV6_MULTICAST_IF, IPV6_MULTICAST_HOPS,
-          IPV6_MULTICAST_LOOP, IPV6_V6ONLY, TCP_NODELAY, gethostname, in_addr, in6_addr, ip_mreq,
+          IPV6_MULTICAST_LOOP, IPV6_V6ONLY, IPV6_TCLASS, IP_TOS, TCP_NODELAY, gethostname, in_addr, in6_addr, ip_mreq,
           ipv6_mreq};
 use core::{GetSocketOption, SetSocketOption, SocketOption, IoContext};
 use ip::{IpAddr, IpAddrV4, IpAddrV6, IpProtocol, Tcp};
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use ffi::{SOL_RAW, ICMP_FILTER, ICMP6_FILTER, IPPROTO_ICMPV6, IP_TRANSPARENT, IPV6_TRANSPARENT,
+          IP_RECVORIGDSTADDR, IPV6_RECVORIGDSTADDR, SO_ORIGINAL_DST, IP6T_SO_ORIGINAL_DST,
+          IP_RECVTTL, IPV6_RECVHOPLIMIT, TCP_CORK, TCP_QUICKACK, sockaddr_storage,
+          MCAST_JOIN_SOURCE_GROUP, MCAST_LEAVE_SOURCE_GROUP, MCAST_BLOCK_SOURCE,
+          MCAST_UNBLOCK_SOURCE, group_source_req};
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use core::Endpoint;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use ip::{Icmp, TcpEndpoint, UdpEndpoint};
 
 use std::io;
 use std::mem;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use std::{cmp, ptr, slice};
 use libc::c_void;
 
 fn in_addr(addr: IpAddrV4) -> in_addr {
@@ -153,6 +165,137 @@ impl GetSocketOption<Tcp> for NoDelay {}
 
 impl SetSocketOption<Tcp> for NoDelay {}
 
+/// Socket option for holding the partial frames until the cork is removed.
+///
+/// Implements the IPPROTO_TCP/TCP_CORK socket option, that is removed by setting it to false.
+///
+/// # Examples
+/// Setting the option:
+///
+/// ```
+/// use asyncio::*;
+/// use asyncio::ip::*;
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let soc = TcpSocket::new(ctx, Tcp::v4()).unwrap();
+///
+/// soc.set_option(Cork::new(true)).unwrap();
+/// ```
+///
+/// Getting the option:
+///
+/// ```
+/// use asyncio::*;
+/// use asyncio::ip::*;
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let soc = TcpSocket::new(ctx, Tcp::v4()).unwrap();
+///
+/// let opt: Cork = soc.get_option().unwrap();
+/// let is_set: bool = opt.get();
+/// ```
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[derive(Default, Clone)]
+pub struct Cork(i32);
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl Cork {
+    pub fn new(on: bool) -> Cork {
+        Cork(on as i32)
+    }
+
+    pub fn get(&self) -> bool {
+        self.0 != 0
+    }
+
+    pub fn set(&mut self, on: bool) {
+        self.0 = on as i32
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl SocketOption<Tcp> for Cork {
+    fn level(&self, _: &Tcp) -> i32 {
+        IPPROTO_TCP.into()
+    }
+
+    fn name(&self, _: &Tcp) -> i32 {
+        TCP_CORK
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl GetSocketOption<Tcp> for Cork {}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl SetSocketOption<Tcp> for Cork {}
+
+/// Socket option for sending the acknowledgements immediately.
+///
+/// Implements the IPPROTO_TCP/TCP_QUICKACK socket option, that the kernel may reset after the
+/// next receiving.
+///
+/// # Examples
+/// Setting the option:
+///
+/// ```
+/// use asyncio::*;
+/// use asyncio::ip::*;
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let soc = TcpSocket::new(ctx, Tcp::v4()).unwrap();
+///
+/// soc.set_option(QuickAck::new(true)).unwrap();
+/// ```
+///
+/// Getting the option:
+///
+/// ```
+/// use asyncio::*;
+/// use asyncio::ip::*;
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let soc = TcpSocket::new(ctx, Tcp::v4()).unwrap();
+///
+/// let opt: QuickAck = soc.get_option().unwrap();
+/// let is_set: bool = opt.get();
+/// ```
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[derive(Default, Clone)]
+pub struct QuickAck(i32);
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl QuickAck {
+    pub fn new(on: bool) -> QuickAck {
+        QuickAck(on as i32)
+    }
+
+    pub fn get(&self) -> bool {
+        self.0 != 0
+    }
+
+    pub fn set(&mut self, on: bool) {
+        self.0 = on as i32
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl SocketOption<Tcp> for QuickAck {
+    fn level(&self, _: &Tcp) -> i32 {
+        IPPROTO_TCP.into()
+    }
+
+    fn name(&self, _: &Tcp) -> i32 {
+        TCP_QUICKACK
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl GetSocketOption<Tcp> for QuickAck {}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl SetSocketOption<Tcp> for QuickAck {}
+
 /// Socket option for time-to-live associated with outgoing unicast packets.
 ///
 /// Implements the IPPROTO_IP/IP_UNICAST_TTL or IPPROTO_IPV6/IPV6_UNICAST_HOPS socket option.
@@ -225,6 +368,87 @@ impl<P: IpProtocol> GetSocketOption<P> for UnicastHops {}
 
 impl<P: IpProtocol> SetSocketOption<P> for UnicastHops {}
 
+/// Socket option for the traffic class (type of service) associated with outgoing packets.
+///
+/// Implements the IPPROTO_IP/IP_TOS or IPPROTO_IPV6/IPV6_TCLASS socket option.
+///
+/// # Examples
+/// Setting the option:
+///
+/// ```
+/// use asyncio::*;
+/// use asyncio::ip::*;
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let soc = UdpSocket::new(ctx, Udp::v4()).unwrap();
+///
+/// soc.set_option(TrafficClass::from_dscp(46)).unwrap();
+/// ```
+///
+/// Getting the option:
+///
+/// ```
+/// use asyncio::*;
+/// use asyncio::ip::*;
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let soc = UdpSocket::new(ctx, Udp::v4()).unwrap();
+///
+/// let opt: TrafficClass = soc.get_option().unwrap();
+/// let dscp: u8 = opt.dscp();
+/// ```
+#[derive(Default, Clone)]
+pub struct TrafficClass(i32);
+
+impl TrafficClass {
+    pub fn new(tc: u8) -> TrafficClass {
+        TrafficClass(tc as i32)
+    }
+
+    /// Returns a traffic class of the differentiated services code point, that ECN bits are zero.
+    pub fn from_dscp(dscp: u8) -> TrafficClass {
+        TrafficClass(((dscp & 0x3F) << 2) as i32)
+    }
+
+    pub fn dscp(&self) -> u8 {
+        (self.0 >> 2) as u8 & 0x3F
+    }
+
+    pub fn get(&self) -> u8 {
+        self.0 as u8
+    }
+
+    pub fn set(&mut self, tc: u8) {
+        self.0 = tc as i32
+    }
+}
+
+impl<P: IpProtocol> SocketOption<P> for TrafficClass {
+    fn level(&self, pro: &P) -> i32 {
+        if pro == &P::v4() {
+            return IPPROTO_IP.into();
+        }
+        if pro == &P::v6() {
+            return IPPROTO_IPV6.into();
+        }
+        unreachable!("Invalid ip version")
+    }
+
+    fn name(&self, pro: &P) -> i32 {
+        if pro == &P::v4() {
+            return IP_TOS;
+        }
+        if pro == &P::v6() {
+            return IPV6_TCLASS;
+        }
+        unreachable!("Invalid ip version")
+    }
+}
+
+impl<P: IpProtocol> GetSocketOption<P> for TrafficClass {}
+
+impl<P: IpProtocol> SetSocketOption<P> for TrafficClass {}
+
 /// Socket option determining whether outgoing multicast packets will be received on the same socket
 /// if it is a member of the multicast group.
 ///
@@ -548,15 +772,66 @@ impl<P: IpProtocol> SetSocketOption<P> for MulticastLeaveGroup {
     }
 }
 
+#[cfg(any(target_os = "linux", target_os = "android"))]
 #[derive(Clone)]
-enum Iface {
-    V4(in_addr),
-    V6(u32),
+struct SourceReq(group_source_req);
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl SourceReq {
+    fn new(group: IpAddr, source: IpAddr, interface: u32) -> Self {
+        fn storage(addr: IpAddr) -> sockaddr_storage {
+            let ep = UdpEndpoint::new(addr, 0);
+            let mut ss: sockaddr_storage = unsafe { mem::zeroed() };
+            unsafe {
+                ptr::copy_nonoverlapping(
+                    ep.as_ptr() as *const u8,
+                    &mut ss as *mut _ as *mut u8,
+                    ep.size() as usize,
+                )
+            };
+            ss
+        }
+
+        SourceReq(group_source_req {
+            gsr_interface: interface,
+            gsr_group: storage(group),
+            gsr_source: storage(source),
+        })
+    }
+
+    fn v4(group: IpAddrV4, source: IpAddrV4) -> Self {
+        SourceReq::new(IpAddr::V4(group), IpAddr::V4(source), 0)
+    }
+
+    fn v6(group: IpAddrV6, source: IpAddrV6) -> Self {
+        let scope_id = group.scope_id();
+        SourceReq::new(IpAddr::V6(group), IpAddr::V6(source), scope_id)
+    }
+
+    fn level<P: IpProtocol>(&self, pro: &P) -> i32 {
+        if pro == &P::v4() {
+            return IPPROTO_IP;
+        }
+        if pro == &P::v6() {
+            return IPPROTO_IPV6;
+        }
+        unreachable!("Invalid ip version")
+    }
+
+    fn as_ptr(&self) -> *const c_void {
+        &self.0 as *const _ as *const _
+    }
+
+    fn size(&self) -> u32 {
+        mem::size_of_val(&self.0) as u32
+    }
 }
 
-/// Socket option for local interface to use for outgoing multicast packets.
+/// Socket option to join a source-specific multicast group, that receives the datagrams sent
+/// from the source only.
 ///
-/// Implements the IPPROTO_IP/IP_MULTICAST_IF socket option.
+/// Implements the IPPROTO_IP/MCAST_JOIN_SOURCE_GROUP or IPPROTO_IPV6/MCAST_JOIN_SOURCE_GROUP
+/// socket option.
 ///
 /// # Examples
 /// Setting the option:
@@ -568,63 +843,714 @@ enum Iface {
 /// let ctx = &IoContext::new().unwrap();
 /// let soc = UdpSocket::new(ctx, Udp::v4()).unwrap();
 ///
-/// //soc.set_option(OutboundInterface::new(IpAddr::V4(IpAddrV4::new(1,2,3,4))));
+/// let opt = MulticastJoinSourceGroup::v4(IpAddrV4::new(232,1,1,1), IpAddrV4::new(192,0,2,1));
+/// //soc.set_option(opt).unwrap();
 /// ```
+#[cfg(any(target_os = "linux", target_os = "android"))]
 #[derive(Clone)]
-pub struct OutboundInterface(Iface);
+pub struct MulticastJoinSourceGroup(SourceReq);
 
-impl OutboundInterface {
-    pub fn v4(interface: IpAddrV4) -> OutboundInterface {
-        OutboundInterface(Iface::V4(in_addr(interface)))
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl MulticastJoinSourceGroup {
+    pub fn v4(group: IpAddrV4, source: IpAddrV4) -> Self {
+        MulticastJoinSourceGroup(SourceReq::v4(group, source))
     }
 
-    pub fn v6(scope_id: u32) -> OutboundInterface {
-        OutboundInterface(Iface::V6(scope_id))
+    /// Returns the option on the interface of the scope id of the `group`.
+    pub fn v6(group: IpAddrV6, source: IpAddrV6) -> Self {
+        MulticastJoinSourceGroup(SourceReq::v6(group, source))
     }
 }
 
-impl<P: IpProtocol> SocketOption<P> for OutboundInterface {
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<P: IpProtocol> SocketOption<P> for MulticastJoinSourceGroup {
     fn level(&self, pro: &P) -> i32 {
-        if pro == &P::v4() {
-            return IPPROTO_IP;
-        }
-        if pro == &P::v6() {
-            return IPPROTO_IPV6;
-        }
-        unreachable!("Invalid ip version")
+        self.0.level(pro)
     }
 
-    fn name(&self, pro: &P) -> i32 {
-        if pro == &P::v4() {
-            return IP_MULTICAST_IF;
-        }
-        if pro == &P::v6() {
-            return IPV6_MULTICAST_IF;
-        }
-        unreachable!("Invalid ip version")
+    fn name(&self, _: &P) -> i32 {
+        MCAST_JOIN_SOURCE_GROUP
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<P: IpProtocol> SetSocketOption<P> for MulticastJoinSourceGroup {
+    fn as_ptr(&self) -> *const c_void {
+        self.0.as_ptr()
     }
 
-    fn capacity(&self) -> u32 {
-        mem::size_of::<in_addr>() as u32
+    fn size(&self) -> u32 {
+        self.0.size()
     }
 }
 
-impl<P: IpProtocol> SetSocketOption<P> for OutboundInterface {
+/// Socket option to leave a source-specific multicast group.
+///
+/// Implements the IPPROTO_IP/MCAST_LEAVE_SOURCE_GROUP or IPPROTO_IPV6/MCAST_LEAVE_SOURCE_GROUP
+/// socket option.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[derive(Clone)]
+pub struct MulticastLeaveSourceGroup(SourceReq);
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl MulticastLeaveSourceGroup {
+    pub fn v4(group: IpAddrV4, source: IpAddrV4) -> Self {
+        MulticastLeaveSourceGroup(SourceReq::v4(group, source))
+    }
+
+    /// Returns the option on the interface of the scope id of the `group`.
+    pub fn v6(group: IpAddrV6, source: IpAddrV6) -> Self {
+        MulticastLeaveSourceGroup(SourceReq::v6(group, source))
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<P: IpProtocol> SocketOption<P> for MulticastLeaveSourceGroup {
+    fn level(&self, pro: &P) -> i32 {
+        self.0.level(pro)
+    }
+
+    fn name(&self, _: &P) -> i32 {
+        MCAST_LEAVE_SOURCE_GROUP
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<P: IpProtocol> SetSocketOption<P> for MulticastLeaveSourceGroup {
     fn as_ptr(&self) -> *const c_void {
-        match &self.0 {
-            &Iface::V4(ref addr) => &addr as *const _ as *const _,
-            &Iface::V6(ref scope_id) => &scope_id as *const _ as *const _,
-        }
+        self.0.as_ptr()
+    }
+
+    fn size(&self) -> u32 {
+        self.0.size()
     }
 }
 
-#[test]
-fn test_host_name() {
-    let ctx = &IoContext::new().unwrap();
-    host_name(ctx).unwrap();
+/// Socket option to block the datagrams from the source in the any-source multicast group joined
+/// by the `MulticastJoinGroup`.
+///
+/// Implements the IPPROTO_IP/MCAST_BLOCK_SOURCE or IPPROTO_IPV6/MCAST_BLOCK_SOURCE socket option.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[derive(Clone)]
+pub struct MulticastBlockSource(SourceReq);
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl MulticastBlockSource {
+    pub fn v4(group: IpAddrV4, source: IpAddrV4) -> Self {
+        MulticastBlockSource(SourceReq::v4(group, source))
+    }
+
+    /// Returns the option on the interface of the scope id of the `group`.
+    pub fn v6(group: IpAddrV6, source: IpAddrV6) -> Self {
+        MulticastBlockSource(SourceReq::v6(group, source))
+    }
 }
 
-#[test]
-fn test_outbound_interface() {
-    assert_eq!(mem::size_of::<u32>(), mem::size_of::<in_addr>());
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<P: IpProtocol> SocketOption<P> for MulticastBlockSource {
+    fn level(&self, pro: &P) -> i32 {
+        self.0.level(pro)
+    }
+
+    fn name(&self, _: &P) -> i32 {
+        MCAST_BLOCK_SOURCE
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<P: IpProtocol> SetSocketOption<P> for MulticastBlockSource {
+    fn as_ptr(&self) -> *const c_void {
+        self.0.as_ptr()
+    }
+
+    fn size(&self) -> u32 {
+        self.0.size()
+    }
+}
+
+/// Socket option to unblock the source blocked by the `MulticastBlockSource`.
+///
+/// Implements the IPPROTO_IP/MCAST_UNBLOCK_SOURCE or IPPROTO_IPV6/MCAST_UNBLOCK_SOURCE socket
+/// option.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[derive(Clone)]
+pub struct MulticastUnblockSource(SourceReq);
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl MulticastUnblockSource {
+    pub fn v4(group: IpAddrV4, source: IpAddrV4) -> Self {
+        MulticastUnblockSource(SourceReq::v4(group, source))
+    }
+
+    /// Returns the option on the interface of the scope id of the `group`.
+    pub fn v6(group: IpAddrV6, source: IpAddrV6) -> Self {
+        MulticastUnblockSource(SourceReq::v6(group, source))
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<P: IpProtocol> SocketOption<P> for MulticastUnblockSource {
+    fn level(&self, pro: &P) -> i32 {
+        self.0.level(pro)
+    }
+
+    fn name(&self, _: &P) -> i32 {
+        MCAST_UNBLOCK_SOURCE
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<P: IpProtocol> SetSocketOption<P> for MulticastUnblockSource {
+    fn as_ptr(&self) -> *const c_void {
+        self.0.as_ptr()
+    }
+
+    fn size(&self) -> u32 {
+        self.0.size()
+    }
+}
+
+#[derive(Clone)]
+enum Iface {
+    V4(in_addr),
+    V6(u32),
+}
+
+/// Socket option for local interface to use for outgoing multicast packets.
+///
+/// Implements the IPPROTO_IP/IP_MULTICAST_IF socket option.
+///
+/// # Examples
+/// Setting the option:
+///
+/// ```
+/// use asyncio::*;
+/// use asyncio::ip::*;
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let soc = UdpSocket::new(ctx, Udp::v4()).unwrap();
+///
+/// //soc.set_option(OutboundInterface::new(IpAddr::V4(IpAddrV4::new(1,2,3,4))));
+/// ```
+#[derive(Clone)]
+pub struct OutboundInterface(Iface);
+
+impl OutboundInterface {
+    pub fn v4(interface: IpAddrV4) -> OutboundInterface {
+        OutboundInterface(Iface::V4(in_addr(interface)))
+    }
+
+    pub fn v6(scope_id: u32) -> OutboundInterface {
+        OutboundInterface(Iface::V6(scope_id))
+    }
+}
+
+impl<P: IpProtocol> SocketOption<P> for OutboundInterface {
+    fn level(&self, pro: &P) -> i32 {
+        if pro == &P::v4() {
+            return IPPROTO_IP;
+        }
+        if pro == &P::v6() {
+            return IPPROTO_IPV6;
+        }
+        unreachable!("Invalid ip version")
+    }
+
+    fn name(&self, pro: &P) -> i32 {
+        if pro == &P::v4() {
+            return IP_MULTICAST_IF;
+        }
+        if pro == &P::v6() {
+            return IPV6_MULTICAST_IF;
+        }
+        unreachable!("Invalid ip version")
+    }
+
+    fn capacity(&self) -> u32 {
+        mem::size_of::<in_addr>() as u32
+    }
+}
+
+impl<P: IpProtocol> SetSocketOption<P> for OutboundInterface {
+    fn as_ptr(&self) -> *const c_void {
+        match &self.0 {
+            &Iface::V4(ref addr) => &addr as *const _ as *const _,
+            &Iface::V6(ref scope_id) => &scope_id as *const _ as *const _,
+        }
+    }
+}
+
+/// Socket option for the filter of the ICMP message types received by the raw socket.
+///
+/// Implements the SOL_RAW/ICMP_FILTER or IPPROTO_ICMPV6/ICMP6_FILTER socket option.
+/// The ICMP_FILTER applies to the message types less than 32 only.
+///
+/// # Examples
+/// Setting the option:
+///
+/// ```rust,no_run
+/// use asyncio::*;
+/// use asyncio::ip::*;
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let soc = IcmpSocket::new(ctx, Icmp::v4()).unwrap();
+///
+/// let mut opt = IcmpFilter::block_all();
+/// opt.pass(0);  // Echo Reply
+/// opt.pass(11); // Time Exceeded
+/// soc.set_option(opt).unwrap();
+/// ```
+///
+/// Getting the option:
+///
+/// ```rust,no_run
+/// use asyncio::*;
+/// use asyncio::ip::*;
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let soc = IcmpSocket::new(ctx, Icmp::v6()).unwrap();
+///
+/// let opt: IcmpFilter = soc.get_option().unwrap();
+/// let is_passed: bool = opt.will_pass(129); // Echo Reply
+/// ```
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[derive(Default, Clone)]
+pub struct IcmpFilter([u32; 8]);
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl IcmpFilter {
+    /// Returns a filter blocking all message types.
+    pub fn block_all() -> IcmpFilter {
+        IcmpFilter([!0; 8])
+    }
+
+    /// Returns a filter passing all message types.
+    pub fn pass_all() -> IcmpFilter {
+        IcmpFilter([0; 8])
+    }
+
+    pub fn block(&mut self, ty: u8) {
+        self.0[(ty >> 5) as usize] |= 1 << (ty & 31)
+    }
+
+    pub fn pass(&mut self, ty: u8) {
+        self.0[(ty >> 5) as usize] &= !(1 << (ty & 31))
+    }
+
+    pub fn will_block(&self, ty: u8) -> bool {
+        self.0[(ty >> 5) as usize] & (1 << (ty & 31)) != 0
+    }
+
+    pub fn will_pass(&self, ty: u8) -> bool {
+        !self.will_block(ty)
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl SocketOption<Icmp> for IcmpFilter {
+    fn level(&self, pro: &Icmp) -> i32 {
+        if pro == &Icmp::v4() {
+            return SOL_RAW;
+        }
+        if pro == &Icmp::v6() {
+            return IPPROTO_ICMPV6;
+        }
+        unreachable!("Invalid ip version")
+    }
+
+    fn name(&self, pro: &Icmp) -> i32 {
+        if pro == &Icmp::v4() {
+            return ICMP_FILTER;
+        }
+        if pro == &Icmp::v6() {
+            return ICMP6_FILTER;
+        }
+        unreachable!("Invalid ip version")
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl GetSocketOption<Icmp> for IcmpFilter {}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl SetSocketOption<Icmp> for IcmpFilter {}
+
+/// Socket option for binding to the non-local address, that is used by the transparent proxy.
+///
+/// Implements the IPPROTO_IP/IP_TRANSPARENT or IPPROTO_IPV6/IPV6_TRANSPARENT socket option,
+/// which requires the CAP_NET_ADMIN capability.
+///
+/// # Examples
+/// Setting the option:
+///
+/// ```rust,no_run
+/// use asyncio::*;
+/// use asyncio::ip::*;
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let soc = UdpSocket::new(ctx, Udp::v4()).unwrap();
+///
+/// soc.set_option(Transparent::new(true)).unwrap();
+/// ```
+///
+/// Getting the option:
+///
+/// ```
+/// use asyncio::*;
+/// use asyncio::ip::*;
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let soc = UdpSocket::new(ctx, Udp::v4()).unwrap();
+///
+/// let opt: Transparent = soc.get_option().unwrap();
+/// let is_set: bool = opt.get();
+/// ```
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[derive(Default, Clone)]
+pub struct Transparent(i32);
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl Transparent {
+    pub fn new(on: bool) -> Transparent {
+        Transparent(on as i32)
+    }
+
+    pub fn get(&self) -> bool {
+        self.0 != 0
+    }
+
+    pub fn set(&mut self, on: bool) {
+        self.0 = on as i32
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<P: IpProtocol> SocketOption<P> for Transparent {
+    fn level(&self, pro: &P) -> i32 {
+        if pro == &P::v4() {
+            return IPPROTO_IP;
+        }
+        if pro == &P::v6() {
+            return IPPROTO_IPV6;
+        }
+        unreachable!("Invalid ip version")
+    }
+
+    fn name(&self, pro: &P) -> i32 {
+        if pro == &P::v4() {
+            return IP_TRANSPARENT;
+        }
+        if pro == &P::v6() {
+            return IPV6_TRANSPARENT;
+        }
+        unreachable!("Invalid ip version")
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<P: IpProtocol> GetSocketOption<P> for Transparent {}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<P: IpProtocol> SetSocketOption<P> for Transparent {}
+
+/// Socket option for receiving the original destination address of the datagram.
+///
+/// Implements the IPPROTO_IP/IP_RECVORIGDSTADDR or IPPROTO_IPV6/IPV6_RECVORIGDSTADDR socket option.
+/// The address is received by `DgramSocket::receive_from_orig_dst`.
+///
+/// # Examples
+/// Setting the option:
+///
+/// ```
+/// use asyncio::*;
+/// use asyncio::ip::*;
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let soc = UdpSocket::new(ctx, Udp::v6()).unwrap();
+///
+/// soc.set_option(RecvOrigDstAddr::new(true)).unwrap();
+/// ```
+///
+/// Getting the option:
+///
+/// ```
+/// use asyncio::*;
+/// use asyncio::ip::*;
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let soc = UdpSocket::new(ctx, Udp::v6()).unwrap();
+///
+/// let opt: RecvOrigDstAddr = soc.get_option().unwrap();
+/// let is_set: bool = opt.get();
+/// ```
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[derive(Default, Clone)]
+pub struct RecvOrigDstAddr(i32);
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl RecvOrigDstAddr {
+    pub fn new(on: bool) -> RecvOrigDstAddr {
+        RecvOrigDstAddr(on as i32)
+    }
+
+    pub fn get(&self) -> bool {
+        self.0 != 0
+    }
+
+    pub fn set(&mut self, on: bool) {
+        self.0 = on as i32
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<P: IpProtocol> SocketOption<P> for RecvOrigDstAddr {
+    fn level(&self, pro: &P) -> i32 {
+        if pro == &P::v4() {
+            return IPPROTO_IP;
+        }
+        if pro == &P::v6() {
+            return IPPROTO_IPV6;
+        }
+        unreachable!("Invalid ip version")
+    }
+
+    fn name(&self, pro: &P) -> i32 {
+        if pro == &P::v4() {
+            return IP_RECVORIGDSTADDR;
+        }
+        if pro == &P::v6() {
+            return IPV6_RECVORIGDSTADDR;
+        }
+        unreachable!("Invalid ip version")
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<P: IpProtocol> GetSocketOption<P> for RecvOrigDstAddr {}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<P: IpProtocol> SetSocketOption<P> for RecvOrigDstAddr {}
+
+/// Socket option for receiving the hop limit of the datagram.
+///
+/// Implements the IPPROTO_IP/IP_RECVTTL or IPPROTO_IPV6/IPV6_RECVHOPLIMIT socket option.
+/// The hop limit is received by `DgramSocket::receive_from_hop_limit`.
+///
+/// # Examples
+/// Setting the option:
+///
+/// ```
+/// use asyncio::*;
+/// use asyncio::ip::*;
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let soc = UdpSocket::new(ctx, Udp::v6()).unwrap();
+///
+/// soc.set_option(RecvHopLimit::new(true)).unwrap();
+/// ```
+///
+/// Getting the option:
+///
+/// ```
+/// use asyncio::*;
+/// use asyncio::ip::*;
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let soc = UdpSocket::new(ctx, Udp::v6()).unwrap();
+///
+/// let opt: RecvHopLimit = soc.get_option().unwrap();
+/// let is_set: bool = opt.get();
+/// ```
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[derive(Default, Clone)]
+pub struct RecvHopLimit(i32);
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl RecvHopLimit {
+    pub fn new(on: bool) -> RecvHopLimit {
+        RecvHopLimit(on as i32)
+    }
+
+    pub fn get(&self) -> bool {
+        self.0 != 0
+    }
+
+    pub fn set(&mut self, on: bool) {
+        self.0 = on as i32
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<P: IpProtocol> SocketOption<P> for RecvHopLimit {
+    fn level(&self, pro: &P) -> i32 {
+        if pro == &P::v4() {
+            return IPPROTO_IP;
+        }
+        if pro == &P::v6() {
+            return IPPROTO_IPV6;
+        }
+        unreachable!("Invalid ip version")
+    }
+
+    fn name(&self, pro: &P) -> i32 {
+        if pro == &P::v4() {
+            return IP_RECVTTL;
+        }
+        if pro == &P::v6() {
+            return IPV6_RECVHOPLIMIT;
+        }
+        unreachable!("Invalid ip version")
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<P: IpProtocol> GetSocketOption<P> for RecvHopLimit {}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<P: IpProtocol> SetSocketOption<P> for RecvHopLimit {}
+
+/// Socket option for the original destination of the connection redirected by the netfilter.
+///
+/// Implements the IPPROTO_IP/SO_ORIGINAL_DST or IPPROTO_IPV6/IP6T_SO_ORIGINAL_DST socket option,
+/// that is used by the transparent proxy accepting the connections of the REDIRECT target.
+///
+/// # Examples
+/// Getting the option:
+///
+/// ```rust,no_run
+/// use asyncio::*;
+/// use asyncio::ip::*;
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let soc = TcpListener::new(ctx, Tcp::v4()).unwrap();
+/// soc.bind(&TcpEndpoint::new(IpAddrV4::any(), 12345)).unwrap();
+/// soc.listen().unwrap();
+///
+/// let (acc, _) = soc.accept().unwrap();
+/// let opt: OriginalDst = acc.get_option().unwrap();
+/// let ep: TcpEndpoint = opt.get().unwrap();
+/// ```
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[derive(Default, Clone)]
+pub struct OriginalDst {
+    ss: [u64; 16],
+    len: u32,
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl OriginalDst {
+    pub fn get(&self) -> Option<TcpEndpoint> {
+        if self.len == 0 {
+            return None;
+        }
+        let len = cmp::min(self.len as usize, mem::size_of_val(&self.ss));
+        let bytes = unsafe { slice::from_raw_parts(self.ss.as_ptr() as *const u8, len) };
+        TcpEndpoint::try_from_sockaddr(bytes).ok()
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl SocketOption<Tcp> for OriginalDst {
+    fn level(&self, pro: &Tcp) -> i32 {
+        if pro == &Tcp::v4() {
+            return IPPROTO_IP;
+        }
+        if pro == &Tcp::v6() {
+            return IPPROTO_IPV6;
+        }
+        unreachable!("Invalid ip version")
+    }
+
+    fn name(&self, pro: &Tcp) -> i32 {
+        if pro == &Tcp::v4() {
+            return SO_ORIGINAL_DST;
+        }
+        if pro == &Tcp::v6() {
+            return IP6T_SO_ORIGINAL_DST;
+        }
+        unreachable!("Invalid ip version")
+    }
+
+    fn capacity(&self) -> u32 {
+        mem::size_of_val(&self.ss) as u32
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl GetSocketOption<Tcp> for OriginalDst {
+    fn as_mut_ptr(&mut self) -> *mut c_void {
+        self.ss.as_mut_ptr() as *mut _
+    }
+
+    unsafe fn resize(&mut self, len: u32) {
+        self.len = len
+    }
+}
+
+#[test]
+fn test_host_name() {
+    let ctx = &IoContext::new().unwrap();
+    host_name(ctx).unwrap();
+}
+
+#[test]
+fn test_outbound_interface() {
+    assert_eq!(mem::size_of::<u32>(), mem::size_of::<in_addr>());
+}
+
+#[test]
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn test_icmp_filter() {
+    let mut opt = IcmpFilter::block_all();
+    assert!(opt.will_block(0));
+    opt.pass(0);
+    opt.pass(129);
+    assert!(opt.will_pass(0) && opt.will_pass(129));
+    assert!(opt.will_block(1) && opt.will_block(128));
+    assert_eq!(opt.0[0], !1);
+    assert_eq!(opt.0[4], !2);
+
+    opt.block(129);
+    assert!(opt.will_block(129));
+    assert!(IcmpFilter::pass_all().will_pass(255));
+}
+
+#[test]
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn test_original_dst() {
+    use core::Endpoint;
+
+    let mut opt = OriginalDst::default();
+    assert!(opt.get().is_none());
+
+    let ep = TcpEndpoint::new(IpAddrV4::new(192, 168, 0, 1), 8080);
+    unsafe {
+        let len = ep.size() as usize;
+        let src = ep.as_ptr() as *const u8;
+        let dst = GetSocketOption::<Tcp>::as_mut_ptr(&mut opt) as *mut u8;
+        ::std::ptr::copy_nonoverlapping(src, dst, len);
+        GetSocketOption::<Tcp>::resize(&mut opt, len as u32);
+    }
+    assert_eq!(opt.get(), Some(ep));
+}
+
+#[test]
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn test_multicast_source_group() {
+    use ip::{Udp, UdpSocket};
+
+    let opt = MulticastJoinSourceGroup::v4(IpAddrV4::new(232, 1, 1, 1), IpAddrV4::loopback());
+    assert_eq!(SocketOption::<Udp>::level(&opt, &Udp::v4()), IPPROTO_IP);
+    assert_eq!(SocketOption::<Udp>::level(&opt, &Udp::v6()), IPPROTO_IPV6);
+    assert_eq!(SetSocketOption::<Udp>::size(&opt) as usize, mem::size_of::<group_source_req>());
+    let ep = UdpEndpoint::new(IpAddrV4::new(232, 1, 1, 1), 0);
+    let gsr = &(opt.0).0;
+    assert_eq!(gsr.gsr_interface, 0);
+    assert_eq!(gsr.gsr_group.ss_family as i32, ep.family());
+
+    // Leaving the group which is not joined.
+    let ctx = &IoContext::new().unwrap();
+    let soc = UdpSocket::new(ctx, Udp::v4()).unwrap();
+    let opt = MulticastLeaveSourceGroup::v4(IpAddrV4::new(232, 1, 1, 1), IpAddrV4::loopback());
+    assert!(soc.set_option(opt).is_err());
 }
@@ -0,0 +1,96 @@
+use ip::{NoDelay, TcpSocket};
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use ip::{Cork, QuickAck};
+use socket_base::{RecvBufferSize, SendBufferSize};
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use socket_base::MaxPacingRate;
+
+use std::io;
+
+/// The presets of the socket options tuned for the typical workloads.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Profile {
+    /// For the interactive request-response, e.g. RPC and games.
+    ///
+    /// Disables the Nagle algorithm and the delayed acknowledgement, and keeps the buffers default.
+    LowLatency,
+
+    /// For the streaming at a high rate, e.g. the file transfer over the fast network.
+    ///
+    /// Enables the Nagle algorithm and sets the buffers to 1 MiB.
+    Throughput,
+
+    /// For the background transfer of the large data, e.g. the backup and the replication.
+    ///
+    /// Enables the Nagle algorithm and the cork to send only the full frames, and sets the buffers
+    /// to 4 MiB.
+    Bulk,
+}
+
+impl Profile {
+    fn buffer_size(&self) -> Option<usize> {
+        match *self {
+            Profile::LowLatency => None,
+            Profile::Throughput => Some(1024 * 1024),
+            Profile::Bulk => Some(4 * 1024 * 1024),
+        }
+    }
+}
+
+impl TcpSocket {
+    /// Applies the socket options of the profile.
+    ///
+    /// The buffer sizes are capped by the system limits (e.g. `net.core.wmem_max` on Linux), and
+    /// the options not supported by the platform are skipped. On Linux the pacing rate is reset to
+    /// unlimited.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use asyncio::IoContext;
+    /// use asyncio::ip::{IpProtocol, Tcp, TcpSocket, Profile, NoDelay};
+    ///
+    /// let ctx = &IoContext::new().unwrap();
+    /// let soc = TcpSocket::new(ctx, Tcp::v4()).unwrap();
+    /// soc.apply_profile(Profile::LowLatency).unwrap();
+    /// assert!(soc.get_option::<NoDelay>().unwrap().get());
+    /// ```
+    pub fn apply_profile(&self, profile: Profile) -> io::Result<()> {
+        self.set_option(NoDelay::new(profile == Profile::LowLatency))?;
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        {
+            self.set_option(QuickAck::new(profile == Profile::LowLatency))?;
+            self.set_option(Cork::new(profile == Profile::Bulk))?;
+            self.set_option(MaxPacingRate::new(!0))?;
+        }
+        if let Some(size) = profile.buffer_size() {
+            self.set_option(SendBufferSize::new(size))?;
+            self.set_option(RecvBufferSize::new(size))?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_apply_profile() {
+    use core::IoContext;
+    use ip::{IpProtocol, Tcp};
+
+    let ctx = &IoContext::new().unwrap();
+    let soc = TcpSocket::new(ctx, Tcp::v4()).unwrap();
+    let default = soc.get_option::<SendBufferSize>().unwrap().get();
+
+    soc.apply_profile(Profile::LowLatency).unwrap();
+    assert!(soc.get_option::<NoDelay>().unwrap().get());
+    assert_eq!(soc.get_option::<SendBufferSize>().unwrap().get(), default);
+
+    soc.apply_profile(Profile::Bulk).unwrap();
+    assert!(!soc.get_option::<NoDelay>().unwrap().get());
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    assert!(soc.get_option::<Cork>().unwrap().get());
+    assert!(soc.get_option::<SendBufferSize>().unwrap().get() >= default);
+
+    soc.apply_profile(Profile::Throughput).unwrap();
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    assert!(!soc.get_option::<Cork>().unwrap().get());
+}
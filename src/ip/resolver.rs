@@ -1,16 +1,45 @@
-use ffi::{SockAddr, getaddrinfo, freeaddrinfo, addrinfo, sockaddr_storage};
-use core::{Protocol, AsIoContext, IoContext, Cancel};
-use handler::Handler;
+use ffi::{getaddrinfo, getaddrinfo_hints, freeaddrinfo, addrinfo, AF_UNSPEC, AI_CANONNAME,
+          OPERATION_CANCELED};
+use core::{Protocol, AsIoContext, IoContext, IoContextWork, Cancel};
+use handler::{Handler, Success, Failure};
 use ip::{IpEndpoint, IpProtocol};
+use ip::idna;
 use ip::resolve_op::{async_resolve, resolve};
 
 use std::io;
-use std::marker::PhantomData;
-use std::ffi::CString;
+use std::fmt;
+use std::ptr;
+use std::vec;
+use std::slice;
+use std::ffi::{CStr, CString};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
 
 /// A query to be passed to a resolver.
 pub trait ResolverQuery<P> {
     fn iter(self) -> io::Result<ResolverIter<P>>;
+
+    /// Returns a key of the query for the cache of the resolver, the query is not cached if `None`.
+    fn cache_key(&self) -> Option<String> {
+        None
+    }
+}
+
+pub fn cache_key<P>(pro: &P, host: &str, port: &str, flags: i32) -> String
+where
+    P: Protocol,
+{
+    format!(
+        "{}/{}/{}/{}/{}/{}",
+        pro.family_type(),
+        pro.socket_type(),
+        pro.protocol_type(),
+        flags,
+        host,
+        port
+    )
 }
 
 impl<P, N, S> ResolverQuery<P> for (P, N, S)
@@ -22,37 +51,236 @@ where
     fn iter(self) -> io::Result<ResolverIter<P>> {
         ResolverIter::new(&self.0, self.1.as_ref(), self.2.as_ref(), 0)
     }
+
+    fn cache_key(&self) -> Option<String> {
+        Some(cache_key(&self.0, self.1.as_ref(), self.2.as_ref(), 0))
+    }
 }
 
 /// A query of the resolver for the passive mode.
 pub struct Passive;
 
+/// A query of the resolver bypassing the cache.
+///
+/// The result is not looked up nor stored in the cache of the resolver.
+pub struct NoCache<Q>(pub Q);
+
+impl<P, Q> ResolverQuery<P> for NoCache<Q>
+where
+    Q: ResolverQuery<P>,
+{
+    fn iter(self) -> io::Result<ResolverIter<P>> {
+        self.0.iter()
+    }
+}
+
+/// A query of the resolver for any socket type and protocol, e.g. both of TCP and UDP.
+///
+/// Each entry of the result has the socket type and the protocol, and the first entry has the
+/// canonical name of the host.
+///
+/// # Examples
+///
+/// ```
+/// use asyncio::{IoContext, Protocol};
+/// use asyncio::ip::{AnySocketType, IpProtocol, Tcp, TcpResolver, Udp};
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let re = TcpResolver::new(ctx);
+/// for e in re.resolve(AnySocketType("127.0.0.1", "80")).unwrap().entries() {
+///   if e.socket_type() == Udp::v4().socket_type() {
+///     println!("udp {}", e.endpoint_as::<Udp>());
+///   } else if e.socket_type() == Tcp::v4().socket_type() {
+///     println!("tcp {}", e.endpoint());
+///   }
+/// }
+/// ```
+pub struct AnySocketType<H, S>(pub H, pub S);
+
+impl<P, H, S> ResolverQuery<P> for AnySocketType<H, S>
+where
+    P: Protocol,
+    H: AsRef<str>,
+    S: AsRef<str>,
+{
+    fn iter(self) -> io::Result<ResolverIter<P>> {
+        ResolverIter::with_hints(AF_UNSPEC, 0, 0, self.0.as_ref(), self.1.as_ref(), AI_CANONNAME)
+    }
+
+    fn cache_key(&self) -> Option<String> {
+        Some(format!(
+            "{}/0/0/{}/{}/{}",
+            AF_UNSPEC,
+            AI_CANONNAME,
+            self.0.as_ref(),
+            self.1.as_ref()
+        ))
+    }
+}
+
+/// An entry produced by a resolver, with the socket type and the protocol of the endpoint.
+#[derive(Clone)]
+pub struct ResolverEntry<P> {
+    ep: IpEndpoint<P>,
+    socket_type: i32,
+    protocol_type: i32,
+    canonical_name: Option<String>,
+}
+
+impl<P> ResolverEntry<P>
+where
+    P: IpProtocol,
+{
+    /// Returns the canonical name of the host, if it was requested by the query.
+    pub fn canonical_name(&self) -> Option<&str> {
+        self.canonical_name.as_ref().map(|name| name.as_str())
+    }
+
+    pub fn endpoint(&self) -> &IpEndpoint<P> {
+        &self.ep
+    }
+
+    /// Returns the endpoint of the other protocol with the same address and port.
+    pub fn endpoint_as<Q>(&self) -> IpEndpoint<Q>
+    where
+        Q: IpProtocol,
+    {
+        IpEndpoint::new(self.ep.addr(), self.ep.port())
+    }
+
+    pub fn into_endpoint(self) -> IpEndpoint<P> {
+        self.ep
+    }
+
+    /// Returns the protocol number of the entry, e.g. `IPPROTO_TCP`.
+    pub fn protocol_type(&self) -> i32 {
+        self.protocol_type
+    }
+
+    /// Returns the socket type of the entry, e.g. `SOCK_STREAM`.
+    pub fn socket_type(&self) -> i32 {
+        self.socket_type
+    }
+}
+
+impl<P: IpProtocol> fmt::Debug for ResolverEntry<P> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ResolverEntry")
+            .field("endpoint", &self.ep)
+            .field("socket_type", &self.socket_type)
+            .field("protocol_type", &self.protocol_type)
+            .field("canonical_name", &self.canonical_name)
+            .finish()
+    }
+}
+
 /// An iterator over the entries produced by a resolver.
 pub struct ResolverIter<P> {
     ai: *mut addrinfo,
     base: *mut addrinfo,
-    _marker: PhantomData<P>,
+    cached: vec::IntoIter<ResolverEntry<P>>,
 }
 
 impl<P> ResolverIter<P>
 where
     P: Protocol,
 {
+    /// Resolves the host and the port, the internationalized host name is converted to the
+    /// punycode.
+    ///
+    /// Returns an error of `InvalidInput` if the host or the port is invalid.
     pub fn new(pro: &P, host: &str, port: &str, flags: i32) -> io::Result<ResolverIter<P>> {
-        let host = CString::new(host).unwrap();
-        let port = CString::new(port).unwrap();
+        let host = CString::new(idna::to_ascii(host)?).unwrap();
+        let port = CString::new(port).map_err(|_| {
+            io::Error::new(io::ErrorKind::InvalidInput, "invalid port: contains a NUL character")
+        })?;
         let ai = getaddrinfo(pro, &host, &port, flags)?;
-        Ok(ResolverIter {
+        Ok(ResolverIter::from_ai(ai))
+    }
+
+    fn with_hints(
+        family: i32,
+        socktype: i32,
+        protocol: i32,
+        host: &str,
+        port: &str,
+        flags: i32,
+    ) -> io::Result<ResolverIter<P>> {
+        let host = CString::new(idna::to_ascii(host)?).unwrap();
+        let port = CString::new(port).map_err(|_| {
+            io::Error::new(io::ErrorKind::InvalidInput, "invalid port: contains a NUL character")
+        })?;
+        let ai = getaddrinfo_hints(family, socktype, protocol, &host, &port, flags)?;
+        Ok(ResolverIter::from_ai(ai))
+    }
+
+    fn from_ai(ai: *mut addrinfo) -> ResolverIter<P> {
+        ResolverIter {
             ai: ai,
             base: ai,
-            _marker: PhantomData,
-        })
+            cached: Vec::new().into_iter(),
+        }
+    }
+
+    fn from_vec(entries: Vec<ResolverEntry<P>>) -> ResolverIter<P> {
+        ResolverIter {
+            ai: ptr::null_mut(),
+            base: ptr::null_mut(),
+            cached: entries.into_iter(),
+        }
+    }
+}
+
+impl<P> ResolverIter<P>
+where
+    P: IpProtocol,
+{
+    /// Returns an iterator over the entries with the socket type and the protocol, instead of
+    /// the endpoints.
+    pub fn entries(self) -> ResolverEntries<P> {
+        ResolverEntries(self)
+    }
+
+    /// Returns the next entry with the socket type and the protocol.
+    ///
+    /// The entries of the malformed addresses are skipped.
+    pub fn next_entry(&mut self) -> Option<ResolverEntry<P>> {
+        if self.ai.is_null() {
+            return self.cached.next();
+        }
+        while !self.ai.is_null() {
+            let ai = unsafe { &*self.ai };
+            self.ai = ai.ai_next;
+            if ai.ai_addr.is_null() {
+                continue;
+            }
+            let bytes =
+                unsafe { slice::from_raw_parts(ai.ai_addr as *const u8, ai.ai_addrlen as usize) };
+            let ep = match IpEndpoint::try_from_sockaddr(bytes) {
+                Ok(ep) => ep,
+                Err(_) => continue,
+            };
+            let canonical_name = if ai.ai_canonname.is_null() {
+                None
+            } else {
+                Some(unsafe { CStr::from_ptr(ai.ai_canonname) }.to_string_lossy().into_owned())
+            };
+            return Some(ResolverEntry {
+                ep: ep,
+                socket_type: ai.ai_socktype,
+                protocol_type: ai.ai_protocol,
+                canonical_name: canonical_name,
+            });
+        }
+        None
     }
 }
 
 impl<P> Drop for ResolverIter<P> {
     fn drop(&mut self) {
-        freeaddrinfo(self.base)
+        if !self.base.is_null() {
+            freeaddrinfo(self.base)
+        }
     }
 }
 
@@ -63,27 +291,88 @@ where
     type Item = IpEndpoint<P>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.ai.is_null() {
-            None
-        } else {
-            unsafe {
-                let ep = IpEndpoint::from_ss(SockAddr::from(
-                    (*self.ai).ai_addr as *const sockaddr_storage,
-                    (*self.ai).ai_addrlen as u8,
-                ));
-                self.ai = (&*self.ai).ai_next;
-                Some(ep)
+        self.next_entry().map(ResolverEntry::into_endpoint)
+    }
+}
+
+unsafe impl<P> Send for ResolverIter<P> {}
+
+/// An iterator over the entries produced by a resolver, returned by `ResolverIter::entries`.
+pub struct ResolverEntries<P>(ResolverIter<P>);
+
+impl<P> Iterator for ResolverEntries<P>
+where
+    P: IpProtocol,
+{
+    type Item = ResolverEntry<P>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next_entry()
+    }
+}
+
+struct CacheEntry<P> {
+    expiry: Instant,
+    res: Result<Vec<ResolverEntry<P>>, (io::ErrorKind, String)>,
+}
+
+struct ResolverCache<P> {
+    ttl: Duration,
+    negative_ttl: Duration,
+    map: Mutex<HashMap<String, CacheEntry<P>>>,
+}
+
+impl<P> ResolverCache<P>
+where
+    P: IpProtocol,
+{
+    fn get(&self, key: &str) -> Option<io::Result<Vec<ResolverEntry<P>>>> {
+        let mut map = self.map.lock().unwrap();
+        let expired = match map.get(key) {
+            Some(entry) if entry.expiry > Instant::now() => {
+                return Some(match entry.res {
+                    Ok(ref entries) => Ok(entries.clone()),
+                    Err((kind, ref msg)) => Err(io::Error::new(kind, msg.clone())),
+                })
             }
+            Some(_) => true,
+            None => false,
+        };
+        if expired {
+            map.remove(key);
         }
+        None
+    }
+
+    fn insert(&self, key: String, res: Result<Vec<ResolverEntry<P>>, (io::ErrorKind, String)>) {
+        let ttl = if res.is_ok() { self.ttl } else { self.negative_ttl };
+        if ttl == Duration::new(0, 0) {
+            return;
+        }
+        let now = Instant::now();
+        let mut map = self.map.lock().unwrap();
+        map.retain(|_, entry| entry.expiry > now);
+        map.insert(
+            key,
+            CacheEntry {
+                expiry: now + ttl,
+                res: res,
+            },
+        );
     }
 }
 
-unsafe impl<P> Send for ResolverIter<P> {}
+/// The cancellations of the outstanding `async_resolve`.
+type PendingResolves = Mutex<HashMap<usize, Box<FnOnce() + Send>>>;
 
 /// An entry produced by a resolver.
+///
+/// The resolver is cheap to clone, that the clones share the cache but not the outstanding
+/// operations to be canceled.
 pub struct Resolver<P> {
     ctx: IoContext,
-    _marker: PhantomData<P>,
+    cache: Option<Arc<ResolverCache<P>>>,
+    pending: Arc<PendingResolves>,
 }
 
 impl<P> Resolver<P>
@@ -93,7 +382,40 @@ where
     pub fn new(ctx: &IoContext) -> Self {
         Resolver {
             ctx: ctx.clone(),
-            _marker: PhantomData,
+            cache: None,
+            pending: Arc::default(),
+        }
+    }
+
+    /// Returns a new resolver caching the results of the queries.
+    ///
+    /// The resolved endpoints are cached for `ttl`, and the errors are cached for `negative_ttl`.
+    /// The duration of zero disables each cache.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use asyncio::IoContext;
+    /// use asyncio::ip::{NoCache, TcpResolver};
+    ///
+    /// let ctx = &IoContext::new().unwrap();
+    /// let re = TcpResolver::with_cache(ctx, Duration::new(60, 0), Duration::new(5, 0));
+    /// let eps: Vec<_> = re.resolve(("127.0.0.1", "80")).unwrap().collect();
+    /// assert_eq!(eps, re.clone().resolve(("127.0.0.1", "80")).unwrap().collect::<Vec<_>>());
+    ///
+    /// // Forces the lookup.
+    /// let _ = re.resolve(NoCache(("127.0.0.1", "80"))).unwrap();
+    /// ```
+    pub fn with_cache(ctx: &IoContext, ttl: Duration, negative_ttl: Duration) -> Self {
+        Resolver {
+            ctx: ctx.clone(),
+            cache: Some(Arc::new(ResolverCache {
+                ttl: ttl,
+                negative_ttl: negative_ttl,
+                map: Mutex::default(),
+            })),
+            pending: Arc::default(),
         }
     }
 
@@ -105,6 +427,77 @@ where
         async_resolve(self, self.resolve(query), handler)
     }
 
+    /// Asynchronously resolves the query on the internal threads of the context.
+    ///
+    /// The handler is completed on the thread calling `run`. The internal threads are configured
+    /// by `IoContextBuilder::threads`, otherwise the query is resolved on the calling thread.
+    /// `getaddrinfo` can't be interrupted, so the `cancel` completes the handler with the
+    /// `OPERATION_CANCELED` error immediately and the result is discarded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io;
+    /// use std::sync::Arc;
+    /// use asyncio::{IoContextBuilder, wrap};
+    /// use asyncio::ip::{ResolverIter, Tcp, TcpResolver};
+    ///
+    /// fn on_resolve(_: Arc<TcpResolver>, res: io::Result<ResolverIter<Tcp>>) {
+    ///   for ep in res.unwrap() {
+    ///     println!("{}", ep);
+    ///   }
+    /// }
+    ///
+    /// let ctx = &IoContextBuilder::new().threads(1).build().unwrap();
+    /// let re = Arc::new(TcpResolver::new(ctx));
+    /// re.async_resolve(("localhost", "80"), wrap(&re, on_resolve));
+    /// ctx.run();
+    /// ```
+    pub fn async_resolve<Q, F>(&self, query: Q, handler: F) -> F::Output
+    where
+        Q: ResolverQuery<P> + Send + 'static,
+        F: Handler<ResolverIter<P>, io::Error>,
+    {
+        static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+        handler.wrap(&self.ctx, move |ctx, handler| {
+            let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+            let slot = Arc::new(Mutex::new(Some(handler)));
+            let cancel = {
+                let ctx = ctx.clone();
+                let slot = slot.clone();
+                move || if let Some(handler) = slot.lock().unwrap().take() {
+                    ctx.do_post(Failure::new(OPERATION_CANCELED, handler))
+                }
+            };
+            self.pending.lock().unwrap().insert(id, Box::new(cancel));
+
+            let re = self.clone();
+            let pending = self.pending.clone();
+            let work = IoContextWork::new(ctx);
+            ctx.as_pool().execute(move || {
+                let res = re.resolve(query);
+                pending.lock().unwrap().remove(&id);
+                if let Some(handler) = slot.lock().unwrap().take() {
+                    match res {
+                        Ok(it) => re.ctx.do_post(Success::new(it, handler)),
+                        Err(err) => re.ctx.do_post(Failure::new(err, handler)),
+                    }
+                    // The thread calling `run` may be waiting in the reactor.
+                    re.ctx.wake();
+                }
+                drop(work);
+            })
+        })
+    }
+
+    /// Removes all results of the cache.
+    pub fn clear_cache(&self) {
+        if let Some(ref cache) = self.cache {
+            cache.map.lock().unwrap().clear()
+        }
+    }
+
     pub fn connect<Q>(&self, query: Q) -> io::Result<(P::Socket, IpEndpoint<P>)>
     where
         Q: ResolverQuery<P>,
@@ -116,7 +509,24 @@ where
     where
         Q: ResolverQuery<P>,
     {
-        query.iter()
+        let (cache, key) = match (self.cache.as_ref(), query.cache_key()) {
+            (Some(cache), Some(key)) => (cache, key),
+            _ => return query.iter(),
+        };
+        if let Some(res) = cache.get(&key) {
+            return res.map(ResolverIter::from_vec);
+        }
+        match query.iter() {
+            Ok(it) => {
+                let entries: Vec<_> = it.entries().collect();
+                cache.insert(key, Ok(entries.clone()));
+                Ok(ResolverIter::from_vec(entries))
+            }
+            Err(err) => {
+                cache.insert(key, Err((err.kind(), err.to_string())));
+                Err(err)
+            }
+        }
     }
 }
 
@@ -127,5 +537,123 @@ unsafe impl<P> AsIoContext for Resolver<P> {
 }
 
 impl<P: 'static> Cancel for Resolver<P> {
-    fn cancel(&self) {}
+    /// Cancels the outstanding `async_resolve`.
+    fn cancel(&self) {
+        let pending: Vec<_> = self.pending
+            .lock()
+            .unwrap()
+            .drain()
+            .map(|(_, cancel)| cancel)
+            .collect();
+        for cancel in pending {
+            cancel()
+        }
+    }
+}
+
+impl<P> Clone for Resolver<P> {
+    fn clone(&self) -> Self {
+        Resolver {
+            ctx: self.ctx.clone(),
+            cache: self.cache.clone(),
+            pending: Arc::default(),
+        }
+    }
+}
+
+#[test]
+fn test_resolver_cache() {
+    use ip::Tcp;
+
+    let ctx = &IoContext::new().unwrap();
+    let re: Resolver<Tcp> = Resolver::with_cache(ctx, Duration::new(60, 0), Duration::new(60, 0));
+    let eps: Vec<_> = re.resolve(("127.0.0.1", "80")).unwrap().collect();
+    assert!(!eps.is_empty());
+    let cache = re.cache.clone().unwrap();
+    assert_eq!(cache.map.lock().unwrap().len(), 1);
+    assert_eq!(re.clone().resolve(("127.0.0.1", "80")).unwrap().collect::<Vec<_>>(), eps);
+
+    assert!(re.resolve(("127.0.0.1", "no-such-service")).is_err());
+    assert!(re.resolve(("127.0.0.1", "no-such-service")).is_err());
+    assert_eq!(cache.map.lock().unwrap().len(), 2);
+
+    let _ = re.resolve(NoCache(("localhost", "80")));
+    assert_eq!(cache.map.lock().unwrap().len(), 2);
+
+    let err = re.resolve(NoCache(("local\0host", "80"))).err().unwrap();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    let err = re.resolve(NoCache(("localhost", "8\00"))).err().unwrap();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    assert_eq!(cache.map.lock().unwrap().len(), 2);
+
+    re.clear_cache();
+    assert!(cache.map.lock().unwrap().is_empty());
+}
+
+#[test]
+fn test_async_resolve() {
+    use std::sync::mpsc;
+    use core::IoContextBuilder;
+    use handler::wrap;
+    use ip::Tcp;
+
+    static RESOLVED: AtomicUsize = AtomicUsize::new(0);
+    static CANCELED: AtomicUsize = AtomicUsize::new(0);
+
+    fn on_resolve(_: Arc<Resolver<Tcp>>, res: io::Result<ResolverIter<Tcp>>) {
+        let mut it = res.unwrap();
+        assert!(it.next().unwrap().addr().is_loopback());
+        RESOLVED.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn on_cancel(_: Arc<Resolver<Tcp>>, res: io::Result<ResolverIter<Tcp>>) {
+        let err = res.err().unwrap();
+        assert_eq!(err.raw_os_error(), io::Error::from(OPERATION_CANCELED).raw_os_error());
+        CANCELED.fetch_add(1, Ordering::SeqCst);
+    }
+
+    let ctx = &IoContextBuilder::new().threads(1).build().unwrap();
+    let re = Arc::new(Resolver::new(ctx));
+    re.async_resolve(("127.0.0.1", "80"), wrap(&re, on_resolve));
+    ctx.run();
+    assert_eq!(RESOLVED.load(Ordering::SeqCst), 1);
+
+    // Blocks the internal thread, that the query is canceled before resolved.
+    let (tx, rx) = mpsc::channel();
+    ctx.as_pool().execute(move || {
+        let _ = rx.recv();
+    });
+    re.async_resolve(("127.0.0.1", "80"), wrap(&re, on_cancel));
+    let re_ = re.clone();
+    ctx.post(move |_| {
+        re_.cancel();
+        tx.send(()).unwrap();
+    });
+    ctx.restart();
+    ctx.run();
+    assert_eq!(CANCELED.load(Ordering::SeqCst), 1);
+    assert_eq!(RESOLVED.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn test_resolver_entries() {
+    use ip::{IpAddrV4, Tcp, Udp};
+
+    let ctx = &IoContext::new().unwrap();
+    let re: Resolver<Tcp> = Resolver::with_cache(ctx, Duration::new(60, 0), Duration::new(0, 0));
+    for _ in 0..2 {
+        let entries: Vec<_> = re.resolve(AnySocketType("127.0.0.1", "80")).unwrap().entries().collect();
+        assert_eq!(entries[0].canonical_name(), Some("127.0.0.1"));
+        let tcp = entries.iter().find(|e| e.socket_type() == Tcp::v4().socket_type()).unwrap();
+        assert_eq!(tcp.protocol_type(), Tcp::v4().protocol_type());
+        assert_eq!(tcp.endpoint(), &IpEndpoint::new(IpAddrV4::loopback(), 80));
+        let udp = entries.iter().find(|e| e.socket_type() == Udp::v4().socket_type()).unwrap();
+        assert_eq!(udp.protocol_type(), Udp::v4().protocol_type());
+        assert_eq!(udp.endpoint_as::<Udp>(), IpEndpoint::new(IpAddrV4::loopback(), 80));
+    }
+
+    let mut it = re.resolve(NoCache(("127.0.0.1", "80"))).unwrap();
+    let e = it.next_entry().unwrap();
+    assert_eq!(e.socket_type(), Tcp::v4().socket_type());
+    assert_eq!(e.canonical_name(), None);
 }
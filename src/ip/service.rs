@@ -0,0 +1,67 @@
+use ffi::{AI_PASSIVE, SOCK_DGRAM, getnameinfo_service};
+use ip::{IpProtocol, ResolverIter};
+
+use std::io;
+
+/// Returns the port of the service name, e.g. `"https"`, or the numeric port string.
+///
+/// The service name is looked up as the resolver does, e.g. in the `/etc/services`.
+///
+/// # Examples
+///
+/// ```
+/// use asyncio::ip::{IpProtocol, Tcp, service_to_port};
+///
+/// assert_eq!(service_to_port("80", Tcp::v4()).unwrap(), 80);
+/// ```
+pub fn service_to_port<P>(service: &str, pro: P) -> io::Result<u16>
+where
+    P: IpProtocol,
+{
+    match ResolverIter::new(&pro, "", service, AI_PASSIVE)?.next() {
+        Some(ep) => Ok(ep.port()),
+        None => Err(io::Error::new(io::ErrorKind::NotFound, "service not found")),
+    }
+}
+
+/// Returns the service name of the port, or `None` if the port has no name.
+///
+/// # Examples
+///
+/// ```
+/// use asyncio::ip::{IpProtocol, Tcp, port_to_service};
+///
+/// assert_eq!(port_to_service(0, Tcp::v4()), None);
+/// ```
+pub fn port_to_service<P>(port: u16, pro: P) -> Option<String>
+where
+    P: IpProtocol,
+{
+    let dgram = pro.socket_type() == SOCK_DGRAM as i32;
+    match getnameinfo_service(port, dgram) {
+        Ok(ref serv) if *serv == port.to_string() => None,
+        Ok(serv) => Some(serv),
+        Err(_) => None,
+    }
+}
+
+#[test]
+fn test_service_to_port() {
+    use ip::{Tcp, Udp};
+
+    assert_eq!(service_to_port("8080", Tcp::v4()).unwrap(), 8080);
+    assert_eq!(service_to_port("8080", Udp::v6()).unwrap(), 8080);
+    assert!(service_to_port("no-such-service", Tcp::v4()).is_err());
+    assert!(service_to_port("local\0host", Tcp::v4()).is_err());
+}
+
+#[test]
+fn test_port_to_service() {
+    use ip::{Tcp, Udp};
+
+    assert_eq!(port_to_service(0, Tcp::v4()), None);
+    assert_eq!(port_to_service(0, Udp::v4()), None);
+    if let Some(port) = service_to_port("http", Tcp::v4()).ok() {
+        assert_eq!(port_to_service(port, Tcp::v4()).unwrap(), "http");
+    }
+}
@@ -0,0 +1,477 @@
+//! The service location by DNS SRV records (RFC 2782).
+//!
+//! This module provides the ordering of SRV targets by priority and weight,
+//! and a connector which tries the targets in order with failover.
+
+use ffi::{Timeout, SOCK_STREAM, HOST_NOT_FOUND, SERVICE_NOT_FOUND, socket};
+use core::{IoContext, AsIoContext, Protocol, Socket, ThreadIoContext, Cancel};
+use handler::{Handler, Complete};
+use ip::{IpAddr, IpProtocol, IpEndpoint};
+use ip::dns::{DnsResolver, DnsRecord, DnsData, TYPE_SRV, TYPE_A, TYPE_AAAA};
+
+use std::io;
+use std::cell::UnsafeCell;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The target host of SRV record.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct SrvTarget {
+    pub priority: u16,
+    pub weight: u16,
+    pub port: u16,
+    pub target: String,
+}
+
+/// Returns the targets of SRV records in order to try.
+///
+/// The targets are sorted by ascending priority, and the targets with the same priority
+/// are ordered by weighted random selection.
+/// Returns a empty list if the service is decidedly not available at the domain,
+/// which is indicated by a single record with the target `"."`.
+///
+/// # Examples
+///
+/// ```
+/// use asyncio::ip::dns::{DnsRecord, DnsData};
+/// use asyncio::ip::srv::srv_order;
+///
+/// let rr = |priority, target: &str| DnsRecord::new("_sip._udp.example.com", 60, DnsData::Srv {
+///     priority: priority, weight: 0, port: 5060, target: target.to_owned(),
+/// });
+/// let targets = srv_order(&[rr(20, "b.example.com"), rr(10, "a.example.com")]);
+/// assert_eq!(targets[0].target, "a.example.com");
+/// assert_eq!(targets[1].target, "b.example.com");
+/// ```
+pub fn srv_order(records: &[DnsRecord]) -> Vec<SrvTarget> {
+    let mut seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or(0) | 1;
+    order_by(records, move |n| {
+        // xorshift32
+        seed ^= seed << 13;
+        seed ^= seed >> 17;
+        seed ^= seed << 5;
+        seed % (n + 1)
+    })
+}
+
+fn order_by<R>(records: &[DnsRecord], mut rand: R) -> Vec<SrvTarget>
+where
+    R: FnMut(u32) -> u32,
+{
+    let mut targets: Vec<_> = records
+        .iter()
+        .filter_map(|rr| match rr.data {
+            DnsData::Srv {
+                priority,
+                weight,
+                port,
+                ref target,
+            } => Some(SrvTarget {
+                priority: priority,
+                weight: weight,
+                port: port,
+                target: target.trim_end_matches('.').to_owned(),
+            }),
+            _ => None,
+        })
+        .collect();
+    if targets.len() == 1 && targets[0].target.is_empty() {
+        return Vec::new();
+    }
+    targets.retain(|t| !t.target.is_empty());
+    targets.sort_by_key(|t| (t.priority, t.weight != 0));
+
+    let mut res = Vec::with_capacity(targets.len());
+    while !targets.is_empty() {
+        let priority = targets[0].priority;
+        let len = targets.iter().take_while(|t| t.priority == priority).count();
+        let mut group: Vec<_> = targets.drain(..len).collect();
+        while !group.is_empty() {
+            let sum = group.iter().map(|t| t.weight as u32).sum();
+            let n = rand(sum);
+            let mut acc = 0;
+            let i = group
+                .iter()
+                .position(|t| {
+                    acc += t.weight as u32;
+                    acc >= n
+                })
+                .unwrap_or(0);
+            res.push(group.remove(i));
+        }
+    }
+    res
+}
+
+/// The connector to the service located by SRV records.
+///
+/// The connector queries `_service._proto.domain`, where the `proto` is `tcp` or `udp` by the protocol,
+/// then tries to connect the addresses of each target in order until the connection succeeds.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use std::io;
+/// use std::sync::Arc;
+/// use asyncio::{IoContext, wrap};
+/// use asyncio::ip::{Tcp, TcpEndpoint, TcpSocket};
+/// use asyncio::ip::dns::DnsResolver;
+/// use asyncio::ip::srv::SrvConnector;
+///
+/// fn on_connect(_: Arc<SrvConnector<Tcp>>, res: io::Result<(TcpSocket, TcpEndpoint)>) {
+///     if let Ok((_, ep)) = res {
+///         println!("connected to {}", ep);
+///     }
+/// }
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let con = Arc::new(SrvConnector::new(&DnsResolver::new(ctx).unwrap()));
+/// con.async_connect("xmpp-client", "example.com", wrap(&con, on_connect));
+/// ctx.run();
+/// ```
+pub struct SrvConnector<P> {
+    resolver: DnsResolver,
+    _marker: PhantomData<P>,
+}
+
+impl<P> SrvConnector<P>
+where
+    P: IpProtocol,
+{
+    /// Returns a connector which queries by the resolver.
+    pub fn new(resolver: &DnsResolver) -> SrvConnector<P> {
+        SrvConnector {
+            resolver: resolver.clone(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns a DNS resolver of the connector.
+    pub fn resolver(&self) -> &DnsResolver {
+        &self.resolver
+    }
+
+    /// Asynchronously connects to the `service` at the `domain`.
+    ///
+    /// The handler is completed with the connected socket and the endpoint,
+    /// or the last error if all of the targets are failed.
+    pub fn async_connect<F>(&self, service: &str, domain: &str, handler: F) -> F::Output
+    where
+        F: Handler<(P::Socket, IpEndpoint<P>), io::Error>,
+    {
+        let proto = if P::v4().socket_type() == SOCK_STREAM {
+            "tcp"
+        } else {
+            "udp"
+        };
+        let name = format!(
+            "_{}._{}.{}",
+            service.trim_start_matches('_'),
+            proto,
+            domain
+        );
+        handler.wrap(self.resolver.as_ctx(), move |_, handler| {
+            let conn = Arc::new(SrvConnect {
+                resolver: self.resolver.clone(),
+                state: UnsafeCell::new(SrvState {
+                    targets: Vec::new(),
+                    addrs: Vec::new(),
+                    res: None,
+                    err: None,
+                }),
+            });
+            self.resolver.async_query(
+                &name,
+                TYPE_SRV,
+                SrvQuery {
+                    conn: conn,
+                    qtype: TYPE_SRV,
+                    handler: handler,
+                },
+            )
+        })
+    }
+}
+
+unsafe impl<P> AsIoContext for SrvConnector<P> {
+    fn as_ctx(&self) -> &IoContext {
+        self.resolver.as_ctx()
+    }
+}
+
+unsafe impl<P> Send for SrvConnector<P> {}
+
+unsafe impl<P> Sync for SrvConnector<P> {}
+
+struct SrvState<P: Protocol> {
+    targets: Vec<SrvTarget>,
+    addrs: Vec<IpAddr>,
+    res: Option<Box<(P::Socket, IpEndpoint<P>)>>,
+    err: Option<io::Error>,
+}
+
+struct SrvConnect<P: Protocol> {
+    resolver: DnsResolver,
+    state: UnsafeCell<SrvState<P>>,
+}
+
+unsafe impl<P: Protocol> Send for SrvConnect<P> {}
+
+unsafe impl<P: Protocol> Sync for SrvConnect<P> {}
+
+impl<P> SrvConnect<P>
+where
+    P: IpProtocol,
+{
+    fn state(&self) -> &mut SrvState<P> {
+        unsafe { &mut *self.state.get() }
+    }
+}
+
+/// Resolves the addresses of the next target.
+fn next_target<F, P>(conn: Arc<SrvConnect<P>>, this: &mut ThreadIoContext, handler: F)
+where
+    F: Complete<(P::Socket, IpEndpoint<P>), io::Error>,
+    P: IpProtocol,
+{
+    let st = conn.state();
+    if st.targets.is_empty() {
+        let err = st.err.take().unwrap_or_else(|| SERVICE_NOT_FOUND.into());
+        return handler.failure(this, err);
+    }
+    this.decrease_outstanding_work();
+    let target = st.targets[0].target.clone();
+    conn.resolver.async_query(
+        &target,
+        TYPE_A,
+        SrvQuery {
+            conn: conn.clone(),
+            qtype: TYPE_A,
+            handler: handler,
+        },
+    )
+}
+
+/// Connects to the next address of the current target.
+fn next_addr<F, P>(conn: Arc<SrvConnect<P>>, this: &mut ThreadIoContext, handler: F)
+where
+    F: Complete<(P::Socket, IpEndpoint<P>), io::Error>,
+    P: IpProtocol,
+{
+    let st = conn.state();
+    while !st.addrs.is_empty() {
+        let ep = IpEndpoint::new(st.addrs.remove(0), st.targets[0].port);
+        let pro = ep.protocol();
+        match socket(&pro) {
+            Ok(fd) => {
+                this.decrease_outstanding_work();
+                let soc = unsafe { P::Socket::from_raw_fd(this.as_ctx(), fd, pro) };
+                st.res = Some(Box::new((soc, ep)));
+                let res = &**st.res.as_ref().unwrap() as *const (P::Socket, IpEndpoint<P>);
+                return unsafe {
+                    P::async_connect(
+                        &(*res).0,
+                        &(*res).1,
+                        SrvConnectOp {
+                            conn: conn.clone(),
+                            handler: handler,
+                        },
+                    )
+                };
+            }
+            Err(err) => st.err = Some(err.into()),
+        }
+    }
+    st.targets.remove(0);
+    next_target(conn, this, handler)
+}
+
+struct SrvQuery<F, P: Protocol> {
+    conn: Arc<SrvConnect<P>>,
+    qtype: u16,
+    handler: F,
+}
+
+impl<F, P> Handler<Vec<DnsRecord>, io::Error> for SrvQuery<F, P>
+where
+    F: Complete<(P::Socket, IpEndpoint<P>), io::Error>,
+    P: IpProtocol,
+{
+    type Output = ();
+
+    type WrappedHandler = Self;
+
+    fn wrap<W>(self, ctx: &IoContext, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx, self)
+    }
+
+    fn wrap_timeout<W>(self, ctx: &Cancel, _: &Timeout, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx.as_ctx(), self)
+    }
+}
+
+impl<F, P> Complete<Vec<DnsRecord>, io::Error> for SrvQuery<F, P>
+where
+    F: Complete<(P::Socket, IpEndpoint<P>), io::Error>,
+    P: IpProtocol,
+{
+    fn success(self, this: &mut ThreadIoContext, res: Vec<DnsRecord>) {
+        let SrvQuery {
+            conn,
+            qtype,
+            handler,
+        } = self;
+        let st = conn.state();
+        match qtype {
+            TYPE_SRV => {
+                st.targets = srv_order(&res);
+                if st.targets.is_empty() {
+                    return handler.failure(this, HOST_NOT_FOUND.into());
+                }
+                next_target(conn, this, handler)
+            }
+            _ => {
+                for rr in res {
+                    match rr.data {
+                        DnsData::A(addr) => st.addrs.push(IpAddr::V4(addr)),
+                        DnsData::Aaaa(addr) => st.addrs.push(IpAddr::V6(addr)),
+                        _ => (),
+                    }
+                }
+                next_query(conn, this, qtype, handler)
+            }
+        }
+    }
+
+    fn failure(self, this: &mut ThreadIoContext, err: io::Error) {
+        let SrvQuery {
+            conn,
+            qtype,
+            handler,
+        } = self;
+        match qtype {
+            TYPE_SRV => handler.failure(this, err),
+            _ => {
+                conn.state().err = Some(err);
+                next_query(conn, this, qtype, handler)
+            }
+        }
+    }
+}
+
+/// Queries the AAAA records after the A records, then connects to the addresses.
+fn next_query<F, P>(conn: Arc<SrvConnect<P>>, this: &mut ThreadIoContext, qtype: u16, handler: F)
+where
+    F: Complete<(P::Socket, IpEndpoint<P>), io::Error>,
+    P: IpProtocol,
+{
+    if qtype == TYPE_A {
+        this.decrease_outstanding_work();
+        let target = conn.state().targets[0].target.clone();
+        conn.resolver.async_query(
+            &target,
+            TYPE_AAAA,
+            SrvQuery {
+                conn: conn.clone(),
+                qtype: TYPE_AAAA,
+                handler: handler,
+            },
+        )
+    } else {
+        next_addr(conn, this, handler)
+    }
+}
+
+struct SrvConnectOp<F, P: Protocol> {
+    conn: Arc<SrvConnect<P>>,
+    handler: F,
+}
+
+impl<F, P> Handler<(), io::Error> for SrvConnectOp<F, P>
+where
+    F: Complete<(P::Socket, IpEndpoint<P>), io::Error>,
+    P: IpProtocol,
+{
+    type Output = ();
+
+    type WrappedHandler = Self;
+
+    fn wrap<W>(self, ctx: &IoContext, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx, self)
+    }
+
+    fn wrap_timeout<W>(self, ctx: &Cancel, _: &Timeout, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx.as_ctx(), self)
+    }
+}
+
+impl<F, P> Complete<(), io::Error> for SrvConnectOp<F, P>
+where
+    F: Complete<(P::Socket, IpEndpoint<P>), io::Error>,
+    P: IpProtocol,
+{
+    fn success(self, this: &mut ThreadIoContext, _: ()) {
+        let res = self.conn.state().res.take().unwrap();
+        self.handler.success(this, *res)
+    }
+
+    fn failure(self, this: &mut ThreadIoContext, err: io::Error) {
+        self.conn.state().err = Some(err);
+        next_addr(self.conn, this, self.handler)
+    }
+}
+
+#[test]
+fn test_srv_order() {
+    let rr = |priority, weight, target: &str| {
+        DnsRecord::new(
+            "_sip._tcp.example.com",
+            60,
+            DnsData::Srv {
+                priority: priority,
+                weight: weight,
+                port: 5060,
+                target: target.to_owned(),
+            },
+        )
+    };
+    let records = [
+        rr(20, 0, "d.example.com."),
+        rr(10, 60, "b.example.com."),
+        rr(10, 0, "a.example.com."),
+        rr(10, 40, "c.example.com."),
+    ];
+    let names = |targets: Vec<SrvTarget>| -> Vec<String> {
+        targets.into_iter().map(|t| t.target).collect()
+    };
+
+    // the zero weight target is placed first then selected by the running sum.
+    assert_eq!(
+        names(order_by(&records, |_| 0)),
+        vec!["a.example.com", "b.example.com", "c.example.com", "d.example.com"]
+    );
+    assert_eq!(
+        names(order_by(&records, |sum| sum)),
+        vec!["c.example.com", "b.example.com", "a.example.com", "d.example.com"]
+    );
+    assert_eq!(srv_order(&records).len(), 4);
+    assert_eq!(srv_order(&records)[3].target, "d.example.com");
+
+    assert!(srv_order(&[rr(0, 0, ".")]).is_empty());
+}
@@ -0,0 +1,635 @@
+//! The Session Traversal Utilities for NAT (STUN) client and the UDP hole punching.
+//!
+//! This module provides the message codec of RFC 5389, a client which sends a binding request
+//! over a `UdpSocket` with retransmission timers and returns the server reflexive address,
+//! and a helper of the simultaneous open between the peers behind the NAT.
+
+use ffi::{Timeout, CONNECTION_REFUSED, TIMED_OUT};
+use core::{IoContext, AsIoContext, ThreadIoContext, Cancel};
+use handler::{Handler, Complete, Failure};
+use ip::{IpAddr, IpAddrV4, IpAddrV6, UdpEndpoint, UdpSocket};
+use socket_base::Wait;
+use SteadyTimer;
+
+use std::io;
+use std::cmp;
+use std::process;
+use std::cell::UnsafeCell;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+pub const BINDING_REQUEST: u16 = 0x0001;
+pub const BINDING_SUCCESS: u16 = 0x0101;
+pub const BINDING_ERROR: u16 = 0x0111;
+
+pub const ATTR_MAPPED_ADDRESS: u16 = 0x0001;
+pub const ATTR_ERROR_CODE: u16 = 0x0009;
+pub const ATTR_XOR_MAPPED_ADDRESS: u16 = 0x0020;
+pub const ATTR_SOFTWARE: u16 = 0x8022;
+
+/// The fixed value in the header of the STUN message.
+pub const MAGIC_COOKIE: u32 = 0x2112_A442;
+
+const HEADER_LEN: usize = 20;
+const FAMILY_V4: u8 = 1;
+const FAMILY_V6: u8 = 2;
+const MAX_RETRANSMIT: usize = 6;
+const PUNCH: &'static [u8] = b"asyncio-punch";
+
+/// A STUN attribute encoded as type, length and value.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct StunAttribute {
+    pub ty: u16,
+    pub data: Vec<u8>,
+}
+
+/// The STUN message.
+///
+/// # Examples
+///
+/// ```
+/// use asyncio::ip::{IpAddrV4, UdpEndpoint};
+/// use asyncio::ip::stun::{StunMessage, BINDING_SUCCESS};
+///
+/// let ep = UdpEndpoint::new(IpAddrV4::new(192, 0, 2, 1), 32853);
+/// let msg = StunMessage::binding_success([1; 12], &ep);
+/// let res = StunMessage::from_bytes(&msg.to_bytes()).unwrap();
+/// assert_eq!(res.msg_type, BINDING_SUCCESS);
+/// assert_eq!(res.mapped_address(), Some(ep));
+/// ```
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct StunMessage {
+    pub msg_type: u16,
+    pub tid: [u8; 12],
+    pub attrs: Vec<StunAttribute>,
+}
+
+fn xor_key(tid: &[u8; 12]) -> [u8; 16] {
+    let mut key = [0; 16];
+    key[..4].copy_from_slice(&MAGIC_COOKIE.to_be_bytes());
+    key[4..].copy_from_slice(tid);
+    key
+}
+
+fn encode_address(ep: &UdpEndpoint, key: &[u8; 16]) -> Vec<u8> {
+    let port = ep.port() ^ ((key[0] as u16) << 8 | key[1] as u16);
+    let (family, addr) = match ep.addr() {
+        IpAddr::V4(addr) => (FAMILY_V4, addr.as_bytes().to_vec()),
+        IpAddr::V6(addr) => (FAMILY_V6, addr.as_bytes().to_vec()),
+    };
+    let mut data = vec![0, family, (port >> 8) as u8, port as u8];
+    data.extend(addr.iter().zip(key.iter()).map(|(a, k)| a ^ k));
+    data
+}
+
+fn decode_address(data: &[u8], key: &[u8; 16]) -> Option<UdpEndpoint> {
+    if data.len() < 4 {
+        return None;
+    }
+    let port = ((data[2] ^ key[0]) as u16) << 8 | (data[3] ^ key[1]) as u16;
+    let addr = &data[4..];
+    match data[1] {
+        FAMILY_V4 if addr.len() == 4 => {
+            let mut bytes = [0; 4];
+            for i in 0..4 {
+                bytes[i] = addr[i] ^ key[i];
+            }
+            Some(UdpEndpoint::new(IpAddrV4::from(bytes), port))
+        }
+        FAMILY_V6 if addr.len() == 16 => {
+            let mut bytes = [0; 16];
+            for i in 0..16 {
+                bytes[i] = addr[i] ^ key[i];
+            }
+            Some(UdpEndpoint::new(IpAddrV6::from(bytes, 0), port))
+        }
+        _ => None,
+    }
+}
+
+impl StunMessage {
+    /// Returns a binding request of the transaction.
+    pub fn binding_request(tid: [u8; 12]) -> StunMessage {
+        StunMessage {
+            msg_type: BINDING_REQUEST,
+            tid: tid,
+            attrs: Vec::new(),
+        }
+    }
+
+    /// Returns a binding success response with the XOR-MAPPED-ADDRESS of the endpoint.
+    pub fn binding_success(tid: [u8; 12], ep: &UdpEndpoint) -> StunMessage {
+        let mut msg = StunMessage {
+            msg_type: BINDING_SUCCESS,
+            tid: tid,
+            attrs: Vec::new(),
+        };
+        let data = encode_address(ep, &xor_key(&tid));
+        msg.set_attribute(ATTR_XOR_MAPPED_ADDRESS, &data);
+        msg
+    }
+
+    /// Returns a value of the attribute.
+    pub fn attribute(&self, ty: u16) -> Option<&[u8]> {
+        self.attrs.iter().find(|attr| attr.ty == ty).map(|attr| &attr.data[..])
+    }
+
+    /// Returns a error code of the ERROR-CODE attribute.
+    pub fn error_code(&self) -> Option<u16> {
+        self.attribute(ATTR_ERROR_CODE).and_then(|data| if data.len() >= 4 {
+            Some((data[2] & 0x7) as u16 * 100 + data[3] as u16)
+        } else {
+            None
+        })
+    }
+
+    /// Returns a reflexive address of the XOR-MAPPED-ADDRESS, or the MAPPED-ADDRESS of the
+    /// classic STUN server.
+    pub fn mapped_address(&self) -> Option<UdpEndpoint> {
+        if let Some(data) = self.attribute(ATTR_XOR_MAPPED_ADDRESS) {
+            return decode_address(data, &xor_key(&self.tid));
+        }
+        self.attribute(ATTR_MAPPED_ADDRESS).and_then(|data| decode_address(data, &[0; 16]))
+    }
+
+    /// Sets a value of the attribute, that replaces the existing one.
+    pub fn set_attribute(&mut self, ty: u16, data: &[u8]) {
+        self.attrs.retain(|attr| attr.ty != ty);
+        self.attrs.push(StunAttribute {
+            ty: ty,
+            data: data.to_vec(),
+        });
+    }
+
+    /// Parses a STUN message, returns `None` if malformed.
+    pub fn from_bytes(buf: &[u8]) -> Option<StunMessage> {
+        if buf.len() < HEADER_LEN || buf[0] & 0xc0 != 0 ||
+            &buf[4..8] != &MAGIC_COOKIE.to_be_bytes()
+        {
+            return None;
+        }
+        let len = (buf[2] as usize) << 8 | buf[3] as usize;
+        if HEADER_LEN + len != buf.len() {
+            return None;
+        }
+        let mut tid = [0; 12];
+        tid.copy_from_slice(&buf[8..20]);
+        let mut msg = StunMessage {
+            msg_type: (buf[0] as u16) << 8 | buf[1] as u16,
+            tid: tid,
+            attrs: Vec::new(),
+        };
+        let mut it = &buf[HEADER_LEN..];
+        while it.len() >= 4 {
+            let ty = (it[0] as u16) << 8 | it[1] as u16;
+            let len = (it[2] as usize) << 8 | it[3] as usize;
+            let padded = (len + 3) & !3;
+            if it.len() < 4 + padded {
+                return None;
+            }
+            msg.attrs.push(StunAttribute {
+                ty: ty,
+                data: it[4..4 + len].to_vec(),
+            });
+            it = &it[4 + padded..];
+        }
+        if it.is_empty() { Some(msg) } else { None }
+    }
+
+    /// Returns a encoded message.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(HEADER_LEN);
+        buf.extend_from_slice(&self.msg_type.to_be_bytes());
+        buf.extend_from_slice(&[0, 0]);
+        buf.extend_from_slice(&MAGIC_COOKIE.to_be_bytes());
+        buf.extend_from_slice(&self.tid);
+        for attr in &self.attrs {
+            buf.extend_from_slice(&attr.ty.to_be_bytes());
+            buf.extend_from_slice(&(attr.data.len() as u16).to_be_bytes());
+            buf.extend_from_slice(&attr.data);
+            while buf.len() % 4 != 0 {
+                buf.push(0);
+            }
+        }
+        let len = (buf.len() - HEADER_LEN) as u16;
+        buf[2..4].copy_from_slice(&len.to_be_bytes());
+        buf
+    }
+}
+
+fn new_tid() -> [u8; 12] {
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or(0);
+    let mut tid = [0; 12];
+    tid[..4].copy_from_slice(&nanos.to_be_bytes());
+    tid[4..8].copy_from_slice(&process::id().to_be_bytes());
+    tid[8..].copy_from_slice(&(COUNT.fetch_add(1, Ordering::SeqCst) as u32).to_be_bytes());
+    tid
+}
+
+/// Returns the reflexive address if the response matches the transaction.
+fn response(buf: &[u8], tid: &[u8; 12]) -> Option<io::Result<UdpEndpoint>> {
+    match StunMessage::from_bytes(buf) {
+        Some(ref msg) if &msg.tid == tid => {
+            match msg.msg_type {
+                BINDING_SUCCESS => msg.mapped_address().map(Ok),
+                BINDING_ERROR => Some(Err(CONNECTION_REFUSED.into())),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// The STUN client over a UDP socket.
+///
+/// The socket is kept by the client, that is used for the hole punching and the communication
+/// with the peer after the reflexive address is obtained.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use asyncio::IoContext;
+/// use asyncio::ip::{IpProtocol, IpAddrV4, Udp, UdpEndpoint, UdpSocket};
+/// use asyncio::ip::stun::StunClient;
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let soc = UdpSocket::new(ctx, Udp::v4()).unwrap();
+/// soc.bind(&UdpEndpoint::new(IpAddrV4::any(), 0)).unwrap();
+/// let cl = StunClient::new(soc);
+/// let server = UdpEndpoint::new(IpAddrV4::new(192, 0, 2, 1), 3478);
+/// println!("{}", cl.binding(&server).unwrap());
+/// ```
+pub struct StunClient {
+    soc: UdpSocket,
+    timer: SteadyTimer,
+    last: Mutex<Option<([u8; 12], UdpEndpoint)>>,
+    buf: UnsafeCell<[u8; 1500]>,
+    expired: AtomicBool,
+    interval: Duration,
+}
+
+impl StunClient {
+    pub fn new(soc: UdpSocket) -> StunClient {
+        let timer = SteadyTimer::new(soc.as_ctx());
+        StunClient {
+            soc: soc,
+            timer: timer,
+            last: Mutex::new(None),
+            buf: UnsafeCell::new([0; 1500]),
+            expired: AtomicBool::new(false),
+            interval: Duration::from_millis(500),
+        }
+    }
+
+    /// Asynchronously obtains the reflexive address from the STUN server.
+    ///
+    /// The binding request is retransmitted with exponential backoff, and the handler is
+    /// completed with `TimedOut` error if the server does not respond.
+    pub fn async_binding<F>(&self, server: &UdpEndpoint, handler: F) -> F::Output
+    where
+        F: Handler<UdpEndpoint, io::Error>,
+    {
+        let server = server.clone();
+        handler.wrap(self.as_ctx(), move |ctx, handler| {
+            let tid = new_tid();
+            *self.last.lock().unwrap() = Some((tid, server));
+            match self.send() {
+                Ok(_) => {
+                    self.start_timer(self.interval);
+                    self.async_receive(StunReceive {
+                        client: self,
+                        retries: 0,
+                        handler: handler,
+                    })
+                }
+                Err(err) => ctx.do_dispatch(Failure::new(err, handler)),
+            }
+        })
+    }
+
+    /// Obtains the reflexive address from the STUN server.
+    ///
+    /// The other datagrams received while waiting for the response are discarded.
+    pub fn binding(&self, server: &UdpEndpoint) -> io::Result<UdpEndpoint> {
+        let tid = new_tid();
+        *self.last.lock().unwrap() = Some((tid, server.clone()));
+        let buf = unsafe { &mut *self.buf.get() };
+        for i in 0..MAX_RETRANSMIT + 1 {
+            self.send()?;
+            let deadline = Instant::now() + self.interval * cmp::min(1 << i, 16);
+            loop {
+                match self.soc.wait_until(Wait::Read, deadline) {
+                    Ok(_) => (),
+                    Err(ref err) if err.kind() == io::ErrorKind::TimedOut => break,
+                    Err(err) => return Err(err),
+                }
+                let (len, ep) = match self.soc.nonblocking_receive_from(buf, 0) {
+                    Ok(res) => res,
+                    Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => continue,
+                    Err(err) => return Err(err),
+                };
+                if &ep == server {
+                    if let Some(res) = response(&buf[..len], &tid) {
+                        return res;
+                    }
+                }
+            }
+        }
+        Err(TIMED_OUT.into())
+    }
+
+    /// Returns the socket of the client.
+    pub fn socket(&self) -> &UdpSocket {
+        &self.soc
+    }
+
+    /// Returns the socket of the client.
+    pub fn into_socket(self) -> UdpSocket {
+        self.soc
+    }
+
+    /// Sets a initial retransmission interval, doubled for each retransmission.
+    pub fn set_interval(&mut self, interval: Duration) {
+        self.interval = interval;
+    }
+
+    fn send(&self) -> io::Result<usize> {
+        let (tid, server) = self.last.lock().unwrap().clone().unwrap();
+        let msg = StunMessage::binding_request(tid);
+        self.soc.send_to(&msg.to_bytes(), 0, &server)
+    }
+
+    fn start_timer(&self, interval: Duration) {
+        self.expired.store(false, Ordering::SeqCst);
+        self.timer.expires_from_now(interval);
+        self.timer.async_wait(StunRetransmit { client: self });
+    }
+
+    fn async_receive<F>(&self, handler: StunReceive<F>)
+    where
+        F: Complete<UdpEndpoint, io::Error>,
+    {
+        let buf = unsafe { &mut *self.buf.get() };
+        self.soc.async_receive_from(buf, 0, handler)
+    }
+}
+
+unsafe impl AsIoContext for StunClient {
+    fn as_ctx(&self) -> &IoContext {
+        self.soc.as_ctx()
+    }
+}
+
+impl Cancel for StunClient {
+    fn cancel(&self) {
+        self.timer.cancel();
+        self.soc.cancel();
+    }
+}
+
+unsafe impl Sync for StunClient {}
+
+struct StunReceive<F> {
+    client: *const StunClient,
+    retries: usize,
+    handler: F,
+}
+
+unsafe impl<F> Send for StunReceive<F> {}
+
+impl<F> Handler<(usize, UdpEndpoint), io::Error> for StunReceive<F>
+where
+    F: Complete<UdpEndpoint, io::Error>,
+{
+    type Output = ();
+
+    type WrappedHandler = Self;
+
+    fn wrap<W>(self, ctx: &IoContext, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx, self)
+    }
+
+    fn wrap_timeout<W>(self, ctx: &Cancel, _: &Timeout, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx.as_ctx(), self)
+    }
+}
+
+impl<F> Complete<(usize, UdpEndpoint), io::Error> for StunReceive<F>
+where
+    F: Complete<UdpEndpoint, io::Error>,
+{
+    fn success(self, this: &mut ThreadIoContext, res: (usize, UdpEndpoint)) {
+        let cl = unsafe { &*self.client };
+        let (len, ep) = res;
+        let buf = unsafe { &*cl.buf.get() };
+        let (tid, server) = cl.last.lock().unwrap().clone().unwrap();
+        if ep == server {
+            match response(&buf[..len], &tid) {
+                Some(Ok(ep)) => {
+                    cl.timer.cancel();
+                    return self.handler.success(this, ep);
+                }
+                Some(Err(err)) => {
+                    cl.timer.cancel();
+                    return self.handler.failure(this, err);
+                }
+                None => (),
+            }
+        }
+        this.decrease_outstanding_work();
+        cl.async_receive(self)
+    }
+
+    fn failure(mut self, this: &mut ThreadIoContext, err: io::Error) {
+        let cl = unsafe { &*self.client };
+        if !cl.expired.load(Ordering::SeqCst) {
+            cl.timer.cancel();
+            return self.handler.failure(this, err);
+        }
+        self.retries += 1;
+        if self.retries > MAX_RETRANSMIT {
+            return self.handler.failure(this, TIMED_OUT.into());
+        }
+        if let Err(err) = cl.send() {
+            return self.handler.failure(this, err);
+        }
+        cl.start_timer(cl.interval * cmp::min(1 << self.retries, 16));
+        this.decrease_outstanding_work();
+        cl.async_receive(self)
+    }
+}
+
+struct StunRetransmit {
+    client: *const StunClient,
+}
+
+unsafe impl Send for StunRetransmit {}
+
+impl Handler<(), io::Error> for StunRetransmit {
+    type Output = ();
+
+    type WrappedHandler = Self;
+
+    fn wrap<W>(self, ctx: &IoContext, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx, self)
+    }
+
+    fn wrap_timeout<W>(self, ctx: &Cancel, _: &Timeout, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx.as_ctx(), self)
+    }
+}
+
+impl Complete<(), io::Error> for StunRetransmit {
+    fn success(self, this: &mut ThreadIoContext, _: ()) {
+        let cl = unsafe { &*self.client };
+        cl.expired.store(true, Ordering::SeqCst);
+        cl.soc.cancel();
+        this.decrease_outstanding_work();
+    }
+
+    fn failure(self, this: &mut ThreadIoContext, _: io::Error) {
+        this.decrease_outstanding_work();
+    }
+}
+
+/// Opens the path through the NATs to the peer's reflexive address by the simultaneous sending.
+///
+/// Both peers call this function at about the same time. A probe datagram is sent to the peer
+/// every `interval` up to `attempts` times, and returns when the peer's probe is received, or
+/// returns the `ETIMEDOUT` error. The other datagrams received during the punching are discarded.
+pub fn punch_hole(
+    soc: &UdpSocket,
+    peer: &UdpEndpoint,
+    attempts: usize,
+    interval: Duration,
+) -> io::Result<()> {
+    let mut buf = [0; 64];
+    for _ in 0..attempts {
+        soc.send_to(PUNCH, 0, peer)?;
+        let deadline = Instant::now() + interval;
+        loop {
+            match soc.wait_until(Wait::Read, deadline) {
+                Ok(_) => (),
+                Err(ref err) if err.kind() == io::ErrorKind::TimedOut => break,
+                Err(err) => return Err(err),
+            }
+            match soc.nonblocking_receive_from(&mut buf, 0) {
+                Ok((len, ref ep)) if ep == peer && &buf[..len] == PUNCH => {
+                    // Answers the last probe, that the peer may be still waiting for.
+                    soc.send_to(PUNCH, 0, peer)?;
+                    return Ok(());
+                }
+                Ok(_) => (),
+                Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => (),
+                Err(err) => return Err(err),
+            }
+        }
+    }
+    Err(TIMED_OUT.into())
+}
+
+#[test]
+fn test_stun_message() {
+    let tid = [0xb7, 0xe7, 0xa7, 0x01, 0xbc, 0x34, 0xd6, 0x86, 0xfa, 0x87, 0xdf, 0xae];
+    let ep = UdpEndpoint::new(IpAddrV4::new(192, 0, 2, 1), 32853);
+    let buf = StunMessage::binding_success(tid, &ep).to_bytes();
+    assert_eq!(buf.len(), 20 + 4 + 8);
+    assert_eq!(&buf[..4], &[0x01, 0x01, 0x00, 0x0c]);
+    // The test vector of RFC 5769.
+    assert_eq!(&buf[20..], &[0x00, 0x20, 0x00, 0x08, 0x00, 0x01, 0xa1, 0x47, 0xe1, 0x12, 0xa6, 0x43]);
+    let msg = StunMessage::from_bytes(&buf).unwrap();
+    assert_eq!(msg.mapped_address(), Some(ep));
+    assert_eq!(StunMessage::from_bytes(&buf[..buf.len() - 1]), None);
+
+    let ep = UdpEndpoint::new(IpAddrV6::loopback(), 3478);
+    let msg = StunMessage::binding_success(tid, &ep);
+    assert_eq!(StunMessage::from_bytes(&msg.to_bytes()).unwrap().mapped_address(), Some(ep));
+
+    let mut msg = StunMessage::binding_request(tid);
+    msg.set_attribute(ATTR_SOFTWARE, b"abc");
+    msg.set_attribute(ATTR_ERROR_CODE, &[0, 0, 4, 20]);
+    let msg = StunMessage::from_bytes(&msg.to_bytes()).unwrap();
+    assert_eq!(msg.attribute(ATTR_SOFTWARE), Some(&b"abc"[..]));
+    assert_eq!(msg.error_code(), Some(420));
+}
+
+#[test]
+fn test_stun_client() {
+    use std::sync::Arc;
+    use std::thread;
+    use handler::wrap;
+    use ip::{IpProtocol, Udp};
+
+    static MAPPED: AtomicBool = AtomicBool::new(false);
+
+    fn server(ctx: &IoContext, count: usize) -> (UdpEndpoint, thread::JoinHandle<()>) {
+        let soc = UdpSocket::new(ctx, Udp::v4()).unwrap();
+        soc.bind(&UdpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+        let ep = soc.local_endpoint().unwrap();
+        let th = thread::spawn(move || for _ in 0..count {
+            let mut buf = [0; 1500];
+            let (len, ep) = soc.receive_from(&mut buf, 0).unwrap();
+            let req = StunMessage::from_bytes(&buf[..len]).unwrap();
+            let res = StunMessage::binding_success(req.tid, &ep);
+            soc.send_to(&res.to_bytes(), 0, &ep).unwrap();
+        });
+        (ep, th)
+    }
+
+    let ctx = &IoContext::new().unwrap();
+    let other = &IoContext::new().unwrap();
+    let (ep, th) = server(other, 2);
+    let soc = UdpSocket::new(ctx, Udp::v4()).unwrap();
+    soc.bind(&UdpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+    let local = soc.local_endpoint().unwrap();
+    let cl = Arc::new(StunClient::new(soc));
+    assert_eq!(cl.binding(&ep).unwrap(), local);
+
+    cl.async_binding(&ep, wrap(&cl, move |_, res: io::Result<UdpEndpoint>| {
+        assert_eq!(res.unwrap(), local);
+        MAPPED.store(true, Ordering::SeqCst);
+    }));
+    ctx.run();
+    assert!(MAPPED.load(Ordering::SeqCst));
+    th.join().unwrap();
+}
+
+#[test]
+fn test_punch_hole() {
+    use std::thread;
+    use ip::{IpProtocol, Udp};
+
+    let ctx = &IoContext::new().unwrap();
+    let a = UdpSocket::new(ctx, Udp::v4()).unwrap();
+    let b = UdpSocket::new(ctx, Udp::v4()).unwrap();
+    a.bind(&UdpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+    b.bind(&UdpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+    let a_ep = a.local_endpoint().unwrap();
+    let b_ep = b.local_endpoint().unwrap();
+    let th = thread::spawn(move || punch_hole(&b, &a_ep, 10, Duration::from_millis(50)));
+    punch_hole(&a, &b_ep, 10, Duration::from_millis(50)).unwrap();
+    th.join().unwrap().unwrap();
+
+    let c = UdpSocket::new(ctx, Udp::v4()).unwrap();
+    assert_eq!(
+        punch_hole(&c, &b_ep, 2, Duration::from_millis(1)).unwrap_err().kind(),
+        io::ErrorKind::TimedOut
+    );
+}
@@ -1,9 +1,15 @@
-use ffi::{AF_INET, AF_INET6, AF_UNSPEC, SOCK_STREAM, IPPROTO_TCP, AI_PASSIVE, AI_NUMERICSERV};
+use ffi::{AF_INET, AF_INET6, SOCK_STREAM, IPPROTO_TCP};
+#[cfg(feature = "resolver")]
+use ffi::{AF_UNSPEC, AI_PASSIVE, AI_NUMERICSERV};
 use core::Protocol;
 use handler::Handler;
 use socket_listener::SocketListener;
 use stream_socket::StreamSocket;
-use ip::{IpEndpoint, IpProtocol, Passive, Resolver, ResolverIter, ResolverQuery};
+use ip::{IpEndpoint, IpProtocol};
+#[cfg(feature = "resolver")]
+use ip::{Passive, Resolver, ResolverIter, ResolverQuery};
+#[cfg(feature = "resolver")]
+use ip::resolver::cache_key;
 
 use std::io;
 use std::fmt;
@@ -54,7 +60,7 @@ use std::mem;
 /// let re = TcpResolver::new(ctx);
 /// let (soc, ep) = re.connect(("localhost", "12345")).unwrap();
 /// ```
-#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+#[derive(Clone, Copy, Eq, Ord, PartialEq, PartialOrd)]
 pub struct Tcp {
     family: i32,
 }
@@ -134,6 +140,17 @@ impl fmt::Display for Tcp {
     }
 }
 
+impl fmt::Debug for Tcp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.family_type() {
+            AF_INET => write!(f, "tcp"),
+            AF_INET6 => write!(f, "tcp6"),
+            _ => write!(f, "Tcp(family={}, protocol={})", self.family, self.protocol_type()),
+        }
+    }
+}
+
+#[cfg(feature = "resolver")]
 impl ResolverQuery<Tcp> for (Passive, u16) {
     fn iter(self) -> io::Result<ResolverIter<Tcp>> {
         let port = self.1.to_string();
@@ -144,18 +161,37 @@ impl ResolverQuery<Tcp> for (Passive, u16) {
             AI_PASSIVE | AI_NUMERICSERV,
         )
     }
+
+    fn cache_key(&self) -> Option<String> {
+        Some(cache_key(
+            &Tcp { family: AF_UNSPEC },
+            "",
+            &self.1.to_string(),
+            AI_PASSIVE | AI_NUMERICSERV,
+        ))
+    }
 }
 
+#[cfg(feature = "resolver")]
 impl<'a> ResolverQuery<Tcp> for (Passive, &'a str) {
     fn iter(self) -> io::Result<ResolverIter<Tcp>> {
         ResolverIter::new(&Tcp { family: AF_UNSPEC }, "", self.1, AI_PASSIVE)
     }
+
+    fn cache_key(&self) -> Option<String> {
+        Some(cache_key(&Tcp { family: AF_UNSPEC }, "", self.1, AI_PASSIVE))
+    }
 }
 
+#[cfg(feature = "resolver")]
 impl<'a, 'b> ResolverQuery<Tcp> for (&'a str, &'b str) {
     fn iter(self) -> io::Result<ResolverIter<Tcp>> {
         ResolverIter::new(&Tcp { family: AF_UNSPEC }, self.0, self.1, 0)
     }
+
+    fn cache_key(&self) -> Option<String> {
+        Some(cache_key(&Tcp { family: AF_UNSPEC }, self.0, self.1, 0))
+    }
 }
 
 /// The TCP endpoint type.
@@ -164,6 +200,7 @@ pub type TcpEndpoint = IpEndpoint<Tcp>;
 /// The TCP socket type.
 pub type TcpSocket = StreamSocket<Tcp>;
 
+#[cfg(feature = "resolver")]
 /// The TCP resolver type.
 pub type TcpResolver = Resolver<Tcp>;
 
@@ -177,6 +214,7 @@ fn test_tcp() {
     assert!(Tcp::v4() != Tcp::v6());
 }
 
+#[cfg(feature = "resolver")]
 #[test]
 fn test_tcp_resolver() {
     use IoContext;
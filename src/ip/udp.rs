@@ -1,8 +1,14 @@
-use ffi::{AF_INET, AF_INET6, AF_UNSPEC, SOCK_DGRAM, IPPROTO_UDP, AI_PASSIVE, AI_NUMERICSERV};
+use ffi::{AF_INET, AF_INET6, SOCK_DGRAM, IPPROTO_UDP};
+#[cfg(feature = "resolver")]
+use ffi::{AF_UNSPEC, AI_PASSIVE, AI_NUMERICSERV};
 use core::Protocol;
 use handler::Handler;
 use dgram_socket::DgramSocket;
-use ip::{IpEndpoint, IpProtocol, Passive, Resolver, ResolverIter, ResolverQuery};
+use ip::{IpEndpoint, IpProtocol};
+#[cfg(feature = "resolver")]
+use ip::{Passive, Resolver, ResolverIter, ResolverQuery};
+#[cfg(feature = "resolver")]
+use ip::resolver::cache_key;
 
 use std::io;
 use std::fmt;
@@ -42,7 +48,7 @@ use std::mem;
 /// let mut buf = [0; 256];
 /// let (len, ep) = soc.receive_from(&mut buf, 0).unwrap();
 /// ```
-#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+#[derive(Clone, Copy, Eq, Ord, PartialEq, PartialOrd)]
 pub struct Udp {
     family: i32,
 }
@@ -122,6 +128,17 @@ impl fmt::Display for Udp {
     }
 }
 
+impl fmt::Debug for Udp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.family_type() {
+            AF_INET => write!(f, "udp"),
+            AF_INET6 => write!(f, "udp6"),
+            _ => write!(f, "Udp(family={}, protocol={})", self.family, self.protocol_type()),
+        }
+    }
+}
+
+#[cfg(feature = "resolver")]
 impl ResolverQuery<Udp> for (Passive, u16) {
     fn iter(self) -> io::Result<ResolverIter<Udp>> {
         let port = self.1.to_string();
@@ -132,18 +149,37 @@ impl ResolverQuery<Udp> for (Passive, u16) {
             AI_PASSIVE | AI_NUMERICSERV,
         )
     }
+
+    fn cache_key(&self) -> Option<String> {
+        Some(cache_key(
+            &Udp { family: AF_UNSPEC },
+            "",
+            &self.1.to_string(),
+            AI_PASSIVE | AI_NUMERICSERV,
+        ))
+    }
 }
 
+#[cfg(feature = "resolver")]
 impl<'a> ResolverQuery<Udp> for (Passive, &'a str) {
     fn iter(self) -> io::Result<ResolverIter<Udp>> {
         ResolverIter::new(&Udp { family: AF_UNSPEC }, "", self.1, AI_PASSIVE)
     }
+
+    fn cache_key(&self) -> Option<String> {
+        Some(cache_key(&Udp { family: AF_UNSPEC }, "", self.1, AI_PASSIVE))
+    }
 }
 
+#[cfg(feature = "resolver")]
 impl<'a, 'b> ResolverQuery<Udp> for (&'a str, &'b str) {
     fn iter(self) -> io::Result<ResolverIter<Udp>> {
         ResolverIter::new(&Udp { family: AF_UNSPEC }, self.0, self.1, 0)
     }
+
+    fn cache_key(&self) -> Option<String> {
+        Some(cache_key(&Udp { family: AF_UNSPEC }, self.0, self.1, 0))
+    }
 }
 
 /// The UDP endpoint type.
@@ -164,6 +200,7 @@ pub type UdpEndpoint = IpEndpoint<Udp>;
 /// ```
 pub type UdpSocket = DgramSocket<Udp>;
 
+#[cfg(feature = "resolver")]
 /// The UDP resolver type.
 pub type UdpResolver = Resolver<Udp>;
 
@@ -174,6 +211,7 @@ fn test_udp() {
     assert!(Udp::v4() != Udp::v6());
 }
 
+#[cfg(feature = "resolver")]
 #[test]
 fn test_udp_resolve() {
     use core::IoContext;
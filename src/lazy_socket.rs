@@ -0,0 +1,167 @@
+use ffi::{RawFd, SystemError, BAD_DESCRIPTOR, socket, setsockopt, set_nonblocking};
+use core::{Protocol, Socket, GetSocketOption, SetSocketOption, AsIoContext, IoContext};
+
+use std::io;
+use std::any::{Any, TypeId};
+
+struct QueuedOption<P: Protocol> {
+    id: TypeId,
+    value: Box<Any + Send>,
+    apply: fn(&P::Socket, Box<Any + Send>) -> Result<(), SystemError>,
+}
+
+fn apply<P, C>(soc: &P::Socket, value: Box<Any + Send>) -> Result<(), SystemError>
+where
+    P: Protocol,
+    C: SetSocketOption<P> + 'static,
+{
+    setsockopt(soc, *value.downcast::<C>().unwrap())
+}
+
+/// Provides a socket not yet opened, that the protocol is decided at the opening.
+///
+/// The socket options set before the opening are queued, and applied in the order of setting
+/// when the socket is opened.
+///
+/// # Examples
+///
+/// ```
+/// use asyncio::{IoContext, LazySocket};
+/// use asyncio::ip::{IpAddrV6, Tcp, TcpEndpoint, TcpListener, IpProtocol};
+/// use asyncio::socket_base::ReuseAddr;
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let mut soc: LazySocket<Tcp> = LazySocket::new(ctx);
+/// soc.set_option(ReuseAddr::new(true));
+/// assert!(soc.get_option::<ReuseAddr>().unwrap().get());
+///
+/// // e.g. the endpoint is resolved later.
+/// let ep = TcpEndpoint::new(IpAddrV6::loopback(), 0);
+/// let soc = soc.open(ep.protocol()).unwrap();
+/// soc.bind(&ep).unwrap();
+/// ```
+pub struct LazySocket<P: Protocol> {
+    ctx: IoContext,
+    opts: Vec<QueuedOption<P>>,
+}
+
+impl<P> LazySocket<P>
+where
+    P: Protocol,
+{
+    pub fn new(ctx: &IoContext) -> Self {
+        LazySocket {
+            ctx: ctx.clone(),
+            opts: Vec::new(),
+        }
+    }
+
+    /// Adopts the native socket of the protocol and applies the queued socket options.
+    pub fn assign(self, soc: RawFd, pro: P) -> io::Result<P::Socket> {
+        set_nonblocking(soc)?;
+        let soc = unsafe { P::Socket::from_raw_fd(&self.ctx, soc, pro) };
+        self.apply_all(soc)
+    }
+
+    fn apply_all(self, soc: P::Socket) -> io::Result<P::Socket> {
+        for opt in self.opts {
+            (opt.apply)(&soc, opt.value)?;
+        }
+        Ok(soc)
+    }
+
+    /// Returns the queued value of the socket option, returns the `EBADF` error if not queued.
+    pub fn get_option<C>(&self) -> io::Result<C>
+    where
+        C: GetSocketOption<P> + Clone + 'static,
+    {
+        let id = TypeId::of::<C>();
+        match self.opts.iter().find(|opt| opt.id == id) {
+            Some(opt) => Ok(opt.value.downcast_ref::<C>().unwrap().clone()),
+            None => Err(BAD_DESCRIPTOR.into()),
+        }
+    }
+
+    /// Opens the socket of the protocol and applies the queued socket options.
+    ///
+    /// The socket is closed if failed to apply any of the options.
+    pub fn open(self, pro: P) -> io::Result<P::Socket> {
+        let soc = unsafe { P::Socket::from_raw_fd(&self.ctx, socket(&pro)?, pro) };
+        self.apply_all(soc)
+    }
+
+    /// Queues the socket option, that replaces the previous value of the same option.
+    pub fn set_option<C>(&mut self, cmd: C)
+    where
+        C: SetSocketOption<P> + Send + 'static,
+    {
+        let id = TypeId::of::<C>();
+        let value = Box::new(cmd);
+        match self.opts.iter_mut().find(|opt| opt.id == id) {
+            Some(opt) => opt.value = value,
+            None => {
+                self.opts.push(QueuedOption {
+                    id: id,
+                    value: value,
+                    apply: apply::<P, C>,
+                })
+            }
+        }
+    }
+}
+
+unsafe impl<P> AsIoContext for LazySocket<P>
+where
+    P: Protocol,
+{
+    fn as_ctx(&self) -> &IoContext {
+        &self.ctx
+    }
+}
+
+#[test]
+fn test_lazy_socket() {
+    use ip::{IpProtocol, Udp};
+    use socket_base::{Broadcast, ReuseAddr};
+
+    let ctx = &IoContext::new().unwrap();
+    let mut soc: LazySocket<Udp> = LazySocket::new(ctx);
+    assert!(soc.get_option::<Broadcast>().is_err());
+    soc.set_option(Broadcast::new(false));
+    soc.set_option(ReuseAddr::new(true));
+    soc.set_option(Broadcast::new(true));
+    assert!(soc.get_option::<Broadcast>().unwrap().get());
+    assert_eq!(soc.opts.len(), 2);
+
+    let soc = soc.open(Udp::v4()).unwrap();
+    assert!(soc.get_option::<Broadcast>().unwrap().get());
+    assert!(soc.get_option::<ReuseAddr>().unwrap().get());
+}
+
+#[test]
+fn test_lazy_socket_assign() {
+    use std::net;
+    use std::os::unix::io::IntoRawFd;
+    use ip::{IpProtocol, Udp, UdpSocket};
+    use socket_base::Broadcast;
+
+    let ctx = &IoContext::new().unwrap();
+    let std = net::UdpSocket::bind("127.0.0.1:0").unwrap();
+    let port = std.local_addr().unwrap().port();
+    let mut soc: LazySocket<Udp> = LazySocket::new(ctx);
+    soc.set_option(Broadcast::new(true));
+    let mut soc = soc.assign(std.into_raw_fd(), Udp::v4()).unwrap();
+    assert!(soc.is_open());
+    assert_eq!(soc.local_endpoint().unwrap().port(), port);
+    assert!(soc.get_option::<Broadcast>().unwrap().get());
+
+    soc.close().unwrap();
+    assert!(!soc.is_open());
+    assert!(soc.local_endpoint().is_err());
+    soc.close().unwrap();
+
+    let other = UdpSocket::new(ctx, Udp::v6()).unwrap();
+    soc.assign(other.into_raw_fd(), Udp::v6()).unwrap();
+    assert!(soc.is_open());
+    assert_eq!(soc.protocol(), &Udp::v6());
+}
@@ -18,8 +18,8 @@ extern crate lazy_static;
 
 extern crate libc;
 
-// #[cfg(feature = "openssl")]
-// extern crate openssl;
+#[cfg(feature = "openssl")]
+extern crate openssl;
 
 #[cfg(feature = "openssl-sys")]
 extern crate openssl_sys;
@@ -30,6 +30,15 @@ extern crate termios;
 #[cfg(feature = "test")]
 extern crate test;
 
+#[cfg(feature = "tokio")]
+extern crate tokio;
+
+#[cfg(feature = "async-std")]
+extern crate async_std;
+
+#[cfg(all(target_os = "linux", feature = "uring"))]
+extern crate io_uring;
+
 extern crate winapi;
 
 extern crate ws2_32;
@@ -39,19 +48,31 @@ mod ffi;
 mod timer;
 
 mod reactor;
+pub use self::reactor::{Interrupter, OpStats};
 
 mod core;
-pub use self::core::{AsIoContext, IoContext, IoContextWork, Protocol, Endpoint, Socket, IoControl,
-                     GetSocketOption, SetSocketOption, Cancel};
+pub use self::core::{AsIoContext, IoContext, IoContextBuilder, IoContextWork, Protocol, Endpoint, Socket, IoControl,
+                     GetSocketOption, SetSocketOption, Cancel, InvokeHook, Scheduling};
+
+mod observer;
+pub use self::observer::{SocketObserver, EndpointInfo};
 
 mod handler;
-pub use self::handler::{Handler, ArcHandler, wrap};
+pub use self::handler::{Handler, ArcHandler, BoxHandler, wrap};
+
+mod error;
+pub use self::error::Error;
+
+mod extensions;
+pub use self::extensions::Extensions;
 
 mod strand;
 pub use self::strand::*;
 
 mod accept_ops;
 
+mod close_ops;
+
 mod connect_ops;
 
 mod read_ops;
@@ -65,33 +86,115 @@ pub type SystemTimer = clock::WaitableTimer<clock::SystemClock>;
 mod streambuf;
 pub use self::streambuf::*;
 
+mod buffer_seq;
+pub use self::buffer_seq::{BufferSequence, MutableBufferSequence};
+
 pub mod socket_base;
 
 mod stream;
 pub use self::stream::*;
 
+mod random_access;
+pub use self::random_access::RandomAccessDevice;
+
 mod dgram_socket;
 pub use self::dgram_socket::*;
 
+mod dgram_batch;
+pub use self::dgram_batch::{DgramBatch, DgramBatchIter};
+
+mod pacer;
+pub use self::pacer::Pacer;
+
+mod backoff;
+pub use self::backoff::Backoff;
+
+mod compat;
+
 mod stream_socket;
 pub use self::stream_socket::*;
 
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod splice;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub use self::splice::tee;
+
+mod send_file;
+
+mod lazy_socket;
+pub use self::lazy_socket::LazySocket;
+
+mod write_queue;
+pub use self::write_queue::{WriteQueue, WritePriority};
+
+mod pending_map;
+pub use self::pending_map::PendingMap;
+
+mod cancellation_scope;
+pub use self::cancellation_scope::CancellationScope;
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod async_counter;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub use self::async_counter::AsyncCounter;
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod readiness;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub use self::readiness::{ReadinessSet, Readiness, Interest};
+
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "macos"))]
+mod file_watcher;
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "macos"))]
+pub use self::file_watcher::{FileWatcher, FileEvent, WatchDescriptor, WatchMask};
+
 mod socket_listener;
 pub use self::socket_listener::*;
 
+mod reuse_port;
+pub use self::reuse_port::ReusePortListenerGroup;
+
+mod server;
+pub use self::server::{Server, Service, Connection};
+
+#[cfg(all(unix, feature = "resolver"))]
+mod uri;
+#[cfg(all(unix, feature = "resolver"))]
+pub use self::uri::{UriStream, connect_uri};
+
 pub mod generic;
 
 pub mod local;
 
 pub mod ip;
 
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub mod ll;
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub mod netlink;
+
+#[cfg(feature = "rudp")]
+pub mod rudp;
+
+#[cfg(feature = "ssl")]
+pub mod ssl;
+
 mod from_str;
 
 pub mod posix;
 
+pub mod prelude;
+
 #[cfg(unix)]
+pub mod pipe;
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub mod process;
+
+#[cfg(all(unix, feature = "signal"))]
 mod signal_set;
-#[cfg(unix)]
+#[cfg(all(unix, feature = "signal"))]
 pub use self::signal_set::{Signal, SignalSet, raise};
 
 #[cfg(feature = "termios")]
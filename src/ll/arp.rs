@@ -0,0 +1,310 @@
+use ffi::{Timeout, TIMED_OUT, INVALID_ARGUMENT, if_nametoindex, if_hwaddr, if_inaddr};
+use core::{IoContext, ThreadIoContext, Cancel};
+use handler::{Handler, Complete, Failure};
+use ip::{IpAddrV4, LlAddr};
+use ll::{Packet, PacketEndpoint, PacketSocket, ETH_P_ARP};
+use SteadyTimer;
+
+use std::io;
+use std::cell::UnsafeCell;
+use std::ffi::CString;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+const ARP_REQUEST: u16 = 1;
+const ARP_REPLY: u16 = 2;
+
+/// The ARP message for IP-v4 over ethernet.
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub struct ArpPacket {
+    /// The operation code, 1 is request and 2 is reply.
+    pub op: u16,
+
+    /// The sender hardware address.
+    pub sender_hw: LlAddr,
+
+    /// The sender protocol address.
+    pub sender_ip: IpAddrV4,
+
+    /// The target hardware address.
+    pub target_hw: LlAddr,
+
+    /// The target protocol address.
+    pub target_ip: IpAddrV4,
+}
+
+impl ArpPacket {
+    /// Returns a ARP request message to resolve the `target_ip`.
+    pub fn request(sender_hw: LlAddr, sender_ip: IpAddrV4, target_ip: IpAddrV4) -> ArpPacket {
+        ArpPacket {
+            op: ARP_REQUEST,
+            sender_hw: sender_hw,
+            sender_ip: sender_ip,
+            target_hw: LlAddr::default(),
+            target_ip: target_ip,
+        }
+    }
+
+    /// Returns true if this is a ARP reply message.
+    pub fn is_reply(&self) -> bool {
+        self.op == ARP_REPLY
+    }
+
+    /// Returns a wire-format bytes of the message.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use asyncio::ip::{IpAddrV4, LlAddr};
+    /// use asyncio::ll::ArpPacket;
+    ///
+    /// let arp = ArpPacket::request(LlAddr::new(1,2,3,4,5,6), IpAddrV4::new(192,168,0,1), IpAddrV4::new(192,168,0,2));
+    /// assert_eq!(ArpPacket::from_bytes(&arp.to_bytes()), Some(arp));
+    /// ```
+    pub fn to_bytes(&self) -> [u8; 28] {
+        let mut buf = [0; 28];
+        buf[0..2].copy_from_slice(&[0, 1]); // ethernet
+        buf[2..4].copy_from_slice(&[0x08, 0x00]); // ip-v4
+        buf[4] = 6;
+        buf[5] = 4;
+        buf[6] = (self.op >> 8) as u8;
+        buf[7] = self.op as u8;
+        buf[8..14].copy_from_slice(self.sender_hw.as_bytes());
+        buf[14..18].copy_from_slice(self.sender_ip.as_bytes());
+        buf[18..24].copy_from_slice(self.target_hw.as_bytes());
+        buf[24..28].copy_from_slice(self.target_ip.as_bytes());
+        buf
+    }
+
+    /// Returns a ARP message parsed from wire-format bytes.
+    ///
+    /// Returns `None` if the bytes is not a IP-v4 over ethernet ARP message.
+    pub fn from_bytes(buf: &[u8]) -> Option<ArpPacket> {
+        if buf.len() < 28 || buf[0..6] != [0, 1, 0x08, 0x00, 6, 4] {
+            return None;
+        }
+        let mut sender_hw = [0; 6];
+        let mut sender_ip = [0; 4];
+        let mut target_hw = [0; 6];
+        let mut target_ip = [0; 4];
+        sender_hw.copy_from_slice(&buf[8..14]);
+        sender_ip.copy_from_slice(&buf[14..18]);
+        target_hw.copy_from_slice(&buf[18..24]);
+        target_ip.copy_from_slice(&buf[24..28]);
+        Some(ArpPacket {
+            op: (buf[6] as u16) << 8 | buf[7] as u16,
+            sender_hw: LlAddr::from(sender_hw),
+            sender_ip: IpAddrV4::from(sender_ip),
+            target_hw: LlAddr::from(target_hw),
+            target_ip: IpAddrV4::from(target_ip),
+        })
+    }
+}
+
+struct ArpProbe {
+    soc: PacketSocket,
+    timer: SteadyTimer,
+    buf: UnsafeCell<[u8; 64]>,
+    target: IpAddrV4,
+    timed_out: AtomicBool,
+}
+
+unsafe impl Send for ArpProbe {}
+
+unsafe impl Sync for ArpProbe {}
+
+impl ArpProbe {
+    fn new(ctx: &IoContext, iface: &str, target: IpAddrV4) -> io::Result<Arc<Self>> {
+        let pro = Packet::dgram(ETH_P_ARP);
+        let name = CString::new(iface).or(Err(INVALID_ARGUMENT))?;
+        let soc = PacketSocket::new(ctx, pro)?;
+        let ifindex = if_nametoindex(&name)?;
+        let sender_hw = LlAddr::from(if_hwaddr(&soc, &name)?);
+        let sender_ip = IpAddrV4::from(if_inaddr(&soc, &name)?);
+        soc.bind(&PacketEndpoint::new(pro, ifindex, LlAddr::default()))?;
+
+        let bcast = LlAddr::new(0xff, 0xff, 0xff, 0xff, 0xff, 0xff);
+        let arp = ArpPacket::request(sender_hw, sender_ip, target);
+        soc.send_to(
+            &arp.to_bytes(),
+            0,
+            &PacketEndpoint::new(pro, ifindex, bcast),
+        )?;
+
+        Ok(Arc::new(ArpProbe {
+            soc: soc,
+            timer: SteadyTimer::new(ctx),
+            buf: UnsafeCell::new([0; 64]),
+            target: target,
+            timed_out: AtomicBool::new(false),
+        }))
+    }
+
+    fn async_receive<F>(&self, handler: ArpReceive<F>)
+    where
+        F: Complete<LlAddr, io::Error>,
+    {
+        let buf = unsafe { &mut *self.buf.get() };
+        self.soc.async_receive_from(buf, 0, handler)
+    }
+}
+
+struct ArpReceive<F> {
+    probe: Arc<ArpProbe>,
+    handler: F,
+}
+
+impl<F> Handler<(usize, PacketEndpoint), io::Error> for ArpReceive<F>
+where
+    F: Complete<LlAddr, io::Error>,
+{
+    type Output = ();
+
+    type WrappedHandler = Self;
+
+    fn wrap<W>(self, ctx: &IoContext, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx, self)
+    }
+
+    fn wrap_timeout<W>(self, ctx: &Cancel, _: &Timeout, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx.as_ctx(), self)
+    }
+}
+
+impl<F> Complete<(usize, PacketEndpoint), io::Error> for ArpReceive<F>
+where
+    F: Complete<LlAddr, io::Error>,
+{
+    fn success(self, this: &mut ThreadIoContext, res: (usize, PacketEndpoint)) {
+        let (len, _) = res;
+        let buf = unsafe { &*self.probe.buf.get() };
+        match ArpPacket::from_bytes(&buf[..len]) {
+            Some(ref arp) if arp.is_reply() && arp.sender_ip == self.probe.target => {
+                self.probe.timer.cancel();
+                self.handler.success(this, arp.sender_hw)
+            }
+            _ => {
+                this.decrease_outstanding_work();
+                let probe = self.probe.clone();
+                probe.async_receive(self)
+            }
+        }
+    }
+
+    fn failure(self, this: &mut ThreadIoContext, err: io::Error) {
+        self.probe.timer.cancel();
+        if self.probe.timed_out.load(Ordering::SeqCst) {
+            self.handler.failure(this, TIMED_OUT.into())
+        } else {
+            self.handler.failure(this, err)
+        }
+    }
+}
+
+struct ArpTimeout {
+    probe: Arc<ArpProbe>,
+}
+
+impl Handler<(), io::Error> for ArpTimeout {
+    type Output = ();
+
+    type WrappedHandler = Self;
+
+    fn wrap<W>(self, ctx: &IoContext, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx, self)
+    }
+
+    fn wrap_timeout<W>(self, ctx: &Cancel, _: &Timeout, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx.as_ctx(), self)
+    }
+}
+
+impl Complete<(), io::Error> for ArpTimeout {
+    fn success(self, this: &mut ThreadIoContext, _: ()) {
+        self.probe.timed_out.store(true, Ordering::SeqCst);
+        self.probe.soc.cancel();
+        this.decrease_outstanding_work();
+    }
+
+    fn failure(self, this: &mut ThreadIoContext, _: io::Error) {
+        this.decrease_outstanding_work();
+    }
+}
+
+/// Sends a ARP request to the `target` from the interface and asynchronously waits for the reply.
+///
+/// The handler is completed with the link-layer address of the `target`, or
+/// `TimedOut` error if no reply is received within the `timeout`.
+/// This requires the `CAP_NET_RAW` capability.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use std::io;
+/// use std::time::Duration;
+/// use std::sync::Arc;
+/// use asyncio::{IoContext, IoContextWork, wrap};
+/// use asyncio::ip::{IpAddrV4, LlAddr};
+/// use asyncio::ll::arp_probe;
+///
+/// fn on_probe(_: Arc<IoContextWork>, res: io::Result<LlAddr>) {
+///     if let Ok(mac) = res {
+///         println!("{}", mac);
+///     }
+/// }
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let work = Arc::new(IoContextWork::new(ctx));
+/// arp_probe(ctx, "eth0", IpAddrV4::new(192,168,0,1), Duration::new(1, 0), wrap(&work, on_probe));
+/// drop(work);
+/// ctx.run();
+/// ```
+pub fn arp_probe<F>(
+    ctx: &IoContext,
+    iface: &str,
+    target: IpAddrV4,
+    timeout: Duration,
+    handler: F,
+) -> F::Output
+where
+    F: Handler<LlAddr, io::Error>,
+{
+    handler.wrap(ctx, move |ctx, handler| match ArpProbe::new(ctx, iface, target) {
+        Ok(probe) => {
+            probe.timer.expires_from_now(timeout);
+            probe.timer.async_wait(ArpTimeout { probe: probe.clone() });
+            probe.async_receive(ArpReceive {
+                probe: probe.clone(),
+                handler: handler,
+            })
+        }
+        Err(err) => ctx.do_dispatch(Failure::new(err, handler)),
+    })
+}
+
+#[test]
+fn test_arp_packet() {
+    let arp = ArpPacket::request(
+        LlAddr::new(1, 2, 3, 4, 5, 6),
+        IpAddrV4::new(10, 0, 0, 1),
+        IpAddrV4::new(10, 0, 0, 2),
+    );
+    let buf = arp.to_bytes();
+    assert_eq!(&buf[..8], &[0, 1, 8, 0, 6, 4, 0, 1]);
+    assert_eq!(ArpPacket::from_bytes(&buf), Some(arp));
+    assert!(!arp.is_reply());
+    assert_eq!(ArpPacket::from_bytes(&buf[..27]), None);
+}
@@ -0,0 +1,284 @@
+use ffi::{sockaddr, sockaddr_ll, socklen_t, SockAddr, AF_PACKET, SOCK_DGRAM, SOCK_RAW};
+use core::{Endpoint, Protocol};
+use dgram_socket::DgramSocket;
+use ip::LlAddr;
+
+use std::fmt;
+use std::mem;
+
+/// The protocol number that receives every protocol.
+pub const ETH_P_ALL: u16 = 0x0003;
+
+/// The protocol number of Internet Protocol version 4.
+pub const ETH_P_IP: u16 = 0x0800;
+
+/// The protocol number of Address Resolution Protocol.
+pub const ETH_P_ARP: u16 = 0x0806;
+
+/// The protocol number of IEEE 802.1Q VLAN tagged frames.
+pub const ETH_P_8021Q: u16 = 0x8100;
+
+/// The protocol number of Internet Protocol version 6.
+pub const ETH_P_IPV6: u16 = 0x86DD;
+
+/// The packet type of the frame addressed to this host.
+pub const PACKET_HOST: u8 = 0;
+
+/// The packet type of the link-layer broadcast frame.
+pub const PACKET_BROADCAST: u8 = 1;
+
+/// The packet type of the link-layer multicast frame.
+pub const PACKET_MULTICAST: u8 = 2;
+
+/// The packet type of the frame addressed to the other host, that is received in promiscuous mode.
+pub const PACKET_OTHERHOST: u8 = 3;
+
+/// The packet type of the frame sent by this host, that is looped back to the packet sockets.
+pub const PACKET_OUTGOING: u8 = 4;
+
+/// The link-layer packet protocol.
+///
+/// # Examples
+///
+/// ```
+/// use asyncio::Protocol;
+/// use asyncio::ll::{Packet, ETH_P_ARP};
+///
+/// let pro = Packet::dgram(ETH_P_ARP);
+/// assert_eq!(pro.ether_type(), ETH_P_ARP);
+/// ```
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub struct Packet {
+    socket_type: i32,
+    protocol: u16,
+}
+
+impl Packet {
+    /// Returns a packet protocol that the link-level header is removed (cooked mode).
+    pub fn dgram(protocol: u16) -> Packet {
+        Packet {
+            socket_type: SOCK_DGRAM,
+            protocol: protocol,
+        }
+    }
+
+    /// Returns a packet protocol that includes the link-level header.
+    pub fn raw(protocol: u16) -> Packet {
+        Packet {
+            socket_type: SOCK_RAW,
+            protocol: protocol,
+        }
+    }
+
+    /// Returns a ethernet protocol number in host byte order.
+    pub fn ether_type(&self) -> u16 {
+        self.protocol
+    }
+}
+
+impl Protocol for Packet {
+    type Endpoint = PacketEndpoint;
+
+    type Socket = PacketSocket;
+
+    fn family_type(&self) -> i32 {
+        AF_PACKET
+    }
+
+    fn socket_type(&self) -> i32 {
+        self.socket_type
+    }
+
+    fn protocol_type(&self) -> i32 {
+        self.protocol.to_be() as i32
+    }
+
+    unsafe fn uninitialized(&self) -> Self::Endpoint {
+        PacketEndpoint {
+            sll: SockAddr::new(AF_PACKET, mem::size_of::<sockaddr_ll>() as u8),
+            socket_type: self.socket_type,
+        }
+    }
+}
+
+impl fmt::Display for Packet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "PACKET")
+    }
+}
+
+/// The endpoint of link-layer packet protocol.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct PacketEndpoint {
+    sll: SockAddr<sockaddr_ll>,
+    socket_type: i32,
+}
+
+impl PacketEndpoint {
+    /// Returns a `PacketEndpoint` from interface index and link-layer address.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use asyncio::ip::LlAddr;
+    /// use asyncio::ll::{Packet, PacketEndpoint, ETH_P_ARP};
+    ///
+    /// let ep = PacketEndpoint::new(Packet::dgram(ETH_P_ARP), 1, LlAddr::new(0xff,0xff,0xff,0xff,0xff,0xff));
+    /// assert_eq!(ep.ifindex(), 1);
+    /// ```
+    pub fn new(pro: Packet, ifindex: u32, addr: LlAddr) -> PacketEndpoint {
+        let mut ep = unsafe { pro.uninitialized() };
+        ep.sll.sa.sll_protocol = pro.protocol.to_be();
+        ep.sll.sa.sll_ifindex = ifindex as i32;
+        ep.sll.sa.sll_hatype = 0;
+        ep.sll.sa.sll_pkttype = 0;
+        ep.sll.sa.sll_halen = 6;
+        ep.sll.sa.sll_addr = [0; 8];
+        ep.sll.sa.sll_addr[..6].copy_from_slice(addr.as_bytes());
+        ep
+    }
+
+    /// Returns a ARP hardware type of the interface, e.g. 1 for ethernet.
+    pub fn hardware_type(&self) -> u16 {
+        self.sll.sa.sll_hatype
+    }
+
+    /// Returns a interface index.
+    pub fn ifindex(&self) -> u32 {
+        self.sll.sa.sll_ifindex as u32
+    }
+
+    /// Returns a packet type of the received frame, e.g. `PACKET_HOST`.
+    pub fn packet_type(&self) -> u8 {
+        self.sll.sa.sll_pkttype
+    }
+
+    /// Returns a link-layer address.
+    pub fn addr(&self) -> LlAddr {
+        let mut bytes = [0; 6];
+        bytes.copy_from_slice(&self.sll.sa.sll_addr[..6]);
+        LlAddr::from(bytes)
+    }
+}
+
+impl Endpoint<Packet> for PacketEndpoint {
+    fn protocol(&self) -> Packet {
+        Packet {
+            socket_type: self.socket_type,
+            protocol: u16::from_be(self.sll.sa.sll_protocol),
+        }
+    }
+
+    fn as_ptr(&self) -> *const sockaddr {
+        &self.sll.sa as *const _ as *const _
+    }
+
+    fn as_mut_ptr(&mut self) -> *mut sockaddr {
+        &mut self.sll.sa as *mut _ as *mut _
+    }
+
+    fn capacity(&self) -> socklen_t {
+        self.sll.capacity() as socklen_t
+    }
+
+    fn size(&self) -> socklen_t {
+        self.sll.size() as socklen_t
+    }
+
+    unsafe fn resize(&mut self, size: socklen_t) {
+        debug_assert!(size <= self.capacity());
+        self.sll.resize(size as u8)
+    }
+}
+
+impl fmt::Debug for PacketEndpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}%{}", self.protocol(), self.addr(), self.ifindex())
+    }
+}
+
+/// The link-layer packet socket type.
+///
+/// The socket requires the `CAP_NET_RAW` capability.
+///
+/// # Examples
+///
+/// Receives the raw ethernet frames of every protocol:
+///
+/// ```
+/// use std::io;
+/// use std::sync::Arc;
+/// use asyncio::{IoContext, wrap};
+/// use asyncio::ll::{Packet, PacketEndpoint, PacketSocket, ETH_P_ALL, PACKET_OUTGOING};
+///
+/// fn on_receive(soc: Arc<PacketSocket>, res: io::Result<(usize, PacketEndpoint)>) {
+///     if let Ok((len, ep)) = res {
+///         if ep.packet_type() != PACKET_OUTGOING {
+///             println!("{} bytes from {:?}", len, ep);
+///         }
+///     }
+/// }
+///
+/// fn capture(ctx: &IoContext, buf: &mut [u8]) -> io::Result<()> {
+///     let soc = Arc::new(PacketSocket::new(ctx, Packet::raw(ETH_P_ALL))?);
+///     soc.async_receive_from(buf, 0, wrap(&soc, on_receive));
+///     Ok(())
+/// }
+/// ```
+pub type PacketSocket = DgramSocket<Packet>;
+
+mod arp;
+pub use self::arp::{ArpPacket, arp_probe};
+
+#[test]
+fn test_packet_endpoint() {
+    let mac = LlAddr::new(1, 2, 3, 4, 5, 6);
+    let ep = PacketEndpoint::new(Packet::dgram(ETH_P_ARP), 2, mac);
+    assert_eq!(ep.ifindex(), 2);
+    assert_eq!(ep.addr(), mac);
+    assert_eq!(ep.protocol(), Packet::dgram(ETH_P_ARP));
+    assert!(ep.protocol() != Packet::raw(ETH_P_ARP));
+    assert_eq!(ep.packet_type(), PACKET_HOST);
+    assert_eq!(ep.hardware_type(), 0);
+}
+
+#[test]
+fn test_packet_receive() {
+    use std::ffi::CString;
+    use std::io;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use ffi::if_nametoindex;
+    use core::IoContext;
+    use handler::wrap;
+    use ip::{IpAddrV4, IpProtocol, Udp, UdpEndpoint, UdpSocket};
+
+    static RECEIVED: AtomicUsize = AtomicUsize::new(0);
+
+    fn on_receive(soc: Arc<PacketSocket>, res: io::Result<(usize, PacketEndpoint)>) {
+        let (len, ep) = res.unwrap();
+        assert_eq!(ep.protocol(), Packet::raw(ETH_P_IP));
+        assert_eq!(ep.ifindex(), soc.local_endpoint().unwrap().ifindex());
+        RECEIVED.store(len, Ordering::SeqCst);
+    }
+
+    let ctx = &IoContext::new().unwrap();
+    // Receives nothing until bound to the loopback.
+    let soc = match PacketSocket::new(ctx, Packet::raw(0)) {
+        Ok(soc) => Arc::new(soc),
+        Err(_) => return, // requires the CAP_NET_RAW.
+    };
+    let lo = if_nametoindex(&CString::new("lo").unwrap()).unwrap();
+    soc.bind(&PacketEndpoint::new(Packet::raw(ETH_P_IP), lo, LlAddr::default()))
+        .unwrap();
+
+    let udp = UdpSocket::new(ctx, Udp::v4()).unwrap();
+    let ep = UdpEndpoint::new(IpAddrV4::loopback(), 9);
+    udp.send_to(&[0; 10], 0, &ep).unwrap();
+
+    let mut buf = [0; 1500];
+    soc.async_receive_from(&mut buf, 0, wrap(&soc, on_receive));
+    ctx.run();
+    // The ethernet, ipv4 and udp headers and the payload.
+    assert_eq!(RECEIVED.load(Ordering::SeqCst), 14 + 20 + 8 + 10);
+}
@@ -24,7 +24,7 @@ use std::mem;
 /// let cl = LocalDgramSocket::new(ctx, ep.protocol()).unwrap();
 /// cl.connect(&ep).unwrap();
 /// ```
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct LocalDgram;
 
 impl Protocol for LocalDgram {
@@ -76,9 +76,18 @@ impl Endpoint<LocalDgram> for LocalEndpoint<LocalDgram> {
     }
 }
 
+impl fmt::Debug for LocalDgram {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unix-dgram")
+    }
+}
+
 impl fmt::Debug for LocalEndpoint<LocalDgram> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{:?}:{:?})", self.protocol(), self.as_pathname())
+        match self.as_pathname() {
+            Some(path) => write!(f, "{:?}:{:?}", self.protocol(), path),
+            None => write!(f, "{:?}:(unnamed)", self.protocol()),
+        }
     }
 }
 
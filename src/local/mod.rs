@@ -1,5 +1,9 @@
 use ffi::{sockaddr_un, socketpair, SockAddr, AF_UNIX, NAME_TOO_LONG};
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use ffi::{SOL_SOCKET, SO_PEERCRED};
 use core::{IoContext, Protocol, Socket};
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use core::{SocketOption, GetSocketOption};
 
 use std::io;
 use std::mem;
@@ -122,6 +126,60 @@ where
     }
 }
 
+/// Socket option to get the credentials of the peer process, that were captured at the `connect`.
+///
+/// Implements the SOL_SOCKET/SO_PEERCRED socket option.
+///
+/// # Examples
+///
+/// ```
+/// use asyncio::IoContext;
+/// use asyncio::local::{LocalStream, PeerCred, connect_pair};
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let (tx, _) = connect_pair(ctx, LocalStream).unwrap();
+///
+/// let cred: PeerCred = tx.get_option().unwrap();
+/// assert_eq!(cred.pid(), std::process::id() as i32);
+/// ```
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[repr(C)]
+#[derive(Default, Clone, Debug, PartialEq, Eq)]
+pub struct PeerCred {
+    pid: i32,
+    uid: u32,
+    gid: u32,
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl PeerCred {
+    pub fn gid(&self) -> u32 {
+        self.gid
+    }
+
+    pub fn pid(&self) -> i32 {
+        self.pid
+    }
+
+    pub fn uid(&self) -> u32 {
+        self.uid
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<P> SocketOption<P> for PeerCred {
+    fn level(&self, _: &P) -> i32 {
+        SOL_SOCKET
+    }
+
+    fn name(&self, _: &P) -> i32 {
+        SO_PEERCRED
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<P> GetSocketOption<P> for PeerCred {}
+
 mod dgram;
 pub use self::dgram::*;
 
@@ -143,3 +201,13 @@ fn test_local_endpoint_limit() {
     assert!(LocalSeqPacketEndpoint::new(&s[..103]).is_ok());
     assert!(LocalSeqPacketEndpoint::new(&s[..108]).is_err());
 }
+
+#[test]
+fn test_local_endpoint_raw() {
+    use core::Endpoint;
+
+    let ep = LocalStreamEndpoint::new("foo").unwrap();
+    let (family, bytes) = ep.to_owned_sockaddr();
+    assert_eq!(family, AF_UNIX);
+    assert_eq!(&bytes[2..], b"foo\0");
+}
@@ -1,11 +1,21 @@
 use ffi::{sockaddr, socklen_t, AF_UNIX, SOCK_SEQPACKET};
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use ffi::Timeout;
 use core::{Endpoint, Protocol};
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use core::{IoContext, AsIoContext, ThreadIoContext, Cancel};
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use handler::{Handler, Complete};
 use socket_listener::SocketListener;
 use dgram_socket::DgramSocket;
 use local::LocalEndpoint;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use local::PeerCred;
 
 use std::fmt;
 use std::mem;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use std::io;
 
 /// The seq-packet protocol.
 ///
@@ -26,7 +36,7 @@ use std::mem;
 /// let cl = LocalSeqPacketSocket::new(ctx, ep.protocol()).unwrap();
 /// cl.connect(&ep).unwrap();
 /// ```
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct LocalSeqPacket;
 
 impl LocalEndpoint<LocalSeqPacket> {
@@ -84,9 +94,18 @@ impl Endpoint<LocalSeqPacket> for LocalEndpoint<LocalSeqPacket> {
     }
 }
 
+impl fmt::Debug for LocalSeqPacket {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unix-seqpacket")
+    }
+}
+
 impl fmt::Debug for LocalEndpoint<LocalSeqPacket> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{:?}:{:?})", self.protocol(), self.as_pathname())
+        match self.as_pathname() {
+            Some(path) => write!(f, "{:?}:{:?}", self.protocol(), path),
+            None => write!(f, "{:?}:(unnamed)", self.protocol()),
+        }
     }
 }
 
@@ -99,6 +118,107 @@ pub type LocalSeqPacketSocket = DgramSocket<LocalSeqPacket>;
 /// The seq-packet listener type.
 pub type LocalSeqPacketListener = SocketListener<LocalSeqPacket>;
 
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl SocketListener<LocalSeqPacket> {
+    /// Accepts a connection with the credentials of the peer process.
+    pub fn accept_with_credentials(
+        &self,
+    ) -> io::Result<(LocalSeqPacketSocket, LocalSeqPacketEndpoint, PeerCred)> {
+        let (soc, ep) = self.accept()?;
+        let cred = soc.get_option()?;
+        Ok((soc, ep, cred))
+    }
+
+    /// Asynchronously accepts a connection with the credentials of the peer process.
+    ///
+    /// The credentials are captured at the accept, that the connection is failed with the error
+    /// of the `getsockopt` if the peer cannot be identified.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io;
+    /// use std::sync::Arc;
+    /// use asyncio::{IoContext, wrap};
+    /// use asyncio::local::*;
+    ///
+    /// fn on_accept(
+    ///     _: Arc<LocalSeqPacketListener>,
+    ///     res: io::Result<(LocalSeqPacketSocket, LocalSeqPacketEndpoint, PeerCred)>,
+    /// ) {
+    ///     let (_, _, cred) = res.unwrap();
+    ///     assert_eq!(cred.pid(), std::process::id() as i32);
+    /// }
+    ///
+    /// let ctx = &IoContext::new().unwrap();
+    /// let ep = LocalSeqPacketEndpoint::new("/tmp/asyncio_doc_seq_packet.sock").unwrap();
+    /// let _ = std::fs::remove_file("/tmp/asyncio_doc_seq_packet.sock");
+    /// let sv = Arc::new(LocalSeqPacketListener::new(ctx, LocalSeqPacket).unwrap());
+    /// sv.bind(&ep).unwrap();
+    /// sv.listen().unwrap();
+    /// sv.async_accept_with_credentials(wrap(&sv, on_accept));
+    ///
+    /// let cl = LocalSeqPacketSocket::new(ctx, LocalSeqPacket).unwrap();
+    /// cl.connect(&ep).unwrap();
+    /// ctx.run();
+    /// ```
+    pub fn async_accept_with_credentials<F>(&self, handler: F) -> F::Output
+    where
+        F: Handler<(LocalSeqPacketSocket, LocalSeqPacketEndpoint, PeerCred), io::Error>,
+    {
+        handler.wrap(self.as_ctx(), move |_, handler| {
+            self.async_accept(AcceptCred { handler: handler })
+        })
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+struct AcceptCred<F> {
+    handler: F,
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<F> Handler<(LocalSeqPacketSocket, LocalSeqPacketEndpoint), io::Error> for AcceptCred<F>
+where
+    F: Complete<(LocalSeqPacketSocket, LocalSeqPacketEndpoint, PeerCred), io::Error>,
+{
+    type Output = ();
+
+    type WrappedHandler = Self;
+
+    fn wrap<W>(self, ctx: &IoContext, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx, self)
+    }
+
+    fn wrap_timeout<W>(self, ctx: &Cancel, _: &Timeout, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx.as_ctx(), self)
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<F> Complete<(LocalSeqPacketSocket, LocalSeqPacketEndpoint), io::Error> for AcceptCred<F>
+where
+    F: Complete<(LocalSeqPacketSocket, LocalSeqPacketEndpoint, PeerCred), io::Error>,
+{
+    fn success(self, this: &mut ThreadIoContext, res: (LocalSeqPacketSocket, LocalSeqPacketEndpoint)) {
+        let (soc, ep) = res;
+        match soc.get_option() {
+            Ok(cred) => self.handler.success(this, (soc, ep, cred)),
+            Err(err) => self.handler.failure(this, err),
+        }
+    }
+
+    fn failure(self, this: &mut ThreadIoContext, err: io::Error) {
+        self.handler.failure(this, err)
+    }
+}
+
 #[test]
 fn test_format() {
     use core::IoContext;
@@ -107,3 +227,39 @@ fn test_format() {
     println!("{:?}", LocalSeqPacket);
     println!("{:?}", LocalSeqPacketEndpoint::new("foo/bar").unwrap());
 }
+
+#[test]
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn test_accept_with_credentials() {
+    use core::IoContext;
+    use local::connect_pair;
+    use std::thread;
+    use std::process;
+
+    let ctx = &IoContext::new().unwrap();
+    let path = format!("/tmp/asyncio_test_seq_packet_{}.sock", process::id());
+    let ep = LocalSeqPacketEndpoint::new(&path).unwrap();
+    let sv = LocalSeqPacketListener::new(ctx, LocalSeqPacket).unwrap();
+    sv.bind(&ep).unwrap();
+    sv.listen().unwrap();
+
+    let thrd = {
+        let ctx = ctx.clone();
+        let ep = ep.clone();
+        thread::spawn(move || {
+            let cl = LocalSeqPacketSocket::new(&ctx, LocalSeqPacket).unwrap();
+            cl.connect(&ep).unwrap();
+            cl.send(b"hello", 0).unwrap();
+        })
+    };
+    let (soc, _, cred) = sv.accept_with_credentials().unwrap();
+    thrd.join().unwrap();
+    assert_eq!(cred.pid(), process::id() as i32);
+    assert_eq!(cred, soc.get_option().unwrap());
+    let mut buf = [0; 16];
+    assert_eq!(soc.receive(&mut buf, 0).unwrap(), 5);
+
+    let (_, rx) = connect_pair(ctx, LocalSeqPacket).unwrap();
+    assert_eq!(rx.get_option::<PeerCred>().unwrap(), cred);
+    let _ = ::std::fs::remove_file(&path);
+}
@@ -26,7 +26,7 @@ use std::mem;
 /// let cl = LocalStreamSocket::new(ctx, ep.protocol()).unwrap();
 /// cl.connect(&ep).unwrap();
 /// ```
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct LocalStream;
 
 impl Protocol for LocalStream {
@@ -78,9 +78,18 @@ impl Endpoint<LocalStream> for LocalEndpoint<LocalStream> {
     }
 }
 
+impl fmt::Debug for LocalStream {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unix-stream")
+    }
+}
+
 impl fmt::Debug for LocalEndpoint<LocalStream> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{:?}:{:?})", self.protocol(), self.as_pathname())
+        match self.as_pathname() {
+            Some(path) => write!(f, "{:?}:{:?}", self.protocol(), path),
+            None => write!(f, "{:?}:(unnamed)", self.protocol()),
+        }
     }
 }
 
@@ -116,6 +125,9 @@ fn test_format() {
     use core::IoContext;
 
     let _ctx = &IoContext::new().unwrap();
-    println!("{:?}", LocalStream);
-    println!("{:?}", LocalStreamEndpoint::new("foo/bar").unwrap());
+    assert_eq!(format!("{:?}", LocalStream), "unix-stream");
+    assert_eq!(
+        format!("{:?}", LocalStreamEndpoint::new("foo/bar").unwrap()),
+        "unix-stream:\"foo/bar\""
+    );
 }
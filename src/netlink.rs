@@ -0,0 +1,483 @@
+//! Provides the netlink sockets, that communicate with the kernel, e.g. the notifications of the
+//! links, the addresses and the routes.
+//!
+//! # Examples
+//!
+//! ```
+//! use asyncio::IoContext;
+//! use asyncio::netlink::{Netlink, NetlinkEndpoint, NetlinkSocket, AddMembership, RTNLGRP_LINK};
+//!
+//! let ctx = &IoContext::new().unwrap();
+//! let pro = Netlink::route();
+//! let soc = NetlinkSocket::new(ctx, pro).unwrap();
+//! soc.bind(&NetlinkEndpoint::new(pro, 0, 0)).unwrap();
+//! soc.set_option(AddMembership::new(RTNLGRP_LINK)).unwrap();
+//! ```
+
+use ffi::{sockaddr, sockaddr_nl, socklen_t, SockAddr, AF_NETLINK, SOCK_RAW, SOL_NETLINK,
+          NETLINK_ADD_MEMBERSHIP, NETLINK_DROP_MEMBERSHIP};
+use core::{Endpoint, Protocol, SocketOption, SetSocketOption};
+use dgram_socket::DgramSocket;
+
+use std::fmt;
+use std::mem;
+
+/// The netlink protocol number of the routing and the link updates.
+pub const NETLINK_ROUTE: i32 = 0;
+
+/// The netlink protocol number of the kernel uevents.
+pub const NETLINK_KOBJECT_UEVENT: i32 = 15;
+
+/// The multicast group of the link notifications of the `NETLINK_ROUTE`.
+pub const RTNLGRP_LINK: u32 = 1;
+
+/// The multicast group of the IP-v4 address notifications of the `NETLINK_ROUTE`.
+pub const RTNLGRP_IPV4_IFADDR: u32 = 5;
+
+/// The multicast group of the IP-v4 route notifications of the `NETLINK_ROUTE`.
+pub const RTNLGRP_IPV4_ROUTE: u32 = 7;
+
+/// The multicast group of the IP-v6 address notifications of the `NETLINK_ROUTE`.
+pub const RTNLGRP_IPV6_IFADDR: u32 = 9;
+
+/// The multicast group of the IP-v6 route notifications of the `NETLINK_ROUTE`.
+pub const RTNLGRP_IPV6_ROUTE: u32 = 11;
+
+/// The message type that is ignored.
+pub const NLMSG_NOOP: u16 = 1;
+
+/// The message type of the error or the acknowledgment.
+pub const NLMSG_ERROR: u16 = 2;
+
+/// The message type of the end of the multipart messages.
+pub const NLMSG_DONE: u16 = 3;
+
+/// The message types of the `NETLINK_ROUTE`.
+pub const RTM_NEWLINK: u16 = 16;
+pub const RTM_DELLINK: u16 = 17;
+pub const RTM_GETLINK: u16 = 18;
+pub const RTM_NEWADDR: u16 = 20;
+pub const RTM_DELADDR: u16 = 21;
+pub const RTM_GETADDR: u16 = 22;
+pub const RTM_NEWROUTE: u16 = 24;
+pub const RTM_DELROUTE: u16 = 25;
+pub const RTM_GETROUTE: u16 = 26;
+
+/// The message flag of the request.
+pub const NLM_F_REQUEST: u16 = 0x1;
+
+/// The message flag of the multipart message, that is terminated by the `NLMSG_DONE`.
+pub const NLM_F_MULTI: u16 = 0x2;
+
+/// The message flag to request the acknowledgment.
+pub const NLM_F_ACK: u16 = 0x4;
+
+/// The message flag to request all the entries.
+pub const NLM_F_DUMP: u16 = 0x300;
+
+const NLMSG_HDRLEN: usize = 16;
+
+fn nlmsg_align(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+/// The netlink protocol.
+///
+/// # Examples
+///
+/// ```
+/// use asyncio::Protocol;
+/// use asyncio::netlink::{Netlink, NETLINK_ROUTE};
+///
+/// assert_eq!(Netlink::route().protocol_type(), NETLINK_ROUTE);
+/// ```
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Netlink {
+    protocol: i32,
+}
+
+impl Netlink {
+    /// Returns a netlink protocol of the protocol number, e.g. `NETLINK_ROUTE`.
+    pub fn new(protocol: i32) -> Netlink {
+        Netlink { protocol: protocol }
+    }
+
+    /// Returns a netlink protocol of the `NETLINK_ROUTE`.
+    pub fn route() -> Netlink {
+        Netlink::new(NETLINK_ROUTE)
+    }
+}
+
+impl Protocol for Netlink {
+    type Endpoint = NetlinkEndpoint;
+
+    type Socket = NetlinkSocket;
+
+    fn family_type(&self) -> i32 {
+        AF_NETLINK
+    }
+
+    fn socket_type(&self) -> i32 {
+        SOCK_RAW
+    }
+
+    fn protocol_type(&self) -> i32 {
+        self.protocol
+    }
+
+    unsafe fn uninitialized(&self) -> Self::Endpoint {
+        NetlinkEndpoint {
+            snl: SockAddr::new(AF_NETLINK, mem::size_of::<sockaddr_nl>() as u8),
+            protocol: self.protocol,
+        }
+    }
+}
+
+impl fmt::Display for Netlink {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "NETLINK")
+    }
+}
+
+/// The endpoint of the netlink protocol.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct NetlinkEndpoint {
+    snl: SockAddr<sockaddr_nl>,
+    protocol: i32,
+}
+
+impl NetlinkEndpoint {
+    /// Returns a `NetlinkEndpoint` from the port id and the bitmask of the multicast groups.
+    ///
+    /// The port id 0 is the kernel as the destination, and is assigned by the kernel as the
+    /// source. The group `n` is the bit `1 << (n - 1)`, that is up to the group 32.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use asyncio::netlink::{Netlink, NetlinkEndpoint};
+    ///
+    /// let ep = NetlinkEndpoint::new(Netlink::route(), 0, 1);
+    /// assert_eq!(ep.pid(), 0);
+    /// assert_eq!(ep.groups(), 1);
+    /// ```
+    pub fn new(pro: Netlink, pid: u32, groups: u32) -> NetlinkEndpoint {
+        let mut ep = unsafe { pro.uninitialized() };
+        ep.snl.sa = unsafe { mem::zeroed() };
+        ep.snl.sa.nl_family = AF_NETLINK as u16;
+        ep.snl.sa.nl_pid = pid;
+        ep.snl.sa.nl_groups = groups;
+        ep
+    }
+
+    /// Returns a bitmask of the multicast groups.
+    pub fn groups(&self) -> u32 {
+        self.snl.sa.nl_groups
+    }
+
+    /// Returns a port id.
+    pub fn pid(&self) -> u32 {
+        self.snl.sa.nl_pid
+    }
+}
+
+impl Endpoint<Netlink> for NetlinkEndpoint {
+    fn protocol(&self) -> Netlink {
+        Netlink::new(self.protocol)
+    }
+
+    fn as_ptr(&self) -> *const sockaddr {
+        &self.snl.sa as *const _ as *const _
+    }
+
+    fn as_mut_ptr(&mut self) -> *mut sockaddr {
+        &mut self.snl.sa as *mut _ as *mut _
+    }
+
+    fn capacity(&self) -> socklen_t {
+        self.snl.capacity() as socklen_t
+    }
+
+    fn size(&self) -> socklen_t {
+        self.snl.size() as socklen_t
+    }
+
+    unsafe fn resize(&mut self, size: socklen_t) {
+        debug_assert!(size <= self.capacity());
+        self.snl.resize(size as u8)
+    }
+}
+
+impl fmt::Debug for NetlinkEndpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}/{:#x}", self.protocol(), self.pid(), self.groups())
+    }
+}
+
+/// The netlink socket type.
+pub type NetlinkSocket = DgramSocket<Netlink>;
+
+/// Socket option to join the multicast group of the netlink.
+///
+/// Implements the SOL_NETLINK/NETLINK_ADD_MEMBERSHIP socket option, that takes the group number
+/// e.g. `RTNLGRP_LINK`, and is not limited to the group 32 unlike the `NetlinkEndpoint`.
+#[derive(Clone)]
+pub struct AddMembership(u32);
+
+impl AddMembership {
+    pub fn new(group: u32) -> AddMembership {
+        AddMembership(group)
+    }
+
+    pub fn get(&self) -> u32 {
+        self.0
+    }
+}
+
+impl SocketOption<Netlink> for AddMembership {
+    fn level(&self, _: &Netlink) -> i32 {
+        SOL_NETLINK
+    }
+
+    fn name(&self, _: &Netlink) -> i32 {
+        NETLINK_ADD_MEMBERSHIP
+    }
+}
+
+impl SetSocketOption<Netlink> for AddMembership {}
+
+/// Socket option to leave the multicast group of the netlink.
+///
+/// Implements the SOL_NETLINK/NETLINK_DROP_MEMBERSHIP socket option.
+#[derive(Clone)]
+pub struct DropMembership(u32);
+
+impl DropMembership {
+    pub fn new(group: u32) -> DropMembership {
+        DropMembership(group)
+    }
+
+    pub fn get(&self) -> u32 {
+        self.0
+    }
+}
+
+impl SocketOption<Netlink> for DropMembership {
+    fn level(&self, _: &Netlink) -> i32 {
+        SOL_NETLINK
+    }
+
+    fn name(&self, _: &Netlink) -> i32 {
+        NETLINK_DROP_MEMBERSHIP
+    }
+}
+
+impl SetSocketOption<Netlink> for DropMembership {}
+
+/// The netlink message, that is the header and the payload.
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub struct NetlinkMessage<'a> {
+    /// The message type, e.g. `RTM_NEWLINK`.
+    pub ty: u16,
+
+    /// The message flags, e.g. `NLM_F_REQUEST`.
+    pub flags: u16,
+
+    /// The sequence number.
+    pub seq: u32,
+
+    /// The port id of the sender.
+    pub pid: u32,
+
+    /// The payload following the header.
+    pub payload: &'a [u8],
+}
+
+impl<'a> NetlinkMessage<'a> {
+    /// Returns a wire-format bytes of the message.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use asyncio::netlink::{NetlinkMessage, RTM_GETLINK, NLM_F_REQUEST, NLM_F_DUMP};
+    ///
+    /// let msg = NetlinkMessage {
+    ///     ty: RTM_GETLINK,
+    ///     flags: NLM_F_REQUEST | NLM_F_DUMP,
+    ///     seq: 1,
+    ///     pid: 0,
+    ///     payload: &[0; 16], // ifinfomsg
+    /// };
+    /// assert_eq!(msg.to_bytes().len(), 32);
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let len = NLMSG_HDRLEN + self.payload.len();
+        let mut buf = Vec::with_capacity(nlmsg_align(len));
+        buf.extend_from_slice(&(len as u32).to_ne_bytes());
+        buf.extend_from_slice(&self.ty.to_ne_bytes());
+        buf.extend_from_slice(&self.flags.to_ne_bytes());
+        buf.extend_from_slice(&self.seq.to_ne_bytes());
+        buf.extend_from_slice(&self.pid.to_ne_bytes());
+        buf.extend_from_slice(self.payload);
+        buf.resize(nlmsg_align(len), 0);
+        buf
+    }
+
+    /// Returns the error number of the `NLMSG_ERROR` message, that is 0 for the acknowledgment.
+    pub fn error(&self) -> Option<i32> {
+        if self.ty != NLMSG_ERROR || self.payload.len() < 4 {
+            return None;
+        }
+        let mut bytes = [0; 4];
+        bytes.copy_from_slice(&self.payload[..4]);
+        Some(-i32::from_ne_bytes(bytes))
+    }
+}
+
+/// An iterator over the netlink messages in the received datagram.
+///
+/// The iteration stops at the malformed message.
+#[derive(Clone, Debug)]
+pub struct NetlinkMessages<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> NetlinkMessages<'a> {
+    pub fn new(buf: &'a [u8]) -> NetlinkMessages<'a> {
+        NetlinkMessages { buf: buf }
+    }
+}
+
+impl<'a> Iterator for NetlinkMessages<'a> {
+    type Item = NetlinkMessage<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let buf = self.buf;
+        if buf.len() < NLMSG_HDRLEN {
+            return None;
+        }
+        let u32_at = |i: usize| {
+            let mut bytes = [0; 4];
+            bytes.copy_from_slice(&buf[i..i + 4]);
+            u32::from_ne_bytes(bytes)
+        };
+        let u16_at = |i: usize| u16::from_ne_bytes([buf[i], buf[i + 1]]);
+        let len = u32_at(0) as usize;
+        if len < NLMSG_HDRLEN || len > buf.len() {
+            self.buf = &[];
+            return None;
+        }
+        let msg = NetlinkMessage {
+            ty: u16_at(4),
+            flags: u16_at(6),
+            seq: u32_at(8),
+            pid: u32_at(12),
+            payload: &buf[NLMSG_HDRLEN..len],
+        };
+        self.buf = &buf[::std::cmp::min(nlmsg_align(len), buf.len())..];
+        Some(msg)
+    }
+}
+
+#[test]
+fn test_netlink_endpoint() {
+    let ep = NetlinkEndpoint::new(Netlink::route(), 1234, 0x11);
+    assert_eq!(ep.pid(), 1234);
+    assert_eq!(ep.groups(), 0x11);
+    assert_eq!(ep.protocol(), Netlink::route());
+    assert!(ep != NetlinkEndpoint::new(Netlink::new(NETLINK_KOBJECT_UEVENT), 1234, 0x11));
+}
+
+#[test]
+fn test_netlink_messages() {
+    let mut buf = NetlinkMessage {
+        ty: RTM_NEWLINK,
+        flags: NLM_F_MULTI,
+        seq: 7,
+        pid: 100,
+        payload: &[1, 2, 3],
+    }.to_bytes();
+    assert_eq!(buf.len(), 20);
+    buf.extend(
+        NetlinkMessage {
+            ty: NLMSG_ERROR,
+            flags: 0,
+            seq: 8,
+            pid: 100,
+            payload: &(-13i32).to_ne_bytes(),
+        }.to_bytes(),
+    );
+    buf.extend_from_slice(&[0xff; 8]);
+
+    let mut it = NetlinkMessages::new(&buf);
+    let msg = it.next().unwrap();
+    assert_eq!((msg.ty, msg.flags, msg.seq, msg.pid), (RTM_NEWLINK, NLM_F_MULTI, 7, 100));
+    assert_eq!(msg.payload, &[1, 2, 3]);
+    assert_eq!(msg.error(), None);
+    assert_eq!(it.next().unwrap().error(), Some(13));
+    assert!(it.next().is_none());
+}
+
+#[test]
+fn test_netlink_dump() {
+    use std::io;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use core::IoContext;
+    use strand::Strand;
+
+    static LINKS: AtomicUsize = AtomicUsize::new(0);
+
+    struct Dump {
+        soc: NetlinkSocket,
+        buf: Vec<u8>,
+    }
+
+    fn on_start(dump: Strand<Dump>) {
+        let buf = &mut dump.get().buf;
+        dump.soc.async_receive_from(buf, 0, dump.wrap(on_receive));
+    }
+
+    fn on_receive(dump: Strand<Dump>, res: io::Result<(usize, NetlinkEndpoint)>) {
+        let (len, ep) = res.unwrap();
+        assert_eq!(ep.pid(), 0);
+        for msg in NetlinkMessages::new(&dump.buf[..len]) {
+            match msg.ty {
+                RTM_NEWLINK => {
+                    LINKS.fetch_add(1, Ordering::SeqCst);
+                }
+                NLMSG_DONE => return,
+                ty => panic!("{}", ty),
+            }
+        }
+        on_start(dump)
+    }
+
+    let ctx = &IoContext::new().unwrap();
+    let pro = Netlink::route();
+    let soc = NetlinkSocket::new(ctx, pro).unwrap();
+    soc.bind(&NetlinkEndpoint::new(pro, 0, 0)).unwrap();
+    soc.set_option(AddMembership::new(RTNLGRP_LINK)).unwrap();
+    soc.set_option(DropMembership::new(RTNLGRP_LINK)).unwrap();
+    assert!(soc.local_endpoint().unwrap().pid() != 0);
+
+    let req = NetlinkMessage {
+        ty: RTM_GETLINK,
+        flags: NLM_F_REQUEST | NLM_F_DUMP,
+        seq: 1,
+        pid: 0,
+        payload: &[0; 16],
+    };
+    soc.send_to(&req.to_bytes(), 0, &NetlinkEndpoint::new(pro, 0, 0))
+        .unwrap();
+
+    Strand::new(
+        ctx,
+        Dump {
+            soc: soc,
+            buf: vec![0; 32768],
+        },
+    ).dispatch(on_start);
+    ctx.run();
+    // The loopback at least.
+    assert!(LINKS.load(Ordering::SeqCst) >= 1);
+}
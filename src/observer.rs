@@ -0,0 +1,174 @@
+use ffi::{RawFd, AsRawFd};
+use core::{IoContext, Protocol, Endpoint};
+
+use std::io;
+use std::fmt;
+use std::slice;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use libc::{sockaddr, AF_INET, AF_INET6, AF_UNIX};
+
+/// Provides a socket address of the endpoint notified to the `SocketObserver`.
+#[derive(Clone, Copy)]
+pub struct EndpointInfo<'a> {
+    sa: &'a [u8],
+    protocol: i32,
+}
+
+impl<'a> EndpointInfo<'a> {
+    fn new<P, E>(ep: &'a E) -> Self
+    where
+        P: Protocol,
+        E: Endpoint<P>,
+    {
+        EndpointInfo {
+            sa: unsafe { slice::from_raw_parts(ep.as_ptr() as *const u8, ep.size() as usize) },
+            protocol: ep.protocol().protocol_type(),
+        }
+    }
+
+    /// Returns a address family.
+    pub fn family(&self) -> i32 {
+        if self.sa.len() < 2 {
+            return 0;
+        }
+        unsafe { &*(self.sa.as_ptr() as *const sockaddr) }.sa_family as i32
+    }
+
+    /// Returns a value suitable for passing as the protocol argument.
+    pub fn protocol_type(&self) -> i32 {
+        self.protocol
+    }
+
+    /// Returns a raw bytes of the socket address.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.sa
+    }
+
+    fn port(&self) -> u16 {
+        (self.sa[2] as u16) << 8 | self.sa[3] as u16
+    }
+}
+
+impl<'a> fmt::Display for EndpointInfo<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.family() {
+            AF_INET if self.sa.len() >= 8 => {
+                let addr = Ipv4Addr::new(self.sa[4], self.sa[5], self.sa[6], self.sa[7]);
+                write!(f, "{}:{}", addr, self.port())
+            }
+            AF_INET6 if self.sa.len() >= 24 => {
+                let mut bytes = [0; 16];
+                bytes.copy_from_slice(&self.sa[8..24]);
+                write!(f, "[{}]:{}", Ipv6Addr::from(bytes), self.port())
+            }
+            AF_UNIX => {
+                let path = &self.sa[2..];
+                let len = path.iter().position(|&ch| ch == 0).unwrap_or(path.len());
+                write!(f, "{}", String::from_utf8_lossy(&path[..len]))
+            }
+            family => write!(f, "family={}", family),
+        }
+    }
+}
+
+impl<'a> fmt::Debug for EndpointInfo<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "EndpointInfo({})", self)
+    }
+}
+
+/// Provides a callback for the socket lifecycle events in the `IoContext`.
+///
+/// All methods are invoked in the thread which performed the event, and the default implementations do nothing.
+///
+/// # Examples
+///
+/// ```
+/// use std::io;
+/// use asyncio::{IoContext, SocketObserver, EndpointInfo};
+/// use asyncio::ip::{IpProtocol, Tcp, TcpSocket};
+/// use std::os::unix::io::RawFd;
+///
+/// struct Logger;
+///
+/// impl SocketObserver for Logger {
+///   fn on_connect(&self, fd: RawFd, ep: EndpointInfo) {
+///     println!("fd {} connected to {}", fd, ep);
+///   }
+///
+///   fn on_error(&self, fd: RawFd, err: &io::Error) {
+///     println!("fd {} failed {}", fd, err);
+///   }
+/// }
+///
+/// let ctx = &IoContext::new().unwrap();
+/// ctx.set_socket_observer(Logger);
+/// let soc = TcpSocket::new(ctx, Tcp::v4()).unwrap();
+/// ```
+pub trait SocketObserver: Send + Sync + 'static {
+    /// Invoked when the socket has been opened or adopted.
+    fn on_open(&self, fd: RawFd) {
+        let _ = fd;
+    }
+
+    /// Invoked when the socket has been connected to the remote endpoint.
+    fn on_connect(&self, fd: RawFd, ep: EndpointInfo) {
+        let _ = (fd, ep);
+    }
+
+    /// Invoked when the listener has accepted a new socket from the remote endpoint.
+    fn on_accept(&self, listener: RawFd, fd: RawFd, ep: EndpointInfo) {
+        let _ = (listener, fd, ep);
+    }
+
+    /// Invoked when the socket has been closed.
+    fn on_close(&self, fd: RawFd) {
+        let _ = fd;
+    }
+
+    /// Invoked when the connect or accept operation has been failed.
+    fn on_error(&self, fd: RawFd, err: &io::Error) {
+        let _ = (fd, err);
+    }
+}
+
+pub fn notify_open(ctx: &IoContext, fd: RawFd) {
+    if let Some(obs) = ctx.socket_observer() {
+        obs.on_open(fd)
+    }
+}
+
+pub fn notify_close(ctx: &IoContext, fd: RawFd) {
+    if let Some(obs) = ctx.socket_observer() {
+        obs.on_close(fd)
+    }
+}
+
+pub fn notify_connect<P, S>(ctx: &IoContext, soc: &S, ep: &P::Endpoint)
+where
+    P: Protocol,
+    S: AsRawFd,
+{
+    if let Some(obs) = ctx.socket_observer() {
+        obs.on_connect(soc.as_raw_fd(), EndpointInfo::new(ep))
+    }
+}
+
+pub fn notify_accept<P, S>(ctx: &IoContext, soc: &S, acc: &P::Socket, ep: &P::Endpoint)
+where
+    P: Protocol,
+    S: AsRawFd,
+{
+    if let Some(obs) = ctx.socket_observer() {
+        obs.on_accept(soc.as_raw_fd(), acc.as_raw_fd(), EndpointInfo::new(ep))
+    }
+}
+
+pub fn notify_error<S>(ctx: &IoContext, soc: &S, err: &io::Error)
+where
+    S: AsRawFd,
+{
+    if let Some(obs) = ctx.socket_observer() {
+        obs.on_error(soc.as_raw_fd(), err)
+    }
+}
@@ -0,0 +1,232 @@
+use ffi::Timeout;
+use core::{AsIoContext, IoContext, Protocol, ThreadIoContext, Cancel};
+use handler::{Handler, Complete};
+use dgram_socket::DgramSocket;
+use SteadyTimer;
+
+use std::io;
+use std::slice;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+struct Bucket {
+    rate: usize,
+    burst: usize,
+    tokens: f64,
+    last: Instant,
+}
+
+/// Provides a token bucket for pacing the sending.
+///
+/// The bucket is refilled at `rate` bytes per second up to `burst` bytes.
+/// A send that exceeds the tokens is delayed, and the following sends are queued after it.
+pub struct Pacer {
+    bucket: Mutex<Bucket>,
+}
+
+impl Pacer {
+    /// Returns a new pacer, the `rate` of zero means unlimited.
+    pub fn new(rate: usize, burst: usize) -> Pacer {
+        Pacer {
+            bucket: Mutex::new(Bucket {
+                rate: rate,
+                burst: burst,
+                tokens: burst as f64,
+                last: Instant::now(),
+            }),
+        }
+    }
+
+    pub fn burst(&self) -> usize {
+        self.bucket.lock().unwrap().burst
+    }
+
+    pub fn rate(&self) -> usize {
+        self.bucket.lock().unwrap().rate
+    }
+
+    pub fn set_burst(&self, burst: usize) {
+        let mut bucket = self.bucket.lock().unwrap();
+        bucket.burst = burst;
+        if bucket.tokens > burst as f64 {
+            bucket.tokens = burst as f64;
+        }
+    }
+
+    pub fn set_rate(&self, rate: usize) {
+        let mut bucket = self.bucket.lock().unwrap();
+        Self::refill(&mut bucket, Instant::now());
+        bucket.rate = rate;
+    }
+
+    /// Takes `len` tokens from the bucket and returns a delay until the tokens are available.
+    pub fn reserve(&self, len: usize) -> Duration {
+        let mut bucket = self.bucket.lock().unwrap();
+        if bucket.rate == 0 {
+            return Duration::new(0, 0);
+        }
+        Self::refill(&mut bucket, Instant::now());
+        bucket.tokens -= len as f64;
+        if bucket.tokens >= 0.0 {
+            return Duration::new(0, 0);
+        }
+        let nanos = (-bucket.tokens * 1_000_000_000.0 / bucket.rate as f64) as u64;
+        Duration::new(nanos / 1_000_000_000, (nanos % 1_000_000_000) as u32)
+    }
+
+    fn refill(bucket: &mut Bucket, now: Instant) {
+        if now > bucket.last {
+            let elapsed = now.duration_since(bucket.last);
+            let secs = elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 / 1_000_000_000.0;
+            let tokens = bucket.tokens + secs * bucket.rate as f64;
+            bucket.tokens = if tokens > bucket.burst as f64 {
+                bucket.burst as f64
+            } else {
+                tokens
+            };
+            bucket.last = now;
+        }
+    }
+}
+
+struct Resume<F>(F);
+
+impl<F> Handler<usize, io::Error> for Resume<F>
+where
+    F: Complete<usize, io::Error>,
+{
+    type Output = ();
+
+    type WrappedHandler = F;
+
+    fn wrap<W>(self, ctx: &IoContext, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx, self.0)
+    }
+
+    fn wrap_timeout<W>(self, ctx: &Cancel, _: &Timeout, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx.as_ctx(), self.0)
+    }
+}
+
+struct AsyncSendPaced<P, F> {
+    soc: *const DgramSocket<P>,
+    buf: *const u8,
+    len: usize,
+    flags: i32,
+    _timer: Box<SteadyTimer>,
+    handler: F,
+}
+
+unsafe impl<P, F> Send for AsyncSendPaced<P, F> {}
+
+impl<P, F> Handler<(), io::Error> for AsyncSendPaced<P, F>
+where
+    P: Protocol,
+    F: Complete<usize, io::Error>,
+{
+    type Output = ();
+
+    type WrappedHandler = Self;
+
+    fn wrap<W>(self, ctx: &IoContext, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx, self)
+    }
+
+    fn wrap_timeout<W>(self, ctx: &Cancel, _: &Timeout, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx.as_ctx(), self)
+    }
+}
+
+impl<P, F> Complete<(), io::Error> for AsyncSendPaced<P, F>
+where
+    P: Protocol,
+    F: Complete<usize, io::Error>,
+{
+    fn success(self, this: &mut ThreadIoContext, _: ()) {
+        let soc = unsafe { &*self.soc };
+        let buf = unsafe { slice::from_raw_parts(self.buf, self.len) };
+        this.decrease_outstanding_work();
+        soc.async_send(buf, self.flags, Resume(self.handler))
+    }
+
+    fn failure(self, this: &mut ThreadIoContext, err: io::Error) {
+        self.handler.failure(this, err)
+    }
+}
+
+impl<P> DgramSocket<P>
+where
+    P: Protocol,
+{
+    /// Sends the buffer after waiting for the tokens of the `Pacer`.
+    ///
+    /// This is a software fallback of the `MaxPacingRate` socket option.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io;
+    /// use std::sync::Arc;
+    /// use asyncio::{IoContext, Pacer, wrap};
+    /// use asyncio::ip::{IpAddrV4, UdpEndpoint, UdpSocket};
+    ///
+    /// fn on_send(_: Arc<UdpSocket>, res: io::Result<usize>) {
+    /// }
+    ///
+    /// let ctx = &IoContext::new().unwrap();
+    /// let ep = UdpEndpoint::new(IpAddrV4::loopback(), 12345);
+    /// let soc = Arc::new(UdpSocket::new(ctx, ep.protocol()).unwrap());
+    /// soc.connect(&ep).unwrap();
+    ///
+    /// let pacer = Pacer::new(125_000, 1500);
+    /// soc.async_send_paced(&pacer, b"frame", 0, wrap(&soc, on_send));
+    /// ctx.run();
+    /// ```
+    pub fn async_send_paced<F>(&self, pacer: &Pacer, buf: &[u8], flags: i32, handler: F) -> F::Output
+    where
+        F: Handler<usize, io::Error>,
+    {
+        let delay = pacer.reserve(buf.len());
+        if delay == Duration::new(0, 0) {
+            return self.async_send(buf, flags, handler);
+        }
+        handler.wrap(self.as_ctx(), move |ctx, handler| {
+            let timer = Box::new(SteadyTimer::new(ctx));
+            timer.expires_from_now(delay);
+            let timer_ptr = &*timer as *const SteadyTimer;
+            unsafe { &*timer_ptr }.async_wait(AsyncSendPaced {
+                soc: self,
+                buf: buf.as_ptr(),
+                len: buf.len(),
+                flags: flags,
+                _timer: timer,
+                handler: handler,
+            })
+        })
+    }
+}
+
+#[test]
+fn test_pacer() {
+    let pacer = Pacer::new(1000, 100);
+    assert_eq!(pacer.reserve(100), Duration::new(0, 0));
+    let delay = pacer.reserve(100);
+    assert!(delay > Duration::from_millis(90) && delay <= Duration::from_millis(100));
+    let delay = pacer.reserve(100);
+    assert!(delay > Duration::from_millis(190) && delay <= Duration::from_millis(200));
+
+    let pacer = Pacer::new(0, 0);
+    assert_eq!(pacer.reserve(1_000_000), Duration::new(0, 0));
+}
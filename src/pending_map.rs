@@ -0,0 +1,271 @@
+use ffi::{Timeout, TIMED_OUT, OPERATION_CANCELED};
+use core::{AsIoContext, IoContext, ThreadIoContext, Cancel};
+use handler::{Handler, BoxHandler, Complete, Success, Failure};
+use SteadyTimer;
+
+use std::io;
+use std::mem;
+use std::hash::Hash;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+struct Entry<R> {
+    expiry: Instant,
+    handler: BoxHandler<R, io::Error>,
+}
+
+struct PendingState<K, R> {
+    map: HashMap<K, Entry<R>>,
+    armed: Option<Instant>,
+}
+
+struct PendingMapImpl<K, R> {
+    timer: SteadyTimer,
+    state: Mutex<PendingState<K, R>>,
+    arming: Mutex<()>,
+}
+
+fn rearm<K, R>(pm: &Arc<PendingMapImpl<K, R>>)
+where
+    K: Eq + Hash + Send + 'static,
+    R: Send + 'static,
+{
+    let _arming = pm.arming.lock().unwrap();
+    let expiry = {
+        let mut state = pm.state.lock().unwrap();
+        let next = state.map.values().map(|entry| entry.expiry).min();
+        match (next, state.armed) {
+            (Some(next), Some(armed)) if armed <= next => return,
+            (Some(next), _) => {
+                state.armed = Some(next);
+                next
+            }
+            (None, _) => return,
+        }
+    };
+    // The previous waiting is canceled in the timer, that does not hold the lock of the state.
+    pm.timer.expires_at(expiry);
+    pm.timer.async_wait(PendingTimeout { pm: pm.clone() });
+}
+
+fn disarm<K, R>(pm: &PendingMapImpl<K, R>) {
+    // Expires immediately instead of the cancel, that the waiting not yet registered to the timer
+    // queue is also completed.
+    pm.timer.expires_at(Instant::now());
+}
+
+struct PendingTimeout<K, R> {
+    pm: Arc<PendingMapImpl<K, R>>,
+}
+
+impl<K, R> Handler<(), io::Error> for PendingTimeout<K, R>
+where
+    K: Eq + Hash + Send + 'static,
+    R: Send + 'static,
+{
+    type Output = ();
+
+    type WrappedHandler = Self;
+
+    fn wrap<W>(self, ctx: &IoContext, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx, self)
+    }
+
+    fn wrap_timeout<W>(self, ctx: &Cancel, _: &Timeout, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx.as_ctx(), self)
+    }
+}
+
+impl<K, R> Complete<(), io::Error> for PendingTimeout<K, R>
+where
+    K: Eq + Hash + Send + 'static,
+    R: Send + 'static,
+{
+    fn success(self, this: &mut ThreadIoContext, _: ()) {
+        this.decrease_outstanding_work();
+        let now = Instant::now();
+        let expired = {
+            let mut state = self.pm.state.lock().unwrap();
+            state.armed = None;
+            let (expired, pending): (Vec<_>, Vec<_>) = mem::replace(&mut state.map, HashMap::new())
+                .into_iter()
+                .partition(|&(_, ref entry)| entry.expiry <= now);
+            state.map = pending.into_iter().collect();
+            expired
+        };
+        for (_, entry) in expired {
+            this.as_ctx().do_dispatch(Failure::new(TIMED_OUT, entry.handler));
+        }
+        rearm(&self.pm)
+    }
+
+    fn failure(self, this: &mut ThreadIoContext, _: io::Error) {
+        // The waiting was canceled by the rearm or disarm.
+        this.decrease_outstanding_work();
+    }
+}
+
+/// Provides a map of the handlers waiting for the response of each request.
+///
+/// Each handler is completed by `complete` or `fail` with the request key,
+/// and failed with `ETIMEDOUT` error if the response is not arrived until the timeout.
+///
+/// # Examples
+///
+/// ```
+/// use std::io;
+/// use std::sync::Arc;
+/// use std::time::Duration;
+/// use asyncio::{IoContext, PendingMap, wrap};
+///
+/// fn on_response(_: Arc<PendingMap<u32, String>>, res: io::Result<String>) {
+///   println!("{:?}", res);
+/// }
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let pm = Arc::new(PendingMap::new(ctx));
+/// pm.insert(1, Duration::new(5, 0), wrap(&pm, on_response));
+/// pm.insert(2, Duration::new(5, 0), wrap(&pm, on_response));
+/// assert_eq!(pm.len(), 2);
+///
+/// pm.complete(&1, "pong".to_string());
+/// pm.cancel_all();  // e.g. the connection is lost.
+/// ctx.run();
+/// assert!(pm.is_empty());
+/// ```
+pub struct PendingMap<K, R> {
+    inner: Arc<PendingMapImpl<K, R>>,
+}
+
+impl<K, R> PendingMap<K, R>
+where
+    K: Eq + Hash + Send + 'static,
+    R: Send + 'static,
+{
+    pub fn new(ctx: &IoContext) -> Self {
+        PendingMap {
+            inner: Arc::new(PendingMapImpl {
+                timer: SteadyTimer::new(ctx),
+                state: Mutex::new(PendingState {
+                    map: HashMap::new(),
+                    armed: None,
+                }),
+                arming: Mutex::default(),
+            }),
+        }
+    }
+
+    /// Cancels all handlers with the `ECANCELED` error.
+    pub fn cancel_all(&self) {
+        let entries = {
+            let _arming = self.inner.arming.lock().unwrap();
+            let mut state = self.inner.state.lock().unwrap();
+            state.armed = None;
+            disarm(&self.inner);
+            mem::replace(&mut state.map, HashMap::new())
+        };
+        for (_, entry) in entries {
+            self.as_ctx().do_dispatch(Failure::new(OPERATION_CANCELED, entry.handler));
+        }
+    }
+
+    /// Completes the handler of the key with the response, returns false if the key is not found.
+    pub fn complete(&self, key: &K, res: R) -> bool {
+        match self.take(key) {
+            Some(entry) => {
+                self.as_ctx().do_dispatch(Success::new(res, entry.handler));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns true if the handler of the key is waiting.
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.inner.state.lock().unwrap().map.contains_key(key)
+    }
+
+    /// Fails the handler of the key with the error, returns false if the key is not found.
+    pub fn fail(&self, key: &K, err: io::Error) -> bool {
+        match self.take(key) {
+            Some(entry) => {
+                self.as_ctx().do_dispatch(Failure::new(err, entry.handler));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Inserts the handler waiting for the key until the timeout.
+    ///
+    /// If the key is already waiting, the previous handler is canceled with the `ECANCELED` error.
+    pub fn insert<F>(&self, key: K, timeout: Duration, handler: F) -> F::Output
+    where
+        F: Handler<R, io::Error>,
+    {
+        let pm = self.inner.clone();
+        handler.wrap(self.as_ctx(), move |ctx, handler| {
+            let entry = Entry {
+                expiry: Instant::now() + timeout,
+                handler: BoxHandler::from_wrapped(handler),
+            };
+            let prev = pm.state.lock().unwrap().map.insert(key, entry);
+            if let Some(prev) = prev {
+                ctx.do_dispatch(Failure::new(OPERATION_CANCELED, prev.handler));
+            }
+            rearm(&pm)
+        })
+    }
+
+    /// Returns true if no handlers are waiting.
+    pub fn is_empty(&self) -> bool {
+        self.inner.state.lock().unwrap().map.is_empty()
+    }
+
+    /// Returns a number of the waiting handlers.
+    pub fn len(&self) -> usize {
+        self.inner.state.lock().unwrap().map.len()
+    }
+
+    /// Cancels the handler of the key with the `ECANCELED` error, returns false if the key is not found.
+    pub fn remove(&self, key: &K) -> bool {
+        match self.take(key) {
+            Some(entry) => {
+                self.as_ctx().do_dispatch(Failure::new(OPERATION_CANCELED, entry.handler));
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn take(&self, key: &K) -> Option<Entry<R>> {
+        let _arming = self.inner.arming.lock().unwrap();
+        let mut state = self.inner.state.lock().unwrap();
+        let entry = state.map.remove(key);
+        if entry.is_some() && state.map.is_empty() {
+            // No more waiting for the timeout, that releases the work of the timer.
+            state.armed = None;
+            disarm(&self.inner);
+        }
+        entry
+    }
+}
+
+unsafe impl<K, R> AsIoContext for PendingMap<K, R> {
+    fn as_ctx(&self) -> &IoContext {
+        self.inner.timer.as_ctx()
+    }
+}
+
+impl<K, R> Clone for PendingMap<K, R> {
+    fn clone(&self) -> Self {
+        PendingMap { inner: self.inner.clone() }
+    }
+}
@@ -0,0 +1,374 @@
+//! Provides the anonymous pipe of the operating system.
+//!
+//! # Examples
+//!
+//! ```
+//! use asyncio::IoContext;
+//! use asyncio::pipe;
+//!
+//! let ctx = &IoContext::new().unwrap();
+//! let (rx, tx) = pipe::pipe(ctx).unwrap();
+//! assert_eq!(tx.write_some(b"hello").unwrap(), 5);
+//!
+//! let mut buf = [0; 16];
+//! assert_eq!(rx.read_some(&mut buf).unwrap(), 5);
+//! ```
+
+use ffi::{AsRawFd, IntoRawFd, RawFd, SystemError, ioctl};
+use reactor::SocketImpl;
+use core::{AsIoContext, IoContext, ThreadIoContext, Perform, Cancel};
+use handler::{Handler, AsyncReadOp, AsyncWriteOp, Complete};
+use read_ops::{Read, async_read_op, blocking_read_op, nonblocking_read_op};
+use write_ops::{Write, async_write_op, blocking_write_op, nonblocking_write_op};
+use stream::Stream;
+use socket_base::BytesReadable;
+use ffi;
+
+use std::io;
+use std::fmt;
+use std::time::Duration;
+
+/// Returns a pair of the read end and the write end of a new pipe.
+pub fn pipe(ctx: &IoContext) -> io::Result<(Reader, Writer)> {
+    let (rfd, wfd) = ffi::pipe()?;
+    Ok(unsafe { (Reader::from_raw_fd(ctx, rfd), Writer::from_raw_fd(ctx, wfd)) })
+}
+
+/// The read end of the pipe.
+pub struct Reader {
+    pimpl: Box<SocketImpl<()>>,
+}
+
+impl Reader {
+    /// Adopts the non-blocking read end of the pipe.
+    pub unsafe fn from_raw_fd(ctx: &IoContext, fd: RawFd) -> Self {
+        Reader { pimpl: SocketImpl::new(ctx, fd, ()) }
+    }
+
+    pub fn available(&self) -> io::Result<usize> {
+        let mut bytes = BytesReadable::default();
+        ioctl(self, &mut bytes)?;
+        Ok(bytes.get())
+    }
+
+    /// Cancels the pending operations and closes the read end, returns the error of the `close`.
+    pub fn close(&mut self) -> io::Result<()> {
+        Ok(self.pimpl.close()?)
+    }
+
+    pub fn get_timeout(&self) -> Duration {
+        self.pimpl.timeout.get()
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.pimpl.is_open()
+    }
+
+    pub fn nonblocking_read_some(&self, buf: &mut [u8]) -> io::Result<usize> {
+        nonblocking_read_op(self, buf, Read::new())
+    }
+
+    pub fn read_some(&self, buf: &mut [u8]) -> io::Result<usize> {
+        blocking_read_op(self, buf, &self.pimpl.timeout, Read::new())
+    }
+
+    pub fn set_timeout(&self, timeout: Duration) -> io::Result<()> {
+        Ok(self.pimpl.timeout.set(timeout)?)
+    }
+}
+
+unsafe impl Send for Reader {}
+
+unsafe impl Sync for Reader {}
+
+unsafe impl AsIoContext for Reader {
+    fn as_ctx(&self) -> &IoContext {
+        self.pimpl.as_ctx()
+    }
+}
+
+impl AsRawFd for Reader {
+    fn as_raw_fd(&self) -> RawFd {
+        self.pimpl.as_raw_fd()
+    }
+}
+
+impl IntoRawFd for Reader {
+    fn into_raw_fd(self) -> RawFd {
+        self.pimpl.into_raw_fd()
+    }
+}
+
+impl Cancel for Reader {
+    fn cancel(&self) {
+        self.pimpl.cancel()
+    }
+}
+
+impl AsyncReadOp for Reader {
+    fn add_read_op(&self, this: &mut ThreadIoContext, op: Box<Perform>, err: SystemError) {
+        self.pimpl.add_read_op(this, op, err)
+    }
+
+    fn next_read_op(&self, this: &mut ThreadIoContext) {
+        self.pimpl.next_read_op(this)
+    }
+}
+
+impl AsyncWriteOp for Reader {
+    fn add_write_op(&self, this: &mut ThreadIoContext, op: Box<Perform>, err: SystemError) {
+        self.pimpl.add_write_op(this, op, err)
+    }
+
+    fn next_write_op(&self, this: &mut ThreadIoContext) {
+        self.pimpl.next_write_op(this)
+    }
+}
+
+impl fmt::Debug for Reader {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Reader({})", self.as_raw_fd())
+    }
+}
+
+impl io::Read for Reader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.read_some(buf)
+    }
+}
+
+/// The read end implements the `Stream` for the reading only, that the writing fails with the
+/// `EBADF` error.
+impl Stream for Reader {
+    type Error = io::Error;
+
+    fn async_read_some<F>(&self, buf: &[u8], handler: F) -> F::Output
+    where
+        F: Handler<usize, Self::Error>,
+    {
+        async_read_op(self, buf, &self.pimpl.timeout, handler, Read::new())
+    }
+
+    fn async_write_some<F>(&self, buf: &[u8], handler: F) -> F::Output
+    where
+        F: Handler<usize, Self::Error>,
+    {
+        async_write_op(self, buf, &self.pimpl.timeout, handler, Write::new())
+    }
+
+    #[doc(hidden)]
+    fn wrap_timeout<F, G, W>(&self, handler: F, wrapper: W) -> F::Output
+    where
+        F: Handler<usize, Self::Error, WrappedHandler = G>,
+        G: Complete<usize, Self::Error>,
+        W: FnOnce(&IoContext, G),
+    {
+        handler.wrap_timeout(self, &self.pimpl.timeout, wrapper)
+    }
+}
+
+/// The write end of the pipe.
+pub struct Writer {
+    pimpl: Box<SocketImpl<()>>,
+}
+
+impl Writer {
+    /// Adopts the non-blocking write end of the pipe.
+    pub unsafe fn from_raw_fd(ctx: &IoContext, fd: RawFd) -> Self {
+        Writer { pimpl: SocketImpl::new(ctx, fd, ()) }
+    }
+
+    pub fn async_write_some<F>(&self, buf: &[u8], handler: F) -> F::Output
+    where
+        F: Handler<usize, io::Error>,
+    {
+        async_write_op(self, buf, &self.pimpl.timeout, handler, Write::new())
+    }
+
+    /// Cancels the pending operations and closes the write end, returns the error of the `close`.
+    pub fn close(&mut self) -> io::Result<()> {
+        Ok(self.pimpl.close()?)
+    }
+
+    pub fn get_timeout(&self) -> Duration {
+        self.pimpl.timeout.get()
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.pimpl.is_open()
+    }
+
+    pub fn nonblocking_write_some(&self, buf: &[u8]) -> io::Result<usize> {
+        nonblocking_write_op(self, buf, Write::new())
+    }
+
+    pub fn set_timeout(&self, timeout: Duration) -> io::Result<()> {
+        Ok(self.pimpl.timeout.set(timeout)?)
+    }
+
+    pub fn write_some(&self, buf: &[u8]) -> io::Result<usize> {
+        blocking_write_op(self, buf, &self.pimpl.timeout, Write::new())
+    }
+}
+
+unsafe impl Send for Writer {}
+
+unsafe impl Sync for Writer {}
+
+unsafe impl AsIoContext for Writer {
+    fn as_ctx(&self) -> &IoContext {
+        self.pimpl.as_ctx()
+    }
+}
+
+impl AsRawFd for Writer {
+    fn as_raw_fd(&self) -> RawFd {
+        self.pimpl.as_raw_fd()
+    }
+}
+
+impl IntoRawFd for Writer {
+    fn into_raw_fd(self) -> RawFd {
+        self.pimpl.into_raw_fd()
+    }
+}
+
+impl Cancel for Writer {
+    fn cancel(&self) {
+        self.pimpl.cancel()
+    }
+}
+
+impl AsyncReadOp for Writer {
+    fn add_read_op(&self, this: &mut ThreadIoContext, op: Box<Perform>, err: SystemError) {
+        self.pimpl.add_read_op(this, op, err)
+    }
+
+    fn next_read_op(&self, this: &mut ThreadIoContext) {
+        self.pimpl.next_read_op(this)
+    }
+}
+
+impl AsyncWriteOp for Writer {
+    fn add_write_op(&self, this: &mut ThreadIoContext, op: Box<Perform>, err: SystemError) {
+        self.pimpl.add_write_op(this, op, err)
+    }
+
+    fn next_write_op(&self, this: &mut ThreadIoContext) {
+        self.pimpl.next_write_op(this)
+    }
+}
+
+impl fmt::Debug for Writer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Writer({})", self.as_raw_fd())
+    }
+}
+
+impl io::Write for Writer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.write_some(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// The write end implements the `Stream` for the writing only, that the reading fails with the
+/// `EBADF` error.
+impl Stream for Writer {
+    type Error = io::Error;
+
+    fn async_read_some<F>(&self, buf: &[u8], handler: F) -> F::Output
+    where
+        F: Handler<usize, Self::Error>,
+    {
+        async_read_op(self, buf, &self.pimpl.timeout, handler, Read::new())
+    }
+
+    fn async_write_some<F>(&self, buf: &[u8], handler: F) -> F::Output
+    where
+        F: Handler<usize, Self::Error>,
+    {
+        async_write_op(self, buf, &self.pimpl.timeout, handler, Write::new())
+    }
+
+    #[doc(hidden)]
+    fn wrap_timeout<F, G, W>(&self, handler: F, wrapper: W) -> F::Output
+    where
+        F: Handler<usize, Self::Error, WrappedHandler = G>,
+        G: Complete<usize, Self::Error>,
+        W: FnOnce(&IoContext, G),
+    {
+        handler.wrap_timeout(self, &self.pimpl.timeout, wrapper)
+    }
+}
+
+#[test]
+fn test_pipe() {
+    let ctx = &IoContext::new().unwrap();
+    let (rx, tx) = pipe(ctx).unwrap();
+    assert_eq!(tx.write_some(b"hello").unwrap(), 5);
+    assert_eq!(rx.available().unwrap(), 5);
+
+    let mut buf = [0; 16];
+    assert_eq!(rx.read_some(&mut buf).unwrap(), 5);
+    assert_eq!(&buf[..5], b"hello");
+    assert_eq!(
+        rx.nonblocking_read_some(&mut buf).unwrap_err().kind(),
+        io::ErrorKind::WouldBlock
+    );
+
+    drop(tx);
+    assert!(rx.read_some(&mut buf).is_err());
+}
+
+#[test]
+fn test_pipe_async() {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use handler::wrap;
+
+    static READ_LEN: AtomicUsize = AtomicUsize::new(0);
+
+    fn on_read(_: Arc<Reader>, res: io::Result<usize>) {
+        READ_LEN.store(res.unwrap(), Ordering::SeqCst);
+    }
+
+    fn on_write(_: Arc<Writer>, res: io::Result<usize>) {
+        assert_eq!(res.unwrap(), 5);
+    }
+
+    let ctx = &IoContext::new().unwrap();
+    let (rx, tx) = pipe(ctx).unwrap();
+    let rx = Arc::new(rx);
+    let tx = Arc::new(tx);
+    let mut buf = [0; 16];
+    rx.async_read_some(&mut buf, wrap(&rx, on_read));
+    tx.async_write_some(b"hello", wrap(&tx, on_write));
+    ctx.run();
+    assert_eq!(READ_LEN.load(Ordering::SeqCst), 5);
+    assert_eq!(&buf[..5], b"hello");
+}
+
+#[test]
+fn test_pipe_close() {
+    let ctx = &IoContext::new().unwrap();
+    let (mut rx, mut tx) = pipe(ctx).unwrap();
+    assert_eq!(tx.write_some(b"hello").unwrap(), 5);
+    tx.close().unwrap();
+    assert!(!tx.is_open());
+    assert!(tx.write_some(b"hello").is_err());
+    tx.close().unwrap();
+
+    let mut buf = [0; 16];
+    assert_eq!(rx.read_some(&mut buf).unwrap(), 5);
+    assert_eq!(
+        rx.read_some(&mut buf).unwrap_err().kind(),
+        io::ErrorKind::ConnectionAborted
+    );
+    rx.close().unwrap();
+    assert!(!rx.is_open());
+    assert!(rx.read_some(&mut buf).is_err());
+}
@@ -2,9 +2,10 @@ use ffi::{AsRawFd, RawFd, SystemError, ioctl};
 use reactor::SocketImpl;
 use core::{IoControl, AsIoContext, IoContext, Perform, ThreadIoContext, Cancel};
 use handler::{Handler, AsyncReadOp, AsyncWriteOp, Complete};
-use read_ops::{Read, async_read_op, blocking_read_op, nonblocking_read_op};
-use write_ops::{Write, async_write_op, blocking_write_op, nonblocking_write_op};
+use read_ops::{Read, ReadAt, async_read_op, blocking_read_op, nonblocking_read_op};
+use write_ops::{Write, WriteAt, async_write_op, blocking_write_op, nonblocking_write_op};
 use stream::Stream;
+use random_access::RandomAccessDevice;
 
 use std::io;
 use std::time::Duration;
@@ -126,6 +127,76 @@ impl Stream for StreamDescriptor {
     }
 }
 
+/// The positioned operations are available if the descriptor is seekable, e.g. the regular file.
+///
+/// # Examples
+///
+/// ```
+/// use std::fs::OpenOptions;
+/// use std::os::unix::io::IntoRawFd;
+/// use asyncio::{IoContext, RandomAccessDevice};
+/// use asyncio::posix::StreamDescriptor;
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let path = std::env::temp_dir().join("asyncio_random_access_doctest");
+/// let file = OpenOptions::new().read(true).write(true).create(true).open(&path).unwrap();
+/// let dev = unsafe { StreamDescriptor::from_raw_fd(ctx, file.into_raw_fd()) };
+///
+/// dev.write_all_at(4, b"world").unwrap();
+/// dev.write_all_at(0, b"hell").unwrap();
+/// let mut buf = [0; 9];
+/// dev.read_exact_at(0, &mut buf).unwrap();
+/// assert_eq!(&buf, b"hellworld");
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+impl RandomAccessDevice for StreamDescriptor {
+    type Error = io::Error;
+
+    fn async_read_some_at<F>(&self, offset: u64, buf: &mut [u8], handler: F) -> F::Output
+    where
+        F: Handler<usize, Self::Error>,
+    {
+        async_read_op(
+            self,
+            buf,
+            &self.pimpl.timeout,
+            handler,
+            ReadAt::new(offset),
+        )
+    }
+
+    fn async_write_some_at<F>(&self, offset: u64, buf: &[u8], handler: F) -> F::Output
+    where
+        F: Handler<usize, Self::Error>,
+    {
+        async_write_op(
+            self,
+            buf,
+            &self.pimpl.timeout,
+            handler,
+            WriteAt::new(offset),
+        )
+    }
+
+    fn read_some_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<usize> {
+        blocking_read_op(self, buf, &self.pimpl.timeout, ReadAt::new(offset))
+    }
+
+    fn write_some_at(&self, offset: u64, buf: &[u8]) -> io::Result<usize> {
+        blocking_write_op(self, buf, &self.pimpl.timeout, WriteAt::new(offset))
+    }
+
+    #[doc(hidden)]
+    fn wrap_timeout<F, G, W>(&self, handler: F, wrapper: W) -> F::Output
+    where
+        F: Handler<usize, Self::Error, WrappedHandler = G>,
+        G: Complete<usize, Self::Error>,
+        W: FnOnce(&IoContext, G),
+    {
+        handler.wrap_timeout(self, &self.pimpl.timeout, wrapper)
+    }
+}
+
 impl io::Write for StreamDescriptor {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
         self.write_some(buf)
@@ -0,0 +1,21 @@
+//! Re-exports the traits commonly used with the sockets and the handlers.
+//!
+//! # Examples
+//!
+//! ```
+//! use asyncio::prelude::*;
+//! use asyncio::IoContext;
+//! use asyncio::ip::{IpAddrV4, Tcp, TcpEndpoint, TcpListener};
+//!
+//! let ctx = &IoContext::new().unwrap();
+//! let ep = TcpEndpoint::new(IpAddrV4::loopback(), 0);
+//! let soc = TcpListener::new(ctx, Tcp::v4()).unwrap();
+//! soc.bind(&ep).unwrap();
+//! assert_eq!(soc.local_endpoint().unwrap().protocol(), Tcp::v4());
+//! ```
+
+pub use core::{AsIoContext, Cancel, Endpoint, GetSocketOption, IoControl, Protocol,
+               SetSocketOption, Socket};
+pub use handler::Handler;
+pub use stream::Stream;
+pub use ip::IpProtocol;
@@ -0,0 +1,228 @@
+//! Provides the child process with the piped standard I/O and the asynchronous wait for the exit.
+//!
+//! The exit is notified by the `pidfd` of the process, that requires Linux 5.3 or later.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::process::{Command, Stdio};
+//! use asyncio::IoContext;
+//! use asyncio::process;
+//!
+//! let ctx = &IoContext::new().unwrap();
+//! let mut cmd = Command::new("echo");
+//! cmd.arg("hello").stdout(Stdio::piped());
+//! let child = process::spawn(ctx, &mut cmd).unwrap();
+//!
+//! let mut buf = [0; 16];
+//! let stdout = child.stdout.as_ref().unwrap();
+//! assert_eq!(stdout.read_some(&mut buf).unwrap(), 6);
+//! assert_eq!(&buf[..6], b"hello\n");
+//! assert!(child.wait().unwrap().success());
+//! ```
+
+use ffi::{AsRawFd, IntoRawFd, RawFd, SystemError, WOULD_BLOCK, pidfd_open, set_nonblocking};
+use reactor::SocketImpl;
+use core::{AsIoContext, IoContext, ThreadIoContext, Perform, Cancel};
+use handler::{Handler, AsyncReadOp};
+use read_ops::{Reader, async_read_op, blocking_read_op, nonblocking_read_op};
+use posix::StreamDescriptor;
+
+use std::io;
+use std::fmt;
+use std::process::{self, Command, ExitStatus};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Spawns the child process of the command.
+///
+/// The standard I/O configured by the `Stdio::piped()` are connected to the `StreamDescriptor`s
+/// registered on the `IoContext`.
+pub fn spawn(ctx: &IoContext, cmd: &mut Command) -> io::Result<Child> {
+    let mut child = cmd.spawn()?;
+    let fd = match pidfd_open(child.id()) {
+        Ok(fd) => fd,
+        Err(err) => {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(err.into());
+        }
+    };
+    let pimpl = SocketImpl::new(ctx, fd, ());
+    Ok(Child {
+        stdin: descriptor(ctx, child.stdin.take())?,
+        stdout: descriptor(ctx, child.stdout.take())?,
+        stderr: descriptor(ctx, child.stderr.take())?,
+        pimpl: pimpl,
+        child: Mutex::new(child),
+    })
+}
+
+fn descriptor<T>(ctx: &IoContext, pipe: Option<T>) -> io::Result<Option<StreamDescriptor>>
+where
+    T: IntoRawFd,
+{
+    match pipe {
+        Some(pipe) => {
+            let fd = pipe.into_raw_fd();
+            let desc = unsafe { StreamDescriptor::from_raw_fd(ctx, fd) };
+            set_nonblocking(fd)?;
+            Ok(Some(desc))
+        }
+        None => Ok(None),
+    }
+}
+
+struct WaitExit;
+
+impl Reader for WaitExit {
+    type Socket = Child;
+
+    type Output = ExitStatus;
+
+    fn read_op(&self, s: &Self::Socket, _: &mut [u8]) -> Result<Self::Output, SystemError> {
+        match s.child.lock().unwrap().try_wait() {
+            Ok(Some(status)) => Ok(status),
+            Ok(None) => Err(WOULD_BLOCK),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+/// The child process spawned by the `spawn`.
+///
+/// The process is neither killed nor waited when this is dropped.
+pub struct Child {
+    pimpl: Box<SocketImpl<()>>,
+    child: Mutex<process::Child>,
+
+    /// The write end of the standard input of the child, if it is piped.
+    pub stdin: Option<StreamDescriptor>,
+
+    /// The read end of the standard output of the child, if it is piped.
+    pub stdout: Option<StreamDescriptor>,
+
+    /// The read end of the standard error of the child, if it is piped.
+    pub stderr: Option<StreamDescriptor>,
+}
+
+impl Child {
+    /// Asynchronously waits for the child to exit, and reaps the child.
+    pub fn async_wait<F>(&self, handler: F) -> F::Output
+    where
+        F: Handler<ExitStatus, io::Error>,
+    {
+        async_read_op(self, &[], &self.pimpl.timeout, handler, WaitExit)
+    }
+
+    pub fn get_timeout(&self) -> Duration {
+        self.pimpl.timeout.get()
+    }
+
+    /// Returns the process identifier of the child.
+    pub fn id(&self) -> u32 {
+        self.child.lock().unwrap().id()
+    }
+
+    /// Sends the `SIGKILL` to the child, returns an error if the child has already been reaped.
+    pub fn kill(&self) -> io::Result<()> {
+        self.child.lock().unwrap().kill()
+    }
+
+    pub fn set_timeout(&self, timeout: Duration) -> io::Result<()> {
+        Ok(self.pimpl.timeout.set(timeout)?)
+    }
+
+    /// Returns the exit status if the child has exited, returns the `EAGAIN` error otherwise.
+    pub fn try_wait(&self) -> io::Result<ExitStatus> {
+        nonblocking_read_op(self, &mut [], WaitExit)
+    }
+
+    /// Waits for the child to exit, and reaps the child.
+    pub fn wait(&self) -> io::Result<ExitStatus> {
+        blocking_read_op(self, &mut [], &self.pimpl.timeout, WaitExit)
+    }
+}
+
+unsafe impl Send for Child {}
+
+unsafe impl Sync for Child {}
+
+unsafe impl AsIoContext for Child {
+    fn as_ctx(&self) -> &IoContext {
+        self.pimpl.as_ctx()
+    }
+}
+
+impl AsRawFd for Child {
+    fn as_raw_fd(&self) -> RawFd {
+        self.pimpl.as_raw_fd()
+    }
+}
+
+impl Cancel for Child {
+    fn cancel(&self) {
+        self.pimpl.cancel()
+    }
+}
+
+impl AsyncReadOp for Child {
+    fn add_read_op(&self, this: &mut ThreadIoContext, op: Box<Perform>, err: SystemError) {
+        self.pimpl.add_read_op(this, op, err)
+    }
+
+    fn next_read_op(&self, this: &mut ThreadIoContext) {
+        self.pimpl.next_read_op(this)
+    }
+}
+
+impl fmt::Debug for Child {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Child({})", self.id())
+    }
+}
+
+#[test]
+fn test_child() {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::process::Stdio;
+    use handler::wrap;
+
+    static CODE: AtomicUsize = AtomicUsize::new(0);
+
+    fn on_wait(_: Arc<Child>, res: io::Result<ExitStatus>) {
+        CODE.store(res.unwrap().code().unwrap() as usize, Ordering::SeqCst);
+    }
+
+    let ctx = &IoContext::new().unwrap();
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c")
+        .arg("read line; echo \"$line\"; echo error >&2; exit 3")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    let child = Arc::new(spawn(ctx, &mut cmd).unwrap());
+    assert_eq!(child.try_wait().unwrap_err().kind(), io::ErrorKind::WouldBlock);
+
+    child.stdin.as_ref().unwrap().write_some(b"hello\n").unwrap();
+    let mut buf = [0; 16];
+    let stdout = child.stdout.as_ref().unwrap();
+    assert_eq!(stdout.read_some(&mut buf).unwrap(), 6);
+    assert_eq!(&buf[..6], b"hello\n");
+    let stderr = child.stderr.as_ref().unwrap();
+    assert_eq!(stderr.read_some(&mut buf).unwrap(), 6);
+    assert_eq!(&buf[..6], b"error\n");
+
+    child.async_wait(wrap(&child, on_wait));
+    ctx.run();
+    assert_eq!(CODE.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn test_child_kill() {
+    let ctx = &IoContext::new().unwrap();
+    let child = spawn(ctx, &mut Command::new("sleep").arg("10")).unwrap();
+    child.kill().unwrap();
+    assert!(!child.wait().unwrap().success());
+}
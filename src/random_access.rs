@@ -0,0 +1,226 @@
+use ffi::Timeout;
+use core::{IoContext, AsIoContext, ThreadIoContext, Cancel};
+use handler::{Handler, Complete, Success};
+
+use std::io;
+use std::slice;
+
+struct AsyncReadExactAt<F, D> {
+    dev: *const D,
+    offset: u64,
+    buf: *mut u8,
+    len: usize,
+    cur: usize,
+    handler: F,
+}
+
+unsafe impl<F, D> Send for AsyncReadExactAt<F, D> {}
+
+impl<F, D> Handler<usize, D::Error> for AsyncReadExactAt<F, D>
+where
+    F: Complete<usize, D::Error>,
+    D: RandomAccessDevice,
+{
+    type Output = ();
+
+    type WrappedHandler = Self;
+
+    fn wrap<W>(self, ctx: &IoContext, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx, self)
+    }
+
+    fn wrap_timeout<W>(self, ctx: &Cancel, _: &Timeout, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx.as_ctx(), self)
+    }
+}
+
+impl<F, D> Complete<usize, D::Error> for AsyncReadExactAt<F, D>
+where
+    F: Complete<usize, D::Error>,
+    D: RandomAccessDevice,
+{
+    fn success(mut self, this: &mut ThreadIoContext, len: usize) {
+        self.cur += len;
+        if self.cur == self.len {
+            let len = self.len;
+            return self.handler.success(this, len);
+        }
+        let dev = unsafe { &*self.dev };
+        let buf = unsafe {
+            slice::from_raw_parts_mut(self.buf.offset(self.cur as isize), self.len - self.cur)
+        };
+        let offset = self.offset + self.cur as u64;
+        dev.async_read_some_at(offset, buf, self)
+    }
+
+    fn failure(self, this: &mut ThreadIoContext, err: D::Error) {
+        self.handler.failure(this, err)
+    }
+}
+
+struct AsyncWriteAllAt<F, D> {
+    dev: *const D,
+    offset: u64,
+    buf: *const u8,
+    len: usize,
+    cur: usize,
+    handler: F,
+}
+
+unsafe impl<F, D> Send for AsyncWriteAllAt<F, D> {}
+
+impl<F, D> Handler<usize, D::Error> for AsyncWriteAllAt<F, D>
+where
+    F: Complete<usize, D::Error>,
+    D: RandomAccessDevice,
+{
+    type Output = ();
+
+    type WrappedHandler = Self;
+
+    fn wrap<W>(self, ctx: &IoContext, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx, self)
+    }
+
+    fn wrap_timeout<W>(self, ctx: &Cancel, _: &Timeout, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx.as_ctx(), self)
+    }
+}
+
+impl<F, D> Complete<usize, D::Error> for AsyncWriteAllAt<F, D>
+where
+    F: Complete<usize, D::Error>,
+    D: RandomAccessDevice,
+{
+    fn success(mut self, this: &mut ThreadIoContext, len: usize) {
+        self.cur += len;
+        if self.cur == self.len {
+            let len = self.len;
+            return self.handler.success(this, len);
+        }
+        let dev = unsafe { &*self.dev };
+        let buf = unsafe {
+            slice::from_raw_parts(self.buf.offset(self.cur as isize), self.len - self.cur)
+        };
+        let offset = self.offset + self.cur as u64;
+        dev.async_write_some_at(offset, buf, self)
+    }
+
+    fn failure(self, this: &mut ThreadIoContext, err: D::Error) {
+        self.handler.failure(this, err)
+    }
+}
+
+/// The device that reads and writes at the given offsets, e.g. the regular file and the block
+/// device.
+///
+/// The positioned operations don't use nor change the current offset of the device, so the
+/// operations at the different offsets may be issued concurrently.
+pub trait RandomAccessDevice: AsIoContext + Cancel + Sized + Send + 'static {
+    type Error: From<io::Error> + Send;
+
+    /// Asynchronously reads the data at the offset until the buffer is filled.
+    fn async_read_exact_at<F>(&self, offset: u64, buf: &mut [u8], handler: F) -> F::Output
+    where
+        F: Handler<usize, Self::Error>,
+    {
+        self.wrap_timeout(handler, move |ctx, handler| {
+            if buf.is_empty() {
+                return ctx.do_dispatch(Success::new(0, handler));
+            }
+            let len = buf.len();
+            let buf_ptr = buf.as_mut_ptr();
+            self.async_read_some_at(
+                offset,
+                buf,
+                AsyncReadExactAt {
+                    dev: self,
+                    offset: offset,
+                    buf: buf_ptr,
+                    len: len,
+                    cur: 0,
+                    handler: handler,
+                },
+            )
+        })
+    }
+
+    /// Asynchronously reads some data at the offset.
+    fn async_read_some_at<F>(&self, offset: u64, buf: &mut [u8], handler: F) -> F::Output
+    where
+        F: Handler<usize, Self::Error>;
+
+    /// Asynchronously writes the whole buffer at the offset.
+    fn async_write_all_at<F>(&self, offset: u64, buf: &[u8], handler: F) -> F::Output
+    where
+        F: Handler<usize, Self::Error>,
+    {
+        self.wrap_timeout(handler, move |ctx, handler| {
+            if buf.is_empty() {
+                return ctx.do_dispatch(Success::new(0, handler));
+            }
+            self.async_write_some_at(
+                offset,
+                buf,
+                AsyncWriteAllAt {
+                    dev: self,
+                    offset: offset,
+                    buf: buf.as_ptr(),
+                    len: buf.len(),
+                    cur: 0,
+                    handler: handler,
+                },
+            )
+        })
+    }
+
+    /// Asynchronously writes some data at the offset.
+    fn async_write_some_at<F>(&self, offset: u64, buf: &[u8], handler: F) -> F::Output
+    where
+        F: Handler<usize, Self::Error>;
+
+    /// Reads the data at the offset until the buffer is filled.
+    fn read_exact_at(&self, mut offset: u64, mut buf: &mut [u8]) -> Result<(), Self::Error> {
+        while !buf.is_empty() {
+            let len = self.read_some_at(offset, buf)?;
+            offset += len as u64;
+            buf = &mut { buf }[len..];
+        }
+        Ok(())
+    }
+
+    /// Reads some data at the offset.
+    fn read_some_at(&self, offset: u64, buf: &mut [u8]) -> Result<usize, Self::Error>;
+
+    /// Writes the whole buffer at the offset.
+    fn write_all_at(&self, mut offset: u64, mut buf: &[u8]) -> Result<(), Self::Error> {
+        while !buf.is_empty() {
+            let len = self.write_some_at(offset, buf)?;
+            offset += len as u64;
+            buf = &buf[len..];
+        }
+        Ok(())
+    }
+
+    /// Writes some data at the offset.
+    fn write_some_at(&self, offset: u64, buf: &[u8]) -> Result<usize, Self::Error>;
+
+    #[doc(hidden)]
+    fn wrap_timeout<F, G, W>(&self, handler: F, wrapper: W) -> F::Output
+    where
+        F: Handler<usize, Self::Error, WrappedHandler = G>,
+        G: Complete<usize, Self::Error>,
+        W: FnOnce(&IoContext, G);
+}
@@ -71,6 +71,10 @@ impl Epoll {
         }
     }
 
+    pub fn reset(&mut self, fd: RawFd) {
+        self.fd = fd
+    }
+
     pub fn intr(fd: RawFd) -> Self {
         Epoll {
             fd: fd,
@@ -128,13 +132,13 @@ pub struct EpollReactor {
 }
 
 impl EpollReactor {
-    pub fn new() -> io::Result<Self> {
+    pub fn new(intr: Intr) -> io::Result<Self> {
         match unsafe { epoll_create1(EPOLL_CLOEXEC) } {
             -1 => Err(SystemError::last_error().into()),
             epfd => Ok(EpollReactor {
                 epfd: epfd,
                 mutex: Default::default(),
-                intr: Intr::new()?,
+                intr: intr,
                 tq: TimerQueue::new()?,
             }),
         }
@@ -174,11 +178,15 @@ impl EpollReactor {
     }
 
     pub fn register_socket(&self, eev: &Epoll) {
-        self.epoll_ctl(eev, EPOLL_CTL_ADD, EPOLLIN | EPOLLOUT | EPOLLET)
+        self.epoll_ctl(eev, EPOLL_CTL_ADD, EPOLLIN | EPOLLOUT | EPOLLET);
+        let mut epoll = self.mutex.lock().unwrap();
+        epoll.insert(EpollRef(eev));
     }
 
     pub fn deregister_socket(&self, eev: &Epoll) {
-        self.epoll_ctl(eev, EPOLL_CTL_DEL, 0)
+        self.epoll_ctl(eev, EPOLL_CTL_DEL, 0);
+        let mut epoll = self.mutex.lock().unwrap();
+        epoll.remove(&EpollRef(eev));
     }
 
     pub fn register_intr(&self, eev: &Epoll) {
@@ -186,7 +194,7 @@ impl EpollReactor {
     }
 
     pub fn deregister_intr(&self, eev: &Epoll) {
-        self.deregister_socket(eev)
+        self.epoll_ctl(eev, EPOLL_CTL_DEL, 0)
     }
 
     pub fn interrupt(&self) {
@@ -287,6 +295,13 @@ impl EpollReactor {
         self.cancel_ops_nolock(eev, ctx, err)
     }
 
+    pub fn cancel_all(&self, ctx: &IoContext, err: SystemError) {
+        let epoll = self.mutex.lock().unwrap();
+        for eev in epoll.iter() {
+            self.cancel_ops_nolock(eev, ctx, err)
+        }
+    }
+
     fn cancel_ops_nolock(&self, eev: &Epoll, ctx: &IoContext, err: SystemError) {
         for ops in &mut [&mut EpollRef(eev).input, &mut EpollRef(eev).output] {
             if !ops.canceled {
@@ -301,6 +316,12 @@ impl EpollReactor {
     }
 }
 
+impl AsRawFd for EpollReactor {
+    fn as_raw_fd(&self) -> RawFd {
+        self.epfd
+    }
+}
+
 impl Drop for EpollReactor {
     fn drop(&mut self) {
         self.intr.cleanup(self);
@@ -1,36 +1,37 @@
-use super::{Handle, Reactor};
-use ffi::{AsRawFd, close, write, SystemError};
+use super::Interrupter;
+use ffi::{AsRawFd, RawFd, close, SystemError};
 
-use libc::{eventfd, EFD_CLOEXEC, EFD_NONBLOCK};
+use libc::{self, eventfd, EFD_CLOEXEC, EFD_NONBLOCK};
 
 pub struct EventFdIntr {
-    efd: Handle,
+    efd: RawFd,
 }
 
 impl EventFdIntr {
     pub fn new() -> Result<Self, SystemError> {
         match unsafe { eventfd(0, EFD_CLOEXEC | EFD_NONBLOCK) } {
             -1 => Err(SystemError::last_error()),
-            fd => Ok(EventFdIntr { efd: Handle::intr(fd) }),
+            fd => Ok(EventFdIntr { efd: fd }),
         }
     }
+}
 
-    pub fn startup(&self, reactor: &Reactor) {
-        reactor.register_intr(&self.efd);
-    }
-
-    pub fn cleanup(&self, reactor: &Reactor) {
-        reactor.deregister_intr(&self.efd)
+impl AsRawFd for EventFdIntr {
+    fn as_raw_fd(&self) -> RawFd {
+        self.efd
     }
+}
 
-    pub fn interrupt(&self) {
-        let buf = [1, 0, 0, 0, 0, 0, 0, 0];
-        write(&self.efd, &buf).unwrap();
+impl Interrupter for EventFdIntr {
+    fn interrupt(&self) {
+        // The counter is never overflowed because the reactor drains it.
+        let buf: [u8; 8] = [1, 0, 0, 0, 0, 0, 0, 0];
+        unsafe { libc::write(self.efd, buf.as_ptr() as *const libc::c_void, buf.len()) };
     }
 }
 
 impl Drop for EventFdIntr {
     fn drop(&mut self) {
-        close(self.efd.as_raw_fd());
+        close(self.efd);
     }
 }
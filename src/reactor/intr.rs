@@ -0,0 +1,42 @@
+use super::{Handle, Reactor};
+use ffi::{AsRawFd, SystemError};
+
+/// Provides a way to wake up the reactor from the waiting.
+///
+/// The reactor watches the readability of the file descriptor, and drains it by `read` when it
+/// becomes readable. For example, an existing self-pipe shared with the other event loop can be
+/// used for the interrupter.
+pub trait Interrupter: AsRawFd + Send + Sync + 'static {
+    /// Makes the file descriptor readable, that must be thread-safe and should be async-signal-safe.
+    fn interrupt(&self);
+}
+
+pub struct Intr {
+    fd: Handle,
+    imp: Box<Interrupter>,
+}
+
+impl Intr {
+    pub fn new() -> Result<Self, SystemError> {
+        Ok(Intr::with(Box::new(super::DefaultIntr::new()?)))
+    }
+
+    pub fn with(imp: Box<Interrupter>) -> Self {
+        Intr {
+            fd: Handle::intr(imp.as_raw_fd()),
+            imp: imp,
+        }
+    }
+
+    pub fn startup(&self, reactor: &Reactor) {
+        reactor.register_intr(&self.fd);
+    }
+
+    pub fn cleanup(&self, reactor: &Reactor) {
+        reactor.deregister_intr(&self.fd)
+    }
+
+    pub fn interrupt(&self) {
+        self.imp.interrupt()
+    }
+}
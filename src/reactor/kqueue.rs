@@ -103,6 +103,10 @@ impl Kevent {
         }
     }
 
+    pub fn reset(&mut self, fd: RawFd) {
+        self.fd = fd
+    }
+
     pub fn intr(fd: RawFd) -> Self {
         Kevent {
             fd: fd,
@@ -162,14 +166,14 @@ pub struct KqueueReactor {
 }
 
 impl KqueueReactor {
-    pub fn new() -> Result<Self, SystemError> {
+    pub fn new(intr: Intr) -> Result<Self, SystemError> {
         match unsafe { libc::kqueue() } {
             -1 => Err(SystemError::last_error()),
             kq => {
                 let kq = KqueueReactor {
                     kq: kq,
                     mutex: Default::default(),
-                    intr: Intr::new()?,
+                    intr: intr,
                     tq: TimerQueue::new()?,
                     sigmask: unsafe {
                         let mut sigmask = mem::uninitialized();
@@ -443,6 +447,13 @@ impl KqueueReactor {
         self.cancel_ops_nolock(kev, ctx, err)
     }
 
+    pub fn cancel_all(&self, ctx: &IoContext, err: SystemError) {
+        let kq = self.mutex.lock().unwrap();
+        for kev in kq.iter() {
+            self.cancel_ops_nolock(kev, ctx, err)
+        }
+    }
+
     pub fn cancel_ops_nolock(&self, kev: &Kevent, ctx: &IoContext, err: SystemError) {
         for ops in &mut [
             &mut KeventRef(kev).input,
@@ -476,6 +487,12 @@ impl KqueueReactor {
     }
 }
 
+impl AsRawFd for KqueueReactor {
+    fn as_raw_fd(&self) -> RawFd {
+        self.kq
+    }
+}
+
 impl Drop for KqueueReactor {
     fn drop(&mut self) {
         self.intr.cleanup(self);
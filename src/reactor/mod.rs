@@ -1,21 +1,34 @@
 mod socket_impl;
 pub use self::socket_impl::SocketImpl;
 
-#[cfg(target_os = "linux")]
+mod op_stats;
+pub use self::op_stats::{OpCounter, OpStats};
+
+mod intr;
+pub use self::intr::{Interrupter, Intr};
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
 mod eventfd;
-#[cfg(target_os = "linux")]
-use self::eventfd::EventFdIntr as Intr;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use self::eventfd::EventFdIntr as DefaultIntr;
 
 #[cfg(target_os = "macos")]
 mod pipe;
 #[cfg(target_os = "macos")]
-use self::pipe::PipeIntr as Intr;
+use self::pipe::PipeIntr as DefaultIntr;
 
-#[cfg(target_os = "linux")]
+#[cfg(all(any(target_os = "linux", target_os = "android"),
+          not(all(target_os = "linux", feature = "uring"))))]
 mod epoll;
-#[cfg(target_os = "linux")]
+#[cfg(all(any(target_os = "linux", target_os = "android"),
+          not(all(target_os = "linux", feature = "uring"))))]
 pub use self::epoll::{Epoll as Handle, EpollReactor as Reactor};
 
+#[cfg(all(target_os = "linux", feature = "uring"))]
+mod uring;
+#[cfg(all(target_os = "linux", feature = "uring"))]
+pub use self::uring::{Uring as Handle, UringReactor as Reactor};
+
 #[cfg(target_os = "macos")]
 mod kqueue;
 #[cfg(target_os = "macos")]
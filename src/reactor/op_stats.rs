@@ -0,0 +1,183 @@
+//! Counts the asynchronous operations queued to each socket in debug builds.
+//!
+//! Each operation is boxed when it is queued to the reactor, so issuing many concurrent
+//! operations on one socket shows up as the number and the size of the live boxes.
+
+use ffi::SystemError;
+use core::Perform;
+#[cfg(debug_assertions)]
+use core::ThreadIoContext;
+
+#[cfg(debug_assertions)]
+use std::mem;
+#[cfg(debug_assertions)]
+use std::cell::Cell;
+#[cfg(debug_assertions)]
+use std::sync::Arc;
+#[cfg(debug_assertions)]
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(all(debug_assertions, target_os = "linux", feature = "uring"))]
+use io_uring::squeue;
+
+/// The statistics of the asynchronous operations of the socket.
+///
+/// The operations are counted only in debug builds, all of the counts are zero in release builds.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct OpStats {
+    /// The number of the operations not yet completed.
+    pub live: usize,
+    /// The total size in bytes of the operations not yet completed.
+    pub live_bytes: usize,
+    /// The number of the operations ever issued.
+    pub total: usize,
+    /// The total size in bytes of the operations ever issued.
+    pub total_bytes: usize,
+}
+
+#[cfg(debug_assertions)]
+fn key(op: &Perform) -> usize {
+    op as *const Perform as *const u8 as usize
+}
+
+#[cfg(debug_assertions)]
+thread_local!(static PERFORMING: Cell<usize> = Cell::new(0));
+
+#[cfg(debug_assertions)]
+#[derive(Default)]
+struct Counts {
+    live: AtomicUsize,
+    live_bytes: AtomicUsize,
+    total: AtomicUsize,
+    total_bytes: AtomicUsize,
+}
+
+#[cfg(debug_assertions)]
+struct Counted {
+    op: Option<Box<Perform>>,
+    size: usize,
+    counts: Arc<Counts>,
+}
+
+#[cfg(debug_assertions)]
+impl Perform for Counted {
+    fn perform(mut self: Box<Self>, this: &mut ThreadIoContext, err: SystemError) {
+        let op = self.op.take().unwrap();
+        // The operation queued again while performing (e.g. `EWOULDBLOCK`) is not a new one.
+        let prev = PERFORMING.with(|cur| cur.replace(key(&*op)));
+        op.perform(this, err);
+        PERFORMING.with(|cur| cur.set(prev));
+    }
+
+    fn type_name(&self) -> &'static str {
+        self.op.as_ref().unwrap().type_name()
+    }
+
+    #[cfg(all(target_os = "linux", feature = "uring"))]
+    fn uring_entry(&mut self) -> Option<squeue::Entry> {
+        self.op.as_mut().unwrap().uring_entry()
+    }
+
+    #[cfg(all(target_os = "linux", feature = "uring"))]
+    fn uring_complete(mut self: Box<Self>, this: &mut ThreadIoContext, res: i32) {
+        let op = self.op.take().unwrap();
+        let prev = PERFORMING.with(|cur| cur.replace(key(&*op)));
+        op.uring_complete(this, res);
+        PERFORMING.with(|cur| cur.set(prev));
+    }
+}
+
+#[cfg(debug_assertions)]
+impl Drop for Counted {
+    fn drop(&mut self) {
+        self.counts.live.fetch_sub(1, Ordering::Relaxed);
+        self.counts.live_bytes.fetch_sub(self.size, Ordering::Relaxed);
+    }
+}
+
+#[cfg(debug_assertions)]
+#[derive(Clone, Default)]
+pub struct OpCounter(Arc<Counts>);
+
+#[cfg(debug_assertions)]
+impl OpCounter {
+    pub fn count(&self, op: Box<Perform>, err: SystemError) -> Box<Perform> {
+        let size = mem::size_of_val(&*op);
+        // The box of the completed operation may be reused by the new one issued in the handler,
+        // that is queued without the error.
+        if err == SystemError::default() || PERFORMING.with(|cur| cur.get()) != key(&*op) {
+            self.0.total.fetch_add(1, Ordering::Relaxed);
+            self.0.total_bytes.fetch_add(size, Ordering::Relaxed);
+        }
+        self.0.live.fetch_add(1, Ordering::Relaxed);
+        self.0.live_bytes.fetch_add(size, Ordering::Relaxed);
+        Box::new(Counted {
+            op: Some(op),
+            size: size,
+            counts: self.0.clone(),
+        })
+    }
+
+    pub fn stats(&self) -> OpStats {
+        OpStats {
+            live: self.0.live.load(Ordering::Relaxed),
+            live_bytes: self.0.live_bytes.load(Ordering::Relaxed),
+            total: self.0.total.load(Ordering::Relaxed),
+            total_bytes: self.0.total_bytes.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(not(debug_assertions))]
+#[derive(Clone, Default)]
+pub struct OpCounter;
+
+#[cfg(not(debug_assertions))]
+impl OpCounter {
+    #[inline]
+    pub fn count(&self, op: Box<Perform>, _: SystemError) -> Box<Perform> {
+        op
+    }
+
+    #[inline]
+    pub fn stats(&self) -> OpStats {
+        OpStats::default()
+    }
+}
+
+#[test]
+#[cfg(debug_assertions)]
+fn test_op_counter() {
+    use core::IoContext;
+    use ffi::WOULD_BLOCK;
+
+    struct Op([u8; 100]);
+
+    impl Perform for Op {
+        fn perform(self: Box<Self>, this: &mut ThreadIoContext, _: SystemError) {
+            // queues itself again as the EWOULDBLOCK.
+            if self.0[0] == 0 {
+                let mut op = self;
+                op.0[0] = 1;
+                let op = COUNTER.with(|counter| counter.count(op, WOULD_BLOCK));
+                this.push(op, SystemError::default());
+            }
+        }
+    }
+
+    thread_local!(static COUNTER: OpCounter = OpCounter::default());
+
+    let ctx = &IoContext::new().unwrap();
+    let mut this = ThreadIoContext::new(ctx, Default::default());
+    this.init();
+    let a = COUNTER.with(|counter| counter.count(Box::new(Op([1; 100])), SystemError::default()));
+    let b = COUNTER.with(|counter| counter.count(Box::new(Op([0; 100])), SystemError::default()));
+    let stats = COUNTER.with(|counter| counter.stats());
+    assert_eq!(stats.live, 2);
+    assert_eq!(stats.live_bytes, 200);
+    assert_eq!(stats.total, 2);
+    a.perform(&mut this, SystemError::default());
+    b.perform(&mut this, SystemError::default());
+    let stats = COUNTER.with(|counter| counter.stats());
+    assert_eq!(stats.live, 1);
+    assert_eq!(stats.total, 2);
+}
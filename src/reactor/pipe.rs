@@ -1,32 +1,29 @@
+use super::Interrupter;
 use ffi::{AsRawFd, RawFd, close, SystemError, pipe};
-use reactor::{Handle, Reactor};
 
 use std::mem;
 use libc;
 
 pub struct PipeIntr {
-    rfd: Handle,
+    rfd: RawFd,
     wfd: RawFd,
 }
 
 impl PipeIntr {
     pub fn new() -> Result<Self, SystemError> {
         let (rfd, wfd) = pipe()?;
-        Ok(PipeIntr {
-            rfd: Handle::intr(rfd),
-            wfd: wfd,
-        })
-    }
-
-    pub fn startup(&self, reactor: &Reactor) {
-        reactor.register_intr(&self.rfd);
+        Ok(PipeIntr { rfd: rfd, wfd: wfd })
     }
+}
 
-    pub fn cleanup(&self, reactor: &Reactor) {
-        reactor.deregister_intr(&self.rfd)
+impl AsRawFd for PipeIntr {
+    fn as_raw_fd(&self) -> RawFd {
+        self.rfd
     }
+}
 
-    pub fn interrupt(&self) {
+impl Interrupter for PipeIntr {
+    fn interrupt(&self) {
         unsafe {
             let buf: [u8; 1] = mem::uninitialized();
             libc::write(self.wfd, buf.as_ptr() as *const libc::c_void, buf.len());
@@ -36,7 +33,7 @@ impl PipeIntr {
 
 impl Drop for PipeIntr {
     fn drop(&mut self) {
-        close(self.rfd.as_raw_fd());
+        close(self.rfd);
         close(self.wfd);
     }
 }
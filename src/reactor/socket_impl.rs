@@ -1,12 +1,18 @@
-use super::Handle;
-use ffi::{RawFd, AsRawFd, SystemError, close, OPERATION_CANCELED, Timeout};
+use super::{Handle, OpCounter, OpStats};
+use ffi::{RawFd, AsRawFd, SystemError, try_close, set_nonblocking, BAD_DESCRIPTOR,
+          OPERATION_CANCELED, Timeout};
 use core::{IoContext, AsIoContext, ThreadIoContext, Perform};
+use observer::{notify_open, notify_close};
+use extensions::Extensions;
 
 pub struct SocketImpl<T> {
     pub data: T,
     ctx: IoContext,
     fd: Handle,
     pub timeout: Timeout,
+    pub extensions: Extensions,
+    observed: bool,
+    ops: OpCounter,
 }
 
 impl<T> SocketImpl<T> {
@@ -16,16 +22,36 @@ impl<T> SocketImpl<T> {
             ctx: ctx.clone(),
             fd: Handle::socket(fd),
             timeout: Timeout::max(),
+            extensions: Extensions::new(),
+            observed: false,
+            ops: OpCounter::default(),
         });
         ctx.as_reactor().register_socket(&soc.fd);
         soc
     }
 
+    pub fn socket(ctx: &IoContext, fd: RawFd, data: T) -> Box<Self> {
+        let mut soc = Self::new(ctx, fd, data);
+        soc.observed = true;
+        notify_open(ctx, fd);
+        soc
+    }
+
     pub fn add_read_op(&self, this: &mut ThreadIoContext, op: Box<Perform>, err: SystemError) {
+        if self.ctx.is_shutdown() {
+            return this.push(op, OPERATION_CANCELED);
+        }
+        let op = self.ops.count(op, err);
+        self.ctx.track_op(&*op);
         self.ctx.as_reactor().add_read_op(&self.fd, this, op, err)
     }
 
     pub fn add_write_op(&self, this: &mut ThreadIoContext, op: Box<Perform>, err: SystemError) {
+        if self.ctx.is_shutdown() {
+            return this.push(op, OPERATION_CANCELED);
+        }
+        let op = self.ops.count(op, err);
+        self.ctx.track_op(&*op);
         self.ctx.as_reactor().add_write_op(&self.fd, this, op, err)
     }
 
@@ -37,6 +63,52 @@ impl<T> SocketImpl<T> {
         self.ctx.as_reactor().next_write_op(&self.fd, this)
     }
 
+    /// Adopts the file descriptor in place of the current one which is closed.
+    pub fn assign(&mut self, fd: RawFd, data: T) -> Result<(), SystemError> {
+        set_nonblocking(fd)?;
+        self.close()?;
+        self.data = data;
+        self.fd.reset(fd);
+        self.ctx.as_reactor().register_socket(&self.fd);
+        self.observed = true;
+        notify_open(&self.ctx, fd);
+        Ok(())
+    }
+
+    /// Cancels the pending operations and closes the file descriptor.
+    pub fn close(&mut self) -> Result<(), SystemError> {
+        let fd = self.fd.as_raw_fd();
+        if fd < 0 {
+            return Ok(());
+        }
+        self.cancel();
+        self.ctx.as_reactor().deregister_socket(&self.fd);
+        if self.observed {
+            self.observed = false;
+            notify_close(&self.ctx, fd);
+        }
+        // The canceled operations not yet completed are kept in the handle.
+        self.fd.reset(-1);
+        try_close(fd)
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.fd.as_raw_fd() >= 0
+    }
+
+    pub fn into_raw_fd(mut self: Box<Self>) -> RawFd {
+        let fd = self.fd.as_raw_fd();
+        self.ctx.as_reactor().deregister_socket(&self.fd);
+        self.observed = false;
+        self.fd = Handle::socket(-1);
+        fd
+    }
+
+    /// Returns the statistics of the operations, that are counted only in debug builds.
+    pub fn op_stats(&self) -> OpStats {
+        self.ops.stats()
+    }
+
     pub fn cancel(&self) {
         self.ctx.clone().as_reactor().cancel_ops(
             &self.fd,
@@ -64,7 +136,16 @@ impl<T> AsRawFd for SocketImpl<T> {
 
 impl<T> Drop for SocketImpl<T> {
     fn drop(&mut self) {
+        if self.fd.as_raw_fd() < 0 {
+            return; // released by into_raw_fd
+        }
         self.ctx.as_reactor().deregister_socket(&self.fd);
-        close(self.fd.as_raw_fd())
+        if self.observed {
+            notify_close(&self.ctx, self.fd.as_raw_fd());
+        }
+        // The error of the close is ignored here, that is reported by the explicit `close`.
+        if let Err(err) = try_close(self.fd.as_raw_fd()) {
+            debug_assert!(err != BAD_DESCRIPTOR, "{}", err);
+        }
     }
 }
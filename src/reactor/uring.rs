@@ -0,0 +1,586 @@
+use super::Intr;
+use ffi::{AsRawFd, RawFd, SystemError, OPERATION_CANCELED, sock_error};
+use core::{AsIoContext, IoContext, ThreadIoContext, Perform};
+use timer::TimerQueue;
+
+use std::io;
+use std::mem;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::collections::{HashSet, VecDeque};
+use std::ops::{Deref, DerefMut};
+use std::hash::{Hash, Hasher};
+use libc::{self, POLLIN, POLLOUT, POLLERR, POLLHUP};
+use io_uring::{opcode, squeue, types, IoUring};
+
+const RING_ENTRIES: u32 = 256;
+
+/// The user data of the requests whose completions are not looked up, e.g. the cancels.
+const IGNORED: u64 = 0;
+
+/// The highest bit of the user data, that tells the poll linked ahead of the operation.
+const LINKED: u64 = 1 << 63;
+
+/// The mask of the generation in the upper half of the user data below the `LINKED`.
+const GENERATION: u32 = 0x7fff_ffff;
+
+fn dispatch_socket(
+    ring: &UringReactor,
+    _: &mut Slab,
+    eev: &mut Uring,
+    output: bool,
+    res: i32,
+    this: &mut ThreadIoContext,
+) {
+    if res < 0 {
+        return; // failed to poll, e.g. the handle is closed
+    }
+    if (res & (POLLERR | POLLHUP) as i32) != 0 {
+        let err = sock_error(eev);
+        ring.cancel_ops_nolock(eev, this.as_ctx(), err);
+        return;
+    }
+    let ops = if output {
+        &mut eev.output
+    } else {
+        &mut eev.input
+    };
+    if let Some(op) = ops.queue.pop_front() {
+        ops.blocked = true;
+        this.push(op, SystemError::default());
+    }
+}
+
+fn dispatch_intr(
+    ring: &UringReactor,
+    slab: &mut Slab,
+    eev: &mut Uring,
+    _: bool,
+    res: i32,
+    _: &mut ThreadIoContext,
+) {
+    if res < 0 {
+        return; // failed to poll, e.g. the handle is closed
+    }
+    unsafe {
+        let mut buf = [0u8; 8];
+        libc::read(eev.fd, buf.as_mut_ptr() as *mut _, buf.len());
+    }
+    ring.poll_add(slab, eev, false);
+}
+
+#[derive(Default)]
+struct Ops {
+    queue: VecDeque<Box<Perform>>,
+    blocked: bool,
+    canceled: bool,
+    polling: Option<u64>,
+    inflight: Option<u64>,
+}
+
+pub struct Uring {
+    fd: RawFd,
+    input: Ops,
+    output: Ops,
+    dispatch: fn(&UringReactor, &mut Slab, &mut Uring, bool, i32, &mut ThreadIoContext),
+}
+
+impl Uring {
+    pub fn socket(fd: RawFd) -> Self {
+        Uring {
+            fd: fd,
+            input: Default::default(),
+            output: Default::default(),
+            dispatch: dispatch_socket,
+        }
+    }
+
+    pub fn reset(&mut self, fd: RawFd) {
+        self.fd = fd
+    }
+
+    pub fn intr(fd: RawFd) -> Self {
+        Uring {
+            fd: fd,
+            input: Default::default(),
+            output: Default::default(),
+            dispatch: dispatch_intr,
+        }
+    }
+}
+
+impl AsRawFd for Uring {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd
+    }
+}
+
+struct UringRef(*const Uring);
+
+impl PartialEq for UringRef {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl Eq for UringRef {}
+
+impl Hash for UringRef {
+    fn hash<H>(&self, state: &mut H)
+    where
+        H: Hasher,
+    {
+        state.write_usize(self.0 as usize)
+    }
+}
+
+impl Deref for UringRef {
+    type Target = Uring;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*self.0 }
+    }
+}
+
+impl DerefMut for UringRef {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { &mut *(self.0 as *mut Uring) }
+    }
+}
+
+/// The request in flight on the ring, with the handle and the direction of it.
+enum Slot {
+    Vacant,
+    Poll(*const Uring, bool),
+    Op(*const Uring, bool, Box<Perform>),
+}
+
+/// The requests in flight, that are identified by the index and the generation of the slot.
+///
+/// The generation is advanced when the slot is removed, the completions of the removed requests
+/// are dropped even if the slot is reused.
+#[derive(Default)]
+struct Slab {
+    slots: Vec<(u32, Slot)>,
+    vacant: Vec<usize>,
+}
+
+impl Slab {
+    fn insert(&mut self, slot: Slot) -> u64 {
+        let index = match self.vacant.pop() {
+            Some(index) => {
+                self.slots[index].1 = slot;
+                index
+            }
+            None => {
+                self.slots.push((1, slot));
+                self.slots.len() - 1
+            }
+        };
+        ((self.slots[index].0 as u64) << 32) | index as u64
+    }
+
+    fn remove(&mut self, token: u64) -> Option<Slot> {
+        let index = (token & 0xffff_ffff) as usize;
+        let gen = ((token & !LINKED) >> 32) as u32;
+        match self.slots.get_mut(index) {
+            Some(&mut (ref mut cur, ref mut slot)) if *cur == gen => {
+                if let Slot::Vacant = *slot {
+                    return None;
+                }
+                *cur = *cur % GENERATION + 1;
+                self.vacant.push(index);
+                Some(mem::replace(slot, Slot::Vacant))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Handles {
+    registered: HashSet<UringRef>,
+    slab: Slab,
+}
+
+/// The operation completed by the ring, that is performed in the queue of the thread.
+struct Completion {
+    op: Box<Perform>,
+    res: i32,
+}
+
+impl Perform for Completion {
+    fn perform(self: Box<Self>, this: &mut ThreadIoContext, _: SystemError) {
+        let Completion { op, res } = *self;
+        op.uring_complete(this, res)
+    }
+
+    fn type_name(&self) -> &'static str {
+        self.op.type_name()
+    }
+}
+
+/// The reactor on the `io_uring` of Linux 5.6 or later, enabled by the `uring` feature.
+///
+/// The operations exposing the request (e.g. the read, the write and the accept) are submitted to
+/// the ring linked behind the one-shot poll of the handle, and completed by the completions. The
+/// other operations wait for the one-shot poll and are performed as same as the epoll.
+///
+/// The user data of the requests is the index of the slab and its generation, the completions of
+/// the removed requests are dropped by the stale generation. The requests are submitted by the
+/// next poll, or at once if the other thread (or the external event loop) is blocking in the poll.
+pub struct UringReactor {
+    ring: IoUring,
+    sq: Mutex<()>,
+    mutex: Mutex<Handles>,
+    waiting: AtomicUsize,
+    pollable: AtomicBool,
+    ext_arg: bool,
+    intr: Intr,
+    pub tq: TimerQueue,
+}
+
+impl UringReactor {
+    pub fn new(intr: Intr) -> io::Result<Self> {
+        let ring = IoUring::new(RING_ENTRIES)?;
+        Ok(UringReactor {
+            ext_arg: ring.params().is_feature_ext_arg(),
+            ring: ring,
+            sq: Mutex::default(),
+            mutex: Default::default(),
+            waiting: AtomicUsize::new(0),
+            pollable: AtomicBool::new(false),
+            intr: intr,
+            tq: TimerQueue::new()?,
+        })
+    }
+
+    pub fn init(&self) {
+        self.intr.startup(self);
+        self.tq.startup(self);
+    }
+
+    pub fn poll(&self, block: bool, this: &mut ThreadIoContext) {
+        let submitter = self.ring.submitter();
+        if !block {
+            let _ = submitter.submit();
+        } else {
+            self.waiting.fetch_add(1, Ordering::SeqCst);
+            if self.ext_arg {
+                let nsec = self.tq.wait_duration(10 * 1_000_000_000);
+                let ts = types::Timespec::new()
+                    .sec((nsec / 1_000_000_000) as u64)
+                    .nsec((nsec % 1_000_000_000) as u32);
+                let args = types::SubmitArgs::new().timespec(&ts);
+                let _ = submitter.submit_with_args(1, &args);
+            } else {
+                let _ = submitter.submit_and_wait(1);
+            }
+            self.waiting.fetch_sub(1, Ordering::SeqCst);
+        }
+
+        self.tq.get_ready_timers(this);
+        let mut handles = self.mutex.lock().unwrap();
+        let handles = &mut *handles;
+        let cqes: Vec<_> = unsafe { self.ring.completion_shared() }
+            .map(|cqe| (cqe.user_data(), cqe.result()))
+            .collect();
+        for (data, res) in cqes {
+            if data == IGNORED || (data & LINKED) != 0 {
+                continue;
+            }
+            match handles.slab.remove(data) {
+                Some(Slot::Poll(eev, output)) => {
+                    if !handles.registered.contains(&UringRef(eev)) {
+                        continue;
+                    }
+                    let mut eev = UringRef(eev);
+                    if output {
+                        eev.output.polling = None;
+                    } else {
+                        eev.input.polling = None;
+                    }
+                    let dispatch = eev.dispatch;
+                    dispatch(self, &mut handles.slab, &mut eev, output, res, this);
+                }
+                Some(Slot::Op(eev, output, op)) => {
+                    if handles.registered.contains(&UringRef(eev)) {
+                        let mut eev = UringRef(eev);
+                        let ops = if output {
+                            &mut eev.output
+                        } else {
+                            &mut eev.input
+                        };
+                        if ops.inflight == Some(data) {
+                            ops.inflight = None;
+                        }
+                    }
+                    this.as_ctx().untrack_op(&*op);
+                    this.push(
+                        Box::new(Completion { op: op, res: res }),
+                        SystemError::default(),
+                    );
+                }
+                _ => (), // the stale generation
+            }
+        }
+    }
+
+    /// Pushes the requests to the submission queue, that are submitted by the next poll.
+    fn push(&self, sqes: &[squeue::Entry]) {
+        let _sq = self.sq.lock().unwrap();
+        while unsafe { self.ring.submission_shared().push_multiple(sqes) }.is_err() {
+            // The submission queue is full.
+            let _ = self.ring.submit();
+        }
+        if self.waiting.load(Ordering::SeqCst) != 0 {
+            let _ = self.ring.submit();
+        }
+    }
+
+    fn poll_add(&self, slab: &mut Slab, eev: &mut Uring, output: bool) {
+        let (fd, ptr) = (eev.fd, eev as *const Uring);
+        let (ops, flags) = if output {
+            (&mut eev.output, POLLOUT)
+        } else {
+            (&mut eev.input, POLLIN)
+        };
+        if ops.polling.is_none() {
+            let token = slab.insert(Slot::Poll(ptr, output));
+            ops.polling = Some(token);
+            self.push(&[
+                opcode::PollAdd::new(types::Fd(fd), flags as u32)
+                    .build()
+                    .user_data(token),
+            ])
+        }
+    }
+
+    /// Cancels the operation in flight and its linked poll, that is completed by the canceled.
+    fn async_cancel(&self, token: u64) {
+        self.push(&[
+            opcode::AsyncCancel::new(token | LINKED).build().user_data(IGNORED),
+            opcode::AsyncCancel::new(token).build().user_data(IGNORED),
+        ])
+    }
+
+    /// Submits the operation to the ring if it exposes the request, otherwise polls the handle.
+    fn block_op(&self, slab: &mut Slab, eev: &Uring, output: bool, mut op: Box<Perform>) {
+        let (fd, ptr) = (eev.fd, eev as *const Uring);
+        let mut eev = UringRef(ptr);
+        let (ops, flags) = if output {
+            (&mut eev.output, POLLOUT)
+        } else {
+            (&mut eev.input, POLLIN)
+        };
+        match op.uring_entry() {
+            Some(sqe) => {
+                // The handle is not ready, the request is linked behind the poll of it.
+                let token = slab.insert(Slot::Op(ptr, output, op));
+                ops.inflight = Some(token);
+                self.push(&[
+                    opcode::PollAdd::new(types::Fd(fd), flags as u32)
+                        .build()
+                        .flags(squeue::Flags::IO_LINK)
+                        .user_data(token | LINKED),
+                    sqe.user_data(token),
+                ])
+            }
+            None => {
+                ops.blocked = false;
+                ops.queue.push_front(op);
+                self.poll_add(slab, &mut UringRef(ptr), output);
+            }
+        }
+    }
+
+    fn remove_requests(&self, slab: &mut Slab, eev: &mut Uring) {
+        for ops in &mut [&mut eev.input, &mut eev.output] {
+            if let Some(token) = ops.polling.take() {
+                slab.remove(token);
+                self.push(&[opcode::PollRemove::new(token).build().user_data(IGNORED)])
+            }
+            // The operation is kept in the slab until it is completed.
+            if let Some(token) = ops.inflight.take() {
+                self.async_cancel(token)
+            }
+        }
+    }
+
+    pub fn register_socket(&self, eev: &Uring) {
+        let mut handles = self.mutex.lock().unwrap();
+        handles.registered.insert(UringRef(eev));
+    }
+
+    pub fn deregister_socket(&self, eev: &Uring) {
+        let mut handles = self.mutex.lock().unwrap();
+        let handles = &mut *handles;
+        handles.registered.remove(&UringRef(eev));
+        self.remove_requests(&mut handles.slab, &mut UringRef(eev));
+    }
+
+    pub fn register_intr(&self, eev: &Uring) {
+        let mut handles = self.mutex.lock().unwrap();
+        let handles = &mut *handles;
+        handles.registered.insert(UringRef(eev));
+        self.poll_add(&mut handles.slab, &mut UringRef(eev), false);
+    }
+
+    pub fn deregister_intr(&self, eev: &Uring) {
+        self.deregister_socket(eev)
+    }
+
+    pub fn interrupt(&self) {
+        self.intr.interrupt()
+    }
+
+    pub fn add_read_op(
+        &self,
+        eev: &Uring,
+        this: &mut ThreadIoContext,
+        op: Box<Perform>,
+        err: SystemError,
+    ) {
+        let mut handles = self.mutex.lock().unwrap();
+        let ops = &mut UringRef(eev).input;
+        if err == SystemError::default() {
+            if ops.queue.is_empty() && !ops.blocked {
+                ops.blocked = true;
+                this.push(op, SystemError::default());
+            } else {
+                ops.queue.push_back(op);
+            }
+        } else if ops.canceled {
+            ops.queue.push_front(op);
+            for op in ops.queue.drain(..) {
+                this.push(op, OPERATION_CANCELED);
+            }
+        } else {
+            self.block_op(&mut handles.slab, eev, false, op);
+        }
+    }
+
+    pub fn add_write_op(
+        &self,
+        eev: &Uring,
+        this: &mut ThreadIoContext,
+        op: Box<Perform>,
+        err: SystemError,
+    ) {
+        let mut handles = self.mutex.lock().unwrap();
+        let ops = &mut UringRef(eev).output;
+        if err == SystemError::default() {
+            if ops.queue.is_empty() && !ops.blocked {
+                ops.blocked = true;
+                this.push(op, SystemError::default());
+            } else {
+                ops.queue.push_back(op);
+            }
+        } else if ops.canceled {
+            ops.queue.push_front(op);
+            for op in ops.queue.drain(..) {
+                this.push(op, OPERATION_CANCELED);
+            }
+        } else {
+            self.block_op(&mut handles.slab, eev, true, op);
+        }
+    }
+
+    pub fn next_read_op(&self, eev: &Uring, this: &mut ThreadIoContext) {
+        let ops = &mut UringRef(eev).input;
+        let _handles = self.mutex.lock().unwrap();
+        if ops.canceled {
+            ops.canceled = false;
+            for op in ops.queue.drain(..) {
+                this.push(op, OPERATION_CANCELED);
+            }
+        } else {
+            if let Some(op) = ops.queue.pop_front() {
+                this.push(op, SystemError::default());
+            } else {
+                ops.blocked = false;
+            }
+        }
+    }
+
+    pub fn next_write_op(&self, eev: &Uring, this: &mut ThreadIoContext) {
+        let ops = &mut UringRef(eev).output;
+        let _handles = self.mutex.lock().unwrap();
+        if ops.canceled {
+            ops.canceled = false;
+            for op in ops.queue.drain(..) {
+                this.push(op, OPERATION_CANCELED);
+            }
+        } else {
+            if let Some(op) = ops.queue.pop_front() {
+                this.push(op, SystemError::default());
+            } else {
+                ops.blocked = false;
+            }
+        }
+    }
+
+    pub fn cancel_ops(&self, eev: &Uring, ctx: &IoContext, err: SystemError) {
+        let _handles = self.mutex.lock().unwrap();
+        self.cancel_ops_nolock(eev, ctx, err)
+    }
+
+    pub fn cancel_all(&self, ctx: &IoContext, err: SystemError) {
+        let handles = self.mutex.lock().unwrap();
+        for eev in handles.registered.iter() {
+            self.cancel_ops_nolock(eev, ctx, err)
+        }
+    }
+
+    fn cancel_ops_nolock(&self, eev: &Uring, ctx: &IoContext, err: SystemError) {
+        for ops in &mut [&mut UringRef(eev).input, &mut UringRef(eev).output] {
+            if !ops.canceled {
+                ops.canceled = true;
+                if !ops.blocked {
+                    for op in ops.queue.drain(..) {
+                        ctx.do_post((op, err))
+                    }
+                } else if let Some(token) = ops.inflight {
+                    self.async_cancel(token)
+                }
+            }
+        }
+    }
+}
+
+impl AsRawFd for UringReactor {
+    /// Returns the ring watched by the external event loop, that is regarded as the thread blocking
+    /// in the poll after this, i.e. the requests are submitted at once.
+    fn as_raw_fd(&self) -> RawFd {
+        if !self.pollable.swap(true, Ordering::SeqCst) {
+            self.waiting.fetch_add(1, Ordering::SeqCst);
+            let _sq = self.sq.lock().unwrap();
+            let _ = self.ring.submit();
+        }
+        self.ring.as_raw_fd()
+    }
+}
+
+impl Drop for UringReactor {
+    fn drop(&mut self) {
+        self.intr.cleanup(self);
+    }
+}
+
+#[test]
+fn test_slab_stale_generation() {
+    use std::ptr;
+
+    let mut slab = Slab::default();
+    let a = slab.insert(Slot::Poll(ptr::null(), false));
+    assert!(a != IGNORED && (a & LINKED) == 0);
+    assert!(slab.remove(a).is_some());
+
+    // the slot is reused by the next generation.
+    let b = slab.insert(Slot::Poll(ptr::null(), true));
+    assert_eq!(a & 0xffff_ffff, b & 0xffff_ffff);
+    assert!(a != b);
+    assert!(slab.remove(a).is_none());
+    assert!(slab.remove(b).is_some());
+    assert!(slab.remove(b).is_none());
+}
@@ -1,11 +1,20 @@
 #![allow(unreachable_patterns)]
 
 use ffi::{AsRawFd, Timeout, SystemError, TRY_AGAIN, WOULD_BLOCK, INTERRUPTED, OPERATION_CANCELED,
-          read, recv, recvfrom, readable};
+          MESSAGE_SIZE, MSG_PEEK, RawFd, iovec, pread, read, readv, recv, recvfrom, recvfrom_into,
+          recvmsg, recvmsg_fds, readable, ioctl};
 use core::{Protocol, Socket, AsIoContext, Exec, Perform, ThreadIoContext};
 use handler::{Complete, Handler, AsyncReadOp};
+use socket_base::BytesReadable;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use ffi::{recvmsg_hoplimit, recvmsg_origdst, splice};
+#[cfg(all(target_os = "linux", feature = "uring"))]
+use ffi::{uring_result, CONNECTION_ABORTED, OPERATION_NOT_SUPPORTED};
+#[cfg(all(target_os = "linux", feature = "uring"))]
+use io_uring::{opcode, squeue, types};
 
 use std::io;
+use std::cmp;
 use std::slice;
 use std::marker::PhantomData;
 
@@ -15,6 +24,18 @@ pub trait Reader: 'static {
     type Output: Send;
 
     fn read_op(&self, s: &Self::Socket, buf: &mut [u8]) -> Result<Self::Output, SystemError>;
+
+    /// Returns the request of the `read_op` to the `io_uring`, if it is supported.
+    #[cfg(all(target_os = "linux", feature = "uring"))]
+    fn uring_entry(&self, _s: &Self::Socket, _buf: &mut [u8]) -> Option<squeue::Entry> {
+        None
+    }
+
+    /// Returns the output of the request from the result of its completion.
+    #[cfg(all(target_os = "linux", feature = "uring"))]
+    fn uring_output(&self, _res: i32) -> Result<Self::Output, SystemError> {
+        Err(OPERATION_NOT_SUPPORTED)
+    }
 }
 
 pub struct Read<S> {
@@ -38,6 +59,81 @@ where
     fn read_op(&self, s: &Self::Socket, buf: &mut [u8]) -> Result<Self::Output, SystemError> {
         read(s, buf)
     }
+
+    #[cfg(all(target_os = "linux", feature = "uring"))]
+    fn uring_entry(&self, s: &Self::Socket, buf: &mut [u8]) -> Option<squeue::Entry> {
+        let len = cmp::min(buf.len(), u32::max_value() as usize) as u32;
+        // The offset of -1 reads from the current position, that is also valid for the pipes.
+        let read = opcode::Read::new(types::Fd(s.as_raw_fd()), buf.as_mut_ptr(), len);
+        Some(read.offset(u64::max_value()).build())
+    }
+
+    #[cfg(all(target_os = "linux", feature = "uring"))]
+    fn uring_output(&self, res: i32) -> Result<Self::Output, SystemError> {
+        match uring_result(res)? {
+            0 => Err(CONNECTION_ABORTED),
+            len => Ok(len),
+        }
+    }
+}
+
+pub struct ReadAt<S> {
+    offset: u64,
+    _marker: PhantomData<S>,
+}
+
+impl<S> ReadAt<S> {
+    pub fn new(offset: u64) -> Self {
+        ReadAt {
+            offset: offset,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<S> Reader for ReadAt<S>
+where
+    S: AsRawFd + AsyncReadOp,
+{
+    type Socket = S;
+
+    type Output = usize;
+
+    fn read_op(&self, s: &Self::Socket, buf: &mut [u8]) -> Result<Self::Output, SystemError> {
+        pread(s, buf, self.offset)
+    }
+}
+
+pub struct ReadV<S> {
+    iov: Vec<iovec>,
+    _marker: PhantomData<S>,
+}
+
+impl<S> ReadV<S> {
+    pub fn new(bufs: Vec<&mut [u8]>) -> Self {
+        ReadV {
+            iov: bufs.into_iter()
+                .map(|buf| iovec {
+                    iov_base: buf.as_mut_ptr() as *mut _,
+                    iov_len: buf.len(),
+                })
+                .collect(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<S> Reader for ReadV<S>
+where
+    S: AsRawFd + AsyncReadOp,
+{
+    type Socket = S;
+
+    type Output = usize;
+
+    fn read_op(&self, s: &Self::Socket, _: &mut [u8]) -> Result<Self::Output, SystemError> {
+        readv(s, &self.iov)
+    }
 }
 
 pub struct Recv<P, S> {
@@ -66,6 +162,51 @@ where
     fn read_op(&self, s: &Self::Socket, buf: &mut [u8]) -> Result<Self::Output, SystemError> {
         recv(s, buf, self.flags)
     }
+
+    #[cfg(all(target_os = "linux", feature = "uring"))]
+    fn uring_entry(&self, s: &Self::Socket, buf: &mut [u8]) -> Option<squeue::Entry> {
+        let len = cmp::min(buf.len(), u32::max_value() as usize) as u32;
+        let recv = opcode::Recv::new(types::Fd(s.as_raw_fd()), buf.as_mut_ptr(), len);
+        Some(recv.flags(self.flags).build())
+    }
+
+    #[cfg(all(target_os = "linux", feature = "uring"))]
+    fn uring_output(&self, res: i32) -> Result<Self::Output, SystemError> {
+        match uring_result(res)? {
+            0 => Err(CONNECTION_ABORTED),
+            len => Ok(len),
+        }
+    }
+}
+
+pub struct RecvFds<P, S> {
+    flags: i32,
+    max_fds: usize,
+    _marker: PhantomData<(P, S)>,
+}
+
+impl<P, S> RecvFds<P, S> {
+    pub fn new(flags: i32, max_fds: usize) -> Self {
+        RecvFds {
+            flags: flags,
+            max_fds: max_fds,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<P, S> Reader for RecvFds<P, S>
+where
+    P: Protocol,
+    S: Socket<P> + AsyncReadOp,
+{
+    type Socket = S;
+
+    type Output = (usize, Vec<RawFd>);
+
+    fn read_op(&self, s: &Self::Socket, buf: &mut [u8]) -> Result<Self::Output, SystemError> {
+        recvmsg_fds(s, buf, self.flags, self.max_fds)
+    }
 }
 
 pub struct RecvFrom<P, S> {
@@ -96,6 +237,220 @@ where
     }
 }
 
+pub struct RecvFromInto<P, S>
+where
+    P: Protocol,
+{
+    flags: i32,
+    ep: *mut P::Endpoint,
+    _marker: PhantomData<S>,
+}
+
+impl<P, S> RecvFromInto<P, S>
+where
+    P: Protocol,
+{
+    pub fn new(flags: i32, ep: &mut P::Endpoint) -> Self {
+        RecvFromInto {
+            flags: flags,
+            ep: ep,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<P, S> Reader for RecvFromInto<P, S>
+where
+    P: Protocol,
+    S: Socket<P> + AsyncReadOp,
+{
+    type Socket = S;
+
+    type Output = usize;
+
+    fn read_op(&self, s: &Self::Socket, buf: &mut [u8]) -> Result<Self::Output, SystemError> {
+        recvfrom_into(s, buf, self.flags, unsafe { &mut *self.ep })
+    }
+}
+
+pub struct RecvFromTrunc<P, S> {
+    flags: i32,
+    _marker: PhantomData<(P, S)>,
+}
+
+impl<P, S> RecvFromTrunc<P, S> {
+    pub fn new(flags: i32) -> Self {
+        RecvFromTrunc {
+            flags: flags,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<P, S> Reader for RecvFromTrunc<P, S>
+where
+    P: Protocol,
+    S: Socket<P> + AsyncReadOp,
+{
+    type Socket = S;
+
+    type Output = (usize, P::Endpoint, bool);
+
+    fn read_op(&self, s: &Self::Socket, buf: &mut [u8]) -> Result<Self::Output, SystemError> {
+        recvmsg(s, buf, self.flags)
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub struct RecvFromOrigDst<P, S> {
+    flags: i32,
+    _marker: PhantomData<(P, S)>,
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<P, S> RecvFromOrigDst<P, S> {
+    pub fn new(flags: i32) -> Self {
+        RecvFromOrigDst {
+            flags: flags,
+            _marker: PhantomData,
+        }
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<P, S> Reader for RecvFromOrigDst<P, S>
+where
+    P: Protocol,
+    S: Socket<P> + AsyncReadOp,
+{
+    type Socket = S;
+
+    type Output = (usize, P::Endpoint, Option<P::Endpoint>);
+
+    fn read_op(&self, s: &Self::Socket, buf: &mut [u8]) -> Result<Self::Output, SystemError> {
+        recvmsg_origdst(s, buf, self.flags)
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub struct RecvFromHopLimit<P, S> {
+    flags: i32,
+    _marker: PhantomData<(P, S)>,
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<P, S> RecvFromHopLimit<P, S> {
+    pub fn new(flags: i32) -> Self {
+        RecvFromHopLimit {
+            flags: flags,
+            _marker: PhantomData,
+        }
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<P, S> Reader for RecvFromHopLimit<P, S>
+where
+    P: Protocol,
+    S: Socket<P> + AsyncReadOp,
+{
+    type Socket = S;
+
+    type Output = (usize, P::Endpoint, Option<u8>);
+
+    fn read_op(&self, s: &Self::Socket, buf: &mut [u8]) -> Result<Self::Output, SystemError> {
+        recvmsg_hoplimit(s, buf, self.flags)
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub struct SpliceToPipe<S> {
+    fd: RawFd,
+    len: usize,
+    flags: i32,
+    _marker: PhantomData<S>,
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<S> SpliceToPipe<S> {
+    pub fn new(fd: RawFd, len: usize, flags: i32) -> Self {
+        SpliceToPipe {
+            fd: fd,
+            len: len,
+            flags: flags,
+            _marker: PhantomData,
+        }
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<S> Reader for SpliceToPipe<S>
+where
+    S: AsRawFd + AsyncReadOp,
+{
+    type Socket = S;
+
+    type Output = usize;
+
+    fn read_op(&self, s: &Self::Socket, _: &mut [u8]) -> Result<Self::Output, SystemError> {
+        splice(s, &self.fd, self.len, self.flags)
+    }
+}
+
+pub struct RecvFromGrow<P, S> {
+    flags: i32,
+    vec: *mut Vec<u8>,
+    max: usize,
+    _marker: PhantomData<(P, S)>,
+}
+
+impl<P, S> RecvFromGrow<P, S> {
+    pub fn new(flags: i32, vec: &mut Vec<u8>, max: usize) -> Self {
+        RecvFromGrow {
+            flags: flags,
+            vec: vec,
+            max: max,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<P, S> Reader for RecvFromGrow<P, S>
+where
+    P: Protocol,
+    S: Socket<P> + AsyncReadOp,
+{
+    type Socket = S;
+
+    type Output = (usize, P::Endpoint);
+
+    fn read_op(&self, s: &Self::Socket, _: &mut [u8]) -> Result<Self::Output, SystemError> {
+        let vec = unsafe { &mut *self.vec };
+
+        // The FIONREAD is a hint of the next datagram size, that is verified by MSG_PEEK.
+        let mut bytes = BytesReadable::default();
+        let hint = ioctl(s, &mut bytes).map(|_| bytes.get()).unwrap_or(0);
+        let mut len = cmp::min(cmp::max(cmp::max(hint, vec.capacity()), 1), self.max);
+        loop {
+            vec.resize(len, 0);
+            let (_, _, trunc) = recvmsg(s, &mut vec[..], self.flags | MSG_PEEK)?;
+            if !trunc {
+                break;
+            }
+            if len == self.max {
+                // The datagram is larger than the limit, discards it.
+                let _ = recvmsg(s, &mut vec[..], self.flags);
+                vec.clear();
+                return Err(MESSAGE_SIZE);
+            }
+            len = cmp::min(len.saturating_mul(2), self.max);
+        }
+        let (len, ep, _) = recvmsg(s, &mut vec[..], self.flags)?;
+        vec.truncate(len);
+        Ok((len, ep))
+    }
+}
+
 struct AsyncRead<F, R>
 where
     R: Reader,
@@ -155,6 +510,24 @@ where
             self.failure(this, err.into())
         }
     }
+
+    #[cfg(all(target_os = "linux", feature = "uring"))]
+    fn uring_entry(&mut self) -> Option<squeue::Entry> {
+        let soc = unsafe { &*self.soc };
+        let buf = unsafe { slice::from_raw_parts_mut(self.buf, self.len) };
+        self.reader.uring_entry(soc, buf)
+    }
+
+    #[cfg(all(target_os = "linux", feature = "uring"))]
+    fn uring_complete(self: Box<Self>, this: &mut ThreadIoContext, res: i32) {
+        match self.reader.uring_output(res) {
+            Ok(res) => self.success(this, res),
+            Err(INTERRUPTED) | Err(TRY_AGAIN) | Err(WOULD_BLOCK) => {
+                self.perform(this, SystemError::default())
+            }
+            Err(err) => self.failure(this, err.into()),
+        }
+    }
 }
 
 impl<F, R> Exec for AsyncRead<F, R>
@@ -0,0 +1,295 @@
+use ffi::{AsRawFd, RawFd, SystemError, WOULD_BLOCK};
+use reactor::SocketImpl;
+use core::{AsIoContext, IoContext, ThreadIoContext, Perform, Cancel};
+use handler::{Handler, AsyncReadOp};
+use read_ops::{Reader, async_read_op, blocking_read_op, nonblocking_read_op};
+
+use std::io;
+use std::fmt;
+use std::cmp;
+use std::time::Duration;
+use libc::{epoll_create1, epoll_ctl, epoll_wait, epoll_event, EPOLL_CLOEXEC, EPOLL_CTL_ADD,
+           EPOLL_CTL_MOD, EPOLL_CTL_DEL, EPOLLIN, EPOLLOUT, EPOLLERR, EPOLLHUP};
+
+/// The kinds of the readiness to be notified.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Interest {
+    Read,
+    Write,
+    ReadWrite,
+}
+
+impl Interest {
+    fn events(&self) -> u32 {
+        match *self {
+            Interest::Read => EPOLLIN as u32,
+            Interest::Write => EPOLLOUT as u32,
+            Interest::ReadWrite => (EPOLLIN | EPOLLOUT) as u32,
+        }
+    }
+}
+
+/// The file descriptor and the kinds of the readiness notified by the `ReadinessSet`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Readiness {
+    fd: RawFd,
+    events: u32,
+}
+
+impl Readiness {
+    pub fn fd(&self) -> RawFd {
+        self.fd
+    }
+
+    /// Returns true if the file descriptor has an error or is hung up.
+    pub fn is_error(&self) -> bool {
+        self.events & (EPOLLERR | EPOLLHUP) as u32 != 0
+    }
+
+    pub fn is_readable(&self) -> bool {
+        self.events & EPOLLIN as u32 != 0
+    }
+
+    pub fn is_writable(&self) -> bool {
+        self.events & EPOLLOUT as u32 != 0
+    }
+}
+
+impl fmt::Debug for Readiness {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Readiness")
+            .field("fd", &self.fd)
+            .field("readable", &self.is_readable())
+            .field("writable", &self.is_writable())
+            .field("error", &self.is_error())
+            .finish()
+    }
+}
+
+struct ReadReadiness {
+    max: usize,
+}
+
+impl Reader for ReadReadiness {
+    type Socket = ReadinessSet;
+
+    type Output = Vec<Readiness>;
+
+    fn read_op(&self, s: &Self::Socket, _: &mut [u8]) -> Result<Self::Output, SystemError> {
+        let mut evs = vec![epoll_event { events: 0, u64: 0 }; cmp::max(self.max, 1)];
+        match unsafe { epoll_wait(s.as_raw_fd(), evs.as_mut_ptr(), evs.len() as i32, 0) } {
+            -1 => Err(SystemError::last_error()),
+            0 => Err(WOULD_BLOCK),
+            len => Ok(
+                evs[..len as usize]
+                    .iter()
+                    .map(|ev| {
+                        Readiness {
+                            fd: ev.u64 as RawFd,
+                            events: ev.events,
+                        }
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Provides the readiness notifications of many file descriptors to a single handler.
+///
+/// The file descriptors are watched in the level-triggered mode like `poll`, so the readiness is
+/// notified again until it is consumed by the nonblocking operations of the file descriptors.
+///
+/// # Examples
+///
+/// ```
+/// use std::io;
+/// use std::sync::Arc;
+/// use asyncio::{IoContext, ReadinessSet, Readiness, Interest, wrap};
+/// use asyncio::ip::{IpProtocol, UdpSocket, UdpEndpoint, Udp, IpAddrV4};
+///
+/// fn on_ready(_: Arc<ReadinessSet>, res: io::Result<Vec<Readiness>>) {
+///   let ready = res.unwrap();
+///   assert_eq!(ready.len(), 1);
+///   assert!(ready[0].is_writable());
+/// }
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let soc = UdpSocket::new(ctx, Udp::v4()).unwrap();
+/// soc.bind(&UdpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+///
+/// let set = Arc::new(ReadinessSet::new(ctx).unwrap());
+/// set.add(&soc, Interest::Write).unwrap();
+/// set.async_wait(16, wrap(&set, on_ready));
+/// ctx.run();
+/// ```
+pub struct ReadinessSet {
+    pimpl: Box<SocketImpl<()>>,
+}
+
+impl ReadinessSet {
+    pub fn new(ctx: &IoContext) -> io::Result<Self> {
+        match unsafe { epoll_create1(EPOLL_CLOEXEC) } {
+            -1 => Err(SystemError::last_error().into()),
+            fd => Ok(ReadinessSet { pimpl: SocketImpl::new(ctx, fd, ()) }),
+        }
+    }
+
+    /// Adds the file descriptor to be watched.
+    ///
+    /// The file descriptor must be removed before it is closed.
+    pub fn add<T>(&self, fd: &T, interest: Interest) -> io::Result<()>
+    where
+        T: AsRawFd,
+    {
+        self.control(EPOLL_CTL_ADD, fd.as_raw_fd(), interest.events())
+    }
+
+    /// Asynchronously waits until any of the file descriptors is ready, the handler takes at most
+    /// `max` readiness.
+    pub fn async_wait<F>(&self, max: usize, handler: F) -> F::Output
+    where
+        F: Handler<Vec<Readiness>, io::Error>,
+    {
+        async_read_op(
+            self,
+            &[],
+            &self.pimpl.timeout,
+            handler,
+            ReadReadiness { max: max },
+        )
+    }
+
+    fn control(&self, op: i32, fd: RawFd, events: u32) -> io::Result<()> {
+        let mut ev = epoll_event {
+            events: events,
+            u64: fd as u64,
+        };
+        match unsafe { epoll_ctl(self.as_raw_fd(), op, fd, &mut ev) } {
+            -1 => Err(SystemError::last_error().into()),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn get_timeout(&self) -> Duration {
+        self.pimpl.timeout.get()
+    }
+
+    /// Changes the interest of the file descriptor.
+    pub fn modify<T>(&self, fd: &T, interest: Interest) -> io::Result<()>
+    where
+        T: AsRawFd,
+    {
+        self.control(EPOLL_CTL_MOD, fd.as_raw_fd(), interest.events())
+    }
+
+    /// Takes at most `max` readiness, returns the `EAGAIN` error if none is ready.
+    pub fn nonblocking_wait(&self, max: usize) -> io::Result<Vec<Readiness>> {
+        nonblocking_read_op(self, &mut [], ReadReadiness { max: max })
+    }
+
+    /// Removes the file descriptor from the set.
+    pub fn remove<T>(&self, fd: &T) -> io::Result<()>
+    where
+        T: AsRawFd,
+    {
+        self.control(EPOLL_CTL_DEL, fd.as_raw_fd(), 0)
+    }
+
+    pub fn set_timeout(&self, timeout: Duration) -> io::Result<()> {
+        Ok(self.pimpl.timeout.set(timeout)?)
+    }
+
+    /// Waits until any of the file descriptors is ready and takes at most `max` readiness.
+    pub fn wait(&self, max: usize) -> io::Result<Vec<Readiness>> {
+        blocking_read_op(self, &mut [], &self.pimpl.timeout, ReadReadiness { max: max })
+    }
+}
+
+unsafe impl Send for ReadinessSet {}
+
+unsafe impl Sync for ReadinessSet {}
+
+unsafe impl AsIoContext for ReadinessSet {
+    fn as_ctx(&self) -> &IoContext {
+        self.pimpl.as_ctx()
+    }
+}
+
+impl AsRawFd for ReadinessSet {
+    fn as_raw_fd(&self) -> RawFd {
+        self.pimpl.as_raw_fd()
+    }
+}
+
+impl Cancel for ReadinessSet {
+    fn cancel(&self) {
+        self.pimpl.cancel()
+    }
+}
+
+impl AsyncReadOp for ReadinessSet {
+    fn add_read_op(&self, this: &mut ThreadIoContext, op: Box<Perform>, err: SystemError) {
+        self.pimpl.add_read_op(this, op, err)
+    }
+
+    fn next_read_op(&self, this: &mut ThreadIoContext) {
+        self.pimpl.next_read_op(this)
+    }
+}
+
+impl fmt::Debug for ReadinessSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ReadinessSet({})", self.as_raw_fd())
+    }
+}
+
+#[test]
+fn test_readiness_set() {
+    use ffi::{pipe, close};
+
+    let ctx = &IoContext::new().unwrap();
+    let set = ReadinessSet::new(ctx).unwrap();
+    let (rfd, wfd) = pipe().unwrap();
+    let (rfd, wfd) = (Fd(rfd), Fd(wfd));
+
+    struct Fd(RawFd);
+
+    impl AsRawFd for Fd {
+        fn as_raw_fd(&self) -> RawFd {
+            self.0
+        }
+    }
+
+    impl Drop for Fd {
+        fn drop(&mut self) {
+            close(self.0);
+        }
+    }
+
+    set.add(&rfd, Interest::Read).unwrap();
+    assert_eq!(
+        set.nonblocking_wait(16).unwrap_err().kind(),
+        io::ErrorKind::WouldBlock
+    );
+
+    set.add(&wfd, Interest::Write).unwrap();
+    let ready = set.wait(16).unwrap();
+    assert_eq!(ready.len(), 1);
+    assert_eq!(ready[0].fd(), wfd.0);
+    assert!(ready[0].is_writable() && !ready[0].is_readable());
+
+    // the level-triggered readiness is notified until consumed.
+    set.remove(&wfd).unwrap();
+    unsafe { ::libc::write(wfd.0, b"x".as_ptr() as *const _, 1) };
+    for _ in 0..2 {
+        let ready = set.wait(16).unwrap();
+        assert_eq!(ready.len(), 1);
+        assert_eq!(ready[0].fd(), rfd.0);
+        assert!(ready[0].is_readable());
+    }
+
+    drop(wfd);
+    assert!(set.wait(16).unwrap()[0].is_error());
+    set.remove(&rfd).unwrap();
+}
@@ -0,0 +1,173 @@
+use core::{Endpoint, IoContext, Protocol};
+use socket_base::ReusePort;
+use socket_listener::SocketListener;
+
+use std::io;
+use std::ops::Index;
+use std::slice;
+
+/// A group of the listeners bound to the same endpoint by the `ReusePort`, that the kernel
+/// distributes the incoming connections across the listeners.
+///
+/// Each listener is registered on its own `IoContext`, e.g. one per worker thread, so that the
+/// accepts are load-balanced without sharing the listener between the threads. The connections
+/// are distributed by the hash of the 4-tuple unless the steering program is attached to any one
+/// of the listeners, e.g. the `AttachReusePortCbpf` on Linux.
+///
+/// # Examples
+///
+/// ```
+/// use asyncio::{IoContext, ReusePortListenerGroup};
+/// use asyncio::ip::{IpProtocol, IpAddrV4, Tcp, TcpEndpoint, TcpSocket};
+///
+/// let ctxs: Vec<IoContext> = (0..4).map(|_| IoContext::new().unwrap()).collect();
+/// let group: ReusePortListenerGroup<Tcp> = ReusePortListenerGroup::new(
+///     &ctxs,
+///     &TcpEndpoint::new(IpAddrV4::loopback(), 0),
+/// ).unwrap();
+/// assert_eq!(group.len(), 4);
+///
+/// let ep = group.local_endpoint().unwrap();
+/// let client = TcpSocket::new(&ctxs[0], Tcp::v4()).unwrap();
+/// client.connect(&ep).unwrap();
+/// ```
+pub struct ReusePortListenerGroup<P> {
+    listeners: Vec<SocketListener<P>>,
+}
+
+impl<P> ReusePortListenerGroup<P>
+where
+    P: Protocol,
+{
+    /// Returns a group of the listening sockets, one per `IoContext`, that are bound to the
+    /// endpoint.
+    ///
+    /// The port 0 of the endpoint is assigned by the kernel for the first listener, and is shared
+    /// by the others. The listeners are in the order of the `IoContext`s, that is the index of the
+    /// reuseport group.
+    pub fn new(ctxs: &[IoContext], ep: &P::Endpoint) -> io::Result<Self> {
+        let mut listeners = Vec::with_capacity(ctxs.len());
+        let mut ep = ep.clone();
+        for ctx in ctxs {
+            let soc = SocketListener::new(ctx, ep.protocol())?;
+            soc.set_option(ReusePort::new(true))?;
+            soc.bind(&ep)?;
+            soc.listen()?;
+            if listeners.is_empty() {
+                ep = soc.local_endpoint()?;
+            }
+            listeners.push(soc);
+        }
+        Ok(ReusePortListenerGroup { listeners: listeners })
+    }
+
+    /// Returns the listener of the index.
+    pub fn get(&self, index: usize) -> Option<&SocketListener<P>> {
+        self.listeners.get(index)
+    }
+
+    /// Returns true if the group has no listeners.
+    pub fn is_empty(&self) -> bool {
+        self.listeners.is_empty()
+    }
+
+    /// Returns an iterator over the listeners.
+    pub fn iter(&self) -> slice::Iter<'_, SocketListener<P>> {
+        self.listeners.iter()
+    }
+
+    /// Returns the number of the listeners.
+    pub fn len(&self) -> usize {
+        self.listeners.len()
+    }
+
+    /// Returns the endpoint shared by the listeners.
+    pub fn local_endpoint(&self) -> io::Result<P::Endpoint> {
+        match self.listeners.first() {
+            Some(soc) => soc.local_endpoint(),
+            None => Err(io::Error::new(io::ErrorKind::NotFound, "no listeners")),
+        }
+    }
+
+    /// Returns the listeners, e.g. to move each listener to its worker thread.
+    pub fn into_inner(self) -> Vec<SocketListener<P>> {
+        self.listeners
+    }
+}
+
+impl<P> Index<usize> for ReusePortListenerGroup<P> {
+    type Output = SocketListener<P>;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.listeners[index]
+    }
+}
+
+impl<'a, P> IntoIterator for &'a ReusePortListenerGroup<P> {
+    type Item = &'a SocketListener<P>;
+
+    type IntoIter = slice::Iter<'a, SocketListener<P>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.listeners.iter()
+    }
+}
+
+#[test]
+fn test_reuse_port_listener_group() {
+    use std::thread;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use handler::wrap;
+    use ip::{IpAddrV4, IpProtocol, Tcp, TcpEndpoint, TcpSocket, TcpListener};
+
+    static ACCEPTED: AtomicUsize = AtomicUsize::new(0);
+
+    fn on_accept(soc: Arc<TcpListener>, res: io::Result<(TcpSocket, TcpEndpoint)>) {
+        if res.is_ok() {
+            ACCEPTED.fetch_add(1, Ordering::SeqCst);
+            soc.async_accept(wrap(&soc, on_accept));
+        }
+    }
+
+    let ctxs: Vec<IoContext> = (0..3).map(|_| IoContext::new().unwrap()).collect();
+    let group: ReusePortListenerGroup<Tcp> =
+        ReusePortListenerGroup::new(&ctxs, &TcpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+    let ep = group.local_endpoint().unwrap();
+    assert!(ep.port() != 0);
+    assert!(group.iter().all(|soc| soc.local_endpoint().unwrap() == ep));
+
+    // The other listener is not allowed to share the port.
+    let other = TcpListener::new(&ctxs[0], Tcp::v4()).unwrap();
+    assert!(other.bind(&ep).is_err());
+
+    let mut listeners = Vec::new();
+    for soc in group.into_inner() {
+        let soc = Arc::new(soc);
+        soc.async_accept(wrap(&soc, on_accept));
+        listeners.push(soc);
+    }
+    let workers: Vec<_> = ctxs.iter()
+        .map(|ctx| {
+            let ctx = ctx.clone();
+            thread::spawn(move || ctx.run())
+        })
+        .collect();
+
+    let clients: Vec<_> = (0..16)
+        .map(|_| {
+            let soc = TcpSocket::new(&ctxs[0], Tcp::v4()).unwrap();
+            soc.connect(&ep).unwrap();
+            soc
+        })
+        .collect();
+    while ACCEPTED.load(Ordering::SeqCst) < clients.len() {
+        thread::yield_now();
+    }
+    for ctx in &ctxs {
+        ctx.stop();
+    }
+    for th in workers {
+        th.join().unwrap();
+    }
+}
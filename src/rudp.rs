@@ -0,0 +1,603 @@
+//! Provides a reliable and ordered byte stream over the connected datagram socket.
+//!
+//! Each segment has a header of the segment type and the sequence number, and is retransmitted
+//! by the timer until the peer acknowledges it. Up to a window of segments are in flight, and the
+//! segments received out of order are held until the missing ones arrive.
+//!
+//! The protocol has no handshake, so both ends are connected to each other by `DgramSocket::connect`
+//! before creating the streams (e.g. after the NAT traversal).
+
+use ffi::{SystemError, Timeout, BROKEN_PIPE, CONNECTION_REFUSED, OPERATION_CANCELED, TIMED_OUT};
+use core::{AsIoContext, IoContext, Protocol, ThreadIoContext, Cancel};
+use handler::{Handler, Complete, BoxHandler, Success, Failure};
+use dgram_socket::DgramSocket;
+use stream::Stream;
+use SteadyTimer;
+
+use std::io;
+use std::cmp;
+use std::slice;
+use std::cell::UnsafeCell;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+const DATA: u8 = 0;
+const ACK: u8 = 1;
+const FIN: u8 = 2;
+
+const HEADER_LEN: usize = 5;
+
+/// The maximum payload of a segment, that fits in the minimum MTU of IPv6.
+pub const MAX_SEGMENT: usize = 1200;
+
+/// The number of segments sent without acknowledgment.
+pub const WINDOW: usize = 64;
+
+const MAX_RETRIES: u32 = 8;
+
+fn initial_rto() -> Duration {
+    Duration::from_millis(200)
+}
+
+fn before(a: u32, b: u32) -> bool {
+    (a.wrapping_sub(b) as i32) < 0
+}
+
+fn segment(kind: u8, seq: u32, data: &[u8]) -> Vec<u8> {
+    let mut seg = Vec::with_capacity(HEADER_LEN + data.len());
+    seg.push(kind);
+    seg.extend_from_slice(&seq.to_be_bytes());
+    seg.extend_from_slice(data);
+    seg
+}
+
+struct PendingOp {
+    buf: *const u8,
+    len: usize,
+    handler: BoxHandler<usize, io::Error>,
+}
+
+type Done = Vec<(BoxHandler<usize, io::Error>, Result<usize, SystemError>)>;
+
+struct RudpState {
+    snd_nxt: u32,
+    unacked: VecDeque<(u32, Vec<u8>)>,
+    fin_sent: bool,
+    rcv_nxt: u32,
+    ooo: BTreeMap<u32, (u8, Vec<u8>)>,
+    ready: VecDeque<u8>,
+    eof: bool,
+    reader: Option<PendingOp>,
+    writer: Option<PendingOp>,
+    receiving: bool,
+    retransmitting: bool,
+    retries: u32,
+    rto: Duration,
+    error: Option<SystemError>,
+}
+
+impl RudpState {
+    fn send<P: Protocol>(&mut self, soc: &DgramSocket<P>, kind: u8, data: &[u8]) {
+        let seg = segment(kind, self.snd_nxt, data);
+        // The lost segment is recovered by the retransmission.
+        let _ = soc.nonblocking_send(&seg, 0);
+        self.unacked.push_back((self.snd_nxt, seg));
+        self.snd_nxt = self.snd_nxt.wrapping_add(1);
+    }
+
+    fn write<P: Protocol>(&mut self, soc: &DgramSocket<P>, buf: *const u8, len: usize) -> usize {
+        let len = cmp::min(len, MAX_SEGMENT);
+        self.send(soc, DATA, unsafe { slice::from_raw_parts(buf, len) });
+        len
+    }
+
+    fn read(&mut self, buf: *const u8, len: usize) -> usize {
+        let len = cmp::min(len, self.ready.len());
+        let buf = unsafe { slice::from_raw_parts_mut(buf as *mut u8, len) };
+        for (dst, src) in buf.iter_mut().zip(self.ready.drain(..len)) {
+            *dst = src;
+        }
+        len
+    }
+
+    fn deliver(&mut self, kind: u8, data: &[u8]) {
+        if kind == FIN {
+            self.eof = true;
+        } else if !self.eof {
+            self.ready.extend(data);
+        }
+        self.rcv_nxt = self.rcv_nxt.wrapping_add(1);
+    }
+
+    fn on_segment<P: Protocol>(&mut self, soc: &DgramSocket<P>, seg: &[u8]) {
+        if seg.len() < HEADER_LEN {
+            return;
+        }
+        let seq = u32::from_be_bytes([seg[1], seg[2], seg[3], seg[4]]);
+        match seg[0] {
+            ACK => {
+                let mut acked = false;
+                while self.unacked.front().map_or(false, |&(s, _)| before(s, seq)) {
+                    self.unacked.pop_front();
+                    acked = true;
+                }
+                if acked {
+                    self.retries = 0;
+                    self.rto = initial_rto();
+                }
+            }
+            kind @ DATA | kind @ FIN => {
+                if seq == self.rcv_nxt {
+                    self.deliver(kind, &seg[HEADER_LEN..]);
+                    while let Some((kind, data)) = self.ooo.remove(&self.rcv_nxt) {
+                        self.deliver(kind, &data);
+                    }
+                } else if before(self.rcv_nxt, seq) &&
+                           before(seq, self.rcv_nxt.wrapping_add(WINDOW as u32))
+                {
+                    self.ooo.insert(seq, (kind, seg[HEADER_LEN..].to_vec()));
+                }
+                let _ = soc.nonblocking_send(&segment(ACK, self.rcv_nxt, &[]), 0);
+            }
+            _ => (),
+        }
+    }
+
+    fn complete<P: Protocol>(&mut self, soc: &DgramSocket<P>, done: &mut Done) {
+        if let Some(op) = self.reader.take() {
+            if let Some(err) = self.error {
+                done.push((op.handler, Err(err)));
+            } else if !self.ready.is_empty() {
+                let len = self.read(op.buf, op.len);
+                done.push((op.handler, Ok(len)));
+            } else if self.eof {
+                done.push((op.handler, Ok(0)));
+            } else {
+                self.reader = Some(op);
+            }
+        }
+        if let Some(op) = self.writer.take() {
+            if let Some(err) = self.error {
+                done.push((op.handler, Err(err)));
+            } else if self.unacked.len() < WINDOW {
+                let len = self.write(soc, op.buf, op.len);
+                done.push((op.handler, Ok(len)));
+            } else {
+                self.writer = Some(op);
+            }
+        }
+    }
+
+    fn fail(&mut self, err: SystemError, done: &mut Done) {
+        if self.error.is_none() {
+            self.error = Some(err);
+        }
+        if let Some(op) = self.reader.take() {
+            done.push((op.handler, Err(err)));
+        }
+        if let Some(op) = self.writer.take() {
+            done.push((op.handler, Err(err)));
+        }
+    }
+}
+
+struct RudpImpl<P> {
+    soc: DgramSocket<P>,
+    timer: SteadyTimer,
+    timeout: Timeout,
+    state: Mutex<RudpState>,
+    rbuf: UnsafeCell<Vec<u8>>,
+}
+
+unsafe impl<P> Send for RudpImpl<P> {}
+
+unsafe impl<P> Sync for RudpImpl<P> {}
+
+/// Starts the receiving and the retransmission timer if needed.
+fn kick<P>(imp: &Arc<RudpImpl<P>>, st: &mut RudpState) -> (bool, bool)
+where
+    P: Protocol,
+{
+    let recv = !st.receiving && st.error.is_none() &&
+        (st.reader.is_some() || st.writer.is_some() || !st.unacked.is_empty());
+    if recv {
+        st.receiving = true;
+    }
+    let timer = !st.retransmitting && st.error.is_none() && !st.unacked.is_empty();
+    if timer {
+        st.retransmitting = true;
+        imp.timer.expires_from_now(st.rto);
+    }
+    (recv, timer)
+}
+
+fn resume<P>(imp: &Arc<RudpImpl<P>>, done: Done, (recv, timer): (bool, bool))
+where
+    P: Protocol,
+{
+    for (handler, res) in done {
+        match res {
+            Ok(len) => imp.soc.as_ctx().do_dispatch(Success::new(len, handler)),
+            Err(err) => imp.soc.as_ctx().do_dispatch(Failure::new(err, handler)),
+        }
+    }
+    if recv {
+        let buf = unsafe { &mut *imp.rbuf.get() };
+        imp.soc.async_receive(buf, 0, RudpRecv { imp: imp.clone() })
+    }
+    if timer {
+        imp.timer.async_wait(RudpTimer { imp: imp.clone() })
+    }
+}
+
+struct RudpRecv<P> {
+    imp: Arc<RudpImpl<P>>,
+}
+
+impl<P> Handler<usize, io::Error> for RudpRecv<P>
+where
+    P: Protocol,
+{
+    type Output = ();
+
+    type WrappedHandler = Self;
+
+    fn wrap<W>(self, ctx: &IoContext, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx, self)
+    }
+
+    fn wrap_timeout<W>(self, ctx: &Cancel, _: &Timeout, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx.as_ctx(), self)
+    }
+}
+
+impl<P> Complete<usize, io::Error> for RudpRecv<P>
+where
+    P: Protocol,
+{
+    fn success(self, this: &mut ThreadIoContext, len: usize) {
+        let RudpRecv { imp } = self;
+        let mut done = Vec::new();
+        let (next, idle) = {
+            let mut st = imp.state.lock().unwrap();
+            st.receiving = false;
+            let rbuf = unsafe { &*imp.rbuf.get() };
+            st.on_segment(&imp.soc, &rbuf[..len]);
+            st.complete(&imp.soc, &mut done);
+            (kick(&imp, &mut st), st.retransmitting && st.unacked.is_empty())
+        };
+        this.decrease_outstanding_work();
+        if idle {
+            // All segments have been acknowledged.
+            imp.timer.cancel();
+        }
+        resume(&imp, done, next)
+    }
+
+    fn failure(self, this: &mut ThreadIoContext, err: io::Error) {
+        let RudpRecv { imp } = self;
+        let mut done = Vec::new();
+        // The peer may not be ready yet, that is recovered by the retransmission.
+        let err = SystemError::from(err);
+        let next = {
+            let mut st = imp.state.lock().unwrap();
+            st.receiving = false;
+            if err != CONNECTION_REFUSED {
+                st.fail(err, &mut done);
+            }
+            kick(&imp, &mut st)
+        };
+        this.decrease_outstanding_work();
+        if err != CONNECTION_REFUSED {
+            imp.timer.cancel();
+        }
+        resume(&imp, done, next)
+    }
+}
+
+struct RudpTimer<P> {
+    imp: Arc<RudpImpl<P>>,
+}
+
+impl<P> Handler<(), io::Error> for RudpTimer<P>
+where
+    P: Protocol,
+{
+    type Output = ();
+
+    type WrappedHandler = Self;
+
+    fn wrap<W>(self, ctx: &IoContext, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx, self)
+    }
+
+    fn wrap_timeout<W>(self, ctx: &Cancel, _: &Timeout, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx.as_ctx(), self)
+    }
+}
+
+impl<P> Complete<(), io::Error> for RudpTimer<P>
+where
+    P: Protocol,
+{
+    fn success(self, this: &mut ThreadIoContext, _: ()) {
+        let RudpTimer { imp } = self;
+        let mut done = Vec::new();
+        let mut expired = false;
+        let next = {
+            let mut st = imp.state.lock().unwrap();
+            st.retransmitting = false;
+            if !st.unacked.is_empty() {
+                st.retries += 1;
+                if st.retries > MAX_RETRIES {
+                    st.fail(TIMED_OUT, &mut done);
+                    expired = true;
+                } else {
+                    // Go-back-N, resends all segments in flight.
+                    for &(_, ref seg) in &st.unacked {
+                        let _ = imp.soc.nonblocking_send(seg, 0);
+                    }
+                    st.rto = st.rto * 2;
+                }
+            }
+            kick(&imp, &mut st)
+        };
+        this.decrease_outstanding_work();
+        if expired {
+            imp.soc.cancel();
+        }
+        resume(&imp, done, next)
+    }
+
+    fn failure(self, this: &mut ThreadIoContext, _: io::Error) {
+        self.imp.state.lock().unwrap().retransmitting = false;
+        this.decrease_outstanding_work();
+    }
+}
+
+/// Provides a reliable and ordered byte stream over the connected datagram socket.
+///
+/// # Examples
+///
+/// ```
+/// use std::io;
+/// use std::sync::Arc;
+/// use asyncio::{IoContext, Stream, wrap};
+/// use asyncio::ip::{IpProtocol, IpAddrV4, Udp, UdpEndpoint, UdpSocket};
+/// use asyncio::rudp::RudpStream;
+///
+/// fn on_read(_: Arc<RudpStream<Udp>>, res: io::Result<usize>) {
+///   assert_eq!(res.unwrap(), 5);
+/// }
+///
+/// fn on_write(_: Arc<RudpStream<Udp>>, res: io::Result<usize>) {
+///   assert_eq!(res.unwrap(), 5);
+/// }
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let a = UdpSocket::new(ctx, Udp::v4()).unwrap();
+/// let b = UdpSocket::new(ctx, Udp::v4()).unwrap();
+/// a.bind(&UdpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+/// b.bind(&UdpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+/// a.connect(&b.local_endpoint().unwrap()).unwrap();
+/// b.connect(&a.local_endpoint().unwrap()).unwrap();
+///
+/// let a = Arc::new(RudpStream::new(a));
+/// let b = Arc::new(RudpStream::new(b));
+/// let mut buf = [0; 16];
+/// b.async_read_some(&mut buf, wrap(&b, on_read));
+/// a.async_write_some(b"hello", wrap(&a, on_write));
+/// ctx.run();
+/// assert_eq!(&buf[..5], b"hello");
+/// ```
+pub struct RudpStream<P> {
+    inner: Arc<RudpImpl<P>>,
+}
+
+impl<P> RudpStream<P>
+where
+    P: Protocol,
+{
+    /// Returns a new stream over the socket connected to the peer.
+    pub fn new(soc: DgramSocket<P>) -> Self {
+        let timer = SteadyTimer::new(soc.as_ctx());
+        RudpStream {
+            inner: Arc::new(RudpImpl {
+                soc: soc,
+                timer: timer,
+                timeout: Timeout::max(),
+                state: Mutex::new(RudpState {
+                    snd_nxt: 0,
+                    unacked: VecDeque::new(),
+                    fin_sent: false,
+                    rcv_nxt: 0,
+                    ooo: BTreeMap::new(),
+                    ready: VecDeque::new(),
+                    eof: false,
+                    reader: None,
+                    writer: None,
+                    receiving: false,
+                    retransmitting: false,
+                    retries: 0,
+                    rto: initial_rto(),
+                    error: None,
+                }),
+                rbuf: UnsafeCell::new(vec![0; HEADER_LEN + MAX_SEGMENT]),
+            }),
+        }
+    }
+
+    /// Returns the number of the segments waiting for the acknowledgment.
+    pub fn in_flight(&self) -> usize {
+        self.inner.state.lock().unwrap().unacked.len()
+    }
+
+    /// Sends the end of stream after the written data, that the peer reads as the length of zero.
+    ///
+    /// The writing after the shutdown fails with the `EPIPE` error.
+    pub fn shutdown(&self) -> io::Result<()> {
+        let imp = &self.inner;
+        let next = {
+            let mut st = imp.state.lock().unwrap();
+            if let Some(err) = st.error {
+                return Err(err.into());
+            }
+            if !st.fin_sent {
+                st.fin_sent = true;
+                st.send(&imp.soc, FIN, &[]);
+            }
+            kick(imp, &mut st)
+        };
+        resume(imp, Vec::new(), next);
+        Ok(())
+    }
+
+    /// Returns a reference to the underlying socket.
+    pub fn socket(&self) -> &DgramSocket<P> {
+        &self.inner.soc
+    }
+}
+
+unsafe impl<P> AsIoContext for RudpStream<P> {
+    fn as_ctx(&self) -> &IoContext {
+        self.inner.soc.as_ctx()
+    }
+}
+
+impl<P> Cancel for RudpStream<P>
+where
+    P: Protocol,
+{
+    fn cancel(&self) {
+        let mut done = Vec::new();
+        {
+            let mut st = self.inner.state.lock().unwrap();
+            if let Some(op) = st.reader.take() {
+                done.push((op.handler, Err(OPERATION_CANCELED)));
+            }
+            if let Some(op) = st.writer.take() {
+                done.push((op.handler, Err(OPERATION_CANCELED)));
+            }
+        }
+        resume(&self.inner, done, (false, false));
+    }
+}
+
+unsafe impl<P> Send for RudpStream<P> {}
+
+unsafe impl<P> Sync for RudpStream<P> {}
+
+impl<P> Stream for RudpStream<P>
+where
+    P: Protocol,
+{
+    type Error = io::Error;
+
+    fn async_read_some<F>(&self, buf: &[u8], handler: F) -> F::Output
+    where
+        F: Handler<usize, Self::Error>,
+    {
+        let imp = self.inner.clone();
+        let (ptr, len) = (buf.as_ptr(), buf.len());
+        handler.wrap(self.as_ctx(), move |_, handler| {
+            let mut done = Vec::new();
+            let next = {
+                let mut st = imp.state.lock().unwrap();
+                st.reader = Some(PendingOp {
+                    buf: ptr,
+                    len: len,
+                    handler: BoxHandler::from_wrapped(handler),
+                });
+                st.complete(&imp.soc, &mut done);
+                kick(&imp, &mut st)
+            };
+            resume(&imp, done, next)
+        })
+    }
+
+    fn async_write_some<F>(&self, buf: &[u8], handler: F) -> F::Output
+    where
+        F: Handler<usize, Self::Error>,
+    {
+        let imp = self.inner.clone();
+        let (ptr, len) = (buf.as_ptr(), buf.len());
+        handler.wrap(self.as_ctx(), move |ctx, handler| {
+            if imp.state.lock().unwrap().fin_sent {
+                return ctx.do_dispatch(Failure::new(BROKEN_PIPE, handler));
+            }
+            let mut done = Vec::new();
+            let next = {
+                let mut st = imp.state.lock().unwrap();
+                st.writer = Some(PendingOp {
+                    buf: ptr,
+                    len: len,
+                    handler: BoxHandler::from_wrapped(handler),
+                });
+                st.complete(&imp.soc, &mut done);
+                kick(&imp, &mut st)
+            };
+            resume(&imp, done, next)
+        })
+    }
+
+    #[doc(hidden)]
+    fn wrap_timeout<F, G, W>(&self, handler: F, wrapper: W) -> F::Output
+    where
+        F: Handler<usize, Self::Error, WrappedHandler = G>,
+        G: Complete<usize, Self::Error>,
+        W: FnOnce(&IoContext, G),
+    {
+        handler.wrap_timeout(self, &self.inner.timeout, wrapper)
+    }
+}
+
+#[test]
+fn test_before() {
+    assert!(before(0, 1));
+    assert!(!before(1, 1));
+    assert!(!before(2, 1));
+    assert!(before(u32::max_value(), 0));
+}
+
+#[test]
+fn test_rudp_reorder() {
+    use ip::{IpProtocol, IpAddrV4, Udp, UdpEndpoint, UdpSocket};
+
+    let ctx = &IoContext::new().unwrap();
+    let a = UdpSocket::new(ctx, Udp::v4()).unwrap();
+    let b = UdpSocket::new(ctx, Udp::v4()).unwrap();
+    a.bind(&UdpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+    b.bind(&UdpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+    b.connect(&a.local_endpoint().unwrap()).unwrap();
+
+    let rx = RudpStream::new(b);
+    let mut st = rx.inner.state.lock().unwrap();
+    st.on_segment(&rx.inner.soc, &segment(DATA, 1, b"world"));
+    st.on_segment(&rx.inner.soc, &segment(FIN, 2, &[]));
+    assert!(st.ready.is_empty());
+    st.on_segment(&rx.inner.soc, &segment(DATA, 0, b"hello "));
+    st.on_segment(&rx.inner.soc, &segment(DATA, 0, b"hello "));
+    assert_eq!(st.rcv_nxt, 3);
+    assert!(st.eof);
+    let mut buf = [0; 16];
+    assert_eq!(st.read(buf.as_mut_ptr(), buf.len()), 11);
+    assert_eq!(&buf[..11], b"hello world");
+
+    // Each segment has been acknowledged by the cumulative sequence number.
+    let mut buf = [0; 16];
+    assert_eq!(a.receive(&mut buf, 0).unwrap(), HEADER_LEN);
+    assert_eq!(&buf[..HEADER_LEN], &segment(ACK, 0, &[])[..]);
+}
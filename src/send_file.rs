@@ -0,0 +1,185 @@
+use ffi::{AsRawFd, RawFd, Timeout};
+use core::{AsIoContext, IoContext, Protocol, ThreadIoContext, Cancel};
+use handler::{Handler, Complete, Success};
+use stream_socket::StreamSocket;
+
+use std::io;
+
+struct AsyncSendFile<F, P> {
+    soc: *const StreamSocket<P>,
+    fd: RawFd,
+    offset: u64,
+    len: usize,
+    total: usize,
+    handler: F,
+}
+
+unsafe impl<F, P> Send for AsyncSendFile<F, P> {}
+
+impl<F, P> AsyncSendFile<F, P>
+where
+    F: Complete<usize, io::Error>,
+    P: Protocol,
+{
+    fn send(self) {
+        let soc = unsafe { &*self.soc };
+        let fd = self.fd;
+        let offset = self.offset + self.total as u64;
+        let len = self.len - self.total;
+        soc.async_send_file_some(&fd, offset, len, self)
+    }
+}
+
+impl<F, P> Handler<usize, io::Error> for AsyncSendFile<F, P>
+where
+    F: Complete<usize, io::Error>,
+    P: Protocol,
+{
+    type Output = ();
+
+    type WrappedHandler = Self;
+
+    fn wrap<W>(self, ctx: &IoContext, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx, self)
+    }
+
+    fn wrap_timeout<W>(self, ctx: &Cancel, _: &Timeout, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx.as_ctx(), self)
+    }
+}
+
+impl<F, P> Complete<usize, io::Error> for AsyncSendFile<F, P>
+where
+    F: Complete<usize, io::Error>,
+    P: Protocol,
+{
+    fn success(mut self, this: &mut ThreadIoContext, len: usize) {
+        self.total += len;
+        if len == 0 || self.total == self.len {
+            self.handler.success(this, self.total)
+        } else {
+            this.decrease_outstanding_work();
+            self.send()
+        }
+    }
+
+    fn failure(self, this: &mut ThreadIoContext, err: io::Error) {
+        self.handler.failure(this, err)
+    }
+}
+
+impl<P> StreamSocket<P>
+where
+    P: Protocol,
+{
+    /// Asynchronously sends `len` bytes of the file from the offset, by the `sendfile` without
+    /// copying to the userspace on Linux, or reading the file otherwise.
+    ///
+    /// The handler is invoked with the sent bytes when `len` bytes were sent or the end of the
+    /// file was reached. The offset of the file is not changed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io;
+    /// use std::fs::File;
+    /// use std::sync::Arc;
+    /// use asyncio::wrap;
+    /// use asyncio::ip::TcpSocket;
+    ///
+    /// fn on_send_file(_: Arc<TcpSocket>, res: io::Result<usize>) {
+    /// }
+    ///
+    /// fn serve(soc: &Arc<TcpSocket>, file: &File, len: usize) {
+    ///   soc.async_send_file(file, 0, len, wrap(soc, on_send_file));
+    /// }
+    /// ```
+    pub fn async_send_file<T, F>(&self, file: &T, offset: u64, len: usize, handler: F) -> F::Output
+    where
+        T: AsRawFd,
+        F: Handler<usize, io::Error>,
+    {
+        let fd = file.as_raw_fd();
+        handler.wrap(self.as_ctx(), move |ctx, handler| {
+            if len == 0 {
+                return ctx.do_dispatch(Success::new(0, handler));
+            }
+            AsyncSendFile {
+                soc: self,
+                fd: fd,
+                offset: offset,
+                len: len,
+                total: 0,
+                handler: handler,
+            }.send()
+        })
+    }
+}
+
+#[test]
+fn test_send_file() {
+    use std::env;
+    use std::fs::OpenOptions;
+    use std::io::Write;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use handler::wrap;
+    use ip::{IpAddrV4, Tcp, TcpEndpoint, TcpListener, TcpSocket, IpProtocol};
+
+    static SENT: AtomicUsize = AtomicUsize::new(0);
+
+    fn on_send_file(_: Arc<TcpSocket>, res: io::Result<usize>) {
+        SENT.store(res.unwrap(), Ordering::SeqCst);
+    }
+
+    let path = env::temp_dir().join("asyncio_test_send_file");
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&path)
+        .unwrap();
+    let data: Vec<u8> = (0..300000).map(|i| i as u8).collect();
+    file.write_all(&data).unwrap();
+
+    let ctx = &IoContext::new().unwrap();
+    let other = &IoContext::new().unwrap();
+    let acc = TcpListener::new(other, Tcp::v4()).unwrap();
+    acc.bind(&TcpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+    acc.listen().unwrap();
+    let soc = TcpSocket::new(ctx, Tcp::v4()).unwrap();
+    soc.connect(&acc.local_endpoint().unwrap()).unwrap();
+    let (peer, _) = acc.accept().unwrap();
+
+    // The blocking send of the chunk.
+    assert_eq!(soc.send_file_some(&file, 299990, 1024).unwrap(), 10);
+    assert_eq!(soc.send_file_some(&file, 300000, 1024).unwrap(), 0);
+    let mut buf = [0; 16];
+    assert_eq!(peer.read_some(&mut buf).unwrap(), 10);
+    assert_eq!(&buf[..10], &data[299990..]);
+
+    // The composed send stops at the end of the file.
+    let soc = Arc::new(soc);
+    soc.async_send_file(&file, 100, 1000000, wrap(&soc, on_send_file));
+    let th = ::std::thread::spawn(move || {
+        let mut buf = vec![0; 300000];
+        let mut len = 0;
+        while len < 299900 {
+            len += peer.read_some(&mut buf[len..]).unwrap();
+        }
+        buf.truncate(len);
+        buf
+    });
+    ctx.run();
+    assert_eq!(SENT.load(Ordering::SeqCst), 299900);
+    assert!(th.join().unwrap() == &data[100..]);
+    drop(file);
+    let _ = ::std::fs::remove_file(&path);
+}
@@ -3,7 +3,7 @@ use serial_port::{SerialPort, SerialPortOption};
 
 use std::io;
 use termios::{Termios, tcsetattr, cfsetspeed, cfgetispeed};
-use termios::os::linux::*;
+use termios::os::target::*;
 
 pub fn setup_serial(fd: RawFd) -> io::Result<Termios> {
     let mut ios = try!(Termios::from_fd(fd));
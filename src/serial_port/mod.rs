@@ -2,9 +2,10 @@ use ffi::{RawFd, AsRawFd, SystemError, INVALID_ARGUMENT};
 use reactor::SocketImpl;
 use core::{AsIoContext, IoContext, ThreadIoContext, Perform, Cancel};
 use handler::{Handler, AsyncReadOp, AsyncWriteOp, Complete};
-use read_ops::{Read, async_read_op, blocking_read_op, nonblocking_read_op};
-use write_ops::{Write, async_write_op, blocking_write_op, nonblocking_write_op};
+use read_ops::{Read, ReadV, async_read_op, blocking_read_op, nonblocking_read_op};
+use write_ops::{Write, WriteV, async_write_op, blocking_write_op, nonblocking_write_op};
 use stream::Stream;
+use buffer_seq::{BufferSequence, MutableBufferSequence};
 
 use std::io;
 use std::time::Duration;
@@ -12,11 +13,11 @@ use std::ffi::CString;
 use libc::{self, O_RDWR, O_NOCTTY, O_NDELAY, O_NONBLOCK, O_CLOEXEC};
 use termios::{Termios, tcsendbreak};
 
-#[cfg(target_os = "linux")]
+#[cfg(any(target_os = "linux", target_os = "android"))]
 mod linux;
-#[cfg(target_os = "linux")]
+#[cfg(any(target_os = "linux", target_os = "android"))]
 use self::linux::setup_serial;
-#[cfg(target_os = "linux")]
+#[cfg(any(target_os = "linux", target_os = "android"))]
 pub use self::linux::{BaudRate, Parity, CSize, FlowControl, StopBits};
 
 #[cfg(target_os = "macos")]
@@ -55,6 +56,33 @@ impl SerialPort {
         })
     }
 
+    /// Asynchronously reads into the sequence of the buffers by the `readv`, that fills them in
+    /// order without copying.
+    pub fn async_read_some_v<B, F>(&self, bufs: &mut B, handler: F) -> F::Output
+    where
+        B: MutableBufferSequence + ?Sized,
+        F: Handler<usize, io::Error>,
+    {
+        let reader = ReadV::new(bufs.buffers_mut());
+        async_read_op(self, &[], &self.pimpl.timeout, handler, reader)
+    }
+
+    /// Asynchronously writes the sequence of the buffers by the `writev`, that gathers them in
+    /// order without copying.
+    pub fn async_write_some_v<B, F>(&self, bufs: &B, handler: F) -> F::Output
+    where
+        B: BufferSequence + ?Sized,
+        F: Handler<usize, io::Error>,
+    {
+        let writer = WriteV::new(bufs.buffers());
+        async_write_op(self, &[], &self.pimpl.timeout, handler, writer)
+    }
+
+    /// Cancels the pending operations and closes the port, returns the error of the `close`.
+    pub fn close(&mut self) -> io::Result<()> {
+        Ok(self.pimpl.close()?)
+    }
+
     pub fn get_option<C>(&self) -> C
     where
         C: SerialPortOption,
@@ -66,6 +94,10 @@ impl SerialPort {
         self.pimpl.timeout.get()
     }
 
+    pub fn is_open(&self) -> bool {
+        self.pimpl.is_open()
+    }
+
     pub fn nonblocking_read_some(&self, buf: &mut [u8]) -> io::Result<usize> {
         nonblocking_read_op(self, buf, Read::new())
     }
@@ -74,6 +106,8 @@ impl SerialPort {
         nonblocking_write_op(self, buf, Write::new())
     }
 
+    /// Reads some data, that fails with the `TimedOut` error unless the port becomes readable
+    /// within the timeout.
     pub fn read_some(&self, buf: &mut [u8]) -> io::Result<usize> {
         blocking_read_op(self, buf, &self.pimpl.timeout, Read::new())
     }
@@ -93,6 +127,8 @@ impl SerialPort {
         Ok(self.pimpl.timeout.set(timeout)?)
     }
 
+    /// Writes some data, that fails with the `TimedOut` error unless the port becomes writable
+    /// within the timeout.
     pub fn write_some(&self, buf: &[u8]) -> io::Result<usize> {
         blocking_write_op(self, buf, &self.pimpl.timeout, Write::new())
     }
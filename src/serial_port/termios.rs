@@ -5,7 +5,7 @@ use async::{Handler};
 use nio::{AsIoEvent, cancel, read, write, async_read, async_write};
 use streams::Stream;
 use serial_port::{SerialPortOption};
-#[cfg(target_os = "linux")] use super::linux::setup;
+#[cfg(any(target_os = "linux", target_os = "android"))] use super::linux::setup;
 #[cfg(target_os = "macos")] use super::macos::setup;
 
 use std::io;
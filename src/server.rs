@@ -0,0 +1,576 @@
+use ffi::{Timeout, Shutdown, shutdown};
+use core::{AsIoContext, IoContext, Protocol, ThreadIoContext, Cancel};
+use handler::{Handler, Complete, wrap};
+use stream::Stream;
+use strand::Strand;
+use socket_listener::SocketListener;
+use SteadyTimer;
+
+use std::io;
+use std::fmt;
+use std::ops::Deref;
+use std::sync::{Arc, Weak, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+/// A service invoked by the `Server` for each accepted connection.
+pub trait Service<P>: Send + Sync + 'static
+where
+    P: Protocol,
+{
+    /// The per-connection state, which is owned by a strand.
+    type Session: Send + 'static;
+
+    /// Returns a new session for the accepted connection.
+    fn new_session(&self, conn: Connection<P>) -> Self::Session;
+
+    /// Invoked in the strand of the session when the connection has been accepted.
+    fn on_start(&self, session: Strand<Self::Session>);
+
+    /// Invoked when the accept operation has been failed.
+    ///
+    /// The server continues to accept a next connection after the call.
+    fn on_error(&self, err: io::Error) {
+        let _ = err;
+    }
+}
+
+struct ConnectionImpl<P>
+where
+    P: Protocol,
+{
+    soc: P::Socket,
+    last: Mutex<Instant>,
+}
+
+impl<P> ConnectionImpl<P>
+where
+    P: Protocol,
+{
+    fn touch(&self) {
+        *self.last.lock().unwrap() = Instant::now();
+    }
+}
+
+unsafe impl<P: Protocol> Send for ConnectionImpl<P> {}
+
+unsafe impl<P: Protocol> Sync for ConnectionImpl<P> {}
+
+/// An accepted connection of the `Server`.
+///
+/// The connection counts against the server's concurrency limit until it is dropped.
+/// The asynchronous operations of the `Stream` trait through the connection are recorded as
+/// activity for the idle timeout.
+pub struct Connection<P>
+where
+    P: Protocol,
+{
+    inner: Arc<ConnectionImpl<P>>,
+    ep: P::Endpoint,
+    _guard: ConnectionGuard,
+}
+
+impl<P> Connection<P>
+where
+    P: Protocol,
+{
+    /// Returns the last time of the activity.
+    pub fn last_activity(&self) -> Instant {
+        *self.inner.last.lock().unwrap()
+    }
+
+    /// Returns a reference to the accepted socket.
+    pub fn socket(&self) -> &P::Socket {
+        &self.inner.soc
+    }
+
+    /// Returns the remote endpoint of the accepted socket.
+    pub fn remote_endpoint(&self) -> &P::Endpoint {
+        &self.ep
+    }
+
+    /// Records an activity of the connection.
+    pub fn touch(&self) {
+        self.inner.touch()
+    }
+}
+
+unsafe impl<P> AsIoContext for Connection<P>
+where
+    P: Protocol,
+    P::Socket: AsIoContext,
+{
+    fn as_ctx(&self) -> &IoContext {
+        self.inner.soc.as_ctx()
+    }
+}
+
+impl<P> Cancel for Connection<P>
+where
+    P: Protocol,
+    P::Socket: Cancel,
+{
+    fn cancel(&self) {
+        self.inner.soc.cancel()
+    }
+}
+
+impl<P> Deref for Connection<P>
+where
+    P: Protocol,
+{
+    type Target = P::Socket;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner.soc
+    }
+}
+
+unsafe impl<P: Protocol> Send for Connection<P> {}
+
+impl<P> Stream for Connection<P>
+where
+    P: Protocol,
+    P::Socket: Stream,
+{
+    type Error = <P::Socket as Stream>::Error;
+
+    fn async_read_some<F>(&self, buf: &[u8], handler: F) -> F::Output
+    where
+        F: Handler<usize, Self::Error>,
+    {
+        self.inner.soc.async_read_some(
+            buf,
+            Touch {
+                conn: self.inner.clone(),
+                handler: handler,
+            },
+        )
+    }
+
+    fn async_write_some<F>(&self, buf: &[u8], handler: F) -> F::Output
+    where
+        F: Handler<usize, Self::Error>,
+    {
+        self.inner.soc.async_write_some(
+            buf,
+            Touch {
+                conn: self.inner.clone(),
+                handler: handler,
+            },
+        )
+    }
+
+    #[doc(hidden)]
+    fn wrap_timeout<F, G, W>(&self, handler: F, wrapper: W) -> F::Output
+    where
+        F: Handler<usize, Self::Error, WrappedHandler = G>,
+        G: Complete<usize, Self::Error>,
+        W: FnOnce(&IoContext, G),
+    {
+        self.inner.soc.wrap_timeout(handler, wrapper)
+    }
+}
+
+struct Touch<P, F>
+where
+    P: Protocol,
+{
+    conn: Arc<ConnectionImpl<P>>,
+    handler: F,
+}
+
+impl<P, F, R, E> Handler<R, E> for Touch<P, F>
+where
+    P: Protocol,
+    F: Handler<R, E>,
+    R: Send + 'static,
+    E: Send + 'static,
+{
+    type Output = F::Output;
+
+    #[doc(hidden)]
+    type WrappedHandler = Touch<P, F::WrappedHandler>;
+
+    #[doc(hidden)]
+    fn wrap<W>(self, ctx: &IoContext, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        let Touch { conn, handler } = self;
+        handler.wrap(ctx, move |ctx, handler| {
+            wrapper(
+                ctx,
+                Touch {
+                    conn: conn,
+                    handler: handler,
+                },
+            )
+        })
+    }
+
+    #[doc(hidden)]
+    fn wrap_timeout<W>(self, ctx: &Cancel, timeout: &Timeout, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        let Touch { conn, handler } = self;
+        handler.wrap_timeout(ctx, timeout, move |ctx, handler| {
+            wrapper(
+                ctx,
+                Touch {
+                    conn: conn,
+                    handler: handler,
+                },
+            )
+        })
+    }
+}
+
+impl<P, F, R, E> Complete<R, E> for Touch<P, F>
+where
+    P: Protocol,
+    F: Complete<R, E>,
+    R: Send + 'static,
+    E: Send + 'static,
+{
+    fn success(self, this: &mut ThreadIoContext, res: R) {
+        self.conn.touch();
+        self.handler.success(this, res)
+    }
+
+    fn failure(self, this: &mut ThreadIoContext, err: E) {
+        self.handler.failure(this, err)
+    }
+}
+
+impl<P> fmt::Debug for Connection<P>
+where
+    P: Protocol,
+    P::Endpoint: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Connection({:?})", self.ep)
+    }
+}
+
+struct ConnectionGuard(Box<Fn() + Send + Sync>);
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        (self.0)()
+    }
+}
+
+struct ServerImpl<P, S>
+where
+    P: Protocol,
+{
+    acc: SocketListener<P>,
+    service: S,
+    max_connections: AtomicUsize,
+    connections: AtomicUsize,
+    paused: AtomicBool,
+    stopped: AtomicBool,
+    conns: Mutex<Vec<Weak<ConnectionImpl<P>>>>,
+    idle_timeout: Mutex<Duration>,
+    sweeping: AtomicBool,
+    timer: SteadyTimer,
+}
+
+unsafe impl<P: Protocol, S> Send for ServerImpl<P, S> {}
+
+unsafe impl<P: Protocol, S> Sync for ServerImpl<P, S> {}
+
+unsafe impl<P, S> AsIoContext for ServerImpl<P, S>
+where
+    P: Protocol,
+{
+    fn as_ctx(&self) -> &IoContext {
+        self.acc.as_ctx()
+    }
+}
+
+fn async_accept<P, S>(sv: &Arc<ServerImpl<P, S>>)
+where
+    P: Protocol,
+    S: Service<P>,
+{
+    sv.acc.async_accept(wrap(sv, on_accept));
+}
+
+fn on_accept<P, S>(sv: Arc<ServerImpl<P, S>>, res: io::Result<(P::Socket, P::Endpoint)>)
+where
+    P: Protocol,
+    S: Service<P>,
+{
+    match res {
+        Ok((soc, ep)) => {
+            sv.connections.fetch_add(1, Ordering::SeqCst);
+            let guard = {
+                let sv = sv.clone();
+                ConnectionGuard(Box::new(move || release(&sv)))
+            };
+            let inner = Arc::new(ConnectionImpl {
+                soc: soc,
+                last: Mutex::new(Instant::now()),
+            });
+            sv.conns.lock().unwrap().push(Arc::downgrade(&inner));
+            let session = sv.service.new_session(Connection {
+                inner: inner,
+                ep: ep,
+                _guard: guard,
+            });
+            let data = sv.clone();
+            Strand::new(sv.as_ctx(), session).dispatch(move |st| data.service.on_start(st));
+
+            if sv.stopped.load(Ordering::SeqCst) {
+                return;
+            }
+            if sv.connections.load(Ordering::SeqCst) < sv.max_connections.load(Ordering::SeqCst) {
+                async_accept(&sv);
+            } else {
+                // releases may race with the pause, re-check after publishing it.
+                sv.paused.store(true, Ordering::SeqCst);
+                resume(&sv);
+            }
+        }
+        Err(err) => {
+            if sv.stopped.load(Ordering::SeqCst) {
+                return;
+            }
+            sv.service.on_error(err);
+            async_accept(&sv);
+        }
+    }
+}
+
+fn release<P, S>(sv: &Arc<ServerImpl<P, S>>)
+where
+    P: Protocol,
+    S: Service<P>,
+{
+    if sv.connections.fetch_sub(1, Ordering::SeqCst) == 1 && sv.stopped.load(Ordering::SeqCst) {
+        sv.timer.cancel();
+    }
+    resume(sv);
+}
+
+fn async_sweep<P, S>(sv: &Arc<ServerImpl<P, S>>)
+where
+    P: Protocol,
+    S: Service<P>,
+{
+    let timeout = *sv.idle_timeout.lock().unwrap();
+    if timeout == Duration::new(0, 0) ||
+        (sv.stopped.load(Ordering::SeqCst) && sv.connections.load(Ordering::SeqCst) == 0)
+    {
+        sv.sweeping.store(false, Ordering::SeqCst);
+        return;
+    }
+    sv.timer.expires_from_now(timeout / 2);
+    sv.timer.async_wait(wrap(sv, on_sweep));
+}
+
+fn on_sweep<P, S>(sv: Arc<ServerImpl<P, S>>, _: io::Result<()>)
+where
+    P: Protocol,
+    S: Service<P>,
+{
+    let timeout = *sv.idle_timeout.lock().unwrap();
+    if timeout > Duration::new(0, 0) {
+        let now = Instant::now();
+        sv.conns.lock().unwrap().retain(|conn| match conn.upgrade() {
+            Some(conn) => {
+                if now.duration_since(*conn.last.lock().unwrap()) >= timeout {
+                    let _ = shutdown(&conn.soc, Shutdown::Both);
+                }
+                true
+            }
+            None => false,
+        });
+    }
+    async_sweep(&sv);
+}
+
+fn start_sweep<P, S>(sv: &Arc<ServerImpl<P, S>>)
+where
+    P: Protocol,
+    S: Service<P>,
+{
+    if !sv.stopped.load(Ordering::SeqCst) && !sv.sweeping.swap(true, Ordering::SeqCst) {
+        async_sweep(sv);
+    }
+}
+
+fn resume<P, S>(sv: &Arc<ServerImpl<P, S>>)
+where
+    P: Protocol,
+    S: Service<P>,
+{
+    if !sv.stopped.load(Ordering::SeqCst) &&
+        sv.connections.load(Ordering::SeqCst) < sv.max_connections.load(Ordering::SeqCst) &&
+        sv.paused.swap(false, Ordering::SeqCst)
+    {
+        async_accept(sv);
+    }
+}
+
+/// Provides an accept loop that dispatches connections to a `Service`.
+///
+/// Each accepted connection runs in its own strand.
+///
+/// # Examples
+///
+/// ```
+/// use asyncio::{IoContext, Strand, Server, Service, Connection};
+/// use asyncio::ip::{IpProtocol, IpAddrV4, Tcp, TcpEndpoint, TcpListener};
+/// use asyncio::socket_base::ReuseAddr;
+///
+/// struct Echo;
+///
+/// impl Service<Tcp> for Echo {
+///   type Session = Connection<Tcp>;
+///
+///   fn new_session(&self, conn: Connection<Tcp>) -> Self::Session {
+///     conn
+///   }
+///
+///   fn on_start(&self, conn: Strand<Self::Session>) {
+///     println!("accepted {}", conn.remote_endpoint());
+///   }
+/// }
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let acc = TcpListener::new(ctx, Tcp::v4()).unwrap();
+/// acc.set_option(ReuseAddr::new(true)).unwrap();
+/// acc.bind(&TcpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+/// acc.listen().unwrap();
+///
+/// let sv = Server::new(acc, Echo);
+/// sv.set_max_connections(100);
+/// sv.start();
+/// sv.shutdown();
+/// ctx.run();
+/// ```
+pub struct Server<P, S>
+where
+    P: Protocol,
+{
+    inner: Arc<ServerImpl<P, S>>,
+}
+
+impl<P, S> Server<P, S>
+where
+    P: Protocol,
+    S: Service<P>,
+{
+    /// Returns a new server for the bound and listening socket.
+    pub fn new(acc: SocketListener<P>, service: S) -> Self {
+        let timer = SteadyTimer::new(acc.as_ctx());
+        Server {
+            inner: Arc::new(ServerImpl {
+                acc: acc,
+                service: service,
+                max_connections: AtomicUsize::new(usize::max_value()),
+                connections: AtomicUsize::new(0),
+                paused: AtomicBool::new(false),
+                stopped: AtomicBool::new(true),
+                conns: Mutex::default(),
+                idle_timeout: Mutex::new(Duration::new(0, 0)),
+                sweeping: AtomicBool::new(false),
+                timer: timer,
+            }),
+        }
+    }
+
+    /// Returns a number of the active connections.
+    pub fn connections(&self) -> usize {
+        self.inner.connections.load(Ordering::SeqCst)
+    }
+
+    /// Returns a period of the idle timeout.
+    pub fn idle_timeout(&self) -> Duration {
+        *self.inner.idle_timeout.lock().unwrap()
+    }
+
+    /// Returns `true` if the server is not accepting.
+    pub fn is_stopped(&self) -> bool {
+        self.inner.stopped.load(Ordering::SeqCst)
+    }
+
+    /// Returns a reference to the listening socket.
+    pub fn listener(&self) -> &SocketListener<P> {
+        &self.inner.acc
+    }
+
+    pub fn local_endpoint(&self) -> io::Result<P::Endpoint> {
+        self.inner.acc.local_endpoint()
+    }
+
+    pub fn max_connections(&self) -> usize {
+        self.inner.max_connections.load(Ordering::SeqCst)
+    }
+
+    /// Returns a reference to the service.
+    pub fn service(&self) -> &S {
+        &self.inner.service
+    }
+
+    /// Sets a maximum number of the active connections.
+    ///
+    /// The server stops accepting while the limit is reached, and resumes when a connection is
+    /// dropped.
+    pub fn set_max_connections(&self, max: usize) {
+        self.inner.max_connections.store(max, Ordering::SeqCst);
+        resume(&self.inner);
+    }
+
+    /// Sets a period of the idle timeout.
+    ///
+    /// The connections with no activity within the period are shut down.
+    /// The connections are checked at every half of the period by a timer shared in the server.
+    /// The zero duration disables the idle timeout, that is the default.
+    pub fn set_idle_timeout(&self, timeout: Duration) {
+        *self.inner.idle_timeout.lock().unwrap() = timeout;
+        start_sweep(&self.inner);
+    }
+
+    /// Starts the accept loop.
+    pub fn start(&self) {
+        if self.inner.stopped.swap(false, Ordering::SeqCst) {
+            self.inner.paused.store(true, Ordering::SeqCst);
+            resume(&self.inner);
+            start_sweep(&self.inner);
+        }
+    }
+
+    /// Stops the accept loop.
+    ///
+    /// The active connections are not closed, the `IoContext` runs until they have been completed.
+    pub fn shutdown(&self) {
+        if !self.inner.stopped.swap(true, Ordering::SeqCst) {
+            self.inner.acc.cancel();
+            if self.inner.connections.load(Ordering::SeqCst) == 0 {
+                self.inner.timer.cancel();
+            }
+            // wakes the reactor up in case of called from the other thread.
+            self.inner.as_ctx().as_reactor().interrupt();
+        }
+    }
+}
+
+impl<P: Protocol, S> Clone for Server<P, S> {
+    fn clone(&self) -> Self {
+        Server { inner: self.inner.clone() }
+    }
+}
+
+unsafe impl<P, S> AsIoContext for Server<P, S>
+where
+    P: Protocol,
+{
+    fn as_ctx(&self) -> &IoContext {
+        self.inner.as_ctx()
+    }
+}
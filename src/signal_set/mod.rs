@@ -6,9 +6,9 @@ use std::io;
 
 pub use ffi::Signal;
 
-#[cfg(target_os = "linux")]
+#[cfg(any(target_os = "linux", target_os = "android"))]
 mod linux;
-#[cfg(target_os = "linux")]
+#[cfg(any(target_os = "linux", target_os = "android"))]
 use self::linux::{SignalImpl, async_wait};
 
 #[cfg(target_os = "macos")]
@@ -1,10 +1,44 @@
 use ffi::{FIONBIO, SOL_SOCKET, SO_BROADCAST, SO_DEBUG, SO_DONTROUTE, SO_KEEPALIVE, linger,
-          SO_REUSEADDR, SO_LINGER, SO_RCVBUF, SO_RCVLOWAT, SO_SNDBUF, SO_SNDLOWAT, FIONREAD};
+          SO_REUSEADDR, SO_REUSEPORT, SO_LINGER, SO_RCVBUF, SO_RCVLOWAT, SO_RCVTIMEO, SO_SNDBUF,
+          SO_SNDLOWAT, SO_SNDTIMEO, FIONREAD, timeval};
 use core::{GetSocketOption, IoControl, SetSocketOption, SocketOption};
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use ffi::{c_void, IFNAMSIZ, SO_BINDTODEVICE, SO_MAX_PACING_RATE, SO_TXTIME, INVALID_ARGUMENT,
+          SO_ATTACH_BPF, SO_ATTACH_REUSEPORT_CBPF, SO_ATTACH_REUSEPORT_EBPF, SO_COOKIE, RawFd,
+          sock_filter, sock_fprog, BPF_LD_W_ABS, BPF_ALU_MOD_K, BPF_RET_A, SKF_AD_CPU,
+          SKF_AD_RXHASH};
+
+use std::time::Duration;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use std::io;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use std::str;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use std::mem;
 
 pub const MAX_CONNECTIONS: i32 = 126;
 
-pub use ffi::Shutdown;
+fn to_timeval(timeout: Option<Duration>) -> timeval {
+    match timeout {
+        Some(timeout) => timeval {
+            tv_sec: timeout.as_secs() as _,
+            tv_usec: timeout.subsec_micros() as _,
+        },
+        None => timeval { tv_sec: 0, tv_usec: 0 },
+    }
+}
+
+fn from_timeval(tv: &timeval) -> Option<Duration> {
+    if tv.tv_sec == 0 && tv.tv_usec == 0 {
+        None
+    } else {
+        Some(Duration::new(tv.tv_sec as u64, tv.tv_usec as u32 * 1000))
+    }
+}
+
+pub use ffi::{Shutdown, Wait};
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub use ffi::{CLOCK_MONOTONIC, CLOCK_TAI, SOF_TXTIME_DEADLINE_MODE, SOF_TXTIME_REPORT_ERRORS};
 
 #[derive(Default, Clone)]
 pub struct NonBlockingIo(i32);
@@ -59,6 +93,215 @@ impl IoControl for BytesReadable {
     }
 }
 
+/// Socket option to attach the eBPF socket filter program.
+///
+/// Implements the SOL_SOCKET/SO_ATTACH_BPF socket option, that takes the file descriptor of the
+/// `BPF_PROG_TYPE_SOCKET_FILTER` program loaded by the `bpf(2)`, e.g. by the libbpf. The program
+/// is referenced by the socket, and the descriptor may be closed after the attaching.
+///
+/// # Examples
+/// Setting the option:
+///
+/// ```rust,no_run
+/// use asyncio::*;
+/// use asyncio::ip::*;
+/// use asyncio::socket_base::AttachBpf;
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let soc = UdpSocket::new(ctx, Udp::v4()).unwrap();
+///
+/// let prog_fd = 3; // loaded elsewhere.
+/// soc.set_option(AttachBpf::new(prog_fd)).unwrap();
+/// ```
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[derive(Clone)]
+pub struct AttachBpf(i32);
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl AttachBpf {
+    pub fn new(prog_fd: RawFd) -> AttachBpf {
+        AttachBpf(prog_fd)
+    }
+
+    pub fn get(&self) -> RawFd {
+        self.0
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<P> SocketOption<P> for AttachBpf {
+    fn level(&self, _: &P) -> i32 {
+        SOL_SOCKET
+    }
+
+    fn name(&self, _: &P) -> i32 {
+        SO_ATTACH_BPF
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<P> SetSocketOption<P> for AttachBpf {}
+
+/// Socket option to attach the eBPF program selecting the socket in the reuseport group.
+///
+/// Implements the SOL_SOCKET/SO_ATTACH_REUSEPORT_EBPF socket option, that takes the file
+/// descriptor of the `BPF_PROG_TYPE_SOCKET_FILTER` or `BPF_PROG_TYPE_SK_REUSEPORT` program. The
+/// program is shared by all the sockets bound to the same address with the `ReusePort`, and is
+/// attached to any one of them after the binding. The `Cookie` of the sockets identifies them
+/// in the `BPF_MAP_TYPE_REUSEPORT_SOCKARRAY` map of the program.
+///
+/// # Examples
+/// Setting the option:
+///
+/// ```rust,no_run
+/// use asyncio::*;
+/// use asyncio::ip::*;
+/// use asyncio::socket_base::{AttachReusePortEbpf, ReusePort};
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let soc = UdpSocket::new(ctx, Udp::v4()).unwrap();
+/// soc.set_option(ReusePort::new(true)).unwrap();
+/// soc.bind(&UdpEndpoint::new(IpAddrV4::any(), 12345)).unwrap();
+///
+/// let prog_fd = 3; // loaded elsewhere.
+/// soc.set_option(AttachReusePortEbpf::new(prog_fd)).unwrap();
+/// ```
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[derive(Clone)]
+pub struct AttachReusePortEbpf(i32);
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl AttachReusePortEbpf {
+    pub fn new(prog_fd: RawFd) -> AttachReusePortEbpf {
+        AttachReusePortEbpf(prog_fd)
+    }
+
+    pub fn get(&self) -> RawFd {
+        self.0
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<P> SocketOption<P> for AttachReusePortEbpf {
+    fn level(&self, _: &P) -> i32 {
+        SOL_SOCKET
+    }
+
+    fn name(&self, _: &P) -> i32 {
+        SO_ATTACH_REUSEPORT_EBPF
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<P> SetSocketOption<P> for AttachReusePortEbpf {}
+
+/// Socket option to attach the classic BPF program steering the packets in the reuseport group.
+///
+/// Implements the SOL_SOCKET/SO_ATTACH_REUSEPORT_CBPF socket option. The program returns the
+/// index of the socket in the group, that is the order of the `bind` for the UDP and of the
+/// `listen` for the TCP, and the packets are distributed by the kernel if the index is out of the
+/// group. The program is shared
+/// by all the sockets bound to the same address with the `ReusePort`, and is attached to any one
+/// of them after the binding.
+///
+/// # Examples
+/// Setting the option:
+///
+/// ```
+/// use asyncio::*;
+/// use asyncio::ip::*;
+/// use asyncio::socket_base::{AttachReusePortCbpf, ReusePort};
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let ep = TcpEndpoint::new(IpAddrV4::loopback(), 0);
+/// let a = TcpListener::new(ctx, Tcp::v4()).unwrap();
+/// a.set_option(ReusePort::new(true)).unwrap();
+/// a.bind(&ep).unwrap();
+/// a.listen().unwrap();
+///
+/// let b = TcpListener::new(ctx, Tcp::v4()).unwrap();
+/// b.set_option(ReusePort::new(true)).unwrap();
+/// b.bind(&a.local_endpoint().unwrap()).unwrap();
+/// b.listen().unwrap();
+///
+/// // The connections on the even CPUs are accepted by the `a`, on the odd CPUs by the `b`.
+/// a.set_option(AttachReusePortCbpf::cpu(2)).unwrap();
+/// ```
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub struct AttachReusePortCbpf {
+    prog: sock_fprog,
+    filter: Vec<sock_filter>,
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl AttachReusePortCbpf {
+    fn modulo(ancillary: u32, num_sockets: u32) -> AttachReusePortCbpf {
+        let mut filter = vec![
+            sock_filter { code: BPF_LD_W_ABS, jt: 0, jf: 0, k: ancillary },
+            sock_filter { code: BPF_ALU_MOD_K, jt: 0, jf: 0, k: num_sockets },
+            sock_filter { code: BPF_RET_A, jt: 0, jf: 0, k: 0 },
+        ];
+        AttachReusePortCbpf {
+            prog: sock_fprog {
+                len: filter.len() as u16,
+                filter: filter.as_mut_ptr(),
+            },
+            filter: filter,
+        }
+    }
+
+    /// Returns a program that selects the socket by the CPU number receiving the packet.
+    ///
+    /// The `num_sockets` is the number of the sockets in the group, that should be the number of
+    /// the CPUs handling the interrupts of the NIC.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_sockets` is zero.
+    pub fn cpu(num_sockets: u32) -> AttachReusePortCbpf {
+        assert!(num_sockets > 0);
+        Self::modulo(SKF_AD_CPU, num_sockets)
+    }
+
+    /// Returns a program that selects the socket by the hash of the source and the destination
+    /// addresses and ports, that steers the packets of a flow to the same socket.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_sockets` is zero.
+    pub fn hash(num_sockets: u32) -> AttachReusePortCbpf {
+        assert!(num_sockets > 0);
+        Self::modulo(SKF_AD_RXHASH, num_sockets)
+    }
+
+    /// Returns the number of the sockets in the group.
+    pub fn num_sockets(&self) -> u32 {
+        self.filter[1].k
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<P> SocketOption<P> for AttachReusePortCbpf {
+    fn level(&self, _: &P) -> i32 {
+        SOL_SOCKET
+    }
+
+    fn name(&self, _: &P) -> i32 {
+        SO_ATTACH_REUSEPORT_CBPF
+    }
+
+    fn capacity(&self) -> u32 {
+        mem::size_of::<sock_fprog>() as u32
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<P> SetSocketOption<P> for AttachReusePortCbpf {
+    fn as_ptr(&self) -> *const c_void {
+        &self.prog as *const _ as *const _
+    }
+}
+
 /// socket option to permit sending of broadcast messages.
 ///
 /// Implements the SOL_SOCKET/SO_BROADCAST socket option.
@@ -121,6 +364,149 @@ impl<P> GetSocketOption<P> for Broadcast {}
 
 impl<P> SetSocketOption<P> for Broadcast {}
 
+/// Socket option to bind a socket to a particular network interface.
+///
+/// Implements the SOL_SOCKET/SO_BINDTODEVICE socket option.
+///
+/// # Examples
+/// Setting the option:
+///
+/// ```rust,no_run
+/// use asyncio::*;
+/// use asyncio::ip::*;
+/// use asyncio::socket_base::BindToDevice;
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let soc = UdpSocket::new(ctx, Udp::v4()).unwrap();
+///
+/// soc.set_option(BindToDevice::new("eth0").unwrap()).unwrap();
+/// ```
+///
+/// Getting the option:
+///
+/// ```
+/// use asyncio::*;
+/// use asyncio::ip::*;
+/// use asyncio::socket_base::BindToDevice;
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let soc = UdpSocket::new(ctx, Udp::v4()).unwrap();
+///
+/// let opt: BindToDevice = soc.get_option().unwrap();
+/// assert_eq!(opt.get(), "");
+/// ```
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[derive(Default, Clone)]
+pub struct BindToDevice {
+    name: [u8; IFNAMSIZ],
+    len: u32,
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl BindToDevice {
+    pub fn new(ifname: &str) -> io::Result<BindToDevice> {
+        let src = ifname.as_bytes();
+        if src.len() >= IFNAMSIZ || src.contains(&0) {
+            return Err(INVALID_ARGUMENT.into());
+        }
+        let mut name = [0; IFNAMSIZ];
+        name[..src.len()].copy_from_slice(src);
+        Ok(BindToDevice {
+            name: name,
+            len: src.len() as u32,
+        })
+    }
+
+    pub fn get(&self) -> &str {
+        let len = self.name.iter().position(|&c| c == 0).unwrap_or(
+            self.len as usize,
+        );
+        str::from_utf8(&self.name[..len]).unwrap_or("")
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<P> SocketOption<P> for BindToDevice {
+    fn level(&self, _: &P) -> i32 {
+        SOL_SOCKET
+    }
+
+    fn name(&self, _: &P) -> i32 {
+        SO_BINDTODEVICE
+    }
+
+    fn capacity(&self) -> u32 {
+        IFNAMSIZ as u32
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<P> GetSocketOption<P> for BindToDevice {
+    fn as_mut_ptr(&mut self) -> *mut c_void {
+        self.name.as_mut_ptr() as *mut _
+    }
+
+    unsafe fn resize(&mut self, len: u32) {
+        self.len = len
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<P> SetSocketOption<P> for BindToDevice {
+    fn as_ptr(&self) -> *const c_void {
+        self.name.as_ptr() as *const _
+    }
+
+    fn size(&self) -> u32 {
+        self.len
+    }
+}
+
+/// Socket option for the cookie of the socket.
+///
+/// Implements the SOL_SOCKET/SO_COOKIE socket option, that is the identifier of the socket given
+/// by the kernel, and never reused while the system is running. The eBPF programs look up the
+/// sockets by the cookie, e.g. by the `bpf_get_socket_cookie`.
+///
+/// # Examples
+/// Getting the option:
+///
+/// ```
+/// use asyncio::*;
+/// use asyncio::ip::*;
+/// use asyncio::socket_base::Cookie;
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let soc = UdpSocket::new(ctx, Udp::v4()).unwrap();
+///
+/// let opt: Cookie = soc.get_option().unwrap();
+/// let cookie: u64 = opt.get();
+/// ```
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[derive(Default, Clone)]
+pub struct Cookie(u64);
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl Cookie {
+    pub fn get(&self) -> u64 {
+        self.0
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<P> SocketOption<P> for Cookie {
+    fn level(&self, _: &P) -> i32 {
+        SOL_SOCKET
+    }
+
+    fn name(&self, _: &P) -> i32 {
+        SO_COOKIE
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<P> GetSocketOption<P> for Cookie {}
+
 /// Socket option to enable socket-level debugging.
 ///
 /// Implements the SOL_SOCKET/SO_DEBUG socket option.
@@ -384,6 +770,153 @@ impl<P> GetSocketOption<P> for Linger {}
 
 impl<P> SetSocketOption<P> for Linger {}
 
+/// Socket option for the maximum pacing rate in bytes per second.
+///
+/// Implements the SOL_SOCKET/SO_MAX_PACING_RATE socket option, that is enforced by the fq qdisc
+/// or the TCP internal pacing.
+///
+/// # Examples
+/// Setting the option:
+///
+/// ```
+/// use asyncio::*;
+/// use asyncio::ip::*;
+/// use asyncio::socket_base::MaxPacingRate;
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let soc = UdpSocket::new(ctx, Udp::v4()).unwrap();
+///
+/// soc.set_option(MaxPacingRate::new(125_000)).unwrap();
+/// ```
+///
+/// Getting the option:
+///
+/// ```
+/// use asyncio::*;
+/// use asyncio::ip::*;
+/// use asyncio::socket_base::MaxPacingRate;
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let soc = UdpSocket::new(ctx, Udp::v4()).unwrap();
+///
+/// let opt: MaxPacingRate = soc.get_option().unwrap();
+/// let rate: u32 = opt.get();
+/// ```
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[derive(Default, Clone)]
+pub struct MaxPacingRate(u32);
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl MaxPacingRate {
+    pub fn new(rate: u32) -> MaxPacingRate {
+        MaxPacingRate(rate)
+    }
+
+    pub fn get(&self) -> u32 {
+        self.0
+    }
+
+    pub fn set(&mut self, rate: u32) {
+        self.0 = rate
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<P> SocketOption<P> for MaxPacingRate {
+    fn level(&self, _: &P) -> i32 {
+        SOL_SOCKET
+    }
+
+    fn name(&self, _: &P) -> i32 {
+        SO_MAX_PACING_RATE
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<P> GetSocketOption<P> for MaxPacingRate {}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<P> SetSocketOption<P> for MaxPacingRate {}
+
+/// Socket option for the transmission time of the datagrams.
+///
+/// Implements the SOL_SOCKET/SO_TXTIME socket option, that enables the transmission time of the
+/// SCM_TXTIME ancillary data given by the `send_to_txtime`, and scheduled by the etf (Earliest
+/// TxTime First) qdisc.
+///
+/// The clock other than `CLOCK_MONOTONIC` requires the CAP_NET_ADMIN capability.
+///
+/// # Examples
+/// Setting the option:
+///
+/// ```
+/// use asyncio::*;
+/// use asyncio::ip::*;
+/// use asyncio::socket_base::{TxTime, CLOCK_MONOTONIC, SOF_TXTIME_REPORT_ERRORS};
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let soc = UdpSocket::new(ctx, Udp::v4()).unwrap();
+///
+/// soc.set_option(TxTime::new(CLOCK_MONOTONIC, SOF_TXTIME_REPORT_ERRORS)).unwrap();
+/// ```
+///
+/// Getting the option:
+///
+/// ```
+/// use asyncio::*;
+/// use asyncio::ip::*;
+/// use asyncio::socket_base::TxTime;
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let soc = UdpSocket::new(ctx, Udp::v4()).unwrap();
+///
+/// let opt: TxTime = soc.get_option().unwrap();
+/// let clockid: i32 = opt.clockid();
+/// let flags: u32 = opt.flags();
+/// ```
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[repr(C)]
+#[derive(Default, Clone)]
+pub struct TxTime {
+    clockid: i32,
+    flags: u32,
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl TxTime {
+    pub fn new(clockid: i32, flags: u32) -> TxTime {
+        TxTime {
+            clockid: clockid,
+            flags: flags,
+        }
+    }
+
+    pub fn clockid(&self) -> i32 {
+        self.clockid
+    }
+
+    pub fn flags(&self) -> u32 {
+        self.flags
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<P> SocketOption<P> for TxTime {
+    fn level(&self, _: &P) -> i32 {
+        SOL_SOCKET
+    }
+
+    fn name(&self, _: &P) -> i32 {
+        SO_TXTIME
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<P> GetSocketOption<P> for TxTime {}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<P> SetSocketOption<P> for TxTime {}
+
 /// Socket option for the receive buffer size of a socket.
 ///
 /// Implements the SOL_SOCKET/SO_RCVBUF socket option.
@@ -508,6 +1041,79 @@ impl<P> GetSocketOption<P> for RecvLowWatermark {}
 
 impl<P> SetSocketOption<P> for RecvLowWatermark {}
 
+/// Socket option for the receive timeout of the kernel.
+///
+/// Implements the SOL_SOCKET/SO_RCVTIMEO socket option, that bounds the blocking receives of the
+/// socket handed to the foreign code. It is distinct from the `set_timeout` of the sockets, and
+/// never applies to the operations of this crate on the non-blocking socket.
+///
+/// # Examples
+/// Setting the option:
+///
+/// ```
+/// use std::time::Duration;
+/// use asyncio::*;
+/// use asyncio::ip::*;
+/// use asyncio::socket_base::RecvTimeout;
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let soc = TcpSocket::new(ctx, Tcp::v4()).unwrap();
+///
+/// soc.set_option(RecvTimeout::new(Some(Duration::from_millis(1500)))).unwrap();
+/// ```
+///
+/// Getting the option:
+///
+/// ```
+/// use std::time::Duration;
+/// use asyncio::*;
+/// use asyncio::ip::*;
+/// use asyncio::socket_base::RecvTimeout;
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let soc = TcpSocket::new(ctx, Tcp::v4()).unwrap();
+///
+/// let opt: RecvTimeout = soc.get_option().unwrap();
+/// let timeout: Option<Duration> = opt.get();
+/// ```
+#[derive(Clone)]
+pub struct RecvTimeout(timeval);
+
+impl RecvTimeout {
+    /// Returns the option of the timeout, that never expires if `None` or zero.
+    pub fn new(timeout: Option<Duration>) -> RecvTimeout {
+        RecvTimeout(to_timeval(timeout))
+    }
+
+    pub fn get(&self) -> Option<Duration> {
+        from_timeval(&self.0)
+    }
+
+    pub fn set(&mut self, timeout: Option<Duration>) {
+        self.0 = to_timeval(timeout)
+    }
+}
+
+impl Default for RecvTimeout {
+    fn default() -> RecvTimeout {
+        RecvTimeout::new(None)
+    }
+}
+
+impl<P> SocketOption<P> for RecvTimeout {
+    fn level(&self, _: &P) -> i32 {
+        SOL_SOCKET
+    }
+
+    fn name(&self, _: &P) -> i32 {
+        SO_RCVTIMEO
+    }
+}
+
+impl<P> GetSocketOption<P> for RecvTimeout {}
+
+impl<P> SetSocketOption<P> for RecvTimeout {}
+
 /// Socket option to allow the socket to be bound to an address that is already in use.
 ///
 /// Implements the SOL_SOCKET/SO_REUSEADDR socket option.
@@ -570,6 +1176,57 @@ impl ReuseAddr {
     }
 }
 
+/// Socket option to allow the multiple sockets to be bound to the same address and port.
+///
+/// Implements the SOL_SOCKET/SO_REUSEPORT socket option.
+///
+/// The option requires Linux 3.9 or later, the older kernels (e.g. of the old Android devices)
+/// return the `ENOPROTOOPT` error.
+///
+/// # Examples
+///
+/// ```
+/// use asyncio::*;
+/// use asyncio::ip::*;
+/// use asyncio::socket_base::ReusePort;
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let soc = UdpSocket::new(ctx, Udp::v4()).unwrap();
+///
+/// soc.set_option(ReusePort::new(true)).unwrap();
+/// assert!(soc.get_option::<ReusePort>().unwrap().get());
+/// ```
+#[derive(Default, Clone)]
+pub struct ReusePort(i32);
+
+impl<P> SocketOption<P> for ReusePort {
+    fn level(&self, _: &P) -> i32 {
+        SOL_SOCKET
+    }
+
+    fn name(&self, _: &P) -> i32 {
+        SO_REUSEPORT
+    }
+}
+
+impl<P> GetSocketOption<P> for ReusePort {}
+
+impl<P> SetSocketOption<P> for ReusePort {}
+
+impl ReusePort {
+    pub fn new(on: bool) -> ReusePort {
+        ReusePort(on as i32)
+    }
+
+    pub fn get(&self) -> bool {
+        self.0 != 0
+    }
+
+    pub fn set(&mut self, on: bool) {
+        self.0 = on as i32
+    }
+}
+
 /// Socket option for the send buffer size of a socket.
 ///
 /// Implements the SOL_SOCKET/SO_SNDBUF socket option.
@@ -693,3 +1350,145 @@ impl<P> SocketOption<P> for SendLowWatermark {
 impl<P> GetSocketOption<P> for SendLowWatermark {}
 
 impl<P> SetSocketOption<P> for SendLowWatermark {}
+
+/// Socket option for the send timeout of the kernel.
+///
+/// Implements the SOL_SOCKET/SO_SNDTIMEO socket option, that bounds the blocking sends of the
+/// socket handed to the foreign code. It is distinct from the `set_timeout` of the sockets, and
+/// never applies to the operations of this crate on the non-blocking socket.
+///
+/// # Examples
+/// Setting the option:
+///
+/// ```
+/// use std::time::Duration;
+/// use asyncio::*;
+/// use asyncio::ip::*;
+/// use asyncio::socket_base::SendTimeout;
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let soc = TcpSocket::new(ctx, Tcp::v4()).unwrap();
+///
+/// soc.set_option(SendTimeout::new(Some(Duration::from_millis(1500)))).unwrap();
+/// ```
+///
+/// Getting the option:
+///
+/// ```
+/// use std::time::Duration;
+/// use asyncio::*;
+/// use asyncio::ip::*;
+/// use asyncio::socket_base::SendTimeout;
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let soc = TcpSocket::new(ctx, Tcp::v4()).unwrap();
+///
+/// let opt: SendTimeout = soc.get_option().unwrap();
+/// let timeout: Option<Duration> = opt.get();
+/// ```
+#[derive(Clone)]
+pub struct SendTimeout(timeval);
+
+impl SendTimeout {
+    /// Returns the option of the timeout, that never expires if `None` or zero.
+    pub fn new(timeout: Option<Duration>) -> SendTimeout {
+        SendTimeout(to_timeval(timeout))
+    }
+
+    pub fn get(&self) -> Option<Duration> {
+        from_timeval(&self.0)
+    }
+
+    pub fn set(&mut self, timeout: Option<Duration>) {
+        self.0 = to_timeval(timeout)
+    }
+}
+
+impl Default for SendTimeout {
+    fn default() -> SendTimeout {
+        SendTimeout::new(None)
+    }
+}
+
+impl<P> SocketOption<P> for SendTimeout {
+    fn level(&self, _: &P) -> i32 {
+        SOL_SOCKET
+    }
+
+    fn name(&self, _: &P) -> i32 {
+        SO_SNDTIMEO
+    }
+}
+
+impl<P> GetSocketOption<P> for SendTimeout {}
+
+impl<P> SetSocketOption<P> for SendTimeout {}
+
+#[test]
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn test_cookie() {
+    use core::IoContext;
+    use ip::{IpProtocol, Udp, UdpSocket};
+
+    let ctx = &IoContext::new().unwrap();
+    let a = UdpSocket::new(ctx, Udp::v4()).unwrap();
+    let b = UdpSocket::new(ctx, Udp::v4()).unwrap();
+    let cookie = a.get_option::<Cookie>().unwrap().get();
+    assert!(cookie != 0);
+    assert_eq!(a.get_option::<Cookie>().unwrap().get(), cookie);
+    assert!(b.get_option::<Cookie>().unwrap().get() != cookie);
+
+    // The invalid program is rejected.
+    assert!(a.set_option(AttachBpf::new(-1)).is_err());
+}
+
+#[test]
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn test_reuseport_cbpf() {
+    use core::IoContext;
+    use ip::{IpProtocol, IpAddrV4, Udp, UdpEndpoint, UdpSocket};
+
+    let ctx = &IoContext::new().unwrap();
+    let a = UdpSocket::new(ctx, Udp::v4()).unwrap();
+    a.set_option(ReusePort::new(true)).unwrap();
+    a.bind(&UdpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+    let ep = a.local_endpoint().unwrap();
+    let b = UdpSocket::new(ctx, Udp::v4()).unwrap();
+    b.set_option(ReusePort::new(true)).unwrap();
+    b.bind(&ep).unwrap();
+
+    // Every flow is steered to the first socket.
+    let opt = AttachReusePortCbpf::hash(1);
+    assert_eq!(opt.num_sockets(), 1);
+    b.set_option(opt).unwrap();
+    for _ in 0..8 {
+        let soc = UdpSocket::new(ctx, Udp::v4()).unwrap();
+        soc.send_to(b"steer", 0, &ep).unwrap();
+    }
+    let mut buf = [0; 16];
+    for _ in 0..8 {
+        assert_eq!(a.receive(&mut buf, 0).unwrap(), 5);
+    }
+    let mut bytes = BytesReadable::default();
+    b.io_control(&mut bytes).unwrap();
+    assert_eq!(bytes.get(), 0);
+}
+
+#[test]
+fn test_timeout_options() {
+    use core::IoContext;
+    use ip::{IpProtocol, Udp, UdpSocket};
+
+    let ctx = &IoContext::new().unwrap();
+    let soc = UdpSocket::new(ctx, Udp::v4()).unwrap();
+    assert_eq!(soc.get_option::<RecvTimeout>().unwrap().get(), None);
+    soc.set_option(RecvTimeout::new(Some(Duration::from_millis(1500)))).unwrap();
+    assert_eq!(soc.get_option::<RecvTimeout>().unwrap().get(), Some(Duration::from_millis(1500)));
+    soc.set_option(SendTimeout::new(Some(Duration::new(2, 0)))).unwrap();
+    assert_eq!(soc.get_option::<SendTimeout>().unwrap().get(), Some(Duration::new(2, 0)));
+    soc.set_option(SendTimeout::new(None)).unwrap();
+    assert_eq!(soc.get_option::<SendTimeout>().unwrap().get(), None);
+
+    // The timeout of the crate is not changed.
+    assert_eq!(soc.get_timeout(), UdpSocket::new(ctx, Udp::v4()).unwrap().get_timeout());
+}
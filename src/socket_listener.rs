@@ -1,16 +1,20 @@
-use ffi::{AsRawFd, RawFd, SystemError, socket, bind, listen, ioctl, getsockopt,
-          setsockopt, getsockname};
-use reactor::SocketImpl;
+use ffi::{AsRawFd, IntoRawFd, RawFd, SystemError, socket, bind, listen, ioctl, getsockopt,
+          raw_getsockopt, raw_setsockopt, setsockopt, getsockname, wait, wait_for};
+use reactor::{SocketImpl, OpStats};
+use extensions::Extensions;
 use core::{Protocol, Socket, IoControl, GetSocketOption, SetSocketOption, AsIoContext, IoContext,
            Perform, ThreadIoContext, Cancel};
 use handler::{Handler, AsyncReadOp};
-use socket_base::MAX_CONNECTIONS;
+use socket_base::{Wait, MAX_CONNECTIONS};
 
 use std::io;
 use std::fmt;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use accept_ops::{async_accept, blocking_accept, nonblocking_accept};
+use accept_ops::{async_accept, async_accept_into, blocking_accept, blocking_accept_into,
+                 nonblocking_accept};
+use close_ops::async_close_gracefully;
+use stream_socket::StreamSocket;
 
 pub struct SocketListener<P> {
     pimpl: Box<SocketImpl<P>>,
@@ -36,6 +40,11 @@ where
         async_accept(self, &self.pimpl.timeout, handler)
     }
 
+    /// Adopts the native socket of the protocol in place of the current socket which is closed.
+    pub fn assign(&mut self, soc: RawFd, pro: P) -> io::Result<()> {
+        Ok(self.pimpl.assign(soc, pro)?)
+    }
+
     pub fn bind(&self, ep: &P::Endpoint) -> io::Result<()> {
         Ok(bind(self, ep)?)
     }
@@ -44,6 +53,54 @@ where
         self.pimpl.cancel()
     }
 
+    /// Cancels the pending operations and closes the socket, returns the error of the `close`.
+    pub fn close(&mut self) -> io::Result<()> {
+        Ok(self.pimpl.close()?)
+    }
+
+    /// Asynchronously closes the listener at the deadline, and returns the pending connections.
+    ///
+    /// The listener stops accepting at once, that the pending `async_accept` handlers are invoked
+    /// with the `OPERATION_CANCELED` error. The connections that were established in the backlog
+    /// by the deadline are accepted without blocking, and handed to the handler rather than reset
+    /// by the close. The listener must not be used until the handler is invoked.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io;
+    /// use std::sync::Arc;
+    /// use std::time::{Duration, Instant};
+    /// use asyncio::{IoContext, wrap};
+    /// use asyncio::ip::{IpProtocol, IpAddrV4, Tcp, TcpEndpoint, TcpListener, TcpSocket};
+    ///
+    /// fn on_close(acc: Arc<TcpListener>, res: io::Result<Vec<(TcpSocket, TcpEndpoint)>>) {
+    ///     // serves the connections that were not accepted yet.
+    ///     assert!(res.unwrap().is_empty());
+    /// }
+    ///
+    /// let ctx = &IoContext::new().unwrap();
+    /// let acc = Arc::new(TcpListener::new(ctx, Tcp::v4()).unwrap());
+    /// acc.bind(&TcpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+    /// acc.listen().unwrap();
+    ///
+    /// let deadline = Instant::now() + Duration::from_millis(100);
+    /// acc.close_gracefully(deadline, wrap(&acc, on_close));
+    /// ctx.run();
+    /// ```
+    pub fn close_gracefully<F>(&self, deadline: Instant, handler: F) -> F::Output
+    where
+        P: 'static,
+        F: Handler<Vec<(P::Socket, P::Endpoint)>, io::Error>,
+    {
+        async_close_gracefully(self, deadline, handler)
+    }
+
+    /// Returns the typed user data attached to the socket.
+    pub fn extensions(&self) -> &Extensions {
+        &self.pimpl.extensions
+    }
+
     pub fn listen(&self) -> io::Result<()> {
         Ok(listen(self, MAX_CONNECTIONS)?)
     }
@@ -74,6 +131,35 @@ where
         Ok(ioctl(self, cmd)?)
     }
 
+    pub fn is_open(&self) -> bool {
+        self.pimpl.is_open()
+    }
+
+    /// Returns the statistics of the asynchronous operations of the socket, that are counted only
+    /// in debug builds.
+    pub fn op_stats(&self) -> OpStats {
+        self.pimpl.op_stats()
+    }
+
+    /// Gets the socket option by the raw `level` and `name` into the buffer, returns the length of
+    /// the option value.
+    ///
+    /// # Safety
+    ///
+    /// The layout of the buffer must be what the kernel expects for the option.
+    pub unsafe fn raw_getsockopt(&self, level: i32, name: i32, buf: &mut [u8]) -> io::Result<usize> {
+        Ok(raw_getsockopt(self, level, name, buf)?)
+    }
+
+    /// Sets the socket option by the raw `level` and `name` from the buffer.
+    ///
+    /// # Safety
+    ///
+    /// The layout of the buffer must be what the kernel expects for the option.
+    pub unsafe fn raw_setsockopt(&self, level: i32, name: i32, buf: &[u8]) -> io::Result<()> {
+        Ok(raw_setsockopt(self, level, name, buf)?)
+    }
+
     pub fn set_timeout(&self, timeout: Duration) -> io::Result<()> {
         Ok(self.pimpl.timeout.set(timeout)?)
     }
@@ -84,6 +170,75 @@ where
     {
         Ok(setsockopt(self, cmd)?)
     }
+
+    /// Blocks until the socket becomes ready to read, write or have the error condition.
+    pub fn wait(&self, w: Wait) -> io::Result<()> {
+        Ok(wait(self, w, -1)?)
+    }
+
+    /// Blocks until the socket becomes ready or the timeout expires with the `ETIMEDOUT` error.
+    pub fn wait_for(&self, w: Wait, timeout: Duration) -> io::Result<()> {
+        Ok(wait_for(self, w, timeout)?)
+    }
+
+    /// Blocks until the socket becomes ready or the deadline with the `ETIMEDOUT` error.
+    pub fn wait_until(&self, w: Wait, deadline: Instant) -> io::Result<()> {
+        let now = Instant::now();
+        let timeout = if deadline > now { deadline - now } else { Duration::new(0, 0) };
+        Ok(wait_for(self, w, timeout)?)
+    }
+}
+
+impl<P> SocketListener<P>
+where
+    P: Protocol<Socket = StreamSocket<P>>,
+{
+    /// Accepts a connection into the peer socket, and returns the endpoint of the remote peer.
+    ///
+    /// The current socket of the peer is closed and replaced by the accepted socket, that keeps
+    /// the timeout of the peer. The socket options are not carried over from the replaced socket.
+    pub fn accept_into(&self, peer: &StreamSocket<P>) -> io::Result<P::Endpoint> {
+        blocking_accept_into(self, peer, &self.pimpl.timeout)
+    }
+
+    /// Asynchronously accepts a connection into the peer socket, like the move-accept of Asio.
+    ///
+    /// The peer must not be used until the handler is invoked. See `accept_into`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io;
+    /// use std::sync::Arc;
+    /// use std::time::Duration;
+    /// use asyncio::{IoContext, wrap};
+    /// use asyncio::ip::{IpProtocol, IpAddrV4, Tcp, TcpEndpoint, TcpListener, TcpSocket};
+    ///
+    /// fn on_accept(soc: Arc<TcpSocket>, res: io::Result<TcpEndpoint>) {
+    ///     let ep = res.unwrap();
+    ///     assert_eq!(soc.remote_endpoint().unwrap(), ep);
+    /// }
+    ///
+    /// let ctx = &IoContext::new().unwrap();
+    /// let acc = TcpListener::new(ctx, Tcp::v4()).unwrap();
+    /// acc.bind(&TcpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+    /// acc.listen().unwrap();
+    ///
+    /// let peer = Arc::new(TcpSocket::new(ctx, Tcp::v4()).unwrap());
+    /// peer.set_timeout(Duration::from_millis(1500)).unwrap();
+    /// acc.async_accept_into(&peer, wrap(&peer, on_accept));
+    ///
+    /// let cl = TcpSocket::new(ctx, Tcp::v4()).unwrap();
+    /// cl.connect(&acc.local_endpoint().unwrap()).unwrap();
+    /// ctx.run();
+    /// assert_eq!(peer.get_timeout(), Duration::from_millis(1500));
+    /// ```
+    pub fn async_accept_into<F>(&self, peer: &StreamSocket<P>, handler: F) -> F::Output
+    where
+        F: Handler<P::Endpoint, io::Error>,
+    {
+        async_accept_into(self, peer, &self.pimpl.timeout, handler)
+    }
 }
 
 unsafe impl<P> AsIoContext for SocketListener<P> {
@@ -98,6 +253,12 @@ impl<P> AsRawFd for SocketListener<P> {
     }
 }
 
+impl<P> IntoRawFd for SocketListener<P> {
+    fn into_raw_fd(self) -> RawFd {
+        self.pimpl.into_raw_fd()
+    }
+}
+
 impl<P: 'static> Cancel for SocketListener<P> {
     fn cancel(&self) {
         self.pimpl.cancel()
@@ -139,6 +300,74 @@ where
     }
 
     unsafe fn from_raw_fd(ctx: &IoContext, soc: RawFd, pro: P) -> Self {
-        SocketListener { pimpl: SocketImpl::new(ctx, soc, pro) }
+        SocketListener { pimpl: SocketImpl::socket(ctx, soc, pro) }
+    }
+}
+
+#[test]
+fn test_accept_into() {
+    use ip::{IpAddrV4, IpProtocol, Tcp, TcpEndpoint, TcpListener, TcpSocket};
+
+    let ctx = &IoContext::new().unwrap();
+    let acc = TcpListener::new(ctx, Tcp::v4()).unwrap();
+    acc.bind(&TcpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+    acc.listen().unwrap();
+    let cl = TcpSocket::new(ctx, Tcp::v4()).unwrap();
+    cl.connect(&acc.local_endpoint().unwrap()).unwrap();
+
+    let peer = TcpSocket::new(ctx, Tcp::v4()).unwrap();
+    peer.set_timeout(Duration::from_millis(1500)).unwrap();
+    let ep = acc.accept_into(&peer).unwrap();
+    assert_eq!(ep, cl.local_endpoint().unwrap());
+    assert_eq!(peer.remote_endpoint().unwrap(), ep);
+    assert_eq!(peer.get_timeout(), Duration::from_millis(1500));
+
+    cl.write_some(b"hello").unwrap();
+    let mut buf = [0; 16];
+    assert_eq!(peer.read_some(&mut buf).unwrap(), 5);
+}
+
+#[test]
+fn test_close_gracefully() {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use ffi::OPERATION_CANCELED;
+    use handler::wrap;
+    use ip::{IpAddrV4, IpProtocol, Tcp, TcpEndpoint, TcpListener, TcpSocket};
+
+    static CANCELED: AtomicUsize = AtomicUsize::new(0);
+    static ACCEPTED: AtomicUsize = AtomicUsize::new(0);
+
+    fn on_accept(_: Arc<TcpListener>, res: io::Result<(TcpSocket, TcpEndpoint)>) {
+        if res.unwrap_err().raw_os_error() == io::Error::from(OPERATION_CANCELED).raw_os_error() {
+            CANCELED.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn on_close(acc: Arc<TcpListener>, res: io::Result<Vec<(TcpSocket, TcpEndpoint)>>) {
+        assert_eq!(acc.as_raw_fd(), -1);
+        ACCEPTED.store(res.unwrap().len(), Ordering::SeqCst);
     }
+
+    let ctx = &IoContext::new().unwrap();
+    let acc = Arc::new(TcpListener::new(ctx, Tcp::v4()).unwrap());
+    acc.bind(&TcpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+    acc.listen().unwrap();
+    let ep = acc.local_endpoint().unwrap();
+    let cl1 = TcpSocket::new(ctx, Tcp::v4()).unwrap();
+    cl1.connect(&ep).unwrap();
+
+    acc.async_accept(wrap(&acc, on_accept));
+    acc.close_gracefully(Instant::now() + Duration::from_millis(200), wrap(&acc, on_close));
+
+    // Connects during the drain period.
+    let port = ep.port();
+    let th = ::std::thread::spawn(move || {
+        ::std::thread::sleep(Duration::from_millis(50));
+        ::std::net::TcpStream::connect(("127.0.0.1", port)).unwrap()
+    });
+    ctx.run();
+    drop(th.join().unwrap());
+    assert_eq!(CANCELED.load(Ordering::SeqCst), 1);
+    assert_eq!(ACCEPTED.load(Ordering::SeqCst), 2);
 }
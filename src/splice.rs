@@ -0,0 +1,251 @@
+use ffi::{AsRawFd, RawFd, Timeout, SPLICE_F_MOVE};
+use core::{AsIoContext, IoContext, Protocol, ThreadIoContext, Cancel};
+use handler::{Handler, Complete, Success};
+use stream_socket::StreamSocket;
+use ffi;
+
+use std::io;
+use std::cmp;
+
+const MAX_CHUNK: usize = 65536;
+
+/// Duplicates up to `len` bytes from the pipe to the other pipe without consuming.
+///
+/// Returns the `EAGAIN` error instead of blocking if the source is empty or the destination is full.
+pub fn tee<R, W>(src: &R, dst: &W, len: usize) -> io::Result<usize>
+where
+    R: AsRawFd,
+    W: AsRawFd,
+{
+    Ok(ffi::tee(src, dst, len, 0)?)
+}
+
+struct AsyncSplice<F, P, Q> {
+    src: *const StreamSocket<P>,
+    dst: *const StreamSocket<Q>,
+    rfd: RawFd,
+    wfd: RawFd,
+    len: usize,
+    total: usize,
+    in_pipe: usize,
+    handler: F,
+}
+
+unsafe impl<F, P, Q> Send for AsyncSplice<F, P, Q> {}
+
+impl<F, P, Q> AsyncSplice<F, P, Q>
+where
+    F: Complete<usize, io::Error>,
+    P: Protocol,
+    Q: Protocol,
+{
+    fn fill(self) {
+        let src = unsafe { &*self.src };
+        let wfd = self.wfd;
+        let len = cmp::min(self.len - self.total, MAX_CHUNK);
+        src.async_splice_to_pipe(&wfd, len, SPLICE_F_MOVE, self)
+    }
+
+    fn drain(self) {
+        let dst = unsafe { &*self.dst };
+        let rfd = self.rfd;
+        let len = self.in_pipe;
+        dst.async_splice_from_pipe(&rfd, len, SPLICE_F_MOVE, self)
+    }
+}
+
+impl<F, P, Q> Handler<usize, io::Error> for AsyncSplice<F, P, Q>
+where
+    F: Complete<usize, io::Error>,
+    P: Protocol,
+    Q: Protocol,
+{
+    type Output = ();
+
+    type WrappedHandler = Self;
+
+    fn wrap<W>(self, ctx: &IoContext, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx, self)
+    }
+
+    fn wrap_timeout<W>(self, ctx: &Cancel, _: &Timeout, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx.as_ctx(), self)
+    }
+}
+
+impl<F, P, Q> Complete<usize, io::Error> for AsyncSplice<F, P, Q>
+where
+    F: Complete<usize, io::Error>,
+    P: Protocol,
+    Q: Protocol,
+{
+    fn success(mut self, this: &mut ThreadIoContext, len: usize) {
+        if self.in_pipe == 0 {
+            // Filled the pipe from the source.
+            if len == 0 {
+                return self.handler.success(this, self.total);
+            }
+            self.in_pipe = len;
+            this.decrease_outstanding_work();
+            return self.drain();
+        }
+
+        // Drained the pipe to the destination.
+        self.in_pipe -= len;
+        self.total += len;
+        if self.in_pipe > 0 {
+            this.decrease_outstanding_work();
+            self.drain()
+        } else if self.total == self.len {
+            self.handler.success(this, self.total)
+        } else {
+            this.decrease_outstanding_work();
+            self.fill()
+        }
+    }
+
+    fn failure(self, this: &mut ThreadIoContext, err: io::Error) {
+        self.handler.failure(this, err)
+    }
+}
+
+impl<P> StreamSocket<P>
+where
+    P: Protocol,
+{
+    /// Asynchronously moves up to `len` bytes from this socket to the other socket through the pipe
+    /// without copying to the userspace.
+    ///
+    /// The handler is invoked with the moved bytes when `len` bytes were moved or the peer has
+    /// shutdown the connection. The pipe must be empty and must not be used by the others until
+    /// the operation completes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io;
+    /// use std::sync::Arc;
+    /// use std::os::unix::io::RawFd;
+    /// use asyncio::{IoContext, wrap};
+    /// use asyncio::ip::TcpSocket;
+    ///
+    /// fn on_splice(_: Arc<TcpSocket>, res: io::Result<usize>) {
+    /// }
+    ///
+    /// fn proxy(src: &Arc<TcpSocket>, dst: &TcpSocket, pipe: (RawFd, RawFd)) {
+    ///   src.async_splice(dst, &pipe.0, &pipe.1, 1024 * 1024, wrap(src, on_splice));
+    /// }
+    /// ```
+    pub fn async_splice<Q, R, W, F>(
+        &self,
+        dst: &StreamSocket<Q>,
+        pipe_r: &R,
+        pipe_w: &W,
+        len: usize,
+        handler: F,
+    ) -> F::Output
+    where
+        Q: Protocol,
+        R: AsRawFd,
+        W: AsRawFd,
+        F: Handler<usize, io::Error>,
+    {
+        let rfd = pipe_r.as_raw_fd();
+        let wfd = pipe_w.as_raw_fd();
+        handler.wrap(self.as_ctx(), move |ctx, handler| {
+            if len == 0 {
+                return ctx.do_dispatch(Success::new(0, handler));
+            }
+            AsyncSplice {
+                src: self,
+                dst: dst,
+                rfd: rfd,
+                wfd: wfd,
+                len: len,
+                total: 0,
+                in_pipe: 0,
+                handler: handler,
+            }.fill()
+        })
+    }
+}
+
+#[test]
+fn test_splice() {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use ffi::{close, pipe};
+    use handler::wrap;
+    use socket_base::Shutdown;
+    use ip::{IpAddrV4, Tcp, TcpEndpoint, TcpListener, TcpSocket, IpProtocol};
+
+    static SPLICED: AtomicUsize = AtomicUsize::new(0);
+
+    fn on_splice(_: Arc<TcpSocket>, res: io::Result<usize>) {
+        SPLICED.store(res.unwrap(), Ordering::SeqCst);
+    }
+
+    fn connect(ctx: &IoContext, acc_ctx: &IoContext) -> (TcpSocket, TcpSocket) {
+        let acc = TcpListener::new(acc_ctx, Tcp::v4()).unwrap();
+        acc.bind(&TcpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+        acc.listen().unwrap();
+        let soc = TcpSocket::new(ctx, Tcp::v4()).unwrap();
+        soc.connect(&acc.local_endpoint().unwrap()).unwrap();
+        (soc, acc.accept().unwrap().0)
+    }
+
+    // The sockets used by the other threads are not stopped by the run.
+    let ctx = &IoContext::new().unwrap();
+    let other = &IoContext::new().unwrap();
+    let (rfd, wfd) = pipe().unwrap();
+
+    // The blocking splice and tee.
+    let (a, b) = connect(other, ctx);
+    a.write_some(b"hello").unwrap();
+    assert_eq!(b.splice_to_pipe(&wfd, 1024, 0).unwrap(), 5);
+    let (rfd2, wfd2) = pipe().unwrap();
+    assert_eq!(tee(&rfd, &wfd2, 1024).unwrap(), 5);
+    assert_eq!(a.splice_from_pipe(&rfd2, 1024, 0).unwrap(), 5);
+    assert_eq!(b.splice_from_pipe(&rfd, 1024, 0).unwrap(), 5);
+    let mut buf = [0; 16];
+    assert_eq!(b.read_some(&mut buf).unwrap(), 5);
+    assert_eq!(&buf[..5], b"hello");
+    assert_eq!(a.read_some(&mut buf).unwrap(), 5);
+    assert_eq!(&buf[..5], b"hello");
+    close(rfd2);
+    close(wfd2);
+
+    // The composed splice until the end of stream.
+    let (c, d) = connect(ctx, other);
+    let data = vec![7; 200000];
+    let b = Arc::new(b);
+    b.async_splice(&c, &rfd, &wfd, data.len() * 2, wrap(&b, on_splice));
+    ::std::thread::spawn(move || {
+        let mut len = 0;
+        while len < data.len() {
+            len += a.write_some(&data[len..]).unwrap();
+        }
+        a.shutdown(Shutdown::Write).unwrap();
+    });
+    let th = ::std::thread::spawn(move || {
+        let mut buf = [0; 4096];
+        let mut len = 0;
+        while len < 200000 {
+            let n = d.read_some(&mut buf).unwrap();
+            assert!(buf[..n].iter().all(|&ch| ch == 7));
+            len += n;
+        }
+        len
+    });
+    ctx.run();
+    assert_eq!(SPLICED.load(Ordering::SeqCst), 200000);
+    assert_eq!(th.join().unwrap(), 200000);
+    close(rfd);
+    close(wfd);
+}
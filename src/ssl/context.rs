@@ -1,348 +1,247 @@
-use ssl::*;
-use ssl::ffi::*;
+use ffi::INVALID_ARGUMENT;
+use ssl::{Error, Result, FileFormat, SslOptions, SslVerifyContext, SslVerifyMode};
 
-use std::mem;
+use std::io;
 use std::ptr;
-use std::slice;
+use std::ffi::CString;
+use std::fs::File;
+use std::io::Read;
 use std::path::Path;
-use std::ffi::{CString, CStr};
-use std::sync::{Arc};
-use libc::{c_void, c_char, c_int, c_long};
-use openssl_sys::*;
-use openssl::types::OpenSslTypeRef;
+use std::sync::Arc;
+use openssl::dh::Dh;
+use openssl::pkey::PKey;
+use openssl::ssl::{Ssl, SslContextBuilder, SslMethod, SslVersion};
+use openssl::x509::X509;
+use openssl_sys::{SSL_CTX_up_ref, SSL_CTX_load_verify_locations};
 
-struct BioBox { handle: *mut BIO }
+type VerifyCallback = Arc<Fn(bool, &SslVerifyContext) -> bool + Send + Sync>;
 
-impl BioBox {
-    fn new_mem_buf(buf: &[u8]) -> Result<BioBox> {
-        let handle = unsafe { BIO_new_mem_buf(buf.as_ptr() as *mut c_void, buf.len() as c_int) };
-        if handle.is_null() {
-            Err(Error::last_ssl_error())
-        } else {
-            Ok(BioBox { handle: handle })
-        }
-    }
-
-    fn new_file(path: &CStr) -> Result<BioBox> {
-        use libc::fopen;
-
-        let fp = unsafe { fopen(path.as_ptr(), b"r".as_ptr() as *const i8) };
-        if fp.is_null() {
-            // NOTE: BIO_new_file が openssl-sys に定義されていないので、
-            //       BIO_new_fp で代用したことにより、エラーコードが正しくないかもしれない
-            return Err(Error::last_ssl_error());
-        }
-
-        let handle = unsafe { BIO_new_fp(fp, 1) }; // 1 is close_flag
-        if handle.is_null() {
-            return Err(Error::last_ssl_error());
-        } else {
-            Ok(BioBox { handle: handle })
-        }
+fn path_to_cstring(path: &Path) -> Result<CString> {
+    match path.to_str().and_then(|path| CString::new(path).ok()) {
+        Some(path) => Ok(path),
+        None => Err(io::Error::from(INVALID_ARGUMENT).into()),
     }
 }
 
-impl Drop for BioBox {
-    fn drop(&mut self) {
-        unsafe  { BIO_free_all(self.handle) }
-    }
+fn read_file(path: &Path) -> Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    File::open(path)?.read_to_end(&mut buf)?;
+    Ok(buf)
 }
 
-struct X509Box { handle: *mut X509 }
-
-impl X509Box {
-    fn pem_read_bio(bio: *mut BIO) -> Result<X509Box> {
-        let handle = unsafe { PEM_read_bio_X509(bio, ptr::null_mut(), None, ptr::null_mut()) };
-        if handle.is_null() {
-            Err(Error::last_ssl_error())
-        } else {
-            Ok(X509Box { handle: handle })
-        }
-    }
+/// The context of the SSL/TLS streams, that holds the certificates, the private key and the
+/// settings of the verification.
+///
+/// The settings are applied to the streams created after that.
+///
+/// # Examples
+///
+/// ```
+/// use asyncio::ssl::{SslContext, SslVerifyMode};
+///
+/// let mut ctx = SslContext::sslv23().unwrap();
+/// ctx.set_verify_mode(SslVerifyMode::PEER);
+/// ctx.set_default_verify_paths().unwrap();
+/// ```
+pub struct SslContext {
+    builder: SslContextBuilder,
+    verify_mode: SslVerifyMode,
+    verify_callback: Option<VerifyCallback>,
 }
 
-impl Drop for X509Box {
-    fn drop(&mut self) {
-        unsafe { X509_free(self.handle) }
-    }
-}
-
-struct DHBox { handle: *mut DH }
-
-impl DHBox {
-    fn pem_read_bio(bio: *mut BIO) -> Result<DHBox> {
-        let handle = unsafe { PEM_read_bio_DHparams(bio, ptr::null_mut(), None, ptr::null_mut()) };
-        if handle.is_null() {
-            Err(Error::last_ssl_error())
-        } else {
-            Ok(DHBox { handle: handle })
+impl SslContext {
+    fn with_version(version: Option<SslVersion>) -> Result<SslContext> {
+        let mut builder = SslContextBuilder::new(SslMethod::tls())?;
+        if version.is_some() {
+            builder.set_min_proto_version(version)?;
+            builder.set_max_proto_version(version)?;
         }
+        Ok(SslContext {
+            builder: builder,
+            verify_mode: SslVerifyMode::NONE,
+            verify_callback: None,
+        })
     }
-}
 
-impl Drop for DHBox {
-    fn drop(&mut self) {
-        unsafe { DH_free(self.handle) }
+    /// Returns a context that negotiates the highest version of the protocols.
+    pub fn sslv23() -> Result<SslContext> {
+        Self::with_version(None)
     }
-}
-
-struct SslContextImpl {
-    handle: *mut SSL_CTX,
-    verify_callback: Box<Fn(bool, &SslVerifyContext) -> bool>,
-    passwd_callback: Box<Fn(&mut [u8], PasswordPurpose) -> usize>,
-}
 
-impl SslContextImpl {
-    fn new(method: unsafe extern "C" fn() -> *const SSL_METHOD) -> SslContextImpl {
-        init();
-
-        let handle = unsafe { SSL_CTX_new(method()) };
-        assert!( !handle.is_null() );
-        SslContextImpl {
-            handle: handle,
-            verify_callback: Box::new(|_, _| false),
-            passwd_callback: Box::new(|_, _| 0),
-        }
+    /// Returns a context of the SSL v3 only, that may not be supported by the OpenSSL.
+    pub fn sslv3() -> Result<SslContext> {
+        Self::with_version(Some(SslVersion::SSL3))
     }
 
-    unsafe extern "C" fn password_callback(buf: *mut c_char, size: c_int, rwflag: c_int, user_data: *mut c_void) -> c_int {
-        let ctx = &*(user_data as *const SslContextImpl);
-        let buf = slice::from_raw_parts_mut(buf as *mut u8, size as usize);
-        (*ctx.passwd_callback)(buf, mem::transmute(rwflag)) as c_int
+    /// Returns a context of the TLS v1 only.
+    pub fn tlsv1() -> Result<SslContext> {
+        Self::with_version(Some(SslVersion::TLS1))
     }
 
-    fn set_password_callback<F>(&mut self, callback: F) -> Result<()>
-        where F: Fn(&mut [u8], PasswordPurpose) -> usize + 'static
-    {
-        self.passwd_callback = Box::new(callback);
-        unsafe {
-            SSL_CTX_set_default_passwd_cb_userdata(self.handle, self as *mut _ as *mut c_void);
-            SSL_CTX_set_default_passwd_cb(self.handle, Self::password_callback);
-        }
+    /// Adds the certificate authority to verify the peer.
+    pub fn add_certificate_authority(&mut self, cert: &[u8], fmt: FileFormat) -> Result<()> {
+        let cert = match fmt {
+            FileFormat::ASN1 => X509::from_der(cert)?,
+            FileFormat::PEM => X509::from_pem(cert)?,
+        };
+        self.builder.cert_store_mut().add_cert(cert)?;
         Ok(())
     }
 
-    extern "C" fn verify_callback(preverified: c_int, ctx: *mut X509_STORE_CTX) -> c_int {
-        assert!( !ctx.is_null() );
-        unsafe {
-            let ssl =  X509_STORE_CTX_get_ex_data(ctx, SSL_get_ex_data_X509_STORE_CTX_idx()) as *mut SSL;
-            if !ssl.is_null() {
-                let this = &*(SSL_CTX_get_app_data(SSL_get_SSL_CTX(ssl)) as *mut SslContextImpl);
-                return (*this.verify_callback)(preverified != 0, SslVerifyContext::from_ptr(ctx)) as i32
-            }
-        }
-        0
-    }
-
-    fn set_verify_callback<F>(&mut self, callback: F) -> Result<()>
-        where F: Fn(bool, &SslVerifyContext) -> bool + 'static
+    /// Adds the directory of the certificate authorities to verify the peer.
+    pub fn add_verify_path<P>(&mut self, path: P) -> Result<()>
+    where
+        P: AsRef<Path>,
     {
-        self.verify_callback = Box::new(callback);
-        unsafe {
-            SSL_CTX_set_app_data(self.handle, self as *mut _ as *mut c_void);
-            SSL_CTX_set_verify(self.handle, SSL_CTX_get_verify_mode(self.handle), Some(Self::verify_callback));
-        }
-        Ok(())
-    }
-}
-
-unsafe impl Send for SslContextImpl {
-}
-
-unsafe impl Sync for SslContextImpl {
-}
-
-#[derive(Clone)]
-pub struct SslContext(Arc<SslContextImpl>);
-
-impl SslContext {
-    pub fn sslv23() -> SslContext {
-        SslContext(Arc::new(SslContextImpl::new(SSLv23_method)))
-    }
-
-    pub fn sslv3() -> SslContext {
-        SslContext(Arc::new(SslContextImpl::new(SSLv3_method)))
-    }
-
-    pub fn tlsv1() -> SslContext {
-        SslContext(Arc::new(SslContextImpl::new(TLSv1_method)))
-    }
-
-    pub unsafe fn as_ptr(&self) -> *mut SSL_CTX {
-        self.0.handle
-    }
-
-    pub fn add_certificate_authority() {
-        // TODO
-    }
-
-    pub fn add_verify_path<P>(&self, path: P) -> Result<()>
-        where P: AsRef<Path>
-    {
-        clear_error();
-        let path = CString::new(path.as_ref().to_str().unwrap()).unwrap();
-        if unsafe { SSL_CTX_load_verify_locations(self.0.handle, ptr::null(), path.as_ptr() as *const i8) } == 1 {
+        let path = path_to_cstring(path.as_ref())?;
+        let ctx = self.builder.as_ptr();
+        if unsafe { SSL_CTX_load_verify_locations(ctx, ptr::null(), path.as_ptr()) } == 1 {
             Ok(())
         } else {
             Err(Error::last_ssl_error())
         }
     }
 
-    pub fn clear_options(&self, options: SslOptions) -> Result<()> {
-        unsafe { SSL_CTX_clear_options(self.0.handle, options.bits() as c_long) };
-        Ok(())
+    /// Clears the options of the context.
+    pub fn clear_options(&mut self, options: SslOptions) {
+        self.builder.clear_options(options);
     }
 
-    pub fn load_verify_file<P>(&self, path: P) -> Result<()>
-        where P: AsRef<Path>
+    /// Loads the file of the certificate authorities to verify the peer.
+    pub fn load_verify_file<P>(&mut self, path: P) -> Result<()>
+    where
+        P: AsRef<Path>,
     {
-        clear_error();
-        let path = CString::new(path.as_ref().to_str().unwrap()).unwrap();
-        if unsafe { SSL_CTX_load_verify_locations(self.0.handle, path.as_ptr() as *const i8, ptr::null()) } == 1 {
-            Ok(())
-        } else {
-            Err(Error::last_ssl_error())
-        }
-    }
-
-    pub fn set_default_verify_paths(&self) -> Result<()> {
-        clear_error();
-        if unsafe { SSL_CTX_set_default_verify_paths(self.0.handle) } == 1 {
-            Ok(())
-        } else {
-            Err(Error::last_ssl_error())
-        }
+        self.builder.set_ca_file(path)?;
+        Ok(())
     }
 
-    pub fn set_options(&self, options: SslOptions) -> Result<()> {
-        unsafe { SSL_CTX_set_options(self.0.handle, options.bits() as c_long) };
+    /// Uses the default locations of the certificate authorities.
+    pub fn set_default_verify_paths(&mut self) -> Result<()> {
+        self.builder.set_default_verify_paths()?;
         Ok(())
     }
 
-    pub fn set_password_callback<F>(&mut self, callback: F) -> Result<()>
-        where F: Fn(&mut [u8], PasswordPurpose) -> usize + 'static
-    {
-        let ctx = &*self.0 as *const SslContextImpl;
-        if let Some(imp) = Arc::get_mut(&mut self.0) {
-            imp.set_password_callback(callback)
-        } else {
-            panic!("It has any strong reference.");  // TODO: returns a Err<Any>
-        }
+    /// Sets the options of the context.
+    pub fn set_options(&mut self, options: SslOptions) {
+        self.builder.set_options(options);
     }
 
-    pub fn set_verify_callback<F>(&mut self, callback: F) -> Result<()>
-        where F: Fn(bool, &SslVerifyContext) -> bool + 'static
+    /// Sets the callback to verify the certificates of the peer.
+    ///
+    /// The callback is given whether the certificate is verified by the OpenSSL, and returns
+    /// whether the certificate is accepted.
+    pub fn set_verify_callback<F>(&mut self, callback: F)
+    where
+        F: Fn(bool, &SslVerifyContext) -> bool + Send + Sync + 'static,
     {
-        let ctx = &*self.0 as *const SslContextImpl;
-        if let Some(imp) = Arc::get_mut(&mut self.0) {
-            imp.set_verify_callback(callback)
-        } else {
-            panic!("It has any strong reference.");  // TODO: returns a Err<Any>
-        }
-    }
-
-    pub fn set_verify_depth(&self, depth: i32) -> Result<()> {
-        unsafe { SSL_CTX_set_verify_depth(self.0.handle, depth) };
-        Ok(())
+        self.verify_callback = Some(Arc::new(callback));
+        let mode = self.verify_mode;
+        self.set_verify_mode(mode)
     }
 
-    pub fn set_verify_mode(&self, mode: SslVerifyMode) -> Result<()> {
-        unsafe { SSL_CTX_set_verify(self.0.handle, mode.bits(), SSL_CTX_get_verify_callback(self.0.handle)) };
-        Ok(())
+    /// Sets the maximum depth of the certificate chain to verify.
+    pub fn set_verify_depth(&mut self, depth: u32) {
+        self.builder.set_verify_depth(depth)
     }
 
-    pub fn use_certificate(&self, cert: &[u8], fmt: FileFormat) -> Result<()> {
-        clear_error();
-        match fmt {
-            FileFormat::PEM => {
-                let bio = try!(BioBox::new_mem_buf(cert));
-                let cert = try!(X509Box::pem_read_bio(bio.handle));
-                if unsafe { SSL_CTX_use_certificate(self.0.handle, cert.handle) } == 1 {
-                    return Ok(());
-                }
-            },
+    /// Sets the mode of the verification.
+    pub fn set_verify_mode(&mut self, mode: SslVerifyMode) {
+        self.verify_mode = mode;
+        match self.verify_callback {
+            Some(ref callback) => {
+                let callback = callback.clone();
+                self.builder.set_verify_callback(mode, move |ok, ctx| callback(ok, ctx))
+            }
+            None => self.builder.set_verify(mode),
         }
-        Err(Error::last_ssl_error())
     }
 
-    pub fn use_certificate_chain() {
-        // TODO
+    /// Uses the certificate of the host.
+    pub fn use_certificate(&mut self, cert: &[u8], fmt: FileFormat) -> Result<()> {
+        let cert = match fmt {
+            FileFormat::ASN1 => X509::from_der(cert)?,
+            FileFormat::PEM => X509::from_pem(cert)?,
+        };
+        self.builder.set_certificate(&cert)?;
+        Ok(())
     }
 
-    pub fn use_certificate_chain_file<P>(&self, path: P) -> Result<()>
-        where P: AsRef<Path>
-    {
-        clear_error();
-        let path = CString::new(path.as_ref().to_str().unwrap()).unwrap();
-        if unsafe { SSL_CTX_use_certificate_chain_file(self.0.handle, path.as_ptr() as *const i8) } == 1 {
-            Ok(())
-        } else {
-            Err(Error::last_ssl_error())
+    /// Uses the certificate chain of the host in the PEM format.
+    pub fn use_certificate_chain(&mut self, chain: &[u8]) -> Result<()> {
+        let mut chain = X509::stack_from_pem(chain)?.into_iter();
+        match chain.next() {
+            Some(cert) => self.builder.set_certificate(&cert)?,
+            None => return Err(io::Error::from(INVALID_ARGUMENT).into()),
         }
-    }
-
-    pub fn use_certificate_file<P>(&self, path: P, fmt: FileFormat) -> Result<()>
-        where P: AsRef<Path>
-    {
-        clear_error();
-        let path = CString::new(path.as_ref().to_str().unwrap()).unwrap();
-        if unsafe { SSL_CTX_use_certificate_file(self.0.handle, path.as_ptr() as *const i8, fmt as i32) } == 1 {
-            Ok(())
-        } else {
-            Err(Error::last_ssl_error())
+        for cert in chain {
+            self.builder.add_extra_chain_cert(cert)?;
         }
+        Ok(())
     }
 
-    pub fn use_private_key() {
-        // TOOD
-    }
-
-    pub fn use_private_key_file<P>(&self, path: P, fmt: FileFormat) -> Result<()>
-        where P: AsRef<Path>
+    /// Uses the file of the certificate chain of the host in the PEM format.
+    pub fn use_certificate_chain_file<P>(&mut self, path: P) -> Result<()>
+    where
+        P: AsRef<Path>,
     {
-        clear_error();
-        match fmt {
-            FileFormat::PEM => {
-                let path = CString::new(path.as_ref().to_str().unwrap()).unwrap();
-                if unsafe { SSL_CTX_use_PrivateKey_file(self.0.handle, path.as_ptr() as *const i8, fmt as i32) } == 1 {
-                    Ok(())
-                } else {
-                    Err(Error::last_ssl_error())
-                }
-            },
-        }
+        self.builder.set_certificate_chain_file(path)?;
+        Ok(())
     }
 
-    pub fn use_rsa_private_key() {
-        // TODO
+    /// Uses the file of the certificate of the host.
+    pub fn use_certificate_file<P>(&mut self, path: P, fmt: FileFormat) -> Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        self.builder.set_certificate_file(path, fmt.into())?;
+        Ok(())
     }
 
-    pub fn use_rsa_prive_key_file() {
-        // TODO
+    /// Uses the private key of the host.
+    pub fn use_private_key(&mut self, key: &[u8], fmt: FileFormat) -> Result<()> {
+        let key = match fmt {
+            FileFormat::ASN1 => PKey::private_key_from_der(key)?,
+            FileFormat::PEM => PKey::private_key_from_pem(key)?,
+        };
+        self.builder.set_private_key(&key)?;
+        Ok(())
     }
 
-    fn do_use_tmp_dh(&self, bio: BioBox) -> Result<()> {
-        let dh = try!(DHBox::pem_read_bio(bio.handle));
-        if unsafe { SSL_CTX_set_tmp_dh(self.0.handle, dh.handle) } == 1 {
-            Ok(())
-        } else {
-            Err(Error::last_ssl_error())
-        }
+    /// Uses the file of the private key of the host.
+    pub fn use_private_key_file<P>(&mut self, path: P, fmt: FileFormat) -> Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        self.builder.set_private_key_file(path, fmt.into())?;
+        Ok(())
     }
 
-    pub fn use_tmp_dh(&self, dh: &[u8]) -> Result<()> {
-        clear_error();
-        let bio = try!(BioBox::new_mem_buf(dh));
-        self.do_use_tmp_dh(bio)
+    /// Uses the Diffie-Hellman parameters in the PEM format.
+    pub fn use_tmp_dh(&mut self, dh: &[u8]) -> Result<()> {
+        let dh = Dh::params_from_pem(dh)?;
+        self.builder.set_tmp_dh(&dh)?;
+        Ok(())
     }
 
-    pub fn use_tmp_dh_file<P>(&self, path: P) -> Result<()>
-        where P: AsRef<Path>
+    /// Uses the file of the Diffie-Hellman parameters in the PEM format.
+    pub fn use_tmp_dh_file<P>(&mut self, path: P) -> Result<()>
+    where
+        P: AsRef<Path>,
     {
-        clear_error();
-        let path = CString::new(path.as_ref().to_str().unwrap()).unwrap();
-        let bio = try!(BioBox::new_file(&path));
-        self.do_use_tmp_dh(bio)
+        let dh = read_file(path.as_ref())?;
+        self.use_tmp_dh(&dh)
+    }
+
+    #[doc(hidden)]
+    pub fn new_ssl(&self) -> Result<Ssl> {
+        // Shares the context being built, that is referenced by the new one.
+        let ctx = unsafe {
+            let ptr = self.builder.as_ptr();
+            SSL_CTX_up_ref(ptr);
+            SslContextBuilder::from_ptr(ptr).build()
+        };
+        Ok(Ssl::new(&ctx)?)
     }
 }
 
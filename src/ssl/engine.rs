@@ -0,0 +1,175 @@
+use ffi::INVALID_ARGUMENT;
+use ssl::{Result, Handshake, SslContext, SslVerifyContext, SslVerifyMode};
+
+use std::cmp;
+use std::io;
+use std::mem;
+use std::slice;
+use std::sync::Arc;
+use openssl::ssl::{ErrorCode, ShutdownResult, Ssl, SslRef, SslStream};
+
+type VerifyCallback = Arc<Fn(bool, &SslVerifyContext) -> bool + Send + Sync>;
+
+/// The transport of the engine, that holds the data received from the next layer and the data
+/// to be sent to it.
+#[derive(Default)]
+struct Bio {
+    input: Vec<u8>,
+    output: Vec<u8>,
+}
+
+impl io::Read for Bio {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.input.is_empty() {
+            return Err(io::ErrorKind::WouldBlock.into());
+        }
+        let len = cmp::min(buf.len(), self.input.len());
+        buf[..len].copy_from_slice(&self.input[..len]);
+        self.input.drain(..len);
+        Ok(len)
+    }
+}
+
+impl io::Write for Bio {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.output.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// What the engine wants to the next layer.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Want {
+    /// Reads from the next layer, and then retries the operation.
+    InputAndRetry,
+
+    /// Writes the output to the next layer, and then retries the operation.
+    OutputAndRetry,
+
+    /// The operation is completed.
+    Nothing,
+
+    /// Writes the output to the next layer, and then the operation is completed.
+    Output,
+}
+
+pub enum Op {
+    Handshake(Handshake),
+    Read(*mut u8, usize),
+    Write(*const u8, usize),
+    Shutdown,
+}
+
+/// Performs the SSL/TLS operations on the memory, that the data is transferred by the stream.
+pub struct Engine {
+    ssl: Option<Ssl>,
+    stream: Option<SslStream<Bio>>,
+    verify_callback: Option<VerifyCallback>,
+}
+
+impl Engine {
+    pub fn new(ctx: &SslContext) -> Result<Engine> {
+        Ok(Engine {
+            ssl: Some(ctx.new_ssl()?),
+            stream: None,
+            verify_callback: None,
+        })
+    }
+
+    /// Passes the data read from the next layer to the engine.
+    pub fn commit_input(&mut self, buf: &[u8]) {
+        let stream = self.stream.as_mut().unwrap();
+        stream.get_mut().input.extend_from_slice(buf);
+    }
+
+    /// Takes the data to be written to the next layer.
+    pub fn take_output(&mut self) -> Vec<u8> {
+        match self.stream {
+            Some(ref mut stream) => mem::replace(&mut stream.get_mut().output, Vec::new()),
+            None => Vec::new(),
+        }
+    }
+
+    pub fn perform(&mut self, op: &Op) -> (Want, Result<usize>) {
+        if let Some(ssl) = self.ssl.take() {
+            match SslStream::new(ssl, Bio::default()) {
+                Ok(stream) => self.stream = Some(stream),
+                Err(err) => return (Want::Nothing, Err(err.into())),
+            }
+        }
+
+        let stream = self.stream.as_mut().unwrap();
+        let res = match *op {
+            Op::Handshake(Handshake::Client) => stream.connect().map(|_| 0).map_err(Some),
+            Op::Handshake(Handshake::Server) => stream.accept().map(|_| 0).map_err(Some),
+            Op::Read(buf, len) => {
+                stream.ssl_read(unsafe { slice::from_raw_parts_mut(buf, len) }).map_err(Some)
+            }
+            Op::Write(buf, len) => {
+                stream.ssl_write(unsafe { slice::from_raw_parts(buf, len) }).map_err(Some)
+            }
+            Op::Shutdown => {
+                match stream.shutdown() {
+                    // Sent the close_notify, and then waits for it from the peer.
+                    Ok(ShutdownResult::Sent) => Err(None),
+                    Ok(ShutdownResult::Received) => Ok(0),
+                    Err(err) => Err(Some(err)),
+                }
+            }
+        };
+
+        let pending = !stream.get_ref().output.is_empty();
+        let (want, done) = if pending {
+            (Want::OutputAndRetry, Want::Output)
+        } else {
+            (Want::InputAndRetry, Want::Nothing)
+        };
+        match res {
+            Ok(len) => (done, Ok(len)),
+            Err(None) => (want, Ok(0)),
+            Err(Some(ref err)) if err.code() == ErrorCode::WANT_READ ||
+                                      err.code() == ErrorCode::WANT_WRITE => (want, Ok(0)),
+            // Closed by the peer.
+            Err(Some(ref err)) if err.code() == ErrorCode::ZERO_RETURN => (done, Ok(0)),
+            Err(Some(err)) => (done, Err(err.into())),
+        }
+    }
+
+    fn ssl_mut(&mut self) -> Result<&mut SslRef> {
+        match self.ssl {
+            Some(ref mut ssl) => Ok(&mut **ssl),
+            // The handshake is already started.
+            None => Err(io::Error::from(INVALID_ARGUMENT).into()),
+        }
+    }
+
+    pub fn set_host_name(&mut self, host: &str) -> Result<()> {
+        self.ssl_mut()?.set_hostname(host)?;
+        Ok(())
+    }
+
+    pub fn set_verify_callback(&mut self, callback: VerifyCallback) -> Result<()> {
+        let mode = self.ssl_mut()?.verify_mode();
+        self.verify_callback = Some(callback);
+        self.set_verify_mode(mode)
+    }
+
+    pub fn set_verify_depth(&mut self, depth: u32) -> Result<()> {
+        self.ssl_mut()?.param_mut().set_depth(depth as i32);
+        Ok(())
+    }
+
+    pub fn set_verify_mode(&mut self, mode: SslVerifyMode) -> Result<()> {
+        let callback = self.verify_callback.clone();
+        let ssl = self.ssl_mut()?;
+        match callback {
+            Some(callback) => ssl.set_verify_callback(mode, move |ok, ctx| callback(ok, ctx)),
+            None => ssl.set_verify(mode),
+        }
+        Ok(())
+    }
+}
@@ -1,26 +1,23 @@
 use std::error;
-use std::result;
-use std::mem;
-use std::str;
 use std::fmt;
 use std::io;
-use openssl;
-use openssl_sys::*;
+use std::result;
+use openssl::error::ErrorStack;
+use openssl::ssl;
 
+/// The error of the SSL/TLS operations.
 #[derive(Debug)]
 pub enum Error {
-    Ssl(openssl::error::Error),
+    /// The errors queued in the OpenSSL.
+    Ssl(ErrorStack),
+    /// The error of the next layer.
     Sys(io::Error),
 }
 
-
 impl Error {
+    /// Returns the errors queued in the OpenSSL, the queue is cleared.
     pub fn last_ssl_error() -> Error {
-        Error::Ssl(unsafe { mem::transmute(ERR_get_error()) })
-    }
-
-    pub fn last_sys_error() -> Error {
-        Error::Sys(io::Error::from_raw_os_error(unsafe { ERR_get_error() as i32 }))
+        Error::Ssl(ErrorStack::get())
     }
 }
 
@@ -39,17 +36,38 @@ impl From<io::Error> for Error {
     }
 }
 
-impl From<openssl::error::Error> for Error {
-    fn from(err: openssl::error::Error) -> Error {
+impl From<ErrorStack> for Error {
+    fn from(err: ErrorStack) -> Error {
         Error::Ssl(err)
     }
 }
 
+impl From<ssl::Error> for Error {
+    fn from(err: ssl::Error) -> Error {
+        match err.into_io_error() {
+            Ok(err) => Error::Sys(err),
+            Err(err) => match err.ssl_error() {
+                Some(stack) => Error::Ssl(stack.clone()),
+                None => Error::Sys(io::Error::new(io::ErrorKind::Other, err.to_string())),
+            },
+        }
+    }
+}
+
+impl From<Error> for io::Error {
+    fn from(err: Error) -> io::Error {
+        match err {
+            Error::Ssl(err) => io::Error::new(io::ErrorKind::Other, err),
+            Error::Sys(err) => err,
+        }
+    }
+}
+
 impl error::Error for Error {
-    fn description(&self) -> &str {
+    fn source(&self) -> Option<&(error::Error + 'static)> {
         match self {
-            &Error::Ssl(ref err) => err.description(),
-            &Error::Sys(ref err) => err.description(),
+            &Error::Ssl(ref err) => Some(err),
+            &Error::Sys(ref err) => Some(err),
         }
     }
 }
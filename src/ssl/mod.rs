@@ -1,16 +1,18 @@
-mod ffi;
+//! The SSL/TLS streams on the OpenSSL.
 
-// mod error;
-// pub use self::error::{Error, Result};
-//
-// mod types;
-// pub use self::types::*;
-//
-// mod verify;
-// pub use self::verify::{SslVerifyContext, Rfc2818Verification};
-//
-// mod context;
-// pub use self::context::SslContext;
-//
-// mod stream;
-// pub use self::stream::SslStream;
+mod error;
+pub use self::error::{Error, Result};
+
+mod types;
+pub use self::types::*;
+
+mod verify;
+pub use self::verify::{SslVerifyContext, Rfc2818Verification};
+
+mod context;
+pub use self::context::SslContext;
+
+mod engine;
+
+mod stream;
+pub use self::stream::SslStream;
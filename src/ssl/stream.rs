@@ -1,442 +1,436 @@
-use unsafe_cell::UnsafeRefCell;
-use error::{ErrCode, eof};
-use core::{IoContext, AsIoContext};
-use async::Handler;
-use streams::Stream;
-use buffers::StreamBuf;
-use ssl::*;
-use ssl::ffi::*;
+use ffi::{Timeout, OPERATION_CANCELED};
+use core::{IoContext, AsIoContext, ThreadIoContext, Cancel};
+use handler::{Handler, Complete, Success, Failure};
+use stream::Stream;
+use ssl::{Error, Result, Handshake, SslContext, SslVerifyContext, SslVerifyMode};
+use ssl::engine::{Engine, Op, Want};
 
 use std::io;
-use std::ptr;
-use std::sync::Mutex;
-use libc::{c_void, c_int, size_t};
-use openssl_sys::*;
-use openssl::types::OpenSslTypeRef;
-
-lazy_static! {
-    static ref ACCEPT_MUTEX: Mutex<()> = Mutex::new(());
-}
+use std::mem;
+use std::slice;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+/// Completes the handshake and the shutdown without the length.
+struct NoLength<F>(F);
+
+impl<F> Complete<usize, Error> for NoLength<F>
+where
+    F: Complete<(), Error>,
+{
+    fn success(self, this: &mut ThreadIoContext, _: usize) {
+        self.0.success(this, ())
+    }
 
-fn do_accept(ssl: *mut SSL, _: *mut c_void, _: size_t) -> c_int {
-    let _lock = ACCEPT_MUTEX.lock();
-    unsafe { SSL_accept(ssl) }
+    fn failure(self, this: &mut ThreadIoContext, err: Error) {
+        self.0.failure(this, err)
+    }
 }
 
-fn do_connect(ssl: *mut SSL, _: *mut c_void, _: size_t) -> c_int {
-    unsafe { SSL_connect(ssl) }
-}
+/// The operation queued while the other operation is running.
+trait QueuedIo: Send {
+    fn start_box(self: Box<Self>, ctx: &IoContext);
 
-fn do_read(ssl: *mut SSL, data: *mut c_void, len: size_t) -> c_int {
-    assert!(len <= i32::max_value() as size_t);
-    unsafe { SSL_read(ssl, data, len as c_int) }
+    fn cancel_box(self: Box<Self>, ctx: &IoContext);
 }
 
-fn do_write(ssl: *mut SSL, data: *mut c_void, len: size_t) -> c_int {
-    assert!(len <= i32::max_value() as size_t);
-    unsafe { SSL_write(ssl, data, len as c_int) }
-}
+impl<S, F> QueuedIo for SslIo<S, F>
+where
+    S: Stream<Error = io::Error>,
+    F: Complete<usize, Error>,
+{
+    fn start_box(self: Box<Self>, ctx: &IoContext) {
+        (*self).start(ctx)
+    }
 
-fn do_shutdown(ssl: *mut SSL, _: *mut c_void, _: size_t) -> c_int {
-    let res = unsafe { SSL_shutdown(ssl) };
-    if res == 0 {
-        unsafe { SSL_shutdown(ssl) }
-    } else {
-        res
+    fn cancel_box(self: Box<Self>, ctx: &IoContext) {
+        let err = Error::from(io::Error::from(OPERATION_CANCELED));
+        ctx.do_post(Failure::new(err, self.handler))
     }
 }
 
-enum Want {
-    InputAndRetry = -2,
-
-    OutputAndRetry = -1,
-
-    Nothing = 0,
-
-    Output = 1,
+/// The engine and the queue of the operations, that runs one operation at a time.
+struct SslState {
+    engine: Engine,
+    busy: bool,
+    queue: VecDeque<Box<QueuedIo>>,
 }
 
-struct Engine {
-    ssl: *mut SSL,
-    ext_bio: *mut BIO,
-    verify_callback: Box<Fn(bool, &SslVerifyContext) -> bool>,
+struct SslIo<S, F> {
+    ssl: *const SslStream<S>,
+    op: Op,
+    want: Want,
+    res: Option<Result<usize>>,
+    input: Vec<u8>,
+    output: Vec<u8>,
+    handler: F,
 }
 
-impl Engine {
-    pub fn new(ctx: &SslContext) -> Result<Engine> {
-        let ssl = unsafe { SSL_new(ctx.as_ptr()) };
-        if ssl.is_null() {
-            return Err(Error::last_ssl_error());
-        }
-
-        unsafe {
-            SSL_set_mode(ssl, SSL_MODE_ENABLE_PARTIAL_WRITE);
-            SSL_set_mode(ssl, SSL_MODE_ACCEPT_MOVING_WRITE_BUFFER);
-            SSL_set_mode(ssl, SSL_MODE_RELEASE_BUFFERS);
-        }
-
-        let mut ext_bio = ptr::null_mut();
-        let mut int_bio = ptr::null_mut();
-        unsafe {
-            BIO_new_bio_pair(&mut int_bio, 4096, &mut ext_bio, 4096);
-            SSL_set_bio(ssl, int_bio, int_bio);
+unsafe impl<S, F> Send for SslIo<S, F> {}
+
+impl<S, F> SslIo<S, F>
+where
+    S: Stream<Error = io::Error>,
+    F: Complete<usize, Error>,
+{
+    fn start(mut self, ctx: &IoContext) {
+        let next = match self.perform() {
+            Some(Ok(len)) => {
+                let next = unsafe { &*self.ssl }.take_next();
+                ctx.do_dispatch(Success::new(len, self.handler));
+                next
+            }
+            Some(Err(err)) => {
+                let next = unsafe { &*self.ssl }.take_next();
+                ctx.do_dispatch(Failure::new(err, self.handler));
+                next
+            }
+            None => return self.next_layer(),
+        };
+        if let Some(io) = next {
+            io.start_box(ctx)
         }
-        Ok(Engine {
-            ssl: ssl,
-            ext_bio: ext_bio,
-            verify_callback: Box::new(|_,_| false),
-        })
-    }
-
-    pub fn set_verify_mode(&self, mode: SslVerifyMode) -> Result<()> {
-        unsafe { SSL_set_verify(self.ssl, mode.bits(), SSL_get_verify_callback(self.ssl)) };
-        Ok(())
     }
 
-    pub fn set_verify_depth(&self, depth: i32) -> Result<()> {
-        unsafe { SSL_set_verify_depth(self.ssl, depth as c_int) };
-        Ok(())
-    }
-
-    extern "C" fn verify_callback(preverified: c_int, ctx: *mut X509_STORE_CTX) -> c_int {
-        if !ctx.is_null() {
-            unsafe {
-                let ssl = X509_STORE_CTX_get_ex_data(ctx, SSL_get_ex_data_X509_STORE_CTX_idx()) as *mut SSL;
-                if !ssl.is_null() {
-                    let this = &*(SSL_get_app_data(ssl) as *const Self);
-                    return (*this.verify_callback)(preverified != 0, SslVerifyContext::from_ptr(ctx)) as c_int;
-                }
+    /// Performs the operation, returns `None` if the next layer is needed.
+    fn perform(&mut self) -> Option<Result<usize>> {
+        let (want, res) = {
+            let mut state = unsafe { &*self.ssl }.state.lock().unwrap();
+            let (want, res) = state.engine.perform(&self.op);
+            if want == Want::Output || want == Want::OutputAndRetry {
+                self.output = state.engine.take_output();
             }
+            (want, res)
+        };
+        self.want = want;
+        match want {
+            Want::Nothing => Some(res),
+            Want::Output => {
+                self.res = Some(res);
+                None
+            }
+            _ => None,
         }
-        0
     }
 
-    pub fn set_verify_callback<F>(&mut self, callback: F) -> Result<()>
-        where F: Fn(bool, &SslVerifyContext) -> bool + 'static
-    {
-        let user_data = self as *mut Self;
-        self.verify_callback = Box::new(callback);
-        unsafe {
-            SSL_set_app_data(self.ssl, user_data as *mut c_void);
-            SSL_set_verify(self.ssl, SSL_get_verify_mode(self.ssl), Some(Self::verify_callback));
+    fn next_layer(mut self) {
+        let soc = &unsafe { &*self.ssl }.soc;
+        // The buffers are owned by the handler, that is alive until the operation is completed.
+        match self.want {
+            Want::InputAndRetry => {
+                self.input.resize(4096, 0);
+                let buf = unsafe { slice::from_raw_parts(self.input.as_ptr(), self.input.len()) };
+                soc.async_read_some(buf, self)
+            }
+            _ => {
+                let buf = unsafe { slice::from_raw_parts(self.output.as_ptr(), self.output.len()) };
+                soc.async_write_some(buf, self)
+            }
         }
-        Ok(())
     }
 
-    fn perform(&self, op: fn(*mut SSL, *mut c_void, size_t) -> c_int, data: *mut c_void, len: size_t) -> (Want, Result<usize>) {
-        let pending_output_before = unsafe { BIO_ctrl_pending(self.ext_bio) };
-        clear_error();
-        let res = op(self.ssl, data, len);
-        let err = unsafe { SSL_get_error(self.ssl, res) };
-        let pending_output_after = unsafe { BIO_ctrl_pending(self.ext_bio) };
-
-        if err == SSL_ERROR_SSL {
-            (Want::Nothing, Err(Error::last_ssl_error()))
-        }
-        else if err == SSL_ERROR_SYSCALL {
-            (Want::Nothing, Err(Error::last_sys_error()))
-        }
-        else if err == SSL_ERROR_WANT_WRITE {
-            (Want::OutputAndRetry, Ok(res as usize))
-        }
-        else if pending_output_after > pending_output_before {
-            (if res > 0 { Want::Output } else { Want::OutputAndRetry }, Ok(res as usize))
+    /// Completes the operation, and then starts the next queued operation.
+    ///
+    /// The next operation is taken out before the handler, which may drop the stream, and is
+    /// counted as the outstanding work in between.
+    fn complete(self, this: &mut ThreadIoContext, res: Result<usize>) {
+        let next = unsafe { &*self.ssl }.take_next();
+        if next.is_some() {
+            this.increase_outstanding_work();
         }
-        else if err == SSL_ERROR_WANT_READ {
-            (Want::InputAndRetry, Ok(res as usize))
+        match res {
+            Ok(len) => self.handler.success(this, len),
+            Err(err) => self.handler.failure(this, err),
         }
-        else if unsafe { SSL_get_shutdown(self.ssl) } & SSL_RECEIVED_SHUTDOWN != 0 {
-            (Want::Nothing, Err(eof().into()))
-        }
-        else {
-            (Want::Nothing, Ok(res as usize))
-        }
-    }
-
-    pub fn handshake(&self, mode: Handshake) -> (Want, Result<usize>) {
-        self.perform(match mode {
-            Handshake::Client => do_connect,
-            Handshake::Server => do_accept,
-        }, ptr::null_mut(), 0)
-    }
-
-    pub fn shutdown(&self) -> (Want, Result<usize>) {
-        self.perform(do_shutdown, ptr::null_mut(), 0)
-    }
-
-    pub fn read(&self, buf: &mut [u8]) -> (Want, Result<usize>) {
-        if buf.len() == 0 {
-            (Want::Nothing, Ok(0))
-        } else {
-            self.perform(do_read, buf.as_ptr() as *mut c_void, buf.len())
+        if let Some(io) = next {
+            io.start_box(this.as_ctx());
+            this.decrease_outstanding_work();
         }
     }
+}
 
-    pub fn write(&self, buf: &[u8]) -> (Want, Result<usize>) {
-        if buf.len() == 0 {
-            (Want::Nothing, Ok(0))
-        } else {
-            self.perform(do_write, buf.as_ptr() as *const _ as *mut c_void, buf.len())
-        }
-    }
+impl<S, F> Handler<usize, io::Error> for SslIo<S, F>
+where
+    S: Stream<Error = io::Error>,
+    F: Complete<usize, Error>,
+{
+    type Output = ();
 
-    pub fn get_output(&self, sbuf: &mut StreamBuf) {
-        let len = {
-            let buf = sbuf.prepare(4096).unwrap();
-            unsafe { BIO_read(self.ext_bio, buf.as_ptr() as *mut c_void, buf.len() as c_int) }
-        };
-        sbuf.commit(len as usize);
-    }
+    type WrappedHandler = Self;
 
-    pub fn put_input(&self, sbuf: &mut StreamBuf) {
-        let len = {
-            let buf = sbuf.as_slice();
-            unsafe { BIO_write(self.ext_bio, buf.as_ptr() as *const c_void, buf.len() as c_int) }
-        };
-        sbuf.consume(len as usize);
+    fn wrap<W>(self, ctx: &IoContext, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx, self)
     }
 
-    pub fn map_error_code(&self) {
-        unsafe { BIO_wpending(self.ext_bio) };
+    fn wrap_timeout<W>(self, ctx: &Cancel, _: &Timeout, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx.as_ctx(), self)
     }
 }
 
-impl Drop for Engine {
-    fn drop(&mut self) {
-        unsafe {
-            BIO_free_all(self.ext_bio);
-            SSL_free(self.ssl);
+impl<S, F> Complete<usize, io::Error> for SslIo<S, F>
+where
+    S: Stream<Error = io::Error>,
+    F: Complete<usize, Error>,
+{
+    fn success(mut self, this: &mut ThreadIoContext, len: usize) {
+        if let Want::InputAndRetry = self.want {
+            if len == 0 {
+                // The next layer is closed without the close_notify.
+                let res = match self.op {
+                    Op::Shutdown => Ok(0),
+                    _ => Err(io::Error::new(io::ErrorKind::UnexpectedEof, "unexpected eof").into()),
+                };
+                return self.complete(this, res);
+            }
+            let ssl = unsafe { &*self.ssl };
+            ssl.state.lock().unwrap().engine.commit_input(&self.input[..len]);
+        } else {
+            self.output.drain(..len);
+            if !self.output.is_empty() {
+                this.decrease_outstanding_work();
+                return self.next_layer();
+            }
+            if let Want::Output = self.want {
+                let res = self.res.take().unwrap();
+                return self.complete(this, res);
+            }
         }
-    }
-}
-
-struct SslHandler<S, O, F> {
-    imp: UnsafeRefCell<SslStreamImpl>,
-    next_layer: UnsafeRefCell<S>,
-    op: O,
-    handler: F,
-}
 
-// impl<S, O, F> Handler<usize, io::Error> for SslHandler<S, O, F>
-//     where S: Stream<io::Error>,
-//           O: FnMut(&Engine) -> (Want, Result<usize>) + Send + 'static,
-//           F: Handler<usize, io::Error>,
-// {
-//     type Output = F::Output;
-
-//     fn callback(self, io: &IoService, res: ::std::result::Result<usize, io::Error>) {
-//     //     let SslHandler { mut imp, next_layer, mut op, handler } = self;
-//     //     let mut imp = unsafe { imp.as_mut() };
-//     //     let imp_clone = UnsafeRefCell::new(imp);
-//     //     let next_layer = unsafe { next_layer.as_ref() };
-
-//     //     match res {
-//     //         Ok(start) if start == 1 => {
-//     //             loop {
-//     //                 match op(&imp.engine) {
-//     //                     (Want::InputAndRetry, _) => {
-//     //                         if imp.input_buf.len() != 0 {
-//     //                             imp.engine.put_input(&mut imp.input_buf);
-//     //                         } else {
-//     //                             match imp.input_buf.prepare(4096) {
-//     //                                 Ok(buf) => {
-//     //                                     let handler = SslHandler {
-//     //                                         imp: imp_clone,
-//     //                                         next_layer: UnsafeRefCell::new(next_layer),
-//     //                                         op: op,
-//     //                                         handler: handler,
-//     //                                     };
-//     //                                     next_layer.async_read_some(buf, handler);
-//     //                                 }
-//     //                                 Err(err) => handler.callback(io, Err(err.into())),
-//     //                             }
-//     //                             return;
-//     //                         }
-//     //                     },
-//     //                     (Want::OutputAndRetry, _) | (Want::Output, _) => {
-//     //                         let handler = SslHandler {
-//     //                             imp: imp_clone,
-//     //                             next_layer: UnsafeRefCell::new(next_layer),
-//     //                             op: op,
-//     //                             handler: handler,
-//     //                         };
-//     //                         let len = imp.output_buf.len();
-//     //                         async_write_until(next_layer, &mut imp.output_buf, len, handler);
-//     //                         return;
-//     //                     },
-//     //                     _ => {
-//     //                         if start > 0 {
-
-//     //                         }
-//     //                     },
-//     //                 }
-//     //             }
-//     //         },
-//     //         Ok(_) => {
-//     //         },
-//     //         Err(err) => return handler.callback(io, Err(err.into())),
-//     //     }
-//     }
-
-//     fn wrap<G>(self, callback: G) -> Callback
-//         where G: FnOnce(&IoService, ErrCode, Self) + Send + 'static,
-//     {
-//         let SslHandler { imp, next_layer, op, handler } = self;
-//         handler.wrap(move |io, ec, handler| {
-//             callback(io, ec, SslHandler {
-//                 imp: imp,
-//                 next_layer: next_layer,
-//                 op: op,
-//                 handler: handler,
-//             })
-//         })
-//     }
-
-//     type AsyncResult = F::AsyncResult;
-
-//     fn async_result(&self) -> Self::AsyncResult {
-//         self.handler.async_result()
-//     }
-// }
-
-struct SslStreamImpl {
-    engine: Engine,
-    input_buf: StreamBuf,
-    output_buf: StreamBuf,
-}
-
-impl SslStreamImpl {
-    fn new(ctx: &SslContext) -> Result<SslStreamImpl> {
-        Ok(SslStreamImpl {
-            engine: try!(Engine::new(ctx)),
-            input_buf: StreamBuf::new(),
-            output_buf: StreamBuf::new(),
-        })
-    }
-
-    fn io_mut<S, F>(&mut self, next_layer: &S, mut op: F) -> Result<usize>
-        where S: Stream<io::Error>,
-              F: FnMut(&Engine) -> (Want, Result<usize>),
-    {
-        // loop {
-        //     match op(&self.engine) {
-        //         (Want::InputAndRetry, _) => {
-        //             if self.input_buf.len() == 0 {
-        //                 let len = try!(next_layer.read_some(try!(self.input_buf.prepare(4096))));
-        //                 self.input_buf.commit(len);
-        //             }
-        //             self.engine.put_input(&mut self.input_buf);
-        //         },
-        //         (Want::OutputAndRetry, _) => {
-        //             self.engine.get_output(&mut self.output_buf);
-        //             let len = self.output_buf.len();
-        //             if len > 0 {
-        //                 try!(write_until(next_layer, &mut self.output_buf, len));
-        //             }
-        //         },
-        //         (Want::Output, res) => {
-        //             self.engine.get_output(&mut self.output_buf);
-        //             let len = self.output_buf.len();
-        //             try!(write_until(next_layer, &mut self.output_buf, len));
-        //             return res;
-        //         },
-        //         (_, res) => if let Ok(len) = res {
-        //             return Ok(len);
-        //         },
-        //     }
-        // }
-        return Ok(0)
+        match self.perform() {
+            Some(res) => self.complete(this, res),
+            None => {
+                this.decrease_outstanding_work();
+                self.next_layer()
+            }
+        }
     }
 
-    fn io<S, O>(&self, next_layer: &S, op: O) -> Result<usize>
-        where S: Stream<io::Error>,
-              O: FnMut(&Engine) -> (Want, Result<usize>),
-    {
-        unsafe { &mut *(self as *const _ as *mut Self) }.io_mut(next_layer, op)
+    fn failure(self, this: &mut ThreadIoContext, err: io::Error) {
+        self.complete(this, Err(err.into()))
     }
 }
 
-unsafe impl Send for SslStreamImpl {
-}
-
-unsafe impl Sync for SslStreamImpl {
-}
-
+/// Provides the SSL/TLS stream on the next layer stream, e.g. the `TcpSocket`.
+///
+/// The stream performs one operation at a time, the operations issued while the other one is
+/// running, e.g. the reading and the writing from the different threads, are queued and performed
+/// in order.
+///
+/// # Examples
+///
+/// ```
+/// use asyncio::IoContext;
+/// use asyncio::ip::{IpProtocol, Tcp, TcpSocket};
+/// use asyncio::ssl::{SslContext, SslStream};
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let ssl_ctx = SslContext::sslv23().unwrap();
+/// let soc = TcpSocket::new(ctx, Tcp::v4()).unwrap();
+/// let ssl = SslStream::new(soc, &ssl_ctx).unwrap();
+/// ```
 pub struct SslStream<S> {
     soc: S,
-    core: SslStreamImpl,
-    _ctx: SslContext,
+    state: Mutex<SslState>,
 }
 
-impl<S: Stream<io::Error>> SslStream<S> {
+impl<S> SslStream<S>
+where
+    S: Stream<Error = io::Error>,
+{
     pub fn new(soc: S, ctx: &SslContext) -> Result<SslStream<S>> {
-        let core = try!(SslStreamImpl::new(ctx));
         Ok(SslStream {
             soc: soc,
-            core: core,
-            _ctx: ctx.clone(),
+            state: Mutex::new(SslState {
+                engine: Engine::new(ctx)?,
+                busy: false,
+                queue: VecDeque::new(),
+            }),
         })
     }
 
-    pub fn async_handshake(&self) {
+    /// Asynchronously performs the handshake with the peer.
+    pub fn async_handshake<F>(&self, mode: Handshake, handler: F) -> F::Output
+    where
+        F: Handler<(), Error>,
+    {
+        handler.wrap(self.as_ctx(), move |ctx, handler| {
+            self.start(ctx, Op::Handshake(mode), NoLength(handler))
+        })
     }
 
-    pub fn async_shutdown(&self) {
+    /// Performs the handshake with the peer, that blocks until completed.
+    pub fn handshake(&mut self, mode: Handshake) -> Result<()>
+    where
+        S: io::Read + io::Write,
+    {
+        let op = Op::Handshake(mode);
+        let mut buf = [0; 4096];
+        loop {
+            let (want, res) = self.engine_mut().perform(&op);
+            let output = self.engine_mut().take_output();
+            self.soc.write_all(&output)?;
+            match want {
+                Want::InputAndRetry => {
+                    let len = self.soc.read(&mut buf)?;
+                    if len == 0 {
+                        let err = io::Error::new(io::ErrorKind::UnexpectedEof, "unexpected eof");
+                        return Err(err.into());
+                    }
+                    self.engine_mut().commit_input(&buf[..len]);
+                }
+                Want::OutputAndRetry => (),
+                Want::Nothing | Want::Output => return res.map(|_| ()),
+            }
+        }
     }
 
-    pub fn handshake(&self, mode: Handshake) -> Result<()> {
-        match self.core.io(&self.soc, move |eng| eng.handshake(mode)) {
-            Ok(_) => Ok(()),
-            Err(err) => Err(err.into()),
-        }
+    /// Asynchronously shuts down the SSL/TLS, that sends the close_notify and waits for it from
+    /// the peer.
+    ///
+    /// The next layer isn't shut down nor closed.
+    pub fn async_shutdown<F>(&self, handler: F) -> F::Output
+    where
+        F: Handler<(), Error>,
+    {
+        handler.wrap(self.as_ctx(), move |ctx, handler| {
+            self.start(ctx, Op::Shutdown, NoLength(handler))
+        })
     }
 
+    /// Returns the next layer stream.
     pub fn next_layer(&self) -> &S {
         &self.soc
     }
 
+    /// Sets the host name of the server name indication before the handshake.
+    pub fn set_host_name(&mut self, host: &str) -> Result<()> {
+        self.engine_mut().set_host_name(host)
+    }
+
+    /// Sets the callback to verify the certificates of the peer before the handshake.
+    ///
+    /// The callback is given whether the certificate is verified by the OpenSSL, and returns
+    /// whether the certificate is accepted.
     pub fn set_verify_callback<F>(&mut self, callback: F) -> Result<()>
-        where F: Fn(bool, &SslVerifyContext) -> bool + 'static
+    where
+        F: Fn(bool, &SslVerifyContext) -> bool + Send + Sync + 'static,
     {
-        self.core.engine.set_verify_callback(callback)
+        self.engine_mut().set_verify_callback(Arc::new(callback))
+    }
+
+    /// Sets the maximum depth of the certificate chain to verify before the handshake.
+    pub fn set_verify_depth(&mut self, depth: u32) -> Result<()> {
+        self.engine_mut().set_verify_depth(depth)
     }
 
-    pub fn set_verify_depth(&self, depth: i32) -> Result<()> {
-        self.core.engine.set_verify_depth(depth)
+    /// Sets the mode of the verification before the handshake.
+    pub fn set_verify_mode(&mut self, mode: SslVerifyMode) -> Result<()> {
+        self.engine_mut().set_verify_mode(mode)
     }
 
-    pub fn set_verify_mode(&self, mode: SslVerifyMode) -> Result<()> {
-        self.core.engine.set_verify_mode(mode)
+    fn engine_mut(&mut self) -> &mut Engine {
+        &mut self.state.get_mut().unwrap().engine
+    }
+
+    /// Starts the operation, or queues it until the running operation is completed.
+    fn start<F>(&self, ctx: &IoContext, op: Op, handler: F)
+    where
+        F: Complete<usize, Error>,
+    {
+        let io = SslIo {
+            ssl: self,
+            op: op,
+            want: Want::Nothing,
+            res: None,
+            input: Vec::new(),
+            output: Vec::new(),
+            handler: handler,
+        };
+        {
+            let mut state = self.state.lock().unwrap();
+            if state.busy {
+                return state.queue.push_back(Box::new(io));
+            }
+            state.busy = true;
+        }
+        io.start(ctx)
     }
 
-    pub fn shutdown(&mut self) -> Result<()> {
-        match self.core.io(&self.soc, |eng| eng.shutdown()) {
-            Ok(_) => Ok(()),
-            Err(err) => Err(err.into()),
+    /// Takes the next queued operation when the running operation is completed, the stream is no
+    /// longer busy if nothing is queued.
+    fn take_next(&self) -> Option<Box<QueuedIo>> {
+        let mut state = self.state.lock().unwrap();
+        let next = state.queue.pop_front();
+        if next.is_none() {
+            state.busy = false;
         }
+        next
     }
 }
 
-unsafe impl<S: Stream<io::Error>> AsIoContext for SslStream<S> {
+unsafe impl<S: Send> Send for SslStream<S> {}
+
+unsafe impl<S: Sync> Sync for SslStream<S> {}
+
+unsafe impl<S> AsIoContext for SslStream<S>
+where
+    S: Stream<Error = io::Error>,
+{
     fn as_ctx(&self) -> &IoContext {
         self.soc.as_ctx()
     }
 }
 
-// impl<S: Stream<io::Error>> Stream<Error> for SslStream<S> {
-//     fn async_read_some<F: Handler<usize, Error>>(&self, buf: &mut [u8], handler: F) -> F::Output {
-//         handler.async_result().get(self.io_service())
-//     }
+impl<S> Cancel for SslStream<S>
+where
+    S: Stream<Error = io::Error>,
+{
+    /// Cancels the running operation, and completes the queued operations with the
+    /// `OPERATION_CANCELED` error.
+    fn cancel(&self) {
+        self.soc.cancel();
+        let queue = mem::replace(&mut self.state.lock().unwrap().queue, VecDeque::new());
+        for io in queue {
+            io.cancel_box(self.as_ctx())
+        }
+    }
+}
 
-//     fn async_write_some<F: Handler<usize, Error>>(&self, buf: &[u8], handler: F) -> F::Output {
-//         handler.async_result().get(self.io_service())
-//     }
+impl<S> Stream for SslStream<S>
+where
+    S: Stream<Error = io::Error>,
+{
+    type Error = Error;
 
-//     fn read_some(&self, buf: &mut [u8]) -> Result<usize> {
-//         self.core.io(&self.soc, |eng| eng.read(buf))
-//     }
+    fn async_read_some<F>(&self, buf: &[u8], handler: F) -> F::Output
+    where
+        F: Handler<usize, Self::Error>,
+    {
+        let op = Op::Read(buf.as_ptr() as *mut u8, buf.len());
+        handler.wrap(self.as_ctx(), move |ctx, handler| self.start(ctx, op, handler))
+    }
 
-//     fn write_some(&self, buf: &[u8]) -> Result<usize> {
-//         self.core.io(&self.soc, |eng| eng.write(buf))
-//     }
-// }
+    fn async_write_some<F>(&self, buf: &[u8], handler: F) -> F::Output
+    where
+        F: Handler<usize, Self::Error>,
+    {
+        let op = Op::Write(buf.as_ptr(), buf.len());
+        handler.wrap(self.as_ctx(), move |ctx, handler| self.start(ctx, op, handler))
+    }
+
+    #[doc(hidden)]
+    fn wrap_timeout<F, G, W>(&self, handler: F, wrapper: W) -> F::Output
+    where
+        F: Handler<usize, Self::Error, WrappedHandler = G>,
+        G: Complete<usize, Self::Error>,
+        W: FnOnce(&IoContext, G),
+    {
+        handler.wrap(self.as_ctx(), wrapper)
+    }
+}
@@ -2,10 +2,10 @@ use ip::IpAddr;
 
 use std::slice;
 use std::str::FromStr;
-use std::ascii::AsciiExt;
 use openssl::nid::Nid;
-use openssl::x509::*;
+use openssl::x509::X509StoreContextRef;
 
+/// The context of the certificate verification, that is passed to the verify callbacks.
 pub type SslVerifyContext = X509StoreContextRef;
 
 fn match_pattern(mut patt: slice::Iter<u8>, mut host: slice::Iter<u8>) -> bool {
@@ -36,10 +36,23 @@ fn match_pattern(mut patt: slice::Iter<u8>, mut host: slice::Iter<u8>) -> bool {
     true
 }
 
+/// Verifies the certificate of the host by the rules of the RFC 2818.
+///
+/// # Examples
+///
+/// ```
+/// use asyncio::ssl::{SslContext, SslVerifyMode, Rfc2818Verification};
+///
+/// let mut ctx = SslContext::sslv23().unwrap();
+/// let verify = Rfc2818Verification("example.com".to_owned());
+/// ctx.set_verify_mode(SslVerifyMode::PEER);
+/// ctx.set_verify_callback(move |ok, ctx| verify.verification(ok, ctx));
+/// ```
 pub struct Rfc2818Verification(pub String);
 
 impl Rfc2818Verification {
-    pub fn verification(&self, preverified: bool, ctx: SslVerifyContext) -> bool {
+    /// Returns true if the certificate is valid for the host.
+    pub fn verification(&self, preverified: bool, ctx: &SslVerifyContext) -> bool {
         if !preverified {
             return false;
         }
@@ -50,9 +63,12 @@ impl Rfc2818Verification {
         }
 
         let addr = IpAddr::from_str(&self.0);
-        let cert = ctx.current_cert().unwrap();
+        let cert = match ctx.current_cert() {
+            Some(cert) => cert,
+            None => return false,
+        };
 
-        for gen in cert.subject_alt_names().unwrap() {
+        for gen in cert.subject_alt_names().into_iter().flat_map(|names| names) {
             if let &Ok(ref addr) = &addr {
                 if let Some(bytes) = gen.ipaddress() {
                     if addr.as_bytes() == bytes {
@@ -69,7 +85,7 @@ impl Rfc2818Verification {
         }
 
         let name = cert.subject_name();
-        for e in name.entries_by_nid(Nid::from_raw(-1)) {
+        for e in name.entries_by_nid(Nid::COMMONNAME) {
             let asn1str = e.data();
             if match_pattern(asn1str.as_slice().iter(), self.0.as_bytes().iter()) {
                 return true;
@@ -1,11 +1,28 @@
-use ffi::Timeout;
+use ffi::{Timeout, TIMED_OUT};
 use core::{AsIoContext, IoContext, ThreadIoContext, Cancel};
-use handler::{Handler};
+use handler::{Handler, Success};
 use strand::{Strand, StrandImmutable, StrandHandler};
+use strand::stack::{self, CoroutineStack, Running};
 use SteadyTimer;
 
 use context::{Context, Transfer};
-use context::stack::{ProtectedFixedSizeStack, Stack, StackError};
+use context::stack::{FixedSizeStack, ProtectedFixedSizeStack, Stack, StackError};
+
+use std::io;
+use std::cmp;
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::panic::Location;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+/// Resumes the coroutine, that is regarded as running on the current thread until suspended.
+unsafe fn resume(context: Context, run: Running, data: usize) -> Transfer {
+    let prev = stack::enter(run);
+    let t = context.resume(data);
+    stack::leave(prev);
+    t
+}
 
 trait CoroutineExec: Send + 'static {
     fn call_box(self: Box<Self>, coro: Coroutine);
@@ -23,6 +40,33 @@ where
 pub struct CoroutineData {
     context: Option<Context>,
     timer: SteadyTimer,
+    deadline: Option<Instant>,
+    expired: bool,
+    id: usize,
+    guard: usize,
+    site: &'static Location<'static>,
+    locals: HashMap<TypeId, Box<Any + Send>>,
+}
+
+impl CoroutineData {
+    fn running(&self) -> Running {
+        Running {
+            id: self.id,
+            guard: self.guard,
+            site: self.site,
+        }
+    }
+
+    fn remaining(&self) -> Option<Duration> {
+        self.deadline.map(|deadline| {
+            let now = Instant::now();
+            if deadline > now {
+                deadline - now
+            } else {
+                Duration::new(0, 0)
+            }
+        })
+    }
 }
 
 unsafe impl AsIoContext for CoroutineData {
@@ -36,9 +80,15 @@ struct CancelRef(*const Cancel, *const Timeout);
 
 impl CancelRef {
     fn timeout(self, coro: &Strand<CoroutineData>) {
-        coro.timer.expires_from_now(unsafe { &*self.1 }.get());
+        let timeout = unsafe { &*self.1 }.get();
+        coro.get().expired = false;
+        coro.timer.expires_from_now(match coro.remaining() {
+            Some(remaining) => cmp::min(timeout, remaining),
+            None => timeout,
+        });
         coro.timer.async_wait(
-            coro.wrap(move |_, res| if let Ok(_) = res {
+            coro.wrap(move |mut coro, res| if let Ok(_) = res {
+                coro.expired = true;
                 unsafe { &*self.0 }.cancel();
             }),
         )
@@ -56,7 +106,7 @@ pub struct CoroutineHandler<R, E>(StrandHandler<CoroutineData, Caller<R, E>, R,
 impl<R, E> Handler<R, E> for CoroutineHandler<R, E>
 where
     R: Send + 'static,
-    E: Send + 'static,
+    E: From<io::Error> + Send + 'static,
 {
     type Output = Result<R, E>;
 
@@ -98,12 +148,18 @@ where
         coro.context = Some(context);
         coro.timer.cancel();
         let res: &mut Option<Self::Output> = unsafe { &mut *(data as *mut Option<Self::Output>) };
-        res.take().unwrap()
+        let res = res.take().unwrap();
+        if coro.expired {
+            // Canceled by the timer.
+            return res.map_err(|_| io::Error::from(TIMED_OUT).into());
+        }
+        res
     }
 }
 
 struct InitData {
-    stack: ProtectedFixedSizeStack,
+    stack: CoroutineStack,
+    site: &'static Location<'static>,
     ctx: IoContext,
     exec: Box<CoroutineExec>,
 }
@@ -113,7 +169,14 @@ pub struct Coroutine<'a>(Strand<'a, CoroutineData>);
 
 impl<'a> Coroutine<'a> {
     extern "C" fn entry(t: Transfer) -> ! {
-        let InitData { stack, ctx, exec } = unsafe { &mut *(t.data as *mut Option<InitData>) }
+        static NEXT_ID: AtomicUsize = AtomicUsize::new(1);
+
+        let InitData {
+            stack,
+            site,
+            ctx,
+            exec,
+        } = unsafe { &mut *(t.data as *mut Option<InitData>) }
             .take()
             .unwrap();
         let mut coro: StrandImmutable<CoroutineData> = Strand::new(
@@ -121,6 +184,12 @@ impl<'a> Coroutine<'a> {
             CoroutineData {
                 context: Some(t.context),
                 timer: SteadyTimer::new(&ctx),
+                deadline: None,
+                expired: false,
+                id: NEXT_ID.fetch_add(1, Ordering::SeqCst),
+                guard: stack.guard(),
+                site: site,
+                locals: HashMap::new(),
             },
         );
         let this = {
@@ -140,7 +209,7 @@ impl<'a> Coroutine<'a> {
 
     extern "C" fn exit(mut t: Transfer) -> Transfer {
         {
-            let stack = unsafe { &mut *(t.data as *mut Option<ProtectedFixedSizeStack>) };
+            let stack = unsafe { &mut *(t.data as *mut Option<CoroutineStack>) };
             // Drop the stack
             let _ = stack.take().unwrap();
         }
@@ -148,6 +217,123 @@ impl<'a> Coroutine<'a> {
         t
     }
 
+    /// Returns the id of the coroutine running on the current thread, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use asyncio::{IoContext, Coroutine, spawn};
+    ///
+    /// fn log(msg: &str) {
+    ///   match Coroutine::current_id() {
+    ///     Some(id) => println!("[coroutine {}] {}", id, msg),
+    ///     None => println!("{}", msg),
+    ///   }
+    /// }
+    ///
+    /// let ctx = &IoContext::new().unwrap();
+    /// spawn(ctx, |coro| {
+    ///   assert_eq!(Coroutine::current_id(), Some(coro.id()));
+    ///   log("hello");
+    /// }).unwrap();
+    /// ctx.run();
+    /// assert_eq!(Coroutine::current_id(), None);
+    /// ```
+    pub fn current_id() -> Option<usize> {
+        stack::current().map(|run| run.id)
+    }
+
+    /// Returns the deadline of the coroutine, if any.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.0.deadline
+    }
+
+    /// Returns the id of the coroutine, that is unique in the process.
+    pub fn id(&self) -> usize {
+        self.0.id
+    }
+
+    /// Returns the value of the type attached to the coroutine.
+    ///
+    /// The values are keyed by the type, and dropped when the coroutine finishes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use asyncio::{IoContext, spawn};
+    ///
+    /// struct RequestId(u32);
+    ///
+    /// let ctx = &IoContext::new().unwrap();
+    /// spawn(ctx, |mut coro| {
+    ///   assert!(coro.local::<RequestId>().is_none());
+    ///   coro.set_local(RequestId(42));
+    ///   assert_eq!(coro.local::<RequestId>().unwrap().0, 42);
+    ///   assert_eq!(coro.take_local::<RequestId>().unwrap().0, 42);
+    /// }).unwrap();
+    /// ctx.run();
+    /// ```
+    pub fn local<T>(&self) -> Option<&T>
+    where
+        T: Any + Send,
+    {
+        self.0.locals.get(&TypeId::of::<T>()).and_then(
+            |val| val.downcast_ref(),
+        )
+    }
+
+    /// Returns the value of the type attached to the coroutine mutably.
+    pub fn local_mut<T>(&mut self) -> Option<&mut T>
+    where
+        T: Any + Send,
+    {
+        self.0.locals.get_mut(&TypeId::of::<T>()).and_then(
+            |val| val.downcast_mut(),
+        )
+    }
+
+    /// Returns the time left until the deadline, that is zero if the deadline has passed.
+    pub fn remaining(&self) -> Option<Duration> {
+        self.0.remaining()
+    }
+
+    /// Sets the deadline of the subsequent operations in the coroutine, or clears it by `None`.
+    ///
+    /// Each operation waiting with a timeout, e.g. the operations of the sockets, fails with the
+    /// `TimedOut` error at the earlier of its own timeout and the deadline, so a sequence of the
+    /// operations is bounded by the deadline as a whole.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io;
+    /// use std::time::{Duration, Instant};
+    /// use asyncio::{IoContext, AsIoContext, Stream, spawn};
+    /// use asyncio::ip::{IpProtocol, IpAddrV4, Tcp, TcpEndpoint, TcpListener, TcpSocket};
+    ///
+    /// let ctx = &IoContext::new().unwrap();
+    /// let acc = TcpListener::new(ctx, Tcp::v4()).unwrap();
+    /// acc.bind(&TcpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+    /// acc.listen().unwrap();
+    /// let ep = acc.local_endpoint().unwrap();
+    ///
+    /// spawn(ctx, move |coro| {
+    ///   coro.set_deadline(Some(Instant::now() + Duration::from_millis(100)));
+    ///   let soc = TcpSocket::new(coro.as_ctx(), Tcp::v4()).unwrap();
+    ///   soc.async_connect(&ep, coro.wrap()).unwrap();
+    ///
+    ///   // the peer never sends, so the read times out at the deadline.
+    ///   let mut buf = [0; 256];
+    ///   let err = soc.async_read_some(&mut buf, coro.wrap()).unwrap_err();
+    ///   assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+    ///   assert_eq!(coro.remaining(), Some(Duration::new(0, 0)));
+    /// }).unwrap();
+    /// ctx.run();
+    /// ```
+    pub fn set_deadline(&self, deadline: Option<Instant>) {
+        self.0.get().deadline = deadline;
+    }
+
     /// Provides a `Coroutine` handler to asynchronous operation.
     ///
     /// # Examples
@@ -172,6 +358,61 @@ impl<'a> Coroutine<'a> {
         let handler: StrandHandler<CoroutineData, Caller<R, E>, R, E> = self.0.wrap(caller::<R, E>);
         CoroutineHandler(handler)
     }
+
+    /// Attaches the value to the coroutine, returns the previous value of the type.
+    pub fn set_local<T>(&mut self, val: T) -> Option<T>
+    where
+        T: Any + Send,
+    {
+        self.0
+            .locals
+            .insert(TypeId::of::<T>(), Box::new(val))
+            .and_then(|val| val.downcast().ok())
+            .map(|val| *val)
+    }
+
+    /// Detaches the value of the type from the coroutine.
+    pub fn take_local<T>(&mut self) -> Option<T>
+    where
+        T: Any + Send,
+    {
+        self.0
+            .locals
+            .remove(&TypeId::of::<T>())
+            .and_then(|val| val.downcast().ok())
+            .map(|val| *val)
+    }
+
+    /// Suspends the coroutine and resumes it after the handlers already queued in the context.
+    ///
+    /// The long-running loop in the coroutine should yield at times, so that it doesn't monopolize
+    /// the thread running the context. See `IoContext::post_yield` for the handlers.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use asyncio::{IoContext, spawn};
+    ///
+    /// let ctx = &IoContext::new().unwrap();
+    /// let count = Arc::new(AtomicUsize::new(0));
+    /// for _ in 0..2 {
+    ///   let count = count.clone();
+    ///   spawn(ctx, move |coro| for _ in 0..100 {
+    ///     count.fetch_add(1, Ordering::SeqCst);
+    ///     coro.yield_now();
+    ///   }).unwrap();
+    /// }
+    /// ctx.run();
+    /// assert_eq!(count.load(Ordering::SeqCst), 200);
+    /// ```
+    pub fn yield_now(&self) {
+        let handler: CoroutineHandler<(), io::Error> = self.wrap();
+        let _ = handler.wrap(self.as_ctx(), |ctx, handler| {
+            ctx.do_post(Success::new((), handler))
+        });
+    }
 }
 
 fn caller<R, E>(mut coro: Strand<CoroutineData>, res: Result<R, E>)
@@ -180,8 +421,11 @@ where
     E: Send + 'static,
 {
     let mut data = Some(res);
+    let run = coro.running();
     let Transfer { context, data } = unsafe {
-        coro.context.take().unwrap().resume(
+        resume(
+            coro.context.take().unwrap(),
+            run,
             &mut data as *mut _ as usize,
         )
     };
@@ -199,31 +443,103 @@ unsafe impl<'a> AsIoContext for Coroutine<'a> {
     }
 }
 
+/// Builds the coroutine with the custom stack.
+///
+/// The stack is protected by the guard page in the default configuration, and the process is
+/// aborted with the report of the `set_overflow_handler` when the coroutine overflows it.
+///
+/// # Examples
+///
+/// ```
+/// use asyncio::{IoContext, CoroutineBuilder};
+///
+/// let ctx = &IoContext::new().unwrap();
+/// CoroutineBuilder::new()
+///     .stack_size(256 * 1024)
+///     .spawn(ctx, |coro| {
+///         println!("coroutine {}", coro.id());
+///     })
+///     .unwrap();
+/// ctx.run();
+/// ```
+pub struct CoroutineBuilder {
+    stack_size: usize,
+    guard_page: bool,
+}
+
+impl CoroutineBuilder {
+    pub fn new() -> Self {
+        CoroutineBuilder::default()
+    }
+
+    /// Enables the guard page below the stack, the default is `true`.
+    ///
+    /// The overflow of the stack without the guard page corrupts the memory silently.
+    pub fn guard_page(mut self, on: bool) -> Self {
+        self.guard_page = on;
+        self
+    }
+
+    /// Spawns the coroutine, that starts on the thread calling `run`.
+    #[track_caller]
+    pub fn spawn<F>(self, ctx: &IoContext, func: F) -> Result<(), StackError>
+    where
+        F: FnOnce(Coroutine) + Send + 'static,
+    {
+        let stack = if self.guard_page {
+            stack::install();
+            CoroutineStack::Protected(ProtectedFixedSizeStack::new(self.stack_size)?)
+        } else {
+            CoroutineStack::Unprotected(FixedSizeStack::new(self.stack_size)?)
+        };
+        let data = InitData {
+            stack: stack,
+            site: Location::caller(),
+            ctx: ctx.clone(),
+            exec: Box::new(func),
+        };
+        let context = unsafe { Context::new(&data.stack, Coroutine::entry) };
+        let data = Some(data);
+        let Transfer { context, data } = unsafe { context.resume(&data as *const _ as usize) };
+        let coro = unsafe { &mut *(data as *mut StrandImmutable<CoroutineData>) };
+        unsafe { coro.get() }.context = Some(context);
+        coro.post(move |mut coro| {
+            let data = coro.this as *mut _ as usize;
+            let run = coro.running();
+            let Transfer { context, data } =
+                unsafe { resume(coro.context.take().unwrap(), run, data) };
+            if data != 0 {
+                if let Some(ctx) = unsafe { &mut *(data as *mut Option<CancelRef>) }.take() {
+                    ctx.timeout(&coro);
+                }
+                coro.context = Some(context);
+            }
+        });
+        Ok(())
+    }
+
+    /// Sets the size of the stack, the default is `Stack::default_size()`.
+    pub fn stack_size(mut self, size: usize) -> Self {
+        self.stack_size = size;
+        self
+    }
+}
+
+impl Default for CoroutineBuilder {
+    fn default() -> Self {
+        CoroutineBuilder {
+            stack_size: Stack::default_size(),
+            guard_page: true,
+        }
+    }
+}
+
+#[track_caller]
 pub fn spawn<F>(ctx: &IoContext, func: F) -> Result<(), StackError>
 where
     F: FnOnce(Coroutine) + Send + 'static,
 {
-    let data = InitData {
-        stack: ProtectedFixedSizeStack::new(Stack::default_size())?,
-        ctx: ctx.clone(),
-        exec: Box::new(func),
-    };
-    let context = unsafe { Context::new(&data.stack, Coroutine::entry) };
-    let data = Some(data);
-    let Transfer { context, data } = unsafe { context.resume(&data as *const _ as usize) };
-    let coro = unsafe { &mut *(data as *mut StrandImmutable<CoroutineData>) };
-    unsafe { coro.get() }.context = Some(context);
-    coro.post(move |mut coro| {
-        let data = coro.this as *mut _ as usize;
-        let Transfer { context, data } = unsafe { coro.context.take().unwrap().resume(data) };
-        if data != 0 {
-            if let Some(ctx) = unsafe { &mut *(data as *mut Option<CancelRef>) }.take() {
-                ctx.timeout(&coro);
-            }
-            coro.context = Some(context);
-        }
-    });
-    Ok(())
+    CoroutineBuilder::new().spawn(ctx, func)
 }
 
 #[test]
@@ -232,3 +548,96 @@ fn test_spawn() {
     spawn(ctx, |coro| {});
     ctx.run();
 }
+
+#[test]
+fn test_deadline() {
+    let ctx = &IoContext::new().unwrap();
+    spawn(ctx, |coro| {
+        assert_eq!(coro.deadline(), None);
+        assert_eq!(coro.remaining(), None);
+
+        let deadline = Instant::now() + Duration::from_secs(60);
+        coro.set_deadline(Some(deadline));
+        assert_eq!(coro.deadline(), Some(deadline));
+        assert!(coro.remaining().unwrap() <= Duration::from_secs(60));
+
+        coro.set_deadline(Some(Instant::now()));
+        assert_eq!(coro.remaining(), Some(Duration::new(0, 0)));
+        coro.set_deadline(None);
+        assert_eq!(coro.remaining(), None);
+    }).unwrap();
+    ctx.run();
+}
+
+#[test]
+fn test_yield_now() {
+    use std::sync::{Arc, Mutex};
+
+    let ctx = &IoContext::new().unwrap();
+    let order = Arc::new(Mutex::new(Vec::new()));
+    for id in 0..2 {
+        let order = order.clone();
+        spawn(ctx, move |coro| for _ in 0..3 {
+            order.lock().unwrap().push(id);
+            coro.yield_now();
+        }).unwrap();
+    }
+    ctx.run();
+    assert_eq!(*order.lock().unwrap(), vec![0, 1, 0, 1, 0, 1]);
+}
+
+#[test]
+fn test_async_wait() {
+    let ctx = &IoContext::new().unwrap();
+    spawn(ctx, |coro| {
+        let timer = SteadyTimer::new(coro.as_ctx());
+        let now = Instant::now();
+        timer.expires_after(Duration::from_millis(10));
+        timer.async_wait(coro.wrap()).unwrap();
+        assert!(now.elapsed() >= Duration::from_millis(10));
+    }).unwrap();
+    ctx.run();
+}
+
+#[test]
+fn test_local() {
+    let ctx = &IoContext::new().unwrap();
+    for _ in 0..2 {
+        spawn(ctx, |mut coro| {
+            let id = coro.id();
+            assert_eq!(Coroutine::current_id(), Some(id));
+            assert_eq!(coro.set_local(id), None);
+            coro.yield_now();
+
+            // the other coroutine has run in between.
+            assert_eq!(Coroutine::current_id(), Some(id));
+            assert_eq!(coro.local::<usize>(), Some(&id));
+            *coro.local_mut::<usize>().unwrap() += 1;
+            assert_eq!(coro.set_local(0usize), Some(id + 1));
+            assert_eq!(coro.take_local::<usize>(), Some(0));
+            assert_eq!(coro.local::<usize>(), None);
+        }).unwrap();
+    }
+    ctx.run();
+    assert_eq!(Coroutine::current_id(), None);
+}
+
+#[test]
+fn test_builder() {
+    use std::sync::Arc;
+    use std::sync::atomic::AtomicBool;
+
+    let ctx = &IoContext::new().unwrap();
+    let done = Arc::new(AtomicBool::new(false));
+    let flag = done.clone();
+    CoroutineBuilder::new()
+        .stack_size(Stack::default_size() * 2)
+        .guard_page(false)
+        .spawn(ctx, move |coro| {
+            coro.yield_now();
+            flag.store(true, Ordering::SeqCst);
+        })
+        .unwrap();
+    ctx.run();
+    assert!(done.load(Ordering::SeqCst));
+}
@@ -312,7 +312,11 @@ impl<'a, T> Deref for StrandImmutable<'a, T> {
 #[cfg(feature = "context")]
 mod coroutine;
 #[cfg(feature = "context")]
-pub use self::coroutine::{spawn, Coroutine, CoroutineHandler};
+pub use self::coroutine::{spawn, Coroutine, CoroutineBuilder, CoroutineHandler};
+#[cfg(feature = "context")]
+mod stack;
+#[cfg(feature = "context")]
+pub use self::stack::{set_overflow_handler, StackOverflow};
 
 #[test]
 fn test_strand() {
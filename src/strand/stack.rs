@@ -0,0 +1,241 @@
+use context::stack::{FixedSizeStack, ProtectedFixedSizeStack, Stack};
+#[cfg(unix)]
+use libc;
+
+use std::cell::Cell;
+use std::ops::Deref;
+use std::panic::Location;
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(unix)]
+use std::{cmp, mem, ptr};
+#[cfg(unix)]
+use std::io::Write;
+#[cfg(unix)]
+use std::sync::Once;
+
+/// The stack of the coroutine, that is protected by the guard page unless disabled.
+pub enum CoroutineStack {
+    Protected(ProtectedFixedSizeStack),
+    Unprotected(FixedSizeStack),
+}
+
+impl CoroutineStack {
+    /// Returns the address of the guard page, or zero if the overflow is not detected.
+    pub fn guard(&self) -> usize {
+        match *self {
+            #[cfg(unix)]
+            CoroutineStack::Protected(ref stack) => stack.bottom() as usize - page_size(),
+            _ => 0,
+        }
+    }
+}
+
+impl Deref for CoroutineStack {
+    type Target = Stack;
+
+    fn deref(&self) -> &Stack {
+        match *self {
+            CoroutineStack::Protected(ref stack) => stack,
+            CoroutineStack::Unprotected(ref stack) => stack,
+        }
+    }
+}
+
+/// The coroutine running on the thread.
+#[derive(Clone, Copy)]
+pub struct Running {
+    pub id: usize,
+    pub guard: usize,
+    pub site: &'static Location<'static>,
+}
+
+thread_local!(static RUNNING: Cell<Option<Running>> = Cell::new(None));
+
+/// Regards the coroutine as running on the current thread, returns the previous one.
+pub fn enter(run: Running) -> Option<Running> {
+    #[cfg(unix)]
+    {
+        if run.guard != 0 {
+            ensure_altstack();
+        }
+    }
+    RUNNING.with(|cur| cur.replace(Some(run)))
+}
+
+pub fn leave(prev: Option<Running>) {
+    RUNNING.with(|cur| cur.set(prev))
+}
+
+pub fn current() -> Option<Running> {
+    RUNNING.with(|cur| cur.get())
+}
+
+/// The coroutine which has overflowed its stack.
+#[derive(Clone, Copy, Debug)]
+pub struct StackOverflow {
+    /// The id of the coroutine.
+    pub id: usize,
+    /// The source file where the coroutine was spawned.
+    pub file: &'static str,
+    /// The line where the coroutine was spawned.
+    pub line: u32,
+}
+
+static HANDLER: AtomicUsize = AtomicUsize::new(0);
+
+/// Sets the handler called when the coroutine overflows its guard page, and then the process is
+/// aborted.
+///
+/// The handler runs in the signal handler on the alternate signal stack, so it should only do
+/// the async-signal-safe works, e.g. writing the message to the file descriptor. The default
+/// handler reports the coroutine id and the spawn site to the standard error.
+///
+/// The overflow is detected only on Unix platforms.
+///
+/// # Examples
+///
+/// ```
+/// use asyncio::{set_overflow_handler, StackOverflow};
+///
+/// fn on_overflow(ovf: &StackOverflow) {
+///   let _ = ovf.id;
+/// }
+///
+/// set_overflow_handler(on_overflow);
+/// ```
+pub fn set_overflow_handler(handler: fn(&StackOverflow)) {
+    HANDLER.store(handler as usize, Ordering::SeqCst);
+}
+
+#[cfg(unix)]
+fn default_handler(ovf: &StackOverflow) {
+    let mut buf = [0; 512];
+    let len = {
+        let mut cur = &mut buf[..];
+        let _ = write!(
+            cur,
+            "coroutine {} spawned at {}:{} has overflowed its stack\n",
+            ovf.id,
+            ovf.file,
+            ovf.line
+        );
+        512 - cur.len()
+    };
+    unsafe { libc::write(2, buf.as_ptr() as *const _, len) };
+}
+
+#[cfg(unix)]
+static PAGE_SIZE: AtomicUsize = AtomicUsize::new(0);
+
+#[cfg(unix)]
+fn page_size() -> usize {
+    match PAGE_SIZE.load(Ordering::Relaxed) {
+        0 => {
+            let size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+            PAGE_SIZE.store(size, Ordering::Relaxed);
+            size
+        }
+        size => size,
+    }
+}
+
+#[cfg(unix)]
+static mut OLD_SIGSEGV: Option<libc::sigaction> = None;
+
+#[cfg(unix)]
+static mut OLD_SIGBUS: Option<libc::sigaction> = None;
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+unsafe fn fault_addr(info: *mut libc::siginfo_t) -> usize {
+    (*info).si_addr() as usize
+}
+
+#[cfg(all(unix, not(any(target_os = "linux", target_os = "android"))))]
+unsafe fn fault_addr(info: *mut libc::siginfo_t) -> usize {
+    (*info).si_addr as usize
+}
+
+#[cfg(unix)]
+extern "C" fn on_fault(signum: libc::c_int, info: *mut libc::siginfo_t, _: *mut libc::c_void) {
+    let addr = unsafe { fault_addr(info) };
+    if let Some(run) = current() {
+        if run.guard != 0 && addr >= run.guard && addr < run.guard + page_size() {
+            let ovf = StackOverflow {
+                id: run.id,
+                file: run.site.file(),
+                line: run.site.line(),
+            };
+            match HANDLER.load(Ordering::SeqCst) {
+                0 => default_handler(&ovf),
+                handler => {
+                    let handler: fn(&StackOverflow) = unsafe { mem::transmute(handler) };
+                    handler(&ovf)
+                }
+            }
+            unsafe { libc::abort() };
+        }
+    }
+
+    // Not a overflow of the coroutine, restores the previous action and faults again.
+    let old = unsafe {
+        match signum {
+            libc::SIGSEGV => OLD_SIGSEGV,
+            _ => OLD_SIGBUS,
+        }
+    };
+    unsafe {
+        match old {
+            Some(ref old) => libc::sigaction(signum, old, ptr::null_mut()),
+            None => libc::signal(signum, libc::SIG_DFL) as libc::c_int,
+        };
+    }
+}
+
+/// Installs the signal handler which detects the overflow of the coroutines.
+#[cfg(unix)]
+pub fn install() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| unsafe {
+        page_size();
+        let mut act: libc::sigaction = mem::zeroed();
+        act.sa_sigaction = on_fault as *const () as usize;
+        act.sa_flags = libc::SA_SIGINFO | libc::SA_ONSTACK;
+        libc::sigemptyset(&mut act.sa_mask);
+        let mut old: libc::sigaction = mem::zeroed();
+        if libc::sigaction(libc::SIGSEGV, &act, &mut old) == 0 {
+            OLD_SIGSEGV = Some(old);
+        }
+        if libc::sigaction(libc::SIGBUS, &act, &mut old) == 0 {
+            OLD_SIGBUS = Some(old);
+        }
+    })
+}
+
+#[cfg(not(unix))]
+pub fn install() {}
+
+/// Sets the alternate signal stack to the thread, that the handler runs on when the stack is
+/// exhausted.
+#[cfg(unix)]
+fn ensure_altstack() {
+    thread_local!(static ALTSTACK: Cell<bool> = Cell::new(false));
+
+    if ALTSTACK.with(|done| done.replace(true)) {
+        return;
+    }
+    unsafe {
+        let mut old: libc::stack_t = mem::zeroed();
+        libc::sigaltstack(ptr::null(), &mut old);
+        if old.ss_flags & libc::SS_DISABLE == 0 {
+            // The threads spawned by the std already have it.
+            return;
+        }
+        // The stack is leaked with the thread, that is used until the thread exits.
+        let size = cmp::max(libc::SIGSTKSZ, 64 * 1024);
+        let buf: &'static mut [u8] = Box::leak(vec![0; size].into_boxed_slice());
+        let mut st: libc::stack_t = mem::zeroed();
+        st.ss_sp = buf.as_mut_ptr() as *mut _;
+        st.ss_size = size;
+        libc::sigaltstack(&st, ptr::null_mut());
+    }
+}
@@ -1,9 +1,14 @@
-use ffi::Timeout;
+use ffi::{Timeout, TIMED_OUT};
 use core::{IoContext, AsIoContext, ThreadIoContext, Cancel};
 use streambuf::{StreamBuf, MatchCond};
-use handler::{Handler, Complete, Failure};
+use handler::{Handler, Complete, Failure, BoxHandler};
+use SteadyTimer;
 
 use std::io;
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
 
 struct AsyncReadToEnd<F, S> {
     soc: *const S,
@@ -170,10 +175,12 @@ where
         let soc = unsafe { &*self.soc };
         let sbuf = unsafe { &mut *self.sbuf };
         sbuf.consume(len);
+        self.len += len;
         self.left -= len;
         if self.left == 0 {
             self.handler.success(this, self.len)
         } else {
+            this.decrease_outstanding_work();
             let buf = &sbuf.as_bytes()[..self.left];
             soc.async_write_some(buf, self)
         }
@@ -184,6 +191,134 @@ where
     }
 }
 
+/// The state shared by the operation and the timer, that outlives the both operations.
+struct TimeoutState {
+    timer: SteadyTimer,
+    done: Mutex<bool>,
+    expired: AtomicBool,
+}
+
+unsafe impl Send for TimeoutState {}
+
+unsafe impl Sync for TimeoutState {}
+
+impl TimeoutState {
+    fn start<S: Stream>(soc: &S, timeout: Duration) -> Arc<Self> {
+        let state = Arc::new(TimeoutState {
+            timer: SteadyTimer::new(soc.as_ctx()),
+            done: Mutex::new(false),
+            expired: AtomicBool::new(false),
+        });
+        state.timer.expires_from_now(timeout);
+        state.timer.async_wait(AsyncExpire {
+            soc: soc,
+            state: state.clone(),
+        });
+        state
+    }
+}
+
+struct AsyncWithTimeout<F> {
+    state: Arc<TimeoutState>,
+    handler: F,
+}
+
+impl<F, E> Handler<usize, E> for AsyncWithTimeout<F>
+where
+    F: Complete<usize, E>,
+    E: From<io::Error> + Send + 'static,
+{
+    type Output = ();
+
+    type WrappedHandler = Self;
+
+    fn wrap<W>(self, ctx: &IoContext, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx, self)
+    }
+
+    fn wrap_timeout<W>(self, ctx: &Cancel, _: &Timeout, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx.as_ctx(), self)
+    }
+}
+
+impl<F, E> Complete<usize, E> for AsyncWithTimeout<F>
+where
+    F: Complete<usize, E>,
+    E: From<io::Error> + Send + 'static,
+{
+    fn success(self, this: &mut ThreadIoContext, len: usize) {
+        *self.state.done.lock().unwrap() = true;
+        self.state.timer.cancel();
+        self.handler.success(this, len)
+    }
+
+    fn failure(self, this: &mut ThreadIoContext, err: E) {
+        if self.state.expired.load(Ordering::SeqCst) {
+            // Canceled by the timer.
+            return self.handler.failure(this, io::Error::from(TIMED_OUT).into());
+        }
+        *self.state.done.lock().unwrap() = true;
+        self.state.timer.cancel();
+        self.handler.failure(this, err)
+    }
+}
+
+struct AsyncExpire<S> {
+    soc: *const S,
+    state: Arc<TimeoutState>,
+}
+
+unsafe impl<S> Send for AsyncExpire<S> {}
+
+impl<S> Handler<(), io::Error> for AsyncExpire<S>
+where
+    S: Stream,
+{
+    type Output = ();
+
+    type WrappedHandler = Self;
+
+    fn wrap<W>(self, ctx: &IoContext, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx, self)
+    }
+
+    fn wrap_timeout<W>(self, ctx: &Cancel, _: &Timeout, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx.as_ctx(), self)
+    }
+}
+
+impl<S> Complete<(), io::Error> for AsyncExpire<S>
+where
+    S: Stream,
+{
+    fn success(self, this: &mut ThreadIoContext, _: ()) {
+        let done = self.state.done.lock().unwrap();
+        if !*done {
+            // The stream is alive until the operation is completed.
+            self.state.expired.store(true, Ordering::SeqCst);
+            unsafe { &*self.soc }.cancel();
+        }
+        drop(done);
+        this.decrease_outstanding_work();
+    }
+
+    fn failure(self, this: &mut ThreadIoContext, _: io::Error) {
+        this.decrease_outstanding_work();
+    }
+}
+
 pub trait Stream: AsIoContext + Cancel + Sized + Send + 'static {
     type Error: From<io::Error> + Send;
 
@@ -195,6 +330,26 @@ pub trait Stream: AsIoContext + Cancel + Sized + Send + 'static {
     where
         F: Handler<usize, Self::Error>;
 
+    /// Asynchronously reads some data, that fails with the `TIMED_OUT` error unless completed
+    /// within the timeout.
+    ///
+    /// The expiry cancels the all outstanding operations of the stream.
+    fn async_read_some_with_timeout<F>(&self, buf: &[u8], timeout: Duration, handler: F) -> F::Output
+    where
+        F: Handler<usize, Self::Error>,
+    {
+        self.wrap_timeout(handler, move |_, handler| {
+            let state = TimeoutState::start(self, timeout);
+            self.async_read_some(
+                buf,
+                AsyncWithTimeout {
+                    state: state,
+                    handler: handler,
+                },
+            )
+        })
+    }
+
     fn async_read_to_end<F>(&self, sbuf: &mut StreamBuf, handler: F) -> F::Output
     where
         F: Handler<usize, Self::Error>,
@@ -265,6 +420,26 @@ pub trait Stream: AsIoContext + Cancel + Sized + Send + 'static {
         })
     }
 
+    /// Asynchronously writes some data, that fails with the `TIMED_OUT` error unless completed
+    /// within the timeout.
+    ///
+    /// The expiry cancels the all outstanding operations of the stream.
+    fn async_write_some_with_timeout<F>(&self, buf: &[u8], timeout: Duration, handler: F) -> F::Output
+    where
+        F: Handler<usize, Self::Error>,
+    {
+        self.wrap_timeout(handler, move |_, handler| {
+            let state = TimeoutState::start(self, timeout);
+            self.async_write_some(
+                buf,
+                AsyncWithTimeout {
+                    state: state,
+                    handler: handler,
+                },
+            )
+        })
+    }
+
     fn async_write_until<M, F>(&self, sbuf: &mut StreamBuf, mut cond: M, handler: F) -> F::Output
     where
         M: MatchCond,
@@ -294,3 +469,186 @@ pub trait Stream: AsIoContext + Cancel + Sized + Send + 'static {
         G: Complete<usize, Self::Error>,
         W: FnOnce(&IoContext, G);
 }
+
+/// The handler completing the boxed handler of the `DynStream`, that converts the error of the
+/// stream into the `io::Error`.
+struct IntoIoError<E> {
+    handler: BoxHandler<usize, io::Error>,
+    _marker: PhantomData<E>,
+}
+
+impl<E> Handler<usize, E> for IntoIoError<E>
+where
+    E: Into<io::Error> + Send + 'static,
+{
+    type Output = ();
+
+    type WrappedHandler = Self;
+
+    fn wrap<W>(self, ctx: &IoContext, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx, self)
+    }
+
+    fn wrap_timeout<W>(self, ctx: &Cancel, _: &Timeout, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx.as_ctx(), self)
+    }
+}
+
+impl<E> Complete<usize, E> for IntoIoError<E>
+where
+    E: Into<io::Error> + Send + 'static,
+{
+    fn success(self, this: &mut ThreadIoContext, len: usize) {
+        self.handler.success(this, len)
+    }
+
+    fn failure(self, this: &mut ThreadIoContext, err: E) {
+        self.handler.failure(this, err.into())
+    }
+}
+
+/// An object-safe stream, that is implemented for all the `Sync` streams of which the error is
+/// converted into the `io::Error`.
+///
+/// The `Box<DynStream>` implements the `Stream`, so the different transports (e.g. `TcpSocket`,
+/// `LocalStreamSocket` and `SslStream`) can be held by one type and used with the composed
+/// operations.
+///
+/// # Examples
+///
+/// ```
+/// use std::io;
+/// use std::sync::Arc;
+/// use asyncio::{IoContext, DynStream, Stream, wrap};
+/// use asyncio::ip::{IpProtocol, Tcp, TcpSocket};
+/// use asyncio::local::{LocalStream, LocalStreamSocket};
+///
+/// fn on_read(_: Arc<Box<DynStream>>, res: io::Result<usize>) {
+/// }
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let streams: Vec<Arc<Box<DynStream>>> = vec![
+///     Arc::new(Box::new(TcpSocket::new(ctx, Tcp::v4()).unwrap())),
+///     Arc::new(Box::new(LocalStreamSocket::new(ctx, LocalStream).unwrap())),
+/// ];
+/// for soc in &streams {
+///     soc.async_read_some(&[0; 16], wrap(soc, on_read));
+/// }
+/// ```
+pub trait DynStream: Cancel + Send + Sync {
+    /// Asynchronously reads some data with the boxed handler.
+    fn async_read_some_boxed(&self, buf: &[u8], handler: BoxHandler<usize, io::Error>);
+
+    /// Asynchronously writes some data with the boxed handler.
+    fn async_write_some_boxed(&self, buf: &[u8], handler: BoxHandler<usize, io::Error>);
+}
+
+impl<S> DynStream for S
+where
+    S: Stream + Sync,
+    S::Error: Into<io::Error>,
+{
+    fn async_read_some_boxed(&self, buf: &[u8], handler: BoxHandler<usize, io::Error>) {
+        self.async_read_some(
+            buf,
+            IntoIoError {
+                handler: handler,
+                _marker: PhantomData,
+            },
+        )
+    }
+
+    fn async_write_some_boxed(&self, buf: &[u8], handler: BoxHandler<usize, io::Error>) {
+        self.async_write_some(
+            buf,
+            IntoIoError {
+                handler: handler,
+                _marker: PhantomData,
+            },
+        )
+    }
+}
+
+unsafe impl AsIoContext for Box<DynStream> {
+    fn as_ctx(&self) -> &IoContext {
+        (**self).as_ctx()
+    }
+}
+
+impl Cancel for Box<DynStream> {
+    fn cancel(&self) {
+        (**self).cancel()
+    }
+}
+
+impl Stream for Box<DynStream> {
+    type Error = io::Error;
+
+    fn async_read_some<F>(&self, buf: &[u8], handler: F) -> F::Output
+    where
+        F: Handler<usize, Self::Error>,
+    {
+        handler.wrap(self.as_ctx(), move |_, handler| {
+            (**self).async_read_some_boxed(buf, BoxHandler::from_wrapped(handler))
+        })
+    }
+
+    fn async_write_some<F>(&self, buf: &[u8], handler: F) -> F::Output
+    where
+        F: Handler<usize, Self::Error>,
+    {
+        handler.wrap(self.as_ctx(), move |_, handler| {
+            (**self).async_write_some_boxed(buf, BoxHandler::from_wrapped(handler))
+        })
+    }
+
+    #[doc(hidden)]
+    fn wrap_timeout<F, G, W>(&self, handler: F, wrapper: W) -> F::Output
+    where
+        F: Handler<usize, Self::Error, WrappedHandler = G>,
+        G: Complete<usize, Self::Error>,
+        W: FnOnce(&IoContext, G),
+    {
+        handler.wrap(self.as_ctx(), wrapper)
+    }
+}
+
+#[test]
+fn test_dyn_stream() {
+    use std::sync::atomic::AtomicUsize;
+    use handler::wrap;
+    use ip::{IpAddrV4, IpProtocol, Tcp, TcpEndpoint, TcpListener, TcpSocket};
+    use local::{LocalStream, connect_pair};
+
+    static READ: AtomicUsize = AtomicUsize::new(0);
+
+    fn on_read(_: Arc<Box<DynStream>>, res: io::Result<usize>) {
+        READ.fetch_add(res.unwrap(), Ordering::SeqCst);
+    }
+
+    let ctx = &IoContext::new().unwrap();
+    let acc = TcpListener::new(ctx, Tcp::v4()).unwrap();
+    acc.bind(&TcpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+    acc.listen().unwrap();
+    let tcp = TcpSocket::new(ctx, Tcp::v4()).unwrap();
+    tcp.connect(&acc.local_endpoint().unwrap()).unwrap();
+    let (tcp_peer, _) = acc.accept().unwrap();
+    let (local, local_peer) = connect_pair(ctx, LocalStream).unwrap();
+
+    let streams: Vec<Arc<Box<DynStream>>> =
+        vec![Arc::new(Box::new(tcp_peer)), Arc::new(Box::new(local_peer))];
+    let bufs = [[0; 16]; 2];
+    for (soc, buf) in streams.iter().zip(&bufs) {
+        soc.async_read_some(buf, wrap(soc, on_read));
+    }
+    tcp.write_some(b"hello").unwrap();
+    local.write_some(b"world!").unwrap();
+    ctx.run();
+    assert_eq!(READ.load(Ordering::SeqCst), 11);
+}
@@ -1,18 +1,27 @@
-use ffi::{AsRawFd, RawFd, SystemError, socket, shutdown, bind, ioctl, getsockopt,
-          setsockopt, getpeername, getsockname};
-use reactor::SocketImpl;
+use ffi::{AsRawFd, IntoRawFd, RawFd, SystemError, socket, shutdown, bind, ioctl, getsockopt,
+          raw_getsockopt, raw_setsockopt, setsockopt, getpeername, getsockname, wait, wait_for};
+use reactor::{SocketImpl, OpStats};
+use extensions::Extensions;
 use core::{Protocol, Socket, IoControl, GetSocketOption, SetSocketOption, AsIoContext, IoContext,
            Perform, ThreadIoContext, Cancel};
 use handler::{Handler, AsyncReadOp, AsyncWriteOp, Complete};
+use close_ops::{async_close, async_drain_and_close};
 use connect_ops::{async_connect, blocking_connect};
-use read_ops::{Read, Recv, async_read_op, blocking_read_op, nonblocking_read_op};
-use write_ops::{Sent, Write, async_write_op, blocking_write_op, nonblocking_write_op};
+use read_ops::{Read, ReadV, Recv, RecvFds, async_read_op, blocking_read_op, nonblocking_read_op};
+use write_ops::{Sent, SendFds, SendFile, Write, WriteV, async_write_op, blocking_write_op, nonblocking_write_op};
 use stream::Stream;
-use socket_base::{BytesReadable, Shutdown};
+use buffer_seq::{BufferSequence, MutableBufferSequence};
+use socket_base::{Wait, BytesReadable, Shutdown};
+use streambuf::StreamBuf;
+use local::LocalStream;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use read_ops::SpliceToPipe;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use write_ops::SpliceFromPipe;
 
 use std::io;
 use std::fmt;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 pub struct StreamSocket<P> {
     pimpl: Box<SocketImpl<P>>,
@@ -27,6 +36,52 @@ where
         Ok(unsafe { Self::from_raw_fd(ctx, soc, pro) })
     }
 
+    /// Asynchronously closes the socket after the peer finishes the connection.
+    ///
+    /// The sending side is shut down, and the data received until the FIN of the peer is
+    /// discarded. The waiting is bounded by the `timeout`, or the timeout of the `Linger` option
+    /// if `None`, that is completed with the `TimedOut` error. The socket is closed without
+    /// blocking in any case. If neither of them is set, or the linger timeout is zero, the socket
+    /// is closed immediately.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io;
+    /// use std::sync::Arc;
+    /// use std::time::Duration;
+    /// use asyncio::{IoContext, Stream, wrap};
+    /// use asyncio::ip::{IpProtocol, IpAddrV4, Tcp, TcpEndpoint, TcpListener, TcpSocket};
+    ///
+    /// fn on_close(_: Arc<TcpListener>, res: io::Result<()>) {
+    ///     res.unwrap();
+    /// }
+    ///
+    /// let ctx = &IoContext::new().unwrap();
+    /// let acc = Arc::new(TcpListener::new(ctx, Tcp::v4()).unwrap());
+    /// acc.bind(&TcpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+    /// acc.listen().unwrap();
+    /// let cl = TcpSocket::new(ctx, Tcp::v4()).unwrap();
+    /// cl.connect(&acc.local_endpoint().unwrap()).unwrap();
+    /// let (sv, _) = acc.accept().unwrap();
+    ///
+    /// sv.write_some(b"bye").unwrap();
+    /// sv.async_close(Some(Duration::from_secs(1)), wrap(&acc, on_close));
+    ///
+    /// // the peer closes after reading all the data.
+    /// let mut buf = [0; 16];
+    /// assert_eq!(cl.read_some(&mut buf).unwrap(), 3);
+    /// drop(cl);
+    /// ctx.run();
+    /// ```
+    pub fn async_close<F>(self, timeout: Option<Duration>, handler: F) -> F::Output
+    where
+        P: 'static,
+        F: Handler<(), io::Error>,
+    {
+        async_close(self, timeout, handler)
+    }
+
     pub fn async_connect<F>(&self, ep: &P::Endpoint, handler: F) -> F::Output
     where
         F: Handler<(), io::Error>,
@@ -34,6 +89,72 @@ where
         async_connect(self, ep, &self.pimpl.timeout, handler)
     }
 
+    /// Asynchronously flushes the data, and closes the socket after the peer finishes the
+    /// connection, that is the teardown of the proxied connection.
+    ///
+    /// Nothing is read by the application any more. All the data of the `sbuf` is written by the
+    /// `async_write_all`, then the sending side is shut down, and the data received until the FIN
+    /// of the peer is discarded. The handler is invoked with the flushed bytes, or with the
+    /// `TimedOut` error if not completed by the `deadline`. The socket is closed without blocking
+    /// in any case.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io;
+    /// use std::thread;
+    /// use std::sync::Arc;
+    /// use std::time::{Duration, Instant};
+    /// use asyncio::{IoContext, Stream, StreamBuf, wrap};
+    /// use asyncio::ip::{IpProtocol, IpAddrV4, Tcp, TcpEndpoint, TcpListener, TcpSocket};
+    ///
+    /// fn on_drain(_: Arc<TcpListener>, res: io::Result<usize>) {
+    ///     assert_eq!(res.unwrap(), 3);
+    /// }
+    ///
+    /// let ctx = &IoContext::new().unwrap();
+    /// let acc = Arc::new(TcpListener::new(ctx, Tcp::v4()).unwrap());
+    /// acc.bind(&TcpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+    /// acc.listen().unwrap();
+    /// let cl = TcpSocket::new(ctx, Tcp::v4()).unwrap();
+    /// cl.connect(&acc.local_endpoint().unwrap()).unwrap();
+    /// let (sv, _) = acc.accept().unwrap();
+    ///
+    /// let deadline = Instant::now() + Duration::from_secs(1);
+    /// sv.async_drain_and_close(StreamBuf::from("bye"), deadline, wrap(&acc, on_drain));
+    ///
+    /// // the peer closes after reading all the data.
+    /// let th = thread::spawn(move || {
+    ///     let mut buf = [0; 16];
+    ///     assert_eq!(cl.read_some(&mut buf).unwrap(), 3);
+    /// });
+    /// ctx.run();
+    /// th.join().unwrap();
+    /// ```
+    pub fn async_drain_and_close<F>(
+        self,
+        sbuf: StreamBuf,
+        deadline: Instant,
+        handler: F,
+    ) -> F::Output
+    where
+        P: 'static,
+        F: Handler<usize, io::Error>,
+    {
+        async_drain_and_close(self, sbuf, deadline, handler)
+    }
+
+    /// Asynchronously reads into the sequence of the buffers by the `readv`, that fills them in
+    /// order without copying.
+    pub fn async_read_some_v<B, F>(&self, bufs: &mut B, handler: F) -> F::Output
+    where
+        B: MutableBufferSequence + ?Sized,
+        F: Handler<usize, io::Error>,
+    {
+        let reader = ReadV::new(bufs.buffers_mut());
+        async_read_op(self, &[], &self.pimpl.timeout, handler, reader)
+    }
+
     pub fn async_receive<F>(&self, buf: &mut [u8], flags: i32, handler: F) -> F::Output
     where
         F: Handler<usize, io::Error>,
@@ -48,6 +169,41 @@ where
         async_write_op(self, buf, &self.pimpl.timeout, handler, Sent::new(flags))
     }
 
+    /// Asynchronously sends up to `len` bytes of the file from the offset, by the `sendfile`
+    /// without copying to the userspace on Linux, or reading the file otherwise.
+    ///
+    /// The offset of the file is not changed. The handler is invoked with zero at the end of the
+    /// file.
+    pub fn async_send_file_some<T, F>(&self, file: &T, offset: u64, len: usize, handler: F) -> F::Output
+    where
+        T: AsRawFd,
+        F: Handler<usize, io::Error>,
+    {
+        async_write_op(
+            self,
+            &[],
+            &self.pimpl.timeout,
+            handler,
+            SendFile::new(file.as_raw_fd(), offset, len),
+        )
+    }
+
+    /// Asynchronously writes the sequence of the buffers by the `writev`, that gathers them in
+    /// order without copying.
+    pub fn async_write_some_v<B, F>(&self, bufs: &B, handler: F) -> F::Output
+    where
+        B: BufferSequence + ?Sized,
+        F: Handler<usize, io::Error>,
+    {
+        let writer = WriteV::new(bufs.buffers());
+        async_write_op(self, &[], &self.pimpl.timeout, handler, writer)
+    }
+
+    /// Adopts the native socket of the protocol in place of the current socket which is closed.
+    pub fn assign(&mut self, soc: RawFd, pro: P) -> io::Result<()> {
+        Ok(self.pimpl.assign(soc, pro)?)
+    }
+
     pub fn available(&self) -> io::Result<usize> {
         let mut bytes = BytesReadable::default();
         ioctl(self, &mut bytes)?;
@@ -58,10 +214,20 @@ where
         Ok(bind(self, ep)?)
     }
 
+    /// Cancels the pending operations and closes the socket, returns the error of the `close`.
+    pub fn close(&mut self) -> io::Result<()> {
+        Ok(self.pimpl.close()?)
+    }
+
     pub fn connect(&self, ep: &P::Endpoint) -> io::Result<()> {
         blocking_connect(self, ep, &self.pimpl.timeout)
     }
 
+    /// Returns the typed user data attached to the socket.
+    pub fn extensions(&self) -> &Extensions {
+        &self.pimpl.extensions
+    }
+
     pub fn local_endpoint(&self) -> io::Result<P::Endpoint> {
         Ok(getsockname(self)?)
     }
@@ -78,6 +244,13 @@ where
         nonblocking_write_op(self, buf, Sent::new(flags))
     }
 
+    pub fn nonblocking_send_file_some<T>(&self, file: &T, offset: u64, len: usize) -> io::Result<usize>
+    where
+        T: AsRawFd,
+    {
+        nonblocking_write_op(self, &[], SendFile::new(file.as_raw_fd(), offset, len))
+    }
+
     pub fn nonblocking_write_some(&self, buf: &[u8]) -> io::Result<usize> {
         nonblocking_write_op(self, buf, Write::new())
     }
@@ -100,6 +273,35 @@ where
         Ok(ioctl(self, cmd)?)
     }
 
+    pub fn is_open(&self) -> bool {
+        self.pimpl.is_open()
+    }
+
+    /// Returns the statistics of the asynchronous operations of the socket, that are counted only
+    /// in debug builds.
+    pub fn op_stats(&self) -> OpStats {
+        self.pimpl.op_stats()
+    }
+
+    /// Gets the socket option by the raw `level` and `name` into the buffer, returns the length of
+    /// the option value.
+    ///
+    /// # Safety
+    ///
+    /// The layout of the buffer must be what the kernel expects for the option.
+    pub unsafe fn raw_getsockopt(&self, level: i32, name: i32, buf: &mut [u8]) -> io::Result<usize> {
+        Ok(raw_getsockopt(self, level, name, buf)?)
+    }
+
+    /// Sets the socket option by the raw `level` and `name` from the buffer.
+    ///
+    /// # Safety
+    ///
+    /// The layout of the buffer must be what the kernel expects for the option.
+    pub unsafe fn raw_setsockopt(&self, level: i32, name: i32, buf: &[u8]) -> io::Result<()> {
+        Ok(raw_setsockopt(self, level, name, buf)?)
+    }
+
     pub fn read_some(&self, buf: &mut [u8]) -> io::Result<usize> {
         blocking_read_op(self, buf, &self.pimpl.timeout, Read::new())
     }
@@ -112,6 +314,19 @@ where
         blocking_write_op(self, buf, &self.pimpl.timeout, Sent::new(flags))
     }
 
+    /// Sends up to `len` bytes of the file from the offset, returns zero at the end of the file.
+    pub fn send_file_some<T>(&self, file: &T, offset: u64, len: usize) -> io::Result<usize>
+    where
+        T: AsRawFd,
+    {
+        blocking_write_op(
+            self,
+            &[],
+            &self.pimpl.timeout,
+            SendFile::new(file.as_raw_fd(), offset, len),
+        )
+    }
+
     pub fn remote_endpoint(&self) -> io::Result<P::Endpoint> {
         Ok(getpeername(self)?)
     }
@@ -134,6 +349,23 @@ where
     pub fn write_some(&self, buf: &[u8]) -> io::Result<usize> {
         blocking_write_op(self, buf, &self.pimpl.timeout, Write::new())
     }
+
+    /// Blocks until the socket becomes ready to read, write or have the error condition.
+    pub fn wait(&self, w: Wait) -> io::Result<()> {
+        Ok(wait(self, w, -1)?)
+    }
+
+    /// Blocks until the socket becomes ready or the timeout expires with the `ETIMEDOUT` error.
+    pub fn wait_for(&self, w: Wait, timeout: Duration) -> io::Result<()> {
+        Ok(wait_for(self, w, timeout)?)
+    }
+
+    /// Blocks until the socket becomes ready or the deadline with the `ETIMEDOUT` error.
+    pub fn wait_until(&self, w: Wait, deadline: Instant) -> io::Result<()> {
+        let now = Instant::now();
+        let timeout = if deadline > now { deadline - now } else { Duration::new(0, 0) };
+        Ok(wait_for(self, w, timeout)?)
+    }
 }
 
 unsafe impl<P> AsIoContext for StreamSocket<P> {
@@ -142,12 +374,152 @@ unsafe impl<P> AsIoContext for StreamSocket<P> {
     }
 }
 
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<P> StreamSocket<P>
+where
+    P: Protocol,
+{
+    /// Asynchronously moves up to `len` bytes from the pipe to this socket.
+    ///
+    /// The pipe must not be empty, that the operation waits for this socket only.
+    pub fn async_splice_from_pipe<T, F>(&self, pipe: &T, len: usize, flags: i32, handler: F) -> F::Output
+    where
+        T: AsRawFd,
+        F: Handler<usize, io::Error>,
+    {
+        async_write_op(
+            self,
+            &[],
+            &self.pimpl.timeout,
+            handler,
+            SpliceFromPipe::new(pipe.as_raw_fd(), len, flags),
+        )
+    }
+
+    /// Asynchronously moves up to `len` bytes from this socket to the pipe.
+    ///
+    /// The pipe must not be full, that the operation waits for this socket only.
+    pub fn async_splice_to_pipe<T, F>(&self, pipe: &T, len: usize, flags: i32, handler: F) -> F::Output
+    where
+        T: AsRawFd,
+        F: Handler<usize, io::Error>,
+    {
+        async_read_op(
+            self,
+            &[],
+            &self.pimpl.timeout,
+            handler,
+            SpliceToPipe::new(pipe.as_raw_fd(), len, flags),
+        )
+    }
+
+    pub fn nonblocking_splice_from_pipe<T>(&self, pipe: &T, len: usize, flags: i32) -> io::Result<usize>
+    where
+        T: AsRawFd,
+    {
+        nonblocking_write_op(self, &[], SpliceFromPipe::new(pipe.as_raw_fd(), len, flags))
+    }
+
+    pub fn nonblocking_splice_to_pipe<T>(&self, pipe: &T, len: usize, flags: i32) -> io::Result<usize>
+    where
+        T: AsRawFd,
+    {
+        nonblocking_read_op(self, &mut [], SpliceToPipe::new(pipe.as_raw_fd(), len, flags))
+    }
+
+    /// Moves up to `len` bytes from the pipe to this socket without copying to the userspace.
+    pub fn splice_from_pipe<T>(&self, pipe: &T, len: usize, flags: i32) -> io::Result<usize>
+    where
+        T: AsRawFd,
+    {
+        blocking_write_op(
+            self,
+            &[],
+            &self.pimpl.timeout,
+            SpliceFromPipe::new(pipe.as_raw_fd(), len, flags),
+        )
+    }
+
+    /// Moves up to `len` bytes from this socket to the pipe without copying to the userspace.
+    ///
+    /// Returns zero if the peer has shutdown the connection.
+    pub fn splice_to_pipe<T>(&self, pipe: &T, len: usize, flags: i32) -> io::Result<usize>
+    where
+        T: AsRawFd,
+    {
+        blocking_read_op(
+            self,
+            &mut [],
+            &self.pimpl.timeout,
+            SpliceToPipe::new(pipe.as_raw_fd(), len, flags),
+        )
+    }
+}
+
+impl StreamSocket<LocalStream> {
+    /// Asynchronously receives the data with the file descriptors passed by the peer, up to
+    /// `max_fds`.
+    ///
+    /// The received file descriptors are owned by the caller, that should close them.
+    pub fn async_receive_fds<F>(&self, buf: &mut [u8], max_fds: usize, handler: F) -> F::Output
+    where
+        F: Handler<(usize, Vec<RawFd>), io::Error>,
+    {
+        async_read_op(self, buf, &self.pimpl.timeout, handler, RecvFds::new(0, max_fds))
+    }
+
+    /// Asynchronously sends the data with the file descriptors to the peer.
+    ///
+    /// The file descriptors are duplicated into the peer, that are still owned by the caller.
+    pub fn async_send_fds<F>(&self, buf: &[u8], fds: &[RawFd], handler: F) -> F::Output
+    where
+        F: Handler<usize, io::Error>,
+    {
+        async_write_op(self, buf, &self.pimpl.timeout, handler, SendFds::new(0, fds))
+    }
+
+    /// Receives the data with the file descriptors passed by the peer, up to `max_fds`.
+    ///
+    /// The received file descriptors are owned by the caller, that should close them.
+    pub fn receive_fds(&self, buf: &mut [u8], max_fds: usize) -> io::Result<(usize, Vec<RawFd>)> {
+        blocking_read_op(self, buf, &self.pimpl.timeout, RecvFds::new(0, max_fds))
+    }
+
+    /// Sends the data with the file descriptors to the peer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::os::unix::io::AsRawFd;
+    /// use asyncio::IoContext;
+    /// use asyncio::local::{LocalStream, connect_pair};
+    ///
+    /// let ctx = &IoContext::new().unwrap();
+    /// let (tx, rx) = connect_pair(ctx, LocalStream).unwrap();
+    /// tx.send_fds(b"fd", &[tx.as_raw_fd()]).unwrap();
+    ///
+    /// let mut buf = [0; 16];
+    /// let (len, fds) = rx.receive_fds(&mut buf, 1).unwrap();
+    /// assert_eq!(&buf[..len], b"fd");
+    /// assert_eq!(fds.len(), 1);
+    /// ```
+    pub fn send_fds(&self, buf: &[u8], fds: &[RawFd]) -> io::Result<usize> {
+        blocking_write_op(self, buf, &self.pimpl.timeout, SendFds::new(0, fds))
+    }
+}
+
 impl<P> AsRawFd for StreamSocket<P> {
     fn as_raw_fd(&self) -> RawFd {
         self.pimpl.as_raw_fd()
     }
 }
 
+impl<P> IntoRawFd for StreamSocket<P> {
+    fn into_raw_fd(self) -> RawFd {
+        self.pimpl.into_raw_fd()
+    }
+}
+
 impl<P: 'static> Cancel for StreamSocket<P> {
     fn cancel(&self) {
         self.pimpl.cancel()
@@ -242,7 +614,7 @@ where
     }
 
     unsafe fn from_raw_fd(ctx: &IoContext, soc: RawFd, pro: P) -> Self {
-        StreamSocket { pimpl: SocketImpl::new(ctx, soc, pro) }
+        StreamSocket { pimpl: SocketImpl::socket(ctx, soc, pro) }
     }
 }
 
@@ -2,8 +2,11 @@ use ffi::NO_BUFFER_SPACE;
 
 use std::io;
 use std::cmp;
+use std::mem;
 use std::ffi::CString;
 use std::num::Wrapping;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex};
 
 /// Automatically resizing buffer.
 #[derive(Clone, Debug)]
@@ -298,6 +301,112 @@ impl io::Write for StreamBuf {
     }
 }
 
+struct BufferPoolImpl {
+    buf_size: usize,
+    max_bufs: usize,
+    idle: Mutex<Vec<Vec<u8>>>,
+}
+
+/// Provides a pool of the `StreamBuf` storages for reusing the allocated memories.
+///
+/// # Examples
+///
+/// ```
+/// use asyncio::BufferPool;
+///
+/// let pool = BufferPool::new(4096, 16);
+/// {
+///     let mut sbuf = pool.lease();
+///     assert_eq!(sbuf.prepare(8192).unwrap().len(), 4096);
+///     sbuf.commit(5);
+///     assert_eq!(sbuf.len(), 5);
+/// }
+/// assert_eq!(pool.available(), 1);
+/// let sbuf = pool.lease();
+/// assert!(sbuf.is_empty());
+/// assert_eq!(pool.available(), 0);
+/// ```
+#[derive(Clone)]
+pub struct BufferPool {
+    inner: Arc<BufferPoolImpl>,
+}
+
+impl BufferPool {
+    /// Returns a new `BufferPool` that keeps up to `max_bufs` buffers of `buf_size` bytes.
+    pub fn new(buf_size: usize, max_bufs: usize) -> BufferPool {
+        BufferPool {
+            inner: Arc::new(BufferPoolImpl {
+                buf_size: buf_size,
+                max_bufs: max_bufs,
+                idle: Mutex::default(),
+            }),
+        }
+    }
+
+    /// Returns a number of the idle buffers in the pool.
+    pub fn available(&self) -> usize {
+        self.inner.idle.lock().unwrap().len()
+    }
+
+    /// Returns a max length of the leased buffer.
+    pub fn buf_size(&self) -> usize {
+        self.inner.buf_size
+    }
+
+    /// Leases a empty buffer, that is allocated if the pool has no idle buffers.
+    ///
+    /// The buffer is returned to the pool when the `PooledBuf` is dropped.
+    pub fn lease(&self) -> PooledBuf {
+        let buf = self.inner.idle.lock().unwrap().pop();
+        let buf = buf.unwrap_or_else(|| Vec::with_capacity(self.inner.buf_size));
+        PooledBuf {
+            sbuf: StreamBuf {
+                buf: buf,
+                max: Wrapping(self.inner.buf_size),
+                rpos: Wrapping(0),
+                wpos: Wrapping(0),
+            },
+            pool: self.inner.clone(),
+        }
+    }
+
+    /// Returns a max number of the idle buffers in the pool.
+    pub fn max_bufs(&self) -> usize {
+        self.inner.max_bufs
+    }
+}
+
+/// A `StreamBuf` leased from the `BufferPool`.
+pub struct PooledBuf {
+    sbuf: StreamBuf,
+    pool: Arc<BufferPoolImpl>,
+}
+
+impl Deref for PooledBuf {
+    type Target = StreamBuf;
+
+    fn deref(&self) -> &Self::Target {
+        &self.sbuf
+    }
+}
+
+impl DerefMut for PooledBuf {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.sbuf
+    }
+}
+
+impl Drop for PooledBuf {
+    fn drop(&mut self) {
+        let mut buf = mem::replace(&mut self.sbuf.buf, Vec::new());
+        let mut idle = self.pool.idle.lock().unwrap();
+        if idle.len() < self.pool.max_bufs {
+            buf.clear();
+            idle.push(buf);
+        }
+    }
+}
+
 fn match_cond_bytes_unchecked(buf: &[u8], head: u8, tail: &[u8]) -> Result<usize, usize> {
     let mut cur = 0;
     let mut it = buf.iter();
@@ -488,3 +597,27 @@ fn test_match_cond() {
     assert_eq!("".match_cond("hello".as_bytes()), Err(0));
     assert_eq!("l".match_cond("hello".as_bytes()), Ok(3));
 }
+
+#[test]
+fn test_buffer_pool() {
+    let pool = BufferPool::new(100, 1);
+    let ptr = {
+        let mut sbuf = pool.lease();
+        assert_eq!(sbuf.max_len(), 100);
+        assert_eq!(sbuf.prepare(200).unwrap().len(), 100);
+        sbuf.commit(100);
+        assert!(sbuf.prepare_exact(1).is_err());
+        sbuf.as_bytes().as_ptr()
+    };
+    assert_eq!(pool.available(), 1);
+
+    let a = pool.lease();
+    let b = pool.lease();
+    assert_eq!(a.len(), 0);
+    assert_eq!(a.capacity(), 100);
+    assert_eq!(a.as_bytes().as_ptr(), ptr);
+    assert_eq!(pool.available(), 0);
+    drop(a);
+    drop(b);
+    assert_eq!(pool.available(), 1);
+}
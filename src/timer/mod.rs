@@ -1,22 +1,24 @@
-use ffi::{SystemError, OPERATION_CANCELED};
+use ffi::{SystemError, OPERATION_CANCELED, TIMED_OUT};
 use reactor::Reactor;
 use core::{AsIoContext, IoContext, Perform, ThreadIoContext};
 
 use std::cmp::Ordering;
+use std::collections::VecDeque;
 use std::ops::{Deref, DerefMut};
 use std::sync::Mutex;
+use std::thread;
 use std::time::{Duration, Instant, SystemTime};
 
 use libc::timespec;
 
-#[cfg(not(target_os = "linux"))]
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
 mod nolinux;
-#[cfg(not(target_os = "linux"))]
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
 use self::nolinux::TimerCtl;
 
-#[cfg(target_os = "linux")]
+#[cfg(any(target_os = "linux", target_os = "android"))]
 mod linux;
-#[cfg(target_os = "linux")]
+#[cfg(any(target_os = "linux", target_os = "android"))]
 use self::linux::TimerFd as TimerCtl;
 
 #[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Debug)]
@@ -53,14 +55,23 @@ impl Expiry {
         }
     }
 
+    /// Returns the expiry brought forward by the duration, that is never the zero.
+    fn advance(&self, dur: Duration) -> Self {
+        let min = Duration::new(0, 1);
+        match self.0.checked_sub(dur) {
+            Some(expiry) if expiry > min => Expiry(expiry),
+            _ => Expiry(min),
+        }
+    }
+
     pub fn left(&self) -> usize {
         self.diff(Expiry::now())
     }
 
     pub fn abs_time(&self) -> timespec {
         timespec {
-            tv_sec: self.0.as_secs() as i64,
-            tv_nsec: self.0.subsec_nanos() as i64,
+            tv_sec: self.0.as_secs() as _,
+            tv_nsec: self.0.subsec_nanos() as _,
         }
     }
 }
@@ -84,7 +95,7 @@ impl From<SystemTime> for Expiry {
 pub struct TimerImpl {
     ctx: IoContext,
     expiry: Expiry,
-    op: Option<Box<Perform>>,
+    ops: VecDeque<Box<Perform>>,
 }
 
 impl TimerImpl {
@@ -92,26 +103,52 @@ impl TimerImpl {
         Box::new(TimerImpl {
             ctx: ctx.clone(),
             expiry: Expiry::zero(),
-            op: None,
+            ops: VecDeque::new(),
         })
     }
 
     pub fn set_wait_op(&self, this: &mut ThreadIoContext, op: Box<Perform>) {
-        if let Some(op) = self.ctx.as_reactor().tq.insert(self, op) {
-            this.push(op, OPERATION_CANCELED)
+        if self.ctx.is_shutdown() {
+            return this.push(op, OPERATION_CANCELED);
         }
+        self.ctx.track_op(&*op);
+        self.ctx.as_reactor().tq.insert(self, op)
     }
 
-    pub fn reset_expiry(&self, expiry: Expiry) {
-        if let Some(op) = self.ctx.as_reactor().tq.erase(self, expiry) {
-            self.ctx.do_dispatch((op, OPERATION_CANCELED))
+    pub fn reset_expiry(&self, expiry: Expiry) -> usize {
+        let ops = self.ctx.as_reactor().tq.erase(self, Some(expiry));
+        self.cancel_ops(ops)
+    }
+
+    pub fn cancel(&self) -> usize {
+        let ops = self.ctx.as_reactor().tq.erase(self, None);
+        self.cancel_ops(ops)
+    }
+
+    pub fn cancel_one(&self) -> usize {
+        let op = self.ctx.as_reactor().tq.erase_one(self);
+        self.cancel_ops(op.into_iter().collect())
+    }
+
+    /// Blocks the current thread until the expiry, or returns the `ETIMEDOUT` error at the
+    /// deadline if it comes first.
+    pub fn wait(&self, deadline: Option<Expiry>) -> Result<(), SystemError> {
+        let left = self.expiry.left();
+        match deadline.map(|deadline| deadline.left()) {
+            Some(timeout) if timeout < left => {
+                thread::sleep(Duration::from_nanos(timeout as u64));
+                Err(TIMED_OUT)
+            }
+            _ => Ok(thread::sleep(Duration::from_nanos(left as u64))),
         }
     }
 
-    pub fn cancel(&self) {
-        if let Some(op) = self.ctx.as_reactor().tq.erase(self, Expiry::zero()) {
+    fn cancel_ops(&self, ops: Vec<Box<Perform>>) -> usize {
+        let len = ops.len();
+        for op in ops {
             self.ctx.do_dispatch((op, OPERATION_CANCELED))
         }
+        len
     }
 }
 
@@ -185,6 +222,17 @@ impl TimerQueue {
         self.ctl.startup(reactor)
     }
 
+    /// Brings forward the expiries of all the waiting timers by the duration.
+    pub fn advance(&self, dur: Duration) {
+        let mut tq = self.mutex.lock().unwrap();
+        for timer in tq.iter_mut() {
+            timer.expiry = timer.expiry.advance(dur);
+        }
+        for timer in tq.first().iter() {
+            self.ctl.reset_timeout(&timer);
+        }
+    }
+
     pub fn cleanup(&self, reactor: &Reactor) {
         self.ctl.cleanup(reactor)
     }
@@ -200,35 +248,65 @@ impl TimerQueue {
             Err(i) => i,
         };
         for mut e in tq.drain(..i) {
-            this.push(e.op.take().unwrap(), SystemError::default());
+            for op in e.ops.drain(..) {
+                this.push(op, SystemError::default());
+            }
+        }
+        if i > 0 {
+            for timer in tq.first().iter() {
+                self.ctl.reset_timeout(&timer);
+            }
         }
     }
 
-    pub fn insert(&self, timer: &TimerImpl, op: Box<Perform>) -> Option<Box<Perform>> {
+    pub fn insert(&self, timer: &TimerImpl, op: Box<Perform>) {
         let mut tq = self.mutex.lock().unwrap();
         let mut timer = TimerImplRef(timer);
-        let old_op = timer.op.take();
-        timer.op = Some(op);
-        let i = tq.binary_search(&timer).unwrap_err();
-        tq.insert(i, timer.clone());
-        if i == 0 {
-            self.ctl.reset_timeout(&timer);
+        timer.ops.push_back(op);
+        if let Err(i) = tq.binary_search(&timer) {
+            tq.insert(i, timer.clone());
+            if i == 0 {
+                self.ctl.reset_timeout(&timer);
+            }
         }
-        old_op
     }
 
-    pub fn erase(&self, timer: &TimerImpl, expiry: Expiry) -> Option<Box<Perform>> {
+    pub fn erase_all(&self) -> Vec<Box<Perform>> {
         let mut tq = self.mutex.lock().unwrap();
-        let mut timer = TimerImplRef(timer);
-        let old_op = timer.op.take();
-        if let Ok(i) = tq.binary_search(&timer) {
+        tq.drain(..).flat_map(|mut timer| timer.ops.drain(..).collect::<Vec<_>>()).collect()
+    }
+
+    fn remove(&self, tq: &mut Vec<TimerImplRef>, timer: &TimerImplRef) {
+        if let Ok(i) = tq.binary_search(timer) {
             tq.remove(i);
-            for timer in tq.first().iter() {
-                self.ctl.reset_timeout(&timer);
+            if i == 0 {
+                for timer in tq.first().iter() {
+                    self.ctl.reset_timeout(&timer);
+                }
             }
         }
-        timer.expiry = expiry;
-        old_op
+    }
+
+    /// Takes all waiting operations of the timer, and changes the expiry if specified.
+    pub fn erase(&self, timer: &TimerImpl, expiry: Option<Expiry>) -> Vec<Box<Perform>> {
+        let mut tq = self.mutex.lock().unwrap();
+        let mut timer = TimerImplRef(timer);
+        self.remove(&mut tq, &timer);
+        if let Some(expiry) = expiry {
+            timer.expiry = expiry;
+        }
+        timer.ops.drain(..).collect()
+    }
+
+    /// Takes the oldest waiting operation of the timer.
+    pub fn erase_one(&self, timer: &TimerImpl) -> Option<Box<Perform>> {
+        let mut tq = self.mutex.lock().unwrap();
+        let mut timer = TimerImplRef(timer);
+        let op = timer.ops.pop_front();
+        if timer.ops.is_empty() {
+            self.remove(&mut tq, &timer);
+        }
+        op
     }
 }
 
@@ -283,13 +361,13 @@ fn test_eq() {
     let t1 = TimerImpl {
         ctx: ctx.clone(),
         expiry: now.into(),
-        op: None,
+        ops: VecDeque::new(),
     };
 
     let t2 = TimerImpl {
         ctx: ctx.clone(),
         expiry: now.into(),
-        op: None,
+        ops: VecDeque::new(),
     };
 
     assert!(TimerImplRef(&t1) == TimerImplRef(&t1));
@@ -305,19 +383,19 @@ fn test_cmp() {
     let t1 = TimerImpl {
         ctx: ctx.clone(),
         expiry: (now + Duration::new(1, 0)).into(),
-        op: None,
+        ops: VecDeque::new(),
     };
 
     let t2 = TimerImpl {
         ctx: ctx.clone(),
         expiry: (now + Duration::new(2, 0)).into(),
-        op: None,
+        ops: VecDeque::new(),
     };
 
     let t3 = TimerImpl {
         ctx: ctx.clone(),
         expiry: (now + Duration::new(2, 0)).into(),
-        op: None,
+        ops: VecDeque::new(),
     };
 
     assert!(TimerImplRef(&t1) < TimerImplRef(&t2));
@@ -0,0 +1,278 @@
+use ffi::{AsRawFd, RawFd, INVALID_ARGUMENT};
+#[cfg(not(feature = "ssl"))]
+use ffi::OPERATION_NOT_SUPPORTED;
+use core::{AsIoContext, IoContext, Cancel};
+#[cfg(feature = "ssl")]
+use core::ThreadIoContext;
+use handler::{Handler, Complete};
+#[cfg(feature = "ssl")]
+use ffi::Timeout;
+use stream::Stream;
+use ip::{TcpResolver, TcpSocket};
+use local::{LocalStreamEndpoint, LocalStreamSocket, LocalStream};
+#[cfg(feature = "ssl")]
+use ssl::{self, Handshake, Rfc2818Verification, SslContext, SslStream, SslVerifyMode};
+#[cfg(feature = "ssl")]
+use socket_base::{RecvTimeout, SendTimeout};
+
+use std::io;
+use std::fmt;
+
+/// A stream connected by the `connect_uri`.
+///
+/// The enum is returned instead of a boxed stream, because the `Stream` is not object safe. It
+/// implements the `Stream`, and is converted into the `Box<DynStream>` if the type should be
+/// erased.
+pub enum UriStream {
+    Tcp(TcpSocket),
+    Local(LocalStreamSocket),
+    #[cfg(feature = "ssl")]
+    Tls(SslStream<TcpSocket>),
+}
+
+/// Connects to the `tcp://host:port`, the `unix:///path` or the `tls://host:port` and returns the
+/// connected stream.
+///
+/// The host is resolved, and each of the resolved endpoints is tried in order. The IPv6 address
+/// is enclosed in brackets, e.g. `tcp://[::1]:80`. The `tls://` stream performs the handshake as
+/// the client, that verifies the certificate of the host by the default verify paths. The `tls://`
+/// scheme fails with the `EOPNOTSUPP` error unless the `ssl` feature is enabled.
+///
+/// # Examples
+///
+/// ```
+/// use asyncio::{IoContext, UriStream, connect_uri};
+/// use asyncio::ip::{IpProtocol, IpAddrV4, Tcp, TcpEndpoint, TcpListener};
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let acc = TcpListener::new(ctx, Tcp::v4()).unwrap();
+/// acc.bind(&TcpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+/// acc.listen().unwrap();
+///
+/// let uri = format!("tcp://127.0.0.1:{}", acc.local_endpoint().unwrap().port());
+/// match connect_uri(ctx, &uri).unwrap() {
+///     UriStream::Tcp(soc) => assert!(soc.remote_endpoint().is_ok()),
+///     _ => unreachable!(),
+/// }
+/// ```
+pub fn connect_uri(ctx: &IoContext, uri: &str) -> io::Result<UriStream> {
+    let (scheme, rest) = match uri.find("://") {
+        Some(i) => (&uri[..i], &uri[i + 3..]),
+        None => return Err(INVALID_ARGUMENT.into()),
+    };
+    match scheme {
+        "tcp" => {
+            let (host, port) = split_host_port(rest)?;
+            let (soc, _) = TcpResolver::new(ctx).connect((host, port))?;
+            Ok(UriStream::Tcp(soc))
+        }
+        "unix" => {
+            let soc = LocalStreamSocket::new(ctx, LocalStream)?;
+            soc.connect(&LocalStreamEndpoint::new(rest)?)?;
+            Ok(UriStream::Local(soc))
+        }
+        #[cfg(feature = "ssl")]
+        "tls" => {
+            let (host, port) = split_host_port(rest)?;
+            let (soc, _) = TcpResolver::new(ctx).connect((host, port))?;
+            Ok(UriStream::Tls(connect_tls(soc, host)?))
+        }
+        #[cfg(not(feature = "ssl"))]
+        "tls" => Err(OPERATION_NOT_SUPPORTED.into()),
+        _ => Err(INVALID_ARGUMENT.into()),
+    }
+}
+
+#[cfg(feature = "ssl")]
+fn connect_tls(soc: TcpSocket, host: &str) -> ssl::Result<SslStream<TcpSocket>> {
+    // The blocking handshake is bounded by the timeout of the socket, and so are the kernel
+    // timeouts for the blocking calls on the raw fd.
+    let timeout = Some(soc.get_timeout());
+    soc.set_option(RecvTimeout::new(timeout))?;
+    soc.set_option(SendTimeout::new(timeout))?;
+    let mut ssl_ctx = SslContext::sslv23()?;
+    ssl_ctx.set_default_verify_paths()?;
+    ssl_ctx.set_verify_mode(SslVerifyMode::PEER);
+    let verify = Rfc2818Verification(host.to_owned());
+    ssl_ctx.set_verify_callback(move |ok, ctx| verify.verification(ok, ctx));
+    let mut ssl = SslStream::new(soc, &ssl_ctx)?;
+    ssl.set_host_name(host)?;
+    ssl.handshake(Handshake::Client)?;
+    Ok(ssl)
+}
+
+/// Converts the error of the `SslStream` into the `io::Error`.
+#[cfg(feature = "ssl")]
+struct SslIoError<F>(F);
+
+#[cfg(feature = "ssl")]
+impl<F> Handler<usize, ssl::Error> for SslIoError<F>
+where
+    F: Complete<usize, io::Error>,
+{
+    type Output = ();
+
+    type WrappedHandler = Self;
+
+    fn wrap<W>(self, ctx: &IoContext, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx, self)
+    }
+
+    fn wrap_timeout<W>(self, ctx: &Cancel, _: &Timeout, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx.as_ctx(), self)
+    }
+}
+
+#[cfg(feature = "ssl")]
+impl<F> Complete<usize, ssl::Error> for SslIoError<F>
+where
+    F: Complete<usize, io::Error>,
+{
+    fn success(self, this: &mut ThreadIoContext, len: usize) {
+        self.0.success(this, len)
+    }
+
+    fn failure(self, this: &mut ThreadIoContext, err: ssl::Error) {
+        self.0.failure(this, err.into())
+    }
+}
+
+fn split_host_port(s: &str) -> io::Result<(&str, &str)> {
+    let s = s.trim_end_matches('/');
+    let i = match s.rfind(':') {
+        Some(i) => i,
+        None => return Err(INVALID_ARGUMENT.into()),
+    };
+    let (host, port) = (&s[..i], &s[i + 1..]);
+    if host.starts_with('[') && host.ends_with(']') {
+        Ok((&host[1..host.len() - 1], port))
+    } else {
+        Ok((host, port))
+    }
+}
+
+unsafe impl AsIoContext for UriStream {
+    fn as_ctx(&self) -> &IoContext {
+        match *self {
+            UriStream::Tcp(ref soc) => soc.as_ctx(),
+            UriStream::Local(ref soc) => soc.as_ctx(),
+            #[cfg(feature = "ssl")]
+            UriStream::Tls(ref ssl) => ssl.as_ctx(),
+        }
+    }
+}
+
+impl AsRawFd for UriStream {
+    fn as_raw_fd(&self) -> RawFd {
+        match *self {
+            UriStream::Tcp(ref soc) => soc.as_raw_fd(),
+            UriStream::Local(ref soc) => soc.as_raw_fd(),
+            #[cfg(feature = "ssl")]
+            UriStream::Tls(ref ssl) => ssl.next_layer().as_raw_fd(),
+        }
+    }
+}
+
+impl Cancel for UriStream {
+    fn cancel(&self) {
+        match *self {
+            UriStream::Tcp(ref soc) => soc.cancel(),
+            UriStream::Local(ref soc) => soc.cancel(),
+            #[cfg(feature = "ssl")]
+            UriStream::Tls(ref ssl) => ssl.cancel(),
+        }
+    }
+}
+
+impl fmt::Debug for UriStream {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            UriStream::Tcp(_) => write!(f, "UriStream::Tcp({})", self.as_raw_fd()),
+            UriStream::Local(_) => write!(f, "UriStream::Local({})", self.as_raw_fd()),
+            #[cfg(feature = "ssl")]
+            UriStream::Tls(_) => write!(f, "UriStream::Tls({})", self.as_raw_fd()),
+        }
+    }
+}
+
+impl Stream for UriStream {
+    type Error = io::Error;
+
+    fn async_read_some<F>(&self, buf: &[u8], handler: F) -> F::Output
+    where
+        F: Handler<usize, Self::Error>,
+    {
+        match *self {
+            UriStream::Tcp(ref soc) => soc.async_read_some(buf, handler),
+            UriStream::Local(ref soc) => soc.async_read_some(buf, handler),
+            #[cfg(feature = "ssl")]
+            UriStream::Tls(ref ssl) => {
+                self.wrap_timeout(handler, move |_, handler| {
+                    ssl.async_read_some(buf, SslIoError(handler))
+                })
+            }
+        }
+    }
+
+    fn async_write_some<F>(&self, buf: &[u8], handler: F) -> F::Output
+    where
+        F: Handler<usize, Self::Error>,
+    {
+        match *self {
+            UriStream::Tcp(ref soc) => soc.async_write_some(buf, handler),
+            UriStream::Local(ref soc) => soc.async_write_some(buf, handler),
+            #[cfg(feature = "ssl")]
+            UriStream::Tls(ref ssl) => {
+                self.wrap_timeout(handler, move |_, handler| {
+                    ssl.async_write_some(buf, SslIoError(handler))
+                })
+            }
+        }
+    }
+
+    #[doc(hidden)]
+    fn wrap_timeout<F, G, W>(&self, handler: F, wrapper: W) -> F::Output
+    where
+        F: Handler<usize, Self::Error, WrappedHandler = G>,
+        G: Complete<usize, Self::Error>,
+        W: FnOnce(&IoContext, G),
+    {
+        match *self {
+            UriStream::Tcp(ref soc) => soc.wrap_timeout(handler, wrapper),
+            UriStream::Local(ref soc) => soc.wrap_timeout(handler, wrapper),
+            #[cfg(feature = "ssl")]
+            UriStream::Tls(ref ssl) => {
+                // The SslStream has no timeout of its own, the timeout of the next layer cancels
+                // the whole stream including the queued operations. The default is kept as max.
+                let timeout = Timeout::max();
+                let _ = timeout.set(ssl.next_layer().get_timeout());
+                handler.wrap_timeout(ssl, &timeout, wrapper)
+            }
+        }
+    }
+}
+
+#[test]
+fn test_split_host_port() {
+    assert_eq!(split_host_port("localhost:80").unwrap(), ("localhost", "80"));
+    assert_eq!(split_host_port("[::1]:http/").unwrap(), ("::1", "http"));
+    assert!(split_host_port("localhost").is_err());
+}
+
+#[test]
+fn test_connect_uri() {
+    let ctx = &IoContext::new().unwrap();
+    #[cfg(not(feature = "ssl"))]
+    assert_eq!(
+        connect_uri(ctx, "tls://localhost:443").unwrap_err().raw_os_error(),
+        Some(::libc::EOPNOTSUPP)
+    );
+    assert!(connect_uri(ctx, "ftp://localhost").is_err());
+    assert!(connect_uri(ctx, "localhost:80").is_err());
+}
@@ -1,13 +1,24 @@
 #![allow(unreachable_patterns)]
 
 use ffi::{AsRawFd, Timeout, SystemError, TRY_AGAIN, WOULD_BLOCK, INTERRUPTED, OPERATION_CANCELED,
-          send, sendto, write, writable};
+          RawFd, iovec, pwrite, send, sendmsg_fds, sendto, write, writev, writable};
 use core::{Protocol, Socket, AsIoContext, Exec, Perform, ThreadIoContext};
 use handler::{Complete, Handler, AsyncWriteOp};
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use ffi::{sendfile, sendmsg_hoplimit, sendmsg_txtime, splice};
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+use ffi::{CONNECTION_ABORTED, pread};
+#[cfg(all(target_os = "linux", feature = "uring"))]
+use ffi::{uring_result, CONNECTION_ABORTED, OPERATION_NOT_SUPPORTED};
+#[cfg(all(target_os = "linux", feature = "uring"))]
+use io_uring::{opcode, squeue, types};
 
 use std::io;
 use std::slice;
 use std::marker::PhantomData;
+#[cfg(any(not(any(target_os = "linux", target_os = "android")),
+          all(target_os = "linux", feature = "uring")))]
+use std::cmp;
 
 pub trait Writer: 'static {
     type Socket: AsRawFd + AsyncWriteOp;
@@ -15,6 +26,18 @@ pub trait Writer: 'static {
     type Output: Send;
 
     fn write_op(&self, s: &Self::Socket, buf: &[u8]) -> Result<Self::Output, SystemError>;
+
+    /// Returns the request of the `write_op` to the `io_uring`, if it is supported.
+    #[cfg(all(target_os = "linux", feature = "uring"))]
+    fn uring_entry(&self, _s: &Self::Socket, _buf: &[u8]) -> Option<squeue::Entry> {
+        None
+    }
+
+    /// Returns the output of the request from the result of its completion.
+    #[cfg(all(target_os = "linux", feature = "uring"))]
+    fn uring_output(&self, _res: i32) -> Result<Self::Output, SystemError> {
+        Err(OPERATION_NOT_SUPPORTED)
+    }
 }
 
 pub struct Sent<P, S> {
@@ -43,6 +66,51 @@ where
     fn write_op(&self, s: &Self::Socket, buf: &[u8]) -> Result<Self::Output, SystemError> {
         send(s, buf, self.flags)
     }
+
+    #[cfg(all(target_os = "linux", feature = "uring"))]
+    fn uring_entry(&self, s: &Self::Socket, buf: &[u8]) -> Option<squeue::Entry> {
+        let len = cmp::min(buf.len(), u32::max_value() as usize) as u32;
+        let send = opcode::Send::new(types::Fd(s.as_raw_fd()), buf.as_ptr(), len);
+        Some(send.flags(self.flags).build())
+    }
+
+    #[cfg(all(target_os = "linux", feature = "uring"))]
+    fn uring_output(&self, res: i32) -> Result<Self::Output, SystemError> {
+        match uring_result(res)? {
+            0 => Err(CONNECTION_ABORTED),
+            len => Ok(len),
+        }
+    }
+}
+
+pub struct SendFds<P, S> {
+    flags: i32,
+    fds: Vec<RawFd>,
+    _marker: PhantomData<(P, S)>,
+}
+
+impl<P, S> SendFds<P, S> {
+    pub fn new(flags: i32, fds: &[RawFd]) -> Self {
+        SendFds {
+            flags: flags,
+            fds: fds.to_vec(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<P, S> Writer for SendFds<P, S>
+where
+    P: Protocol,
+    S: Socket<P> + AsyncWriteOp,
+{
+    type Socket = S;
+
+    type Output = usize;
+
+    fn write_op(&self, s: &Self::Socket, buf: &[u8]) -> Result<Self::Output, SystemError> {
+        sendmsg_fds(s, buf, self.flags, &self.fds)
+    }
 }
 
 pub struct SendTo<P, S>
@@ -81,6 +149,136 @@ where
     }
 }
 
+pub struct SendFile<S> {
+    fd: RawFd,
+    offset: u64,
+    len: usize,
+    _marker: PhantomData<S>,
+}
+
+impl<S> SendFile<S> {
+    pub fn new(fd: RawFd, offset: u64, len: usize) -> Self {
+        SendFile {
+            fd: fd,
+            offset: offset,
+            len: len,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<S> Writer for SendFile<S>
+where
+    S: AsRawFd + AsyncWriteOp,
+{
+    type Socket = S;
+
+    type Output = usize;
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn write_op(&self, soc: &Self::Socket, _: &[u8]) -> Result<Self::Output, SystemError> {
+        sendfile(soc, &self.fd, self.offset, self.len)
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    fn write_op(&self, soc: &Self::Socket, _: &[u8]) -> Result<Self::Output, SystemError> {
+        // Reads the chunk again if the socket would block, that leaves the file as is.
+        let mut buf = [0; 16384];
+        let len = cmp::min(self.len, buf.len());
+        if len == 0 {
+            return Ok(0);
+        }
+        let len = match pread(&self.fd, &mut buf[..len], self.offset) {
+            Ok(len) => len,
+            Err(CONNECTION_ABORTED) => return Ok(0),
+            Err(err) => return Err(err),
+        };
+        write(soc, &buf[..len])
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub struct SendToHopLimit<P, S>
+where
+    P: Protocol,
+{
+    flags: i32,
+    ep: P::Endpoint,
+    hops: u8,
+    _marker: PhantomData<(P, S)>,
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<P, S> SendToHopLimit<P, S>
+where
+    P: Protocol,
+{
+    pub fn new(flags: i32, ep: &P::Endpoint, hops: u8) -> Self {
+        SendToHopLimit {
+            flags: flags,
+            ep: ep.clone(),
+            hops: hops,
+            _marker: PhantomData,
+        }
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<P, S> Writer for SendToHopLimit<P, S>
+where
+    P: Protocol,
+    S: Socket<P> + AsyncWriteOp,
+{
+    type Socket = S;
+
+    type Output = usize;
+
+    fn write_op(&self, s: &Self::Socket, buf: &[u8]) -> Result<Self::Output, SystemError> {
+        sendmsg_hoplimit(s, buf, self.flags, &self.ep, self.hops)
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub struct SendToTxTime<P, S>
+where
+    P: Protocol,
+{
+    flags: i32,
+    ep: P::Endpoint,
+    txtime: u64,
+    _marker: PhantomData<(P, S)>,
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<P, S> SendToTxTime<P, S>
+where
+    P: Protocol,
+{
+    pub fn new(flags: i32, ep: &P::Endpoint, txtime: u64) -> Self {
+        SendToTxTime {
+            flags: flags,
+            ep: ep.clone(),
+            txtime: txtime,
+            _marker: PhantomData,
+        }
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<P, S> Writer for SendToTxTime<P, S>
+where
+    P: Protocol,
+    S: Socket<P> + AsyncWriteOp,
+{
+    type Socket = S;
+
+    type Output = usize;
+
+    fn write_op(&self, s: &Self::Socket, buf: &[u8]) -> Result<Self::Output, SystemError> {
+        sendmsg_txtime(s, buf, self.flags, &self.ep, self.txtime)
+    }
+}
+
 pub struct Write<S> {
     _marker: PhantomData<S>,
 }
@@ -102,6 +300,112 @@ where
     fn write_op(&self, soc: &Self::Socket, buf: &[u8]) -> Result<Self::Output, SystemError> {
         write(soc, buf)
     }
+
+    #[cfg(all(target_os = "linux", feature = "uring"))]
+    fn uring_entry(&self, soc: &Self::Socket, buf: &[u8]) -> Option<squeue::Entry> {
+        let len = cmp::min(buf.len(), u32::max_value() as usize) as u32;
+        // The offset of -1 writes to the current position, that is also valid for the pipes.
+        let write = opcode::Write::new(types::Fd(soc.as_raw_fd()), buf.as_ptr(), len);
+        Some(write.offset(u64::max_value()).build())
+    }
+
+    #[cfg(all(target_os = "linux", feature = "uring"))]
+    fn uring_output(&self, res: i32) -> Result<Self::Output, SystemError> {
+        uring_result(res)
+    }
+}
+
+pub struct WriteAt<S> {
+    offset: u64,
+    _marker: PhantomData<S>,
+}
+
+impl<S> WriteAt<S> {
+    pub fn new(offset: u64) -> Self {
+        WriteAt {
+            offset: offset,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<S> Writer for WriteAt<S>
+where
+    S: AsRawFd + AsyncWriteOp,
+{
+    type Socket = S;
+
+    type Output = usize;
+
+    fn write_op(&self, soc: &Self::Socket, buf: &[u8]) -> Result<Self::Output, SystemError> {
+        pwrite(soc, buf, self.offset)
+    }
+}
+
+pub struct WriteV<S> {
+    iov: Vec<iovec>,
+    _marker: PhantomData<S>,
+}
+
+impl<S> WriteV<S> {
+    pub fn new(bufs: Vec<&[u8]>) -> Self {
+        WriteV {
+            iov: bufs.into_iter()
+                .map(|buf| iovec {
+                    iov_base: buf.as_ptr() as *mut _,
+                    iov_len: buf.len(),
+                })
+                .collect(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<S> Writer for WriteV<S>
+where
+    S: AsRawFd + AsyncWriteOp,
+{
+    type Socket = S;
+
+    type Output = usize;
+
+    fn write_op(&self, soc: &Self::Socket, _: &[u8]) -> Result<Self::Output, SystemError> {
+        writev(soc, &self.iov)
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub struct SpliceFromPipe<S> {
+    fd: RawFd,
+    len: usize,
+    flags: i32,
+    _marker: PhantomData<S>,
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<S> SpliceFromPipe<S> {
+    pub fn new(fd: RawFd, len: usize, flags: i32) -> Self {
+        SpliceFromPipe {
+            fd: fd,
+            len: len,
+            flags: flags,
+            _marker: PhantomData,
+        }
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<S> Writer for SpliceFromPipe<S>
+where
+    S: AsRawFd + AsyncWriteOp,
+{
+    type Socket = S;
+
+    type Output = usize;
+
+    fn write_op(&self, soc: &Self::Socket, _: &[u8]) -> Result<Self::Output, SystemError> {
+        splice(&self.fd, soc, self.len, self.flags)
+    }
 }
 
 struct AsyncWrite<F, W>
@@ -163,6 +467,24 @@ where
             self.failure(this, err.into())
         }
     }
+
+    #[cfg(all(target_os = "linux", feature = "uring"))]
+    fn uring_entry(&mut self) -> Option<squeue::Entry> {
+        let soc = unsafe { &*self.soc };
+        let buf = unsafe { slice::from_raw_parts(self.buf, self.len) };
+        self.writer.uring_entry(soc, buf)
+    }
+
+    #[cfg(all(target_os = "linux", feature = "uring"))]
+    fn uring_complete(self: Box<Self>, this: &mut ThreadIoContext, res: i32) {
+        match self.writer.uring_output(res) {
+            Ok(res) => self.success(this, res),
+            Err(INTERRUPTED) | Err(TRY_AGAIN) | Err(WOULD_BLOCK) => {
+                self.perform(this, SystemError::default())
+            }
+            Err(err) => self.failure(this, err.into()),
+        }
+    }
 }
 
 impl<F, W> Exec for AsyncWrite<F, W>
@@ -0,0 +1,293 @@
+use ffi::Timeout;
+use core::{AsIoContext, IoContext, ThreadIoContext, Cancel};
+use handler::{Handler, Complete, Failure};
+use stream::Stream;
+use streambuf::StreamBuf;
+use ip::{TcpSocket, TrafficClass};
+
+use std::sync::{Arc, Mutex};
+use std::collections::VecDeque;
+
+/// A priority of the message written by the `WriteQueue`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum WritePriority {
+    /// The message is written before any queued low priority messages.
+    High,
+
+    /// The message is written in the order of the queue.
+    Low,
+}
+
+trait QueuedHandler<E>: Send + 'static {
+    fn success_box(self: Box<Self>, this: &mut ThreadIoContext, len: usize);
+
+    fn failure_box(self: Box<Self>, this: &mut ThreadIoContext, err: E);
+}
+
+impl<F, E> QueuedHandler<E> for F
+where
+    F: Complete<usize, E>,
+    E: Send + 'static,
+{
+    fn success_box(self: Box<Self>, this: &mut ThreadIoContext, len: usize) {
+        self.success(this, len)
+    }
+
+    fn failure_box(self: Box<Self>, this: &mut ThreadIoContext, err: E) {
+        self.failure(this, err)
+    }
+}
+
+struct Entry<E> {
+    sbuf: StreamBuf,
+    prio: WritePriority,
+    handler: Box<QueuedHandler<E>>,
+}
+
+struct QueueState<E> {
+    busy: bool,
+    high: VecDeque<Box<Entry<E>>>,
+    low: VecDeque<Box<Entry<E>>>,
+    last: Option<WritePriority>,
+}
+
+impl<E> QueueState<E> {
+    fn pop(&mut self) -> Option<Box<Entry<E>>> {
+        let entry = self.high.pop_front().or_else(|| self.low.pop_front());
+        self.busy = entry.is_some();
+        entry
+    }
+}
+
+struct WriteQueueImpl<S>
+where
+    S: Stream,
+{
+    soc: S,
+    state: Mutex<QueueState<S::Error>>,
+    hook: Mutex<Option<Box<Fn(&S, WritePriority) + Send + Sync>>>,
+}
+
+unsafe impl<S: Stream> Send for WriteQueueImpl<S> {}
+
+unsafe impl<S: Stream> Sync for WriteQueueImpl<S> {}
+
+fn start<S>(wq: &Arc<WriteQueueImpl<S>>, mut entry: Box<Entry<S::Error>>)
+where
+    S: Stream,
+{
+    let switched = {
+        let mut state = wq.state.lock().unwrap();
+        let switched = state.last != Some(entry.prio);
+        state.last = Some(entry.prio);
+        switched
+    };
+    if switched {
+        if let Some(ref hook) = *wq.hook.lock().unwrap() {
+            hook(&wq.soc, entry.prio)
+        }
+    }
+    let sbuf = &mut entry.sbuf as *mut StreamBuf;
+    wq.soc.async_write_all::<usize, _>(
+        unsafe { &mut *sbuf },
+        WriteQueueOp {
+            wq: wq.clone(),
+            entry: entry,
+        },
+    )
+}
+
+fn next<S>(wq: &Arc<WriteQueueImpl<S>>)
+where
+    S: Stream,
+{
+    let entry = wq.state.lock().unwrap().pop();
+    if let Some(entry) = entry {
+        start(wq, entry)
+    }
+}
+
+struct WriteQueueOp<S>
+where
+    S: Stream,
+{
+    wq: Arc<WriteQueueImpl<S>>,
+    entry: Box<Entry<S::Error>>,
+}
+
+impl<S> Handler<usize, S::Error> for WriteQueueOp<S>
+where
+    S: Stream,
+{
+    type Output = ();
+
+    type WrappedHandler = Self;
+
+    fn wrap<W>(self, ctx: &IoContext, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx, self)
+    }
+
+    fn wrap_timeout<W>(self, ctx: &Cancel, _: &Timeout, wrapper: W) -> Self::Output
+    where
+        W: FnOnce(&IoContext, Self::WrappedHandler),
+    {
+        wrapper(ctx.as_ctx(), self)
+    }
+}
+
+impl<S> Complete<usize, S::Error> for WriteQueueOp<S>
+where
+    S: Stream,
+{
+    fn success(self, this: &mut ThreadIoContext, len: usize) {
+        let WriteQueueOp { wq, entry } = self;
+        entry.handler.success_box(this, len);
+        next(&wq)
+    }
+
+    fn failure(self, this: &mut ThreadIoContext, err: S::Error) {
+        let WriteQueueOp { wq, entry } = self;
+        entry.handler.failure_box(this, err);
+        next(&wq)
+    }
+}
+
+/// Provides a write queue with two priorities for the stream.
+///
+/// Each message is written by `Stream::async_write_all` one by one, that a high priority message
+/// (e.g. ping or ack) bypasses the queued low priority messages but never interrupts the message
+/// being written.
+///
+/// # Examples
+///
+/// ```
+/// use std::io;
+/// use std::sync::Arc;
+/// use asyncio::{IoContext, WriteQueue, WritePriority, wrap};
+/// use asyncio::ip::{IpProtocol, Tcp, TcpSocket};
+///
+/// fn on_write(_: Arc<WriteQueue<TcpSocket>>, res: io::Result<usize>) {
+/// }
+///
+/// let ctx = &IoContext::new().unwrap();
+/// let wq = Arc::new(WriteQueue::new(TcpSocket::new(ctx, Tcp::v4()).unwrap()));
+/// wq.set_dscp(46, 0);
+/// wq.async_write(b"bulk data", WritePriority::Low, wrap(&wq, on_write));
+/// wq.async_write(b"ping", WritePriority::High, wrap(&wq, on_write));
+/// ```
+pub struct WriteQueue<S>
+where
+    S: Stream,
+{
+    inner: Arc<WriteQueueImpl<S>>,
+}
+
+impl<S> WriteQueue<S>
+where
+    S: Stream,
+{
+    pub fn new(soc: S) -> Self {
+        WriteQueue {
+            inner: Arc::new(WriteQueueImpl {
+                soc: soc,
+                state: Mutex::new(QueueState {
+                    busy: false,
+                    high: VecDeque::new(),
+                    low: VecDeque::new(),
+                    last: None,
+                }),
+                hook: Mutex::default(),
+            }),
+        }
+    }
+
+    /// Queues a copy of the buffer, the handler is invoked when the whole buffer has been written.
+    pub fn async_write<F>(&self, buf: &[u8], prio: WritePriority, handler: F) -> F::Output
+    where
+        F: Handler<usize, S::Error>,
+    {
+        let mut sbuf = StreamBuf::new();
+        let res = sbuf.prepare(buf.len()).map(|dst| dst[..buf.len()].copy_from_slice(buf));
+        if res.is_ok() {
+            sbuf.commit(buf.len());
+        }
+        let wq = self.inner.clone();
+        handler.wrap(self.inner.soc.as_ctx(), move |ctx, handler| {
+            if let Err(err) = res {
+                return ctx.do_dispatch(Failure::new(err, handler));
+            }
+            let entry = Box::new(Entry {
+                sbuf: sbuf,
+                prio: prio,
+                handler: Box::new(handler),
+            });
+            let entry = {
+                let mut state = wq.state.lock().unwrap();
+                match prio {
+                    WritePriority::High => state.high.push_back(entry),
+                    WritePriority::Low => state.low.push_back(entry),
+                }
+                if state.busy { None } else { state.pop() }
+            };
+            if let Some(entry) = entry {
+                start(&wq, entry)
+            }
+        })
+    }
+
+    /// Returns a number of the queued messages, that does not include the message being written.
+    pub fn pending(&self) -> usize {
+        let state = self.inner.state.lock().unwrap();
+        state.high.len() + state.low.len()
+    }
+
+    /// Sets a function invoked before writing a message of the priority different from the last one.
+    pub fn set_priority_hook<F>(&self, hook: F)
+    where
+        F: Fn(&S, WritePriority) + Send + Sync + 'static,
+    {
+        *self.inner.hook.lock().unwrap() = Some(Box::new(hook));
+    }
+
+    /// Returns a reference to the stream.
+    pub fn socket(&self) -> &S {
+        &self.inner.soc
+    }
+}
+
+impl WriteQueue<TcpSocket> {
+    /// Sets a differentiated services code point for each priority.
+    ///
+    /// The `TrafficClass` option of the socket is switched before writing a message.
+    pub fn set_dscp(&self, high: u8, low: u8) {
+        self.set_priority_hook(move |soc: &TcpSocket, prio| {
+            let dscp = match prio {
+                WritePriority::High => high,
+                WritePriority::Low => low,
+            };
+            let _ = soc.set_option(TrafficClass::from_dscp(dscp));
+        });
+        self.inner.state.lock().unwrap().last = None;
+    }
+}
+
+unsafe impl<S> AsIoContext for WriteQueue<S>
+where
+    S: Stream,
+{
+    fn as_ctx(&self) -> &IoContext {
+        self.inner.soc.as_ctx()
+    }
+}
+
+impl<S> Clone for WriteQueue<S>
+where
+    S: Stream,
+{
+    fn clone(&self) -> Self {
+        WriteQueue { inner: self.inner.clone() }
+    }
+}
@@ -0,0 +1,39 @@
+extern crate asyncio;
+use std::io;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use asyncio::*;
+use asyncio::ip::*;
+
+static TIMED_OUT: AtomicUsize = AtomicUsize::new(0);
+
+fn on_close(_: Arc<TcpListener>, res: io::Result<()>) {
+    match res {
+        Err(ref err) if err.kind() == io::ErrorKind::TimedOut => {
+            TIMED_OUT.fetch_add(1, Ordering::SeqCst);
+        }
+        res => panic!("{:?}", res),
+    }
+}
+
+#[test]
+fn main() {
+    let ctx = &IoContext::new().unwrap();
+    let acc = Arc::new(TcpListener::new(ctx, Tcp::v4()).unwrap());
+    acc.bind(&TcpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+    acc.listen().unwrap();
+
+    let cl = TcpSocket::new(ctx, Tcp::v4()).unwrap();
+    cl.connect(&acc.local_endpoint().unwrap()).unwrap();
+    let (sv, _) = acc.accept().unwrap();
+
+    // the peer never closes, so the close times out in the virtual time.
+    sv.async_close(Some(Duration::new(3600, 0)), wrap(&acc, on_close));
+    ctx.post(|ctx| ctx.advance_clock(Duration::new(3600, 0)));
+    let now = Instant::now();
+    ctx.run();
+    assert!(now.elapsed() < Duration::new(60, 0));
+    assert_eq!(TIMED_OUT.load(Ordering::SeqCst), 1);
+    drop(cl);
+}
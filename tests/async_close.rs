@@ -0,0 +1,82 @@
+extern crate asyncio;
+use std::io;
+use std::thread;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use asyncio::*;
+use asyncio::ip::*;
+use asyncio::socket_base::Linger;
+
+static CLOSED: AtomicUsize = AtomicUsize::new(0);
+static TIMED_OUT: AtomicUsize = AtomicUsize::new(0);
+
+fn on_close(_: Arc<TcpListener>, res: io::Result<()>) {
+    match res {
+        Ok(_) => CLOSED.fetch_add(1, Ordering::SeqCst),
+        Err(ref err) if err.kind() == io::ErrorKind::TimedOut => {
+            TIMED_OUT.fetch_add(1, Ordering::SeqCst)
+        }
+        Err(err) => panic!("{}", err),
+    };
+}
+
+fn connect(acc: &TcpListener) -> (TcpSocket, TcpSocket) {
+    let ctx = acc.as_ctx();
+    let cl = TcpSocket::new(ctx, Tcp::v4()).unwrap();
+    cl.connect(&acc.local_endpoint().unwrap()).unwrap();
+    let (sv, _) = acc.accept().unwrap();
+    (sv, cl)
+}
+
+fn read_to_end(cl: &TcpSocket) -> io::Result<Vec<u8>> {
+    let mut vec = Vec::new();
+    let mut buf = [0; 256];
+    loop {
+        match cl.read_some(&mut buf) {
+            Ok(len) => vec.extend_from_slice(&buf[..len]),
+            Err(ref err) if err.kind() == io::ErrorKind::ConnectionAborted => return Ok(vec),
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+#[test]
+fn main() {
+    let ctx = &IoContext::new().unwrap();
+    let acc = Arc::new(TcpListener::new(ctx, Tcp::v4()).unwrap());
+    acc.bind(&TcpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+    acc.listen().unwrap();
+
+    // completes when the peer closes after reading all the data.
+    let (sv, cl) = connect(&acc);
+    sv.write_some(b"hello").unwrap();
+    sv.async_close(Some(Duration::from_secs(10)), wrap(&acc, on_close));
+    let thrd = thread::spawn(move || {
+        assert_eq!(read_to_end(&cl).unwrap(), b"hello");
+        cl.write_some(b"ignored").unwrap();
+    });
+    ctx.run();
+    thrd.join().unwrap();
+    assert_eq!(CLOSED.load(Ordering::SeqCst), 1);
+
+    // completes with the timed out if the peer does not close.
+    ctx.restart();
+    let (sv, cl) = connect(&acc);
+    let now = Instant::now();
+    sv.async_close(Some(Duration::from_millis(50)), wrap(&acc, on_close));
+    ctx.run();
+    assert!(now.elapsed() >= Duration::from_millis(50));
+    assert_eq!(TIMED_OUT.load(Ordering::SeqCst), 1);
+    ctx.restart();
+    assert_eq!(read_to_end(&cl).unwrap(), b"");
+
+    // closes immediately without the timeout.
+    let (sv, cl) = connect(&acc);
+    sv.set_option(Linger::new(Some(0))).unwrap();
+    sv.async_close(None, wrap(&acc, on_close));
+    ctx.run();
+    assert_eq!(CLOSED.load(Ordering::SeqCst), 2);
+    ctx.restart();
+    assert!(read_to_end(&cl).is_err()); // reset by the linger of zero.
+}
@@ -0,0 +1,52 @@
+extern crate asyncio;
+use std::io;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use asyncio::*;
+use asyncio::ip::*;
+
+const LEN: usize = 1000;
+
+static mut GOAL_COUNT: usize = 0;
+
+struct Sender {
+    soc: UdpSocket,
+    pacer: Pacer,
+    buf: [u8; LEN],
+}
+
+unsafe impl AsIoContext for Sender {
+    fn as_ctx(&self) -> &IoContext {
+        self.soc.as_ctx()
+    }
+}
+
+fn on_send(sd: Arc<Sender>, res: io::Result<usize>) {
+    assert_eq!(res.unwrap(), LEN);
+    unsafe {
+        GOAL_COUNT += 1;
+        if GOAL_COUNT < 5 {
+            sd.soc.async_send_paced(&sd.pacer, &sd.buf, 0, wrap(&sd, on_send));
+        }
+    }
+}
+
+#[test]
+fn main() {
+    let ctx = &IoContext::new().unwrap();
+    let rx = UdpSocket::new(ctx, Udp::v4()).unwrap();
+    rx.bind(&UdpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+
+    let sd = Arc::new(Sender {
+        soc: UdpSocket::new(ctx, Udp::v4()).unwrap(),
+        pacer: Pacer::new(10_000, LEN),
+        buf: [0; LEN],
+    });
+    sd.soc.connect(&rx.local_endpoint().unwrap()).unwrap();
+
+    let now = Instant::now();
+    sd.soc.async_send_paced(&sd.pacer, &sd.buf, 0, wrap(&sd, on_send));
+    ctx.run();
+    assert_eq!(unsafe { GOAL_COUNT }, 5);
+    assert!(now.elapsed() >= Duration::from_millis(350));
+}
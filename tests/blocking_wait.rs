@@ -0,0 +1,37 @@
+extern crate asyncio;
+use std::io;
+use std::time::{Duration, Instant};
+use asyncio::*;
+use asyncio::ip::*;
+use asyncio::socket_base::Wait;
+
+#[test]
+fn main() {
+    let ctx = &IoContext::new().unwrap();
+
+    // The timer is waited without running the context.
+    let timer = SteadyTimer::new(ctx);
+    timer.expires_from_now(Duration::from_millis(10));
+    let now = Instant::now();
+    timer.wait().unwrap();
+    assert!(now.elapsed() >= Duration::from_millis(10));
+    timer.expires_after(Duration::new(60, 0));
+    assert_eq!(
+        timer.wait_for(Duration::from_millis(1)).unwrap_err().kind(),
+        io::ErrorKind::TimedOut
+    );
+
+    let ep = UdpEndpoint::new(IpAddrV4::loopback(), 0);
+    let rx = UdpSocket::new(ctx, ep.protocol()).unwrap();
+    rx.bind(&ep).unwrap();
+    let tx = UdpSocket::new(ctx, ep.protocol()).unwrap();
+    assert_eq!(
+        rx.wait_for(Wait::Read, Duration::from_millis(1)).unwrap_err().kind(),
+        io::ErrorKind::TimedOut
+    );
+    tx.wait_until(Wait::Write, Instant::now()).unwrap();
+    tx.send_to(b"hello", 0, &rx.local_endpoint().unwrap()).unwrap();
+    rx.wait(Wait::Read).unwrap();
+    let mut buf = [0; 16];
+    assert_eq!(rx.nonblocking_receive(&mut buf, 0).unwrap(), 5);
+}
@@ -0,0 +1,60 @@
+extern crate asyncio;
+use std::io;
+use std::sync::Arc;
+use std::collections::HashMap;
+use std::time::Duration;
+use asyncio::*;
+
+static mut GOAL_COUNT: usize = 0;
+
+struct Pending {
+    timer: SteadyTimer,
+    arc: Arc<SteadyTimer>,
+    map: HashMap<u32, BoxHandler<(), io::Error>>,
+}
+
+impl Pending {
+    fn on_start(mut pd: Strand<Self>) {
+        let handler = BoxHandler::new(pd.wrap(Self::on_strand_wait));
+        let arc = BoxHandler::new(wrap(&pd.arc, on_arc_wait));
+        pd.map.insert(1, arc);
+        pd.map.insert(2, handler);
+        let handler = pd.map.remove(&2).unwrap();
+        pd.timer.expires_from_now(Duration::new(0, 1000000));
+        pd.timer.async_wait(handler);
+    }
+
+    fn on_strand_wait(mut pd: Strand<Self>, res: io::Result<()>) {
+        res.unwrap();
+        unsafe {
+            GOAL_COUNT += 1;
+        }
+        let handler = pd.map.remove(&1).unwrap();
+        assert!(pd.map.is_empty());
+        pd.arc.expires_from_now(Duration::new(0, 1000000));
+        pd.arc.async_wait(handler);
+    }
+}
+
+fn on_arc_wait(_: Arc<SteadyTimer>, res: io::Result<()>) {
+    res.unwrap();
+    unsafe {
+        assert_eq!(GOAL_COUNT, 1);
+        GOAL_COUNT += 1;
+    }
+}
+
+#[test]
+fn main() {
+    let ctx = &IoContext::new().unwrap();
+    Strand::new(
+        ctx,
+        Pending {
+            timer: SteadyTimer::new(ctx),
+            arc: Arc::new(SteadyTimer::new(ctx)),
+            map: HashMap::new(),
+        },
+    ).dispatch(Pending::on_start);
+    ctx.run();
+    assert_eq!(unsafe { GOAL_COUNT }, 2);
+}
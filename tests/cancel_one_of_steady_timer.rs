@@ -0,0 +1,42 @@
+extern crate asyncio;
+use std::io;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use asyncio::*;
+
+static CANCELED: AtomicUsize = AtomicUsize::new(0);
+static EXPIRED: AtomicUsize = AtomicUsize::new(0);
+
+fn on_wait(_: Arc<SteadyTimer>, res: io::Result<()>) {
+    if let Ok(_) = res {
+        EXPIRED.fetch_add(1, Ordering::SeqCst);
+    } else {
+        CANCELED.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+fn on_cancel(timer: Arc<SteadyTimer>, _: io::Result<()>) {
+    assert_eq!(timer.cancel_one(), 1);
+    assert_eq!(timer.cancel_one(), 1);
+    assert_eq!(CANCELED.load(Ordering::SeqCst), 2);
+    timer.expires_from_now(Duration::new(0, 1000000));
+    timer.async_wait(wrap(&timer, on_wait));
+    timer.async_wait(wrap(&timer, on_wait));
+}
+
+#[test]
+fn main() {
+    let ctx = &IoContext::new().unwrap();
+    let timer = Arc::new(SteadyTimer::new(ctx));
+    let other = Arc::new(SteadyTimer::new(ctx));
+    timer.expires_from_now(Duration::new(60, 0));
+    for _ in 0..4 {
+        timer.async_wait(wrap(&timer, on_wait));
+    }
+    other.expires_from_now(Duration::new(0, 1000000));
+    other.async_wait(wrap(&timer, on_cancel));
+    ctx.run();
+    assert_eq!(CANCELED.load(Ordering::SeqCst), 4);
+    assert_eq!(EXPIRED.load(Ordering::SeqCst), 2);
+}
@@ -0,0 +1,84 @@
+extern crate asyncio;
+use std::io;
+use std::thread;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use asyncio::*;
+use asyncio::ip::*;
+
+static FLUSHED: AtomicUsize = AtomicUsize::new(0);
+static TIMED_OUT: AtomicUsize = AtomicUsize::new(0);
+
+fn on_drain(_: Arc<TcpListener>, res: io::Result<usize>) {
+    match res {
+        Ok(len) => FLUSHED.fetch_add(len, Ordering::SeqCst),
+        Err(ref err) if err.kind() == io::ErrorKind::TimedOut => {
+            TIMED_OUT.fetch_add(1, Ordering::SeqCst)
+        }
+        Err(err) => panic!("{}", err),
+    };
+}
+
+fn connect(acc: &TcpListener) -> (TcpSocket, TcpSocket) {
+    let ctx = acc.as_ctx();
+    let cl = TcpSocket::new(ctx, Tcp::v4()).unwrap();
+    cl.connect(&acc.local_endpoint().unwrap()).unwrap();
+    let (sv, _) = acc.accept().unwrap();
+    (sv, cl)
+}
+
+fn read_to_end(cl: &TcpSocket) -> io::Result<Vec<u8>> {
+    let mut vec = Vec::new();
+    let mut buf = [0; 4096];
+    loop {
+        match cl.read_some(&mut buf) {
+            Ok(len) => vec.extend_from_slice(&buf[..len]),
+            Err(ref err) if err.kind() == io::ErrorKind::ConnectionAborted => return Ok(vec),
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+#[test]
+fn main() {
+    let ctx = &IoContext::new().unwrap();
+    let acc = Arc::new(TcpListener::new(ctx, Tcp::v4()).unwrap());
+    acc.bind(&TcpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+    acc.listen().unwrap();
+
+    // flushes the data larger than the socket buffer, and completes when the peer closes.
+    let (sv, cl) = connect(&acc);
+    let data: Vec<u8> = (0..4 * 1024 * 1024).map(|i| i as u8).collect();
+    let deadline = Instant::now() + Duration::from_secs(10);
+    sv.async_drain_and_close(StreamBuf::from(data.clone()), deadline, wrap(&acc, on_drain));
+    let thrd = thread::spawn(move || {
+        assert_eq!(read_to_end(&cl).unwrap(), data);
+        cl.write_some(b"ignored").unwrap();
+    });
+    ctx.run();
+    thrd.join().unwrap();
+    assert_eq!(FLUSHED.load(Ordering::SeqCst), 4 * 1024 * 1024);
+
+    // completes with the flushed bytes of zero if nothing is queued.
+    ctx.restart();
+    let (sv, cl) = connect(&acc);
+    let deadline = Instant::now() + Duration::from_secs(10);
+    sv.async_drain_and_close(StreamBuf::new(), deadline, wrap(&acc, on_drain));
+    let thrd = thread::spawn(move || assert_eq!(read_to_end(&cl).unwrap(), b""));
+    ctx.run();
+    thrd.join().unwrap();
+    assert_eq!(FLUSHED.load(Ordering::SeqCst), 4 * 1024 * 1024);
+    assert_eq!(TIMED_OUT.load(Ordering::SeqCst), 0);
+
+    // completes with the timed out if the peer does not close by the deadline.
+    ctx.restart();
+    let (sv, cl) = connect(&acc);
+    let deadline = Instant::now() + Duration::from_millis(50);
+    sv.async_drain_and_close(StreamBuf::from("hello"), deadline, wrap(&acc, on_drain));
+    ctx.run();
+    assert!(Instant::now() >= deadline);
+    assert_eq!(TIMED_OUT.load(Ordering::SeqCst), 1);
+    ctx.restart();
+    assert_eq!(read_to_end(&cl).unwrap(), b"hello");
+}
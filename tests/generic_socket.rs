@@ -0,0 +1,134 @@
+extern crate asyncio;
+use std::io;
+use std::fs;
+use std::sync::Arc;
+use asyncio::*;
+use asyncio::ip::*;
+use asyncio::local::*;
+use asyncio::generic::*;
+use asyncio::socket_base::*;
+
+const MESSAGE: &'static [u8] = b"hello generic";
+
+static mut GOAL_FLAG: usize = 0;
+
+#[test]
+fn main_stream_over_inet() {
+    let ctx = &IoContext::new().unwrap();
+    let ep = GenericStreamEndpoint::from_endpoint(&TcpEndpoint::new(IpAddrV4::loopback(), 0));
+    let pro = ep.protocol();
+
+    let acc = GenericSocketListener::new(ctx, pro).unwrap();
+    acc.set_option(ReuseAddr::new(true)).unwrap();
+    acc.bind(&ep).unwrap();
+    acc.listen().unwrap();
+    let ep = acc.local_endpoint().unwrap();
+    assert_eq!(ep.family(), TcpEndpoint::new(IpAddrV4::loopback(), 0).protocol().family_type());
+
+    let cl = GenericStreamSocket::new(ctx, pro).unwrap();
+    cl.connect(&ep).unwrap();
+    let (sv, _) = acc.accept().unwrap();
+    assert_eq!(cl.remote_endpoint().unwrap(), ep);
+    assert_eq!(sv.remote_endpoint().unwrap(), cl.local_endpoint().unwrap());
+
+    let mut buf = [0; 32];
+    assert_eq!(cl.write_some(MESSAGE).unwrap(), MESSAGE.len());
+    assert_eq!(sv.read_some(&mut buf).unwrap(), MESSAGE.len());
+    assert_eq!(&buf[..MESSAGE.len()], MESSAGE);
+    cl.shutdown(Shutdown::Write).unwrap();
+    println!("{:?}", cl);
+}
+
+fn on_accept(
+    _: Arc<GenericSeqPacketListener>,
+    res: io::Result<(GenericSeqPacketSocket, GenericSeqPacketEndpoint)>,
+) {
+    let (soc, _) = res.unwrap();
+    let mut buf = [0; 32];
+    let len = soc.receive(&mut buf, 0).unwrap();
+    assert_eq!(&buf[..len], MESSAGE);
+    unsafe {
+        GOAL_FLAG += 1;
+    }
+}
+
+fn on_connect(soc: Arc<GenericSeqPacketSocket>, res: io::Result<()>) {
+    res.unwrap();
+    soc.async_send(MESSAGE, 0, wrap(&soc, on_send));
+}
+
+fn on_send(_: Arc<GenericSeqPacketSocket>, res: io::Result<usize>) {
+    assert_eq!(res.unwrap(), MESSAGE.len());
+    unsafe {
+        GOAL_FLAG += 1;
+    }
+}
+
+#[test]
+fn main_seq_packet_over_local() {
+    let path = "/tmp/asyncio_generic_seq_packet.sock";
+    let _ = fs::remove_file(path);
+
+    let ctx = &IoContext::new().unwrap();
+    let ep = GenericSeqPacketEndpoint::from_endpoint(&LocalSeqPacketEndpoint::new(path).unwrap());
+    let pro = Endpoint::protocol(&ep);
+
+    let acc = Arc::new(GenericSeqPacketListener::new(ctx, pro).unwrap());
+    acc.bind(&ep).unwrap();
+    acc.listen().unwrap();
+    acc.async_accept(wrap(&acc, on_accept));
+
+    let cl = Arc::new(GenericSeqPacketSocket::new(ctx, pro).unwrap());
+    cl.async_connect(&ep, wrap(&cl, on_connect));
+    ctx.run();
+    let _ = fs::remove_file(path);
+    assert_eq!(unsafe { GOAL_FLAG } >= 2, true);
+}
+
+#[test]
+fn main_dgram_over_local() {
+    let path1 = "/tmp/asyncio_generic_dgram1.sock";
+    let path2 = "/tmp/asyncio_generic_dgram2.sock";
+    let _ = fs::remove_file(path1);
+    let _ = fs::remove_file(path2);
+
+    let ctx = &IoContext::new().unwrap();
+    let ep1 = GenericDgramEndpoint::from_endpoint(&LocalDgramEndpoint::new(path1).unwrap());
+    let ep2 = GenericDgramEndpoint::from_endpoint(&LocalDgramEndpoint::new(path2).unwrap());
+    let soc1 = GenericDgramSocket::new(ctx, ep1.protocol()).unwrap();
+    let soc2 = GenericDgramSocket::new(ctx, ep2.protocol()).unwrap();
+    soc1.bind(&ep1).unwrap();
+    soc2.bind(&ep2).unwrap();
+    assert_eq!(soc1.local_endpoint().unwrap(), ep1);
+
+    let mut buf = [0; 32];
+    assert_eq!(soc1.send_to(MESSAGE, 0, &ep2).unwrap(), MESSAGE.len());
+    let (len, ep) = soc2.receive_from(&mut buf, 0).unwrap();
+    assert_eq!(&buf[..len], MESSAGE);
+    assert_eq!(ep, ep1);
+
+    soc2.connect(&ep1).unwrap();
+    assert_eq!(soc2.remote_endpoint().unwrap(), ep1);
+    assert_eq!(soc2.send(MESSAGE, 0).unwrap(), MESSAGE.len());
+    assert_eq!(soc1.available().unwrap(), MESSAGE.len());
+    assert_eq!(soc1.receive(&mut buf, 0).unwrap(), MESSAGE.len());
+
+    let _ = fs::remove_file(path1);
+    let _ = fs::remove_file(path2);
+}
+
+#[test]
+fn main_raw_over_inet() {
+    let ctx = &IoContext::new().unwrap();
+    let ep = GenericRawEndpoint::from_endpoint(&IcmpEndpoint::new(IpAddrV4::loopback(), 0));
+    let pro = ep.protocol();
+    assert_eq!(pro.family_type(), Icmp::v4().family_type());
+    assert_eq!(pro.protocol_type(), Icmp::v4().protocol_type());
+    match GenericRawSocket::new(ctx, pro) {
+        Ok(soc) => {
+            soc.bind(&ep).unwrap();
+            assert_eq!(soc.local_endpoint().unwrap().family(), ep.family());
+        }
+        Err(err) => assert_eq!(err.kind(), io::ErrorKind::PermissionDenied),
+    }
+}
@@ -0,0 +1,78 @@
+#![cfg(any(target_os = "linux", target_os = "android"))]
+
+extern crate asyncio;
+use std::io;
+use asyncio::*;
+use asyncio::ip::*;
+
+static mut GOAL_FLAG: bool = false;
+
+struct Receiver {
+    soc: UdpSocket,
+    ep: UdpEndpoint,
+    buf: [u8; 16],
+}
+
+impl Receiver {
+    fn on_start(rx: Strand<Self>) {
+        rx.soc.async_send_to_hop_limit(
+            b"hello",
+            0,
+            &rx.ep,
+            255,
+            rx.wrap(Self::on_send),
+        );
+    }
+
+    fn on_send(rx: Strand<Self>, res: io::Result<usize>) {
+        assert_eq!(res.unwrap(), 5);
+        rx.soc.async_receive_from_hop_limit(
+            &mut rx.get().buf,
+            0,
+            rx.wrap(Self::on_receive),
+        );
+    }
+
+    fn on_receive(rx: Strand<Self>, res: io::Result<(usize, UdpEndpoint, Option<u8>)>) {
+        let (len, _, hops) = res.unwrap();
+        assert_eq!(len, 5);
+        assert_eq!(&rx.buf[..len], b"hello");
+        assert_eq!(hops, Some(255));
+        unsafe {
+            GOAL_FLAG = true;
+        }
+    }
+}
+
+#[test]
+fn main() {
+    let ctx = &IoContext::new().unwrap();
+
+    // the hop limit is not received unless enabled.
+    let soc = UdpSocket::new(ctx, Udp::v4()).unwrap();
+    soc.bind(&UdpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+    let ep = soc.local_endpoint().unwrap();
+    let mut buf = [0; 16];
+    soc.send_to_hop_limit(b"hello", 0, &ep, 7).unwrap();
+    assert_eq!(soc.receive_from_hop_limit(&mut buf, 0).unwrap().2, None);
+
+    soc.set_option(RecvHopLimit::new(true)).unwrap();
+    assert!(soc.get_option::<RecvHopLimit>().unwrap().get());
+    soc.send_to_hop_limit(b"hello", 0, &ep, 7).unwrap();
+    assert_eq!(soc.receive_from_hop_limit(&mut buf, 0).unwrap().2, Some(7));
+
+    let soc = UdpSocket::new(ctx, Udp::v6()).unwrap();
+    soc.set_option(RecvHopLimit::new(true)).unwrap();
+    soc.bind(&UdpEndpoint::new(IpAddrV6::loopback(), 0)).unwrap();
+    let ep = soc.local_endpoint().unwrap();
+    Strand::new(
+        ctx,
+        Receiver {
+            soc: soc,
+            ep: ep,
+            buf: [0; 16],
+        },
+    ).dispatch(Receiver::on_start);
+    ctx.run();
+    assert!(unsafe { GOAL_FLAG });
+}
@@ -0,0 +1,58 @@
+#![cfg(debug_assertions)]
+
+extern crate asyncio;
+use std::io;
+use asyncio::*;
+use asyncio::ip::*;
+
+static mut CANCELED: usize = 0;
+
+struct Receiver {
+    soc: UdpSocket,
+    bufs: [[u8; 16]; 3],
+}
+
+impl Receiver {
+    fn on_start(rx: Strand<Self>) {
+        for i in 0..3 {
+            rx.soc.async_receive(&mut rx.get().bufs[i], 0, rx.wrap(Self::on_receive));
+        }
+        rx.post(Self::on_check);
+    }
+
+    fn on_check(rx: Strand<Self>) {
+        let stats = rx.soc.op_stats();
+        assert_eq!(stats.live, 3);
+        assert_eq!(stats.total, 3);
+        assert_eq!(stats.live_bytes, stats.total_bytes);
+        assert!(stats.live_bytes > 0);
+        rx.soc.cancel();
+    }
+
+    fn on_receive(_: Strand<Self>, res: io::Result<usize>) {
+        assert!(res.is_err());
+        unsafe {
+            CANCELED += 1;
+        }
+    }
+}
+
+#[test]
+fn main() {
+    let ctx = &IoContext::new().unwrap();
+    let soc = UdpSocket::new(ctx, Udp::v4()).unwrap();
+    soc.bind(&UdpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+    assert_eq!(soc.op_stats(), OpStats::default());
+    let rx = Strand::new(
+        ctx,
+        Receiver {
+            soc: soc,
+            bufs: [[0; 16]; 3],
+        },
+    );
+    rx.dispatch(Receiver::on_start);
+    ctx.run();
+    assert_eq!(unsafe { CANCELED }, 3);
+    assert_eq!(rx.soc.op_stats().live, 0);
+    assert_eq!(rx.soc.op_stats().total, 3);
+}
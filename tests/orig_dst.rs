@@ -0,0 +1,56 @@
+#![cfg(any(target_os = "linux", target_os = "android"))]
+
+extern crate asyncio;
+use std::io;
+use asyncio::*;
+use asyncio::ip::*;
+
+static mut GOAL_FLAG: bool = false;
+
+struct Receiver {
+    soc: UdpSocket,
+    ep: UdpEndpoint,
+    buf: [u8; 16],
+}
+
+impl Receiver {
+    fn on_start(rx: Strand<Self>) {
+        rx.soc.async_receive_from_orig_dst(
+            &mut rx.get().buf,
+            0,
+            rx.wrap(Self::on_receive),
+        );
+    }
+
+    fn on_receive(rx: Strand<Self>, res: io::Result<(usize, UdpEndpoint, Option<UdpEndpoint>)>) {
+        let (len, _, dst) = res.unwrap();
+        assert_eq!(len, 5);
+        assert_eq!(&rx.buf[..len], b"hello");
+        assert_eq!(dst, Some(rx.ep.clone()));
+        unsafe {
+            GOAL_FLAG = true;
+        }
+    }
+}
+
+#[test]
+fn main() {
+    let ctx = &IoContext::new().unwrap();
+    let soc = UdpSocket::new(ctx, Udp::v6()).unwrap();
+    soc.set_option(RecvOrigDstAddr::new(true)).unwrap();
+    assert!(soc.get_option::<RecvOrigDstAddr>().unwrap().get());
+    soc.bind(&UdpEndpoint::new(IpAddrV6::any(), 0)).unwrap();
+    let ep = UdpEndpoint::new(IpAddrV6::loopback(), soc.local_endpoint().unwrap().port());
+
+    soc.send_to(b"hello", 0, &ep).unwrap();
+    Strand::new(
+        ctx,
+        Receiver {
+            soc: soc,
+            ep: ep,
+            buf: [0; 16],
+        },
+    ).dispatch(Receiver::on_start);
+    ctx.run();
+    assert!(unsafe { GOAL_FLAG });
+}
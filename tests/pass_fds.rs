@@ -0,0 +1,69 @@
+extern crate asyncio;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::fs::{self, File, OpenOptions};
+use std::os::unix::io::{AsRawFd, FromRawFd, RawFd};
+use asyncio::*;
+use asyncio::local::*;
+
+static mut GOAL_FLAG: bool = false;
+
+struct Receiver {
+    soc: LocalStreamSocket,
+    buf: [u8; 16],
+}
+
+impl Receiver {
+    fn on_start(rx: Strand<Self>) {
+        rx.soc.async_receive_fds(&mut rx.get().buf, 4, rx.wrap(Self::on_receive));
+    }
+
+    fn on_receive(rx: Strand<Self>, res: io::Result<(usize, Vec<RawFd>)>) {
+        let (len, fds) = res.unwrap();
+        assert_eq!(&rx.buf[..len], b"file");
+        assert_eq!(fds.len(), 1);
+        let mut file = unsafe { File::from_raw_fd(fds[0]) };
+        file.seek(SeekFrom::Start(0)).unwrap();
+        let mut data = String::new();
+        file.read_to_string(&mut data).unwrap();
+        assert_eq!(data, "hello");
+        unsafe {
+            GOAL_FLAG = true;
+        }
+    }
+}
+
+struct Sender {
+    soc: LocalStreamSocket,
+    file: File,
+}
+
+impl Sender {
+    fn on_start(tx: Strand<Self>) {
+        let fd = tx.file.as_raw_fd();
+        tx.soc.async_send_fds(b"file", &[fd], tx.wrap(Self::on_send));
+    }
+
+    fn on_send(_: Strand<Self>, res: io::Result<usize>) {
+        assert_eq!(res.unwrap(), 4);
+    }
+}
+
+#[test]
+fn main() {
+    let path = format!("/tmp/asyncio_pass_fds_{}", std::process::id());
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .open(&path)
+        .unwrap();
+    file.write_all(b"hello").unwrap();
+    fs::remove_file(&path).unwrap();
+
+    let ctx = &IoContext::new().unwrap();
+    let (tx, rx) = connect_pair(ctx, LocalStream).unwrap();
+    Strand::new(ctx, Sender { soc: tx, file: file }).dispatch(Sender::on_start);
+    Strand::new(ctx, Receiver { soc: rx, buf: [0; 16] }).dispatch(Receiver::on_start);
+    ctx.run();
+    assert!(unsafe { GOAL_FLAG });
+}
@@ -0,0 +1,69 @@
+extern crate asyncio;
+use std::io;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use asyncio::*;
+
+static mut GOAL_COUNT: usize = 0;
+
+struct Client {
+    pm: PendingMap<u32, String>,
+    timer: SteadyTimer,
+}
+
+unsafe impl AsIoContext for Client {
+    fn as_ctx(&self) -> &IoContext {
+        self.timer.as_ctx()
+    }
+}
+
+fn on_timeout(_: Arc<Client>, res: io::Result<String>) {
+    assert_eq!(res.unwrap_err().kind(), io::ErrorKind::TimedOut);
+    unsafe {
+        GOAL_COUNT += 1;
+    }
+}
+
+fn on_response(_: Arc<Client>, res: io::Result<String>) {
+    assert_eq!(res.unwrap(), "pong");
+    unsafe {
+        GOAL_COUNT += 1;
+    }
+}
+
+fn on_lost(_: Arc<Client>, res: io::Result<String>) {
+    assert!(res.is_err());
+    unsafe {
+        GOAL_COUNT += 1;
+    }
+}
+
+fn on_wait(cl: Arc<Client>, res: io::Result<()>) {
+    res.unwrap();
+    assert!(cl.pm.complete(&2, "pong".to_string()));
+    assert!(!cl.pm.complete(&2, "pong".to_string()));
+    assert!(!cl.pm.contains_key(&1));
+    assert!(cl.pm.contains_key(&3));
+    cl.pm.cancel_all();
+}
+
+#[test]
+fn main() {
+    let ctx = &IoContext::new().unwrap();
+    let cl = Arc::new(Client {
+        pm: PendingMap::new(ctx),
+        timer: SteadyTimer::new(ctx),
+    });
+    cl.pm.insert(1, Duration::from_millis(10), wrap(&cl, on_timeout));
+    cl.pm.insert(2, Duration::new(5, 0), wrap(&cl, on_response));
+    cl.pm.insert(3, Duration::new(5, 0), wrap(&cl, on_lost));
+    assert_eq!(cl.pm.len(), 3);
+
+    let now = Instant::now();
+    cl.timer.expires_from_now(Duration::from_millis(100));
+    cl.timer.async_wait(wrap(&cl, on_wait));
+    ctx.run();
+    assert!(now.elapsed() < Duration::new(1, 0));
+    assert!(cl.pm.is_empty());
+    assert_eq!(unsafe { GOAL_COUNT }, 3);
+}
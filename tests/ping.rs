@@ -0,0 +1,42 @@
+#![cfg(unix)]
+
+extern crate asyncio;
+use std::io;
+use std::time::Duration;
+use asyncio::*;
+use asyncio::ip::*;
+use asyncio::ip::icmp::{EchoReply, Pinger};
+
+static mut GOAL_FLAG: bool = false;
+
+fn on_ping(pinger: Strand<Pinger>, res: io::Result<EchoReply>) {
+    let reply = res.unwrap();
+    assert_eq!(reply.seq, 2);
+    assert!(reply.ttl.is_some());
+    assert!(reply.ep.addr().is_loopback());
+    pinger.cancel();
+    unsafe {
+        GOAL_FLAG = true;
+    }
+}
+
+#[test]
+fn main() {
+    let ctx = &IoContext::new().unwrap();
+    let soc = match IcmpSocket::new(ctx, Icmp::v4()) {
+        Ok(soc) => soc,
+        // the raw socket requires the privilege.
+        Err(_) => return,
+    };
+    let pinger = Pinger::new(soc);
+    let ep = IcmpEndpoint::new(IpAddrV4::loopback(), 0);
+    let reply = pinger.ping(&ep, 1, Duration::new(1, 0)).unwrap();
+    assert_eq!(reply.seq, 1);
+    assert_eq!(reply.len, 32);
+
+    Strand::new(ctx, pinger).dispatch(move |pinger| {
+        pinger.async_ping(&ep, 2, pinger.wrap(on_ping))
+    });
+    ctx.run();
+    assert!(unsafe { GOAL_FLAG });
+}
@@ -0,0 +1,80 @@
+#![cfg(unix)]
+
+extern crate asyncio;
+use std::io;
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::os::unix::io::IntoRawFd;
+use asyncio::*;
+use asyncio::posix::StreamDescriptor;
+
+static mut GOAL_FLAG: bool = false;
+
+struct Device {
+    dev: StreamDescriptor,
+    buf: [u8; 8192],
+}
+
+impl Device {
+    fn on_start(dev: Strand<Self>) {
+        for (i, c) in dev.get().buf.iter_mut().enumerate() {
+            *c = i as u8;
+        }
+        dev.dev.async_write_all_at(
+            100,
+            &dev.buf,
+            dev.wrap(Self::on_write),
+        );
+    }
+
+    fn on_write(dev: Strand<Self>, res: io::Result<usize>) {
+        assert_eq!(res.unwrap(), 8192);
+        dev.get().buf = [0; 8192];
+        dev.dev.async_read_exact_at(
+            100,
+            &mut dev.get().buf,
+            dev.wrap(Self::on_read),
+        );
+    }
+
+    fn on_read(dev: Strand<Self>, res: io::Result<usize>) {
+        assert_eq!(res.unwrap(), 8192);
+        for (i, &c) in dev.buf.iter().enumerate() {
+            assert_eq!(c, i as u8);
+        }
+        let mut head = [0xff; 100];
+        dev.dev.read_exact_at(0, &mut head).unwrap();
+        assert!(head.iter().all(|&c| c == 0));
+
+        // reading beyond the end of the file.
+        let mut buf = [0; 16];
+        assert!(dev.dev.read_exact_at(8290, &mut buf).is_err());
+        unsafe {
+            GOAL_FLAG = true;
+        }
+    }
+}
+
+#[test]
+fn main() {
+    let path = env::temp_dir().join("asyncio_random_access_test");
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&path)
+        .unwrap();
+    let ctx = &IoContext::new().unwrap();
+    let dev = unsafe { StreamDescriptor::from_raw_fd(ctx, file.into_raw_fd()) };
+    Strand::new(
+        ctx,
+        Device {
+            dev: dev,
+            buf: [0; 8192],
+        },
+    ).dispatch(Device::on_start);
+    ctx.run();
+    fs::remove_file(&path).unwrap();
+    assert!(unsafe { GOAL_FLAG });
+}
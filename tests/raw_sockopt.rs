@@ -0,0 +1,26 @@
+extern crate asyncio;
+extern crate libc;
+use asyncio::*;
+use asyncio::ip::*;
+use asyncio::socket_base::{Broadcast, ReuseAddr};
+
+#[test]
+fn main() {
+    let ctx = &IoContext::new().unwrap();
+
+    let acc = TcpListener::new(ctx, Tcp::v4()).unwrap();
+    unsafe { acc.raw_setsockopt(libc::SOL_SOCKET, libc::SO_REUSEADDR, &1i32.to_ne_bytes()) }.unwrap();
+    assert!(acc.get_option::<ReuseAddr>().unwrap().get());
+
+    let soc = TcpSocket::new(ctx, Tcp::v4()).unwrap();
+    soc.set_option(NoDelay::new(true)).unwrap();
+    let mut buf = [0; 4];
+    let len = unsafe { soc.raw_getsockopt(libc::IPPROTO_TCP, libc::TCP_NODELAY, &mut buf) }.unwrap();
+    assert_eq!(len, 4);
+    assert_ne!(i32::from_ne_bytes(buf), 0);
+
+    let soc = UdpSocket::new(ctx, Udp::v4()).unwrap();
+    unsafe { soc.raw_setsockopt(libc::SOL_SOCKET, libc::SO_BROADCAST, &1i32.to_ne_bytes()) }.unwrap();
+    assert!(soc.get_option::<Broadcast>().unwrap().get());
+    assert!(unsafe { soc.raw_getsockopt(libc::SOL_SOCKET, -1, &mut buf) }.is_err());
+}
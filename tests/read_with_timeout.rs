@@ -0,0 +1,51 @@
+extern crate asyncio;
+use std::io;
+use std::time::Duration;
+use asyncio::*;
+use asyncio::ip::*;
+
+static mut GOAL_FLAG: bool = false;
+
+struct TcpClient {
+    soc: TcpSocket,
+    buf: [u8; 16],
+}
+
+impl TcpClient {
+    fn on_start(cl: Strand<Self>) {
+        cl.soc.async_read_some_with_timeout(
+            &mut cl.get().buf,
+            Duration::from_millis(100),
+            cl.wrap(Self::on_read),
+        );
+    }
+
+    fn on_read(cl: Strand<Self>, res: io::Result<usize>) {
+        assert_eq!(res.unwrap_err().kind(), io::ErrorKind::TimedOut);
+        cl.soc.async_write_some_with_timeout(
+            b"hello",
+            Duration::from_millis(100),
+            cl.wrap(Self::on_write),
+        );
+    }
+
+    fn on_write(_: Strand<Self>, res: io::Result<usize>) {
+        assert_eq!(res.unwrap(), 5);
+        unsafe {
+            GOAL_FLAG = true;
+        }
+    }
+}
+
+#[test]
+fn main() {
+    let ctx = &IoContext::new().unwrap();
+    let acc = TcpListener::new(ctx, Tcp::v4()).unwrap();
+    acc.bind(&TcpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+    acc.listen().unwrap();
+    let soc = TcpSocket::new(ctx, Tcp::v4()).unwrap();
+    soc.connect(&acc.local_endpoint().unwrap()).unwrap();
+    Strand::new(ctx, TcpClient { soc: soc, buf: [0; 16] }).dispatch(TcpClient::on_start);
+    ctx.run();
+    assert!(unsafe { GOAL_FLAG });
+}
@@ -0,0 +1,32 @@
+extern crate asyncio;
+use std::io;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use asyncio::*;
+
+static EXPIRED: AtomicUsize = AtomicUsize::new(0);
+
+fn on_wait(_: Arc<SteadyTimer>, res: io::Result<()>) {
+    if let Err(err) = res {
+        panic!("{}", err);
+    }
+    EXPIRED.fetch_add(1, Ordering::SeqCst);
+}
+
+#[test]
+fn main() {
+    let ctx = &IoContext::new().unwrap();
+    let t1 = Arc::new(SteadyTimer::new(ctx));
+    let t2 = Arc::new(SteadyTimer::new(ctx));
+
+    // the second timer expires on time after the first one is drained from the queue.
+    let now = Instant::now();
+    t1.expires_from_now(Duration::from_millis(50));
+    t1.async_wait(wrap(&t1, on_wait));
+    t2.expires_from_now(Duration::from_millis(100));
+    t2.async_wait(wrap(&t2, on_wait));
+    ctx.run();
+    assert_eq!(EXPIRED.load(Ordering::SeqCst), 2);
+    assert!(now.elapsed() < Duration::from_secs(5));
+}
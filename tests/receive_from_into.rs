@@ -0,0 +1,72 @@
+extern crate asyncio;
+use std::io;
+use asyncio::*;
+use asyncio::ip::*;
+
+static mut GOAL_COUNT: usize = 0;
+
+struct Receiver {
+    soc: UdpSocket,
+    buf: [u8; 16],
+    ep: UdpEndpoint,
+    peer: UdpEndpoint,
+}
+
+impl Receiver {
+    fn on_start(rx: Strand<Self>) {
+        let rx_ = rx.get();
+        rx.soc.async_receive_from_into(
+            &mut rx_.buf,
+            0,
+            &mut rx_.ep,
+            rx.wrap(Self::on_receive),
+        );
+    }
+
+    fn on_receive(rx: Strand<Self>, res: io::Result<usize>) {
+        assert_eq!(res.unwrap(), 5);
+        assert_eq!(&rx.buf[..5], b"hello");
+        assert_eq!(rx.ep, rx.peer);
+        unsafe {
+            GOAL_COUNT += 1;
+            if GOAL_COUNT < 3 {
+                rx.get().ep = UdpEndpoint::new(IpAddrV4::any(), 0);
+                Self::on_start(rx);
+            }
+        }
+    }
+}
+
+#[test]
+fn main() {
+    let ctx = &IoContext::new().unwrap();
+    let soc = UdpSocket::new(ctx, Udp::v4()).unwrap();
+    soc.bind(&UdpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+    let ep = soc.local_endpoint().unwrap();
+    let cli = UdpSocket::new(ctx, Udp::v4()).unwrap();
+    cli.bind(&UdpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+    let peer = cli.local_endpoint().unwrap();
+
+    cli.send_to(b"hello", 0, &ep).unwrap();
+    let mut buf = [0; 16];
+    let mut from = UdpEndpoint::new(IpAddrV4::any(), 0);
+    assert_eq!(soc.receive_from_into(&mut buf, 0, &mut from).unwrap(), 5);
+    assert_eq!(from, peer);
+    let err = soc.nonblocking_receive_from_into(&mut buf, 0, &mut from).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+
+    for _ in 0..3 {
+        cli.send_to(b"hello", 0, &ep).unwrap();
+    }
+    Strand::new(
+        ctx,
+        Receiver {
+            soc: soc,
+            buf: [0; 16],
+            ep: from,
+            peer: peer,
+        },
+    ).dispatch(Receiver::on_start);
+    ctx.run();
+    assert_eq!(unsafe { GOAL_COUNT }, 3);
+}
@@ -0,0 +1,21 @@
+extern crate asyncio;
+use asyncio::*;
+use asyncio::ip::*;
+use asyncio::socket_base::ReusePort;
+
+#[test]
+fn main() {
+    let ctx = &IoContext::new().unwrap();
+    let a = UdpSocket::new(ctx, Udp::v4()).unwrap();
+    a.set_option(ReusePort::new(true)).unwrap();
+    a.bind(&UdpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+    let ep = a.local_endpoint().unwrap();
+
+    let b = UdpSocket::new(ctx, Udp::v4()).unwrap();
+    b.set_option(ReusePort::new(true)).unwrap();
+    b.bind(&ep).unwrap();
+    assert_eq!(b.local_endpoint().unwrap(), ep);
+
+    let c = UdpSocket::new(ctx, Udp::v4()).unwrap();
+    assert!(c.bind(&ep).is_err());
+}
@@ -0,0 +1,81 @@
+#![cfg(feature = "rudp")]
+
+extern crate asyncio;
+use std::io;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use asyncio::*;
+use asyncio::ip::*;
+use asyncio::rudp::RudpStream;
+
+const LEN: usize = 300000;
+
+static SENT: AtomicUsize = AtomicUsize::new(0);
+static RECEIVED: AtomicUsize = AtomicUsize::new(0);
+
+struct Sender {
+    soc: RudpStream<Udp>,
+    buf: Vec<u8>,
+}
+
+fn on_write(tx: Arc<Sender>, res: io::Result<usize>) {
+    let len = res.unwrap();
+    let len = SENT.fetch_add(len, Ordering::SeqCst) + len;
+    if len < LEN {
+        tx.soc.async_write_some(&tx.buf[len..], wrap(&tx, on_write));
+    } else {
+        tx.soc.shutdown().unwrap();
+    }
+}
+
+struct Receiver {
+    soc: RudpStream<Udp>,
+    buf: [u8; 4096],
+}
+
+fn on_read(rx: Arc<Receiver>, res: io::Result<usize>) {
+    let len = res.unwrap();
+    if len > 0 {
+        assert!(rx.buf[..len].iter().all(|&ch| ch == 7));
+        RECEIVED.fetch_add(len, Ordering::SeqCst);
+        rx.soc.async_read_some(&rx.buf, wrap(&rx, on_read));
+    }
+}
+
+unsafe impl AsIoContext for Receiver {
+    fn as_ctx(&self) -> &IoContext {
+        self.soc.as_ctx()
+    }
+}
+
+unsafe impl AsIoContext for Sender {
+    fn as_ctx(&self) -> &IoContext {
+        self.soc.as_ctx()
+    }
+}
+
+#[test]
+fn main() {
+    let ctx = &IoContext::new().unwrap();
+    let a = UdpSocket::new(ctx, Udp::v4()).unwrap();
+    let b = UdpSocket::new(ctx, Udp::v4()).unwrap();
+    a.bind(&UdpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+    b.bind(&UdpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+    a.connect(&b.local_endpoint().unwrap()).unwrap();
+    b.connect(&a.local_endpoint().unwrap()).unwrap();
+
+    let tx = Arc::new(Sender {
+        soc: RudpStream::new(a),
+        buf: vec![7; LEN],
+    });
+    let rx = Arc::new(Receiver {
+        soc: RudpStream::new(b),
+        buf: [0; 4096],
+    });
+    rx.soc.async_read_some(&rx.buf, wrap(&rx, on_read));
+    tx.soc.async_write_some(&tx.buf, wrap(&tx, on_write));
+    ctx.run();
+    assert_eq!(SENT.load(Ordering::SeqCst), LEN);
+    assert_eq!(RECEIVED.load(Ordering::SeqCst), LEN);
+    assert_eq!(tx.soc.in_flight(), 0);
+}
@@ -0,0 +1,67 @@
+extern crate asyncio;
+use std::io;
+use asyncio::*;
+use asyncio::ip::*;
+
+static mut GOAL_FLAG: bool = false;
+
+struct TcpServer {
+    soc: TcpSocket,
+    head: [u8; 5],
+    body: [u8; 16],
+}
+
+impl TcpServer {
+    fn on_start(sv: Strand<Self>) {
+        let sv_ = sv.get();
+        sv.soc.async_read_some_v(
+            &mut [&mut sv_.head[..], &mut sv_.body[..]],
+            sv.wrap(Self::on_read),
+        );
+    }
+
+    fn on_read(sv: Strand<Self>, res: io::Result<usize>) {
+        assert_eq!(res.unwrap(), 11);
+        assert_eq!(&sv.head, b"hello");
+        assert_eq!(&sv.body[..6], b" world");
+        unsafe {
+            GOAL_FLAG = true;
+        }
+    }
+}
+
+struct TcpClient {
+    soc: TcpSocket,
+}
+
+impl TcpClient {
+    fn on_start(cl: Strand<Self>) {
+        cl.soc.async_write_some_v(&[&b"hello"[..], &b" world"[..]], cl.wrap(Self::on_write));
+    }
+
+    fn on_write(_: Strand<Self>, res: io::Result<usize>) {
+        assert_eq!(res.unwrap(), 11);
+    }
+}
+
+#[test]
+fn main() {
+    let ctx = &IoContext::new().unwrap();
+    let acc = TcpListener::new(ctx, Tcp::v4()).unwrap();
+    acc.bind(&TcpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+    acc.listen().unwrap();
+    let soc = TcpSocket::new(ctx, Tcp::v4()).unwrap();
+    soc.connect(&acc.local_endpoint().unwrap()).unwrap();
+    let (acc_soc, _) = acc.accept().unwrap();
+    Strand::new(ctx, TcpClient { soc: soc }).dispatch(TcpClient::on_start);
+    Strand::new(
+        ctx,
+        TcpServer {
+            soc: acc_soc,
+            head: [0; 5],
+            body: [0; 16],
+        },
+    ).dispatch(TcpServer::on_start);
+    ctx.run();
+    assert!(unsafe { GOAL_FLAG });
+}
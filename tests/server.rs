@@ -0,0 +1,155 @@
+extern crate asyncio;
+use std::io;
+use std::thread;
+use std::time::{Duration, Instant};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use asyncio::*;
+use asyncio::ip::*;
+use asyncio::socket_base::*;
+
+const MESSAGE: &'static [u8] = b"hello server";
+
+static ACTIVE: AtomicUsize = AtomicUsize::new(0);
+
+static GOAL_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+struct EchoSession {
+    conn: Connection<Tcp>,
+    buf: [u8; 256],
+}
+
+impl EchoSession {
+    fn on_recv(sv: Strand<Self>, res: io::Result<usize>) {
+        let len = res.unwrap();
+        assert_eq!(&sv.buf[..len], MESSAGE);
+        sv.conn.async_write_some(&sv.buf[..len], sv.wrap(Self::on_send));
+    }
+
+    fn on_send(_: Strand<Self>, res: io::Result<usize>) {
+        assert_eq!(res.unwrap(), MESSAGE.len());
+        GOAL_COUNT.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+impl Drop for EchoSession {
+    fn drop(&mut self) {
+        ACTIVE.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+struct Echo;
+
+impl Service<Tcp> for Echo {
+    type Session = EchoSession;
+
+    fn new_session(&self, conn: Connection<Tcp>) -> Self::Session {
+        assert_eq!(ACTIVE.fetch_add(1, Ordering::SeqCst), 0);
+        EchoSession {
+            conn: conn,
+            buf: [0; 256],
+        }
+    }
+
+    fn on_start(&self, sv: Strand<Self::Session>) {
+        sv.conn.async_read_some(&mut sv.get().buf, sv.wrap(EchoSession::on_recv));
+    }
+
+    fn on_error(&self, err: io::Error) {
+        panic!("{:?}", err);
+    }
+}
+
+#[test]
+fn main() {
+    let ctx = &IoContext::new().unwrap();
+    let acc = TcpListener::new(ctx, Tcp::v4()).unwrap();
+    acc.set_option(ReuseAddr::new(true)).unwrap();
+    acc.bind(&TcpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+    acc.listen().unwrap();
+
+    let sv = Server::new(acc, Echo);
+    sv.set_max_connections(1);
+    assert_eq!(sv.max_connections(), 1);
+    assert!(sv.is_stopped());
+    sv.start();
+    assert!(!sv.is_stopped());
+
+    let ep = sv.local_endpoint().unwrap();
+    let cl = sv.clone();
+    let th = thread::spawn(move || {
+        let ctx = &IoContext::new().unwrap();
+        let socs: Vec<_> = (0..2)
+            .map(|_| {
+                let soc = TcpSocket::new(ctx, Tcp::v4()).unwrap();
+                soc.connect(&ep).unwrap();
+                assert_eq!(soc.write_some(MESSAGE).unwrap(), MESSAGE.len());
+                soc
+            })
+            .collect();
+        for soc in &socs {
+            let mut buf = [0; 256];
+            assert_eq!(soc.read_some(&mut buf).unwrap(), MESSAGE.len());
+            assert_eq!(&buf[..MESSAGE.len()], MESSAGE);
+        }
+        cl.shutdown();
+    });
+
+    ctx.run();
+    th.join().unwrap();
+    assert!(sv.is_stopped());
+    assert_eq!(sv.connections(), 0);
+    assert_eq!(GOAL_COUNT.load(Ordering::SeqCst), 2);
+}
+
+static IDLE_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+struct Idle;
+
+impl Service<Tcp> for Idle {
+    type Session = (Connection<Tcp>, [u8; 256]);
+
+    fn new_session(&self, conn: Connection<Tcp>) -> Self::Session {
+        (conn, [0; 256])
+    }
+
+    fn on_start(&self, sv: Strand<Self::Session>) {
+        sv.0.async_read_some(&mut sv.get().1, sv.wrap(Self::on_recv));
+    }
+}
+
+impl Idle {
+    fn on_recv(_: Strand<(Connection<Tcp>, [u8; 256])>, res: io::Result<usize>) {
+        assert!(res.is_err());
+        IDLE_COUNT.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn main_idle_timeout() {
+    let ctx = &IoContext::new().unwrap();
+    let acc = TcpListener::new(ctx, Tcp::v4()).unwrap();
+    acc.bind(&TcpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+    acc.listen().unwrap();
+
+    let sv = Server::new(acc, Idle);
+    sv.set_idle_timeout(Duration::from_millis(100));
+    assert_eq!(sv.idle_timeout(), Duration::from_millis(100));
+    sv.start();
+
+    let ep = sv.local_endpoint().unwrap();
+    let cl = sv.clone();
+    let now = Instant::now();
+    let th = thread::spawn(move || {
+        let ctx = &IoContext::new().unwrap();
+        let soc = TcpSocket::new(ctx, Tcp::v4()).unwrap();
+        soc.connect(&ep).unwrap();
+        let mut buf = [0; 256];
+        assert!(soc.read_some(&mut buf).is_err());
+        cl.shutdown();
+    });
+
+    ctx.run();
+    th.join().unwrap();
+    assert!(now.elapsed() >= Duration::from_millis(100));
+    assert_eq!(IDLE_COUNT.load(Ordering::SeqCst), 1);
+}
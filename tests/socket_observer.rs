@@ -0,0 +1,96 @@
+extern crate asyncio;
+use std::io;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::os::unix::io::RawFd;
+use asyncio::*;
+use asyncio::ip::*;
+
+static OPEN_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+static CONNECT_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+static ACCEPT_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+static CLOSE_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+static ERROR_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+struct Counter;
+
+impl SocketObserver for Counter {
+    fn on_open(&self, _: RawFd) {
+        OPEN_COUNT.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn on_connect(&self, _: RawFd, ep: EndpointInfo) {
+        assert_eq!(ep.to_string().starts_with("127.0.0.1:"), true);
+        CONNECT_COUNT.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn on_accept(&self, _: RawFd, _: RawFd, ep: EndpointInfo) {
+        assert_eq!(ep.family(), Tcp::v4().family_type());
+        ACCEPT_COUNT.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn on_close(&self, _: RawFd) {
+        CLOSE_COUNT.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn on_error(&self, _: RawFd, err: &io::Error) {
+        assert_eq!(err.kind(), io::ErrorKind::ConnectionRefused);
+        ERROR_COUNT.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+fn on_accept(_: Arc<TcpListener>, res: io::Result<(TcpSocket, TcpEndpoint)>) {
+    res.unwrap();
+}
+
+fn on_connect(_: Arc<TcpSocket>, res: io::Result<()>) {
+    res.unwrap();
+}
+
+fn on_refused(_: Arc<TcpSocket>, res: io::Result<()>) {
+    assert!(res.is_err());
+}
+
+#[test]
+fn main() {
+    let ctx = &IoContext::new().unwrap();
+    let ep = {
+        let acc = TcpListener::new(ctx, Tcp::v4()).unwrap();
+        acc.bind(&TcpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+        acc.local_endpoint().unwrap()
+    };
+    ctx.set_socket_observer(Counter);
+    {
+        let acc = Arc::new(TcpListener::new(ctx, Tcp::v4()).unwrap());
+        acc.bind(&TcpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+        acc.listen().unwrap();
+        let sv = acc.local_endpoint().unwrap();
+
+        let cl1 = TcpSocket::new(ctx, Tcp::v4()).unwrap();
+        cl1.connect(&sv).unwrap();
+        let _ = acc.accept().unwrap();
+
+        let cl2 = Arc::new(TcpSocket::new(ctx, Tcp::v4()).unwrap());
+        cl2.async_connect(&sv, wrap(&cl2, on_connect));
+        acc.async_accept(wrap(&acc, on_accept));
+        ctx.run();
+
+        ctx.restart();
+        let cl3 = Arc::new(TcpSocket::new(ctx, Tcp::v4()).unwrap());
+        cl3.async_connect(&ep, wrap(&cl3, on_refused));
+        ctx.run();
+    }
+    assert_eq!(OPEN_COUNT.load(Ordering::SeqCst), 6);
+    assert_eq!(CONNECT_COUNT.load(Ordering::SeqCst), 2);
+    assert_eq!(ACCEPT_COUNT.load(Ordering::SeqCst), 2);
+    assert_eq!(CLOSE_COUNT.load(Ordering::SeqCst), 6);
+    assert_eq!(ERROR_COUNT.load(Ordering::SeqCst), 1);
+
+    ctx.remove_socket_observer();
+    let _ = TcpSocket::new(ctx, Tcp::v4()).unwrap();
+    assert_eq!(OPEN_COUNT.load(Ordering::SeqCst), 6);
+}
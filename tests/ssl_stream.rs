@@ -0,0 +1,298 @@
+#![cfg(feature = "ssl")]
+
+extern crate asyncio;
+extern crate libc;
+extern crate openssl;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use asyncio::*;
+use asyncio::ip::*;
+use asyncio::ssl::*;
+use openssl::asn1::Asn1Time;
+use openssl::ec::{EcGroup, EcKey};
+use openssl::hash::MessageDigest;
+use openssl::nid::Nid;
+use openssl::pkey::PKey;
+use openssl::x509::{X509, X509NameBuilder};
+
+static mut GOAL_FLAG: bool = false;
+
+static VERIFIED: AtomicUsize = AtomicUsize::new(0);
+
+fn self_signed() -> (Vec<u8>, Vec<u8>) {
+    let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+    let key = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+    let mut name = X509NameBuilder::new().unwrap();
+    name.append_entry_by_nid(Nid::COMMONNAME, "localhost").unwrap();
+    let name = name.build();
+    let mut cert = X509::builder().unwrap();
+    cert.set_version(2).unwrap();
+    cert.set_subject_name(&name).unwrap();
+    cert.set_issuer_name(&name).unwrap();
+    cert.set_pubkey(&key).unwrap();
+    cert.set_not_before(&Asn1Time::days_from_now(0).unwrap()).unwrap();
+    cert.set_not_after(&Asn1Time::days_from_now(1).unwrap()).unwrap();
+    cert.sign(&key, MessageDigest::sha256()).unwrap();
+    (cert.build().to_pem().unwrap(), key.private_key_to_pem_pkcs8().unwrap())
+}
+
+struct TcpServer {
+    ssl: SslStream<TcpSocket>,
+    buf: [u8; 16],
+}
+
+impl TcpServer {
+    fn on_start(sv: Strand<Self>) {
+        sv.ssl.async_handshake(Handshake::Server, sv.wrap(Self::on_handshake));
+    }
+
+    fn on_handshake(sv: Strand<Self>, res: Result<()>) {
+        res.unwrap();
+        sv.ssl.async_read_some(&mut sv.get().buf, sv.wrap(Self::on_read));
+    }
+
+    fn on_read(sv: Strand<Self>, res: Result<usize>) {
+        let len = res.unwrap();
+        assert_eq!(&sv.buf[..len], b"hello");
+        sv.ssl.async_write_some(&sv.buf[..len], sv.wrap(Self::on_write));
+    }
+
+    fn on_write(sv: Strand<Self>, res: Result<usize>) {
+        assert_eq!(res.unwrap(), 5);
+        sv.ssl.async_shutdown(sv.wrap(Self::on_shutdown));
+    }
+
+    fn on_shutdown(_: Strand<Self>, res: Result<()>) {
+        res.unwrap();
+    }
+}
+
+struct TcpClient {
+    ssl: SslStream<TcpSocket>,
+    buf: [u8; 16],
+}
+
+impl TcpClient {
+    fn on_start(cl: Strand<Self>) {
+        cl.ssl.async_handshake(Handshake::Client, cl.wrap(Self::on_handshake));
+    }
+
+    fn on_handshake(cl: Strand<Self>, res: Result<()>) {
+        res.unwrap();
+        // the reading is queued until the writing is completed.
+        cl.ssl.async_write_some(b"hello", cl.wrap(Self::on_write));
+        cl.ssl.async_read_some(&mut cl.get().buf, cl.wrap(Self::on_read));
+    }
+
+    fn on_write(_: Strand<Self>, res: Result<usize>) {
+        assert_eq!(res.unwrap(), 5);
+    }
+
+    fn on_read(cl: Strand<Self>, res: Result<usize>) {
+        let len = res.unwrap();
+        assert_eq!(&cl.buf[..len], b"hello");
+        cl.ssl.async_shutdown(cl.wrap(Self::on_shutdown));
+    }
+
+    fn on_shutdown(_: Strand<Self>, res: Result<()>) {
+        res.unwrap();
+        unsafe {
+            GOAL_FLAG = true;
+        }
+    }
+}
+
+#[test]
+fn main() {
+    let (cert, key) = self_signed();
+    let mut sv_ctx = SslContext::sslv23().unwrap();
+    sv_ctx.use_certificate(&cert, FileFormat::PEM).unwrap();
+    sv_ctx.use_private_key(&key, FileFormat::PEM).unwrap();
+    let cl_ctx = SslContext::sslv23().unwrap();
+
+    let ctx = &IoContext::new().unwrap();
+    let acc = TcpListener::new(ctx, Tcp::v4()).unwrap();
+    acc.bind(&TcpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+    acc.listen().unwrap();
+    let soc = TcpSocket::new(ctx, Tcp::v4()).unwrap();
+    soc.connect(&acc.local_endpoint().unwrap()).unwrap();
+    let (acc_soc, _) = acc.accept().unwrap();
+
+    let mut ssl = SslStream::new(soc, &cl_ctx).unwrap();
+    ssl.set_verify_mode(SslVerifyMode::PEER).unwrap();
+    ssl.set_verify_callback(|ok, ctx| {
+        // the self-signed certificate is not verified by the OpenSSL, but accepts it.
+        if !ok && ctx.error_depth() == 0 {
+            VERIFIED.fetch_add(1, Ordering::SeqCst);
+        }
+        true
+    }).unwrap();
+    Strand::new(ctx, TcpClient { ssl: ssl, buf: [0; 16] }).dispatch(TcpClient::on_start);
+    Strand::new(
+        ctx,
+        TcpServer {
+            ssl: SslStream::new(acc_soc, &sv_ctx).unwrap(),
+            buf: [0; 16],
+        },
+    ).dispatch(TcpServer::on_start);
+
+    ctx.run();
+    assert!(VERIFIED.load(Ordering::SeqCst) > 0);
+    assert!(unsafe { GOAL_FLAG });
+}
+
+#[test]
+fn blocking_handshake() {
+    use std::io;
+    use std::sync::Arc;
+    use std::thread;
+
+    fn on_handshake(_: Arc<SslStream<TcpSocket>>, res: Result<()>) {
+        res.unwrap();
+    }
+
+    let (cert, key) = self_signed();
+    let mut sv_ctx = SslContext::sslv23().unwrap();
+    sv_ctx.use_certificate(&cert, FileFormat::PEM).unwrap();
+    sv_ctx.use_private_key(&key, FileFormat::PEM).unwrap();
+
+    let ctx = &IoContext::new().unwrap();
+    let acc = TcpListener::new(ctx, Tcp::v4()).unwrap();
+    acc.bind(&TcpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+    acc.listen().unwrap();
+    let ep = acc.local_endpoint().unwrap();
+
+    let soc = TcpSocket::new(ctx, Tcp::v4()).unwrap();
+    soc.connect(&ep).unwrap();
+    let (acc_soc, _) = acc.accept().unwrap();
+    let sv = Arc::new(SslStream::new(acc_soc, &sv_ctx).unwrap());
+    sv.async_handshake(Handshake::Server, wrap(&sv, on_handshake));
+    let thrd = thread::spawn(move || {
+        let mut ssl = SslStream::new(soc, &SslContext::sslv23().unwrap()).unwrap();
+        ssl.handshake(Handshake::Client).unwrap();
+    });
+    ctx.run();
+    thrd.join().unwrap();
+
+    // the self-signed certificate is not verified by the connect_uri.
+    ctx.restart();
+    let thrd = thread::spawn(move || {
+        let (soc, _) = acc.accept().unwrap();
+        let mut ssl = SslStream::new(soc, &sv_ctx).unwrap();
+        assert!(ssl.handshake(Handshake::Server).is_err());
+    });
+    let uri = format!("tls://localhost:{}", ep.port());
+    let err = connect_uri(ctx, &uri).unwrap_err();
+    assert!(err.kind() != io::ErrorKind::Unsupported);
+    thrd.join().unwrap();
+}
+
+struct OrderServer {
+    ssl: SslStream<TcpSocket>,
+}
+
+impl OrderServer {
+    fn on_start(sv: Strand<Self>) {
+        sv.ssl.async_handshake(Handshake::Server, sv.wrap(Self::on_handshake));
+    }
+
+    fn on_handshake(sv: Strand<Self>, res: Result<()>) {
+        res.unwrap();
+        sv.ssl.async_write_some(b"hello", sv.wrap(Self::on_write));
+    }
+
+    fn on_write(_: Strand<Self>, res: Result<usize>) {
+        assert_eq!(res.unwrap(), 5);
+    }
+}
+
+struct OrderClient {
+    ssl: SslStream<TcpSocket>,
+    buf: [u8; 5],
+    log: Vec<usize>,
+}
+
+impl OrderClient {
+    fn on_start(cl: Strand<Self>) {
+        cl.ssl.async_handshake(Handshake::Client, cl.wrap(Self::on_handshake));
+    }
+
+    fn on_handshake(cl: Strand<Self>, res: Result<()>) {
+        res.unwrap();
+        // the second reading is completed from the record decrypted by the first one.
+        cl.ssl.async_read_some(&mut cl.get().buf[..2], cl.wrap(Self::on_read));
+        cl.ssl.async_read_some(&mut cl.get().buf[2..], cl.wrap(Self::on_read));
+    }
+
+    fn on_read(cl: Strand<Self>, res: Result<usize>) {
+        cl.get().log.push(res.unwrap());
+        if cl.log.len() == 2 {
+            assert_eq!(&cl.buf, b"hello");
+            unsafe {
+                ORDERED = cl.log == [2, 3];
+            }
+        }
+    }
+}
+
+static mut ORDERED: bool = false;
+
+#[test]
+fn completion_order() {
+    let (cert, key) = self_signed();
+    let mut sv_ctx = SslContext::sslv23().unwrap();
+    sv_ctx.use_certificate(&cert, FileFormat::PEM).unwrap();
+    sv_ctx.use_private_key(&key, FileFormat::PEM).unwrap();
+
+    let ctx = &IoContext::new().unwrap();
+    let acc = TcpListener::new(ctx, Tcp::v4()).unwrap();
+    acc.bind(&TcpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+    acc.listen().unwrap();
+    let soc = TcpSocket::new(ctx, Tcp::v4()).unwrap();
+    soc.connect(&acc.local_endpoint().unwrap()).unwrap();
+    let (acc_soc, _) = acc.accept().unwrap();
+
+    let ssl = SslStream::new(soc, &SslContext::sslv23().unwrap()).unwrap();
+    Strand::new(ctx, OrderClient { ssl: ssl, buf: [0; 5], log: Vec::new() })
+        .dispatch(OrderClient::on_start);
+    Strand::new(ctx, OrderServer { ssl: SslStream::new(acc_soc, &sv_ctx).unwrap() })
+        .dispatch(OrderServer::on_start);
+    ctx.run();
+    assert!(unsafe { ORDERED });
+}
+
+#[test]
+fn cancel_queued() {
+    use std::io;
+    use std::sync::Arc;
+
+    static CANCELED: AtomicUsize = AtomicUsize::new(0);
+
+    fn on_handshake(_: Arc<SslStream<TcpSocket>>, res: Result<()>) {
+        let err = io::Error::from(res.unwrap_err());
+        assert_eq!(err.raw_os_error(), Some(libc::ECANCELED));
+        CANCELED.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn on_read(_: Arc<SslStream<TcpSocket>>, res: Result<usize>) {
+        let err = io::Error::from(res.unwrap_err());
+        assert_eq!(err.raw_os_error(), Some(libc::ECANCELED));
+        CANCELED.fetch_add(1, Ordering::SeqCst);
+    }
+
+    let ctx = &IoContext::new().unwrap();
+    let acc = TcpListener::new(ctx, Tcp::v4()).unwrap();
+    acc.bind(&TcpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+    acc.listen().unwrap();
+    let soc = TcpSocket::new(ctx, Tcp::v4()).unwrap();
+    soc.connect(&acc.local_endpoint().unwrap()).unwrap();
+    let (_acc_soc, _) = acc.accept().unwrap();
+
+    // the handshake waits for the peer, and the reading is queued behind it.
+    let ssl = Arc::new(SslStream::new(soc, &SslContext::sslv23().unwrap()).unwrap());
+    let mut buf = [0; 16];
+    ssl.async_handshake(Handshake::Client, wrap(&ssl, on_handshake));
+    ssl.async_read_some(&mut buf, wrap(&ssl, on_read));
+    ssl.cancel();
+    ctx.run();
+    assert_eq!(CANCELED.load(Ordering::SeqCst), 2);
+}
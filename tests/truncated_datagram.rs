@@ -0,0 +1,68 @@
+extern crate asyncio;
+use std::io;
+use asyncio::*;
+use asyncio::ip::*;
+
+static mut GOAL_FLAG: bool = false;
+
+struct Receiver {
+    soc: UdpSocket,
+    buf: Vec<u8>,
+}
+
+impl Receiver {
+    fn on_start(rx: Strand<Self>) {
+        rx.soc.async_receive_from_vec(
+            &mut rx.get().buf,
+            65536,
+            0,
+            rx.wrap(Self::on_receive),
+        );
+    }
+
+    fn on_receive(rx: Strand<Self>, res: io::Result<(usize, UdpEndpoint)>) {
+        let (len, _) = res.unwrap();
+        assert_eq!(len, 5000);
+        assert_eq!(rx.buf.len(), 5000);
+        assert!(rx.buf.iter().all(|&ch| ch == 7));
+        unsafe {
+            GOAL_FLAG = true;
+        }
+    }
+}
+
+#[test]
+fn main() {
+    let ctx = &IoContext::new().unwrap();
+    let soc = UdpSocket::new(ctx, Udp::v4()).unwrap();
+    soc.bind(&UdpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+    let ep = soc.local_endpoint().unwrap();
+
+    soc.send_to(&[1; 100], 0, &ep).unwrap();
+    let mut buf = [0; 10];
+    let (len, _, trunc) = soc.receive_from_trunc(&mut buf, 0).unwrap();
+    assert_eq!(len, 10);
+    assert!(trunc);
+
+    soc.send_to(&[1; 10], 0, &ep).unwrap();
+    let (len, _, trunc) = soc.receive_from_trunc(&mut buf, 0).unwrap();
+    assert_eq!(len, 10);
+    assert!(!trunc);
+
+    soc.send_to(&[1; 2000], 0, &ep).unwrap();
+    let mut vec = Vec::new();
+    let err = soc.receive_from_vec(&mut vec, 1000, 0).unwrap_err();
+    assert_eq!(err.raw_os_error(), Some(90)); // EMSGSIZE
+    assert!(vec.is_empty());
+
+    soc.send_to(&[7; 5000], 0, &ep).unwrap();
+    Strand::new(
+        ctx,
+        Receiver {
+            soc: soc,
+            buf: Vec::with_capacity(16),
+        },
+    ).dispatch(Receiver::on_start);
+    ctx.run();
+    assert!(unsafe { GOAL_FLAG });
+}
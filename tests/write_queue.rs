@@ -0,0 +1,63 @@
+extern crate asyncio;
+use std::io;
+use std::thread;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use asyncio::*;
+use asyncio::ip::*;
+
+const BULK_LEN: usize = 1024 * 1024;
+
+const PING: &'static [u8] = b"ping";
+
+static GOAL_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+fn on_bulk(_: Arc<WriteQueue<TcpSocket>>, res: io::Result<usize>) {
+    assert_eq!(res.unwrap(), BULK_LEN);
+    GOAL_COUNT.fetch_add(1, Ordering::SeqCst);
+}
+
+fn on_ping(wq: Arc<WriteQueue<TcpSocket>>, res: io::Result<usize>) {
+    assert_eq!(res.unwrap(), PING.len());
+    assert_eq!(wq.pending(), 2);
+    GOAL_COUNT.fetch_add(1, Ordering::SeqCst);
+}
+
+#[test]
+fn main() {
+    let ctx = &IoContext::new().unwrap();
+    let acc = TcpListener::new(&IoContext::new().unwrap(), Tcp::v4()).unwrap();
+    acc.bind(&TcpEndpoint::new(IpAddrV4::loopback(), 0)).unwrap();
+    acc.listen().unwrap();
+
+    let soc = TcpSocket::new(ctx, Tcp::v4()).unwrap();
+    soc.connect(&acc.local_endpoint().unwrap()).unwrap();
+    let (sv, _) = acc.accept().unwrap();
+
+    let th = thread::spawn(move || {
+        let mut data = Vec::new();
+        let mut buf = [0; 65536];
+        while data.len() < BULK_LEN * 3 + PING.len() {
+            let len = sv.read_some(&mut buf).unwrap();
+            data.extend_from_slice(&buf[..len]);
+        }
+        data
+    });
+
+    let wq = Arc::new(WriteQueue::new(soc));
+    wq.set_dscp(46, 0);
+    for ch in b"abc" {
+        wq.async_write(&vec![*ch; BULK_LEN], WritePriority::Low, wrap(&wq, on_bulk));
+    }
+    wq.async_write(PING, WritePriority::High, wrap(&wq, on_ping));
+    assert_eq!(wq.pending(), 3);
+    ctx.run();
+    assert_eq!(wq.pending(), 0);
+    assert_eq!(GOAL_COUNT.load(Ordering::SeqCst), 4);
+
+    let data = th.join().unwrap();
+    assert!(data[..BULK_LEN].iter().all(|&ch| ch == b'a'));
+    assert_eq!(&data[BULK_LEN..BULK_LEN + PING.len()], PING);
+    assert!(data[BULK_LEN + PING.len()..BULK_LEN * 2 + PING.len()].iter().all(|&ch| ch == b'b'));
+    assert!(data[BULK_LEN * 2 + PING.len()..].iter().all(|&ch| ch == b'c'));
+}